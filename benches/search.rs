@@ -0,0 +1,61 @@
+//! Compares the search strategies on synthetic size curves.
+//!
+//! Each probe stands in for one tool run (pngquant, gs, ...): it sleeps for a
+//! fixed latency and returns the size the curve gives for that parameter, so
+//! the numbers reflect probe count and parallelism rather than real encoders.
+
+use std::time::Duration;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use crnch::search::Strategy;
+
+const TOOL_LATENCY: Duration = Duration::from_micros(200);
+
+/// Size in KB at a parameter value in 72..=300 (DPI-like range)
+type Curve = fn(u64) -> u64;
+
+fn linear(v: u64) -> u64 {
+    v * 3
+}
+
+/// Image-heavy PDFs: size grows with the square of resolution
+fn quadratic(v: u64) -> u64 {
+    v * v / 60
+}
+
+/// Quantizers plateau, then jump when more colors become available
+fn stepped(v: u64) -> u64 {
+    300 + (v / 25) * 120
+}
+
+fn bench_strategies(c: &mut Criterion) {
+    let curves: [(&str, Curve, u64); 3] = [
+        ("linear", linear, 600),
+        ("quadratic", quadratic, 700),
+        ("stepped", stepped, 1000),
+    ];
+    let strategies = [
+        ("binary", Strategy::Binary),
+        ("interpolation", Strategy::Interpolation),
+        ("parallel4", Strategy::Parallel(4)),
+    ];
+
+    for (curve_name, curve, target) in curves {
+        let mut group = c.benchmark_group(format!("search/{}", curve_name));
+        group.sample_size(20);
+        for (name, strategy) in strategies {
+            group.bench_with_input(BenchmarkId::from_parameter(name), &strategy, |b, strategy| {
+                b.iter(|| {
+                    strategy.run(72, 300, target, 14, |_, v| {
+                        std::thread::sleep(TOOL_LATENCY);
+                        Ok(Some(curve(v)))
+                    })
+                    .unwrap()
+                })
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_strategies);
+criterion_main!(benches);
@@ -0,0 +1,322 @@
+# crnch-Meldungen, Deutsch. Fehlende Schlüssel fallen auf Englisch zurück.
+
+## Decisions
+decision-keep-original = Originaldatei behalten?
+decision-grayscale = Zielgröße wird in Graustufen erreicht ({ $size } KB). Fortfahren?
+decision-grayscale-resize = Zielgröße in Farbe nicht erreichbar. Mit verkleinerter Graustufenversion fortfahren?
+decision-bw = Zielgröße wird in 1-Bit-Schwarzweiß erreicht ({ $size } KB). Fortfahren?
+decision-bw-resize = Zielgröße in Farbe nicht erreichbar. Mit verkleinerter Schwarzweißversion fortfahren?
+decision-bw-fallback = Auf 1-Bit-Schwarzweiß reduzieren, um Platz zu sparen?
+decision-resize-color = Stattdessen das Farbbild verkleinern?
+decision-resize = Zielgröße nicht erreichbar. Bildabmessungen verkleinern?
+decision-save-smallest = Zielgröße nicht erreichbar. Kleinstmögliche Version speichern?
+decision-save-floor = Kleinstmögliche Version speichern?
+decision-rasterize = Letzter Ausweg: Seiten mit { $dpi } DPI rastern ({ $size } KB)? Text wird zum Bild: unscharf, nicht markier- oder durchsuchbar.
+decision-grayscale-fallback = In Graustufen (S/W) umwandeln, um Platz zu sparen?
+decision-resize-to-fit = Bildabmessungen passend verkleinern?
+decision-drop-frames = Letzter Ausweg: nur 1 von { $step } Einzelbildern behalten ({ $size } KB)? Die Bewegung wird ruckeliger.
+decision-crop-borders = Das Zuschneiden der einfarbigen Ränder ergibt { $size } KB. Zuschneiden?
+
+summary-keep-original = Original wird behalten
+summary-grayscale = Umwandlung in Graustufen
+summary-grayscale-resize = Graustufen als Basis zum Verkleinern
+summary-bw = Umwandlung in Schwarzweiß
+summary-bw-resize = Schwarzweiß als Basis zum Verkleinern
+summary-resize-color = Farbbild wird verkleinert
+summary-resize = Bild wird verkleinert
+summary-save-smallest = kleinstmögliche Version wird gespeichert
+summary-save-floor = kleinstmögliche Version wird gespeichert
+summary-rasterize = Seiten werden gerastert
+summary-resize-to-fit = passend verkleinern
+summary-drop-frames = Einzelbilder werden ausgelassen
+summary-crop-borders = Ränder werden zugeschnitten
+
+## Input and output checks
+error-stdout-terminal = Komprimierte Daten werden nicht auf ein Terminal geschrieben.
+tip-stdout-terminal = Tipp: Leite die Ausgabe um, z. B. crnch { $file } --stdout > out.{ $ext }
+error-in-place-symlink = --in-place ersetzt die Datei, ein Link auf sie ist nicht möglich; nutze --if-under-target skip oder copy.
+error-not-found = Datei '{ $file }' nicht gefunden.
+tip-not-found = Tipp: Prüfe den Dateipfad und versuche es erneut.
+tip-not-found-path =      Verwende einen absoluten oder einen vom aktuellen Verzeichnis relativen Pfad.
+error-is-directory = '{ $file }' ist ein Verzeichnis, keine Datei.
+tip-is-directory = Tipp: Komprimiere einzelne Dateien oder mit --recursive ein ganzes Verzeichnis.
+error-unreadable = Datei '{ $file }' kann nicht gelesen werden: { $error }
+tip-unreadable = Tipp: Prüfe die Dateirechte mit: ls -l { $file }
+error-temp-dir = Temporäres Verzeichnis kann nicht angelegt werden: { $error }
+warning-exists-skipping = Datei '{ $file }' existiert bereits. Wird übersprungen (Auto-Ja-Modus).
+prompt-overwrite = { $file } überschreiben?
+cancelled = Vorgang abgebrochen.
+error-input = Eingabefehler: { $error }
+error-same-file = Eingabe- und Ausgabedatei dürfen nicht dieselbe sein.
+tip-same-file = Tipp: Gib mit --output eine andere Ausgabedatei an.
+
+## Leftovers from interrupted runs
+warning-leftovers = Ein früherer Lauf für '{ $file }' wurde unterbrochen und hat { $count } temporäre Datei(en) hinterlassen.
+prompt-clean-up = Aufräumen?
+leftovers-removed = { $count } übrig gebliebene Datei(en) entfernt.
+leftovers-resume = Fortsetzen mit: crnch { $file } --output { $output }
+warning-leftovers-failed = Übrig gebliebene Dateien konnten nicht entfernt werden: { $error }
+
+## Results
+already-under-target = { $file } ist bereits kleiner als die Zielgröße; nichts geschrieben.
+already-optimized = { $file } übersprungen (bereits optimiert).
+not-worth-recompressing = { $file } unverändert übernommen (Neukomprimierung lohnt nicht).
+duplicates-found = { $count } doppelte Datei(en) übernehmen die Ausgabe der ersten Kopie.
+warning-aborted = Nach einem Fehler abgebrochen (--on-error abort); { $count } Datei(en) nicht begonnen.
+error-output-missing = Komprimierung abgeschlossen, aber die Ausgabedatei fehlt.
+hint-output-missing = Das deutet auf einen Systemfehler hin. Prüfe Speicherplatz und Rechte.
+error-output-empty = Die Ausgabedatei ist leer (0 Byte).
+hint-output-empty = Die Komprimierung ist fehlgeschlagen. Die Originaldatei ist unverändert.
+warning-target-missed = Zielgröße nicht erreicht.
+tip-target-pdf = Tipp: Versuche eine größere Zielgröße oder eine niedrigere Qualitätsstufe.
+tip-target-jpg = Tipp: Verkleinere die Bildabmessungen für eine bessere Komprimierung.
+tip-target-png = Tipp: Verkleinere das Bild oder wandle es in JPEG um.
+warning-changes = Neben der Größe geändert:
+warning-cancelled = Abgebrochen. Temporäre Dateien wurden entfernt.
+error-failed = Komprimierung fehlgeschlagen: { $error }
+tip-failed-tools = Tipp: Prüfe, ob alle benötigten Programme installiert sind.
+tip-failed-tools-help =      Siehe crnch --help für Installationshinweise.
+tip-failed-permissions = Tipp: Prüfe die Datei- und Verzeichnisrechte.
+tip-failed-space = Tipp: Gib Speicherplatz frei und versuche es erneut.
+
+## Re-targeting
+retarget-found = Frühere crnch-Ausgabe ({ $size }): wird aus '{ $source }' neu erstellt
+retarget-ceiling = Suche { $parameter } bis höchstens { $value }, wo der letzte Lauf lag
+
+## crnch review
+review-none-missed = Kein Lauf der letzten { $minutes } Minuten hat seine Zielgröße oder Mindestqualität verfehlt.
+review-missed-target = Ziel { $size }
+review-low-quality = Qualität { $quality }
+review-low-dpi = { $dpi } DPI
+prompt-review-pick = { $count } Lauf/Läufe haben die Zielgröße oder Mindestqualität verfehlt. Welche erneut ausführen? (Leertaste wählt, Enter bestätigt)
+prompt-review-size = Neue Zielgröße (z. B. 500k)
+prompt-review-level = Komprimierungsstufe
+
+## Command line
+selection-left-out = { $count } Datei(en) durch --min-size, --max-size oder --only ausgelassen
+error-no-self-update = Dieser crnch-Build enthält kein Self-Update. Aktualisiere ihn über deinen Paketmanager.
+error-no-history = Kein Cache-Verzeichnis, aus dem der Laufverlauf gelesen werden kann.
+error-read-rules = { $file } kann nicht gelesen werden: { $error }
+error-invalid-rules = Ungültige Regeldatei { $file }: { $error }
+plan-counts = { $compress } Datei(en) zu komprimieren, { $under } bereits unter der Zielgröße.
+error-plan-failed = { $count } geplante Datei(en) wurden nicht komprimiert.
+error-no-records = '{ $file }' enthält keine Einträge zum Prüfen.
+verify-decoded-only = nur dekodiert, kein Eintrag
+error-verify-failed = { $failed } von { $count } Datei(en) haben die Prüfung nicht bestanden.
+verify-done = { $count } Datei(en) geprüft
+extract-start = Extrahiere '{ $file }'...
+extract-done = { $count } Bild(er) nach { $dir } extrahiert
+animate-start = Animiere { $count } Einzelbilder mit { $fps } fps...
+animate-frames = { $size } Einzelbilder
+probe-range = erreichbar: { $floor } – { $ceiling }  ({ $method }; Original { $original })
+error-merge-not-pdf = --merge fügt PDFs zusammen; { $file } ist keine.
+merge-count = { $count } PDFs
+error-split-not-pdf = --split-max teilt PDFs; { $file } ist keine.
+split-page = Seite { $page }
+split-pages = Seiten { $first }-{ $last }
+split-done = { $file } in { $count } Teil(e) von höchstens { $size } geteilt
+error-rename = { $file } kann nicht in { $renamed } umbenannt werden: { $error }
+error-duplicate-failed = Gleich wie { $file }, das nicht komprimiert wurde.
+error-manifest-write = Das Manifest konnte nicht nach { $file } geschrieben werden: { $error }
+error-over-budget = Zusammen kommen die Dateien auf { $total }, über dem Budget von { $budget }.
+within-budget = Zusammen { $total }, innerhalb des Budgets von { $budget }
+error-batch-failed = { $failed } von { $count } Datei(en) wurden nicht komprimiert.
+error-not-directory = '{ $dir }' ist kein Verzeichnis; --recursive komprimiert Verzeichnisse.
+error-no-supported-files = Keine unterstützten Dateien unter { $dirs }.
+batch-start = Komprimiere { $count } Datei(en)...
+error-in-place-convert = { $file } wird als .{ $ext } geschrieben und kann sich daher nicht selbst ersetzen.
+error-in-place-url = --in-place braucht lokale Dateien; eine URL hat kein Original, das ersetzt werden könnte.
+target-platform = { $size } (unter dem Limit von { $platform })
+log-level = Stufe: { $level }
+log-quality = Qualität: { $quality }
+error-stdout-write = Schreiben nach stdout nicht möglich: { $error }
+error-serialize = Ergebnis kann nicht serialisiert werden: { $error }
+error-read-output = Ausgabedatei kann nicht gelesen werden: { $error }
+privacy-nothing-found = nichts Identifizierendes in der Eingabe
+privacy-removed = entfernt: { $fields }
+privacy-remaining = { $count } identifizierende Felder verbleiben
+privacy-summary = Datenschutz: { $removed }; { $remaining }
+progress-downloading = Lade { $file } herunter
+progress-downloaded = { $file } heruntergeladen ({ $size })
+
+## Self-update
+update-current = crnch { $version } ist aktuell.
+update-available = crnch { $version } ist verfügbar (installiert: { $installed }).
+prompt-update = { $file } durch crnch { $version } ersetzen?
+update-cancelled = Update abgebrochen.
+update-done = Auf crnch { $version } aktualisiert.
+update-notice = crnch { $version } verfügbar · `crnch self-update` ausführen
+
+## Missing tools
+error-label = Fehler
+warning-label = Warnung
+error-missing-tools = Fehlende Abhängigkeiten: { $tools }
+missing-tools-why = crnch nutzt bewährte externe Programme.
+missing-tools-install = Mit diesem Befehl installieren:
+missing-tools-yay = ODER mit Yay:
+missing-tools-arch = Arch:   { $command }
+missing-tools-debian = Debian: { $command }
+missing-tools-mac = Mac:    { $command }
+error-missing-tool = Fehlende Abhängigkeit: { $tool }
+missing-tool-install = Mit diesem Befehl installieren:
+missing-tool-other = Installiere { $tool } über deinen Paketmanager
+why-cwebp = WebP-Dateien werden mit cwebp aus libwebp kodiert.
+why-gifsicle = GIF-Dateien werden mit gifsicle optimiert.
+why-avifenc = AVIF-Dateien werden mit avifenc aus libavif kodiert.
+why-dcraw = Kamera-RAW-Dateien werden mit dcraw (oder darktable-cli) entwickelt.
+why-ffmpeg = Videos werden mit ffmpeg neu kodiert (und mit dessen ffprobe vermessen).
+why-heif-enc = HEIC-Ausgabe wird mit heif-enc aus libheif kodiert.
+why-zstd = --zstd-Archive werden mit dem Programm zstd gepackt.
+why-butteraugli = --max-distance wird mit butteraugli_main aus libjxl gemessen.
+why-tesseract = --ocr erkennt Text mit tesseract.
+why-ocrmypdf = --ocr fügt die Textebene mit ocrmypdf hinzu.
+
+## Summaries and panels
+log-start = Crnche '{ $file }'...
+log-target = Ziel:
+log-done = Fertig!
+progress-done = Fertig! ({ $seconds }s)
+summary-title = KOMPRIMIERUNGSÜBERSICHT
+system-title = SYSTEMINFORMATIONEN
+input-title = EINGABEDATEI
+result-title = KOMPRIMIERUNGSERGEBNIS
+label-input = Eingabe
+label-output = Ausgabe
+label-output-file = Ausgabedatei
+label-size = Größe
+label-saved = Gespart
+label-method = Methode
+label-time = Zeit
+label-os = BS
+label-arch = Arch
+label-cpu = CPU
+label-ram = RAM
+label-filename = Dateiname
+label-type = Typ
+label-path = Pfad
+label-dimensions = Abmessungen
+label-resolution = Auflösung
+label-target = Ziel
+label-reduction = Reduktion
+label-ratio = Verhältnis
+summary-grew-from-nothing = Datei von < 1 KB gewachsen
+summary-grew = Datei um { $percent }% gewachsen
+summary-saved = { $size } gespart, Verhältnis { $ratio }:1
+result-saved = { $size } gespart
+value-bytes = { $count } Byte
+value-approx = { $size } (ungefähr)
+value-pixels = { $width }x{ $height } Pixel
+value-target-auto = Automatisch (nach Voreinstellung)
+value-unknown = Unbekannt
+value-not-found = Nicht gefunden
+table-file = Datei
+table-before = Vorher
+table-after = Nachher
+table-saved = Gespart
+table-method = Methode
+table-time = Zeit
+table-skipped = übersprungen
+table-skipped-optimized = übersprungen (bereits optimiert)
+table-files = { $count } Datei(en)
+pages-tag = SEITEN
+pages-title = Größe pro Seite
+pages-page = Seite { $number }
+pages-shared = Gemeinsam
+pages-smaller = { $count } kleinere Seiten
+nerd-stage = STUFE { $number }
+nerd-cmd = Befehl
+nerd-next = weiter
+nerd-quality = Qualität
+nerd-scale = Skalierung
+nerd-range = Bereich
+nerd-decision-skipped = Übersprungen: { $decision } (Stufe { $stage } deaktiviert)
+nerd-decision-accepted = Per Richtlinie angenommen
+nerd-decision-declined = Per Richtlinie abgelehnt
+nerd-dpi-achieved = Zielgröße bei { $dpi } DPI erreicht ({ $size } KB)
+nerd-dpi-final = Komprimiere PDF mit { $dpi } DPI zur endgültigen Ausgabe...
+
+## Compression progress and warnings
+progress-eating = Bytes werden verspeist...
+progress-optimizing-jpg = Optimiere JPG...
+progress-page-images = Versuche Seitenbilder...
+progress-desaturating = Entsättige...
+progress-scaling = Skaliere...
+progress-floor-above-target = Minimum > Ziel
+target-above-original = Die gewünschte Größe ({ $target } KB) ist größer als oder gleich der Originalgröße ({ $size } KB). Keine Komprimierung durchgeführt.
+jpg-cannot-reach = Dieses Bild lässt sich nicht auf die gewünschte Größe (60-95 % des Originals) komprimieren. Original wird behalten.
+keeping-best-color = Beste Farbversion wird behalten ({ $size } KB).
+keeping-best = Beste Version wird behalten ({ $size } KB).
+warning-apng-floor = Animiertes PNG: { $size } KB ist das Kleinste, wenn alle Einzelbilder bleiben; als .webp schreiben (-o NAME.webp), um weiterzukommen.
+warning-animation-floor = Selbst die kleinste Einstellung ergibt { $size } KB; weniger oder kleinere Einzelbilder kommen näher heran.
+warning-quality-floor = Selbst Qualität 5 ergibt { $size } KB.
+warning-quality-floor-resize = Selbst Qualität 5 ergibt { $size } KB; ein kleineres Bild kommt näher heran.
+warning-quality-floor-heic = Selbst Qualität 5 ergibt { $size } KB; JPEG-Ausgabe (ohne --keep-heic) kann weiter verkleinern.
+warning-no-quality-keeps = Keine Qualitätsstufe hält { $criterion } ein; Qualität 100 wird verwendet.
+warning-video-bitrate = { $target } KB auf { $seconds }s lassen { $kbps } kbps für das Bild; stattdessen wird mit { $min } kbps kodiert.
+warning-video-over = Die beste Zwei-Pass-Kodierung ergibt { $size } KB, über dem Ziel von { $target } KB.
+warning-icon-lossless = Icons werden nur verlustfrei optimiert; entferne nicht benötigte Bildgrößen, um kleiner zu werden.
+warning-lossless-only = Verlustfreie Optimierung ergibt { $size } KB, und die verlustbehaftete Stufe ist übersprungen.
+warning-gif-floor = Selbst die stärkste verlustbehaftete Einstellung ergibt { $size } KB; weniger oder kleinere Einzelbilder kommen näher heran.
+warning-svg-floor = SVG: { $size } KB bei 1 Nachkommastelle ist das Kleinste, was Minifizieren schafft.
+warning-archive-rest = Die Teile, die crnch nicht komprimieren kann, belegen allein { $size } KB.
+warning-entry-kept = { $entry } unverändert übernommen: { $error }
+warning-pdf-dropped = Bei der Komprimierung verloren: { $lost }.
+warning-pdf-dropped-retry = Bei der Komprimierung verloren: { $lost }; neuer Versuch mit allen Anmerkungen.
+warning-pdf-still-dropped = Weiterhin verloren: { $lost }; prüfe die Ausgabe, bevor du dich darauf verlässt.
+warning-pdf-unreadable = Die PDF-Struktur ist nicht lesbar (verschlüsselt oder beschädigt); prüfe Lesezeichen, Links und Formularfelder in der Ausgabe selbst.
+warning-scan-unreadable = Der Scan-Modus kann in dieser PDF (verschlüsselt oder beschädigt) getippte Seiten nicht von Scans unterscheiden; Seiten bleiben unverändert.
+pdf-lossy-only = PDF-Komprimierung ist verlustbehaftet; ohne die verlustbehaftete Stufe bleibt das Original erhalten.
+warning-below-minimum = Ziel unter dem Minimum!
+floor-smallest = Kleinstmöglich: { $size } KB
+floor-target = Dein Ziel: { $size } KB
+floor-best = Beste Ausgabe nahe am Ziel: { $size } KB
+warning-quality-limit = Zielgröße nicht erreichbar, ohne die Qualität zu zerstören.
+tip-quality-limit = Tipp: Zielgröße nicht erreichbar, ohne die Qualität zu zerstören.
+tip-quality-limit-higher = Versuche eine größere Zielgröße.
+warning-low-dpi = Sehr niedrige DPI - Bilder können verpixelt wirken.
+warning-quick-unsupported = Der Schnellmodus kann diese PDF nicht umstrukturieren (Objektstreams oder Verschlüsselung); unverändert übernommen.
+quick-lossless-only = Der Schnellmodus ist nur verlustfrei; mit --pdf-mode full wird die Zielgröße erreicht.
+page-images-miss = Selbst Seitenbilder mit { $dpi } DPI verfehlen die Zielgröße.
+warning-rasterized = Seiten mit { $dpi } DPI gerastert: Text ist nicht mehr auswählbar oder durchsuchbar.
+warning-limit-reached = Grenze erreicht!
+limit-smallest = Kleinste Größe ohne Verkleinern: { $size } KB (Ziel: { $target } KB)
+fallback-worked = { $label } hat funktioniert! ({ $size } KB)
+fallback-resizing = Bild wird passend verkleinert...
+fallback-resized = Auf { $scale } % skaliert.
+fallback-keeping = Die Version mit { $size } KB wird behalten.
+
+## Engine errors
+error-unsupported-type = Nicht unterstützter Dateityp: .{ $ext }
+error-cannot-convert = .{ $from } kann nicht in .{ $to } umgewandelt werden; Bilder werden in PNG, JPG, WebP, AVIF oder PDF umgewandelt.
+error-input-only = { $format } ist nur als Eingabe möglich; schreibe es als .{ $ext }.
+error-input-only-image = { $format } ist nur als Eingabe möglich; schreibe es als .png oder .jpg.
+error-resize = ImageMagick konnte { $file } nicht verkleinern.
+error-icon = { $file } ist kein lesbares Icon.
+error-scrub = Entfernen der Metadaten fehlgeschlagen ({ $tool }).
+error-privacy = Datenschutzprüfung fehlgeschlagen: { $count } identifizierende(s) Feld(er) verbleiben: { $fields }
+error-cancelled-by-user = Komprimierung vom Benutzer abgebrochen.
+error-privacy-symlink = Ein Symlink würde die Metadaten des Originals preisgeben; nutze --if-under-target copy mit --privacy.
+error-magick = ImageMagick ist fehlgeschlagen.
+error-webp = { $file } konnte nicht als WebP kodiert werden.
+error-heic-decode = { $file } konnte nicht dekodiert werden; installiere libheif (heif-convert) oder ein ImageMagick mit HEIC-Unterstützung.
+error-raw-develop = { $file } konnte nicht entwickelt werden; installiere dcraw oder darktable (darktable-cli).
+error-magick-read = ImageMagick konnte { $file } nicht lesen.
+error-tool-encode = { $tool } konnte { $file } nicht kodieren.
+error-heic-encode = heif-enc konnte { $file } nicht kodieren; diesem libheif fehlt evtl. ein HEVC-Encoder. Ohne --keep-heic entsteht JPEG.
+error-duration = ffprobe konnte die Dauer von { $file } nicht lesen.
+error-to-pdf = { $file } konnte weder mit img2pdf noch mit ImageMagick in eine PDF umgewandelt werden.
+error-tool-read = { $tool } konnte { $file } nicht lesen.
+error-cbr-unpack = { $file } konnte nicht entpackt werden; CBR-Dateien brauchen unrar oder bsdtar (libarchive).
+error-zstd = zstd konnte { $file } nicht komprimieren.
+error-cbr-write = CBR (RAR) kann nicht geschrieben werden; schreibe den Comic als .cbz.
+error-ocr-encrypted = ocrmypdf kann keine verschlüsselten PDFs lesen; lass --ocr für { $file } weg oder entschlüssle sie zuerst.
+error-ocr = ocrmypdf konnte { $file } keine Textebene hinzufügen.
+error-quick-needs-gs = { $flag } braucht Ghostscript; es funktioniert nicht mit --pdf-mode quick.
+error-compression-cancelled = Komprimierung abgebrochen.
+error-pdf-password-missing = { $file } ist passwortgeschützt; gib das Passwort mit --password an.
+error-pdf-password-wrong = Ghostscript konnte { $file } mit dem angegebenen Passwort nicht öffnen.
+error-gs = Ghostscript ist fehlgeschlagen.
+warning-theme = [theme] wird ignoriert: { $error }
+warning-no-language = Keine Meldungen für '{ $lang }'; Englisch wird verwendet. Verfügbar: { $available }
+error-create-dir = '{ $dir }' kann nicht angelegt werden: { $error }
+warning-script-write = { $file } kann nicht geschrieben werden: { $error }
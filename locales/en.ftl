@@ -0,0 +1,324 @@
+# crnch messages, English (the fallback for every other language)
+#
+# key = text, with { $name } for values filled in at run time.
+
+## Decisions
+decision-keep-original = Keep original file?
+decision-grayscale = Target reached by converting to Grayscale ({ $size } KB). Proceed?
+decision-grayscale-resize = Target unreachable in Color. Proceed with Grayscale Resizing?
+decision-bw = Target reached in 1-bit black and white ({ $size } KB). Proceed?
+decision-bw-resize = Target unreachable in Color. Proceed with black-and-white resizing?
+decision-bw-fallback = Reduce to 1-bit black and white to save space?
+decision-resize-color = Resize the Color image instead?
+decision-resize = Target unreachable. Resize image dimensions?
+decision-save-smallest = Target unreachable. Save smallest possible?
+decision-save-floor = Save the smallest possible version?
+decision-rasterize = Last resort: rasterize pages at { $dpi } DPI ({ $size } KB)? Text becomes a picture: blurry, not selectable or searchable.
+decision-grayscale-fallback = Convert to Grayscale (B&W) to save space?
+decision-resize-to-fit = Resize image dimensions to fit?
+decision-drop-frames = Last resort: keep 1 in { $step } frames ({ $size } KB)? Motion gets choppier.
+decision-crop-borders = Cropping the uniform borders reaches { $size } KB. Crop them?
+
+summary-keep-original = keeping original
+summary-grayscale = converting to grayscale
+summary-grayscale-resize = using grayscale for resizing
+summary-bw = converting to black and white
+summary-bw-resize = using black and white for resizing
+summary-resize-color = resizing color image
+summary-resize = resizing image
+summary-save-smallest = saving smallest possible
+summary-save-floor = saving smallest possible version
+summary-rasterize = rasterizing pages
+summary-resize-to-fit = resizing to fit
+summary-drop-frames = dropping frames
+summary-crop-borders = cropping borders
+
+## Input and output checks
+error-stdout-terminal = Refusing to write compressed data to a terminal.
+tip-stdout-terminal = Tip: Pipe or redirect it, e.g. crnch { $file } --stdout > out.{ $ext }
+error-in-place-symlink = --in-place replaces the file, so there is nothing to link to; use --if-under-target skip or copy.
+error-not-found = File '{ $file }' not found.
+tip-not-found = Tip: Check the file path and try again.
+tip-not-found-path =      Use absolute path or relative path from current directory.
+error-is-directory = '{ $file }' is a directory, not a file.
+tip-is-directory = Tip: Compress individual files, or a whole directory with --recursive.
+error-unreadable = Cannot read file '{ $file }': { $error }
+tip-unreadable = Tip: Check file permissions with: ls -l { $file }
+error-temp-dir = Cannot create temporary directory: { $error }
+warning-exists-skipping = File '{ $file }' already exists. Skipping (auto-yes mode).
+prompt-overwrite = Overwrite { $file }?
+cancelled = Operation cancelled.
+error-input = Input error: { $error }
+error-same-file = Input and output files cannot be the same.
+tip-same-file = Tip: Use --output to specify a different output file.
+
+## Leftovers from interrupted runs
+warning-leftovers = An earlier run on '{ $file }' was interrupted and left { $count } temporary file(s) behind.
+prompt-clean-up = Clean them up?
+leftovers-removed = Removed { $count } leftover file(s).
+leftovers-resume = Resume with: crnch { $file } --output { $output }
+warning-leftovers-failed = Could not clean up leftovers: { $error }
+
+## Results
+already-under-target = { $file } is already under the target size; nothing written.
+already-optimized = { $file } skipped (already optimized).
+not-worth-recompressing = { $file } kept as is (not worth recompressing).
+duplicates-found = { $count } duplicate file(s) will reuse the first copy's output.
+warning-aborted = Stopped after a failure (--on-error abort); { $count } file(s) not started.
+error-output-missing = Compression completed but output file not found.
+hint-output-missing = This may indicate a system error. Check disk space and permissions.
+error-output-empty = Output file is empty (0 bytes).
+hint-output-empty = This indicates a compression failure. The original file is intact.
+warning-target-missed = Could not reach target size.
+tip-target-pdf = Tip: Try a larger target size, or use lower quality settings.
+tip-target-jpg = Tip: Try resizing the image dimensions for better compression.
+tip-target-png = Tip: Try resizing the image or converting to JPEG format.
+warning-changes = Changed besides the size:
+warning-cancelled = Cancelled. Temporary files were cleaned up.
+error-failed = Compression failed: { $error }
+tip-failed-tools = Tip: Check that all required tools are installed.
+tip-failed-tools-help =      Run: crnch --help for installation instructions.
+tip-failed-permissions = Tip: Check file and directory permissions.
+tip-failed-space = Tip: Free up disk space and try again.
+
+## Re-targeting
+retarget-found = Earlier crnch output ({ $size }): redoing it from '{ $source }'
+retarget-ceiling = Searching { $parameter } at or below { $value }, where the last run landed
+
+## crnch review
+review-none-missed = No run in the last { $minutes } minutes missed its target or quality floor.
+review-missed-target = target { $size }
+review-low-quality = quality { $quality }
+review-low-dpi = { $dpi } DPI
+prompt-review-pick = { $count } run(s) missed their target or quality floor. Re-run which? (space selects, enter confirms)
+prompt-review-size = New target size (e.g. 500k)
+prompt-review-level = Compression level
+
+## Command line
+selection-left-out = { $count } file(s) left out by --min-size, --max-size or --only
+error-no-self-update = This build of crnch was made without self-update. Update it through your package manager.
+error-no-history = No cache directory to read the run history from.
+error-read-rules = Cannot read { $file }: { $error }
+error-invalid-rules = Invalid rules file { $file }: { $error }
+plan-counts = { $compress } file(s) to compress, { $under } already under target.
+error-plan-failed = { $count } planned file(s) were not compressed.
+error-no-records = '{ $file }' has no records to verify against.
+verify-decoded-only = decoded only, no record
+error-verify-failed = { $failed } of { $count } file(s) failed verification.
+verify-done = { $count } file(s) verified
+extract-start = Extracting '{ $file }'...
+extract-done = Extracted { $count } image(s) to { $dir }
+animate-start = Animating { $count } frames at { $fps } fps...
+animate-frames = { $size } of frames
+probe-range = achievable: { $floor } – { $ceiling }  ({ $method }; original { $original })
+error-merge-not-pdf = --merge joins PDFs; { $file } isn't one.
+merge-count = { $count } PDFs
+error-split-not-pdf = --split-max splits PDFs; { $file } isn't one.
+split-page = page { $page }
+split-pages = pages { $first }-{ $last }
+split-done = { $file } split into { $count } part(s) of at most { $size }
+error-rename = Cannot rename { $file } to { $renamed }: { $error }
+error-duplicate-failed = Same as { $file }, which wasn't compressed.
+error-manifest-write = Couldn't write the manifest to { $file }: { $error }
+error-over-budget = Together the files come to { $total }, over the { $budget } budget.
+within-budget = Together { $total }, within the { $budget } budget
+error-batch-failed = { $failed } of { $count } file(s) were not compressed.
+error-not-directory = '{ $dir }' is not a directory; --recursive compresses directories.
+error-no-supported-files = No supported files under { $dirs }.
+batch-start = Compressing { $count } file(s)...
+error-in-place-convert = { $file } is written as .{ $ext }, so it can't replace itself.
+error-in-place-url = --in-place needs local files; a URL has no original to replace.
+target-platform = { $size } (under the { $platform } limit)
+log-level = Level: { $level }
+log-quality = Quality: { $quality }
+error-stdout-write = Cannot write to stdout: { $error }
+error-serialize = Cannot serialize result: { $error }
+error-read-output = Cannot read output file: { $error }
+privacy-nothing-found = nothing identifying in the input
+privacy-removed = removed { $fields }
+privacy-remaining = { $count } identifying fields remain
+privacy-summary = Privacy: { $removed }; { $remaining }
+progress-downloading = Downloading { $file }
+progress-downloaded = Downloaded { $file } ({ $size })
+
+## Self-update
+update-current = crnch { $version } is up to date.
+update-available = crnch { $version } is available (installed: { $installed }).
+prompt-update = Replace { $file } with crnch { $version }?
+update-cancelled = Update cancelled.
+update-done = Updated to crnch { $version }.
+update-notice = crnch { $version } available · run `crnch self-update`
+
+## Missing tools
+error-label = Error
+warning-label = Warning
+error-missing-tools = Missing dependencies: { $tools }
+missing-tools-why = crnch relies on external industry-standard tools.
+missing-tools-install = Run this command to install them:
+missing-tools-yay = OR via Yay:
+missing-tools-arch = Arch:   { $command }
+missing-tools-debian = Debian: { $command }
+missing-tools-mac = Mac:    { $command }
+error-missing-tool = Missing dependency: { $tool }
+missing-tool-install = Run this command to install it:
+missing-tool-other = Install { $tool } from your package manager
+why-cwebp = WebP files are encoded with libwebp's cwebp.
+why-gifsicle = GIF files are optimized with gifsicle.
+why-avifenc = AVIF files are encoded with libavif's avifenc.
+why-dcraw = Camera RAW files are developed with dcraw (or darktable-cli).
+why-ffmpeg = Videos are re-encoded with ffmpeg (and measured with its ffprobe).
+why-heif-enc = HEIC output is encoded with libheif's heif-enc.
+why-zstd = --zstd archives are packed with the zstd tool.
+why-butteraugli = --max-distance is measured with libjxl's butteraugli_main.
+why-tesseract = --ocr recognizes text with tesseract.
+why-ocrmypdf = --ocr adds the text layer with ocrmypdf.
+
+## Summaries and panels
+log-start = Crnching '{ $file }'...
+log-target = Target:
+log-done = Done!
+progress-done = Done! ({ $seconds }s)
+summary-title = COMPRESSION SUMMARY
+system-title = SYSTEM INFORMATION
+input-title = INPUT FILE
+result-title = COMPRESSION RESULT
+label-input = Input
+label-output = Output
+label-output-file = Output File
+label-size = Size
+label-saved = Saved
+label-method = Method
+label-time = Time
+label-os = OS
+label-arch = Arch
+label-cpu = CPU
+label-ram = RAM
+label-filename = Filename
+label-type = Type
+label-path = Path
+label-dimensions = Dimensions
+label-resolution = Resolution
+label-target = Target
+label-reduction = Reduction
+label-ratio = Ratio
+summary-grew-from-nothing = file grew from < 1 KB
+summary-grew = file grew by { $percent }%
+summary-saved = { $size } saved, { $ratio }:1 ratio
+result-saved = { $size } saved
+value-bytes = { $count } bytes
+value-approx = { $size } (approx)
+value-pixels = { $width }x{ $height } pixels
+value-target-auto = Auto (preset-based)
+value-unknown = Unknown
+value-not-found = Not found
+table-file = File
+table-before = Before
+table-after = After
+table-saved = Saved
+table-method = Method
+table-time = Time
+table-skipped = skipped
+table-skipped-optimized = skipped (already optimized)
+table-files = { $count } file(s)
+pages-tag = PAGES
+pages-title = Size by Page
+pages-page = Page { $number }
+pages-shared = Shared
+pages-smaller = { $count } smaller pages
+nerd-stage = STAGE { $number }
+nerd-cmd = Cmd
+nerd-next = next
+nerd-quality = Quality
+nerd-scale = Scale
+nerd-range = Range
+nerd-decision-skipped = Skipped: { $decision } ({ $stage } stage disabled)
+nerd-decision-accepted = Accepted by policy
+nerd-decision-declined = Declined by policy
+nerd-dpi-achieved = Target achieved at { $dpi } DPI ({ $size } KB)
+nerd-dpi-final = Compressing PDF at { $dpi } DPI to final output...
+
+## Compression progress and warnings
+progress-eating = Eating those bytes...
+progress-optimizing-jpg = Optimizing JPG...
+progress-page-images = Trying page images...
+progress-desaturating = Desaturating...
+progress-scaling = Scaling...
+progress-floor-above-target = Floor > Target
+target-above-original = Requested size ({ $target }) KB is larger than or equal to original file size ({ $size } KB). No compression performed.
+jpg-cannot-reach = This image cannot be compressed to the desired size (60-95% of original). Keeping original.
+keeping-best-color = Keeping best color version ({ $size } KB).
+keeping-best = Keeping best version ({ $size } KB).
+warning-apng-floor = Animated PNG: { $size } KB is as small as it gets with every frame kept; write it as .webp (-o NAME.webp) to go further.
+warning-animation-floor = Even the smallest setting gives { $size } KB; fewer or smaller frames will get closer.
+warning-quality-floor = Even quality 5 gives { $size } KB.
+warning-quality-floor-resize = Even quality 5 gives { $size } KB; a smaller image will get closer.
+warning-quality-floor-heic = Even quality 5 gives { $size } KB; JPEG output (without --keep-heic) can resize further.
+warning-no-quality-keeps = No quality keeps { $criterion }; using quality 100.
+warning-video-bitrate = { $target } KB over { $seconds }s leaves { $kbps } kbps for the picture; encoding at { $min } kbps instead.
+warning-video-over = Closest two-pass encode is { $size } KB, over the { $target } KB target.
+warning-icon-lossless = Icons are only optimized losslessly; drop frame sizes the icon doesn't need to go smaller.
+warning-lossless-only = Lossless optimization gives { $size } KB and the lossy stage is skipped.
+warning-gif-floor = Even the lossiest setting gives { $size } KB; fewer or smaller frames will get closer.
+warning-svg-floor = SVG: { $size } KB at 1 decimal is as small as minifying gets.
+warning-archive-rest = The parts crnch can't compress take { $size } KB on their own.
+warning-entry-kept = { $entry } kept as is: { $error }
+warning-pdf-dropped = Compression dropped { $lost }.
+warning-pdf-dropped-retry = Compression dropped { $lost }; retrying with every annotation kept.
+warning-pdf-still-dropped = Still dropped { $lost }; check the output before relying on it.
+warning-pdf-unreadable = Cannot read the PDF structure (encrypted or damaged); check bookmarks, links and form fields in the output yourself.
+warning-scan-unreadable = Scan mode can't tell typed pages from scans in this PDF (encrypted or damaged); pages kept as they are.
+pdf-lossy-only = PDF compression is lossy; with the lossy stage skipped the original is kept.
+warning-below-minimum = Target Below Minimum!
+floor-smallest = Smallest possible: { $size } KB
+floor-target = Your target: { $size } KB
+floor-best = Best possible output near target is: { $size } KB
+warning-quality-limit = Could not reach target size without destroying quality.
+tip-quality-limit = Tip: Could not reach target size without destroying quality.
+tip-quality-limit-higher = Try a higher size.
+warning-low-dpi = Very low DPI - images may appear pixelated.
+warning-quick-unsupported = Quick mode can't restructure this PDF (object streams or encryption); kept as is.
+quick-lossless-only = Quick mode is lossless only; use --pdf-mode full to reach the target.
+page-images-miss = Even { $dpi } DPI page images miss the target.
+warning-rasterized = Pages rasterized at { $dpi } DPI: text is no longer selectable or searchable.
+warning-limit-reached = Limit Reached!
+limit-smallest = Smallest size without resizing: { $size } KB (Target: { $target } KB)
+fallback-worked = { $label } worked! ({ $size } KB)
+fallback-resizing = Resizing image to fit...
+fallback-resized = Resized to { $scale }% scale.
+fallback-keeping = Keeping the { $size } KB version.
+
+## Engine errors
+error-unsupported-type = Unsupported file type: .{ $ext }
+error-cannot-convert = Can't convert .{ $from } to .{ $to }; images convert to PNG, JPG, WebP, AVIF or PDF.
+error-input-only = { $format } is input only; write it as .{ $ext }.
+error-input-only-image = { $format } is input only; write it as .png or .jpg.
+error-resize = ImageMagick could not resize { $file }.
+error-icon = { $file } is not a readable icon.
+error-scrub = Metadata scrub failed ({ $tool }).
+error-privacy = Privacy check failed: { $count } identifying field(s) remain: { $fields }
+error-cancelled-by-user = Compression cancelled by user.
+error-privacy-symlink = A symlink would expose the original's metadata; use --if-under-target copy with --privacy.
+error-magick = ImageMagick failed.
+error-webp = Could not encode { $file } as WebP.
+error-heic-decode = Could not decode { $file }; install libheif (heif-convert) or an ImageMagick built with HEIC support.
+error-raw-develop = Could not develop { $file }; install dcraw or darktable (darktable-cli).
+error-magick-read = ImageMagick could not read { $file }.
+error-tool-encode = { $tool } could not encode { $file }.
+error-heic-encode = heif-enc could not encode { $file }; this libheif may lack an HEVC encoder. Drop --keep-heic for JPEG.
+error-duration = ffprobe could not read the duration of { $file }.
+error-to-pdf = Could not turn { $file } into a PDF with img2pdf or ImageMagick.
+error-tool-read = { $tool } could not read { $file }.
+error-cbr-unpack = Could not unpack { $file }; CBR files need unrar or bsdtar (libarchive).
+error-zstd = zstd could not compress { $file }.
+error-cbr-write = CBR (RAR) can't be written; write the comic as .cbz.
+error-ocr-encrypted = ocrmypdf can't read encrypted PDFs; drop --ocr for { $file } or decrypt it first.
+error-ocr = ocrmypdf could not add a text layer to { $file }.
+error-quick-needs-gs = { $flag } needs Ghostscript; it doesn't work with --pdf-mode quick.
+error-compression-cancelled = Compression cancelled.
+error-pdf-password-missing = { $file } is password-protected; give the password with --password.
+error-pdf-password-wrong = Ghostscript could not open { $file } with the given password.
+error-gs = Ghostscript failed.
+warning-theme = Ignoring [theme]: { $error }
+warning-no-language = No messages for '{ $lang }'; using English. Available: { $available }
+error-create-dir = Cannot create '{ $dir }': { $error }
+warning-script-write = Cannot write { $file }: { $error }
@@ -0,0 +1,209 @@
+//! Assemble an image sequence into an animated WebP or GIF (`--animate`),
+//! searched down to the target size the way a single image is.
+
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+use anyhow::{anyhow, Result};
+use crate::compression::{CompResult, CompressionLevel};
+use crate::exec::ToolCommand;
+use crate::options::{CompressionOptions, MetadataMode};
+use crate::progress::Attempt;
+use crate::search;
+use crate::t;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AnimationFormat {
+    /// Searched over `-quality`
+    Webp,
+    /// Searched over the palette size
+    Gif,
+}
+
+impl AnimationFormat {
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = Path::new(path).extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "webp" => Some(AnimationFormat::Webp),
+            "gif" => Some(AnimationFormat::Gif),
+            _ => None,
+        }
+    }
+
+    /// Searchable parameter range, smallest output first
+    fn range(self) -> (u64, u64) {
+        match self {
+            AnimationFormat::Webp => (5, 95),
+            AnimationFormat::Gif => (2, 256),
+        }
+    }
+
+    /// Parameter used when no target is given
+    fn preset(self, level: Option<CompressionLevel>) -> u64 {
+        match (self, level) {
+            (AnimationFormat::Webp, Some(CompressionLevel::Low)) => 90,
+            (AnimationFormat::Webp, Some(CompressionLevel::High)) => 50,
+            (AnimationFormat::Webp, _) => 75,
+            (AnimationFormat::Gif, Some(CompressionLevel::Low)) => 256,
+            (AnimationFormat::Gif, Some(CompressionLevel::High)) => 64,
+            (AnimationFormat::Gif, _) => 128,
+        }
+    }
+
+    fn parameter(self) -> &'static str {
+        match self {
+            AnimationFormat::Webp => "quality",
+            AnimationFormat::Gif => "colors",
+        }
+    }
+
+    fn describe(self, value: u64) -> String {
+        match self {
+            AnimationFormat::Webp => format!("Animated WebP (quality {})", value),
+            AnimationFormat::Gif => format!("Animated GIF ({} colors)", value),
+        }
+    }
+}
+
+/// Combine `frames`, in order, into `output` playing at `fps`, as large as
+/// still fits `opts.target_kb`
+pub fn animate(frames: &[String], output: &str, fps: u32, opts: &CompressionOptions) -> Result<CompResult> {
+    let format = AnimationFormat::from_path(output).ok_or_else(|| {
+        let ext = Path::new(output).extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+        anyhow!("Animations are written as .webp or .gif, not .{}", ext)
+    })?;
+    if frames.len() < 2 {
+        return Err(anyhow!("An animation needs at least two frames."));
+    }
+    if let Some(missing) = frames.iter().find(|f| !Path::new(f).is_file()) {
+        return Err(anyhow!("Frame '{}' not found.", missing));
+    }
+    let start = Instant::now();
+    let sink = opts.progress.as_ref();
+    let original_kb = frames.iter().map(|f| fs::metadata(f).map(|m| m.len()).unwrap_or(0)).sum::<u64>() / 1024;
+
+    let encode = |value: u64, dest: &str| -> Result<Option<u64>> {
+        opts.cancel.check()?;
+        let mut cmd = ToolCommand::new("magick");
+        cmd.arg("-delay").arg(format!("1x{}", fps.max(1))).arg("-loop").arg("0").args(frames);
+        match format {
+            AnimationFormat::Webp => { cmd.arg("-quality").arg(value.to_string()).arg("-define").arg("webp:method=6"); },
+            AnimationFormat::Gif => { cmd.arg("-colors").arg(value.to_string()).arg("-layers").arg("Optimize"); },
+        }
+        if opts.metadata == MetadataMode::Strip { cmd.arg("-strip"); }
+        cmd.arg(dest);
+        opts.limits.apply(&mut cmd);
+        let status = opts.executor.run(&cmd, &opts.cancel)?;
+        Ok(status.success().then(|| fs::metadata(dest).map(|m| m.len() / 1024).unwrap_or(0)))
+    };
+
+    sink.on_stage(1, "Assemble Animation");
+    let Some(target) = opts.target_kb else {
+        let value = format.preset(opts.level);
+        let size = encode(value, output)?.ok_or_else(|| anyhow!("magick could not assemble the animation."))?;
+        return Ok(CompResult {
+            algorithm: format.describe(value),
+            time_ms: start.elapsed().as_millis(),
+            original_kb,
+            final_kb: size,
+            ..Default::default()
+        });
+    };
+
+    // Every hit is larger than the one before, so it replaces the output
+    let probe_path = opts.scratch_path(output, &format!("animate.tmp.{}", output.rsplit('.').next().unwrap_or("img")));
+    let (lo, hi) = format.range();
+    let max_probes = opts.effort.image_probes();
+    let searched = search::binary(lo, hi, target, max_probes, |index, value| {
+        let t0 = Instant::now();
+        let size = encode(value, &probe_path)?;
+        if let Some(size_kb) = size {
+            sink.on_attempt(&Attempt {
+                stage: 1,
+                index,
+                max: max_probes,
+                parameter: format.parameter(),
+                value,
+                size_kb,
+                target_kb: target,
+                time_ms: t0.elapsed().as_millis(),
+            });
+            if size_kb <= target {
+                fs::rename(&probe_path, output)?;
+            }
+        }
+        Ok(size)
+    });
+    let _ = fs::remove_file(&probe_path);
+    let searched = searched?;
+
+    let (value, final_kb, target_met) = match searched.best {
+        Some((value, size)) => (value, size, true),
+        None => {
+            let size = encode(lo, output)?.ok_or_else(|| anyhow!("magick could not assemble the animation."))?;
+            opts.reporter.warning(&t!("warning-animation-floor", size = size));
+            (lo, size, false)
+        },
+    };
+    Ok(CompResult {
+        algorithm: format.describe(value),
+        time_ms: start.elapsed().as_millis(),
+        original_kb,
+        final_kb,
+        target_kb: Some(target),
+        target_met: Some(target_met),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::tests::{FakeRun, FakeTools};
+
+    fn frames(dir: &tempfile::TempDir, count: usize) -> Vec<String> {
+        (1..=count).map(|i| {
+            let path = dir.path().join(format!("frame{:02}.png", i));
+            fs::write(&path, vec![0u8; 50 * 1024]).unwrap();
+            path.to_string_lossy().to_string()
+        }).collect()
+    }
+
+    #[test]
+    fn test_webp_quality_is_searched_to_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let frames = frames(&dir, 4);
+        // Output grows 10 KB per quality step
+        let tools = FakeTools::new(|cmd| {
+            let i = cmd.args.iter().position(|a| a == "-quality").unwrap();
+            FakeRun::Write(cmd.args[i + 1].parse::<u64>().unwrap() * 10)
+        });
+        let opts = CompressionOptions::builder().target_kb(Some(505)).executor(tools.clone()).build();
+        let output = dir.path().join("out.webp").to_string_lossy().to_string();
+
+        let result = animate(&frames, &output, 12, &opts).unwrap();
+        assert_eq!(result.algorithm, "Animated WebP (quality 50)");
+        assert_eq!(result.final_kb, 500);
+        assert_eq!(result.target_met, Some(true));
+        assert_eq!(fs::metadata(&output).unwrap().len(), 500 * 1024);
+        assert_eq!(result.original_kb, 200);
+        assert!(fs::read_dir(dir.path()).unwrap().all(|e| !e.unwrap().file_name().to_string_lossy().contains(".tmp")));
+    }
+
+    #[test]
+    fn test_gif_misses_with_warning_and_rejects_other_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        let frames = frames(&dir, 3);
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(10))
+            .executor(FakeTools::new(|_| FakeRun::Write(40)))
+            .build();
+        let output = dir.path().join("out.gif").to_string_lossy().to_string();
+
+        let result = animate(&frames, &output, 10, &opts).unwrap();
+        assert_eq!(result.algorithm, "Animated GIF (2 colors)");
+        assert_eq!(result.target_met, Some(false));
+        assert!(animate(&frames, "out.mp4", 10, &opts).is_err());
+        assert!(animate(&frames[..1], &output, 10, &opts).is_err());
+    }
+}
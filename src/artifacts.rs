@@ -0,0 +1,134 @@
+//! Scratch files handed from stage to stage.
+//!
+//! A stage's winner used to be copied into place and its scratch file
+//! deleted afterwards, a full read and write of the file each time (up to
+//! four on the PNG path). Candidates are moved instead: a rename within a
+//! filesystem, else `fs::copy`, which on Linux goes through
+//! `copy_file_range` and reflinks on btrfs and XFS. [`Scratch`] tracks an
+//! engine's intermediates and removes whatever is left when it drops.
+//! [`Shared`] keeps the ones that don't depend on the target for the next
+//! run over the same input.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Move `src` to `dest`, replacing it
+pub fn move_file(src: impl AsRef<Path>, dest: impl AsRef<Path>) -> io::Result<()> {
+    let (src, dest) = (src.as_ref(), dest.as_ref());
+    if fs::rename(src, dest).is_ok() {
+        return Ok(());
+    }
+    // Scratch space on another filesystem (--temp-dir, staged I/O)
+    fs::copy(src, dest)?;
+    let _ = fs::remove_file(src);
+    Ok(())
+}
+
+/// Intermediate files removed on drop, whichever way the engine returns
+#[derive(Debug, Default)]
+pub struct Scratch {
+    paths: Vec<PathBuf>,
+}
+
+impl Scratch {
+    pub fn track(&mut self, path: impl Into<PathBuf>) {
+        self.paths.push(path.into());
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Intermediates that are the same whatever the target (the lossless pass,
+/// a decode), made once per input and copied out to every later run that
+/// asks for them: `--sizes` compresses one input several times
+#[derive(Debug)]
+pub struct Shared {
+    dir: tempfile::TempDir,
+    made: Mutex<HashMap<String, Arc<Mutex<Option<PathBuf>>>>>,
+    count: AtomicUsize,
+}
+
+impl Shared {
+    /// Kept in a private folder under `temp_dir`, else the system's
+    pub fn new(temp_dir: Option<&Path>) -> io::Result<Self> {
+        let mut builder = tempfile::Builder::new();
+        builder.prefix("crnch-shared-");
+        let dir = match temp_dir {
+            Some(dir) => builder.tempdir_in(dir)?,
+            None => builder.tempdir()?,
+        };
+        Ok(Shared { dir, made: Mutex::default(), count: AtomicUsize::new(0) })
+    }
+
+    /// Fill `dest` with artifact `key`: a copy of the kept one, else whatever
+    /// `make` writes there, kept when it reports success. Runs asking for the
+    /// same key wait for the first one's `make`.
+    pub fn get_or_make(&self, key: &str, dest: &Path, make: impl FnOnce() -> anyhow::Result<bool>) -> anyhow::Result<bool> {
+        let slot = self.made.lock().unwrap().entry(key.to_string()).or_default().clone();
+        let mut kept = slot.lock().unwrap();
+        if let Some(ref path) = *kept {
+            fs::copy(path, dest)?;
+            return Ok(true);
+        }
+        let made = make()?;
+        if made && dest.is_file() {
+            let path = self.dir.path().join(self.count.fetch_add(1, Ordering::Relaxed).to_string());
+            fs::copy(dest, &path)?;
+            *kept = Some(path);
+        }
+        Ok(made)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_replaces_and_scratch_cleans_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b, out) = (dir.path().join("a.tmp"), dir.path().join("b.tmp"), dir.path().join("out"));
+        fs::write(&a, b"winner").unwrap();
+        fs::write(&b, b"loser").unwrap();
+        fs::write(&out, b"old").unwrap();
+        {
+            let mut scratch = Scratch::default();
+            scratch.track(&a);
+            scratch.track(&b);
+            move_file(&a, &out).unwrap();
+        }
+        assert_eq!(fs::read(&out).unwrap(), b"winner");
+        assert!(!a.exists() && !b.exists());
+    }
+
+    #[test]
+    fn test_shared_makes_each_artifact_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let shared = Shared::new(Some(dir.path())).unwrap();
+        let makes = AtomicUsize::new(0);
+        let make = |dest: &Path, ok: bool| {
+            makes.fetch_add(1, Ordering::Relaxed);
+            fs::write(dest, b"lossless").unwrap();
+            Ok(ok)
+        };
+        let (first, second) = (dir.path().join("a.png"), dir.path().join("b.png"));
+        assert!(!shared.get_or_make("oxipng:in.png", &first, || make(&first, false)).unwrap());
+        // A failed make isn't kept
+        assert!(shared.get_or_make("oxipng:in.png", &first, || make(&first, true)).unwrap());
+        assert!(shared.get_or_make("oxipng:in.png", &second, || make(&second, true)).unwrap());
+        assert_eq!(makes.load(Ordering::Relaxed), 2);
+        assert_eq!(fs::read(&second).unwrap(), b"lossless");
+        shared.get_or_make("oxipng:other.png", &second, || make(&second, true)).unwrap();
+        assert_eq!(makes.load(Ordering::Relaxed), 3);
+    }
+}
@@ -0,0 +1,508 @@
+//! Compress many files at once on per-format worker lanes.
+//!
+//! A PDF goes through Ghostscript a dozen times and can take minutes where an
+//! image takes seconds. With one shared queue a few big PDFs hold every
+//! worker while the images wait, so documents and images get their own
+//! lanes, sized by estimated cost. A worker whose lane runs dry helps out on
+//! the other one.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use anyhow::Result;
+use clap::ValueEnum;
+use crate::compression::{compress_file, CompResult};
+use crate::format::Format;
+use crate::glob::Ignore;
+use crate::options::CompressionOptions;
+use crate::utils::TargetSize;
+use crate::{organize, utils, verify};
+
+/// What a failed file does to the rest of a run
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, ValueEnum)]
+pub enum OnError {
+    /// Record it and go on with the others
+    #[default]
+    Continue,
+    /// Start no more files; those already running finish
+    Abort,
+}
+
+/// Result for a job left alone after an earlier one failed under [`OnError::Abort`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotStarted;
+
+impl fmt::Display for NotStarted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Not started: an earlier file failed.")
+    }
+}
+
+impl std::error::Error for NotStarted {}
+
+/// One file to compress
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+    pub input: String,
+    pub output: String,
+    /// This file's own target over the run's, e.g. its share of a total
+    /// budget or one of `--sizes`
+    pub target: Option<TargetSize>,
+}
+
+impl Job {
+    pub fn new(input: impl Into<String>, output: impl Into<String>) -> Self {
+        Job { input: input.into(), output: output.into(), target: None }
+    }
+
+    /// Output named `name` in `root/YYYY/MM/`, dated by the input's capture
+    /// time (see [`organize::dated_path`])
+    pub fn dated(input: impl Into<String>, root: &Path, name: &str) -> Self {
+        let input = input.into();
+        let output = organize::dated_path(Path::new(&input), root, name).to_string_lossy().to_string();
+        Job { input, output, target: None }
+    }
+}
+
+/// Which files a batch run takes on: tiny icons can grow when recompressed
+/// and huge files may not be worth the wait
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Selection {
+    pub min_bytes: Option<u64>,
+    pub max_bytes: Option<u64>,
+    /// Only these formats; empty takes every one
+    pub only: Vec<Format>,
+}
+
+impl Selection {
+    pub fn is_unrestricted(&self) -> bool {
+        *self == Selection::default()
+    }
+
+    /// A file that can't be read is admitted, to fail with its own error
+    pub fn admits(&self, path: &Path) -> bool {
+        if !self.only.is_empty() && !Format::from_path(&path.to_string_lossy()).is_some_and(|f| self.only.contains(&f)) {
+            return false;
+        }
+        let Ok(size) = std::fs::metadata(path).map(|m| m.len()) else { return true };
+        self.min_bytes.is_none_or(|min| size >= min) && self.max_bytes.is_none_or(|max| size <= max)
+    }
+}
+
+/// A job for every supported file under `root`, its output at the same
+/// relative path under `out_dir` with the name `name` gives it. Anything
+/// already inside `out_dir` is left out, so a mirror nested in its own tree
+/// isn't compressed again, and so are earlier `crnched_` outputs and whatever
+/// `ignore` (with `root`'s `.crnchignore`) matches.
+pub fn mirror_tree(root: &Path, out_dir: &Path, ignore: &Ignore, name: impl Fn(&Path) -> String) -> Result<Vec<Job>> {
+    let skip = std::path::absolute(out_dir)?;
+    let ignore = ignore.with_file_in(root)?;
+    let mut jobs = Vec::new();
+    let walk = walkdir::WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            let ignored = e.depth() > 0 && ignore.matches(e.path().strip_prefix(root).unwrap_or(e.path()), e.file_type().is_dir());
+            !ignored && std::path::absolute(e.path()).map_or(true, |p| p != skip)
+        });
+    for entry in walk {
+        let entry = entry?;
+        let input = entry.path();
+        let earlier_output = entry.file_name().to_string_lossy().starts_with(utils::DEFAULT_OUTPUT_PREFIX);
+        if !entry.file_type().is_file() || earlier_output || Format::from_path(&input.to_string_lossy()).is_none() {
+            continue;
+        }
+        let relative = input.strip_prefix(root).unwrap_or(input);
+        let output = out_dir.join(relative).with_file_name(name(input));
+        jobs.push(Job::new(input.to_string_lossy(), output.to_string_lossy()));
+    }
+    Ok(jobs)
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Lane {
+    /// Long Ghostscript jobs, biggest first so the last one isn't a giant
+    Documents,
+    /// Short image jobs, smallest first so results start arriving at once
+    Images,
+}
+
+impl Lane {
+    pub fn for_format(format: Option<Format>) -> Lane {
+        match format {
+            Some(Format::Pdf) => Lane::Documents,
+            _ => Lane::Images,
+        }
+    }
+}
+
+/// Rough relative cost of compressing `size_kb` of `format`: a PDF search
+/// re-renders the whole document per probe, pngquant re-quantizes per probe
+/// (Office documents and zips are mostly their images),
+/// cwebp and gifsicle probes are slower re-encodes than JPEG's cheap ones, and
+/// HEIC, RAW and PSD pay a full decode before their JPEG search, TIFF scans go through
+/// the PDF engine, and video is a full re-encode, twice with a target
+pub fn estimated_cost(format: Option<Format>, size_kb: u64) -> u64 {
+    let per_kb = match format {
+        Some(Format::Pdf) | Some(Format::Tiff) | Some(Format::Video) => 20,
+        Some(Format::Png) | Some(Format::Ico) | Some(Format::Office) | Some(Format::Zip) | Some(Format::Comic) => 4,
+        Some(Format::Webp) | Some(Format::Gif) => 2,
+        Some(Format::Heic) | Some(Format::Avif) | Some(Format::Raw) | Some(Format::Psd) => 3,
+        _ => 1,
+    };
+    size_kb.max(1) * per_kb
+}
+
+/// Workers for the (documents, images) lanes, in proportion to each lane's
+/// total cost; a lane with work always gets at least one when there are two
+pub fn split_workers(workers: usize, document_cost: u64, image_cost: u64) -> (usize, usize) {
+    let workers = workers.max(1);
+    match (document_cost, image_cost) {
+        (0, _) => (0, workers),
+        (_, 0) => (workers, 0),
+        // A lone worker clears the quick images first, then moves over
+        _ if workers == 1 => (0, 1),
+        (d, i) => {
+            let share = (workers as f64 * d as f64 / (d + i) as f64).round() as usize;
+            let documents = share.clamp(1, workers - 1);
+            (documents, workers - documents)
+        },
+    }
+}
+
+/// Compress every job with `opts` on up to `workers` threads.
+///
+/// `on_done` is called from the worker threads as each job finishes; the
+/// returned results are in `jobs` order. Decisions are resolved by
+/// `opts.decisions` from several threads at once, so use a non-interactive
+/// policy.
+pub fn compress_all(
+    jobs: &[Job],
+    workers: usize,
+    opts: &CompressionOptions,
+    on_done: impl Fn(&Job, &Result<CompResult>) + Sync,
+) -> Vec<Result<CompResult>> {
+    run_all(jobs, workers, OnError::Continue, |job| compress_job(job, opts), on_done)
+}
+
+/// One job, as [`compress_all`] runs it. With a configured temp dir the job
+/// gets a private one inside it: same-named outputs from different folders
+/// would otherwise share scratch names there.
+pub fn compress_job(job: &Job, opts: &CompressionOptions) -> Result<CompResult> {
+    // Dated and mirrored jobs land in folders that may not exist yet
+    if let Some(parent) = Path::new(&job.output).parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let private = opts.temp_dir.as_ref().map(|dir| tempfile::Builder::new().prefix("crnch-job-").tempdir_in(dir)).transpose()?;
+    if private.is_none() && job.target.is_none() {
+        return compress_file(&job.input, &job.output, opts);
+    }
+    let mut job_opts = opts.clone();
+    if let Some(ref private) = private {
+        job_opts.temp_dir = Some(private.path().to_path_buf());
+    }
+    if let Some(target) = job.target {
+        job_opts.set_target(target);
+    }
+    compress_file(&job.input, &job.output, &job_opts)
+}
+
+/// For each job, the earlier one with the same target whose input is
+/// byte-identical, if any; only inputs that share a size are hashed
+pub fn duplicates(jobs: &[Job]) -> Vec<Option<usize>> {
+    let sizes: Vec<Option<u64>> = jobs.iter().map(|j| std::fs::metadata(&j.input).ok().map(|m| m.len())).collect();
+    let mut shared: HashMap<u64, usize> = HashMap::new();
+    for size in sizes.iter().flatten() {
+        *shared.entry(*size).or_default() += 1;
+    }
+    let mut seen: HashMap<(u64, String, Option<TargetSize>), usize> = HashMap::new();
+    let mut twins = vec![None; jobs.len()];
+    for (i, job) in jobs.iter().enumerate() {
+        let Some(size) = sizes[i].filter(|size| shared[size] > 1) else { continue };
+        // verify's sha256 rather than a blake3 dependency: only same-size
+        // inputs get here, so hashing speed hardly matters
+        let Ok(hash) = verify::sha256_file(Path::new(&job.input)) else { continue };
+        match seen.get(&(size, hash.clone(), job.target)) {
+            Some(&first) => twins[i] = Some(first),
+            None => { seen.insert((size, hash, job.target), i); },
+        }
+    }
+    twins
+}
+
+/// `output` as a hard link to `done`, a twin's finished output; a copy where
+/// links don't work (across filesystems, FAT)
+pub fn link_duplicate(done: &Path, output: &Path) -> Result<()> {
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    if std::fs::hard_link(done, output).is_err() {
+        std::fs::copy(done, output)?;
+    }
+    Ok(())
+}
+
+/// Split `total_kb` over the jobs in proportion to their sizes, so every file
+/// gives up the same fraction; shares round down (to at least 1 KB) and never
+/// add up to more than the total. A set that already fits keeps its sizes.
+pub fn share_budget(jobs: &mut [Job], total_kb: u64) {
+    let sizes: Vec<u64> = jobs.iter().map(|j| std::fs::metadata(&j.input).map_or(0, |m| m.len())).collect();
+    let sum: u64 = sizes.iter().sum();
+    let count = jobs.len() as u64;
+    for (job, size) in jobs.iter_mut().zip(sizes) {
+        // Empty or unreadable inputs split it evenly, to fail on their own
+        let share = match sum {
+            0 => total_kb / count,
+            sum => (total_kb as u128 * size as u128 / sum as u128) as u64,
+        };
+        job.target = Some(TargetSize::from_kb(share.max(1)));
+    }
+}
+
+/// `work` over every job on the per-format lanes of [`compress_all`], for
+/// callers that do more per file than compress it. Under [`OnError::Abort`]
+/// the jobs still queued after a failure get [`NotStarted`].
+pub fn run_all<R: Send>(
+    jobs: &[Job],
+    workers: usize,
+    on_error: OnError,
+    work: impl Fn(&Job) -> Result<R> + Sync,
+    on_done: impl Fn(&Job, &Result<R>) + Sync,
+) -> Vec<Result<R>> {
+    let mut documents: Vec<(usize, u64)> = Vec::new();
+    let mut images: Vec<(usize, u64)> = Vec::new();
+    for (i, job) in jobs.iter().enumerate() {
+        let format = Format::from_path(&job.input);
+        let size_kb = std::fs::metadata(&job.input).map(|m| m.len() / 1024).unwrap_or(0);
+        let cost = estimated_cost(format, size_kb);
+        match Lane::for_format(format) {
+            Lane::Documents => documents.push((i, cost)),
+            Lane::Images => images.push((i, cost)),
+        }
+    }
+    let document_cost = documents.iter().map(|(_, c)| c).sum();
+    let image_cost = images.iter().map(|(_, c)| c).sum();
+    let (document_workers, image_workers) = split_workers(workers.min(jobs.len()), document_cost, image_cost);
+    documents.sort_by_key(|&(_, cost)| std::cmp::Reverse(cost));
+    images.sort_by_key(|&(_, cost)| cost);
+
+    let queues = Mutex::new((
+        documents.into_iter().map(|(i, _)| i).collect::<VecDeque<_>>(),
+        images.into_iter().map(|(i, _)| i).collect::<VecDeque<_>>(),
+    ));
+    let results: Mutex<Vec<Option<Result<R>>>> = Mutex::new(jobs.iter().map(|_| None).collect());
+
+    let next = |lane: Lane| {
+        let mut queues = queues.lock().unwrap();
+        let (documents, images) = &mut *queues;
+        match lane {
+            Lane::Documents => documents.pop_front().or_else(|| images.pop_front()),
+            Lane::Images => images.pop_front().or_else(|| documents.pop_front()),
+        }
+    };
+    let stopped = AtomicBool::new(false);
+    let run = |lane: Lane| {
+        while let Some(i) = next(lane) {
+            let job = &jobs[i];
+            let result = if stopped.load(Ordering::SeqCst) { Err(NotStarted.into()) } else { work(job) };
+            if result.is_err() && on_error == OnError::Abort {
+                stopped.store(true, Ordering::SeqCst);
+            }
+            on_done(job, &result);
+            results.lock().unwrap()[i] = Some(result);
+        }
+    };
+    std::thread::scope(|scope| {
+        for _ in 0..document_workers {
+            scope.spawn(|| run(Lane::Documents));
+        }
+        for _ in 0..image_workers {
+            scope.spawn(|| run(Lane::Images));
+        }
+    });
+
+    results.into_inner().unwrap().into_iter()
+        .map(|r| r.expect("every queued job runs"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::compression::tests::{FakeRun, FakeTools};
+
+    #[test]
+    fn test_split_follows_cost() {
+        assert_eq!(split_workers(4, 0, 10), (0, 4));
+        assert_eq!(split_workers(4, 10, 0), (4, 0));
+        assert_eq!(split_workers(1, 10, 10), (0, 1));
+        assert_eq!(split_workers(4, 1000, 10), (3, 1));
+        assert_eq!(split_workers(4, 10, 1000), (1, 3));
+        assert_eq!(split_workers(4, 50, 50), (2, 2));
+    }
+
+    #[test]
+    fn test_images_finish_before_documents_on_one_worker() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut jobs = Vec::new();
+        for (name, kb) in [("big.pdf", 40), ("small.jpg", 4), ("mid.png", 8)] {
+            let input = dir.path().join(name);
+            fs::write(&input, vec![0u8; kb * 1024]).unwrap();
+            jobs.push(Job::new(input.to_string_lossy(), dir.path().join(format!("crnched_{}", name)).to_string_lossy()));
+        }
+        let opts = CompressionOptions::builder()
+            .executor(FakeTools::new(|_| FakeRun::Write(2)))
+            .build();
+        let order = Mutex::new(Vec::new());
+
+        let results = compress_all(&jobs, 1, &opts, |job, _| order.lock().unwrap().push(job.input.clone()));
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|r| r.is_ok()));
+        let order = order.into_inner().unwrap();
+        assert_eq!(order, [jobs[1].input.clone(), jobs[2].input.clone(), jobs[0].input.clone()]);
+    }
+
+    #[test]
+    fn test_mirror_tree_keeps_layout_and_skips_its_own_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("photos");
+        fs::create_dir_all(root.join("2024/trip")).unwrap();
+        fs::create_dir_all(root.join("small")).unwrap();
+        fs::create_dir_all(root.join("web/node_modules")).unwrap();
+        for name in ["a.png", "crnched_a.png", "notes.txt", "2024/trip/b.JPG", "small/a.png", "web/node_modules/icon.png", "web/a_thumb.png"] {
+            fs::write(root.join(name), b"x").unwrap();
+        }
+        fs::write(root.join(".crnchignore"), "node_modules\n").unwrap();
+        let out = root.join("small");
+        let jobs = mirror_tree(&root, &out, &Ignore::new(["*_thumb.*"]), |p| p.file_name().unwrap().to_string_lossy().to_lowercase()).unwrap();
+        let job = |input: &str, output: &str| Job::new(root.join(input).to_string_lossy(), out.join(output).to_string_lossy());
+        assert_eq!(jobs, [job("2024/trip/b.JPG", "2024/trip/b.jpg"), job("a.png", "a.png")]);
+    }
+
+    #[test]
+    fn test_selection_by_size_and_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = |name: &str, kb: usize| {
+            let path = dir.path().join(name);
+            fs::write(&path, vec![0u8; kb * 1024]).unwrap();
+            path
+        };
+        let (icon, photo, scan) = (file("icon.png", 2), file("photo.jpg", 600), file("scan.pdf", 600));
+        assert!(Selection::default().is_unrestricted() && Selection::default().admits(&icon));
+
+        let selection = Selection { min_bytes: Some(500 * 1024), max_bytes: Some(50 << 20), only: vec![Format::Jpg, Format::Png] };
+        assert!(!selection.is_unrestricted());
+        assert!(!selection.admits(&icon) && selection.admits(&photo) && !selection.admits(&scan));
+        assert!(!Selection { max_bytes: Some(100 * 1024), ..Default::default() }.admits(&photo));
+        assert!(selection.admits(&dir.path().join("missing.jpg")));
+    }
+
+    #[test]
+    fn test_budget_shares_follow_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut jobs = Vec::new();
+        for (name, kb) in [("deck.pdf", 6000), ("photo.jpg", 3000), ("logo.png", 1)] {
+            let input = dir.path().join(name);
+            fs::write(&input, vec![0u8; kb * 1024]).unwrap();
+            jobs.push(Job::new(input.to_string_lossy(), name));
+        }
+        share_budget(&mut jobs, 2000);
+        let shares: Vec<u64> = jobs.iter().map(|j| j.target.unwrap().kb()).collect();
+        assert_eq!(shares, [1333, 666, 1]);
+        assert!(shares.iter().sum::<u64>() <= 2000);
+
+        // Already fits: every file keeps its own size
+        share_budget(&mut jobs, 9001);
+        assert_eq!(jobs.iter().map(|j| j.target.unwrap().kb()).collect::<Vec<_>>(), [6000, 3000, 1]);
+    }
+
+    #[test]
+    fn test_duplicates_point_at_the_first_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut jobs = Vec::new();
+        for (name, data) in [("a.jpg", b"same".as_slice()), ("b.jpg", b"diff"), ("export/a copy.jpg", b"same"), ("c.jpg", b"longer")] {
+            let input = dir.path().join(name);
+            fs::create_dir_all(input.parent().unwrap()).unwrap();
+            fs::write(&input, data).unwrap();
+            jobs.push(Job::new(input.to_string_lossy(), name));
+        }
+        assert_eq!(duplicates(&jobs), [None, None, Some(0), None]);
+        // The same input at another size is its own job
+        jobs[2].target = Some(TargetSize::from_kb(50));
+        assert_eq!(duplicates(&jobs), [None, None, None, None]);
+
+        let done = dir.path().join("crnched_a.jpg");
+        fs::write(&done, b"small").unwrap();
+        let twin = dir.path().join("out/crnched_a copy.jpg");
+        link_duplicate(&done, &twin).unwrap();
+        assert_eq!(fs::read(&twin).unwrap(), b"small");
+    }
+
+    #[test]
+    fn test_abort_leaves_the_rest_unstarted() {
+        let jobs: Vec<Job> = ["a.png", "b.png", "c.png"].iter().map(|name| Job::new(*name, *name)).collect();
+        let ran = Mutex::new(Vec::new());
+        let work = |job: &Job| {
+            ran.lock().unwrap().push(job.input.clone());
+            if job.input == "a.png" { Err(anyhow::anyhow!("broken")) } else { Ok(()) }
+        };
+
+        let results = run_all(&jobs, 1, OnError::Abort, work, |_, _| {});
+        assert_eq!(*ran.lock().unwrap(), ["a.png"]);
+        assert!(results[1..].iter().all(|r| r.as_ref().is_err_and(|e| e.is::<NotStarted>())));
+
+        ran.lock().unwrap().clear();
+        let results = run_all(&jobs, 1, OnError::Continue, work, |_, _| {});
+        assert_eq!(ran.lock().unwrap().len(), 3);
+        assert!(results[0].is_err() && results[1..].iter().all(|r| r.is_ok()));
+    }
+
+    #[test]
+    fn test_same_named_jobs_get_private_scratch_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let scratch = dir.path().join("scratch");
+        fs::create_dir_all(&scratch).unwrap();
+        let mut jobs = Vec::new();
+        for folder in ["a", "b"] {
+            fs::create_dir_all(dir.path().join(folder)).unwrap();
+            let input = dir.path().join(folder).join("photo.png");
+            fs::write(&input, vec![0u8; 64 * 1024]).unwrap();
+            jobs.push(Job::new(input.to_string_lossy(), dir.path().join("out").join(folder).join("photo.png").to_string_lossy()));
+        }
+        let written = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let log = written.clone();
+        let opts = CompressionOptions::builder()
+            .temp_dir(&scratch)
+            .executor(FakeTools::new(move |cmd| {
+                log.lock().unwrap().push(cmd.output_path().unwrap_or_default().to_string());
+                FakeRun::Write(2)
+            }))
+            .build();
+
+        let results = compress_all(&jobs, 2, &opts, |_, _| {});
+        assert!(results.iter().all(|r| r.is_ok()));
+        let in_scratch: Vec<String> = written.lock().unwrap().iter().filter(|p| p.starts_with(&*scratch.to_string_lossy())).cloned().collect();
+        assert!(!in_scratch.is_empty() && in_scratch.iter().all(|p| p.contains("crnch-job-")));
+        let job_dirs: std::collections::HashSet<&str> = in_scratch.iter().map(|p| &p[..p.find("crnch-job-").unwrap() + 16]).collect();
+        assert_eq!(job_dirs.len(), 2);
+        assert_eq!(fs::read_dir(&scratch).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_dated_jobs_create_their_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("photo.jpg");
+        fs::write(&input, vec![0u8; 8 * 1024]).unwrap();
+        let job = Job::dated(input.to_string_lossy(), &dir.path().join("out"), "photo.jpg");
+        let month = organize::capture_month(&input).unwrap();
+        assert_eq!(Path::new(&job.output), month.dir_in(&dir.path().join("out")).join("photo.jpg"));
+
+        let opts = CompressionOptions::builder().executor(FakeTools::new(|_| FakeRun::Write(2))).build();
+        let results = compress_all(std::slice::from_ref(&job), 2, &opts, |_, _| {});
+        assert!(results[0].is_ok());
+        assert!(Path::new(&job.output).exists());
+    }
+}
@@ -0,0 +1,139 @@
+//! BMP header reading, and the content check that picks PNG or JPEG output.
+
+use std::path::Path;
+use crate::format::Format;
+use crate::screenshot::{FlatCounter, MAX_ROWS};
+
+/// Parsed `BITMAPFILEHEADER` and `BITMAPINFOHEADER` (or a later version)
+struct Header {
+    file_size: usize,
+    pixel_offset: usize,
+    width: u32,
+    height: u32,
+    bits: u16,
+    /// `BI_RGB` (0), RLE (1, 2) or `BI_BITFIELDS` (3)
+    compression: u32,
+    alpha_mask: u32,
+}
+
+fn header(data: &[u8]) -> Option<Header> {
+    if data.len() < 54 || !data.starts_with(b"BM") {
+        return None;
+    }
+    let u16_at = |p: usize| u16::from_le_bytes([data[p], data[p + 1]]);
+    let u32_at = |p: usize| u32::from_le_bytes([data[p], data[p + 1], data[p + 2], data[p + 3]]);
+    let info_size = u32_at(14) as usize;
+    if info_size < 40 {
+        return None;
+    }
+    // V4 and V5 headers carry the alpha mask after the RGB masks
+    let alpha_mask = if info_size >= 56 { u32_at(14 + 52) } else { 0 };
+    Some(Header {
+        file_size: u32_at(2) as usize,
+        pixel_offset: u32_at(10) as usize,
+        width: (u32_at(18) as i32).unsigned_abs(),
+        // Negative heights are top-down rows
+        height: (u32_at(22) as i32).unsigned_abs(),
+        bits: u16_at(28),
+        compression: u32_at(30),
+        alpha_mask,
+    })
+}
+
+pub fn dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    header(data).map(|h| (h.width, h.height))
+}
+
+/// 32-bit with an alpha mask; 32-bit BMPs without one leave that byte unused
+pub fn has_alpha(data: &[u8]) -> bool {
+    header(data).is_some_and(|h| h.bits == 32 && h.alpha_mask != 0)
+}
+
+/// Whether the header's file size covers exactly the bytes present (some
+/// writers leave it 0, then the pixel array has to fit)
+pub fn is_complete(data: &[u8]) -> bool {
+    header(data).is_some_and(|h| match h.file_size {
+        0 => h.compression != 0 || h.pixel_offset + stride(&h) * h.height as usize <= data.len(),
+        size => size == data.len(),
+    })
+}
+
+fn stride(h: &Header) -> usize {
+    (h.bits as usize * h.width as usize).div_ceil(32) * 4
+}
+
+/// Flat color areas (screenshots, diagrams) compress best as PNG; photos as
+/// JPEG. Palette images count as flat, anything unreadable as a photo.
+pub fn looks_flat(data: &[u8]) -> bool {
+    let Some(h) = header(data) else { return false };
+    if h.bits <= 8 {
+        return true;
+    }
+    if !matches!((h.bits, h.compression), (24, 0) | (32, 0 | 3)) {
+        return false;
+    }
+    let channels = h.bits as usize / 8;
+    let row_len = h.width as usize * channels;
+    let stride = stride(&h);
+    let Some(pixels) = data.get(h.pixel_offset..) else { return false };
+    let mut counter = FlatCounter::default();
+    for row in pixels.chunks_exact(stride).take(MAX_ROWS.min(h.height as usize)) {
+        counter.add_row(&row[..row_len], channels);
+    }
+    counter.is_flat()
+}
+
+/// What the BMP at `path` is written as: PNG for flat content or alpha, JPEG for photos
+pub fn output_format(path: &Path) -> Format {
+    match std::fs::read(path) {
+        Ok(data) if looks_flat(&data) || has_alpha(&data) => Format::Png,
+        Ok(_) => Format::Jpg,
+        Err(_) => Format::Png,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Bottom-up 24-bit BMP from `pixel(x, y)` (RGB, written as BGR)
+    pub(crate) fn bmp(width: u32, height: u32, pixel: impl Fn(u32, u32) -> [u8; 3]) -> Vec<u8> {
+        let stride = (24 * width as usize).div_ceil(32) * 4;
+        let mut pixels = Vec::new();
+        for y in (0..height).rev() {
+            let start = pixels.len();
+            for x in 0..width {
+                let [r, g, b] = pixel(x, y);
+                pixels.extend([b, g, r]);
+            }
+            pixels.resize(start + stride, 0);
+        }
+        let mut data = b"BM".to_vec();
+        data.extend((54 + pixels.len() as u32).to_le_bytes());
+        data.extend([0, 0, 0, 0]);
+        data.extend(54u32.to_le_bytes());
+        data.extend(40u32.to_le_bytes());
+        data.extend(width.to_le_bytes());
+        data.extend(height.to_le_bytes());
+        data.extend(1u16.to_le_bytes());
+        data.extend(24u16.to_le_bytes());
+        data.extend([0; 24]);
+        data.extend(pixels);
+        data
+    }
+
+    #[test]
+    fn test_header_and_content() {
+        let ui = bmp(30, 20, |_, y| if y < 4 { [40, 44, 52] } else { [255, 255, 255] });
+        assert_eq!(dimensions(&ui), Some((30, 20)));
+        assert!(is_complete(&ui) && !has_alpha(&ui));
+        assert!(looks_flat(&ui));
+        assert!(!is_complete(&ui[..ui.len() - 1]));
+
+        let photo = bmp(30, 20, |x, y| {
+            let n = x.wrapping_mul(2_654_435_761).wrapping_add(y.wrapping_mul(40_503));
+            [(n >> 3) as u8, (n >> 11) as u8, (n >> 19) as u8]
+        });
+        assert!(!looks_flat(&photo));
+    }
+}
@@ -0,0 +1,42 @@
+//! Butteraugli distance of an output from its input (0 is identical, about 1
+//! is the edge of visible), by libjxl's `butteraugli_main`; the
+//! `--max-distance` criterion of [`perceptual`](crate::perceptual).
+//!
+//! butteraugli reads PNG and JPEG only, so anything else is decoded to a
+//! PNG first.
+
+use std::fs;
+use anyhow::{anyhow, Result};
+use crate::artifacts::Scratch;
+use crate::compression::run_tool;
+use crate::exec::ToolCommand;
+use crate::format::Format;
+use crate::options::CompressionOptions;
+
+/// Distance of `candidate` from `original`; `scratch` is a path prefix for the decodes and the report
+pub fn distance(original: &str, candidate: &str, scratch: &str, opts: &CompressionOptions) -> Result<f64> {
+    let mut files = Scratch::default();
+    let mut readable = |path: &str, name: &str| -> Result<String> {
+        if matches!(Format::from_path(path), Some(Format::Png | Format::Jpg)) {
+            return Ok(path.to_string());
+        }
+        let decoded = format!("{}.{}.png", scratch, name);
+        files.track(&decoded);
+        let status = run_tool(opts, ToolCommand::new("magick").arg(path).arg(&decoded).quiet_stderr(!opts.nerd))?;
+        if !status.success() {
+            return Err(anyhow!("ImageMagick could not decode {} for butteraugli.", path));
+        }
+        Ok(decoded)
+    };
+    let (original_png, candidate_png) = (readable(original, "original")?, readable(candidate, "candidate")?);
+    let report = format!("{}.txt", scratch);
+    files.track(&report);
+    let mut cmd = ToolCommand::new("butteraugli_main");
+    cmd.arg(&original_png).arg(&candidate_png).stdout_to(&report).quiet_stderr(!opts.nerd);
+    let ran = run_tool(opts, &cmd)?.success();
+    // The first line is the max-norm distance; a 3-norm line follows
+    fs::read_to_string(&report).ok()
+        .and_then(|text| text.split_whitespace().next().and_then(|d| d.parse::<f64>().ok()))
+        .filter(|_| ran)
+        .ok_or_else(|| anyhow!("butteraugli could not compare {} with {}.", candidate, original))
+}
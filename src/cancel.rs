@@ -0,0 +1,100 @@
+use std::fmt;
+use std::process::{Child, Command, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::Result;
+
+/// Error returned when a run was stopped through its [`CancellationToken`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Compression cancelled.")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Shared flag that asks a running compression to stop.
+///
+/// Clones share the same flag, so the CLI can keep one for its Ctrl+C
+/// handler and hand another to the engines via the options.
+#[derive(Clone, Default, Debug)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// `Err(Cancelled)` once cancellation was requested
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(Cancelled.into());
+        }
+        Ok(())
+    }
+
+    /// Run `cmd` to completion, killing it if cancellation is requested
+    pub fn run(&self, cmd: &mut Command) -> Result<ExitStatus> {
+        self.check()?;
+        let child = cmd.spawn()?;
+        self.wait(child)
+    }
+
+    fn wait(&self, mut child: Child) -> Result<ExitStatus> {
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(status);
+            }
+            if self.is_cancelled() {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(Cancelled.into());
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_clones_share_state() {
+        let token = CancellationToken::new();
+        let other = token.clone();
+        assert!(token.check().is_ok());
+        other.cancel();
+        assert!(token.is_cancelled());
+        let err = token.check().unwrap_err();
+        assert!(err.downcast_ref::<Cancelled>().is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_kills_child_on_cancel() {
+        let token = CancellationToken::new();
+        let killer = token.clone();
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            killer.cancel();
+        });
+        let started = std::time::Instant::now();
+        let err = token.run(Command::new("sleep").arg("10")).unwrap_err();
+        handle.join().unwrap();
+        assert!(err.downcast_ref::<Cancelled>().is_some());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
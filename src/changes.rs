@@ -0,0 +1,303 @@
+//! Consequential changes between an input and its compressed output.
+//!
+//! The size summary says what was saved; these say what was lost on the way
+//! (a color profile, transparency, fillable form fields), found by reading
+//! both files' structure after the run.
+
+use std::path::Path;
+use serde::Serialize;
+use crate::bmp;
+use crate::format::Format;
+use crate::gif;
+use crate::psd;
+use crate::webp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    IccRemoved,
+    CmykToRgb,
+    AlphaFlattened,
+    OrientationBaked,
+    OrientationRemoved,
+    FormsFlattened,
+}
+
+/// One thing the output no longer has, with a sentence for the user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub message: String,
+}
+
+impl Change {
+    fn new(kind: ChangeKind, message: &str) -> Self {
+        Change { kind, message: message.to_string() }
+    }
+}
+
+/// What the container says about a file, as far as the changes care
+#[derive(Debug, Default, PartialEq)]
+struct Traits {
+    icc: bool,
+    cmyk: bool,
+    alpha: bool,
+    /// EXIF orientation tag (1-8)
+    orientation: Option<u16>,
+    dimensions: Option<(u32, u32)>,
+    forms: bool,
+}
+
+/// Changes from `input` to `output`; unreadable files report none. The
+/// output is read by its own extension when that differs (PNG to WebP).
+pub fn detect(input: &Path, output: &Path, format: Format) -> Vec<Change> {
+    let (Ok(before), Ok(after)) = (std::fs::read(input), std::fs::read(output)) else { return Vec::new() };
+    let output_format = Format::from_path(&output.to_string_lossy()).unwrap_or(format);
+    compare(&inspect(&before, format), &inspect(&after, output_format))
+}
+
+/// Width and height from the file's header, where the container has them
+pub fn dimensions(path: &Path, format: Format) -> Option<(u32, u32)> {
+    inspect(&std::fs::read(path).ok()?, format).dimensions
+}
+
+fn compare(before: &Traits, after: &Traits) -> Vec<Change> {
+    let mut changes = Vec::new();
+    if before.icc && !after.icc {
+        changes.push(Change::new(ChangeKind::IccRemoved, "Embedded ICC color profile removed; colors may shift in color-managed apps"));
+    }
+    if before.cmyk && !after.cmyk {
+        changes.push(Change::new(ChangeKind::CmykToRgb, "CMYK converted to RGB; printed colors may differ"));
+    }
+    if before.alpha && !after.alpha {
+        changes.push(Change::new(ChangeKind::AlphaFlattened, "Transparency flattened onto a solid background"));
+    }
+    if let Some(orientation) = before.orientation.filter(|o| (2..=8).contains(o)) {
+        if after.orientation.is_none_or(|o| o == 1) {
+            let swapped = match (before.dimensions, after.dimensions) {
+                (Some((w, h)), Some((w2, h2))) => w != h && w == h2 && h == w2,
+                _ => false,
+            };
+            changes.push(if orientation >= 5 && swapped {
+                Change::new(ChangeKind::OrientationBaked, "EXIF rotation applied to the pixels")
+            } else {
+                Change::new(ChangeKind::OrientationRemoved, "EXIF orientation removed; the image may now display rotated or mirrored")
+            });
+        }
+    }
+    if before.forms && !after.forms {
+        changes.push(Change::new(ChangeKind::FormsFlattened, "PDF form fields flattened; they can no longer be filled in"));
+    }
+    changes
+}
+
+fn inspect(data: &[u8], format: Format) -> Traits {
+    match format {
+        Format::Jpg => inspect_jpeg(data),
+        Format::Png => inspect_png(data),
+        Format::Pdf => inspect_pdf(data),
+        Format::Webp => inspect_webp(data),
+        Format::Heic | Format::Avif => inspect_heic(data),
+        Format::Svg | Format::Raw | Format::Tiff | Format::Ico | Format::Office | Format::Zip | Format::Comic | Format::Video => Traits::default(),
+        Format::Bmp => Traits { alpha: bmp::has_alpha(data), dimensions: bmp::dimensions(data), ..Traits::default() },
+        Format::Psd => Traits { alpha: psd::has_alpha(data), dimensions: psd::dimensions(data), ..Traits::default() },
+        Format::Gif => Traits { alpha: gif::has_transparency(data), dimensions: gif::dimensions(data), ..Traits::default() },
+    }
+}
+
+fn inspect_jpeg(data: &[u8]) -> Traits {
+    let mut traits = Traits::default();
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return traits;
+    }
+    let mut pos = 2;
+    while pos + 4 <= data.len() && data[pos] == 0xFF {
+        let marker = data[pos + 1];
+        match marker {
+            0xFF => { pos += 1; continue; },
+            0x01 | 0xD0..=0xD7 => { pos += 2; continue; },
+            0xD9 | 0xDA => break,
+            _ => {},
+        }
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let Some(payload) = data.get(pos + 4..pos + 2 + len) else { break };
+        match marker {
+            0xE1 if payload.starts_with(b"Exif\0\0") => traits.orientation = exif_orientation(&payload[6..]),
+            0xE2 if payload.starts_with(b"ICC_PROFILE\0") => traits.icc = true,
+            // Start of frame (not DHT, JPG or DAC, which share the range)
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) && payload.len() >= 6 => {
+                let height = u16::from_be_bytes([payload[1], payload[2]]) as u32;
+                let width = u16::from_be_bytes([payload[3], payload[4]]) as u32;
+                traits.dimensions = Some((width, height));
+                traits.cmyk = payload[5] == 4;
+            },
+            _ => {},
+        }
+        pos += 2 + len;
+    }
+    traits
+}
+
+fn inspect_png(data: &[u8]) -> Traits {
+    let mut traits = Traits::default();
+    if !data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return traits;
+    }
+    let mut pos = 8;
+    while pos + 8 <= data.len() {
+        let len = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        let Some(body) = data.get(pos + 8..pos + 8 + len) else { break };
+        match kind {
+            b"IHDR" if body.len() >= 10 => {
+                let width = u32::from_be_bytes([body[0], body[1], body[2], body[3]]);
+                let height = u32::from_be_bytes([body[4], body[5], body[6], body[7]]);
+                traits.dimensions = Some((width, height));
+                // Gray + alpha or RGBA
+                traits.alpha = matches!(body[9], 4 | 6);
+            },
+            b"tRNS" => traits.alpha = true,
+            b"iCCP" => traits.icc = true,
+            b"eXIf" => traits.orientation = exif_orientation(body),
+            b"IEND" => break,
+            _ => {},
+        }
+        pos += 12 + len;
+    }
+    traits
+}
+
+fn inspect_webp(data: &[u8]) -> Traits {
+    let mut traits = Traits {
+        dimensions: webp::dimensions(data),
+        orientation: webp::exif(data).and_then(exif_orientation),
+        ..Traits::default()
+    };
+    for (kind, body) in webp::chunks(data) {
+        match kind {
+            b"VP8X" if !body.is_empty() => traits.alpha |= body[0] & webp::FLAG_ALPHA != 0,
+            b"ICCP" => traits.icc = true,
+            b"ALPH" => traits.alpha = true,
+            // Lossless bitstreams carry an alpha hint after the dimensions
+            b"VP8L" if body.len() >= 5 => traits.alpha |= body[4] & 0x10 != 0,
+            _ => {},
+        }
+    }
+    traits
+}
+
+/// HEIF boxes found by their bytes; the `ispe` box holds the image size
+fn inspect_heic(data: &[u8]) -> Traits {
+    let find = |needle: &[u8]| data.windows(needle.len()).position(|w| w == needle);
+    Traits {
+        icc: find(b"colrprof").is_some(),
+        alpha: find(b"urn:mpeg:hevc:2015:auxid:1").is_some() || find(b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha").is_some(),
+        dimensions: find(b"ispe").and_then(|i| data.get(i + 8..i + 16)).map(|b| (
+            u32::from_be_bytes([b[0], b[1], b[2], b[3]]),
+            u32::from_be_bytes([b[4], b[5], b[6], b[7]]),
+        )),
+        ..Traits::default()
+    }
+}
+
+fn inspect_pdf(data: &[u8]) -> Traits {
+    let has = |needle: &[u8]| data.windows(needle.len()).any(|w| w == needle);
+    Traits {
+        icc: has(b"/ICCBased"),
+        cmyk: has(b"/DeviceCMYK"),
+        forms: has(b"/AcroForm") && has(b"/Widget"),
+        ..Traits::default()
+    }
+}
+
+/// Orientation tag from IFD0 of a TIFF-structured EXIF block
+fn exif_orientation(tiff: &[u8]) -> Option<u16> {
+    let big_endian = match tiff.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |at: usize| tiff.get(at..at + 2).map(|b| if big_endian { u16::from_be_bytes([b[0], b[1]]) } else { u16::from_le_bytes([b[0], b[1]]) });
+    let ifd = tiff.get(4..8).map(|b| {
+        let b = [b[0], b[1], b[2], b[3]];
+        if big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) }
+    })? as usize;
+    (0..u16_at(ifd)? as usize)
+        .map(|i| ifd + 2 + i * 12)
+        .find(|&entry| u16_at(entry) == Some(0x0112))
+        .and_then(|entry| u16_at(entry + 8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let mut seg = vec![0xFF, marker];
+        seg.extend(((payload.len() + 2) as u16).to_be_bytes());
+        seg.extend(payload);
+        seg
+    }
+
+    fn jpeg(width: u16, height: u16, orientation: Option<u16>, icc: bool) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8];
+        if let Some(o) = orientation {
+            let mut exif = b"Exif\0\0II\x2a\x00\x08\x00\x00\x00\x01\x00\x12\x01\x03\x00\x01\x00\x00\x00".to_vec();
+            exif.extend(o.to_le_bytes());
+            exif.extend([0, 0, 0, 0, 0, 0]);
+            data.extend(segment(0xE1, &exif));
+        }
+        if icc {
+            data.extend(segment(0xE2, b"ICC_PROFILE\0\x01\x01"));
+        }
+        let mut sof = vec![8];
+        sof.extend(height.to_be_bytes());
+        sof.extend(width.to_be_bytes());
+        sof.push(3);
+        data.extend(segment(0xC0, &sof));
+        data.extend([0xFF, 0xDA]);
+        data
+    }
+
+    #[test]
+    fn test_jpeg_profile_and_orientation() {
+        let before = inspect_jpeg(&jpeg(400, 300, Some(6), true));
+        assert_eq!(before, Traits { icc: true, orientation: Some(6), dimensions: Some((400, 300)), ..Traits::default() });
+
+        let kinds = |after: &[u8]| compare(&before, &inspect_jpeg(after)).into_iter().map(|c| c.kind).collect::<Vec<_>>();
+        assert_eq!(kinds(&jpeg(300, 400, None, false)), [ChangeKind::IccRemoved, ChangeKind::OrientationBaked]);
+        assert_eq!(kinds(&jpeg(400, 300, None, true)), [ChangeKind::OrientationRemoved]);
+        assert!(kinds(&jpeg(400, 300, Some(6), true)).is_empty());
+    }
+
+    #[test]
+    fn test_png_alpha_and_pdf_forms() {
+        let png = |color_type: u8| {
+            let mut data = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x10\0\0\0\x10\x08".to_vec();
+            data.extend([color_type, 0, 0, 0, 0, 0, 0, 0]);
+            data
+        };
+        let changes = compare(&inspect_png(&png(6)), &inspect_png(&png(2)));
+        assert_eq!(changes.iter().map(|c| c.kind).collect::<Vec<_>>(), [ChangeKind::AlphaFlattened]);
+
+        let form = inspect_pdf(b"<< /AcroForm 5 0 R >> << /Subtype /Widget /ColorSpace /DeviceCMYK >>");
+        let flat = inspect_pdf(b"<< /ColorSpace /DeviceRGB >>");
+        let kinds: Vec<_> = compare(&form, &flat).into_iter().map(|c| c.kind).collect();
+        assert_eq!(kinds, [ChangeKind::CmykToRgb, ChangeKind::FormsFlattened]);
+    }
+
+    #[test]
+    fn test_jpeg_to_webp_keeps_profile() {
+        use crate::webp::{tests::webp, FLAG_ALPHA, FLAG_ICC};
+        let dir = tempfile::tempdir().unwrap();
+        let (input, output) = (dir.path().join("a.jpg"), dir.path().join("a.webp"));
+        std::fs::write(&input, jpeg(3, 2, None, true)).unwrap();
+        std::fs::write(&output, webp(FLAG_ICC, &[(b"ICCP", b"icc")])).unwrap();
+        assert!(detect(&input, &output, Format::Jpg).is_empty());
+
+        let with_alpha = inspect_webp(&webp(FLAG_ALPHA, &[]));
+        assert_eq!(with_alpha, Traits { alpha: true, dimensions: Some((3, 2)), ..Traits::default() });
+        std::fs::write(&output, webp(0, &[])).unwrap();
+        assert_eq!(detect(&input, &output, Format::Jpg)[0].kind, ChangeKind::IccRemoved);
+    }
+}
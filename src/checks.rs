@@ -1,8 +1,17 @@
 use anyhow::Result;
 use colored::*;
+use crnch::format::Format;
+use crnch::t;
+use crate::theme::{Paint, Role};
 use which::which;
 
-pub fn check_dependencies() -> Result<()> {
+/// The tools every run needs, plus the optional ones `formats` need
+pub fn check_dependencies(formats: &[Format]) -> Result<()> {
+    check_required()?;
+    formats.iter().try_for_each(|&format| check_format_tool(format))
+}
+
+fn check_required() -> Result<()> {
     let tools = ["gs", "magick", "pngquant", "jpegoptim", "oxipng"];
     let mut missing_tools = Vec::new();
 
@@ -18,35 +27,91 @@ pub fn check_dependencies() -> Result<()> {
     }
 
     // 2. If missing, report error and give specific install instructions
-    println!("\n{} Missing dependencies: {:?}", "❌ Error:".red().bold(), missing_tools);
-    println!("{}", "crnch relies on external industry-standard tools.".yellow());
-    println!("\n{}", "⬇️  Run this command to install them:".blue().bold());
+    outln!("\n{} {}", format!("❌ {}", t!("error-label")).paint(Role::Error).bold(), t!("error-missing-tools", tools = format!("{:?}", missing_tools)));
+    outln!("{}", t!("missing-tools-why").paint(Role::Warning));
+    outln!("\n{}", format!("⬇️  {}", t!("missing-tools-install")).paint(Role::Accent).bold());
 
     let info = os_info::get();
     
     // Smart Distro Detection
     match info.os_type() {
         os_info::Type::Arch => {
-            println!("   {}", "sudo pacman -S ghostscript imagemagick pngquant".green());
-            println!("   {} {}", "OR via Yay:".dimmed(), "yay -S ghostscript imagemagick pngquant".green());
+            outln!("   {}", "sudo pacman -S ghostscript imagemagick pngquant".paint(Role::Success));
+            outln!("   {} {}", t!("missing-tools-yay").paint(Role::Label), "yay -S ghostscript imagemagick pngquant".paint(Role::Success));
         },
         os_info::Type::Ubuntu | os_info::Type::Debian | os_info::Type::Pop | os_info::Type::Mint => {
-            println!("   {}", "sudo apt update && sudo apt install ghostscript imagemagick pngquant".green());
+            outln!("   {}", "sudo apt update && sudo apt install ghostscript imagemagick pngquant".paint(Role::Success));
         },
         os_info::Type::Fedora | os_info::Type::CentOS => {
-            println!("   {}", "sudo dnf install ghostscript ImageMagick pngquant".green());
+            outln!("   {}", "sudo dnf install ghostscript ImageMagick pngquant".paint(Role::Success));
         },
         os_info::Type::Macos => {
-            println!("   {}", "brew install ghostscript imagemagick pngquant".green());
+            outln!("   {}", "brew install ghostscript imagemagick pngquant".paint(Role::Success));
         },
         _ => {
             // Fallback / Unknown Linux
-            println!("   {}", "Arch:   sudo pacman -S ghostscript imagemagick pngquant".green());
-            println!("   {}", "Debian: sudo apt install ghostscript imagemagick pngquant".green());
-            println!("   {}", "Mac:    brew install ghostscript imagemagick pngquant".green());
+            outln!("   {}", t!("missing-tools-arch", command = "sudo pacman -S ghostscript imagemagick pngquant").paint(Role::Success));
+            outln!("   {}", t!("missing-tools-debian", command = "sudo apt install ghostscript imagemagick pngquant").paint(Role::Success));
+            outln!("   {}", t!("missing-tools-mac", command = "brew install ghostscript imagemagick pngquant").paint(Role::Success));
         }
     }
 
-    println!();
+    outln!();
+    std::process::exit(1);
+}
+
+/// The tool only `format` needs, if any: cwebp for WebP, gifsicle for GIF,
+/// avifenc for AVIF, dcraw (or darktable-cli) for camera RAW, ffmpeg for video
+pub fn check_format_tool(format: Format) -> Result<()> {
+    match format {
+        Format::Webp => check_optional("cwebp", "why-cwebp", ["libwebp", "webp", "libwebp-tools", "webp"]),
+        Format::Gif => check_optional("gifsicle", "why-gifsicle", ["gifsicle", "gifsicle", "gifsicle", "gifsicle"]),
+        Format::Avif => check_optional("avifenc", "why-avifenc", ["libavif", "libavif-bin", "libavif-tools", "libavif"]),
+        Format::Raw if which("darktable-cli").is_ok() => Ok(()),
+        Format::Raw => check_optional("dcraw", "why-dcraw", ["dcraw", "dcraw", "dcraw", "dcraw"]),
+        Format::Video => check_optional("ffmpeg", "why-ffmpeg", ["ffmpeg", "ffmpeg", "ffmpeg-free", "ffmpeg"]),
+        _ => Ok(()),
+    }
+}
+
+/// heif-enc, needed only to write HEIC (`--keep-heic`)
+pub fn check_heif_enc() -> Result<()> {
+    check_optional("heif-enc", "why-heif-enc", ["libheif", "libheif-examples", "libheif-tools", "libheif"])
+}
+
+/// zstd, needed only for `--zstd` archives
+pub fn check_zstd() -> Result<()> {
+    check_optional("zstd", "why-zstd", ["zstd", "zstd", "zstd", "zstd"])
+}
+
+/// butteraugli_main, needed only for `--max-distance`
+pub fn check_butteraugli() -> Result<()> {
+    check_optional("butteraugli_main", "why-butteraugli", ["libjxl", "libjxl-tools", "libjxl-utils", "jpeg-xl"])
+}
+
+/// ocrmypdf and the tesseract it runs, needed only for `--ocr`
+pub fn check_ocr() -> Result<()> {
+    check_optional("tesseract", "why-tesseract", ["tesseract", "tesseract-ocr", "tesseract", "tesseract"])?;
+    check_optional("ocrmypdf", "why-ocrmypdf", ["ocrmypdf", "ocrmypdf", "ocrmypdf", "ocrmypdf"])
+}
+
+/// One tool only some formats need; `why` is its catalog key, `packages`
+/// for Arch, Debian, Fedora and macOS
+fn check_optional(tool: &str, why: &str, packages: [&str; 4]) -> Result<()> {
+    if which(tool).is_ok() {
+        return Ok(());
+    }
+    outln!("\n{} {}", format!("❌ {}", t!("error-label")).paint(Role::Error).bold(), t!("error-missing-tool", tool = tool));
+    outln!("{}", t!(why).paint(Role::Warning));
+    outln!("\n{}", format!("⬇️  {}", t!("missing-tool-install")).paint(Role::Accent).bold());
+    let install = match os_info::get().os_type() {
+        os_info::Type::Arch => format!("sudo pacman -S {}", packages[0]),
+        os_info::Type::Ubuntu | os_info::Type::Debian | os_info::Type::Pop | os_info::Type::Mint => format!("sudo apt install {}", packages[1]),
+        os_info::Type::Fedora | os_info::Type::CentOS => format!("sudo dnf install {}", packages[2]),
+        os_info::Type::Macos => format!("brew install {}", packages[3]),
+        _ => t!("missing-tool-other", tool = tool),
+    };
+    outln!("   {}", install.paint(Role::Success));
+    outln!();
     std::process::exit(1);
-}
\ No newline at end of file
+}
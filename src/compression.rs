@@ -1,26 +1,103 @@
-use std::process::Command;
+use std::io::{Read, Write};
 use std::path::Path;
 use anyhow::{Result, anyhow};
 use clap::ValueEnum;
 use std::fs;
 use std::time::Instant;
-use dialoguer::Confirm;
-use colored::*;
-use crate::logger::{self, PacmanProgress};
-use crate::utils;
+use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+use crate::artifacts::{move_file, Scratch};
+use crate::cancel::Cancelled;
+use crate::changes::{self, Change};
+use crate::decision::Decision;
+use crate::exec::{ToolCommand, ToolStatus};
+use crate::format::Format;
+use crate::gif;
+use crate::ico;
+use crate::jpeg;
+use crate::office;
+use crate::optimized;
+use crate::options::{find_scratch_files, BwMode, ComicPages, CompressionOptions, MetadataMode, PdfImages, PdfMode, Stage, UnderTarget};
+use crate::pdf_interactive;
+use crate::pdf_pages;
+use crate::pdf_quick;
+use crate::perceptual::{self, Criterion};
+use crate::screenshot;
+use crate::search;
+use crate::svg;
+use crate::progress::{Attempt, NoProgress, Recorder};
+use crate::privacy::{self, PrivacyReport};
+use crate::scan;
+use crate::throttle::Throttle;
+use crate::webp;
+use crate::zip;
+use crate::t;
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum CompressionLevel {
     Low,    // Better Quality
     Medium, // Balanced
     High,   // Smallest size
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StageRecord {
+    pub number: u32,
+    pub name: String,
+}
+
+/// Everything known about a finished run.
+///
+/// Engines only fill `algorithm` and `time_ms`; `compress_file` adds sizes,
+/// target status and the stages/attempts/fallbacks recorded along the way.
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct CompResult {
     pub algorithm: String,
     pub time_ms: u128,
+    pub original_kb: u64,
+    pub final_kb: u64,
+    pub original_bytes: u64,
+    pub final_bytes: u64,
+    pub target_kb: Option<u64>,
+    /// Exact limit the output was held to (see [`CompressionOptions::target_limit`])
+    pub target_bytes: Option<u64>,
+    /// Decided in bytes, not KB
+    pub target_met: Option<bool>,
+    pub stages: Vec<StageRecord>,
+    pub attempts: Vec<Attempt>,
+    pub fallbacks: Vec<String>,
+    /// No output was written (`UnderTarget::Skip`, or `skip_optimized`)
+    pub skipped: bool,
+    /// Skipped for looking optimized already rather than for being under target
+    pub already_optimized: bool,
+    /// The output saved less than `min_savings`; the original was kept in its place
+    pub not_worth: bool,
+    /// Set for `--privacy` runs
+    pub privacy: Option<PrivacyReport>,
+    /// What the output lost besides bytes (color profile, transparency, ...)
+    pub changes: Vec<Change>,
+}
+
+/// A `strict` run that ended over its target; the output was removed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetMissed {
+    pub final_bytes: u64,
+    pub target_bytes: u64,
+}
+
+impl std::fmt::Display for TargetMissed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.target_bytes.is_multiple_of(1024) {
+            write!(f, "Target missed: {} KB, over the {} KB target; the output was removed.", self.final_bytes.div_ceil(1024), self.target_bytes / 1024)
+        } else {
+            write!(f, "Target missed: {} bytes, over the {} byte target; the output was removed.", self.final_bytes, self.target_bytes)
+        }
+    }
 }
 
+impl std::error::Error for TargetMissed {}
+
 /// RAII helper for temp files - automatically cleans up on drop
 #[allow(dead_code)]
 struct TempFile {
@@ -58,8 +135,14 @@ fn temp_path(base: &str, suffix: &str) -> String {
     format!("{}.{}.tmp.{}", base, std::process::id(), suffix)
 }
 
+/// Size in KB, rounded up: a file that fits in N KB this way is at most
+/// N * 1024 bytes, so a search comparing KB never lands over a target
 fn get_file_size_kb(path: &str) -> u64 {
-    fs::metadata(path).map(|m| m.len() / 1024).unwrap_or(0)
+    get_file_size_bytes(path).div_ceil(1024)
+}
+
+fn get_file_size_bytes(path: &str) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
 }
 
 /// Helper to create CompResult with timing from a start instant
@@ -67,73 +150,578 @@ fn result_with_time(algorithm: impl Into<String>, start: Instant) -> CompResult
     CompResult {
         algorithm: algorithm.into(),
         time_ms: start.elapsed().as_millis(),
+        ..Default::default()
+    }
+}
+
+/// Remove intermediates left behind by an engine that bailed out early.
+///
+/// Every scratch file is named `<output name>.<...>.tmp...` (see
+/// `CompressionOptions::scratch_path`), so they can be found without
+/// each engine tracking its own.
+fn remove_scratch_files(output: &str, opts: &CompressionOptions) {
+    let Some(name) = Path::new(output).file_name().map(|n| n.to_string_lossy().to_string()) else { return };
+    for path in find_scratch_files(&opts.scratch_dir(output), &name) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Run one tool invocation through the configured executor
+pub(crate) fn run_tool(opts: &CompressionOptions, cmd: &ToolCommand) -> Result<ToolStatus> {
+    if opts.limits.is_unlimited() {
+        return opts.executor.run(cmd, &opts.cancel);
+    }
+    let mut limited = cmd.clone();
+    opts.limits.apply(&mut limited);
+    opts.executor.run(&limited, &opts.cancel)
+}
+
+/// Resolve a decision through the configured policy.
+///
+/// Answers given without a prompt are noted in nerd output so the run can
+/// still be followed.
+fn decide(opts: &CompressionOptions, decision: Decision) -> Result<bool> {
+    // Choices leading into a skipped stage are declined without asking
+    if let Some(stage) = decision.stage().filter(|s| !opts.runs(*s)) {
+        if opts.nerd {
+            opts.reporter.message(&format!("   [{}]", t!("nerd-decision-skipped", decision = decision.summary(), stage = stage.name())));
+        }
+        return Ok(false);
+    }
+    let accepted = opts.decisions.resolve(&decision)?;
+    if opts.nerd && !opts.decisions.is_interactive() {
+        let verdict = if accepted { t!("nerd-decision-accepted") } else { t!("nerd-decision-declined") };
+        opts.reporter.message(&format!("   [{}: {}]", verdict, decision.summary()));
+    }
+    Ok(accepted)
+}
+
+/// Upper bound for a stage's search: `hi`, or lower when an earlier run's
+/// setting for the same search overshot the target
+fn search_ceiling(opts: &CompressionOptions, stage: u32, parameter: &str, lo: u64, hi: u64) -> u64 {
+    match opts.prior {
+        Some(ref prior) if prior.stage == stage && prior.parameter == parameter => prior.value.clamp(lo, hi),
+        _ => hi,
+    }
+}
+
+/// oxipng optimization level (and Zopfli) for the run's effort
+fn oxipng_level_args(opts: &CompressionOptions) -> Vec<String> {
+    let mut args = vec!["-o".to_string(), opts.effort.oxipng_level().to_string()];
+    if opts.effort.zopfli() {
+        args.push("--zopfli".to_string());
+    }
+    args
+}
+
+/// ImageMagick arguments that drop color: 8-bit gray, or 1-bit with `--bw`
+fn desaturate_args(opts: &CompressionOptions) -> &'static [&'static str] {
+    match opts.bw {
+        None => &["-colorspace", "Gray", "-depth", "8"],
+        Some(BwMode::Dither) => &["-colorspace", "Gray", "-dither", "FloydSteinberg", "-monochrome"],
+        Some(BwMode::Threshold) => &["-colorspace", "Gray", "-threshold", "50%", "-type", "Bilevel"],
+    }
+}
+
+/// What the color-dropping stage is called in messages and algorithm names
+fn desaturate_label(opts: &CompressionOptions) -> &'static str {
+    if opts.bw.is_some() { "Black & White" } else { "Grayscale" }
+}
+
+/// Strip flags for oxipng matching the requested metadata handling
+fn oxipng_strip_args(opts: &CompressionOptions) -> &'static [&'static str] {
+    match opts.metadata {
+        MetadataMode::Strip => &["--strip", "safe"],
+        MetadataMode::Keep => &[],
+    }
+}
+
+pub fn compress_file(input: &str, output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    let format = Format::from_path(input).ok_or_else(|| {
+        let ext = Path::new(input).extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+        anyhow!(t!("error-unsupported-type", ext = ext))
+    })?;
+
+    let recorder = Arc::new(Recorder::new(opts.progress.clone()));
+    let mut engine_opts = opts.clone();
+    engine_opts.progress = recorder.clone();
+    let original_bytes = get_file_size_bytes(input);
+    let original_kb = original_bytes.div_ceil(1024);
+    let output_existed = Path::new(output).exists();
+
+    let fit = fit_geometry(input, format, opts).filter(|_| matches!(Format::from_path(output).unwrap_or(format), Format::Jpg | Format::Png));
+    // --pages, the image filters, --ocr and --scan-mode make a different document, so neither can stand in for the original
+    let selecting = (opts.pdf_pages.is_some() || opts.pdf_images != PdfImages::Keep || opts.pdf_ocr || opts.pdf_scan_mode) && format == Format::Pdf;
+    // Still over the size when it's over the dimensions
+    let under_target = fit.is_none() && !selecting && opts.target_limit().is_some_and(|t| t >= original_bytes);
+    if let Some(throttle) = opts.io_throttle.as_ref().filter(|_| !under_target) {
+        return compress_staged(input, output, throttle, opts);
+    }
+
+    // Format changes on the way (`--convert-to`, and the input-only formats): any
+    // image to PNG, JPG, WebP, AVIF or PDF
+    let output_format = Format::from_path(output).unwrap_or(format);
+    let converting = output_format != format;
+    if let Some(effort) = opts.png_effort.filter(|_| output_format == Format::Png) {
+        engine_opts.effort = effort;
+    }
+    let convertible = format.is_image() && matches!(output_format, Format::Png | Format::Jpg | Format::Webp | Format::Avif | Format::Pdf);
+    if converting && !convertible {
+        return Err(anyhow!(t!("error-cannot-convert", from = format.extension(), to = output_format.extension())));
+    }
+    let source = source_label(format);
+
+    // The engines start from the downscaled copy
+    let fitted = opts.scratch_path(output, &format!("fit.tmp.{}", format.extension()));
+    let mut scratch = Scratch::default();
+    let downscaled = match fit {
+        Some(ref geometry) => {
+            scratch.track(&fitted);
+            Some(fit_to(input, &fitted, geometry, format, &engine_opts)?)
+        },
+        None => None,
+    };
+    let input = if downscaled.is_some() { fitted.as_str() } else { input };
+
+    // --min-ssim and --max-distance: the lowest quality that still looks right is
+    // the floor under a target, and the quality itself without one
+    let comparable = output_format != Format::Pdf || format == Format::Pdf;
+    let criteria = opts.min_ssim.map(Criterion::MinSsim).into_iter().chain(opts.max_distance.map(Criterion::MaxDistance));
+    let mut look_floor = None;
+    for criterion in criteria.filter(|_| comparable && !under_target && opts.quality.is_none()) {
+        if let Some(floor) = perceptual::quality_floor(input, output, output_format, criterion, &engine_opts)? {
+            if opts.nerd { opts.reporter.nerd_result("Quality floor", &format!("{} ({})", floor, criterion), true); }
+            look_floor = look_floor.max(Some(floor));
+        }
+    }
+    if let Some(floor) = look_floor {
+        match opts.target_kb {
+            None => engine_opts.quality = Some(floor),
+            Some(_) if output_format == Format::Pdf => engine_opts.min_dpi = Some(quality_dpi(floor)),
+            Some(_) => engine_opts.min_quality = Some(opts.quality_floor(floor)),
+        }
+    }
+
+    let looks_optimized = || opts.skip_optimized.is_some_and(|min| optimized::expected_savings(Path::new(input), format).is_some_and(|s| s < min));
+    let run = match (opts.target_kb, output_format) {
+        (Some(target), _) if under_target && !converting => handle_under_target(input, output, target, original_kb, &engine_opts),
+        (None, _) if !converting && fit.is_none() && !selecting && looks_optimized() => {
+            opts.progress.on_fallback("Skipped (already optimized)");
+            Ok(CompResult { skipped: true, already_optimized: true, ..result_with_time("Skipped (already optimized)", Instant::now()) })
+        },
+        (_, Format::Heic) => compress_heic(input, output, &engine_opts),
+        (_, Format::Jpg) if format == Format::Heic => compress_heic_to_jpg(input, output, &engine_opts),
+        (_, Format::Jpg) if format == Format::Raw => compress_raw(input, output, &engine_opts),
+        (_, Format::Pdf) if converting => compress_to_pdf(input, output, &source, &engine_opts),
+        (_, Format::Avif) => compress_avif(input, output, &engine_opts),
+        // cwebp reads PNG, JPEG and WebP, gif2webp GIF; the rest are decoded first
+        (_, Format::Webp) if !matches!(format, Format::Jpg | Format::Png | Format::Gif | Format::Webp) => compress_decoded(input, output, output_format, &source, &engine_opts),
+        (_, Format::Png | Format::Jpg) if converting => compress_decoded(input, output, output_format, &source, &engine_opts),
+        (_, Format::Bmp) => Err(anyhow!(t!("error-input-only-image", format = "BMP"))),
+        (_, Format::Psd) => Err(anyhow!(t!("error-input-only-image", format = "PSD"))),
+        (_, Format::Raw) => Err(anyhow!(t!("error-input-only", format = "Camera RAW", ext = "jpg"))),
+        (_, Format::Tiff) => Err(anyhow!(t!("error-input-only", format = "TIFF", ext = "pdf"))),
+        (_, Format::Webp) => compress_webp(input, output, &engine_opts),
+        (_, Format::Jpg) => compress_jpg(input, output, &engine_opts),
+        (_, Format::Png) => compress_png(input, output, &engine_opts),
+        (_, Format::Pdf) => compress_pdf(input, output, &engine_opts),
+        (_, Format::Gif) => compress_gif(input, output, &engine_opts),
+        (_, Format::Svg) => compress_svg(input, output, &engine_opts),
+        (_, Format::Ico) => compress_ico(input, output, &engine_opts),
+        (_, Format::Video) => compress_video(input, output, &engine_opts),
+        (_, Format::Office | Format::Zip | Format::Comic) => compress_zip(input, output, output_format, &engine_opts),
+    };
+    let run = match (run, downscaled) {
+        (Ok(result), Some(label)) => Ok(CompResult { algorithm: format!("{} → {}", label, result.algorithm), ..result }),
+        (run, _) => run,
+    };
+    let run = match run {
+        Ok(result) if opts.privacy && !result.skipped => scrub_metadata(input, output, format, output_format, &engine_opts)
+            .map(|report| CompResult { privacy: Some(report), ..result }),
+        other => other,
+    };
+    let mut result = match run {
+        Ok(result) => result,
+        Err(e) => {
+            remove_scratch_files(output, opts);
+            // A cancelled run may leave a half-written output behind, and a
+            // privacy run must not leave an unverified one
+            if (e.downcast_ref::<Cancelled>().is_some() || opts.privacy) && !output_existed {
+                let _ = fs::remove_file(output);
+            }
+            return Err(e);
+        }
+    };
+
+    // Never ship a bigger file, nor (with --min-savings and no target) one that
+    // saved too little: the original goes out in its place. Not across a format
+    // change, a downscale or a page selection, and not when the output had to be scrubbed
+    let replaceable = !result.skipped && !converting && fit.is_none() && !selecting && result.privacy.is_none();
+    let output_bytes = get_file_size_bytes(output);
+    if replaceable && output_bytes > original_bytes {
+        engine_opts.progress.on_fallback("Original kept (no reduction possible)");
+        fs::copy(input, output)?;
+        result.algorithm = "No reduction possible (original kept)".to_string();
+    } else if let Some(min) = opts.min_savings.filter(|_| replaceable && opts.target_kb.is_none() && original_bytes > 0) {
+        let saved = (original_bytes - output_bytes) as f64 / original_bytes as f64 * 100.0;
+        if saved < min {
+            engine_opts.progress.on_fallback("Original kept (not worth recompressing)");
+            fs::copy(input, output)?;
+            result.algorithm = format!("Not worth recompressing (saved {:.1}%, original kept)", saved);
+            result.not_worth = true;
+        }
+    }
+
+    let log = recorder.take();
+    result.original_kb = original_kb;
+    result.original_bytes = original_bytes;
+    result.final_bytes = if result.skipped { original_bytes } else { get_file_size_bytes(output) };
+    result.final_kb = result.final_bytes.div_ceil(1024);
+    result.target_kb = opts.target_kb;
+    result.target_bytes = opts.target_limit();
+    result.target_met = result.target_bytes.map(|t| result.final_bytes <= t);
+    if let (true, Some(false), Some(target_bytes)) = (opts.strict, result.target_met, result.target_bytes) {
+        let _ = fs::remove_file(output);
+        return Err(TargetMissed { final_bytes: result.final_bytes, target_bytes }.into());
+    }
+    result.stages = log.stages;
+    result.attempts = log.attempts;
+    result.fallbacks = log.fallbacks;
+    if !result.skipped {
+        result.changes = changes::detect(Path::new(input), Path::new(output), format);
+    }
+    opts.progress.on_done(&result);
+    Ok(result)
+}
+
+/// ImageMagick geometry that shrinks `input` (a JPG or PNG) into the
+/// `max_width` x `max_height` box, when it doesn't fit already
+fn fit_geometry(input: &str, format: Format, opts: &CompressionOptions) -> Option<String> {
+    if (opts.max_width.is_none() && opts.max_height.is_none()) || !matches!(format, Format::Jpg | Format::Png) {
+        return None;
+    }
+    let (width, height) = changes::dimensions(Path::new(input), format)?;
+    if width <= opts.max_width.unwrap_or(u32::MAX) && height <= opts.max_height.unwrap_or(u32::MAX) {
+        return None;
+    }
+    let side = |max: Option<u32>| max.map_or(String::new(), |m| m.to_string());
+    Some(format!("{}x{}>", side(opts.max_width), side(opts.max_height)))
+}
+
+/// Downscale `input` to `dest`; ImageMagick keeps a JPEG's own quality
+fn fit_to(input: &str, dest: &str, geometry: &str, format: Format, opts: &CompressionOptions) -> Result<String> {
+    opts.cancel.check()?;
+    let mut cmd = ToolCommand::new("magick");
+    cmd.arg(input).arg("-resize").arg(geometry).arg(dest);
+    if opts.nerd { opts.reporter.nerd_cmd(&cmd.shell_line()); }
+    if !shared_or_make(opts, &format!("fit {}", geometry), input, dest, || Ok(run_tool(opts, cmd.quiet_stderr(!opts.nerd))?.success()))? {
+        return Err(anyhow!(t!("error-resize", file = input)));
+    }
+    Ok(match changes::dimensions(Path::new(dest), format) {
+        Some((width, height)) => format!("Downscaled to {}x{}", width, height),
+        None => format!("Downscaled to {}", geometry.trim_end_matches('>')),
+    })
+}
+
+/// Strip identifying metadata from `output`, then re-scan it; any field left is an error
+fn scrub_metadata(input: &str, output: &str, input_format: Format, format: Format, opts: &CompressionOptions) -> Result<PrivacyReport> {
+    opts.cancel.check()?;
+    let found = privacy::scan(Path::new(input), input_format)?;
+    // HEIC is re-encoded from an already stripped decode, AVIF without the source's metadata
+    if matches!(format, Format::Heic | Format::Avif) {
+        return privacy_verdict(output, format, found);
+    }
+    // WebP metadata is whole chunks, dropped here without a tool
+    if format == Format::Webp {
+        if let Some(stripped) = webp::strip_metadata(&fs::read(output)?) {
+            fs::write(output, stripped)?;
+        }
+        return privacy_verdict(output, format, found);
+    }
+    // Office properties are cleared in place; images and archive members are scrubbed one by one
+    if matches!(format, Format::Office | Format::Zip | Format::Comic) {
+        let mut entries = zip::read(Path::new(output))?;
+        if format == Format::Office {
+            office::clear_people(&mut entries);
+        }
+        let members = members_of(format, &entries);
+        let dir = staging_dir(opts)?;
+        for i in members {
+            let path = staged_member(&dir, i, &entries[i])?;
+            let member_format = Format::from_path(&path).unwrap_or(Format::Png);
+            scrub_metadata(&path, &path, member_format, member_format, opts)?;
+            entries[i].data = fs::read(&path)?;
+        }
+        write_zip(output, format, &entries, opts)?;
+        return privacy_verdict(output, format, found);
+    }
+    // Icon frames are PNGs (scrubbed like any other) or BMPs, which carry nothing
+    if format == Format::Ico {
+        let mut frames = ico::frames(&fs::read(output)?).ok_or_else(|| anyhow!(t!("error-icon", file = output)))?;
+        let dir = staging_dir(opts)?;
+        for (i, frame) in frames.iter_mut().enumerate().filter(|(_, f)| f.is_png()) {
+            let path = dir.path().join(format!("{}.png", i)).to_string_lossy().to_string();
+            fs::write(&path, &frame.data)?;
+            scrub_metadata(&path, &path, Format::Png, Format::Png, opts)?;
+            frame.data = fs::read(&path)?;
+        }
+        fs::write(output, ico::to_bytes(&frames))?;
+        return privacy_verdict(output, format, found);
+    }
+    // SVG metadata is markup, dropped by the minifier's own pass
+    if format == Format::Svg {
+        svg::write(Path::new(output), &svg::minify(&svg::read(Path::new(output))?, None, false))?;
+        return privacy_verdict(output, format, found);
+    }
+    // ffmpeg picks the container from the extension, so video keeps its own
+    let extension = match format {
+        Format::Video => Path::new(output).extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_else(|| "mp4".into()),
+        _ => format.extension().to_string(),
+    };
+    let scrubbed = opts.scratch_path(output, &format!("privacy.tmp.{}", extension));
+    let mut cmd = match format {
+        Format::Jpg => {
+            let mut cmd = ToolCommand::new("jpegoptim");
+            cmd.arg("--strip-all").arg("--stdout").arg(output).stdout_to(&scrubbed);
+            cmd
+        },
+        Format::Png => {
+            let mut cmd = ToolCommand::new("oxipng");
+            cmd.arg("-o").arg("0").arg("--strip").arg("all").arg("--quiet").arg("--out").arg(&scrubbed).arg(output);
+            cmd
+        },
+        Format::Gif => {
+            let mut cmd = ToolCommand::new("gifsicle");
+            cmd.arg("--no-comments").arg("--no-extensions").arg(output).arg("-o").arg(&scrubbed);
+            cmd
+        },
+        Format::Webp | Format::Heic | Format::Avif | Format::Svg | Format::Bmp | Format::Raw | Format::Tiff | Format::Psd | Format::Ico | Format::Office | Format::Zip | Format::Comic => unreachable!("scrubbed above"),
+        Format::Video => {
+            let mut cmd = ToolCommand::new("ffmpeg");
+            cmd.arg("-y").arg("-v").arg("error").arg("-i").arg(output).arg("-map_metadata").arg("-1").arg("-c").arg("copy").arg(&scrubbed);
+            cmd
+        },
+        Format::Pdf => {
+            // The trailing pdfmark overrides the Info dict read from the file; JPEGs
+            // are re-encoded since passing them through would keep their EXIF
+            let mut cmd = ToolCommand::new("gs");
+            cmd.arg("-sDEVICE=pdfwrite").arg("-dPassThroughJPEGImages=false")
+                .arg("-dNOPAUSE").arg("-dQUIET").arg("-dBATCH")
+                .arg(format!("-sOutputFile={}", scrubbed)).arg(output)
+                .arg("-c").arg(BLANK_DOCINFO);
+            cmd
+        },
+    };
+    let status = run_tool(opts, cmd.quiet_stderr(!opts.nerd))?;
+    if !status.success() {
+        let _ = fs::remove_file(&scrubbed);
+        return Err(anyhow!(t!("error-scrub", tool = cmd.program)));
+    }
+    fs::rename(&scrubbed, output)?;
+    privacy_verdict(output, format, found)
+}
+
+fn privacy_verdict(output: &str, format: Format, found: Vec<String>) -> Result<PrivacyReport> {
+    let remaining = privacy::scan(Path::new(output), format)?;
+    if !remaining.is_empty() {
+        return Err(anyhow!(t!("error-privacy", count = remaining.len(), fields = remaining.join(", "))));
     }
+    Ok(PrivacyReport { found, remaining })
+}
+
+/// Private temp dir for staged runs, inside `opts.temp_dir` when set
+fn staging_dir(opts: &CompressionOptions) -> Result<tempfile::TempDir> {
+    let mut staging = tempfile::Builder::new();
+    staging.prefix("crnch-");
+    Ok(match opts.temp_dir {
+        Some(ref d) => staging.tempdir_in(d)?,
+        None => staging.tempdir()?,
+    })
 }
 
-pub fn compress_file(input: &str, output: &str, size_str: Option<String>, level: Option<CompressionLevel>, nerd: bool, auto_yes: bool) -> Result<CompResult> {
-    let path = Path::new(input);
-    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
-    let target_kb = if let Some(s) = size_str { utils::parse_size(&s) } else { None };
+/// Run the engines on a local copy so the only I/O against the input's and
+/// output's filesystem is one throttled read and one throttled write
+fn compress_staged(input: &str, output: &str, throttle: &Throttle, opts: &CompressionOptions) -> Result<CompResult> {
+    let dir = staging_dir(opts)?;
+    let file_name = |path: &str| Path::new(path).file_name().map(|n| n.to_os_string()).unwrap_or_else(|| "crnch".into());
+    fs::create_dir(dir.path().join("in"))?;
+    fs::create_dir(dir.path().join("out"))?;
+    let local_in = dir.path().join("in").join(file_name(input));
+    let local_out = dir.path().join("out").join(file_name(output));
 
-    match ext.as_str() {
-        "jpg" | "jpeg" => compress_jpg(input, output, target_kb, level, nerd, auto_yes),
-        "png" => compress_png(input, output, target_kb, level, nerd, auto_yes),
-        "pdf" => compress_pdf(input, output, target_kb, level, nerd, auto_yes),
-        _ => Err(anyhow!("Unsupported file type: .{}", ext)),
+    throttle.copy(Path::new(input), &local_in, &opts.cancel)?;
+    let mut local_opts = opts.clone();
+    local_opts.io_throttle = None;
+    local_opts.temp_dir = Some(dir.path().to_path_buf());
+    let result = compress_file(&local_in.to_string_lossy(), &local_out.to_string_lossy(), &local_opts)?;
+
+    let output_existed = Path::new(output).exists();
+    if let Err(e) = throttle.copy(&local_out, Path::new(output), &opts.cancel) {
+        if !output_existed {
+            let _ = fs::remove_file(output);
+        }
+        return Err(e);
     }
+    Ok(result)
+}
+
+/// Compress `format` data read from `reader` and write the result to `writer`.
+///
+/// The external tools only work on files, so the data is staged in a private
+/// temp dir (inside `opts.temp_dir` when set) that is removed on return.
+pub fn compress_stream<R: Read, W: Write>(reader: &mut R, writer: &mut W, format: Format, opts: &CompressionOptions) -> Result<CompResult> {
+    let dir = staging_dir(opts)?;
+    let input = dir.path().join(format!("input.{}", format.extension()));
+    let output = dir.path().join(format!("output.{}", format.extension()));
+    std::io::copy(reader, &mut fs::File::create(&input)?)?;
+
+    let result = compress_file(&input.to_string_lossy(), &output.to_string_lossy(), opts)?;
+    std::io::copy(&mut fs::File::open(&output)?, writer)?;
+    writer.flush()?;
+    Ok(result)
+}
+
+/// In-memory convenience wrapper around [`compress_stream`]
+pub fn compress_bytes(data: &[u8], format: Format, opts: &CompressionOptions) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    compress_stream(&mut &data[..], &mut out, format, opts)?;
+    Ok(out)
 }
 
 // ---------------------- ENGINES ----------------------
 
-// JPG: Smart Extent -> Fallbacks (My Version - Robust)
-fn compress_jpg(input: &str, output: &str, target_kb: Option<u64>, level: Option<CompressionLevel>, nerd: bool, auto_yes: bool) -> Result<CompResult> {
+// Target at or above the original: nothing to compress, same for every format
+fn handle_under_target(input: &str, output: &str, target: u64, original_size: u64, opts: &CompressionOptions) -> Result<CompResult> {
+    let sink = opts.progress.as_ref();
+    let rep = opts.reporter.as_ref();
     let start = Instant::now();
-    let progress = PacmanProgress::new(1, "Optimizing JPG...");
-    let tmp_optim = format!("{}.jpegoptim.tmp.jpg", output);
-    let original_size = get_file_size_kb(input);
-    if let Some(target) = target_kb {
-        if target >= original_size {
-            println!("Requested size ({}) KB is larger than or equal to original file size ({} KB). No compression performed.", target, original_size);
-            let should_keep = if auto_yes {
-                if nerd { println!("   [Auto-yes enabled, keeping original]"); }
-                true
-            } else {
-                Confirm::new().with_prompt("Keep original file?").default(true).interact()?
-            };
-            if should_keep {
-                fs::copy(input, output)?;
-                return Ok(result_with_time("No compression (requested size >= original)", start));
-            } else {
-                return Err(anyhow!("Compression cancelled by user."));
+    let mode = match opts.under_target {
+        UnderTarget::Ask => {
+            rep.message(&t!("target-above-original", target = target, size = original_size));
+            if !decide(opts, Decision::KeepOriginal)? {
+                return Err(anyhow!(t!("error-cancelled-by-user")));
             }
-        }
+            UnderTarget::Copy
+        },
+        mode => mode,
+    };
+    match mode {
+        UnderTarget::Skip => {
+            sink.on_fallback("Skipped (target >= original size)");
+            let mut result = result_with_time("Skipped (requested size >= original)", start);
+            result.skipped = true;
+            Ok(result)
+        },
+        UnderTarget::Symlink if opts.privacy => {
+            Err(anyhow!(t!("error-privacy-symlink")))
+        },
+        UnderTarget::Symlink => {
+            link_to_original(input, output)?;
+            sink.on_fallback("Output linked to original (target >= original size)");
+            Ok(result_with_time("Symlink to original (requested size >= original)", start))
+        },
+        UnderTarget::Ask | UnderTarget::Copy => {
+            sink.on_fallback("Original kept (target >= original size)");
+            match opts.io_throttle {
+                Some(ref throttle) => { throttle.copy(Path::new(input), Path::new(output), &opts.cancel)?; },
+                None => { fs::copy(input, output)?; },
+            }
+            Ok(result_with_time("No compression (requested size >= original)", start))
+        },
     }
+}
+
+fn link_to_original(input: &str, output: &str) -> Result<()> {
+    let original = fs::canonicalize(input)?;
+    if fs::symlink_metadata(output).is_ok() {
+        fs::remove_file(output)?;
+    }
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&original, output)?;
+    #[cfg(windows)]
+    std::os::windows::fs::symlink_file(&original, output)?;
+    Ok(())
+}
+
+/// Whether ImageMagick's extent search, which has no floor of its own, went
+/// under `min_quality` for `path`
+fn under_quality_floor(path: &str, opts: &CompressionOptions) -> bool {
+    opts.min_quality.is_some_and(|min| jpeg::estimate_quality(Path::new(path)).is_some_and(|q| u64::from(q) < min))
+}
+
+// JPG: Smart Extent -> Fallbacks (My Version - Robust)
+fn compress_jpg(input: &str, output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    let (target_kb, nerd) = (opts.target_kb, opts.nerd);
+    let sink = opts.progress.as_ref();
+    let rep = opts.reporter.as_ref();
+    let start = Instant::now();
+    let progress = rep.progress(1, &t!("progress-optimizing-jpg"));
+    let tmp_optim = opts.scratch_path(output, "jpegoptim.tmp.jpg");
+    let jpegoptim_strip = if opts.metadata == MetadataMode::Strip { "--strip-all" } else { "--strip-none" };
+    // Re-encoding above the source's own quality only adds bytes
+    let source_quality = jpeg::estimate_quality(Path::new(input));
 
     // If no size flag, use standard preset
     if target_kb.is_none() {
+        opts.cancel.check()?;
+        sink.on_stage(1, "JPEG Lossless Optimization");
         if nerd {
-            logger::nerd_stage(1, "JPEG Lossless Optimization");
-            logger::nerd_result("Tool", "jpegoptim", false);
-            logger::nerd_result("Complexity", "O(n) I/O bound", false);
-            logger::nerd_result("Strategy", "Stripping metadata and optimizing", false);
-            logger::nerd_cmd(&format!("jpegoptim --strip-all --stdout {} > tmp", input));
+            rep.nerd_stage(1, "JPEG Lossless Optimization");
+            rep.nerd_result("Tool", "jpegoptim", false);
+            rep.nerd_result("Complexity", "O(n) I/O bound", false);
+            rep.nerd_result("Strategy", "Stripping metadata and optimizing", false);
+            rep.nerd_cmd(&format!("jpegoptim --strip-all --stdout {} > tmp", input));
         }
         // Run jpegoptim for lossless optimization
-        let status = Command::new("jpegoptim")
-            .arg("--strip-all")
+        let optimized = shared_or_make(opts, "jpegoptim", input, &tmp_optim, || Ok(run_tool(opts, ToolCommand::new("jpegoptim")
+            .arg(jpegoptim_strip)
             .arg("--stdout")
             .arg(input)
-            .stdout(fs::File::create(&tmp_optim)?)
-            .stderr(if nerd { std::process::Stdio::inherit() } else { std::process::Stdio::null() })
-            .status()?;
-        if !status.success() {
-            if nerd { logger::nerd_result("Status", "jpegoptim failed, skipping to magick stage", true); }
+            .stdout_to(&tmp_optim)
+            .quiet_stderr(!nerd))?.success()))?;
+        if !optimized {
+            if nerd { rep.nerd_result("Status", "jpegoptim failed, skipping to magick stage", true); }
+            sink.on_fallback("jpegoptim failed, using input for magick");
             // Fallback: use input directly for magick
             fs::copy(input, &tmp_optim)?;
         }
         let optim_size = get_file_size_kb(&tmp_optim);
         if nerd {
-            logger::nerd_result("Output Size", &format!("{} KB", optim_size), true);
+            rep.nerd_result("Output Size", &format!("{} KB", optim_size), true);
+        }
+        if !opts.runs(Stage::Lossy) {
+            sink.on_fallback("Lossy stage skipped, lossless result kept");
+            move_file(&tmp_optim, output)?;
+            progress.finish();
+            return Ok(result_with_time("jpegoptim (Lossless)", start));
+        }
+        // A fixed quality is one encode, never above the source's own; config
+        // `[jpeg] quality` applies when neither --quality nor --level is given
+        if let Some(quality) = opts.quality.or(opts.jpeg_quality.filter(|_| opts.level.is_none())) {
+            let quality = quality.min(source_quality.map_or(100, u64::from));
+            opts.cancel.check()?;
+            sink.on_stage(2, "JPEG Lossy Compression");
+            if nerd {
+                rep.nerd_stage(2, "JPEG Lossy Compression");
+                rep.nerd_result("Tool", "ImageMagick", false);
+                rep.nerd_result("Strategy", "Fixed quality", false);
+                rep.nerd_cmd(&format!("magick {} -sampling-factor 4:4:4 -interlace Plane -quality {} {}", &tmp_optim, quality, output));
+            }
+            let mut cmd = ToolCommand::new("magick");
+            cmd.arg(&tmp_optim)
+                .arg("-sampling-factor").arg("4:4:4")
+                .arg("-interlace").arg("Plane")
+                .arg("-quality").arg(quality.to_string());
+            if opts.metadata == MetadataMode::Strip { cmd.arg("-strip"); }
+            cmd.arg(output);
+            let status = run_tool(opts, &cmd)?;
+            fs::remove_file(&tmp_optim).ok();
+            if !status.success() { return Err(anyhow!(t!("error-magick"))); }
+            progress.finish();
+            if nerd {
+                rep.nerd_output_summary(input, output, get_file_size_kb(input), get_file_size_kb(output), "jpegoptim + magick (Fixed Quality)", start.elapsed().as_secs_f64());
+            }
+            return Ok(result_with_time(format!("jpegoptim + magick (quality {})", quality), start));
         }
         // Adaptive target compression: try 60%, then 65%, ..., up to 95% of original size
         let original_size = get_file_size_kb(input);
@@ -141,39 +729,58 @@ fn compress_jpg(input: &str, output: &str, target_kb: Option<u64>, level: Option
         let mut final_size = original_size;
         let mut final_target = original_size;
         let mut tried_targets = Vec::new();
-        for percent in [60, 65, 70, 75, 80, 85, 90, 95] {
+        opts.cancel.check()?;
+        sink.on_stage(2, "JPEG Lossy Compression");
+        for (attempt, percent) in [60, 65, 70, 75, 80, 85, 90, 95].into_iter().enumerate() {
+            opts.cancel.check()?;
             let target_kb = original_size * percent / 100;
-            let try_out = if percent == 60 { output.to_string() } else { format!("{}.tgt{}p.jpg", output, percent) };
+            let try_out = if percent == 60 { output.to_string() } else { opts.scratch_path(output, &format!("tgt{}p.tmp.jpg", percent)) };
             if nerd {
-                logger::nerd_stage(2, "JPEG Lossy Compression");
-                logger::nerd_result("Tool", "ImageMagick", false);
-                logger::nerd_result("Complexity", "O(n) I/O bound", false);
-                logger::nerd_result("Strategy", "Targeted lossy compression", false);
-                logger::nerd_result("Target", &format!("{} KB ({}% of original)", target_kb, percent), false);
-                logger::nerd_cmd(&format!("magick ... -define jpeg:extent={}KB -sampling-factor 4:4:4 -interlace Plane -strip {} {}", target_kb, &tmp_optim, &try_out));
-            }
-            let mut cmd = Command::new("magick");
+                rep.nerd_stage(2, "JPEG Lossy Compression");
+                rep.nerd_result("Tool", "ImageMagick", false);
+                rep.nerd_result("Complexity", "O(n) I/O bound", false);
+                rep.nerd_result("Strategy", "Targeted lossy compression", false);
+                rep.nerd_result("Target", &format!("{} KB ({}% of original)", target_kb, percent), false);
+                if let Some(q) = source_quality {
+                    rep.nerd_result("Quality Ceiling", &format!("{} (estimated source quality)", q), false);
+                }
+                rep.nerd_cmd(&format!("magick ... -define jpeg:extent={}KB -sampling-factor 4:4:4 -interlace Plane -strip {} {}", target_kb, &tmp_optim, &try_out));
+            }
+            let t0 = Instant::now();
+            let mut cmd = ToolCommand::new("magick");
             cmd.arg(&tmp_optim)
                 .arg("-define").arg(format!("jpeg:extent={}KB", target_kb))
                 .arg("-sampling-factor").arg("4:4:4")
-                .arg("-interlace").arg("Plane")
-                .arg("-strip")
-                .arg(&try_out);
-            let status = cmd.status()?;
+                .arg("-interlace").arg("Plane");
+            // The extent search starts from -quality and only goes down
+            if let Some(q) = source_quality { cmd.arg("-quality").arg(q.to_string()); }
+            if opts.metadata == MetadataMode::Strip { cmd.arg("-strip"); }
+            cmd.arg(&try_out);
+            let status = run_tool(opts, &cmd)?;
             if !status.success() { continue; }
             let out_size = get_file_size_kb(&try_out);
             tried_targets.push(try_out.clone());
+            sink.on_attempt(&Attempt {
+                stage: 2,
+                index: attempt as u32 + 1,
+                max: 8,
+                parameter: "extent",
+                value: target_kb,
+                size_kb: out_size,
+                target_kb,
+                time_ms: t0.elapsed().as_millis(),
+            });
             if nerd {
                 let hit_miss = if out_size <= target_kb {"Hit!"} else {"Miss"};
-                logger::nerd_result("Result", &format!("{} KB ({})", out_size, hit_miss), true);
+                rep.nerd_result("Result", &format!("{} KB ({})", out_size, hit_miss), true);
             }
-            if out_size <= target_kb {
+            if out_size <= target_kb && !under_quality_floor(&try_out, opts) {
                 final_size = out_size;
                 final_target = target_kb;
                 success = true;
                 // Move/copy to output if not already
                 if try_out != output {
-                    fs::copy(&try_out, output)?;
+                    move_file(&try_out, output)?;
                 }
                 break;
             }
@@ -186,87 +793,112 @@ fn compress_jpg(input: &str, output: &str, target_kb: Option<u64>, level: Option
         progress.finish();
         let total_time = start.elapsed().as_secs_f64();
         if nerd {
-            logger::nerd_output_summary(input, output, original_size, final_size, "jpegoptim + magick (Standard Preset)", total_time);
+            rep.nerd_output_summary(input, output, original_size, final_size, "jpegoptim + magick (Standard Preset)", total_time);
         }
         if success {
             Ok(result_with_time(format!("jpegoptim + magick (Standard Preset, target {} KB)", final_target), start))
         } else {
             // Inform user compression not possible
-            println!("This image cannot be compressed to the desired size (60-95% of original). Keeping original.");
+            rep.message(&t!("jpg-cannot-reach"));
+            sink.on_fallback("Original kept (no preset reduction)");
             fs::copy(input, output)?;
             Ok(result_with_time("jpegoptim + magick (No reduction, original kept)", start))
         }
     } else {
         // Original lossy/target logic for JPG compression
+        opts.cancel.check()?;
+        sink.on_stage(1, "JPEG Lossless Optimization");
         if nerd {
-            logger::nerd_stage(1, "JPEG Lossless Optimization");
-            logger::nerd_result("Tool", "jpegoptim", false);
-                logger::nerd_result("Complexity", "O(n) I/O bound", false);
-                logger::nerd_result("Strategy", "Stripping metadata and optimizing", false);
-            logger::nerd_cmd(&format!("jpegoptim --strip-all --stdout {} > tmp", input));
+            rep.nerd_stage(1, "JPEG Lossless Optimization");
+            rep.nerd_result("Tool", "jpegoptim", false);
+                rep.nerd_result("Complexity", "O(n) I/O bound", false);
+                rep.nerd_result("Strategy", "Stripping metadata and optimizing", false);
+            rep.nerd_cmd(&format!("jpegoptim --strip-all --stdout {} > tmp", input));
         }
         // Run jpegoptim for lossless optimization
-        let status = Command::new("jpegoptim")
-            .arg("--strip-all")
+        let optimized = shared_or_make(opts, "jpegoptim", input, &tmp_optim, || Ok(run_tool(opts, ToolCommand::new("jpegoptim")
+            .arg(jpegoptim_strip)
             .arg("--stdout")
             .arg(input)
-            .stdout(fs::File::create(&tmp_optim)?)
-            .stderr(if nerd { std::process::Stdio::inherit() } else { std::process::Stdio::null() })
-            .status()?;
-        if !status.success() {
+            .stdout_to(&tmp_optim)
+            .quiet_stderr(!nerd))?.success()))?;
+        if !optimized {
             // If jpegoptim fails, fallback to magick directly
-            if nerd { logger::nerd_result("jpegoptim failed, skipping to lossy stage", "", true); }
+            if nerd { rep.nerd_result("jpegoptim failed, skipping to lossy stage", "", true); }
         }
         let optim_size = get_file_size_kb(&tmp_optim);
         if nerd {
-            logger::nerd_result("Output Size after jpegoptim", &format!("{} KB", optim_size), false);
+            rep.nerd_result("Output Size after jpegoptim", &format!("{} KB", optim_size), false);
         }
         // If target met, use jpegoptim result
         if let Some(target) = target_kb {
             if optim_size <= target {
-                fs::copy(&tmp_optim, output)?;
-                fs::remove_file(&tmp_optim).ok();
+                move_file(&tmp_optim, output)?;
                 progress.finish();
                 if nerd {
                     let original_size = get_file_size_kb(input);
                     let final_size = get_file_size_kb(output);
                     let total_time = start.elapsed().as_secs_f64();
-                    logger::nerd_output_summary(input, output, original_size, final_size, "jpegoptim (Lossless)", total_time);
+                    rep.nerd_output_summary(input, output, original_size, final_size, "jpegoptim (Lossless)", total_time);
                 }
                 return Ok(result_with_time("jpegoptim (Lossless)", start));
             }
+            if !opts.runs(Stage::Lossy) {
+                sink.on_fallback("Lossy stage skipped");
+                move_file(&tmp_optim, output).or_else(|_| fs::copy(input, output).map(drop))?;
+                fs::remove_file(&tmp_optim).ok();
+                progress.finish();
+                return handle_fallback_options(output, target, get_file_size_kb(output), "JPG", opts);
+            }
+            // Guetzli only encodes at quality 84 and up, and drops metadata
+            if opts.effort.guetzli() && opts.metadata == MetadataMode::Strip {
+                if let Some(result) = try_guetzli(input, output, &tmp_optim, target, start, opts)? {
+                    fs::remove_file(&tmp_optim).ok();
+                    progress.finish();
+                    return Ok(result);
+                }
+            }
         }
 
         // Stage 2: Lossy compression with ImageMagick
+        opts.cancel.check()?;
+        sink.on_stage(2, "JPEG Lossy Compression");
         if nerd {
-            logger::nerd_stage(2, "JPEG Lossy Compression");
-            logger::nerd_result("Tool", "ImageMagick", false);
-                logger::nerd_result("Complexity", "O(n) I/O bound", false);
-                logger::nerd_result("Strategy", "Smart extent targeting", false);
+            rep.nerd_stage(2, "JPEG Lossy Compression");
+            rep.nerd_result("Tool", "ImageMagick", false);
+                rep.nerd_result("Complexity", "O(n) I/O bound", false);
+                rep.nerd_result("Strategy", "Smart extent targeting", false);
+            if let Some(q) = source_quality {
+                rep.nerd_result("Source Quality", &format!("~{} (from quantization tables)", q), false);
+            }
         }
-        let mut cmd = Command::new("magick");
-        cmd.arg(&tmp_optim).arg("-strip");
+        let mut cmd = ToolCommand::new("magick");
+        cmd.arg(&tmp_optim);
+        if opts.metadata == MetadataMode::Strip { cmd.arg("-strip"); }
         cmd.arg("-sampling-factor").arg("4:4:4");
 
         if let Some(kb) = target_kb {
             let arg = format!("jpeg:extent={}KB", kb);
             cmd.arg("-define").arg(&arg);
-            if nerd { logger::nerd_cmd(&format!("magick ... -define {}", arg)); }
-        } else if let Some(lvl) = level {
-            let q = match lvl {
-                CompressionLevel::Low => "85",
-                CompressionLevel::Medium => "75",
-                CompressionLevel::High => "50",
-            };
-            cmd.arg("-quality").arg(q);
-        } else {
-            cmd.arg("-quality").arg("80");
+            if let Some(q) = source_quality { cmd.arg("-quality").arg(q.to_string()); }
+            if nerd { rep.nerd_cmd(&format!("magick ... -define {}", arg)); }
         }
 
         cmd.arg(output);
-        let status = cmd.status()?;
+        let status = run_tool(opts, &cmd)?;
+        if target_kb.is_some() && status.success() && under_quality_floor(output, opts) {
+            // Re-encode at the floor and let the fallbacks deal with the miss
+            let quality = opts.quality_floor(1).min(source_quality.map_or(100, u64::from));
+            let mut cmd = ToolCommand::new("magick");
+            cmd.arg(&tmp_optim);
+            if opts.metadata == MetadataMode::Strip { cmd.arg("-strip"); }
+            cmd.arg("-sampling-factor").arg("4:4:4").arg("-quality").arg(quality.to_string()).arg(output);
+            sink.on_fallback(&format!("Quality floor {} kept", quality));
+            if nerd { rep.nerd_result("Quality floor", &format!("{} (extent went lower)", quality), true); }
+            run_tool(opts, &cmd)?;
+        }
         fs::remove_file(&tmp_optim).ok();
-        if !status.success() { return Err(anyhow!("ImageMagick failed.")); }
+        if !status.success() { return Err(anyhow!(t!("error-magick"))); }
         progress.finish();
 
         // Check & Fallbacks
@@ -274,16 +906,16 @@ fn compress_jpg(input: &str, output: &str, target_kb: Option<u64>, level: Option
             let current_size = get_file_size_kb(output);
             if nerd {
                 let hit = if current_size <= target { "Hit!" } else { "Miss" };
-                logger::nerd_result("Target", &format!("{} KB", target), false);
-                logger::nerd_result("Result", &format!("{} KB ({})", current_size, hit), true);
+                rep.nerd_result("Target", &format!("{} KB", target), false);
+                rep.nerd_result("Result", &format!("{} KB ({})", current_size, hit), true);
             }
             if current_size > target {
-                let fallback_result = handle_fallback_options(output, target, current_size, nerd, "JPG");
+                let fallback_result = handle_fallback_options(output, target, current_size, "JPG", opts);
                 if nerd {
                     let final_size = get_file_size_kb(output);
                     let original_size = get_file_size_kb(input);
                     let total_time = start.elapsed().as_secs_f64();
-                    logger::nerd_output_summary(input, output, original_size, final_size, "jpegoptim + ImageMagick", total_time);
+                    rep.nerd_output_summary(input, output, original_size, final_size, "jpegoptim + ImageMagick", total_time);
                 }
                 return fallback_result;
             }
@@ -293,65 +925,103 @@ fn compress_jpg(input: &str, output: &str, target_kb: Option<u64>, level: Option
             let final_size = get_file_size_kb(output);
             let original_size = get_file_size_kb(input);
             let total_time = start.elapsed().as_secs_f64();
-            logger::nerd_output_summary(input, output, original_size, final_size, "jpegoptim + ImageMagick", total_time);
+            rep.nerd_output_summary(input, output, original_size, final_size, "jpegoptim + ImageMagick", total_time);
         }
         Ok(result_with_time("jpegoptim + ImageMagick", start))
     }
 }
 
+/// Guetzli re-encode at its lowest quality; the output when it fits the
+/// target. A missing or failing guetzli just means no result.
+fn try_guetzli(input: &str, output: &str, source: &str, target: u64, start: Instant, opts: &CompressionOptions) -> Result<Option<CompResult>> {
+    opts.cancel.check()?;
+    opts.progress.on_stage(2, "Guetzli Re-encode");
+    if opts.nerd {
+        opts.reporter.nerd_stage(2, "Guetzli Re-encode");
+        opts.reporter.nerd_cmd(&format!("guetzli --quality 84 {} {}", source, output));
+    }
+    let mut scratch = Scratch::default();
+    let guetzli_out = opts.scratch_path(output, "guetzli.tmp.jpg");
+    scratch.track(&guetzli_out);
+    let status = run_tool(opts, ToolCommand::new("guetzli").arg("--quality").arg("84").arg(source).arg(&guetzli_out).quiet_stderr(true));
+    opts.cancel.check()?;
+    if !status.is_ok_and(|s| s.success()) {
+        opts.progress.on_fallback("guetzli unavailable or failed");
+        return Ok(None);
+    }
+    let size = get_file_size_kb(&guetzli_out);
+    if opts.nerd {
+        opts.reporter.nerd_result("Result", &format!("{} KB ({})", size, if size <= target { "Hit!" } else { "Miss" }), true);
+    }
+    if size > target {
+        return Ok(None);
+    }
+    move_file(&guetzli_out, output)?;
+    if opts.nerd {
+        opts.reporter.nerd_output_summary(input, output, get_file_size_kb(input), size, "Guetzli (quality 84)", start.elapsed().as_secs_f64());
+    }
+    Ok(Some(result_with_time("Guetzli (quality 84)", start)))
+}
+
 // PNG: Waterfall Strategy (His Version - Smartest Logic)
-fn compress_png(input: &str, output: &str, target_kb: Option<u64>, _level: Option<CompressionLevel>, nerd: bool, auto_yes: bool) -> Result<CompResult> {
+fn compress_png(input: &str, output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    if Format::Png.is_animated(Path::new(input)) {
+        return compress_apng(input, output, opts);
+    }
+    let (target_kb, nerd) = (opts.target_kb, opts.nerd);
+    let sink = opts.progress.as_ref();
+    let rep = opts.reporter.as_ref();
     let start = Instant::now();
     let original_size = get_file_size_kb(input);
-    if let Some(target) = target_kb {
-        if target >= original_size {
-            println!("Requested size ({}) KB is larger than or equal to original file size ({} KB). No compression performed.", target, original_size);
-            let should_keep = if auto_yes {
-                if nerd { println!("   [Auto-yes enabled, keeping original]"); }
-                true
-            } else {
-                Confirm::new().with_prompt("Keep original file?").default(true).interact()?
-            };
-            if should_keep {
-                fs::copy(input, output)?;
-                return Ok(result_with_time("No compression (requested size >= original)", start));
-            } else {
-                return Err(anyhow!("Compression cancelled by user."));
-            }
-        }
-    }
 
     // Use a single PacmanProgress bar for normal mode, always 100 steps
     let mut progress = if !nerd {
-        Some(PacmanProgress::new(100, "Eating those bytes..."))
+        Some(rep.progress(100, &t!("progress-eating")))
     } else {
         None
     };
+    opts.cancel.check()?;
+    sink.on_stage(1, "Stripping off Metadata");
     if nerd {
-        logger::nerd_stage(1, "Stripping off Metadata");
-        logger::nerd_result("Tool", "Oxipng", false);
-        logger::nerd_result("Strategy", "Removing metadata from the image (lossless)", false);
-        logger::nerd_result("Original Size", &format!("{} KB", original_size), false);
-        logger::nerd_cmd(&format!("oxipng -o 2 --strip safe --quiet --out {} {}", output, input));
-    }
-    let oxi_out = format!("{}.oxipng.tmp.png", output);
-    let _oxi_status = Command::new("oxipng")
-        .arg("-o").arg("2").arg("--strip").arg("safe").arg("--quiet")
-        .arg("--out").arg(&oxi_out).arg(input)
-        .status()?;
+        rep.nerd_stage(1, "Stripping off Metadata");
+        rep.nerd_result("Tool", "Oxipng", false);
+        rep.nerd_result("Strategy", "Removing metadata from the image (lossless)", false);
+        rep.nerd_result("Original Size", &format!("{} KB", original_size), false);
+        rep.nerd_cmd(&format!("oxipng {} --strip safe --quiet --out {} {}", oxipng_level_args(opts).join(" "), output, input));
+    }
+    let mut scratch = Scratch::default();
+    let oxi_out = opts.scratch_path(output, "oxipng.tmp.png");
+    scratch.track(&oxi_out);
+    shared_or_make(opts, "oxipng", input, &oxi_out, || Ok(run_tool(opts, ToolCommand::new("oxipng")
+        .args(oxipng_level_args(opts)).args(oxipng_strip_args(opts)).arg("--quiet")
+        .arg("--out").arg(&oxi_out).arg(input))?.success()))?;
     // No progress bar update here; only animate in the lossless branch below
     if nerd {
         let oxi_size = get_file_size_kb(&oxi_out);
         let meta_removed = original_size.saturating_sub(oxi_size);
-        logger::nerd_result("Metadata Removed", &format!("{} KB", meta_removed), false);
-        logger::nerd_result("Output Size after oxipng", &format!("{} KB", oxi_size), false);
+        rep.nerd_result("Metadata Removed", &format!("{} KB", meta_removed), false);
+        rep.nerd_result("Output Size after oxipng", &format!("{} KB", oxi_size), false);
         let reduction = if original_size > 0 { (original_size - oxi_size) as f64 / original_size as f64 * 100.0 } else { 0.0 };
-        logger::nerd_result("Reduction", &format!("{:.2}%", reduction), true);
+        rep.nerd_result("Reduction", &format!("{:.2}%", reduction), true);
     }
     let oxi_size = get_file_size_kb(&oxi_out);
 
-    // If no target, return lossless result with smooth Pacman bar
+    // If no target, return lossless result with smooth Pacman bar; --quality
+    // adds one pngquant pass with it as the ceiling, kept when it's smaller
     if target_kb.is_none() {
+        let mut algorithm = "oxipng (Lossless)".to_string();
+        if let Some(quality) = opts.quality.filter(|_| opts.runs(Stage::Quantize)) {
+            opts.cancel.check()?;
+            let pq_out = opts.scratch_path(output, "pngquant.tmp.png");
+            scratch.track(&pq_out);
+            if nerd { rep.nerd_cmd(&format!("pngquant --quality 0-{} --force --output {} {}", quality, pq_out, oxi_out)); }
+            let status = run_tool(opts, ToolCommand::new("pngquant").arg("--quality").arg(format!("0-{}", quality))
+                .arg("--force").arg("--output").arg(&pq_out).arg(&oxi_out))?;
+            if status.success() && get_file_size_kb(&pq_out) < oxi_size {
+                move_file(&pq_out, &oxi_out)?;
+                algorithm = format!("pngquant (quality {}) + oxipng", quality);
+            }
+        }
         if let Some(ref mut bar) = progress {
             for i in 1..=100 {
                 bar.set(i);
@@ -359,74 +1029,129 @@ fn compress_png(input: &str, output: &str, target_kb: Option<u64>, _level: Optio
             }
             bar.finish();
         }
-        fs::copy(&oxi_out, output)?;
-        fs::remove_file(&oxi_out).ok();
+        move_file(&oxi_out, output)?;
         if nerd {
             let total_time = start.elapsed().as_secs_f64();
             let final_size = get_file_size_kb(output);
-            logger::nerd_output_summary(input, output, original_size, final_size, "oxipng (Lossless)", total_time);
+            rep.nerd_output_summary(input, output, original_size, final_size, &algorithm, total_time);
         }
-        return Ok(result_with_time("oxipng (Lossless)", start));
+        return Ok(result_with_time(algorithm, start));
     }
 
     let target = target_kb.unwrap();
     if oxi_size <= target {
-        fs::copy(&oxi_out, output)?;
-        fs::remove_file(&oxi_out).ok();
+        move_file(&oxi_out, output)?;
         if nerd {
-            logger::nerd_result("Result", "Target hit losslessly!", true);
+            rep.nerd_result("Result", "Target hit losslessly!", true);
             let total_time = start.elapsed().as_secs_f64();
             let final_size = get_file_size_kb(output);
-            logger::nerd_output_summary(input, output, original_size, final_size, "oxipng (Lossless)", total_time);
+            rep.nerd_output_summary(input, output, original_size, final_size, "oxipng (Lossless)", total_time);
         }
         return Ok(result_with_time("oxipng (Lossless)", start));
     }
 
-    // 2. COLOR QUANTIZATION (Binary Search on Quality Index)
-    if nerd {
-        logger::nerd_stage(2, "Color Quantization");
-        logger::nerd_result("Tool", "pngquant", false);
-        logger::nerd_result("Strategy", "Color Quantization using Binary search for quality index 30-100(lossy)", false);
-        logger::nerd_result("Complexity", "O(log n)", false);
-        logger::nerd_cmd(&format!("pngquant --quality 30-100 --force --output {} {}", output, &oxi_out));
-        let color_check = if oxi_size < original_size * 95 / 100 { "Likely Color" } else { "Likely BW" };
-        logger::nerd_result("Color Check Result", color_check, false);
-    }
-    let mut min_q = 30;
-    let mut max_q = 100;
-    let mut best_candidate: Option<(u8, u64)> = None;
-    let pq_out = format!("{}.pngquant.tmp.png", output);
-    let mut attempts = 0;
-    // Color quantization
-    while min_q <= max_q && attempts < 8 {
-        attempts += 1;
-        let mid_q = (min_q + max_q) / 2;
-        let t0 = Instant::now();
-        let status = Command::new("pngquant")
-            .arg("--quality").arg(format!("{}-{}", mid_q, max_q))
-            .arg("--force").arg("--output").arg(&pq_out).arg(&oxi_out)
-            .status()?;
-        let elapsed_ms = t0.elapsed().as_millis();
-        if !status.success() {
-            max_q = mid_q - 1;
-            continue;
-        }
-        let pq_size = get_file_size_kb(&pq_out);
-        let action = if pq_size <= target { "min=mid+1" } else { "max=mid-1" };
+    // Screenshots: crop uniform borders, then quantize without dithering
+    let is_screenshot = screenshot::looks_like_screenshot(Path::new(input));
+    let mut oxi_size = oxi_size;
+    if is_screenshot && opts.runs(Stage::Resize) {
+        opts.cancel.check()?;
+        let trim_out = opts.scratch_path(output, "trim.tmp.png");
+        scratch.track(&trim_out);
         if nerd {
-            logger::nerd_quality_attempt(attempts, 8, mid_q as u8, pq_size, target, elapsed_ms, action);
+            rep.nerd_result("Screenshot", "detected, cropping uniform borders", false);
+            rep.nerd_cmd(&format!("magick {} -trim +repage {}", &oxi_out, &trim_out));
         }
-        if pq_size <= target {
-            best_candidate = Some((mid_q as u8, pq_size));
-            min_q = mid_q + 1; // Try higher quality
-        } else {
-            if mid_q == 30
-                && nerd {
-                    logger::nerd_result("quality floor reached in pngquant, cannot compress further:", "", true);
-                }
-            max_q = mid_q - 1; // Try lower quality
+        let status = run_tool(opts, ToolCommand::new("magick").arg(&oxi_out).arg("-trim").arg("+repage").arg(&trim_out))?;
+        if status.success() {
+            let _ = run_tool(opts, ToolCommand::new("oxipng").args(oxipng_level_args(opts)).args(oxipng_strip_args(opts)).arg("--quiet").arg(&trim_out));
+        }
+        let trim_size = get_file_size_kb(&trim_out);
+        if status.success() && trim_size < oxi_size && decide(opts, Decision::CropBorders { size_kb: trim_size })? {
+            sink.on_fallback("Uniform borders cropped");
+            move_file(&trim_out, &oxi_out)?;
+            oxi_size = trim_size;
+            if nerd { rep.nerd_result("Output Size after crop", &format!("{} KB", oxi_size), true); }
+        }
+        if oxi_size <= target {
+            move_file(&oxi_out, output)?;
+            if let Some(ref mut bar) = progress {
+                bar.set(100);
+                bar.finish();
+            }
+            if nerd {
+                let total_time = start.elapsed().as_secs_f64();
+                rep.nerd_output_summary(input, output, original_size, get_file_size_kb(output), "Screenshot (Border Crop + Oxipng)", total_time);
+            }
+            return Ok(result_with_time("Screenshot (Border Crop + Oxipng)", start));
         }
     }
+
+    // 2. COLOR QUANTIZATION (Binary Search on Quality Index)
+    let pq_out = opts.scratch_path(output, "pngquant.tmp.png");
+    scratch.track(&pq_out);
+    // The last probe may be a miss; every hit is kept here instead
+    let pq_best = opts.scratch_path(output, "pngquant.best.tmp.png");
+    scratch.track(&pq_best);
+    // Dithering smears text edges and bloats flat areas, and low qualities
+    // band UI gradients, so screenshots search a narrower range
+    let min_q = opts.quality_floor(if is_screenshot { 50 } else { 30 });
+    let best_candidate = if opts.runs(Stage::Quantize) {
+        opts.cancel.check()?;
+        sink.on_stage(2, "Color Quantization");
+        if nerd {
+            rep.nerd_stage(2, "Color Quantization");
+            rep.nerd_result("Tool", "pngquant", false);
+            rep.nerd_result("Strategy", &format!("Color Quantization using Binary search for quality index {}-100(lossy)", min_q), false);
+            rep.nerd_result("Complexity", "O(log n)", false);
+            rep.nerd_cmd(&format!("pngquant --quality {}-100 --force --output {} {}", min_q, output, &oxi_out));
+            let color_check = if oxi_size < original_size * 95 / 100 { "Likely Color" } else { "Likely BW" };
+            rep.nerd_result("Color Check Result", color_check, false);
+        }
+        // Color quantization: highest quality whose output fits. pngquant's
+        // ceiling follows the search's upper bound down after each miss.
+        let mut max_q = search_ceiling(opts, 2, "quality", min_q, 100);
+        let max_probes = opts.effort.image_probes();
+        let search = search::binary(min_q, max_q, opts.search_window(target), max_probes, |attempt, mid_q| {
+            opts.cancel.check()?;
+            let t0 = Instant::now();
+            let mut cmd = ToolCommand::new("pngquant");
+            cmd.arg("--quality").arg(format!("{}-{}", mid_q, max_q));
+            if is_screenshot { cmd.arg("--nofs"); }
+            let status = run_tool(opts, cmd.arg("--force").arg("--output").arg(&pq_out).arg(&oxi_out))?;
+            let elapsed_ms = t0.elapsed().as_millis();
+            if !status.success() {
+                max_q = mid_q - 1;
+                return Ok(None);
+            }
+            let pq_size = get_file_size_kb(&pq_out);
+            if pq_size > target {
+                max_q = mid_q - 1;
+            } else {
+                move_file(&pq_out, &pq_best)?;
+            }
+            sink.on_attempt(&Attempt {
+                stage: 2,
+                index: attempt,
+                max: max_probes,
+                parameter: "quality",
+                value: mid_q,
+                size_kb: pq_size,
+                target_kb: target,
+                time_ms: elapsed_ms,
+            });
+            let action = if pq_size <= target { "min=mid+1" } else { "max=mid-1" };
+            if nerd {
+                rep.nerd_quality_attempt(attempt, max_probes, mid_q as u8, pq_size, target, elapsed_ms, action);
+                if pq_size > target && mid_q == min_q {
+                    rep.nerd_result("quality floor reached in pngquant, cannot compress further:", "", true);
+                }
+            }
+            Ok(Some(pq_size))
+        })?;
+        search.best.map(|(q, size)| (q as u8, size))
+    } else {
+        None
+    };
     if let Some(ref mut bar) = progress {
         for i in 26..=50 {
             bar.set(i);
@@ -437,46 +1162,54 @@ fn compress_png(input: &str, output: &str, target_kb: Option<u64>, _level: Optio
     // If we found a good quantization, use it
     let _color_candidate_path: Option<String>;
     if let Some((q, _)) = best_candidate {
-        fs::copy(&pq_out, output)?;
-        fs::remove_file(&pq_out).ok();
-        fs::remove_file(&oxi_out).ok();
+        move_file(&pq_best, output)?;
         
         // Polish
-        let _ = Command::new("oxipng").arg("-o").arg("2").arg("--strip").arg("safe").arg("--quiet").arg(output).status();
+        let _ = run_tool(opts, ToolCommand::new("oxipng").args(oxipng_level_args(opts)).args(oxipng_strip_args(opts)).arg("--quiet").arg(output));
         if let Some(ref mut bar) = progress {
             bar.set(100);
             bar.finish();
         }
         if nerd {
-            logger::nerd_result("Optimal Quality", &q.to_string(), true);
+            rep.nerd_result("Optimal Quality", &q.to_string(), true);
             let total_time = start.elapsed().as_secs_f64();
             let final_size = get_file_size_kb(output);
-            logger::nerd_output_summary(input, output, original_size, final_size, "Hybrid (Oxipng + Binary Search)", total_time);
+            rep.nerd_output_summary(input, output, original_size, final_size, "Hybrid (Oxipng + Binary Search)", total_time);
         }
         return Ok(result_with_time("Hybrid (Oxipng + Binary Search)", start));
-    } else {
+    } else if opts.runs(Stage::Quantize) {
         // Keep track of the last attempt (best effort color)
         _color_candidate_path = Some(pq_out.clone());
+    } else {
+        _color_candidate_path = None;
     }
 
-    // 3. GRAYSCALE (XEROX MODE)
-    let gray_out = format!("{}.gray.tmp.png", output);
-    if nerd {
-        let color_check = if oxi_size < original_size * 95 / 100 { "Likely Color" } else { "Likely BW" };
-        logger::nerd_stage(3, "Grayscale Conversion");
-        if color_check == "Likely BW" {
-            logger::nerd_result("Tool", "magick", false);
-            logger::nerd_result("Strategy", "Convert to grayscale", false);
-            logger::nerd_result("Complexity", "O(n) I/O bound", false);
-        } else {
-            logger::nerd_result("grayscale conversion not required for this image.:", "", true);
+    // 3. GRAYSCALE (XEROX MODE), or 1-bit fax mode with --bw
+    let label = desaturate_label(opts);
+    let gray_out = opts.scratch_path(output, "gray.tmp.png");
+    scratch.track(&gray_out);
+    // A skipped grayscale stage never fits and is never smaller
+    let gray_size = if opts.runs(Stage::Grayscale) {
+        opts.cancel.check()?;
+        sink.on_stage(3, &format!("{} Conversion", label));
+        if nerd {
+            let color_check = if oxi_size < original_size * 95 / 100 { "Likely Color" } else { "Likely BW" };
+            rep.nerd_stage(3, &format!("{} Conversion", label));
+            if color_check == "Likely BW" {
+                rep.nerd_result("Tool", "magick", false);
+                rep.nerd_result("Strategy", if opts.bw.is_some() { "Convert to 1-bit black and white" } else { "Convert to grayscale" }, false);
+                rep.nerd_result("Complexity", "O(n) I/O bound", false);
+            } else {
+                rep.nerd_result("grayscale conversion not required for this image.:", "", true);
+            }
+            rep.message(""); // Add blank line after stage 3 and warning
         }
-        println!(); // Add blank line after stage 3 and warning
-    }
-    let _gray_status = Command::new("magick")
-        .arg(&oxi_out).arg("-colorspace").arg("Gray").arg("-depth").arg("8").arg(&gray_out)
-        .status()?;
-    let gray_size = get_file_size_kb(&gray_out);
+        let _gray_status = run_tool(opts, ToolCommand::new("magick")
+            .arg(&oxi_out).args(desaturate_args(opts)).arg(&gray_out))?;
+        get_file_size_kb(&gray_out)
+    } else {
+        u64::MAX
+    };
 
     // Branch A: Grayscale fits
     if gray_size <= target {
@@ -485,25 +1218,18 @@ fn compress_png(input: &str, output: &str, target_kb: Option<u64>, _level: Optio
             bar.finish();
         }
         progress = None; // Clear progress bar reference
-        let should_grayscale = if auto_yes {
-            if nerd { println!("   [Auto-yes enabled, converting to grayscale]"); }
-            true
-        } else {
-            Confirm::new().with_prompt(format!("Target reached by converting to Grayscale ({} KB). Proceed?", gray_size)).default(true).interact()?
-        };
-        if should_grayscale {
-            fs::copy(&gray_out, output)?;
-            // Cleanup
-            fs::remove_file(&gray_out).ok();
-            fs::remove_file(&oxi_out).ok();
-            if let Some(ref p) = _color_candidate_path { fs::remove_file(p).ok(); }
-            if nerd { logger::nerd_result("Result", "Converted to Grayscale", true); }
+        let decision = if opts.bw.is_some() { Decision::BlackAndWhite { size_kb: gray_size } } else { Decision::Grayscale { size_kb: gray_size } };
+        if decide(opts, decision)? {
+            sink.on_fallback(&format!("{} conversion", label));
+            move_file(&gray_out, output)?;
+            let algorithm = format!("pngquant + {}", label);
+            if nerd { rep.nerd_result("Result", &format!("Converted to {}", label), true); }
             if nerd {
                 let total_time = start.elapsed().as_secs_f64();
                 let final_size = get_file_size_kb(output);
-                logger::nerd_output_summary(input, output, original_size, final_size, "pngquant + Grayscale", total_time);
+                rep.nerd_output_summary(input, output, original_size, final_size, &algorithm, total_time);
             }
-            return Ok(result_with_time("pngquant + Grayscale", start));
+            return Ok(result_with_time(algorithm, start));
         }
     }
 
@@ -518,32 +1244,21 @@ fn compress_png(input: &str, output: &str, target_kb: Option<u64>, _level: Optio
         }
         progress = None; // Clear progress bar reference
         // Grayscale is smaller, offer it as base for resizing
-        let should_use_grayscale = if auto_yes {
-            if nerd { println!("   [Auto-yes enabled, using grayscale for resizing]"); }
-            true
-        } else {
-            Confirm::new().with_prompt("Target unreachable in Color. Proceed with Grayscale Resizing?").default(true).interact()?
-        };
-        if should_use_grayscale {
+        let decision = if opts.bw.is_some() { Decision::BlackAndWhiteResize } else { Decision::GrayscaleResize };
+        if decide(opts, decision)? {
+            sink.on_fallback(&format!("{} base for resizing", label));
             resize_input = &gray_out;
         } else {
             // User rejected grayscale - ask if they want to resize color instead
-            let should_resize_color = if auto_yes {
-                if nerd { println!("   [Auto-yes enabled, resizing color image]"); }
-                true
-            } else {
-                Confirm::new().with_prompt("Resize the Color image instead?").default(false).interact()?
-            };
+            let should_resize_color = decide(opts, Decision::ResizeColor)?;
             if !should_resize_color {
                 // User rejected all options - save best effort and exit
+                sink.on_fallback("Best effort color kept");
                 if let Some(ref p) = _color_candidate_path {
-                    fs::copy(p, output)?;
-                    fs::remove_file(p).ok();
+                    move_file(p, output)?;
                 } else {
-                    fs::copy(&oxi_out, output)?;
+                    move_file(&oxi_out, output)?;
                 }
-                fs::remove_file(&oxi_out).ok();
-                fs::remove_file(&gray_out).ok();
                 if let Some(ref mut bar) = progress {
                     bar.set(100);
                     bar.finish();
@@ -551,9 +1266,9 @@ fn compress_png(input: &str, output: &str, target_kb: Option<u64>, _level: Optio
                 if nerd {
                     let total_time = start.elapsed().as_secs_f64();
                     let final_size = get_file_size_kb(output);
-                    logger::nerd_output_summary(input, output, original_size, final_size, "pngquant (Best Effort Color)", total_time);
+                    rep.nerd_output_summary(input, output, original_size, final_size, "pngquant (Best Effort Color)", total_time);
                 }
-                println!("   Keeping best color version ({} KB).", get_file_size_kb(output));
+                rep.message(&format!("   {}", t!("keeping-best-color", size = get_file_size_kb(output))));
                 return Ok(result_with_time("pngquant (Best Effort Color)", start));
             }
             // else: proceed with color resize
@@ -566,22 +1281,15 @@ fn compress_png(input: &str, output: &str, target_kb: Option<u64>, _level: Optio
         }
         progress = None; // Clear progress bar reference
         // Gray is not smaller than oxi - ask about resizing color
-        let should_resize = if auto_yes {
-            if nerd { println!("   [Auto-yes enabled, resizing image]"); }
-            true
-        } else {
-            Confirm::new().with_prompt("Target unreachable. Resize image dimensions?").default(false).interact()?
-        };
+        let should_resize = decide(opts, Decision::Resize)?;
         if !should_resize {
             // Save best effort
+            sink.on_fallback("Best effort kept");
             if let Some(ref p) = _color_candidate_path {
-                fs::copy(p, output)?;
-                fs::remove_file(p).ok();
+                move_file(p, output)?;
             } else {
-                fs::copy(&oxi_out, output)?;
+                move_file(&oxi_out, output)?;
             }
-            fs::remove_file(&oxi_out).ok();
-            fs::remove_file(&gray_out).ok();
             if let Some(ref mut bar) = progress {
                 bar.set(100);
                 bar.finish();
@@ -589,40 +1297,55 @@ fn compress_png(input: &str, output: &str, target_kb: Option<u64>, _level: Optio
             if nerd {
                 let total_time = start.elapsed().as_secs_f64();
                 let final_size = get_file_size_kb(output);
-                logger::nerd_output_summary(input, output, original_size, final_size, "pngquant (Best Effort)", total_time);
+                rep.nerd_output_summary(input, output, original_size, final_size, "pngquant (Best Effort)", total_time);
             }
-            println!("   Keeping best version ({} KB).", get_file_size_kb(output));
+            rep.message(&format!("   {}", t!("keeping-best", size = get_file_size_kb(output))));
             return Ok(result_with_time("pngquant (Best Effort)", start));
         }
     }
 
     // 4. RESIZE LOOP
+    opts.cancel.check()?;
+    sink.on_stage(4, "Image Resizing");
     if nerd {
-        logger::nerd_stage(4, "Image Resizing");
-        logger::nerd_result("Tool", "magick", false);
-        logger::nerd_result("Strategy", "Resizing image dimentions using Binary search as Scale index(too lossy)", false);
-        logger::nerd_result("Complexity", "O(log n)", false);
-        logger::nerd_cmd("magick <in> -resize <scale>% <out>");
-    }
-    let mut min_scale = 1;
-    let mut max_scale = 100;
+        rep.nerd_stage(4, "Image Resizing");
+        rep.nerd_result("Tool", "magick", false);
+        rep.nerd_result("Strategy", "Resizing image dimentions using Binary search as Scale index(too lossy)", false);
+        rep.nerd_result("Complexity", "O(log n)", false);
+        rep.nerd_cmd("magick <in> -resize <scale>% <out>");
+    }
+    let mut min_scale = opts.scale_floor();
+    let mut max_scale = search_ceiling(opts, 4, "scale", min_scale, 100);
     let mut best_scale: Option<(u8, u64)> = None;
-    let resize_out = format!("{}.resize.tmp.png", output);
+    let resize_out = opts.scratch_path(output, "resize.tmp.png");
+    scratch.track(&resize_out);
+    let max_probes = opts.effort.image_probes();
     let mut attempts = 0;
-    while min_scale <= max_scale && attempts < 8 {
+    while min_scale <= max_scale && attempts < max_probes {
+        opts.cancel.check()?;
         attempts += 1;
         let mid_scale = (min_scale + max_scale) / 2;
         let t0 = Instant::now();
-        let status = Command::new("magick")
+        let status = run_tool(opts, ToolCommand::new("magick")
             .arg(resize_input)
             .arg("-resize").arg(format!("{}%", mid_scale))
-            .arg(&resize_out).status()?;
+            .arg(&resize_out))?;
         let elapsed_ms = t0.elapsed().as_millis();
         if status.success() {
             let size = get_file_size_kb(&resize_out);
+            sink.on_attempt(&Attempt {
+                stage: 4,
+                index: attempts,
+                max: max_probes,
+                parameter: "scale",
+                value: mid_scale,
+                size_kb: size,
+                target_kb: target,
+                time_ms: elapsed_ms,
+            });
             let action = if size <= target { "min=mid+1" } else { "max=mid-1" };
             if nerd {
-                logger::nerd_scale_attempt(attempts, 8, mid_scale as u8, size, target, elapsed_ms, action);
+                rep.nerd_scale_attempt(attempts, max_probes, mid_scale as u8, size, target, elapsed_ms, action);
             }
             if size <= target {
                 best_scale = Some((mid_scale as u8, size));
@@ -642,270 +1365,1477 @@ fn compress_png(input: &str, output: &str, target_kb: Option<u64>, _level: Optio
     }
     let mut final_size = 0;
     if let Some((scale, size)) = best_scale {
-        fs::copy(&resize_out, output)?;
+        sink.on_fallback(&format!("Resize to {}%", scale));
+        move_file(&resize_out, output)?;
         final_size = size;
-        if nerd { logger::nerd_result("Resize fits target", &format!("{}%", scale), true); }
+        if nerd { rep.nerd_result("Resize fits target", &format!("{}%", scale), true); }
         // Final Polish
-        let _ = Command::new("oxipng").arg("-o").arg("2").arg("--strip").arg("safe").arg("--quiet").arg(output).status();
+        let _ = run_tool(opts, ToolCommand::new("oxipng").args(oxipng_level_args(opts)).args(oxipng_strip_args(opts)).arg("--quiet").arg(output));
     } else {
         // Impossible
-        let should_save_smallest = if auto_yes {
-            if nerd { println!("   [Auto-yes enabled, saving smallest possible]"); }
-            true
-        } else {
-            Confirm::new().with_prompt("Target unreachable. Save smallest possible?").default(true).interact()?
-        };
+        let should_save_smallest = decide(opts, Decision::SaveSmallest)?;
         if should_save_smallest {
+            sink.on_fallback("Smallest resize kept (target unreachable)");
             final_size = get_file_size_kb(&resize_out);
-            fs::copy(&resize_out, output)?;
+            move_file(&resize_out, output)?;
         }
     }
-    // Cleanup
-    fs::remove_file(&oxi_out).ok();
-    fs::remove_file(&gray_out).ok();
-    fs::remove_file(&resize_out).ok();
-    if let Some(ref p) = _color_candidate_path { fs::remove_file(p).ok(); }
     if nerd {
         let total_time = start.elapsed().as_secs_f64();
-        logger::nerd_output_summary(input, output, original_size, final_size, "PNG Hybrid Chain", total_time);
+        rep.nerd_output_summary(input, output, original_size, final_size, "PNG Hybrid Chain", total_time);
     }
     Ok(result_with_time("Hybrid Chain", start))
 }
 
-// PDF: Binary Search (Optimal) with Floor Detection
-fn compress_pdf(input: &str, output: &str, target_kb: Option<u64>, _level: Option<CompressionLevel>, nerd: bool, auto_yes: bool) -> Result<CompResult> {
-    let total_start = Instant::now();
-    let original_size = get_file_size_kb(input);
-    let mut _gs_calls: u32 = 0;
-    if let Some(target) = target_kb {
-        if target >= original_size {
-            println!("Requested size ({}) KB is larger than or equal to original file size ({} KB). No compression performed.", target, original_size);
-            let should_keep = if auto_yes {
-                if nerd { println!("   [Auto-yes enabled, keeping original]"); }
-                true
-            } else {
-                Confirm::new().with_prompt("Keep original file?").default(true).interact()?
-            };
-            if should_keep {
-                fs::copy(input, output)?;
-                return Ok(result_with_time("No compression (requested size >= original)", total_start));
-            } else {
-                return Err(anyhow!("Compression cancelled by user."));
+/// Quality used when no target is given, for the encoders searched over quality
+fn preset_quality(opts: &CompressionOptions) -> u64 {
+    opts.quality.unwrap_or(match opts.level {
+        Some(CompressionLevel::Low) => 90,
+        Some(CompressionLevel::Medium) => 75,
+        Some(CompressionLevel::High) => 50,
+        None => 80,
+    })
+}
+
+/// Ghostscript image resolution for `--quality`: 36 DPI at 1 up to 300 at 100
+pub(crate) fn quality_dpi(quality: u64) -> u64 {
+    36 + quality.min(100) * 264 / 100
+}
+
+/// Binary search the highest `encode` quality (5, or `min_quality`, to 100)
+/// whose output fits `target`; the best hit ends up at `output`. `None`
+/// when nothing fits.
+fn quality_search(
+    output: &str,
+    target: u64,
+    stage: u32,
+    probe_suffix: &str,
+    opts: &CompressionOptions,
+    encode: impl Fn(u64, &str) -> Result<Option<u64>>,
+) -> Result<Option<u64>> {
+    let sink = opts.progress.as_ref();
+    let rep = opts.reporter.as_ref();
+    let probe = opts.scratch_path(output, probe_suffix);
+    let mut scratch = Scratch::default();
+    scratch.track(&probe);
+    let min_q = opts.quality_floor(5);
+    let max_q = search_ceiling(opts, stage, "quality", min_q, 100);
+    let max_probes = opts.effort.image_probes();
+    let search = search::binary(min_q, max_q, opts.search_window(target), max_probes, |attempt, quality| {
+        let t0 = Instant::now();
+        let size = encode(quality, &probe)?;
+        if let Some(size_kb) = size {
+            sink.on_attempt(&Attempt {
+                stage,
+                index: attempt,
+                max: max_probes,
+                parameter: "quality",
+                value: quality,
+                size_kb,
+                target_kb: target,
+                time_ms: t0.elapsed().as_millis(),
+            });
+            if opts.nerd {
+                let action = if size_kb <= target { "min=mid+1" } else { "max=mid-1" };
+                rep.nerd_quality_attempt(attempt, max_probes, quality as u8, size_kb, target, t0.elapsed().as_millis(), action);
+            }
+            // Every hit is larger than the one before, so it replaces the output
+            if size_kb <= target {
+                move_file(&probe, output)?;
             }
         }
+        Ok(size)
+    })?;
+    Ok(search.best.map(|(quality, _)| quality))
+}
+
+// APNG: oxipng only; pngquant, grayscale and resize would keep just the first frame
+fn compress_apng(input: &str, output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    let start = Instant::now();
+    opts.cancel.check()?;
+    opts.progress.on_stage(1, "APNG Lossless Optimization");
+    if opts.nerd {
+        opts.reporter.nerd_stage(1, "APNG Lossless Optimization");
+        opts.reporter.nerd_result("Strategy", "oxipng only, every frame kept", false);
+    }
+    let oxi_out = opts.scratch_path(output, "oxipng.tmp.png");
+    let mut scratch = Scratch::default();
+    scratch.track(&oxi_out);
+    let status = run_tool(opts, ToolCommand::new("oxipng")
+        .args(oxipng_level_args(opts)).args(oxipng_strip_args(opts)).arg("--quiet")
+        .arg("--out").arg(&oxi_out).arg(input))?;
+    let size = if status.success() && get_file_size_kb(&oxi_out) < get_file_size_kb(input) {
+        move_file(&oxi_out, output)?;
+        get_file_size_kb(output)
+    } else {
+        fs::copy(input, output)?;
+        get_file_size_kb(input)
+    };
+    if opts.target_kb.is_some_and(|target| size > target) {
+        opts.reporter.warning(&t!("warning-apng-floor", size = size));
     }
+    Ok(result_with_time("oxipng (APNG, all frames kept)", start))
+}
 
-    if target_kb.is_none() {
-        // Smart preset selection based on file size
-        let preset = if original_size > 50_000 {
-            // Large files (>50MB): aggressive compression
-            "/ebook"
-        } else if original_size > 10_000 {
-            // Medium files (10-50MB): balanced compression
-            "/ebook"
-        } else if original_size > 1_000 {
-            // Small-medium files (1-10MB): moderate compression
-            "/printer"
-        } else {
-            // Small files (<1MB): light compression
-            "/printer"
+// WebP: cwebp, Binary Search on Quality. Also the engine for PNG/JPG -> .webp;
+// animations (animated WebP, APNG, GIF) go through encoders that keep every frame
+fn compress_webp(input: &str, output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    let sink = opts.progress.as_ref();
+    let rep = opts.reporter.as_ref();
+    let start = Instant::now();
+    let method = if opts.effort.get() >= 7 { "6" } else { "4" };
+    let metadata = if opts.metadata == MetadataMode::Strip { "none" } else { "all" };
+    let format = Format::from_path(input);
+    let animated = format.is_some_and(|f| f.is_animated(Path::new(input)));
+    let describe = |quality: u64, note: &str| format!("{}WebP (quality {}{})", if animated { "Animated " } else { "" }, quality, note);
+    let encode = |quality: u64, dest: &str| -> Result<Option<u64>> {
+        opts.cancel.check()?;
+        let mut cmd = match (format, animated) {
+            (Some(Format::Gif), _) => {
+                let mut cmd = ToolCommand::new("gif2webp");
+                cmd.arg("-quiet").arg("-lossy").arg("-q").arg(quality.to_string()).arg("-m").arg(method)
+                    .arg("-metadata").arg(metadata).arg(input).arg("-o").arg(dest);
+                cmd
+            },
+            (_, true) => {
+                let mut cmd = ToolCommand::new("magick");
+                // Without the prefix ImageMagick reads only an APNG's default image
+                let source = if format == Some(Format::Png) { format!("apng:{}", input) } else { input.to_string() };
+                cmd.arg(source).arg("-quality").arg(quality.to_string()).arg("-define").arg(format!("webp:method={}", method));
+                if opts.metadata == MetadataMode::Strip { cmd.arg("-strip"); }
+                cmd.arg(dest);
+                cmd
+            },
+            _ => {
+                let mut cmd = ToolCommand::new("cwebp");
+                cmd.arg("-quiet").arg("-q").arg(quality.to_string()).arg("-m").arg(method)
+                    .arg("-metadata").arg(metadata).arg(input).arg("-o").arg(dest);
+                cmd
+            },
         };
-        
-        if nerd {
-            logger::nerd_stage(1, "Smart Compression");
-            logger::nerd_result("Tool", "Ghostscript", false);
-            logger::nerd_result("Strategy", &format!("Preset-based compression ({})", preset), false);
-            logger::nerd_result("Reason", &format!("Selected {} for {} KB file", preset, original_size), false);
-        }
-        let progress = PacmanProgress::new(1, "Eating those bytes...");
-        run_gs(input, output, preset, None)?;
-        progress.finish();
-        if nerd {
+        cmd.quiet_stderr(!opts.nerd);
+        if opts.nerd { rep.nerd_cmd(&cmd.shell_line()); }
+        let status = run_tool(opts, &cmd)?;
+        Ok(status.success().then(|| get_file_size_kb(dest)))
+    };
+    sink.on_stage(1, "WebP Encoding");
+    if opts.nerd {
+        rep.nerd_stage(1, "WebP Encoding");
+        rep.nerd_result("Tool", "cwebp", false);
+    }
+
+    let Some(target) = opts.target_kb else {
+        let quality = preset_quality(opts);
+        let size = encode(quality, output)?.ok_or_else(|| anyhow!(t!("error-webp", file = input)))?;
+        // Re-encoding a WebP at a fixed quality can grow it
+        if Format::from_path(input) == Some(Format::Webp) && size >= get_file_size_kb(input) {
+            fs::copy(input, output)?;
+            sink.on_fallback("Original kept (re-encoding was not smaller)");
+            return Ok(result_with_time("No compression (already optimal)", start));
+        }
+        return Ok(result_with_time(describe(quality, ""), start));
+    };
+
+    match quality_search(output, target, 1, "cwebp.tmp.webp", opts, encode)? {
+        Some(quality) => Ok(result_with_time(describe(quality, ""), start)),
+        None => {
+            let size = encode(5, output)?.ok_or_else(|| anyhow!(t!("error-webp", file = input)))?;
+            rep.warning(&t!("warning-quality-floor-resize", size = size));
+            Ok(result_with_time(describe(5, ", target missed"), start))
+        },
+    }
+}
+
+/// Decode a HEIC to a full-quality JPEG (EXIF kept, stripped with `Strip`);
+/// heif-convert first, ImageMagick's libheif delegate when that's missing
+fn decode_heic(input: &str, dest: &str, opts: &CompressionOptions) -> Result<()> {
+    opts.cancel.check()?;
+    let mut cmd = ToolCommand::new("heif-convert");
+    cmd.arg("-q").arg("100").arg(input).arg(dest);
+    if !run_tool(opts, cmd.quiet_stderr(!opts.nerd))?.success() {
+        let mut cmd = ToolCommand::new("magick");
+        cmd.arg(input).arg("-quality").arg("100").arg(dest);
+        if !run_tool(opts, cmd.quiet_stderr(!opts.nerd))?.success() {
+            return Err(anyhow!(t!("error-heic-decode", file = input)));
+        }
+    }
+    if opts.metadata == MetadataMode::Strip {
+        run_tool(opts, ToolCommand::new("jpegoptim").arg("--strip-all").arg("--quiet").arg(dest))?;
+    }
+    Ok(())
+}
+
+// HEIC -> JPG: decode, then the JPG engine against the decoded copy
+fn compress_heic_to_jpg(input: &str, output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    opts.progress.on_stage(1, "HEIC Decoding");
+    let decoded = opts.scratch_path(output, "heic.tmp.jpg");
+    let mut scratch = Scratch::default();
+    scratch.track(&decoded);
+    decode_heic(input, &decoded, opts)?;
+    let result = compress_jpg(&decoded, output, opts)?;
+    Ok(CompResult { algorithm: format!("HEIC → {}", result.algorithm), ..result })
+}
+
+/// Whether `cmd` ran and succeeded, for tools with a fallback. A missing
+/// tool is an error from the executor; only cancelling is passed on.
+fn tool_succeeded(opts: &CompressionOptions, cmd: &mut ToolCommand) -> Result<bool> {
+    match run_tool(opts, cmd.quiet_stderr(!opts.nerd)) {
+        Ok(status) => Ok(status.success()),
+        Err(e) if e.downcast_ref::<Cancelled>().is_some() => Err(e),
+        Err(_) => Ok(false),
+    }
+}
+
+/// Develop a camera RAW to a full-quality JPEG at `dest`: dcraw with the
+/// camera's white balance through ImageMagick, or darktable-cli when dcraw
+/// is missing or can't read the camera
+fn develop_raw(input: &str, dest: &str, ppm: &str, opts: &CompressionOptions) -> Result<()> {
+    opts.cancel.check()?;
+    let succeeded = |cmd: &mut ToolCommand| tool_succeeded(opts, cmd);
+    let developed = succeeded(ToolCommand::new("dcraw").arg("-c").arg("-w").arg(input).stdout_to(ppm))?
+        && succeeded(ToolCommand::new("magick").arg(ppm).arg("-quality").arg("100").arg(dest))?;
+    if !developed && !succeeded(ToolCommand::new("darktable-cli").arg(input).arg(dest)
+        .arg("--core").arg("--conf").arg("plugins/imageio/format/jpeg/quality=100"))? {
+        return Err(anyhow!(t!("error-raw-develop", file = input)));
+    }
+    if opts.metadata == MetadataMode::Strip {
+        run_tool(opts, ToolCommand::new("jpegoptim").arg("--strip-all").arg("--quiet").arg(dest))?;
+    }
+    Ok(())
+}
+
+// RAW -> JPG: develop, then the JPG engine against the developed copy
+fn compress_raw(input: &str, output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    opts.progress.on_stage(1, "RAW Development");
+    let developed = opts.scratch_path(output, "raw.tmp.jpg");
+    let ppm = opts.scratch_path(output, "raw.tmp.ppm");
+    let mut scratch = Scratch::default();
+    scratch.track(&developed);
+    scratch.track(&ppm);
+    develop_raw(input, &developed, &ppm, opts)?;
+    let result = compress_jpg(&developed, output, opts)?;
+    Ok(CompResult { algorithm: format!("RAW → {}", result.algorithm), ..result })
+}
+
+/// What a conversion's algorithm name calls the input format
+fn source_label(format: Format) -> String {
+    match format {
+        Format::Raw => "RAW".to_string(),
+        _ => format.extension().to_uppercase(),
+    }
+}
+
+/// `make` writes `dest` from `input` for `stage`, unless a run over the same
+/// input already did and left a copy in `opts.shared`
+fn shared_or_make(opts: &CompressionOptions, stage: &str, input: &str, dest: &str, make: impl FnOnce() -> Result<bool>) -> Result<bool> {
+    match opts.shared {
+        Some(ref shared) => shared.get_or_make(&format!("{}:{}", stage, input), Path::new(dest), make),
+        None => make(),
+    }
+}
+
+/// Decode `input` to `dest` losslessly with ImageMagick: the first frame or
+/// page of multi-image files (for PSD, the flattened composite), and for a
+/// JPEG `dest`, transparency flattened onto white
+fn decode_with_magick(input: &str, dest: &str, opts: &CompressionOptions) -> Result<()> {
+    let stage = format!("decode.{}", Path::new(dest).extension().and_then(|e| e.to_str()).unwrap_or(""));
+    shared_or_make(opts, &stage, input, dest, || decode_once(input, dest, opts).map(|_| true)).map(drop)
+}
+
+fn decode_once(input: &str, dest: &str, opts: &CompressionOptions) -> Result<()> {
+    opts.cancel.check()?;
+    let mut cmd = ToolCommand::new("magick");
+    match Format::from_path(input) {
+        Some(Format::Gif | Format::Tiff | Format::Psd) => cmd.arg(format!("{}[0]", input)),
+        _ => cmd.arg(input),
+    };
+    if Format::from_path(dest) == Some(Format::Jpg) {
+        cmd.arg("-background").arg("white").arg("-alpha").arg("remove").arg("-quality").arg("100");
+    }
+    cmd.arg(dest);
+    match run_tool(opts, cmd.quiet_stderr(!opts.nerd))?.success() {
+        true => Ok(()),
+        false => Err(anyhow!(t!("error-magick-read", file = input))),
+    }
+}
+
+// Any image -> PNG/JPG/WebP (comic PNG pages -> JPG too): lossless decode, then
+// that format's engine against the decoded copy
+fn compress_decoded(input: &str, output: &str, format: Format, source: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    opts.progress.on_stage(1, &format!("{} Decoding", source));
+    // WebP is encoded from a PNG decode
+    let decoded_format = if format == Format::Jpg { Format::Jpg } else { Format::Png };
+    let decoded = opts.scratch_path(output, &format!("decoded.tmp.{}", decoded_format.extension()));
+    let mut scratch = Scratch::default();
+    scratch.track(&decoded);
+    decode_with_magick(input, &decoded, opts)?;
+    let result = match format {
+        Format::Jpg => compress_jpg(&decoded, output, opts)?,
+        Format::Webp => compress_webp(&decoded, output, opts)?,
+        _ => compress_png(&decoded, output, opts)?,
+    };
+    Ok(CompResult { algorithm: format!("{} → {}", source, result.algorithm), ..result })
+}
+
+// AVIF: avifenc with Binary Search on Quality; inputs other than PNG and JPEG
+// (avifenc's own) are decoded to PNG first
+fn compress_avif(input: &str, output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    let rep = opts.reporter.as_ref();
+    let start = Instant::now();
+    let mut scratch = Scratch::default();
+    let source = match Format::from_path(input) {
+        Some(Format::Png | Format::Jpg) => input.to_string(),
+        _ => {
+            opts.progress.on_stage(1, "AVIF Decoding");
+            let decoded = opts.scratch_path(output, "decoded.tmp.png");
+            scratch.track(&decoded);
+            decode_with_magick(input, &decoded, opts)?;
+            decoded
+        },
+    };
+    let encode = |quality: u64, dest: &str| -> Result<Option<u64>> {
+        opts.cancel.check()?;
+        let mut cmd = ToolCommand::new("avifenc");
+        cmd.arg("-q").arg(quality.to_string()).arg("-s").arg(opts.effort.avif_speed().to_string());
+        if opts.metadata == MetadataMode::Strip || opts.privacy {
+            cmd.arg("--ignore-exif").arg("--ignore-xmp");
+        }
+        cmd.arg(&source).arg(dest);
+        if opts.nerd { rep.nerd_cmd(&cmd.shell_line()); }
+        let status = run_tool(opts, cmd.quiet_stderr(!opts.nerd))?;
+        Ok(status.success().then(|| get_file_size_kb(dest)))
+    };
+    let failed = || anyhow!(t!("error-tool-encode", tool = "avifenc", file = input));
+    opts.progress.on_stage(2, "AVIF Encoding");
+
+    let Some(target) = opts.target_kb else {
+        let quality = preset_quality(opts);
+        let size = encode(quality, output)?.ok_or_else(failed)?;
+        if Format::from_path(input) == Some(Format::Avif) && size >= get_file_size_kb(input) {
+            fs::copy(input, output)?;
+            opts.progress.on_fallback("Original kept (re-encoding was not smaller)");
+            return Ok(result_with_time("No compression (already optimal)", start));
+        }
+        return Ok(result_with_time(format!("AVIF (quality {})", quality), start));
+    };
+    match quality_search(output, target, 2, "avifenc.tmp.avif", opts, encode)? {
+        Some(quality) => Ok(result_with_time(format!("AVIF (quality {})", quality), start)),
+        None => {
+            let size = encode(5, output)?.ok_or_else(failed)?;
+            rep.warning(&t!("warning-quality-floor", size = size));
+            Ok(result_with_time("AVIF (quality 5, target missed)", start))
+        },
+    }
+}
+
+// HEIC kept as HEIC: decode, then heif-enc with Binary Search on Quality
+fn compress_heic(input: &str, output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    let rep = opts.reporter.as_ref();
+    let start = Instant::now();
+    let decoded = opts.scratch_path(output, "heic.tmp.jpg");
+    let mut scratch = Scratch::default();
+    scratch.track(&decoded);
+    opts.progress.on_stage(1, "HEIC Re-encoding");
+    decode_heic(input, &decoded, opts)?;
+    let encode = |quality: u64, dest: &str| -> Result<Option<u64>> {
+        opts.cancel.check()?;
+        let mut cmd = ToolCommand::new("heif-enc");
+        cmd.arg("-q").arg(quality.to_string()).arg(&decoded).arg("-o").arg(dest);
+        if opts.nerd { rep.nerd_cmd(&cmd.shell_line()); }
+        let status = run_tool(opts, cmd.quiet_stderr(!opts.nerd))?;
+        Ok(status.success().then(|| get_file_size_kb(dest)))
+    };
+    let failed = || anyhow!(t!("error-heic-encode", file = input));
+
+    let Some(target) = opts.target_kb else {
+        let quality = preset_quality(opts);
+        let size = encode(quality, output)?.ok_or_else(failed)?;
+        if size >= get_file_size_kb(input) {
+            fs::copy(input, output)?;
+            opts.progress.on_fallback("Original kept (re-encoding was not smaller)");
+            return Ok(result_with_time("No compression (already optimal)", start));
+        }
+        return Ok(result_with_time(format!("HEIC (quality {})", quality), start));
+    };
+    match quality_search(output, target, 1, "heif-enc.tmp.heic", opts, encode)? {
+        Some(quality) => Ok(result_with_time(format!("HEIC (quality {})", quality), start)),
+        None => {
+            let size = encode(5, output)?.ok_or_else(failed)?;
+            rep.warning(&t!("warning-quality-floor-heic", size = size));
+            Ok(result_with_time("HEIC (quality 5, target missed)", start))
+        },
+    }
+}
+
+/// Audio bitrate (kbit/s) in two-pass encodes
+const VIDEO_AUDIO_KBPS: u64 = 128;
+/// Video bitrate (kbit/s) below which x264 output falls apart; smaller targets are missed
+const VIDEO_MIN_KBPS: u64 = 50;
+/// Two-pass encodes rerun at a corrected bitrate when the muxed file overshoots
+const VIDEO_PASSES: u32 = 3;
+
+/// x264 CRF without a target, from `--level`
+fn video_crf(opts: &CompressionOptions) -> u64 {
+    match opts.level {
+        Some(CompressionLevel::Low) => 20,
+        Some(CompressionLevel::Medium) => 26,
+        Some(CompressionLevel::High) => 30,
+        None => 23,
+    }
+}
+
+/// Length in seconds, from ffprobe
+fn video_duration(input: &str, output: &str, opts: &CompressionOptions) -> Result<f64> {
+    let probe = opts.scratch_path(output, "ffprobe.tmp.txt");
+    let mut scratch = Scratch::default();
+    scratch.track(&probe);
+    let mut cmd = ToolCommand::new("ffprobe");
+    cmd.arg("-v").arg("error").arg("-show_entries").arg("format=duration")
+        .arg("-of").arg("default=noprint_wrappers=1:nokey=1").arg(input).stdout_to(&probe);
+    let ran = run_tool(opts, cmd.quiet_stderr(!opts.nerd))?.success();
+    fs::read_to_string(&probe).ok()
+        .and_then(|text| text.trim().parse::<f64>().ok())
+        .filter(|&seconds| ran && seconds > 0.0)
+        .ok_or_else(|| anyhow!(t!("error-duration", file = input)))
+}
+
+/// `ffmpeg -i input` set up for x264 at the effort's preset, without metadata unless kept
+fn x264_command(input: &str, opts: &CompressionOptions) -> ToolCommand {
+    let mut cmd = ToolCommand::new("ffmpeg");
+    cmd.arg("-y").arg("-v").arg("error").arg("-i").arg(input)
+        .arg("-c:v").arg("libx264").arg("-preset").arg(opts.effort.x264_preset());
+    if opts.metadata == MetadataMode::Strip {
+        cmd.arg("-map_metadata").arg("-1");
+    }
+    cmd
+}
+
+// Video: x264 at a --level CRF, or two passes at the bitrate that spends --size over the duration
+fn compress_video(input: &str, output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    let rep = opts.reporter.as_ref();
+    let start = Instant::now();
+    // MP4 and MOV get their index up front, so playback starts before the download ends
+    let faststart = !output.to_lowercase().ends_with(".mkv");
+    let encode = |cmd: &mut ToolCommand, dest: &str| -> Result<()> {
+        opts.cancel.check()?;
+        if faststart && dest != "-" {
+            cmd.arg("-movflags").arg("+faststart");
+        }
+        cmd.arg(dest);
+        if opts.nerd { rep.nerd_cmd(&cmd.shell_line()); }
+        match run_tool(opts, cmd.quiet_stderr(!opts.nerd))?.success() {
+            true => Ok(()),
+            false => Err(anyhow!(t!("error-tool-encode", tool = "ffmpeg", file = input))),
+        }
+    };
+
+    let Some(target) = opts.target_kb else {
+        let crf = video_crf(opts);
+        opts.progress.on_stage(1, "Video CRF Encoding");
+        encode(x264_command(input, opts).arg("-crf").arg(crf.to_string()).arg("-c:a").arg("aac").arg("-b:a").arg(format!("{}k", VIDEO_AUDIO_KBPS)), output)?;
+        if get_file_size_kb(output) >= get_file_size_kb(input) {
+            fs::copy(input, output)?;
+            opts.progress.on_fallback("Original kept (re-encoding was not smaller)");
+            return Ok(result_with_time("No compression (already optimal)", start));
+        }
+        return Ok(result_with_time(format!("x264 (CRF {})", crf), start));
+    };
+
+    opts.progress.on_stage(1, "Video Two-Pass Encoding");
+    let seconds = video_duration(input, output, opts)?;
+    // KB to kbit, spread over the duration, less the audio and ~3% for the container
+    let total_kbps = (target as f64 * 8.192 / seconds * 0.97) as u64;
+    let audio_kbps = VIDEO_AUDIO_KBPS.min(total_kbps / 4).max(32);
+    let mut video_kbps = total_kbps.saturating_sub(audio_kbps);
+    if video_kbps < VIDEO_MIN_KBPS {
+        rep.warning(&t!("warning-video-bitrate", target = target, seconds = format!("{:.0}", seconds), kbps = video_kbps, min = VIDEO_MIN_KBPS));
+        video_kbps = VIDEO_MIN_KBPS;
+    }
+    let passlog = opts.scratch_path(output, "x264.tmp");
+    let mut scratch = Scratch::default();
+    scratch.track(format!("{}-0.log", passlog));
+    scratch.track(format!("{}-0.log.mbtree", passlog));
+
+    for pass in 1..=VIDEO_PASSES {
+        let t0 = Instant::now();
+        let bitrate = format!("{}k", video_kbps);
+        encode(x264_command(input, opts).arg("-b:v").arg(&bitrate).arg("-pass").arg("1").arg("-passlogfile").arg(&passlog).arg("-an").arg("-f").arg("null"), "-")?;
+        encode(x264_command(input, opts).arg("-b:v").arg(&bitrate).arg("-pass").arg("2").arg("-passlogfile").arg(&passlog)
+            .arg("-c:a").arg("aac").arg("-b:a").arg(format!("{}k", audio_kbps)), output)?;
+        let size_kb = get_file_size_kb(output);
+        opts.progress.on_attempt(&Attempt {
+            stage: 1,
+            index: pass,
+            max: VIDEO_PASSES,
+            parameter: "bitrate",
+            value: video_kbps,
+            size_kb,
+            target_kb: target,
+            time_ms: t0.elapsed().as_millis(),
+        });
+        if size_kb <= target {
+            return Ok(result_with_time(format!("x264 two-pass ({} kbps)", video_kbps), start));
+        }
+        if video_kbps == VIDEO_MIN_KBPS {
+            break;
+        }
+        // Scale by the overshoot, with a little more margin each time
+        video_kbps = (video_kbps * target * 95 / (size_kb * 100)).max(VIDEO_MIN_KBPS);
+    }
+    rep.warning(&t!("warning-video-over", size = get_file_size_kb(output), target = target));
+    Ok(result_with_time(format!("x264 two-pass ({} kbps, target missed)", video_kbps), start))
+}
+
+// ICO: every frame as a PNG through oxipng; BMP frames become PNG when that's smaller
+fn compress_ico(input: &str, output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    let rep = opts.reporter.as_ref();
+    let start = Instant::now();
+    opts.progress.on_stage(1, "Icon Frame Optimization");
+    let mut frames = ico::frames(&fs::read(input)?).ok_or_else(|| anyhow!(t!("error-icon", file = input)))?;
+    let dir = staging_dir(opts)?;
+    let mut optimized = 0;
+    for (i, frame) in frames.iter_mut().enumerate() {
+        opts.cancel.check()?;
+        let png = dir.path().join(format!("{}.png", i)).to_string_lossy().to_string();
+        let out = dir.path().join(format!("out_{}.png", i)).to_string_lossy().to_string();
+        if frame.is_png() {
+            fs::write(&png, &frame.data)?;
+        } else if !tool_succeeded(opts, ToolCommand::new("magick").arg(format!("{}[{}]", input, i)).arg(&png))? {
+            continue;
+        }
+        let status = run_tool(opts, ToolCommand::new("oxipng")
+            .args(oxipng_level_args(opts)).args(oxipng_strip_args(opts)).arg("--quiet")
+            .arg("--out").arg(&out).arg(&png))?;
+        if !status.success() {
+            continue;
+        }
+        let data = fs::read(&out)?;
+        if opts.nerd {
+            let (w, h) = frame.size();
+            rep.nerd_result(&format!("{}x{}", w, h), &format!("{} -> {} bytes", frame.data.len(), data.len()), false);
+        }
+        if data.len() < frame.data.len() {
+            frame.data = data;
+            optimized += 1;
+        }
+    }
+    if optimized == 0 {
+        fs::copy(input, output)?;
+        opts.progress.on_fallback("Original kept (no frame got smaller)");
+        return Ok(result_with_time("No compression (already optimal)", start));
+    }
+    fs::write(output, ico::to_bytes(&frames))?;
+    if opts.target_kb.is_some_and(|t| get_file_size_kb(output) > t) {
+        rep.warning(&t!("warning-icon-lossless"));
+    }
+    Ok(result_with_time(format!("ICO ({} of {} frames optimized)", optimized, frames.len()), start))
+}
+
+// Image -> PDF: every page (of a TIFF scan) in order through img2pdf (lossless)
+// or ImageMagick, then the PDF engine against the bundle
+fn compress_to_pdf(input: &str, output: &str, source: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    opts.cancel.check()?;
+    opts.progress.on_stage(1, &format!("{} to PDF", source));
+    let bundle = opts.scratch_path(output, "bundle.tmp.pdf");
+    let mut scratch = Scratch::default();
+    scratch.track(&bundle);
+    let bundled = tool_succeeded(opts, ToolCommand::new("img2pdf").arg(input).arg("-o").arg(&bundle))?
+        || tool_succeeded(opts, ToolCommand::new("magick").arg(input).arg(&bundle))?;
+    if !bundled {
+        return Err(anyhow!(t!("error-to-pdf", file = input)));
+    }
+    let result = compress_pdf(&bundle, output, opts)?;
+    Ok(CompResult { algorithm: format!("{} → {}", source, result.algorithm), ..result })
+}
+
+// GIF: gifsicle -O3 -> Lossy Bisection -> Frame Dropping (last resort)
+fn compress_gif(input: &str, output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    let sink = opts.progress.as_ref();
+    let rep = opts.reporter.as_ref();
+    let start = Instant::now();
+    let original_size = get_file_size_kb(input);
+    let delays = gif::frame_delays(&fs::read(input)?).unwrap_or_default();
+    let average_delay = delays.iter().map(|&d| d as u64).sum::<u64>() / delays.len().max(1) as u64;
+    // Quality 100 is lossless; each step below adds 2 to gifsicle's --lossy.
+    // Keeping every `step`th frame stretches the delay to keep the duration.
+    let encode = |quality: u64, step: Option<u64>, dest: &str| -> Result<Option<u64>> {
+        opts.cancel.check()?;
+        let mut cmd = ToolCommand::new("gifsicle");
+        cmd.arg("-O3");
+        if quality < 100 { cmd.arg(format!("--lossy={}", (100 - quality) * 2)); }
+        if opts.metadata == MetadataMode::Strip { cmd.arg("--no-comments").arg("--no-extensions"); }
+        if let Some(step) = step.filter(|_| average_delay > 0) { cmd.arg("--delay").arg((average_delay * step).to_string()); }
+        cmd.arg(input);
+        if let Some(step) = step {
+            cmd.args((0..delays.len()).step_by(step as usize).map(|i| format!("#{}", i)));
+        }
+        cmd.arg("-o").arg(dest);
+        if opts.nerd { rep.nerd_cmd(&cmd.shell_line()); }
+        let status = run_tool(opts, cmd.quiet_stderr(!opts.nerd))?;
+        Ok(status.success().then(|| get_file_size_kb(dest)))
+    };
+    let failed = || anyhow!(t!("error-tool-read", tool = "gifsicle", file = input));
+
+    // 1. LOSSLESS (-O3)
+    sink.on_stage(1, "GIF Lossless Optimization");
+    if opts.nerd {
+        rep.nerd_stage(1, "GIF Lossless Optimization");
+        rep.nerd_result("Tool", "gifsicle -O3", false);
+    }
+    let lossless = opts.scratch_path(output, "gifsicle.tmp.gif");
+    let mut scratch = Scratch::default();
+    scratch.track(&lossless);
+    let lossless_size = encode(100, None, &lossless)?.ok_or_else(failed)?;
+
+    let Some(target) = opts.target_kb else {
+        let quality = opts.quality.unwrap_or(match opts.level {
+            Some(CompressionLevel::High) => 50,
+            Some(CompressionLevel::Medium) => 80,
+            _ => 100,
+        });
+        let size = if quality == 100 {
+            move_file(&lossless, output)?;
+            lossless_size
+        } else {
+            encode(quality, None, output)?.ok_or_else(failed)?
+        };
+        if size >= original_size {
+            fs::copy(input, output)?;
+            sink.on_fallback("Original kept (already optimal)");
+            return Ok(result_with_time("No compression (already optimal)", start));
+        }
+        return Ok(result_with_time(gif_algorithm(quality), start));
+    };
+    if lossless_size <= target {
+        move_file(&lossless, output)?;
+        return Ok(result_with_time(gif_algorithm(100), start));
+    }
+    if !opts.runs(Stage::Lossy) {
+        move_file(&lossless, output)?;
+        rep.warning(&t!("warning-lossless-only", size = lossless_size));
+        return Ok(result_with_time(format!("{} (target missed)", gif_algorithm(100)), start));
+    }
+
+    // 2. LOSSY BISECTION (--lossy)
+    sink.on_stage(2, "GIF Lossy Compression");
+    if opts.nerd { rep.nerd_stage(2, "GIF Lossy Compression"); }
+    if let Some(quality) = quality_search(output, target, 2, "lossy.tmp.gif", opts, |q, dest| encode(q, None, dest))? {
+        return Ok(result_with_time(gif_algorithm(quality), start));
+    }
+
+    // 3. FRAME DROPPING, at the lowest quality
+    if delays.len() >= 4 {
+        sink.on_stage(3, "Frame Dropping");
+        if opts.nerd { rep.nerd_stage(3, "Frame Dropping"); }
+        let dropped = opts.scratch_path(output, "frames.tmp.gif");
+        scratch.track(&dropped);
+        for step in 2..=4u64 {
+            let Some(size_kb) = encode(5, Some(step), &dropped)? else { break };
+            if size_kb > target {
+                continue;
+            }
+            if decide(opts, Decision::DropFrames { step, size_kb })? {
+                move_file(&dropped, output)?;
+                sink.on_fallback(&format!("Frames dropped (1 in {} kept)", step));
+                return Ok(result_with_time(format!("{} (1 in {} frames)", gif_algorithm(5), step), start));
+            }
+            break;
+        }
+    }
+
+    let size = encode(5, None, output)?.ok_or_else(failed)?;
+    rep.warning(&t!("warning-gif-floor", size = size));
+    Ok(result_with_time(format!("{} (target missed)", gif_algorithm(5)), start))
+}
+
+/// Decimals kept in SVG numbers when no target is given
+fn preset_precision(opts: &CompressionOptions) -> usize {
+    match opts.level {
+        Some(CompressionLevel::Low) => 5,
+        Some(CompressionLevel::High) => 2,
+        Some(CompressionLevel::Medium) | None => 3,
+    }
+}
+
+// SVG: in-process minify, Binary Search on number precision; gzipped for .svgz
+fn compress_svg(input: &str, output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    let (sink, rep) = (opts.progress.as_ref(), opts.reporter.as_ref());
+    let start = Instant::now();
+    opts.cancel.check()?;
+    let source = svg::read(Path::new(input))?;
+    let keep_metadata = opts.metadata == MetadataMode::Keep;
+    let gzip = output.to_lowercase().ends_with(".svgz");
+    let describe = |precision: usize| format!("SVG minify (precision {}){}", precision, if gzip { " + gzip" } else { "" });
+    sink.on_stage(1, "SVG Minification");
+    if opts.nerd {
+        rep.nerd_stage(1, "SVG Minification");
+        rep.nerd_result("Tool", "built-in (no svgo)", false);
+        rep.nerd_result("Strategy", "Drop comments and editor state, round numbers", !gzip);
+        if gzip {
+            rep.nerd_result("Output", "gzip (.svgz)", true);
+        }
+    }
+
+    let Some(target) = opts.target_kb else {
+        let precision = preset_precision(opts);
+        svg::write(Path::new(output), &svg::minify(&source, Some(precision), keep_metadata))?;
+        return Ok(result_with_time(describe(precision), start));
+    };
+
+    let probe = opts.scratch_path(output, &format!("precision.tmp.{}", if gzip { "svgz" } else { "svg" }));
+    let mut scratch = Scratch::default();
+    scratch.track(&probe);
+    let max_precision = search_ceiling(opts, 1, "precision", 1, 5);
+    let max_probes = opts.effort.image_probes();
+    let search = search::binary(1, max_precision, opts.search_window(target), max_probes, |attempt, precision| {
+        opts.cancel.check()?;
+        let t0 = Instant::now();
+        svg::write(Path::new(&probe), &svg::minify(&source, Some(precision as usize), keep_metadata))?;
+        let size_kb = get_file_size_kb(&probe);
+        sink.on_attempt(&Attempt {
+            stage: 1,
+            index: attempt,
+            max: max_probes,
+            parameter: "precision",
+            value: precision,
+            size_kb,
+            target_kb: target,
+            time_ms: t0.elapsed().as_millis(),
+        });
+        // More decimals only ever add bytes, so each hit replaces the output
+        if size_kb <= target {
+            move_file(&probe, output)?;
+        }
+        Ok(Some(size_kb))
+    })?;
+    match search.best {
+        Some((precision, _)) => Ok(result_with_time(describe(precision as usize), start)),
+        None => {
+            svg::write(Path::new(output), &svg::minify(&source, Some(1), keep_metadata))?;
+            rep.warning(&t!("warning-svg-floor", size = get_file_size_kb(output)));
+            sink.on_fallback("No precision hit the target, 1 decimal used");
+            Ok(result_with_time(describe(1), start))
+        },
+    }
+}
+
+/// The entries the engines run over: an Office package's media images, a
+/// comic's pages, or any archive member crnch can write back under its name
+fn members_of(format: Format, entries: &[zip::Entry]) -> Vec<usize> {
+    let wanted = |entry: &zip::Entry| match (format, Format::from_path(&entry.name)) {
+        (Format::Office, _) => office::is_media(&entry.name),
+        (Format::Comic, Some(page)) => matches!(page, Format::Jpg | Format::Png | Format::Webp | Format::Gif),
+        (_, Some(member)) => !matches!(member, Format::Bmp | Format::Raw | Format::Tiff | Format::Psd),
+        (_, None) => false,
+    };
+    (0..entries.len()).filter(|&i| !entries[i].is_dir() && wanted(&entries[i])).collect()
+}
+
+/// A RAR comic's files as entries sorted by path, through unrar or bsdtar (libarchive)
+fn extract_rar(input: &str, opts: &CompressionOptions) -> Result<Vec<zip::Entry>> {
+    let dir = staging_dir(opts)?;
+    let dest = dir.path().to_string_lossy().to_string();
+    let extracted = tool_succeeded(opts, ToolCommand::new("unrar").arg("x").arg("-o+").arg("-inul").arg(input).arg(format!("{}/", dest)))?
+        || tool_succeeded(opts, ToolCommand::new("bsdtar").arg("-xf").arg(input).arg("-C").arg(&dest))?;
+    if !extracted {
+        return Err(anyhow!(t!("error-cbr-unpack", file = input)));
+    }
+    let mut entries = Vec::new();
+    let mut pending = vec![dir.path().to_path_buf()];
+    while let Some(folder) = pending.pop() {
+        for item in fs::read_dir(&folder)?.flatten() {
+            let path = item.path();
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let name = path.strip_prefix(dir.path())?.components()
+                .map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+            entries.push(zip::Entry::new(name, fs::read(&path)?));
+        }
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+/// Member `i` written into `dir` under its own extension (`.svgz` stays gzipped)
+fn staged_member(dir: &tempfile::TempDir, i: usize, entry: &zip::Entry) -> Result<String> {
+    let extension = Path::new(&entry.name).extension().and_then(|e| e.to_str()).unwrap_or("bin").to_lowercase();
+    let path = dir.path().join(format!("{}.{}", i, extension)).to_string_lossy().to_string();
+    fs::write(&path, &entry.data)?;
+    Ok(path)
+}
+
+/// Zip `entries` to `output`; archives take zstd when asked, Office packages never
+fn write_zip(output: &str, format: Format, entries: &[zip::Entry], opts: &CompressionOptions) -> Result<()> {
+    if format == Format::Office || !opts.zip_zstd {
+        return zip::write(Path::new(output), entries);
+    }
+    let dir = staging_dir(opts)?;
+    let data = zip::to_bytes_with(entries, |entry| {
+        if zip::is_compressed_image(&entry.name) || entry.data.is_empty() {
+            return Ok((zip::STORED, entry.data.clone()));
+        }
+        opts.cancel.check()?;
+        let (plain, packed) = (dir.path().join("member"), dir.path().join("member.zst"));
+        fs::write(&plain, &entry.data)?;
+        let mut cmd = ToolCommand::new("zstd");
+        cmd.arg("-19").arg("-q").arg("-f").arg(plain.to_string_lossy()).arg("-o").arg(packed.to_string_lossy());
+        if !run_tool(opts, cmd.quiet_stderr(!opts.nerd))?.success() {
+            return Err(anyhow!(t!("error-zstd", file = entry.name)));
+        }
+        Ok((zip::ZSTD, fs::read(&packed)?))
+    })?;
+    Ok(fs::write(output, data)?)
+}
+
+// Zip archives and Office (.docx/.pptx) packages: the engines over each
+// member they support (an Office package's images), the target split
+// between them by size after the rest of the archive
+fn compress_zip(input: &str, output: &str, format: Format, opts: &CompressionOptions) -> Result<CompResult> {
+    let (sink, rep) = (opts.progress.as_ref(), opts.reporter.as_ref());
+    let start = Instant::now();
+    if format == Format::Comic && output.to_lowercase().ends_with(".cbr") {
+        return Err(anyhow!(t!("error-cbr-write")));
+    }
+    let mut entries = match fs::read(input)?.starts_with(b"Rar!") {
+        true => extract_rar(input, opts)?,
+        false => zip::read(Path::new(input))?,
+    };
+    let members = members_of(format, &entries);
+    let stage = match format {
+        Format::Office => "Embedded Image Recompression",
+        Format::Comic => "Page Recompression",
+        _ => "Member Recompression",
+    };
+    sink.on_stage(1, stage);
+    if opts.nerd {
+        rep.nerd_stage(1, stage);
+        rep.nerd_result("Files", &members.len().to_string(), false);
+    }
+
+    // Everything else is kept as is; what it takes zipped, with the members'
+    // own headers, comes off the target first
+    let member_bytes: u64 = members.iter().map(|&i| entries[i].data.len() as u64).sum();
+    let budget_kb = match opts.target_kb {
+        Some(target) => {
+            let rest: Vec<zip::Entry> = entries.iter().enumerate()
+                .map(|(i, e)| if members.contains(&i) { zip::Entry { data: Vec::new(), ..e.clone() } } else { e.clone() })
+                .collect();
+            let rest_kb = (zip::to_bytes(&rest)?.len() as u64).div_ceil(1024);
+            if rest_kb >= target {
+                rep.warning(&t!("warning-archive-rest", size = rest_kb));
+            }
+            Some(target.saturating_sub(rest_kb).max(1))
+        },
+        None => None,
+    };
+
+    let dir = staging_dir(opts)?;
+    let mut member_opts = opts.clone();
+    member_opts.progress = Arc::new(NoProgress);
+    member_opts.privacy = false;
+    member_opts.under_target = UnderTarget::Skip;
+    member_opts.io_throttle = None;
+    member_opts.temp_dir = Some(dir.path().to_path_buf());
+    let mut recompressed = 0;
+    for &i in &members {
+        opts.cancel.check()?;
+        let member_in = staged_member(&dir, i, &entries[i])?;
+        let entry = &entries[i];
+        // PNG pages written as JPEG with --comic-pages jpg
+        let to_jpg = format == Format::Comic && opts.comic_pages == ComicPages::Jpg && Format::from_path(&entry.name) == Some(Format::Png);
+        let mut member_out = dir.path().join(format!("out_{}", Path::new(&member_in).file_name().unwrap_or_default().to_string_lossy()));
+        if to_jpg {
+            member_out.set_extension("jpg");
+        }
+        let member_out = member_out.to_string_lossy().to_string();
+        // Each member gets the share of the budget its size is of all of them
+        member_opts.target_kb = budget_kb.map(|b| (b * entry.data.len() as u64 / member_bytes.max(1)).max(1));
+        let run = match to_jpg {
+            true => compress_decoded(&member_in, &member_out, Format::Jpg, "PNG", &member_opts),
+            false => compress_file(&member_in, &member_out, &member_opts),
+        };
+        match run {
+            Ok(result) if !result.skipped => {
+                let data = fs::read(&member_out)?;
+                if opts.nerd {
+                    rep.nerd_result(&entry.name, &format!("{} -> {} KB ({})", entry.data.len() / 1024, data.len() / 1024, result.algorithm), false);
+                }
+                if data.len() < entry.data.len() {
+                    if to_jpg {
+                        entries[i].name = Path::new(&entry.name).with_extension("jpg").to_string_lossy().replace('\\', "/");
+                    }
+                    entries[i].data = data;
+                    recompressed += 1;
+                }
+            },
+            Ok(_) => {},
+            Err(e) if e.downcast_ref::<Cancelled>().is_some() => return Err(e),
+            Err(e) => rep.warning(&t!("warning-entry-kept", entry = entry.name, error = format!("{:#}", e))),
+        }
+    }
+
+    write_zip(output, format, &entries, opts)?;
+    if opts.target_kb.is_some_and(|t| get_file_size_kb(output) > t) {
+        sink.on_fallback("Members at their smallest, target missed");
+    }
+    Ok(result_with_time(match format {
+        Format::Office => format!("Office images ({} of {} recompressed)", recompressed, members.len()),
+        Format::Comic => format!("Comic ({} of {} pages recompressed)", recompressed, members.len()),
+        _ if opts.zip_zstd => format!("ZIP, zstd ({} of {} members recompressed)", recompressed, members.len()),
+        _ => format!("ZIP ({} of {} members recompressed)", recompressed, members.len()),
+    }, start))
+}
+
+fn gif_algorithm(quality: u64) -> String {
+    match quality {
+        100 => "gifsicle -O3 (Lossless)".to_string(),
+        q => format!("gifsicle -O3 --lossy={}", (100 - q) * 2),
+    }
+}
+
+// PDF: the pipeline below, then nerd mode's page report
+fn compress_pdf(input: &str, output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    // Bilevel pages go to Group 4 before the text layer, which rendering would drop
+    let (scanned, searchable) = (opts.scratch_path(output, "scan.tmp.pdf"), opts.scratch_path(output, "ocr.tmp.pdf"));
+    let run = || -> Result<CompResult> {
+        let mut source = input;
+        if opts.pdf_scan_mode && scan::encode_bilevel_pages(source, &scanned, opts)? {
+            source = &scanned;
+        }
+        if opts.pdf_ocr {
+            add_text_layer(source, &searchable, opts)?;
+            source = &searchable;
+        }
+        compress_pdf_checked(source, output, opts)
+    };
+    let result = run();
+    let _ = fs::remove_file(&scanned);
+    let _ = fs::remove_file(&searchable);
+    let result = result?;
+    if opts.nerd {
+        report_pages(input, output, opts);
+    }
+    Ok(result)
+}
+
+/// `--ocr`: ocrmypdf's text layer over the scanned pages, at their own
+/// resolution; pages that already have text are left alone
+fn add_text_layer(input: &str, dest: &str, opts: &CompressionOptions) -> Result<()> {
+    opts.cancel.check()?;
+    if pdf_encrypted(input) {
+        return Err(anyhow!(t!("error-ocr-encrypted", file = input)));
+    }
+    let mut cmd = ToolCommand::new("ocrmypdf");
+    cmd.arg("--skip-text").arg("--optimize").arg("0").arg("--output-type").arg("pdf");
+    if !opts.nerd { cmd.arg("--quiet"); }
+    cmd.arg(input).arg(dest).quiet_stderr(!opts.nerd);
+    if opts.nerd { opts.reporter.nerd_cmd(&cmd.shell_line()); }
+    if !run_tool(opts, &cmd)?.success() {
+        return Err(anyhow!(t!("error-ocr", file = input)));
+    }
+    Ok(())
+}
+
+/// The pipeline, run again with every annotation kept when the first pass
+/// drops bookmarks, links or form fields
+fn compress_pdf_checked(input: &str, output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    let mut result = compress_pdf_pipeline(input, output, opts)?;
+    if let Some(lost) = lost_interactive(input, output, opts) {
+        let rep = opts.reporter.as_ref();
+        if opts.pdf_keep_interactive {
+            rep.warning(&t!("warning-pdf-dropped", lost = lost.join(", ")));
+        } else {
+            rep.warning(&t!("warning-pdf-dropped-retry", lost = lost.join(", ")));
+            let mut safe = opts.clone();
+            safe.pdf_keep_interactive = true;
+            result = compress_pdf_pipeline(input, output, &safe)?;
+            if let Some(lost) = lost_interactive(input, output, &safe) {
+                rep.warning(&t!("warning-pdf-still-dropped", lost = lost.join(", ")));
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Bookmarks, links and form fields the input has and the output doesn't;
+/// `None` when nothing's missing, either can't be read (with a warning), or
+/// --pages left some out on purpose
+fn lost_interactive(input: &str, output: &str, opts: &CompressionOptions) -> Option<Vec<String>> {
+    if opts.pdf_mode == PdfMode::Quick || opts.pdf_pages.is_some() {
+        return None;
+    }
+    let count = |path: &str| fs::read(path).ok().and_then(|data| pdf_interactive::count(&data));
+    let (Some(before), Some(after)) = (count(input), count(output)) else {
+        opts.reporter.warning(&t!("warning-pdf-unreadable"));
+        return None;
+    };
+    let lost = before.lost(&after);
+    (!lost.is_empty()).then_some(lost)
+}
+
+/// Nerd mode: which pages the bytes are in, before and after
+fn report_pages(input: &str, output: &str, opts: &CompressionOptions) {
+    let sizes = |path: &str| fs::read(path).ok().and_then(|data| pdf_pages::page_sizes(&data));
+    match sizes(input) {
+        Some(before) => opts.reporter.nerd_pages(&before, sizes(output).as_ref()),
+        None => opts.reporter.nerd_result("Pages", "no per-page breakdown (object streams or encryption)", true),
+    }
+}
+
+// PDF: Binary Search (Optimal) with Floor Detection
+fn compress_pdf_pipeline(input: &str, output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    let (target_kb, nerd) = (opts.target_kb, opts.nerd);
+    let sink = opts.progress.as_ref();
+    let rep = opts.reporter.as_ref();
+    let total_start = Instant::now();
+    let original_size = get_file_size_kb(input);
+    let mut _gs_calls: u32 = 0;
+
+    if opts.pdf_mode == PdfMode::Quick {
+        let needs_gs = [
+            (opts.pdf_pages.is_some(), "--pages"),
+            (opts.pdf_images == PdfImages::Remove, "--remove-images"),
+            (opts.pdf_images == PdfImages::Grayscale, "--images-grayscale"),
+            (opts.pdf_scrub_metadata, "--scrub-metadata"),
+            (opts.pdf_ocr, "--ocr"),
+            (opts.pdf_scan_mode, "--scan-mode"),
+        ];
+        if let Some((_, flag)) = needs_gs.iter().find(|(set, _)| *set) {
+            return Err(anyhow!(t!("error-quick-needs-gs", flag = flag)));
+        }
+        return compress_pdf_quick(input, output, opts);
+    }
+
+    // Every Ghostscript pass re-renders images, so there's nothing lossless to fall back on
+    if !opts.runs(Stage::Lossy) {
+        rep.message(&t!("pdf-lossy-only"));
+        sink.on_fallback("Lossy stage skipped, original kept");
+        fs::copy(input, output)?;
+        return Ok(result_with_time("No compression (lossy stage skipped)", total_start));
+    }
+
+    // --dpi as given, or the DPI --quality maps to: one pass, no search
+    let fixed = match (opts.pdf_dpi, opts.quality) {
+        (Some(dpi), _) => Some((dpi, format!("Ghostscript {} DPI", dpi))),
+        (None, Some(quality)) if target_kb.is_none() => Some((quality_dpi(quality), format!("Ghostscript {} DPI (quality {})", quality_dpi(quality), quality))),
+        _ => None,
+    };
+    if let Some((dpi, algorithm)) = fixed {
+        opts.cancel.check()?;
+        let stage = if opts.pdf_dpi.is_some() { "Fixed DPI" } else { "Fixed Quality" };
+        sink.on_stage(1, stage);
+        if nerd {
+            rep.nerd_stage(1, stage);
+            rep.nerd_result("Tool", "Ghostscript", false);
+            rep.nerd_result("Strategy", &format!("Images downsampled to {}", algorithm.trim_start_matches("Ghostscript ")), false);
+        }
+        let progress = rep.progress(1, &t!("progress-eating"));
+        run_gs(input, output, "/printer", Some(dpi), opts)?;
+        progress.finish();
+        if nerd {
+            rep.nerd_output_summary(input, output, original_size, get_file_size_kb(output), &algorithm, total_start.elapsed().as_secs_f64());
+        }
+        return Ok(result_with_time(algorithm, total_start));
+    }
+
+    if target_kb.is_none() {
+        // Smart preset selection based on file size, unless one is configured
+        let preset = if let Some(preset) = opts.pdf_preset {
+            preset.gs_setting()
+        } else if original_size > 50_000 {
+            // Large files (>50MB): aggressive compression
+            "/ebook"
+        } else if original_size > 10_000 {
+            // Medium files (10-50MB): balanced compression
+            "/ebook"
+        } else if original_size > 1_000 {
+            // Small-medium files (1-10MB): moderate compression
+            "/printer"
+        } else {
+            // Small files (<1MB): light compression
+            "/printer"
+        };
+        
+        opts.cancel.check()?;
+        
+        sink.on_stage(1, "Smart Compression");
+        if nerd {
+            rep.nerd_stage(1, "Smart Compression");
+            rep.nerd_result("Tool", "Ghostscript", false);
+            rep.nerd_result("Strategy", &format!("Preset-based compression ({})", preset), false);
+            let reason = match opts.pdf_preset {
+                Some(_) => "Configured preset".to_string(),
+                None => format!("Selected {} for {} KB file", preset, original_size),
+            };
+            rep.nerd_result("Reason", &reason, false);
+        }
+        let progress = rep.progress(1, &t!("progress-eating"));
+        run_gs(input, output, preset, None, opts)?;
+        progress.finish();
+        if nerd {
             let total_time = total_start.elapsed().as_secs_f64();
             let final_size = get_file_size_kb(output);
-            logger::nerd_output_summary(input, output, original_size, final_size, &format!("Smart Compression ({})", preset), total_time);
+            rep.nerd_output_summary(input, output, original_size, final_size, &format!("Smart Compression ({})", preset), total_time);
         }
         return Ok(result_with_time(format!("Smart Compression ({})", preset), total_start));
     }
 
     let target = target_kb.unwrap();
-    let temp_output = format!("{}.tmp", output);
+    let temp_output = opts.scratch_path(output, "tmp");
 
     // Stage 1: Floor Detection
     let mut floor_size = 0;
     let mut floor_checked = false;
+    opts.cancel.check()?;
+    sink.on_stage(1, "Floor Detection");
     if nerd {
-        logger::nerd_stage(1, "Floor Detection");
-        logger::nerd_result("Tool", "Ghostscript", false);
-        logger::nerd_result("Strategy", "PDF minimum size calculation using /screen preset", false);
+        rep.nerd_stage(1, "Floor Detection");
+        rep.nerd_result("Tool", "Ghostscript", false);
+        rep.nerd_result("Strategy", "PDF minimum size calculation using /screen preset", false);
+    }
+    // A locked PDF fails every pass the same way, so say why now
+    let floor = run_gs(input, &temp_output, "/screen", None, opts);
+    if floor.is_err() && pdf_encrypted(input) {
+        return Err(gs_failed(input, opts));
     }
-    if run_gs(input, &temp_output, "/screen", None).is_ok() {
+    if floor.is_ok() {
         _gs_calls += 1;
         floor_size = get_file_size_kb(&temp_output);
         floor_checked = true;
         if nerd {
             if floor_size > target {
-                logger::nerd_result("Status", "Floor > Target (cannot be compressed to the desired target)", true);
+                rep.nerd_result("Status", "Floor > Target (cannot be compressed to the desired target)", true);
             } else {
-                logger::nerd_result("Status", "Floor < Target (size reduction possible)", true);
+                rep.nerd_result("Status", "Floor < Target (size reduction possible)", true);
             }
         }
     }
 
     if floor_checked && floor_size > target {
-        let progress = PacmanProgress::new(1, "Floor > Target");
-        progress.finish_with_message("Floor > Target");
+        let progress = rep.progress(1, &t!("progress-floor-above-target"));
+        progress.finish_with_message(&t!("progress-floor-above-target"));
         if nerd {
-            println!("\n{}", "WARNING: Target Below Minimum!".yellow().bold());
-            println!("   Smallest possible: {} KB", floor_size.to_string().cyan());
-            println!("   Your target: {} KB", target.to_string().red());
-            println!("   Best possible output near target is: {} KB", floor_size.to_string().green());
-            println!("WARNING: Could not reach target size without destroying quality.");
-        }
-        let should_save_floor = if auto_yes {
-            if nerd { println!("   [Auto-yes enabled, saving smallest possible version]"); }
-            true
-        } else {
-            Confirm::new().with_prompt("   Save the smallest possible version?").default(true).interact()?
-        };
+            rep.warning(&t!("warning-below-minimum"));
+            rep.message(&format!("   {}", t!("floor-smallest", size = floor_size)));
+            rep.message(&format!("   {}", t!("floor-target", size = target)));
+            rep.message(&format!("   {}", t!("floor-best", size = floor_size)));
+            rep.warning(&t!("warning-quality-limit"));
+        }
+        if let Some(dpi) = opts.min_dpi {
+            let _ = fs::remove_file(&temp_output);
+            return pdf_at_dpi_floor(input, output, dpi, opts, total_start);
+        }
+        if let Some(result) = rasterize_fallback(input, output, target, opts)? {
+            let _ = fs::remove_file(&temp_output);
+            return Ok(result);
+        }
+        let should_save_floor = decide(opts, Decision::SaveFloor)?;
         if !should_save_floor {
             let _ = fs::remove_file(&temp_output);
-            return Err(anyhow!("Compression cancelled."));
+            return Err(anyhow!(t!("error-compression-cancelled")));
         }
-        fs::rename(&temp_output, output)?;
+        sink.on_fallback("Floor /screen output kept (target below minimum)");
+        move_file(&temp_output, output)?;
         if nerd {
             let total_time = total_start.elapsed().as_secs_f64();
             let final_size = get_file_size_kb(output);
-            logger::nerd_output_summary(input, output, original_size, final_size, "Floor (Min Quality)", total_time);
+            rep.nerd_output_summary(input, output, original_size, final_size, "Floor (Min Quality)", total_time);
         }
-        println!("Tip: Could not reach target size without destroying quality.\n   Try a higher size.");
+        rep.message(&format!("{}\n   {}", t!("tip-quality-limit"), t!("tip-quality-limit-higher")));
         return Ok(result_with_time("Floor (Min Quality)", total_start));
     }
     
     // Smart DPI range based on compression ratio
     let compression_ratio = original_size as f64 / target as f64;
-    let (mut min_dpi, mut max_dpi): (u64, u64) = match compression_ratio {
+    let (min_dpi, max_dpi): (u64, u64) = match compression_ratio {
         r if r > 10.0 => (50, 150),   // Extreme compression
         r if r > 3.0  => (72, 250),   // Heavy compression
         r if r > 2.0  => (100, 400),  // Moderate compression
         _             => (150, 600),  // Light compression
     };
+    let (min_dpi, max_dpi) = match opts.min_dpi {
+        Some(floor) => (min_dpi.max(floor), max_dpi.max(floor)),
+        None => (min_dpi, max_dpi),
+    };
+    
+    opts.cancel.check()?;
     
+    sink.on_stage(2, "Size Reduction");
     if nerd {
-        logger::nerd_stage(2, "Size Reduction");
-        logger::nerd_result("Tool", "Ghostscript", false);
-        logger::nerd_result("Strategy", "PDF compression using Binary search with adaptive DPI range", false);
-        logger::nerd_result("Complexity", "O(log n) search iterations, O(n) compression per attempt", false);
-        logger::nerd_cmd("gs ... -dColorImageResolution=<dpi> ...");
-        logger::nerd_result(
+        rep.nerd_stage(2, "Size Reduction");
+        rep.nerd_result("Tool", "Ghostscript", false);
+        rep.nerd_result("Strategy", "PDF compression using Binary search with adaptive DPI range", false);
+        rep.nerd_result("Complexity", "O(log n) search iterations, O(n) compression per attempt", false);
+        rep.nerd_cmd("gs ... -dColorImageResolution=<dpi> ...");
+        rep.nerd_result(
             "Smart DPI Range", 
             &format!("{}-{} DPI (ratio: {:.1}:1)", min_dpi, max_dpi, compression_ratio),
             false
         );
-        logger::nerd_result("Note", "Each iteration re-renders entire PDF (3-6s per attempt is normal)", false);
-    }
-    let mut best_dpi: u64 = 0;
-    let mut best_size: u64 = 0;
-    let mut found_valid = false;
-    let max_iterations: u32 = 14;
-    let mut attempts: u32 = 0;
-    let mut search_progress = PacmanProgress::new(14, "Eating those bytes...");
-    while min_dpi <= max_dpi && attempts < max_iterations {
-        attempts += 1;
-        let mid_dpi = (min_dpi + max_dpi) / 2;
-        if nerd && attempts == 1 {
-            logger::nerd_search_range(min_dpi, max_dpi, mid_dpi);
+        rep.nerd_result("Note", "Each iteration re-renders entire PDF (3-6s per attempt is normal)", false);
+    }
+    // Each probe gets its own scratch file so higher efforts can run them concurrently
+    let max_iterations = opts.effort.pdf_probes();
+    let search_progress = Mutex::new(rep.progress(max_iterations as u64, &t!("progress-eating")));
+    let probe_files = Mutex::new(Scratch::default());
+    let probe_path = |dpi: u64| opts.scratch_path(output, &format!("dpi{}.tmp.pdf", dpi));
+    let max_dpi = search_ceiling(opts, 2, "dpi", min_dpi, max_dpi);
+    let search = opts.effort.strategy().run(min_dpi, max_dpi, opts.search_window(target), max_iterations, |attempt, mid_dpi| {
+        opts.cancel.check()?;
+        if nerd && attempt == 1 {
+            rep.nerd_search_range(min_dpi, max_dpi, mid_dpi);
+        }
+        let iter_start = Instant::now();
+        let probe_out = probe_path(mid_dpi);
+        probe_files.lock().unwrap().track(&probe_out);
+        if run_gs(input, &probe_out, "/printer", Some(mid_dpi), opts).is_err() {
+            return Ok(None);
+        }
+        let size = get_file_size_kb(&probe_out);
+        search_progress.lock().unwrap().set(attempt as u64 + 1);
+        sink.on_attempt(&Attempt {
+            stage: 2,
+            index: attempt,
+            max: max_iterations,
+            parameter: "dpi",
+            value: mid_dpi,
+            size_kb: size,
+            target_kb: target,
+            time_ms: iter_start.elapsed().as_millis(),
+        });
+        let action_str = if size <= target { "min=mid+1" } else { "max=mid-1" };
+        if nerd {
+            rep.nerd_attempt(attempt, max_iterations, mid_dpi, size, target, iter_start.elapsed().as_millis(), action_str);
+        }
+        if size > target {
+            let _ = fs::remove_file(&probe_out);
+        }
+        Ok(Some(size))
+    })?;
+    if let Some((best_dpi, _)) = search.best {
+        move_file(probe_path(best_dpi), output)?;
+    }
+    drop(probe_files);
+    let _ = fs::remove_file(&temp_output);
+    search_progress.into_inner().unwrap().finish();
+    
+    if let Some((best_dpi, best_size)) = search.best {
+        if nerd {
+            rep.message("");
+            rep.message(&format!("  └─ {}", t!("nerd-dpi-achieved", dpi = best_dpi, size = best_size)));
+            rep.message(&format!("     {}", t!("nerd-dpi-final", dpi = best_dpi)));
+            rep.message("");
+            let total_time = total_start.elapsed().as_secs_f64();
+            rep.nerd_output_summary(input, output, original_size, best_size, &format!("Ghostscript Binary Search ({} DPI)", best_dpi), total_time);
+        } else if best_dpi < 50 {
+            rep.warning(&t!("warning-low-dpi"));
+        }
+        Ok(result_with_time(format!("Binary Search ({} DPI)", best_dpi), total_start))
+    } else {
+        if let Some(dpi) = opts.min_dpi {
+            return pdf_at_dpi_floor(input, output, dpi, opts, total_start);
+        }
+        if let Some(result) = rasterize_fallback(input, output, target, opts)? {
+            return Ok(result);
+        }
+        sink.on_fallback("No DPI hit the target, /screen preset used");
+        run_gs(input, output, "/screen", None, opts)?;
+        Ok(result_with_time("Fallback /screen", total_start))
+    }
+}
+
+/// Nothing under the target stays above `min_dpi`: keep the floor and miss the target
+fn pdf_at_dpi_floor(input: &str, output: &str, dpi: u64, opts: &CompressionOptions, start: Instant) -> Result<CompResult> {
+    run_gs(input, output, "/printer", Some(dpi), opts)?;
+    opts.progress.on_fallback(&format!("DPI floor {} kept (target missed)", dpi));
+    Ok(result_with_time(format!("Ghostscript {} DPI (floor, target missed)", dpi), start))
+}
+
+// PDF quick mode: lossless restructuring, no Ghostscript
+fn compress_pdf_quick(input: &str, output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    let (sink, rep) = (opts.progress.as_ref(), opts.reporter.as_ref());
+    let start = Instant::now();
+    opts.cancel.check()?;
+    sink.on_stage(1, "Quick Optimization");
+    if opts.nerd {
+        rep.nerd_stage(1, "Quick Optimization");
+        rep.nerd_result("Tool", "built-in (no Ghostscript)", false);
+        rep.nerd_result("Strategy", "Drop unused objects, merge duplicates, re-deflate streams", false);
+    }
+    let Some(stats) = pdf_quick::optimize(Path::new(input), Path::new(output))? else {
+        rep.warning(&t!("warning-quick-unsupported"));
+        sink.on_fallback("Unsupported structure, original kept");
+        fs::copy(input, output)?;
+        return Ok(result_with_time("Quick (unchanged)", start));
+    };
+    if opts.nerd {
+        rep.nerd_result("Objects", &format!("{} -> {}", stats.objects_before, stats.objects_after), false);
+        rep.nerd_result("Streams", &format!("{} recompressed", stats.streams_recompressed), true);
+    }
+    if opts.target_kb.is_some_and(|t| get_file_size_kb(output) > t) {
+        rep.message(&t!("quick-lossless-only"));
+    }
+    Ok(result_with_time("Quick (stream recompression)", start))
+}
+
+/// DPI range tried when rasterizing pages
+const RASTER_DPI: (u64, u64) = (36, 150);
+/// 1-bit pages need more dots to stay legible, and Group 4 makes them cheap
+const BILEVEL_DPI: (u64, u64) = (100, 300);
+
+/// Last resort for PDFs whose size is vector content that downsampling can't
+/// touch: render each page to a JPEG (1-bit Group 4 fax with `--bw`) at the
+/// highest DPI that fits the target. `None` when no DPI fits or the user declines.
+fn rasterize_fallback(input: &str, output: &str, target: u64, opts: &CompressionOptions) -> Result<Option<CompResult>> {
+    if !opts.runs(Stage::Lossy) {
+        return Ok(None);
+    }
+    let (nerd, sink, rep) = (opts.nerd, opts.progress.as_ref(), opts.reporter.as_ref());
+    let start = Instant::now();
+    let raster_out = opts.scratch_path(output, "raster.tmp.pdf");
+    let best_out = opts.scratch_path(output, "raster-best.tmp.pdf");
+    let bilevel = opts.bw.is_some();
+    let (min_dpi, max_dpi) = if bilevel { BILEVEL_DPI } else { RASTER_DPI };
+    let max_dpi = search_ceiling(opts, 3, "dpi", min_dpi, max_dpi);
+    let max_iterations = opts.effort.image_probes();
+
+    opts.cancel.check()?;
+    sink.on_stage(3, "Rasterize Pages");
+    if nerd {
+        rep.nerd_stage(3, "Rasterize Pages");
+        if bilevel {
+            rep.nerd_result("Tool", "ImageMagick (Ghostscript delegate)", false);
+            rep.nerd_result("Strategy", "Render pages to 1-bit Group 4 images, binary search on DPI", false);
+            rep.nerd_cmd(&format!("magick -density <dpi> <in> {} -compress Group4 <out>", desaturate_args(opts).join(" ")));
+        } else {
+            rep.nerd_result("Tool", "Ghostscript (pdfimage24)", false);
+            rep.nerd_result("Strategy", "Render pages to JPEG images, binary search on DPI", false);
+            rep.nerd_cmd("gs -sDEVICE=pdfimage24 -r<dpi> -sCompression=JPEG ...");
         }
+    }
+    let mut progress = rep.progress(max_iterations as u64, &t!("progress-page-images"));
+    let search = search::binary(min_dpi, max_dpi, opts.search_window(target), max_iterations, |attempt, dpi| {
+        opts.cancel.check()?;
         let iter_start = Instant::now();
-        if run_gs(input, &temp_output, "/printer", Some(mid_dpi)).is_ok() {
-            _gs_calls += 1;
-            let size = get_file_size_kb(&temp_output);
-            search_progress.set(attempts as u64 + 1);
+        let rendered = if bilevel { run_bilevel_pages(input, &raster_out, dpi, opts) } else { run_gs_raster(input, &raster_out, dpi, opts) };
+        if rendered.is_err() {
+            return Ok(None);
+        }
+        let size = get_file_size_kb(&raster_out);
+        progress.set(attempt as u64);
+        sink.on_attempt(&Attempt {
+            stage: 3,
+            index: attempt,
+            max: max_iterations,
+            parameter: "dpi",
+            value: dpi,
+            size_kb: size,
+            target_kb: target,
+            time_ms: iter_start.elapsed().as_millis(),
+        });
+        if nerd {
             let action_str = if size <= target { "min=mid+1" } else { "max=mid-1" };
-            if nerd {
-                logger::nerd_attempt(attempts, 14, mid_dpi, size, target, iter_start.elapsed().as_millis(), action_str);
-            }
-            if size <= target {
-                fs::copy(&temp_output, output)?;
-                found_valid = true;
-                best_dpi = mid_dpi;
-                best_size = size;
-                min_dpi = mid_dpi + 1;
-            } else {
-                max_dpi = mid_dpi - 1;
-            }
+            rep.nerd_attempt(attempt, max_iterations, dpi, size, target, iter_start.elapsed().as_millis(), action_str);
         }
-    }
-    let _ = fs::remove_file(&temp_output);
-    search_progress.finish();
-    
-    if found_valid {
+        if size <= target {
+            move_file(&raster_out, &best_out)?;
+        }
+        Ok(Some(size))
+    })?;
+    let _ = fs::remove_file(&raster_out);
+    progress.finish();
+
+    let Some((dpi, size_kb)) = search.best else {
         if nerd {
-            println!();
-            println!("  {} Target achieved at {} DPI ({} KB)", "└─".cyan(), best_dpi.to_string().green(), best_size.to_string().green());
-            println!("     Compressing PDF at {} DPI to final output...", best_dpi.to_string().cyan());
-            println!();
-            let total_time = total_start.elapsed().as_secs_f64();
-            logger::nerd_output_summary(input, output, original_size, best_size, &format!("Ghostscript Binary Search ({} DPI)", best_dpi), total_time);
-        } else if best_dpi < 50 {
-            println!("\n{}", "   Note: Very low DPI - images may appear pixelated.".yellow());
+            rep.message(&format!("   {}", t!("page-images-miss", dpi = min_dpi)));
         }
-        Ok(result_with_time(format!("Binary Search ({} DPI)", best_dpi), total_start))
-    } else {
-        run_gs(input, output, "/screen", None)?;
-        Ok(result_with_time("Fallback /screen", total_start))
+        return Ok(None);
+    };
+    if !decide(opts, Decision::Rasterize { dpi, size_kb })? {
+        let _ = fs::remove_file(&best_out);
+        return Ok(None);
     }
+    move_file(&best_out, output)?;
+    rep.warning(&t!("warning-rasterized", dpi = dpi));
+    sink.on_fallback(&format!("Pages rasterized at {} DPI", dpi));
+    let kind = if bilevel { "Black & White Pages" } else { "Rasterized Pages" };
+    Ok(Some(result_with_time(format!("{} ({} DPI)", kind, dpi), start)))
 }
 
 // ==================== SHARED FALLBACK LOGIC ====================
 
-fn handle_fallback_options(output: &str, target: u64, current_size: u64, nerd: bool, format: &str) -> Result<CompResult> {
+fn handle_fallback_options(output: &str, target: u64, current_size: u64, format: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    let nerd = opts.nerd;
+    let sink = opts.progress.as_ref();
+    let rep = opts.reporter.as_ref();
     let fallback_start = Instant::now();
-    println!("\n{}", "WARNING: Limit Reached!".yellow().bold());
-    println!("   Smallest size without resizing: {} KB (Target: {} KB)", current_size.to_string().cyan(), target);
+    rep.warning(&t!("warning-limit-reached"));
+    rep.message(&format!("   {}", t!("limit-smallest", size = current_size, target = target)));
 
-    // Option 1: Grayscale
-    if Confirm::new().with_prompt("   Convert to Grayscale (B&W) to save space?").default(true).interact()? {
-        if nerd { logger::nerd_stage(3, "Grayscale Conversion"); }
-        let progress = PacmanProgress::new(1, "Desaturating...");
+    // Option 1: Grayscale, or black and white with --bw
+    let label = desaturate_label(opts);
+    let decision = if opts.bw.is_some() { Decision::BlackAndWhiteFallback } else { Decision::GrayscaleFallback };
+    if decide(opts, decision)? {
+        opts.cancel.check()?;
+        sink.on_stage(3, &format!("{} Conversion", label));
+        if nerd { rep.nerd_stage(3, &format!("{} Conversion", label)); }
+        let progress = rep.progress(1, &t!("progress-desaturating"));
         
-        let status = Command::new("magick")
-            .arg(output).arg("-colorspace").arg("Gray").arg("-depth").arg("8").arg(output).status()?;
+        let status = run_tool(opts, ToolCommand::new("magick")
+            .arg(output).args(desaturate_args(opts)).arg(output))?;
         
         progress.finish();
         
         if status.success() {
             let gray_size = get_file_size_kb(output);
             if gray_size <= target {
-                println!("   ✨ Grayscale worked! ({} KB)", gray_size);
-                return Ok(result_with_time(format!("{} + Grayscale", format), fallback_start));
-            } else if nerd { logger::nerd_result("Grayscale size", &format!("{} KB (Still > Target)", gray_size), true); }
+                sink.on_fallback(&format!("{} conversion", label));
+                rep.message(&format!("   ✨ {}", t!("fallback-worked", label = label, size = gray_size)));
+                return Ok(result_with_time(format!("{} + {}", format, label), fallback_start));
+            } else if nerd { rep.nerd_result(&format!("{} size", label), &format!("{} KB (Still > Target)", gray_size), true); }
         }
     }
 
     // Option 2: Brutal Resize
-    if Confirm::new().with_prompt("   Resize image dimensions to fit?").default(false).interact()? {
-        if nerd { logger::nerd_stage(4, "Dimension Scaling (Binary Search)"); }
-        println!("   Resizing image to fit...");
+    if decide(opts, Decision::ResizeToFit)? {
+        opts.cancel.check()?;
+        sink.on_stage(4, "Dimension Scaling (Binary Search)");
+        if nerd { rep.nerd_stage(4, "Dimension Scaling (Binary Search)"); }
+        rep.message(&format!("   {}", t!("fallback-resizing")));
         
-        let mut min_scale = 1;
+        let mut min_scale = opts.scale_floor();
         let mut max_scale = 99;
         let mut best_scale = 0;
+        let max_probes = opts.effort.image_probes();
         let mut attempts = 0;
-        let mut progress = PacmanProgress::new(8, "Scaling...");
+        let mut progress = rep.progress(max_probes as u64, &t!("progress-scaling"));
 
-        while min_scale <= max_scale && attempts < 8 {
+        while min_scale <= max_scale && attempts < max_probes as u64 {
+            opts.cancel.check()?;
             attempts += 1;
             progress.set(attempts);
             let mid_scale = (min_scale + max_scale) / 2;
 
-            let status = Command::new("magick")
-                .arg(output).arg("-resize").arg(format!("{}%", mid_scale)).arg(output).status()?;
+            let t0 = Instant::now();
+            let status = run_tool(opts, ToolCommand::new("magick")
+                .arg(output).arg("-resize").arg(format!("{}%", mid_scale)).arg(output))?;
 
             if status.success() {
                 let size = get_file_size_kb(output);
+                sink.on_attempt(&Attempt {
+                    stage: 4,
+                    index: attempts as u32,
+                    max: max_probes,
+                    parameter: "scale",
+                    value: mid_scale,
+                    size_kb: size,
+                    target_kb: target,
+                    time_ms: t0.elapsed().as_millis(),
+                });
                 if nerd {
-                    logger::nerd_result(&format!("Scale {}%", mid_scale), &format!("{} KB", size), size <= target);
+                    rep.nerd_result(&format!("Scale {}%", mid_scale), &format!("{} KB", size), size <= target);
                 }
 
                 if size <= target {
@@ -919,22 +2849,27 @@ fn handle_fallback_options(output: &str, target: u64, current_size: u64, nerd: b
         progress.finish();
 
         if best_scale > 0 {
-            Command::new("magick").arg(output).arg("-resize").arg(format!("{}%", best_scale)).arg(output).status()?;
-            println!("   Resized to {}% scale.", best_scale);
+            run_tool(opts, ToolCommand::new("magick").arg(output).arg("-resize").arg(format!("{}%", best_scale)).arg(output))?;
+            sink.on_fallback(&format!("Resize to {}%", best_scale));
+            rep.message(&format!("   {}", t!("fallback-resized", scale = best_scale)));
             return Ok(result_with_time(format!("{} + Resize {}%", format, best_scale), fallback_start));
         }
     }
 
-    println!("   Keeping the {} KB version.", get_file_size_kb(output));
+    sink.on_fallback("Best effort kept");
+    rep.message(&format!("   {}", t!("fallback-keeping", size = get_file_size_kb(output))));
     Ok(result_with_time("Best Effort", fallback_start))
 }
 
-fn run_gs(input: &str, output: &str, setting: &str, dpi: Option<u64>) -> Result<()> {
-    let mut cmd = Command::new("gs");
+pub(crate) fn run_gs(input: &str, output: &str, setting: &str, dpi: Option<u64>, opts: &CompressionOptions) -> Result<()> {
+    let mut cmd = ToolCommand::new("gs");
     cmd.arg("-sDEVICE=pdfwrite")
         .arg("-dCompatibilityLevel=1.4")
         .arg("-dCompressFonts=true")
-        .arg("-dSubsetFonts=true");
+        .arg(format!("-dSubsetFonts={}", opts.pdf_fonts.subset));
+    if !opts.pdf_fonts.embed { cmd.arg("-dEmbedAllFonts=false"); }
+    if opts.pdf_keep_interactive { cmd.arg("-dPreserveAnnots=true").arg("-dShowAnnots=true").arg("-dPrinted=false"); }
+    if opts.pdf_scrub_metadata { cmd.arg("-dOmitXMP=true").arg("-dOmitInfoDate=true"); }
     if let Some(d) = dpi {
         cmd.arg("-dDownsampleColorImages=true")
            .arg(format!("-dColorImageResolution={}", d))
@@ -943,9 +2878,1605 @@ fn run_gs(input: &str, output: &str, setting: &str, dpi: Option<u64>) -> Result<
     } else {
         cmd.arg(format!("-dPDFSETTINGS={}", setting));
     }
-    cmd.arg("-dNOPAUSE").arg("-dQUIET").arg("-dBATCH")
+    cmd.args(gs_threshold_args(dpi.unwrap_or_else(|| preset_dpi(setting)), opts))
+       .args(gs_images_args(opts))
+       .arg("-dNOPAUSE").arg("-dQUIET").arg("-dBATCH").args(gs_password_arg(opts)).args(gs_pages_arg(opts))
+       .arg(format!("-sOutputFile={}", output));
+    if opts.pdf_fonts.unembed_standard {
+        cmd.arg("-c").arg(format!("<< /NeverEmbed [{}] >> setdistillerparams", STANDARD_FONTS.map(|f| format!("/{}", f)).join(" "))).arg("-f");
+    }
+    cmd.arg(input);
+    // Page thumbnails never make it through pdfwrite; the Info dict read from
+    // the input does, unless a trailing pdfmark overrides it
+    if opts.pdf_scrub_metadata { cmd.arg("-c").arg(BLANK_DOCINFO); }
+    let status = run_tool(opts, &cmd)?;
+    if !status.success() { return Err(gs_failed(input, opts)); }
+    Ok(())
+}
+/// pdfmark that empties the document Info fields Ghostscript would carry over
+const BLANK_DOCINFO: &str = "[ /Author () /Title () /Subject () /Keywords () /Creator () /Producer () /DOCINFO pdfmark";
+/// The base-14 fonts every PDF viewer has, unembedded by `--unembed-standard-fonts`
+const STANDARD_FONTS: [&str; 14] = [
+    "Courier", "Courier-Bold", "Courier-Oblique", "Courier-BoldOblique",
+    "Helvetica", "Helvetica-Bold", "Helvetica-Oblique", "Helvetica-BoldOblique",
+    "Times-Roman", "Times-Bold", "Times-Italic", "Times-BoldItalic",
+    "Symbol", "ZapfDingbats",
+];
+/// Render every page to a JPEG image and wrap them in a new PDF
+fn run_gs_raster(input: &str, output: &str, dpi: u64, opts: &CompressionOptions) -> Result<()> {
+    let mut cmd = ToolCommand::new("gs");
+    match opts.pdf_images {
+        PdfImages::Keep => cmd.arg("-sDEVICE=pdfimage24"),
+        PdfImages::Remove => cmd.arg("-sDEVICE=pdfimage24").arg("-dFILTERIMAGE"),
+        PdfImages::Grayscale => cmd.arg("-sDEVICE=pdfimage8"),
+    };
+    cmd
+       .arg(format!("-r{}", dpi))
+       .arg("-sCompression=JPEG")
+       .arg("-dJPEGQ=75")
+       .arg("-dNOPAUSE").arg("-dQUIET").arg("-dBATCH").args(gs_password_arg(opts)).args(gs_pages_arg(opts))
        .arg(format!("-sOutputFile={}", output)).arg(input);
-    let status = cmd.status()?;
-    if !status.success() { return Err(anyhow!("Ghostscript failed.")); }
+    let status = run_tool(opts, &cmd)?;
+    if !status.success() { return Err(gs_failed(input, opts)); }
+    Ok(())
+}
+/// `-sPDFPassword` for an encrypted input, from `--password`
+pub(crate) fn gs_password_arg(opts: &CompressionOptions) -> Option<String> {
+    opts.pdf_password.as_ref().map(|password| format!("-sPDFPassword={}", password))
+}
+/// `-sPageList` for the `--pages` selection
+pub(crate) fn gs_pages_arg(opts: &CompressionOptions) -> Option<String> {
+    opts.pdf_pages.as_ref().map(|pages| format!("-sPageList={}", pages))
+}
+/// Image resolution a `-dPDFSETTINGS` preset downsamples to
+fn preset_dpi(setting: &str) -> u64 {
+    match setting {
+        "/screen" => 72,
+        "/ebook" => 150,
+        _ => 300,
+    }
+}
+/// Downsample thresholds for `--downsample-above`; Ghostscript takes them as a
+/// multiple of the `resolution` images are downsampled to
+fn gs_threshold_args(resolution: u64, opts: &CompressionOptions) -> Vec<String> {
+    let Some(above) = opts.pdf_downsample_above else { return vec![] };
+    let threshold = (above as f64 / resolution.max(1) as f64).max(1.0);
+    ["Color", "Gray", "Mono"].iter().map(|kind| format!("-d{}ImageDownsampleThreshold={:.2}", kind, threshold)).collect()
+}
+/// Ghostscript's image filter (`--remove-images`) or gray conversion (`--images-grayscale`)
+fn gs_images_args(opts: &CompressionOptions) -> Vec<&'static str> {
+    match opts.pdf_images {
+        PdfImages::Keep => vec![],
+        PdfImages::Remove => vec!["-dFILTERIMAGE"],
+        PdfImages::Grayscale => vec!["-sColorConversionStrategy=Gray", "-dProcessColorModel=/DeviceGray"],
+    }
+}
+/// Whether the PDF at `path` has an encryption dictionary
+fn pdf_encrypted(path: &str) -> bool {
+    fs::read(path).is_ok_and(|data| data.windows(8).any(|w| w == b"/Encrypt"))
+}
+/// Why Ghostscript couldn't read `input`: a missing or wrong password when it's encrypted
+pub(crate) fn gs_failed(input: &str, opts: &CompressionOptions) -> anyhow::Error {
+    match (pdf_encrypted(input), &opts.pdf_password) {
+        (true, None) => anyhow!(t!("error-pdf-password-missing", file = input)),
+        (true, Some(_)) => anyhow!(t!("error-pdf-password-wrong", file = input)),
+        (false, _) => anyhow!(t!("error-gs")),
+    }
+}
+/// Render every page to 1-bit black and white, CCITT Group 4 compressed
+pub(crate) fn run_bilevel_pages(input: &str, output: &str, dpi: u64, opts: &CompressionOptions) -> Result<()> {
+    let mut cmd = ToolCommand::new("magick");
+    if let Some(password) = &opts.pdf_password {
+        cmd.arg("-authenticate").arg(password);
+    }
+    // ImageMagick numbers pages from 0
+    let input = match &opts.pdf_pages {
+        Some(pages) => format!("{}[{}]", input, pages.split(',').map(|part| part.split('-')
+            .map(|n| n.parse::<u32>().map_or(0, |n| n.saturating_sub(1)).to_string())
+            .collect::<Vec<_>>().join("-")).collect::<Vec<_>>().join(",")),
+        None => input.to_string(),
+    };
+    cmd.arg("-density").arg(dpi.to_string()).arg(&input)
+       .args(desaturate_args(opts))
+       .arg("-compress").arg("Group4")
+       .arg(output);
+    let status = run_tool(opts, &cmd)?;
+    if !status.success() { return Err(anyhow!(t!("error-magick"))); }
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use crate::cancel::CancellationToken;
+    use crate::decision::DecisionPolicy;
+    use crate::options::{Effort, PdfFonts, PdfPreset};
+    use crate::retarget::Setting;
+    use crate::exec::Executor;
+    use crate::utils::TargetSize;
+
+    /// What a faked tool does with its output file
+    pub(crate) enum FakeRun {
+        Write(u64),
+        /// Text output, like ffprobe's
+        Print(String),
+        Keep,
+        Fail,
+    }
+
+    /// Executor that writes zero-filled files of scripted sizes (in KB)
+    pub(crate) struct FakeTools {
+        script: Box<dyn Fn(&ToolCommand) -> FakeRun + Send + Sync>,
+        pub(crate) calls: Mutex<Vec<ToolCommand>>,
+    }
+
+    impl FakeTools {
+        pub(crate) fn new(script: impl Fn(&ToolCommand) -> FakeRun + Send + Sync + 'static) -> Arc<Self> {
+            Arc::new(FakeTools { script: Box::new(script), calls: Mutex::new(Vec::new()) })
+        }
+
+        pub(crate) fn calls_to(&self, program: &str) -> usize {
+            self.calls.lock().unwrap().iter().filter(|c| c.program == program).count()
+        }
+    }
+
+    impl Executor for FakeTools {
+        fn run(&self, cmd: &ToolCommand, cancel: &CancellationToken) -> Result<ToolStatus> {
+            cancel.check()?;
+            self.calls.lock().unwrap().push(cmd.clone());
+            match (self.script)(cmd) {
+                FakeRun::Write(kb) => {
+                    fs::write(cmd.output_path().expect("tool without output"), vec![0u8; (kb * 1024) as usize])?;
+                    Ok(ToolStatus::ok())
+                },
+                FakeRun::Print(text) => {
+                    fs::write(cmd.output_path().expect("tool without output"), text)?;
+                    Ok(ToolStatus::ok())
+                },
+                FakeRun::Keep => Ok(ToolStatus::ok()),
+                FakeRun::Fail => Ok(ToolStatus::failed(1)),
+            }
+        }
+    }
+
+    fn arg_after<'a>(cmd: &'a ToolCommand, flag: &str) -> &'a str {
+        let i = cmd.args.iter().position(|a| a == flag).unwrap();
+        &cmd.args[i + 1]
+    }
+
+    fn setup(name: &str, kb: u64) -> (tempfile::TempDir, String, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join(name);
+        fs::write(&input, vec![0u8; (kb * 1024) as usize]).unwrap();
+        let output = dir.path().join(format!("crnched_{}", name));
+        (dir, input.to_string_lossy().to_string(), output.to_string_lossy().to_string())
+    }
+
+    fn leftover_tmp_files(dir: &tempfile::TempDir) -> Vec<String> {
+        fs::read_dir(dir.path()).unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|n| n.contains(".tmp"))
+            .collect()
+    }
+
+    #[test]
+    fn test_png_quantization_binary_search_hits_target() {
+        let (dir, input, output) = setup("image.png", 500);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "oxipng" if cmd.args.contains(&"--out".to_string()) => FakeRun::Write(400),
+            "oxipng" => FakeRun::Keep,
+            "pngquant" => {
+                let min_q: u64 = arg_after(cmd, "--quality").split('-').next().unwrap().parse().unwrap();
+                FakeRun::Write(min_q * 4)
+            },
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(300))
+            .executor(tools.clone())
+            .build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "Hybrid (Oxipng + Binary Search)");
+        assert_eq!(result.target_met, Some(true));
+        assert!(result.final_kb <= 300);
+        assert!(result.attempts.iter().all(|a| a.parameter == "quality"));
+        assert!(result.attempts.iter().filter(|a| a.hit()).all(|a| a.value <= 75));
+        assert!(tools.calls_to("pngquant") <= 8);
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_png_screenshot_crops_borders_and_skips_dithering() {
+        let (dir, input, output) = setup("shot.png", 0);
+        let mut png = crate::screenshot::tests::rgb_png(32, 32, |_, y| if y < 4 { [30, 30, 30] } else { [250, 250, 250] });
+        png.extend(vec![0u8; 500 * 1024]);
+        fs::write(&input, png).unwrap();
+        let quality_floors = Arc::new(Mutex::new(Vec::new()));
+        let floors = quality_floors.clone();
+        let tools = FakeTools::new(move |cmd| match cmd.program.as_str() {
+            "oxipng" if cmd.args.contains(&"--out".to_string()) => FakeRun::Write(400),
+            "oxipng" if cmd.output_path().unwrap().contains("trim") => FakeRun::Write(250),
+            "oxipng" => FakeRun::Keep,
+            "magick" => FakeRun::Write(380),
+            "pngquant" => {
+                assert!(cmd.args.contains(&"--nofs".to_string()));
+                floors.lock().unwrap().push(arg_after(cmd, "--quality").split('-').next().unwrap().parse::<u64>().unwrap());
+                FakeRun::Write(200)
+            },
+            _ => FakeRun::Fail,
+        });
+
+        let opts = CompressionOptions::builder().target_kb(Some(300)).auto_yes(true).executor(tools.clone()).build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "Screenshot (Border Crop + Oxipng)");
+        assert_eq!(result.final_kb, 250);
+        assert_eq!(tools.calls_to("pngquant"), 0);
+        assert!(leftover_tmp_files(&dir).is_empty());
+
+        // Without cropping it goes to undithered quantization, never below 50
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(300))
+            .skip_stages([Stage::Resize])
+            .executor(tools.clone())
+            .build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "Hybrid (Oxipng + Binary Search)");
+        assert!(quality_floors.lock().unwrap().iter().all(|&q| q >= 50));
+
+        // Cropping changes the picture, so --non-destructive keeps the borders
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(300))
+            .decisions(DecisionPolicy::NeverDestructive)
+            .executor(tools.clone())
+            .build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "Hybrid (Oxipng + Binary Search)");
+        assert!(!result.fallbacks.iter().any(|f| f.contains("cropped")));
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_png_never_destructive_keeps_best_color() {
+        let (dir, input, output) = setup("image.png", 500);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "oxipng" => FakeRun::Write(400),
+            "pngquant" => FakeRun::Write(350),
+            "magick" => FakeRun::Write(90),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(100))
+            .decisions(DecisionPolicy::NeverDestructive)
+            .executor(tools.clone())
+            .build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "pngquant (Best Effort Color)");
+        assert_eq!(result.final_kb, 350);
+        assert_eq!(result.target_met, Some(false));
+        assert_eq!(result.fallbacks, vec!["Best effort color kept".to_string()]);
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_strict_removes_an_output_over_target() {
+        let (dir, input, output) = setup("image.png", 500);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "oxipng" => FakeRun::Write(400),
+            "pngquant" => FakeRun::Write(350),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(100))
+            .decisions(DecisionPolicy::NeverDestructive)
+            .strict(true)
+            .executor(tools.clone())
+            .build();
+
+        let err = compress_file(&input, &output, &opts).unwrap_err();
+        assert_eq!(err.downcast_ref::<TargetMissed>(), Some(&TargetMissed { final_bytes: 350 * 1024, target_bytes: 100 * 1024 }));
+        assert_eq!(err.to_string(), "Target missed: 350 KB, over the 100 KB target; the output was removed.");
+        assert!(!Path::new(&output).exists());
+        assert!(leftover_tmp_files(&dir).is_empty());
+
+        // Held to the byte when the target was given in bytes
+        let opts = CompressionOptions::builder()
+            .target(Some(TargetSize::from_bytes(350 * 1024 - 1)))
+            .decisions(DecisionPolicy::NeverDestructive)
+            .strict(true)
+            .executor(tools)
+            .build();
+        let err = compress_file(&input, &output, &opts).unwrap_err();
+        assert_eq!(err.to_string(), "Target missed: 358400 bytes, over the 358399 byte target; the output was removed.");
+    }
+
+    #[test]
+    fn test_pdf_dpi_search_converges_on_largest_fitting_dpi() {
+        let (_dir, input, output) = setup("doc.pdf", 2000);
+        let tools = FakeTools::new(|cmd| {
+            if cmd.args.iter().any(|a| a == "-dPDFSETTINGS=/screen") {
+                return FakeRun::Write(100);
+            }
+            let dpi: u64 = cmd.args.iter()
+                .find_map(|a| a.strip_prefix("-dColorImageResolution="))
+                .unwrap()
+                .parse()
+                .unwrap();
+            FakeRun::Write(dpi * 3)
+        });
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(600))
+            .executor(tools.clone())
+            .build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "Binary Search (200 DPI)");
+        assert_eq!(result.final_kb, 600);
+        assert_eq!(result.target_met, Some(true));
+        assert!(result.attempts.len() <= 14);
+        assert!(result.attempts.iter().all(|a| a.parameter == "dpi"));
+    }
+
+    #[test]
+    fn test_retarget_searches_below_the_earlier_setting() {
+        let (_dir, input, output) = setup("doc.pdf", 2000);
+        let tools = FakeTools::new(|cmd| match cmd.args.iter().find_map(|a| a.strip_prefix("-dColorImageResolution=")) {
+            Some(dpi) => FakeRun::Write(dpi.parse::<u64>().unwrap() * 3),
+            None => FakeRun::Write(100),
+        });
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(300))
+            .prior(Some(Setting { stage: 2, parameter: "dpi".into(), value: 130 }))
+            .executor(tools)
+            .build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "Binary Search (100 DPI)");
+        assert!(result.attempts.iter().all(|a| a.value <= 130));
+    }
+
+    #[test]
+    fn test_png_to_webp_searches_cwebp_quality() {
+        let (dir, input, _) = setup("image.png", 500);
+        let output = dir.path().join("image.webp").to_string_lossy().to_string();
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "cwebp" => FakeRun::Write(arg_after(cmd, "-q").parse::<u64>().unwrap() * 4),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(300))
+            .executor(tools.clone())
+            .build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "WebP (quality 75)");
+        assert_eq!(result.final_kb, 300);
+        assert_eq!(result.target_met, Some(true));
+        assert!(result.attempts.iter().all(|a| a.stage == 1 && a.parameter == "quality"));
+        assert!(leftover_tmp_files(&dir).is_empty());
+
+        // Converting always encodes, even with the target above the original
+        let opts = CompressionOptions::builder().target_kb(Some(900)).executor(tools.clone()).build();
+        assert!(compress_file(&input, &output, &opts).unwrap().algorithm.starts_with("WebP"));
+
+        let (_pdf_dir, pdf, _) = setup("doc.pdf", 100);
+        let err = compress_file(&pdf, &output, &opts).unwrap_err();
+        assert!(err.to_string().contains("Can't convert .pdf to .webp"));
+    }
+
+    #[test]
+    fn test_heic_decodes_to_jpg_or_searches_heif_enc_quality() {
+        let (dir, input, _) = setup("IMG_0001.heic", 1000);
+        let jpg = dir.path().join("IMG_0001.jpg").to_string_lossy().to_string();
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "heif-convert" => FakeRun::Fail,
+            "magick" if cmd.args[0].ends_with(".heic") => FakeRun::Write(1000),
+            "magick" => FakeRun::Write(arg_after(cmd, "-define").trim_start_matches("jpeg:extent=").trim_end_matches("KB").parse::<u64>().unwrap() - 10),
+            "jpegoptim" => FakeRun::Write(1000),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder().executor(tools.clone()).build();
+        let result = compress_file(&input, &jpg, &opts).unwrap();
+        assert_eq!(result.algorithm, "HEIC → jpegoptim + magick (Standard Preset, target 600 KB)");
+        assert_eq!(tools.calls_to("heif-convert"), 1);
+
+        let heic = dir.path().join("small.heic").to_string_lossy().to_string();
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "heif-convert" => FakeRun::Write(1500),
+            "heif-enc" => FakeRun::Write(arg_after(cmd, "-q").parse::<u64>().unwrap() * 4),
+            _ => FakeRun::Keep,
+        });
+        let opts = CompressionOptions::builder().target_kb(Some(300)).executor(tools).build();
+        let result = compress_file(&input, &heic, &opts).unwrap();
+        assert_eq!(result.algorithm, "HEIC (quality 75)");
+        assert_eq!(result.final_kb, 300);
+        assert!(leftover_tmp_files(&dir).is_empty());
+        assert!(compress_file(&input, &dir.path().join("x.png").to_string_lossy(), &opts).is_err());
+    }
+
+    #[test]
+    fn test_raw_develops_with_dcraw_or_falls_back_to_darktable() {
+        let (dir, input, _) = setup("DSC_0042.nef", 40_000);
+        let jpg = dir.path().join("DSC_0042.jpg").to_string_lossy().to_string();
+        let script = |dcraw_reads_it: bool| move |cmd: &ToolCommand| match cmd.program.as_str() {
+            "dcraw" if dcraw_reads_it => FakeRun::Write(100_000),
+            "dcraw" => FakeRun::Fail,
+            "darktable-cli" => FakeRun::Write(1000),
+            "magick" if cmd.args[0].ends_with(".ppm") => FakeRun::Write(1000),
+            "magick" => FakeRun::Write(arg_after(cmd, "-define").trim_start_matches("jpeg:extent=").trim_end_matches("KB").parse::<u64>().unwrap() - 10),
+            "jpegoptim" if cmd.stdout_to.is_some() => FakeRun::Write(1000),
+            _ => FakeRun::Keep,
+        };
+        let tools = FakeTools::new(script(true));
+        let opts = CompressionOptions::builder().executor(tools.clone()).build();
+        let result = compress_file(&input, &jpg, &opts).unwrap();
+        assert_eq!(result.algorithm, "RAW → jpegoptim + magick (Standard Preset, target 600 KB)");
+        assert_eq!(tools.calls_to("darktable-cli"), 0);
+
+        let tools = FakeTools::new(script(false));
+        let opts = CompressionOptions::builder().target_kb(Some(800)).executor(tools.clone()).build();
+        assert!(compress_file(&input, &jpg, &opts).unwrap().algorithm.starts_with("RAW → "));
+        assert_eq!(tools.calls_to("darktable-cli"), 1);
+        assert!(leftover_tmp_files(&dir).is_empty());
+        assert!(compress_file(&input, &dir.path().join("copy.dng").to_string_lossy(), &opts).is_err());
+    }
+
+    #[test]
+    fn test_office_images_share_the_target() {
+        let (dir, input, output) = setup("report.docx", 0);
+        fs::write(&input, crate::office::tests::docx(&[("image1.png", vec![0; 600 * 1024]), ("image2.png", vec![0; 300 * 1024])])).unwrap();
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "oxipng" if cmd.args.contains(&"--out".to_string()) => FakeRun::Write(get_file_size_kb(cmd.args.last().unwrap()) * 9 / 10),
+            "pngquant" => FakeRun::Write(arg_after(cmd, "--quality").split('-').next().unwrap().parse::<u64>().unwrap() * 2),
+            _ => FakeRun::Keep,
+        });
+        let opts = CompressionOptions::builder().target_kb(Some(300)).executor(tools.clone()).build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "Office images (2 of 2 recompressed)");
+        assert!(result.final_kb <= 300, "{} KB", result.final_kb);
+        let entries = crate::zip::read(Path::new(&output)).unwrap();
+        assert_eq!(entries[1].name, "word/document.xml");
+        assert!(entries[3].data.len() > entries[4].data.len());
+
+        let opts = CompressionOptions::builder().privacy(true).executor(tools).build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.privacy.unwrap().found, ["document author"]);
+        assert!(crate::office::people(&crate::zip::read(Path::new(&output)).unwrap()).is_empty());
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_zip_members_are_recompressed_and_repacked() {
+        let (dir, input, output) = setup("attachments.zip", 0);
+        let inner = |name: &str, data: Vec<u8>| zip::Entry::new(name, data);
+        let entries = vec![
+            inner("scans/", Vec::new()),
+            inner("scans/page1.png", vec![0; 800 * 1024]),
+            inner("scans/page2.bmp", vec![1; 200 * 1024]),
+            inner("notes.txt", "notes ".repeat(1000).into_bytes()),
+        ];
+        fs::write(&input, zip::to_bytes(&entries).unwrap()).unwrap();
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "oxipng" if cmd.args.contains(&"--out".to_string()) => FakeRun::Write(700),
+            "pngquant" => FakeRun::Write(arg_after(cmd, "--quality").split('-').next().unwrap().parse::<u64>().unwrap() * 3),
+            "zstd" => FakeRun::Write(1),
+            _ => FakeRun::Keep,
+        });
+        let opts = CompressionOptions::builder().target_kb(Some(300)).executor(tools.clone()).build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "ZIP (1 of 1 members recompressed)");
+        let repacked = zip::read(Path::new(&output)).unwrap();
+        assert_eq!(repacked.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(), ["scans/", "scans/page1.png", "scans/page2.bmp", "notes.txt"]);
+        assert_eq!(repacked[2..], entries[2..]);
+        assert!(result.final_kb <= 300, "{} KB", result.final_kb);
+
+        // zstd for the text and the BMP; the PNG stays stored
+        let opts = CompressionOptions::builder().zip_zstd(true).executor(tools.clone()).build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert!(result.algorithm.starts_with("ZIP, zstd"));
+        assert_eq!(tools.calls_to("zstd"), 2);
+        assert!(zip::entries(&fs::read(&output).unwrap()).is_none());
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_fixed_quality_without_target() {
+        let (dir, input, output) = setup("diagram.png", 900);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "oxipng" => FakeRun::Write(800),
+            "pngquant" => FakeRun::Write(arg_after(cmd, "--quality").trim_start_matches("0-").parse::<u64>().unwrap() * 4),
+            "gs" => FakeRun::Write(300),
+            _ => FakeRun::Keep,
+        });
+        let opts = CompressionOptions::builder().quality(Some(60)).executor(tools.clone()).build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!((result.algorithm.as_str(), result.final_kb), ("pngquant (quality 60) + oxipng", 240));
+
+        let (_pdf_dir, pdf, pdf_out) = setup("report.pdf", 900);
+        let result = compress_file(&pdf, &pdf_out, &opts).unwrap();
+        assert_eq!(result.algorithm, "Ghostscript 194 DPI (quality 60)");
+        assert!(tools.calls.lock().unwrap().iter().any(|c| c.args.contains(&"-dColorImageResolution=194".to_string())));
+
+        let (jpg_dir, jpg, jpg_out) = setup("photo.jpg", 900);
+        let jpg_tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "jpegoptim" => FakeRun::Write(850),
+            "magick" => FakeRun::Write(arg_after(cmd, "-quality").parse::<u64>().unwrap() * 5),
+            _ => FakeRun::Keep,
+        });
+        let result = compress_file(&jpg, &jpg_out, &CompressionOptions::builder().quality(Some(60)).executor(jpg_tools.clone()).build()).unwrap();
+        assert_eq!((result.algorithm.as_str(), result.final_kb), ("jpegoptim + magick (quality 60)", 300));
+        let encodes: Vec<ToolCommand> = jpg_tools.calls.lock().unwrap().iter().filter(|c| c.program == "magick").cloned().collect();
+        assert_eq!(encodes.len(), 1);
+        assert_eq!(arg_after(&encodes[0], "-quality"), "60");
+        assert!(leftover_tmp_files(&dir).is_empty() && leftover_tmp_files(&jpg_dir).is_empty());
+    }
+
+    #[test]
+    fn test_config_jpeg_quality_without_target() {
+        let (_dir, input, output) = setup("photo.jpg", 900);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "jpegoptim" => FakeRun::Write(850),
+            "magick" => FakeRun::Write(400),
+            _ => FakeRun::Keep,
+        });
+        let config = crate::config::Config::parse("[jpeg]\nquality = 82\n").unwrap();
+        let opts = CompressionOptions::builder().jpeg_quality(config.formats.jpeg_quality).executor(tools.clone()).build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "jpegoptim + magick (quality 82)");
+        let calls = tools.calls.lock().unwrap();
+        let magick: Vec<&ToolCommand> = calls.iter().filter(|c| c.program == "magick").collect();
+        assert_eq!(magick.len(), 1);
+        assert_eq!(arg_after(magick[0], "-quality"), "82");
+        assert!(!magick[0].args.iter().any(|a| a.starts_with("jpeg:extent")));
+    }
+
+    /// The quality in a perceptual probe's name (`...look-q70.tmp.jpg`)
+    fn probe_quality(path: &str) -> u64 {
+        let digits = &path[path.find("look-q").unwrap() + 6..];
+        digits[..digits.find('.').unwrap()].parse().unwrap()
+    }
+
+    #[test]
+    fn test_min_ssim_sets_the_jpeg_quality_without_target() {
+        let (dir, input, output) = setup("photo.jpg", 900);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "jpegoptim" => FakeRun::Write(850),
+            // SSIM climbs with quality: q70 is the first to reach 0.7
+            "magick" if cmd.args.contains(&"SSIM".to_string()) => FakeRun::Print(format!("{}", probe_quality(&cmd.args[1]) as f64 / 100.0)),
+            "magick" => FakeRun::Write(arg_after(cmd, "-quality").parse::<u64>().unwrap() * 5),
+            _ => FakeRun::Keep,
+        });
+        let opts = CompressionOptions::builder().min_ssim(Some(0.7)).executor(tools.clone()).build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!((result.algorithm.as_str(), result.final_kb), ("jpegoptim + magick (quality 70)", 350));
+        let calls = tools.calls.lock().unwrap();
+        let encode = calls.iter().rfind(|c| c.program == "magick").unwrap();
+        assert_eq!((encode.args.last().unwrap().as_str(), arg_after(encode, "-quality")), (output.as_str(), "70"));
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_max_distance_sets_the_jpeg_quality_without_target() {
+        let (dir, input, output) = setup("photo.jpg", 900);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "jpegoptim" => FakeRun::Write(850),
+            // Distance falls with quality: q80 is the first within 2.0
+            "butteraugli_main" => FakeRun::Print(format!("{}\n", (100 - probe_quality(&cmd.args[1])) as f64 / 10.0)),
+            "magick" => FakeRun::Write(arg_after(cmd, "-quality").parse::<u64>().unwrap() * 5),
+            _ => FakeRun::Keep,
+        });
+        let opts = CompressionOptions::builder().max_distance(Some(2.0)).executor(tools.clone()).build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!((result.algorithm.as_str(), result.final_kb), ("jpegoptim + magick (quality 80)", 400));
+        let calls = tools.calls.lock().unwrap();
+        let encode = calls.iter().rfind(|c| c.program == "magick").unwrap();
+        assert_eq!((encode.args.last().unwrap().as_str(), arg_after(encode, "-quality")), (output.as_str(), "80"));
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_pdf_at_fixed_dpi_is_one_pass() {
+        let (dir, input, output) = setup("scan.pdf", 900);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "gs" => FakeRun::Write(400),
+            _ => FakeRun::Keep,
+        });
+        let opts = CompressionOptions::builder().pdf_dpi(Some(150)).executor(tools.clone()).build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!((result.algorithm.as_str(), result.final_kb), ("Ghostscript 150 DPI", 400));
+        assert_eq!(tools.calls_to("gs"), 1);
+        assert!(tools.calls.lock().unwrap().iter().any(|c| c.args.contains(&"-dColorImageResolution=150".to_string())));
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_downsample_above_sets_threshold() {
+        let (_dir, input, output) = setup("report.pdf", 900);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "gs" => FakeRun::Write(400),
+            _ => FakeRun::Keep,
+        });
+        let threshold = |opts: &CompressionOptions| {
+            compress_file(&input, &output, opts).unwrap();
+            let calls = tools.calls.lock().unwrap();
+            calls.last().unwrap().args.iter().find_map(|a| a.strip_prefix("-dColorImageDownsampleThreshold=").map(str::to_string))
+        };
+        // Images over 200 DPI go down to 100; the rest stay
+        let fixed = CompressionOptions::builder().pdf_dpi(Some(100)).pdf_downsample_above(Some(200)).executor(tools.clone()).build();
+        assert_eq!(threshold(&fixed).as_deref(), Some("2.00"));
+        let preset = CompressionOptions::builder().pdf_preset(Some(PdfPreset::Screen)).pdf_downsample_above(Some(144)).executor(tools.clone()).build();
+        assert_eq!(threshold(&preset).as_deref(), Some("2.00"));
+        // Below the resolution it's Ghostscript's plain downsampling
+        let low = CompressionOptions::builder().pdf_dpi(Some(300)).pdf_downsample_above(Some(150)).executor(tools.clone()).build();
+        assert_eq!(threshold(&low).as_deref(), Some("1.00"));
+        let unset = CompressionOptions::builder().pdf_dpi(Some(100)).executor(tools.clone()).build();
+        assert_eq!(threshold(&unset), None);
+    }
+
+    #[test]
+    fn test_font_controls_reach_ghostscript() {
+        let (_dir, input, output) = setup("paper.pdf", 900);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "gs" => FakeRun::Write(400),
+            _ => FakeRun::Keep,
+        });
+        let last_call = |opts: &CompressionOptions| {
+            compress_file(&input, &output, opts).unwrap();
+            tools.calls.lock().unwrap().last().unwrap().clone()
+        };
+        let default = last_call(&CompressionOptions::builder().pdf_dpi(Some(150)).executor(tools.clone()).build()).args;
+        assert!(default.contains(&"-dSubsetFonts=true".to_string()));
+        assert!(!default.iter().any(|a| a.starts_with("-dEmbedAllFonts") || a == "-c"));
+
+        let fonts = PdfFonts { subset: false, embed: false, unembed_standard: true };
+        let call = last_call(&CompressionOptions::builder().pdf_dpi(Some(150)).pdf_fonts(fonts).executor(tools.clone()).build());
+        assert!(call.args.contains(&"-dSubsetFonts=false".to_string()) && call.args.contains(&"-dEmbedAllFonts=false".to_string()));
+        // The distiller params come before the input, which -f marks
+        let ps = arg_after(&call, "-c");
+        assert!(ps.contains("/Helvetica-Bold") && ps.ends_with("setdistillerparams"), "{}", ps);
+        assert_eq!(call.args[call.args.len() - 2..], ["-f".to_string(), input.clone()]);
+    }
+
+    #[test]
+    fn test_scrub_metadata_in_every_gs_pass() {
+        let (dir, input, output) = setup("contract.pdf", 900);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "gs" => FakeRun::Write(300),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder().target_kb(Some(500)).pdf_scrub_metadata(true).executor(tools.clone()).build();
+        compress_file(&input, &output, &opts).unwrap();
+        for call in tools.calls.lock().unwrap().iter() {
+            assert!(call.args.contains(&"-dOmitXMP=true".to_string()));
+            // After the input, so it wins over the input's Info dict
+            assert_eq!(call.args[call.args.len() - 3..], [input.clone(), "-c".to_string(), BLANK_DOCINFO.to_string()]);
+        }
+        assert!(leftover_tmp_files(&dir).is_empty());
+
+        let quick = CompressionOptions::builder().pdf_scrub_metadata(true).pdf_mode(PdfMode::Quick).executor(tools.clone()).build();
+        assert!(compress_file(&input, &output, &quick).unwrap_err().to_string().contains("--scrub-metadata"));
+    }
+
+    #[test]
+    fn test_lost_links_retry_with_annotations_kept() {
+        use crate::pdf_interactive::tests::pdf;
+        let (dir, input, output) = setup("manual.pdf", 0);
+        let padding = "x".repeat(50_000);
+        let image = format!("<< /Length {} >>\nstream\n{}\nendstream", padding.len(), padding);
+        let (catalog, pages, link) = ("<< /Type /Catalog /Pages 2 0 R >>", "<< /Type /Pages /Kids [3 0 R] /Count 1 >>", "<< /Type /Annot /Subtype /Link >>");
+        let page = "<< /Type /Page /Parent 2 0 R /Annots [4 0 R] /Contents 5 0 R >>";
+        fs::write(&input, pdf(&[catalog, pages, page, link, &image])).unwrap();
+        // gs drops the link unless told to keep every annotation
+        let text = |objects: &[&str]| String::from_utf8(pdf(objects)).unwrap();
+        let (flattened, kept) = (text(&[catalog, pages, "<< /Type /Page /Parent 2 0 R >>"]), text(&[catalog, pages, page, link]));
+        let tools = FakeTools::new(move |cmd| match cmd.program.as_str() {
+            "gs" if cmd.args.contains(&"-dPreserveAnnots=true".to_string()) => FakeRun::Print(kept.clone()),
+            "gs" => FakeRun::Print(flattened.clone()),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder().pdf_dpi(Some(150)).executor(tools.clone()).build();
+        compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(tools.calls_to("gs"), 2);
+        let after = crate::pdf_interactive::count(&fs::read(&output).unwrap()).unwrap();
+        assert_eq!(after.links, 1);
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_ocr_layer_added_before_compression() {
+        let (dir, input, output) = setup("scan.pdf", 900);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "ocrmypdf" => FakeRun::Write(920),
+            "gs" => FakeRun::Write(300),
+            _ => FakeRun::Fail,
+        });
+        // Already under the target, but without text to search
+        let opts = CompressionOptions::builder().target_kb(Some(2000)).pdf_ocr(true).executor(tools.clone()).build();
+        assert_eq!(compress_file(&input, &output, &opts).unwrap().final_kb, 300);
+        let calls = tools.calls.lock().unwrap();
+        assert_eq!(calls[0].program, "ocrmypdf");
+        assert!(calls[0].args.contains(&"--skip-text".to_string()));
+        let searchable = calls[0].args.last().unwrap();
+        assert!(calls[1..].iter().all(|c| c.program == "gs" && c.args.last() == Some(searchable)));
+        drop(calls);
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_scan_mode_pages_reach_the_pipeline() {
+        let (dir, input, output) = setup("forms.pdf", 0);
+        // Two pages that only draw a scanned image
+        let image = format!("<< /Type /XObject /Subtype /Image /Length {} >>\nstream\n{}\nendstream", 900 * 1024, "\0".repeat(900 * 1024));
+        let page = "<< /Type /Page /Parent 2 0 R /Resources << /XObject << /Im0 5 0 R >> >> >>";
+        fs::write(&input, crate::pdf_interactive::tests::pdf(&["<< /Type /Catalog /Pages 2 0 R >>", "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >>", page, page, &image])).unwrap();
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "magick" if cmd.args.contains(&"-fx".to_string()) => FakeRun::Print("0.99\n0.99\n".into()),
+            "magick" => FakeRun::Write(80),
+            "gs" => FakeRun::Write(70),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder().pdf_dpi(Some(300)).pdf_scan_mode(true).executor(tools.clone()).build();
+        assert_eq!(compress_file(&input, &output, &opts).unwrap().final_kb, 70);
+        let calls = tools.calls.lock().unwrap();
+        assert_eq!(calls.iter().map(|c| c.program.as_str()).collect::<Vec<_>>(), ["magick", "magick", "gs"]);
+        assert!(calls[2].args.last().unwrap().ends_with("scan.tmp.pdf"));
+        drop(calls);
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_encrypted_pdf_needs_password() {
+        let (_dir, input, output) = setup("locked.pdf", 0);
+        fs::write(&input, b"%PDF-1.7\ntrailer << /Encrypt 9 0 R >>\n%%EOF\n").unwrap();
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "gs" if cmd.args.contains(&"-sPDFPassword=secret".to_string()) => FakeRun::Write(1),
+            _ => FakeRun::Fail,
+        });
+        let locked = CompressionOptions::builder().target(Some(TargetSize::from_bytes(10))).executor(tools.clone()).build();
+        let err = compress_file(&input, &output, &locked).unwrap_err().to_string();
+        assert!(err.contains("--password"), "{}", err);
+        assert_eq!(tools.calls_to("gs"), 1);
+
+        let wrong = CompressionOptions::builder().pdf_password(Some("guess".into())).executor(tools.clone()).build();
+        assert!(compress_file(&input, &output, &wrong).unwrap_err().to_string().contains("given password"));
+        let opts = CompressionOptions::builder().pdf_password(Some("secret".into())).executor(tools.clone()).build();
+        assert!(compress_file(&input, &output, &opts).is_ok());
+    }
+
+    #[test]
+    fn test_page_selection_runs_even_under_target() {
+        let (dir, input, output) = setup("book.pdf", 900);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "gs" => FakeRun::Write(950),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder().target_kb(Some(2000)).pdf_pages(Some("1-10,15".into())).executor(tools.clone()).build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        // Grown, but eleven pages aren't the original's three hundred
+        assert_eq!(result.final_kb, 950);
+        assert!(tools.calls.lock().unwrap().iter().all(|c| c.args.contains(&"-sPageList=1-10,15".to_string())));
+        assert!(leftover_tmp_files(&dir).is_empty());
+
+        let quick = CompressionOptions::builder().pdf_pages(Some("2".into())).pdf_mode(PdfMode::Quick).executor(tools.clone()).build();
+        assert!(compress_file(&input, &output, &quick).is_err());
+    }
+
+    #[test]
+    fn test_image_filters_reach_every_gs_pass() {
+        let (dir, input, output) = setup("contract.pdf", 900);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "gs" => FakeRun::Write(40),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder().target_kb(Some(2000)).pdf_images(PdfImages::Remove).executor(tools.clone()).build();
+        // Over the target already, but the images still have to go
+        assert_eq!(compress_file(&input, &output, &opts).unwrap().final_kb, 40);
+        assert!(tools.calls.lock().unwrap().iter().all(|c| c.args.contains(&"-dFILTERIMAGE".to_string())));
+        assert!(leftover_tmp_files(&dir).is_empty());
+
+        let gray = CompressionOptions::builder().pdf_images(PdfImages::Grayscale).executor(tools.clone()).build();
+        compress_file(&input, &output, &gray).unwrap();
+        assert!(tools.calls.lock().unwrap().iter().any(|c| c.args.contains(&"-sColorConversionStrategy=Gray".to_string())));
+        let quick = CompressionOptions::builder().pdf_images(PdfImages::Grayscale).pdf_mode(PdfMode::Quick).executor(tools.clone()).build();
+        assert!(compress_file(&input, &output, &quick).unwrap_err().to_string().contains("--images-grayscale"));
+    }
+
+    #[test]
+    fn test_output_never_larger_than_input() {
+        let (dir, input, output) = setup("optimized.png", 100);
+        let original = fs::read(&input).unwrap();
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "oxipng" => FakeRun::Write(130),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder().executor(tools.clone()).build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "No reduction possible (original kept)");
+        assert_eq!((result.final_bytes, fs::read(&output).unwrap()), (result.original_bytes, original));
+        assert_eq!(result.fallbacks, vec!["Original kept (no reduction possible)".to_string()]);
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_min_savings_keeps_original_for_marginal_gains() {
+        let (_dir, input, output) = setup("screenshot.png", 100);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "oxipng" => FakeRun::Write(97),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder().min_savings(Some(5.0)).executor(tools.clone()).build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert!(result.not_worth);
+        assert_eq!(result.algorithm, "Not worth recompressing (saved 3.0%, original kept)");
+        assert_eq!(result.final_bytes, result.original_bytes);
+
+        let opts = CompressionOptions::builder().min_savings(Some(2.5)).executor(tools.clone()).build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert!(!result.not_worth);
+        assert_eq!(result.final_kb, 97);
+    }
+
+    #[test]
+    fn test_video_bitrate_from_target_and_crf_from_level() {
+        let (dir, input, output) = setup("clip.mp4", 10_000);
+        // Muxed output 10% over the requested video and audio bitrates
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "ffprobe" => FakeRun::Print("20.000000\n".into()),
+            "ffmpeg" if cmd.args.contains(&"-crf".to_string()) => FakeRun::Write(3000),
+            "ffmpeg" if arg_after(cmd, "-pass") == "2" => {
+                let kbps = |flag| arg_after(cmd, flag).trim_end_matches('k').parse::<u64>().unwrap();
+                FakeRun::Write((kbps("-b:v") + kbps("-b:a")) * 20 / 8 * 11 / 10)
+            },
+            _ => FakeRun::Keep,
+        });
+        let opts = CompressionOptions::builder().target_kb(Some(2000)).executor(tools.clone()).build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "x264 two-pass (579 kbps)");
+        assert_eq!(tools.calls_to("ffmpeg"), 4);
+        assert!(get_file_size_kb(&output) <= 2000);
+        assert!(tools.calls.lock().unwrap().iter().any(|c| c.args.windows(2).any(|w| w == ["-pass", "1"]) && c.args.contains(&"-an".to_string())));
+
+        let opts = CompressionOptions::builder().level(Some(CompressionLevel::High)).executor(tools.clone()).build();
+        assert_eq!(compress_file(&input, &output, &opts).unwrap().algorithm, "x264 (CRF 30)");
+        assert!(leftover_tmp_files(&dir).is_empty());
+
+        let opts = CompressionOptions::builder().target_kb(Some(2000)).executor(FakeTools::new(|_| FakeRun::Fail)).build();
+        assert!(compress_file(&input, &output, &opts).unwrap_err().to_string().contains("duration"));
+    }
+
+    #[test]
+    fn test_comic_png_pages_become_jpegs() {
+        let (dir, input, output) = setup("issue_01.cbz", 0);
+        let page = |name: &str| zip::Entry::new(name, vec![3; 400 * 1024]);
+        fs::write(&input, zip::to_bytes(&[page("001.png"), page("002.png"), zip::Entry::new("ComicInfo.xml", b"<ComicInfo/>".to_vec())]).unwrap()).unwrap();
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "magick" if cmd.args[0].ends_with(".png") => FakeRun::Write(500),
+            "jpegoptim" => FakeRun::Write(90),
+            _ => FakeRun::Keep,
+        });
+        let opts = CompressionOptions::builder().target_kb(Some(300)).comic_pages(ComicPages::Jpg).executor(tools.clone()).build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "Comic (2 of 2 pages recompressed)");
+        let repacked = zip::read(Path::new(&output)).unwrap();
+        assert_eq!(repacked.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(), ["001.jpg", "002.jpg", "ComicInfo.xml"]);
+        assert!(result.final_kb <= 300, "{} KB", result.final_kb);
+        assert!(leftover_tmp_files(&dir).is_empty());
+
+        // RAR comics need unrar or bsdtar, and are never written back as RAR
+        let cbr = dir.path().join("issue_02.cbr").to_string_lossy().to_string();
+        fs::write(&cbr, b"Rar!\x1a\x07\x01\x00").unwrap();
+        let failing = FakeTools::new(|_| FakeRun::Fail);
+        let opts = CompressionOptions::builder().executor(failing.clone()).build();
+        assert!(compress_file(&cbr, &output, &opts).unwrap_err().to_string().contains("unrar"));
+        assert_eq!((failing.calls_to("unrar"), failing.calls_to("bsdtar")), (1, 1));
+        assert!(compress_file(&input, &cbr, &opts).is_err());
+    }
+
+    #[test]
+    fn test_bmp_is_decoded_then_compressed_as_png_or_jpg() {
+        let (dir, input, _) = setup("capture.bmp", 0);
+        fs::write(&input, crate::bmp::tests::bmp(300, 200, |_, y| if y < 30 { [40, 44, 52] } else { [255, 255, 255] })).unwrap();
+        assert_eq!(crate::bmp::output_format(Path::new(&input)), Format::Png);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "magick" if cmd.args[0].ends_with(".bmp") => FakeRun::Write(150),
+            "oxipng" if cmd.args.contains(&"--out".to_string()) => FakeRun::Write(40),
+            "jpegoptim" => FakeRun::Write(100),
+            _ => FakeRun::Keep,
+        });
+        let opts = CompressionOptions::builder().executor(tools.clone()).build();
+
+        let png = dir.path().join("capture.png").to_string_lossy().to_string();
+        let result = compress_file(&input, &png, &opts).unwrap();
+        assert!(result.algorithm.starts_with("BMP → "), "{}", result.algorithm);
+        assert!(fs::metadata(&png).is_ok());
+        let jpg = dir.path().join("capture.jpg").to_string_lossy().to_string();
+        compress_file(&input, &jpg, &opts).unwrap();
+        assert!(tools.calls.lock().unwrap().iter().any(|c| c.program == "magick" && c.args.contains(&"-quality".to_string()) && c.args[0].ends_with(".bmp")));
+        assert!(leftover_tmp_files(&dir).is_empty());
+        assert!(compress_file(&input, &dir.path().join("copy.bmp").to_string_lossy(), &opts).is_err());
+    }
+
+    #[test]
+    fn test_convert_to_any_image_format() {
+        let (dir, input, _) = setup("screenshot.png", 800);
+        let avif = dir.path().join("screenshot.avif").to_string_lossy().to_string();
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "avifenc" => FakeRun::Write(arg_after(cmd, "-q").parse::<u64>().unwrap() * 4),
+            "magick" => FakeRun::Write(900),
+            "jpegoptim" => FakeRun::Write(150),
+            _ => FakeRun::Keep,
+        });
+        let opts = CompressionOptions::builder().target_kb(Some(200)).executor(tools.clone()).build();
+
+        // avifenc reads the PNG itself; the quality search finds 50 (200 KB)
+        let result = compress_file(&input, &avif, &opts).unwrap();
+        assert_eq!(result.algorithm, "AVIF (quality 50)");
+        assert_eq!(tools.calls_to("magick"), 0);
+
+        // GIF to JPEG goes through a decode of the first frame
+        let (_gif_dir, gif, _) = setup("loop.gif", 500);
+        let jpg = dir.path().join("loop.jpg").to_string_lossy().to_string();
+        let result = compress_file(&gif, &jpg, &opts).unwrap();
+        assert!(result.algorithm.starts_with("GIF → "), "{}", result.algorithm);
+        assert!(tools.calls.lock().unwrap().iter().any(|c| c.program == "magick" && c.args[0].ends_with("loop.gif[0]")));
+        assert!(leftover_tmp_files(&dir).is_empty());
+
+        let (_pdf_dir, pdf, _) = setup("doc.pdf", 100);
+        assert!(compress_file(&pdf, &avif, &opts).unwrap_err().to_string().contains("images convert to"));
+    }
+
+    #[test]
+    fn test_ico_frames_are_optimized_and_repacked() {
+        let (dir, input, output) = setup("favicon.ico", 0);
+        let png = [&b"\x89PNG\r\n\x1a\n"[..], &[7; 3000]].concat();
+        fs::write(&input, crate::ico::tests::ico(&[(16, vec![40; 1100]), (32, vec![40; 4200]), (256, png)])).unwrap();
+        // The 16px BMP frame gets no smaller as a PNG and stays a BMP
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "magick" => FakeRun::Write(2),
+            "oxipng" if cmd.args.last().unwrap().ends_with("0.png") => FakeRun::Write(2),
+            "oxipng" => FakeRun::Write(1),
+            _ => FakeRun::Keep,
+        });
+        let opts = CompressionOptions::builder().executor(tools.clone()).build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "ICO (2 of 3 frames optimized)");
+        let frames = crate::ico::frames(&fs::read(&output).unwrap()).unwrap();
+        assert_eq!(frames.iter().map(|f| f.data.len()).collect::<Vec<_>>(), [1100, 1024, 1024]);
+        assert!(tools.calls.lock().unwrap().iter().any(|c| c.program == "magick" && c.args[0].ends_with(".ico[1]")));
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_psd_composite_is_flattened_by_alpha() {
+        let (dir, input, _) = setup("mockup.psd", 0);
+        fs::write(&input, crate::psd::tests::psd(1200, 800, 4, 3)).unwrap();
+        assert_eq!(crate::psd::output_format(Path::new(&input)), Format::Png);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "magick" if cmd.args[0].ends_with(".psd[0]") => FakeRun::Write(900),
+            "oxipng" if cmd.args.contains(&"--out".to_string()) => FakeRun::Write(300),
+            _ => FakeRun::Keep,
+        });
+        let opts = CompressionOptions::builder().executor(tools.clone()).build();
+
+        let png = dir.path().join("mockup.png").to_string_lossy().to_string();
+        let result = compress_file(&input, &png, &opts).unwrap();
+        assert!(result.algorithm.starts_with("PSD → "), "{}", result.algorithm);
+        assert_eq!(tools.calls_to("magick"), 1);
+        assert!(leftover_tmp_files(&dir).is_empty());
+        assert!(compress_file(&input, &dir.path().join("copy.psd").to_string_lossy(), &opts).is_err());
+    }
+
+    #[test]
+    fn test_gif_lossy_bisection_then_frame_dropping() {
+        let (dir, input, output) = setup("loop.gif", 0);
+        let mut data = crate::gif::tests::gif(&[10; 8], &[]);
+        data.resize(1000 * 1024, 0);
+        fs::write(&input, data).unwrap();
+        let delays = Arc::new(Mutex::new(Vec::new()));
+        let seen = delays.clone();
+        // -O3 alone gives 900 KB, each --lossy step 2 KB less, dropped frames proportionally less
+        let tools = FakeTools::new(move |cmd| {
+            let lossy: u64 = cmd.args.iter().find_map(|a| a.strip_prefix("--lossy=")).map_or(0, |l| l.parse().unwrap());
+            let frames = cmd.args.iter().filter(|a| a.starts_with('#')).count() as u64;
+            if frames > 0 {
+                seen.lock().unwrap().push(arg_after(cmd, "--delay").to_string());
+            }
+            FakeRun::Write((900 - lossy * 2) * if frames > 0 { frames } else { 8 } / 8)
+        });
+
+        let opts = CompressionOptions::builder().target_kb(Some(700)).executor(tools.clone()).build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "gifsicle -O3 --lossy=100");
+        assert!(result.attempts.iter().all(|a| a.stage == 2));
+
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(300))
+            .decisions(DecisionPolicy::AlwaysAccept)
+            .executor(tools.clone())
+            .build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "gifsicle -O3 --lossy=190 (1 in 2 frames)");
+        assert_eq!(result.final_kb, 260);
+        assert_eq!(*delays.lock().unwrap(), ["20"]);
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_animations_keep_their_frames() {
+        let (dir, input, output) = setup("spinner.png", 0);
+        let mut apng = b"\x89PNG\r\n\x1a\n\0\0\0\x08acTL\0\0\0\x0c\0\0\0\0\0\0\0\0".to_vec();
+        apng.resize(500 * 1024, 0);
+        fs::write(&input, apng).unwrap();
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "oxipng" => FakeRun::Write(400),
+            "magick" if cmd.args[0].starts_with("apng:") => FakeRun::Write(arg_after(cmd, "-quality").parse::<u64>().unwrap() * 4),
+            "gif2webp" => FakeRun::Write(100),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder().target_kb(Some(300)).executor(tools.clone()).build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "oxipng (APNG, all frames kept)");
+        assert_eq!((result.final_kb, result.target_met), (400, Some(false)));
+        assert_eq!(tools.calls_to("pngquant"), 0);
+
+        let webp = dir.path().join("spinner.webp").to_string_lossy().to_string();
+        assert_eq!(compress_file(&input, &webp, &opts).unwrap().algorithm, "Animated WebP (quality 75)");
+
+        let gif = dir.path().join("loop.gif");
+        let mut data = crate::gif::tests::gif(&[10, 10], &[]);
+        data.resize(500 * 1024, 0);
+        fs::write(&gif, data).unwrap();
+        let result = compress_file(&gif.to_string_lossy(), &webp, &opts).unwrap();
+        assert!(result.algorithm.starts_with("Animated WebP"), "{}", result.algorithm);
+        assert!(tools.calls_to("gif2webp") > 0);
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_high_effort_searches_pdf_in_parallel_and_uses_zopfli() {
+        let (dir, input, output) = setup("doc.pdf", 2000);
+        let tools = FakeTools::new(|cmd| {
+            match cmd.args.iter().find_map(|a| a.strip_prefix("-dColorImageResolution=")) {
+                Some(dpi) => FakeRun::Write(dpi.parse::<u64>().unwrap() * 3),
+                None => FakeRun::Write(100),
+            }
+        });
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(600))
+            .effort(Effort::new(9))
+            .executor(tools)
+            .build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "Binary Search (200 DPI)");
+        assert_eq!(result.final_kb, 600);
+        assert!(result.attempts.len() <= 18);
+        assert!(leftover_tmp_files(&dir).is_empty());
+
+        let (_dir, input, output) = setup("logo.png", 500);
+        let oxipng = Arc::new(Mutex::new(None));
+        let seen = oxipng.clone();
+        let tools = FakeTools::new(move |cmd| {
+            if cmd.program == "oxipng" {
+                seen.lock().unwrap().get_or_insert(cmd.clone());
+            }
+            FakeRun::Write(100)
+        });
+        let opts = CompressionOptions::builder().target_kb(Some(200)).effort(Effort::new(9)).executor(tools).build();
+        compress_file(&input, &output, &opts).unwrap();
+        let oxipng = oxipng.lock().unwrap().clone().unwrap();
+        assert_eq!(arg_after(&oxipng, "-o"), "6");
+        assert!(oxipng.args.contains(&"--zopfli".to_string()));
+    }
+
+    #[test]
+    fn test_tiff_is_bundled_into_a_pdf_then_compressed() {
+        let (dir, input, _) = setup("scans.tiff", 6000);
+        let output = dir.path().join("crnched_scans.pdf").to_string_lossy().to_string();
+        // img2pdf refuses (alpha channel), so ImageMagick bundles the pages
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "img2pdf" => FakeRun::Fail,
+            "magick" => FakeRun::Write(5000),
+            _ => match cmd.args.iter().find_map(|a| a.strip_prefix("-dColorImageResolution=")) {
+                Some(dpi) => FakeRun::Write(dpi.parse::<u64>().unwrap() * 3),
+                None => FakeRun::Write(100),
+            },
+        });
+        let opts = CompressionOptions::builder().target_kb(Some(600)).executor(tools.clone()).build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "TIFF → Binary Search (200 DPI)");
+        assert_eq!((tools.calls_to("img2pdf"), tools.calls_to("magick")), (1, 1));
+        assert!(leftover_tmp_files(&dir).is_empty());
+        assert!(compress_file(&input, &dir.path().join("copy.tiff").to_string_lossy(), &opts).is_err());
+    }
+
+    #[test]
+    fn test_pdf_floor_above_target_declined_by_policy() {
+        let (dir, input, output) = setup("doc.pdf", 2000);
+        let tools = FakeTools::new(|_| FakeRun::Write(800));
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(500))
+            .decisions(DecisionPolicy::Custom(Arc::new(|_| false)))
+            .executor(tools)
+            .build();
+
+        assert!(compress_file(&input, &output, &opts).is_err());
+        assert!(!Path::new(&output).exists());
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_pdf_rasterizes_when_floor_above_target() {
+        let (dir, input, output) = setup("drawing.pdf", 2000);
+        let tools = FakeTools::new(|cmd| {
+            match cmd.args.iter().find_map(|a| a.strip_prefix("-r")).and_then(|d| d.parse::<u64>().ok()) {
+                Some(dpi) => FakeRun::Write(dpi * 4),
+                None => FakeRun::Write(1900),
+            }
+        });
+        let asked = Arc::new(Mutex::new(Vec::new()));
+        let seen = asked.clone();
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(400))
+            .decisions(DecisionPolicy::Custom(Arc::new(move |d| {
+                seen.lock().unwrap().push(d.clone());
+                true
+            })))
+            .executor(tools)
+            .build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "Rasterized Pages (100 DPI)");
+        assert_eq!(result.final_kb, 400);
+        assert_eq!(*asked.lock().unwrap(), vec![Decision::Rasterize { dpi: 100, size_kb: 400 }]);
+        assert!(leftover_tmp_files(&dir).is_empty());
+
+        // Declining falls through to the usual floor question
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(400))
+            .decisions(DecisionPolicy::Custom(Arc::new(|d| matches!(d, Decision::SaveFloor))))
+            .executor(FakeTools::new(|cmd| if cmd.args.iter().any(|a| a == "-sDEVICE=pdfimage24") { FakeRun::Write(300) } else { FakeRun::Write(1900) }))
+            .build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "Floor (Min Quality)");
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_bw_replaces_grayscale_in_png_and_pdf_chains() {
+        let (dir, input, output) = setup("form.png", 500);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "oxipng" if cmd.args.contains(&"--out".to_string()) => FakeRun::Write(400),
+            "oxipng" => FakeRun::Keep,
+            "pngquant" => FakeRun::Write(350),
+            "magick" if cmd.args.contains(&"-threshold".to_string()) => FakeRun::Write(60),
+            "magick" => FakeRun::Write(300),
+            _ => FakeRun::Fail,
+        });
+        let asked = Arc::new(Mutex::new(Vec::new()));
+        let seen = asked.clone();
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(100))
+            .bw(Some(BwMode::Threshold))
+            .decisions(DecisionPolicy::Custom(Arc::new(move |d| {
+                seen.lock().unwrap().push(d.clone());
+                true
+            })))
+            .executor(tools)
+            .build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "pngquant + Black & White");
+        assert_eq!(result.final_kb, 60);
+        assert_eq!(*asked.lock().unwrap(), vec![Decision::BlackAndWhite { size_kb: 60 }]);
+        assert!(leftover_tmp_files(&dir).is_empty());
+
+        // Scanned PDFs rasterize to Group 4 fax pages at a legible DPI
+        let (dir, input, output) = setup("receipt.pdf", 2000);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "magick" => {
+                assert!(cmd.args.contains(&"Group4".to_string()) && cmd.args.contains(&"-monochrome".to_string()));
+                FakeRun::Write(arg_after(cmd, "-density").parse::<u64>().unwrap() * 2)
+            },
+            _ => FakeRun::Write(1900),
+        });
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(400))
+            .bw(Some(BwMode::Dither))
+            .decisions(DecisionPolicy::AlwaysAccept)
+            .executor(tools)
+            .build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "Black & White Pages (200 DPI)");
+        assert_eq!(result.final_kb, 400);
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_pdf_quick_mode_runs_no_tools() {
+        let (_dir, input, output) = setup("doc.pdf", 0);
+        let pdf = format!(
+            "%PDF-1.4\n1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n\
+             2 0 obj\n<< /Type /Pages /Kids [] /Count 0 >>\nendobj\n\
+             3 0 obj\n<< /Unused ({}) >>\nendobj\n\
+             trailer\n<< /Size 4 /Root 1 0 R >>\n%%EOF\n",
+            "x".repeat(500)
+        );
+        fs::write(&input, pdf).unwrap();
+        let tools = FakeTools::new(|_| FakeRun::Fail);
+        let opts = CompressionOptions::builder()
+            .pdf_mode(PdfMode::Quick)
+            .executor(tools.clone())
+            .build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "Quick (stream recompression)");
+        assert_eq!(tools.calls_to("gs"), 0);
+        assert!(!String::from_utf8_lossy(&fs::read(&output).unwrap()).contains("/Unused"));
+    }
+
+    #[test]
+    fn test_svg_precision_search_and_svgz_output() {
+        let (dir, input, output) = setup("logo.svg", 0);
+        // 3000 numbers: 6 bytes each at 2 decimals (17 KB), 7 at 3 (20 KB)
+        let path = "12.345678 ".repeat(3000);
+        fs::write(&input, format!("<svg><!-- editor -->\n  <path d=\"{}\"/>\n</svg>\n", path.trim_end())).unwrap();
+        let tools = FakeTools::new(|_| FakeRun::Fail);
+        let opts = CompressionOptions::builder().target_kb(Some(19)).executor(tools.clone()).build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "SVG minify (precision 2)");
+        assert!(result.attempts.iter().all(|a| a.parameter == "precision"));
+        assert!(fs::read_to_string(&output).unwrap().starts_with("<svg><path d=\"12.35 12.35"));
+        assert!(leftover_tmp_files(&dir).is_empty());
+
+        let svgz = dir.path().join("logo.svgz").to_string_lossy().to_string();
+        let opts = CompressionOptions::builder().executor(tools.clone()).build();
+        let result = compress_file(&input, &svgz, &opts).unwrap();
+        assert_eq!(result.algorithm, "SVG minify (precision 3) + gzip");
+        assert!(crate::svg::read(Path::new(&svgz)).unwrap().contains("12.346 "));
+        assert_eq!(tools.calls_to("magick"), 0);
+    }
+
+    #[test]
+    fn test_jpg_preset_takes_first_extent_that_fits() {
+        let (_dir, input, output) = setup("photo.jpg", 1000);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "jpegoptim" => FakeRun::Write(1000),
+            "magick" => {
+                let extent: u64 = arg_after(cmd, "-define")
+                    .trim_start_matches("jpeg:extent=")
+                    .trim_end_matches("KB")
+                    .parse()
+                    .unwrap();
+                FakeRun::Write(extent - 10)
+            },
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder().executor(tools.clone()).build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "jpegoptim + magick (Standard Preset, target 600 KB)");
+        assert_eq!(result.final_kb, 590);
+        assert_eq!(tools.calls_to("magick"), 1);
+    }
+
+    #[test]
+    fn test_png_skipped_stages_never_run() {
+        let (_dir, input, output) = setup("image.png", 500);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "oxipng" => FakeRun::Write(400),
+            "pngquant" => FakeRun::Write(90),
+            "magick" => FakeRun::Write(90),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(100))
+            .decisions(DecisionPolicy::AlwaysAccept)
+            .skip_stages([Stage::Quantize, Stage::Grayscale, Stage::Resize])
+            .executor(tools.clone())
+            .build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(tools.calls_to("pngquant"), 0);
+        assert_eq!(tools.calls_to("magick"), 0);
+        assert_eq!(result.final_kb, 400);
+        assert_eq!(result.fallbacks, vec!["Best effort kept".to_string()]);
+    }
+
+    #[test]
+    fn test_jpg_skip_lossy_keeps_lossless_result() {
+        let (_dir, input, output) = setup("photo.jpg", 1000);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "jpegoptim" => FakeRun::Write(950),
+            _ => FakeRun::Write(100),
+        });
+        let opts = CompressionOptions::builder()
+            .skip_stages([Stage::Lossy])
+            .executor(tools.clone())
+            .build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.algorithm, "jpegoptim (Lossless)");
+        assert_eq!(result.final_kb, 950);
+        assert_eq!(tools.calls_to("magick"), 0);
+    }
+
+    #[test]
+    fn test_already_optimized_jpeg_is_skipped_without_a_target() {
+        let (_dir, input, output) = setup("photo.jpg", 10);
+        let mut jpeg = jpeg::tests::jpeg_with_quality(70);
+        jpeg.resize(500 * 1024, 0);
+        fs::write(&input, jpeg).unwrap();
+        let tools = FakeTools::new(|_| FakeRun::Write(400));
+        let opts = CompressionOptions::builder().skip_optimized(Some(10)).executor(tools.clone()).build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert!(result.skipped && result.already_optimized);
+        assert_eq!(tools.calls_to("jpegoptim"), 0);
+        assert!(!Path::new(&output).exists());
+
+        // A target still has to be met
+        let opts = CompressionOptions::builder().skip_optimized(Some(10)).target_kb(Some(450)).executor(tools.clone()).build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert!(!result.skipped);
+        assert!(tools.calls_to("jpegoptim") > 0);
+    }
+
+    #[test]
+    fn test_under_target_policy_is_format_independent() {
+        for name in ["photo.jpg", "image.png", "scan.pdf"] {
+            let (_dir, input, output) = setup(name, 100);
+            let tools = FakeTools::new(|_| FakeRun::Fail);
+            let skip = CompressionOptions::builder()
+                .target_kb(Some(200))
+                .under_target(UnderTarget::Skip)
+                .executor(tools.clone())
+                .build();
+            let result = compress_file(&input, &output, &skip).unwrap();
+            assert!(result.skipped, "{}", name);
+            assert!(!Path::new(&output).exists(), "{}", name);
+
+            let copy = CompressionOptions::builder()
+                .target_kb(Some(100))
+                .under_target(UnderTarget::Copy)
+                .executor(tools.clone())
+                .build();
+            let result = compress_file(&input, &output, &copy).unwrap();
+            assert_eq!(result.final_kb, 100, "{}", name);
+            assert_eq!(result.fallbacks, vec!["Original kept (target >= original size)"]);
+            assert_eq!(tools.calls.lock().unwrap().len(), 0, "{}", name);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_under_target_symlink_points_at_original() {
+        let (_dir, input, output) = setup("image.png", 100);
+        fs::write(&output, b"stale").unwrap();
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(150))
+            .under_target(UnderTarget::Symlink)
+            .executor(FakeTools::new(|_| FakeRun::Fail))
+            .build();
+
+        compress_file(&input, &output, &opts).unwrap();
+        assert!(fs::symlink_metadata(&output).unwrap().file_type().is_symlink());
+        assert_eq!(fs::canonicalize(&output).unwrap(), fs::canonicalize(&input).unwrap());
+    }
+
+    #[test]
+    fn test_throttled_run_keeps_tools_off_the_share() {
+        let (dir, input, output) = setup("image.png", 500);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "oxipng" => FakeRun::Write(80),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(100))
+            .io_throttle(Throttle::new(None))
+            .executor(tools.clone())
+            .build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.final_kb, 80);
+        assert_eq!(get_file_size_kb(&output), 80);
+        let share = dir.path().to_string_lossy().to_string();
+        assert!(tools.calls.lock().unwrap().iter().all(|c| c.args.iter().all(|a| !a.starts_with(&share))));
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_privacy_run_scrubs_and_verifies_output() {
+        let (dir, input, output) = setup("photo.jpg", 300);
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "jpegoptim" => FakeRun::Write(250),
+            _ => FakeRun::Write(90),
+        });
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(100))
+            .privacy(true)
+            .executor(tools.clone())
+            .build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.privacy, Some(PrivacyReport::default()));
+        let calls = tools.calls.lock().unwrap();
+        let scrub = calls.last().unwrap();
+        assert_eq!(scrub.program, "jpegoptim");
+        assert!(scrub.args.contains(&"--strip-all".to_string()) && scrub.args.contains(&output));
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_jpg_never_reencodes_above_source_quality() {
+        let (_dir, input, output) = setup("photo.jpg", 1);
+        let mut data = crate::jpeg::tests::jpeg_with_quality(60);
+        data.resize(400 * 1024, 0);
+        fs::write(&input, data).unwrap();
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "jpegoptim" => FakeRun::Write(390),
+            _ => FakeRun::Write(200),
+        });
+        let opts = CompressionOptions::builder()
+            .level(Some(CompressionLevel::Low))
+            .executor(tools.clone())
+            .build();
+
+        compress_file(&input, &output, &opts).unwrap();
+        let calls = tools.calls.lock().unwrap();
+        let magick = calls.iter().find(|c| c.program == "magick").unwrap();
+        assert_eq!(arg_after(magick, "-quality"), "60");
+    }
+
+    #[test]
+    fn test_jpg_extent_under_min_quality_misses_instead() {
+        let (_dir, input, output) = setup("photo.jpg", 1000);
+        let jpeg_at = |cmd: &ToolCommand, quality: u32, kb: usize| {
+            let mut data = crate::jpeg::tests::jpeg_with_quality(quality);
+            data.resize(kb * 1024, 0);
+            fs::write(cmd.output_path().unwrap(), data).unwrap();
+            FakeRun::Keep
+        };
+        let tools = FakeTools::new(move |cmd| match cmd.program.as_str() {
+            "jpegoptim" => FakeRun::Write(900),
+            "magick" if cmd.args.contains(&"-define".to_string()) => jpeg_at(cmd, 20, 90),
+            "magick" => jpeg_at(cmd, 60, 150),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(100))
+            .min_quality(Some(60))
+            .decisions(DecisionPolicy::NeverDestructive)
+            .executor(tools.clone())
+            .build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!((result.final_kb, result.target_met), (150, Some(false)));
+        assert_eq!(result.fallbacks[0], "Quality floor 60 kept");
+        assert_eq!(tools.calls_to("magick"), 2);
+    }
+
+    #[test]
+    fn test_png_searches_stay_above_min_quality_and_scale() {
+        let (_dir, input, output) = setup("image.png", 500);
+        let probes = Arc::new(Mutex::new(Vec::new()));
+        let seen = probes.clone();
+        let tools = FakeTools::new(move |cmd| match cmd.program.as_str() {
+            "oxipng" => FakeRun::Write(400),
+            "pngquant" => {
+                seen.lock().unwrap().push(("quality", arg_after(cmd, "--quality").split('-').next().unwrap().parse::<u64>().unwrap()));
+                FakeRun::Write(350)
+            },
+            "magick" if cmd.args.contains(&"-resize".to_string()) => {
+                seen.lock().unwrap().push(("scale", arg_after(cmd, "-resize").trim_end_matches('%').parse().unwrap()));
+                FakeRun::Write(200)
+            },
+            "magick" => FakeRun::Write(380),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(100))
+            .min_quality(Some(60))
+            .min_scale(Some(50))
+            .decisions(DecisionPolicy::AlwaysAccept)
+            .executor(tools)
+            .build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert_eq!(result.target_met, Some(false));
+        let probes = probes.lock().unwrap();
+        assert!(probes.iter().any(|&(p, _)| p == "scale"));
+        assert!(probes.iter().all(|&(p, v)| v >= if p == "quality" { 60 } else { 50 }), "{:?}", probes);
+    }
+
+    #[test]
+    fn test_oversized_images_are_downscaled_first() {
+        let (dir, input, output) = setup("poster.png", 0);
+        let png = |width: u32, height: u32, kb: usize| {
+            let mut data = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+            data.extend(width.to_be_bytes());
+            data.extend(height.to_be_bytes());
+            data.extend([8, 6, 0, 0, 0]);
+            data.resize(kb * 1024, 0);
+            data
+        };
+        fs::write(&input, png(4000, 3000, 500)).unwrap();
+        let tools = FakeTools::new(move |cmd| match cmd.program.as_str() {
+            "magick" if arg_after(cmd, "-resize") == "1920x1920>" => {
+                fs::write(cmd.output_path().unwrap(), png(1920, 1440, 200)).unwrap();
+                FakeRun::Keep
+            },
+            "oxipng" => FakeRun::Write(150),
+            _ => FakeRun::Fail,
+        });
+        // Under the size already, but not under the dimensions
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(600))
+            .max_dimensions(Some(1920), Some(1920))
+            .executor(tools.clone())
+            .build();
+
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert!(result.algorithm.starts_with("Downscaled to 1920x1440 → "), "{}", result.algorithm);
+        assert!(!result.skipped);
+        assert_eq!(result.final_kb, 150);
+        assert!(leftover_tmp_files(&dir).is_empty());
+
+        let opts = CompressionOptions::builder().target_kb(Some(300)).max_dimensions(Some(5000), None).executor(tools.clone()).build();
+        let result = compress_file(&input, &output, &opts).unwrap();
+        assert!(!result.algorithm.starts_with("Downscaled"), "{}", result.algorithm);
+        assert_eq!(tools.calls_to("magick"), 1);
+    }
+
+    #[test]
+    fn test_sizes_share_the_lossless_pass() {
+        let (dir, input, output) = setup("image.png", 500);
+        let passes = Arc::new(Mutex::new(0));
+        let counted = passes.clone();
+        let tools = FakeTools::new(move |cmd| match cmd.program.as_str() {
+            "oxipng" if cmd.args.contains(&"--out".to_string()) => {
+                *counted.lock().unwrap() += 1;
+                FakeRun::Write(400)
+            },
+            "oxipng" => FakeRun::Keep,
+            "pngquant" => FakeRun::Write(arg_after(cmd, "--quality").split('-').next().unwrap().parse::<u64>().unwrap() * 4),
+            _ => FakeRun::Fail,
+        });
+        let shared = Arc::new(crate::artifacts::Shared::new(Some(dir.path())).unwrap());
+        let small = dir.path().join("image_200k.png").to_string_lossy().to_string();
+        for (target, output) in [(450, &output), (200, &small)] {
+            let opts = CompressionOptions::builder().target_kb(Some(target)).shared(shared.clone()).executor(tools.clone()).build();
+            assert_eq!(compress_file(&input, output, &opts).unwrap().target_met, Some(true));
+        }
+        assert_eq!(*passes.lock().unwrap(), 1);
+        assert_eq!(get_file_size_kb(&output), 400);
+        assert!(get_file_size_kb(&small) <= 200);
+    }
+
+    #[test]
+    fn test_cancelled_run_cleans_up() {
+        let (dir, input, output) = setup("image.png", 500);
+        let tools = FakeTools::new(|_| FakeRun::Write(400));
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(100))
+            .executor(tools)
+            .cancel_token(cancel)
+            .build();
+
+        let err = compress_file(&input, &output, &opts).unwrap_err();
+        assert!(err.downcast_ref::<Cancelled>().is_some());
+        assert!(!Path::new(&output).exists());
+        assert!(leftover_tmp_files(&dir).is_empty());
+    }
+}
@@ -0,0 +1,199 @@
+//! User configuration from `~/.config/crnch/config.toml`.
+//!
+//! Every section is optional and a missing file is the same as an empty one.
+//!
+//! ```toml
+//! [theme]
+//! preset = "high-contrast"   # default, high-contrast or plain
+//! border = "none"            # per-role overrides, e.g. "bold magenta"
+//!
+//! [output]
+//! prefix = ""                # default "crnched_"
+//! suffix = ".min"            # photo.jpg -> photo.min.jpg
+//! template = "{stem}_{target}k.{ext}"   # instead of prefix/suffix, see --name-template
+//! dir = "~/Compressed"       # like --output-dir
+//!
+//! [jpeg]
+//! quality = 82               # without --size or --level
+//!
+//! [png]
+//! effort = 8                 # without --effort
+//!
+//! [pdf]
+//! preset = "ebook"           # screen, ebook, printer or prepress, without --size
+//!
+//! [prompts]
+//! yes = true                 # like -y
+//!
+//! [update]
+//! check = false
+//! ```
+//!
+//! Flags on the command line win over all of these.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Context, Result};
+use toml_edit::DocumentMut;
+use clap::ValueEnum;
+use crate::naming::NameTemplate;
+use crate::options::PdfPreset;
+use crate::utils;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub theme: ThemeConfig,
+    pub output: OutputConfig,
+    pub formats: FormatConfig,
+    /// Answer yes to every prompt (`[prompts] yes`), like `-y`
+    pub auto_yes: bool,
+    /// Background check for newer releases (`[update] check`)
+    pub update_check: bool,
+}
+
+/// The `[theme]` section; names are checked by whoever renders the output
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThemeConfig {
+    pub preset: Option<String>,
+    /// Role name → style spec, e.g. `warning = "bold magenta"`
+    pub styles: BTreeMap<String, String>,
+}
+
+/// The `[output]` section: how default output names are built
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OutputConfig {
+    pub prefix: Option<String>,
+    pub suffix: Option<String>,
+    pub template: Option<NameTemplate>,
+    /// Folder for outputs when neither `-o` nor `--output-dir` is given
+    pub dir: Option<PathBuf>,
+}
+
+/// The `[jpeg]`, `[png]` and `[pdf]` sections: per-format defaults
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FormatConfig {
+    pub jpeg_quality: Option<u64>,
+    pub png_effort: Option<u8>,
+    pub pdf_preset: Option<PdfPreset>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            theme: ThemeConfig::default(),
+            output: OutputConfig::default(),
+            formats: FormatConfig::default(),
+            auto_yes: false,
+            update_check: true,
+        }
+    }
+}
+
+impl Config {
+    pub fn default_location() -> Option<PathBuf> {
+        utils::config_dir().map(|dir| dir.join("config.toml"))
+    }
+
+    /// Read `path`; a file that doesn't exist yields the defaults
+    pub fn load(path: &Path) -> Result<Config> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Config::parse(&text).with_context(|| format!("Invalid config file {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(anyhow!("Cannot read {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Config> {
+        let doc: DocumentMut = text.parse()?;
+        let mut config = Config::default();
+
+        if let Some(theme) = doc.get("theme") {
+            let table = theme.as_table_like().ok_or_else(|| anyhow!("[theme] must be a table"))?;
+            for (key, value) in table.iter() {
+                let value = value.as_str().ok_or_else(|| anyhow!("theme.{} must be a string", key))?;
+                if key == "preset" {
+                    config.theme.preset = Some(value.to_string());
+                } else {
+                    config.theme.styles.insert(key.to_string(), value.to_string());
+                }
+            }
+        }
+
+        if let Some(output) = doc.get("output") {
+            let string = |key: &str| -> Result<Option<String>> {
+                match output.get(key) {
+                    Some(value) => value.as_str()
+                        .map(|v| Some(v.to_string()))
+                        .ok_or_else(|| anyhow!("output.{} must be a string", key)),
+                    None => Ok(None),
+                }
+            };
+            config.output.prefix = string("prefix")?;
+            config.output.suffix = string("suffix")?;
+            config.output.template = string("template")?.map(|t| t.parse()).transpose()?;
+            config.output.dir = string("dir")?.map(|dir| utils::expand_home(&dir));
+        }
+
+        let integer = |section: &str, key: &str, range: std::ops::RangeInclusive<i64>| -> Result<Option<i64>> {
+            match doc.get(section).and_then(|table| table.get(key)) {
+                Some(value) => value.as_integer()
+                    .filter(|n| range.contains(n))
+                    .map(Some)
+                    .ok_or_else(|| anyhow!("{}.{} must be a whole number from {} to {}", section, key, range.start(), range.end())),
+                None => Ok(None),
+            }
+        };
+        config.formats.jpeg_quality = integer("jpeg", "quality", 1..=100)?.map(|q| q as u64);
+        config.formats.png_effort = integer("png", "effort", 1..=10)?.map(|e| e as u8);
+        if let Some(preset) = doc.get("pdf").and_then(|pdf| pdf.get("preset")) {
+            let name = preset.as_str().ok_or_else(|| anyhow!("pdf.preset must be a string"))?;
+            config.formats.pdf_preset = Some(PdfPreset::from_str(name, true)
+                .map_err(|_| anyhow!("pdf.preset must be screen, ebook, printer or prepress, not '{}'", name))?);
+        }
+
+        if let Some(yes) = doc.get("prompts").and_then(|prompts| prompts.get("yes")) {
+            config.auto_yes = yes.as_bool().ok_or_else(|| anyhow!("prompts.yes must be true or false"))?;
+        }
+
+        if let Some(check) = doc.get("update").and_then(|update| update.get("check")) {
+            config.update_check = check.as_bool().ok_or_else(|| anyhow!("update.check must be true or false"))?;
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_theme_and_update() {
+        let config = Config::parse("[theme]\npreset = \"high-contrast\"\nborder = \"none\"\n\n[output]\nsuffix = \".min\"\n\n[update]\ncheck = false\n").unwrap();
+        assert_eq!(config.theme.preset.as_deref(), Some("high-contrast"));
+        assert_eq!(config.theme.styles.get("border").map(String::as_str), Some("none"));
+        assert_eq!(config.output.suffix.as_deref(), Some(".min"));
+        assert_eq!(config.output.prefix, None);
+        let templated = Config::parse("[output]\ntemplate = \"{stem}_{target}k.{ext}\"\n").unwrap();
+        assert_eq!(templated.output.template, "{stem}_{target}k.{ext}".parse().ok());
+        assert!(!config.update_check);
+    }
+
+    #[test]
+    fn test_parse_format_defaults() {
+        let config = Config::parse("[output]\ndir = \"out\"\n\n[jpeg]\nquality = 82\n\n[png]\neffort = 8\n\n[pdf]\npreset = \"ebook\"\n\n[prompts]\nyes = true\n").unwrap();
+        assert_eq!(config.formats, FormatConfig { jpeg_quality: Some(82), png_effort: Some(8), pdf_preset: Some(PdfPreset::Ebook) });
+        assert_eq!(config.output.dir, Some(PathBuf::from("out")));
+        assert!(config.auto_yes);
+        assert!(Config::parse("[jpeg]\nquality = 0\n").is_err());
+        assert!(Config::parse("[png]\neffort = \"max\"\n").is_err());
+        assert!(Config::parse("[pdf]\npreset = \"tiny\"\n").is_err());
+    }
+
+    #[test]
+    fn test_missing_sections_use_defaults() {
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+        assert!(Config::parse("[theme]\nborder = 3\n").is_err());
+        assert!(Config::parse("[output]\ntemplate = \"{name}.{ext}\"\n").is_err());
+        assert!(Config::load(Path::new("/nonexistent/crnch/config.toml")).unwrap().update_check);
+    }
+}
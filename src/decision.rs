@@ -0,0 +1,194 @@
+use std::fmt;
+use std::sync::Arc;
+use anyhow::Result;
+use dialoguer::Confirm;
+use crate::options::Stage;
+use crate::t;
+
+/// A yes/no question the engines need answered to continue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// Target is at or above the original size; copy the original unchanged?
+    KeepOriginal,
+    /// Grayscale alone reaches the target; use it?
+    Grayscale { size_kb: u64 },
+    /// Color cannot reach the target; resize the grayscale version?
+    GrayscaleResize,
+    /// `--bw`: 1-bit black and white alone reaches the target; use it?
+    BlackAndWhite { size_kb: u64 },
+    /// `--bw`: color cannot reach the target; resize the black-and-white version?
+    BlackAndWhiteResize,
+    /// Grayscale was declined; resize the color version instead?
+    ResizeColor,
+    /// Target unreachable without shrinking dimensions; resize?
+    Resize,
+    /// Even the smallest resize misses the target; save it anyway?
+    SaveSmallest,
+    /// PDF floor is above the target; save the floor version anyway?
+    SaveFloor,
+    /// PDF target missed; render every page to an image at `dpi` instead?
+    Rasterize { dpi: u64, size_kb: u64 },
+    /// Lossy output still misses the target; desaturate it?
+    GrayscaleFallback,
+    /// `--bw`: lossy output still misses the target; reduce it to black and white?
+    BlackAndWhiteFallback,
+    /// Lossy output still misses the target; shrink dimensions?
+    ResizeToFit,
+    /// GIF target missed; keep only 1 in `step` frames?
+    DropFrames { step: u64, size_kb: u64 },
+    /// Screenshot: cropping its uniform borders gets it to `size_kb`; crop?
+    CropBorders { size_kb: u64 },
+}
+
+impl Decision {
+    /// Question shown when the policy asks on the terminal
+    pub fn prompt(&self) -> String {
+        match self {
+            Decision::KeepOriginal => t!("decision-keep-original"),
+            Decision::Grayscale { size_kb } => t!("decision-grayscale", size = size_kb),
+            Decision::GrayscaleResize => t!("decision-grayscale-resize"),
+            Decision::BlackAndWhite { size_kb } => t!("decision-bw", size = size_kb),
+            Decision::BlackAndWhiteResize => t!("decision-bw-resize"),
+            Decision::ResizeColor => t!("decision-resize-color"),
+            Decision::Resize => t!("decision-resize"),
+            Decision::SaveSmallest => t!("decision-save-smallest"),
+            // Follow-ups to an earlier line, indented under it
+            Decision::SaveFloor => format!("   {}", t!("decision-save-floor")),
+            Decision::Rasterize { dpi, size_kb } => format!("   {}", t!("decision-rasterize", dpi = dpi, size = size_kb)),
+            Decision::GrayscaleFallback => format!("   {}", t!("decision-grayscale-fallback")),
+            Decision::BlackAndWhiteFallback => format!("   {}", t!("decision-bw-fallback")),
+            Decision::ResizeToFit => format!("   {}", t!("decision-resize-to-fit")),
+            Decision::DropFrames { step, size_kb } => format!("   {}", t!("decision-drop-frames", step = step, size = size_kb)),
+            Decision::CropBorders { size_kb } => format!("   {}", t!("decision-crop-borders", size = size_kb)),
+        }
+    }
+
+    /// Answer pre-selected in the prompt
+    pub fn default_answer(&self) -> bool {
+        !matches!(self, Decision::ResizeColor | Decision::Resize | Decision::ResizeToFit | Decision::Rasterize { .. } | Decision::DropFrames { .. })
+    }
+
+    /// Whether accepting throws away color or pixels
+    pub fn is_destructive(&self) -> bool {
+        matches!(
+            self,
+            Decision::Grayscale { .. }
+                | Decision::GrayscaleResize
+                | Decision::BlackAndWhite { .. }
+                | Decision::BlackAndWhiteResize
+                | Decision::BlackAndWhiteFallback
+                | Decision::ResizeColor
+                | Decision::Resize
+                | Decision::GrayscaleFallback
+                | Decision::ResizeToFit
+                | Decision::Rasterize { .. }
+                | Decision::DropFrames { .. }
+                | Decision::CropBorders { .. }
+        )
+    }
+
+    /// Pipeline stage that accepting would run, if it's one that can be skipped
+    pub fn stage(&self) -> Option<Stage> {
+        match self {
+            Decision::Grayscale { .. }
+                | Decision::GrayscaleFallback
+                | Decision::BlackAndWhite { .. }
+                | Decision::BlackAndWhiteFallback => Some(Stage::Grayscale),
+            Decision::GrayscaleResize
+                | Decision::BlackAndWhiteResize
+                | Decision::ResizeColor
+                | Decision::Resize
+                | Decision::SaveSmallest
+                | Decision::ResizeToFit
+                | Decision::CropBorders { .. } => Some(Stage::Resize),
+            Decision::Rasterize { .. } | Decision::DropFrames { .. } => Some(Stage::Lossy),
+            Decision::KeepOriginal | Decision::SaveFloor => None,
+        }
+    }
+
+    /// Short description used when a decision is taken without asking
+    pub fn summary(&self) -> String {
+        match self {
+            Decision::KeepOriginal => t!("summary-keep-original"),
+            Decision::Grayscale { .. } | Decision::GrayscaleFallback => t!("summary-grayscale"),
+            Decision::GrayscaleResize => t!("summary-grayscale-resize"),
+            Decision::BlackAndWhite { .. } | Decision::BlackAndWhiteFallback => t!("summary-bw"),
+            Decision::BlackAndWhiteResize => t!("summary-bw-resize"),
+            Decision::ResizeColor => t!("summary-resize-color"),
+            Decision::Resize => t!("summary-resize"),
+            Decision::SaveSmallest => t!("summary-save-smallest"),
+            Decision::SaveFloor => t!("summary-save-floor"),
+            Decision::Rasterize { .. } => t!("summary-rasterize"),
+            Decision::ResizeToFit => t!("summary-resize-to-fit"),
+            Decision::DropFrames { .. } => t!("summary-drop-frames"),
+            Decision::CropBorders { .. } => t!("summary-crop-borders"),
+        }
+    }
+}
+
+/// How [`Decision`]s get resolved during a run
+#[derive(Clone, Default)]
+pub enum DecisionPolicy {
+    /// Prompt on the terminal (needs a TTY)
+    #[default]
+    Ask,
+    /// Accept everything (the CLI's `-y`)
+    AlwaysAccept,
+    /// Accept only decisions that keep color and dimensions intact
+    NeverDestructive,
+    /// Let the embedder decide
+    Custom(Arc<dyn Fn(&Decision) -> bool + Send + Sync>),
+}
+
+impl fmt::Debug for DecisionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecisionPolicy::Ask => write!(f, "Ask"),
+            DecisionPolicy::AlwaysAccept => write!(f, "AlwaysAccept"),
+            DecisionPolicy::NeverDestructive => write!(f, "NeverDestructive"),
+            DecisionPolicy::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
+
+impl DecisionPolicy {
+    pub fn is_interactive(&self) -> bool {
+        matches!(self, DecisionPolicy::Ask)
+    }
+
+    pub fn resolve(&self, decision: &Decision) -> Result<bool> {
+        match self {
+            DecisionPolicy::Ask => Ok(Confirm::new()
+                .with_prompt(decision.prompt())
+                .default(decision.default_answer())
+                .interact()?),
+            DecisionPolicy::AlwaysAccept => Ok(true),
+            DecisionPolicy::NeverDestructive => Ok(!decision.is_destructive()),
+            DecisionPolicy::Custom(f) => Ok(f(decision)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_never_destructive_declines_lossy_choices() {
+        let policy = DecisionPolicy::NeverDestructive;
+        assert!(policy.resolve(&Decision::KeepOriginal).unwrap());
+        assert!(policy.resolve(&Decision::SaveFloor).unwrap());
+        assert!(!policy.resolve(&Decision::Grayscale { size_kb: 10 }).unwrap());
+        assert!(!policy.resolve(&Decision::Resize).unwrap());
+        assert!(!policy.resolve(&Decision::Rasterize { dpi: 72, size_kb: 10 }).unwrap());
+        assert!(!policy.resolve(&Decision::CropBorders { size_kb: 10 }).unwrap());
+    }
+
+    #[test]
+    fn test_custom_policy_sees_decision() {
+        let policy = DecisionPolicy::Custom(Arc::new(|d| matches!(d, Decision::Resize)));
+        assert!(policy.resolve(&Decision::Resize).unwrap());
+        assert!(!policy.resolve(&Decision::GrayscaleResize).unwrap());
+        assert!(!policy.is_interactive());
+    }
+}
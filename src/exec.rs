@@ -0,0 +1,380 @@
+use std::fs;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use anyhow::Result;
+use crate::cancel::CancellationToken;
+
+/// One external tool invocation, independent of how it gets executed.
+///
+/// Built like `std::process::Command`, so engine code reads the same.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Redirect stdout into this file (jpegoptim `--stdout`)
+    pub stdout_to: Option<String>,
+    /// Discard the tool's stderr instead of passing it through
+    pub quiet_stderr: bool,
+    /// Extra environment variables for the child
+    pub env: Vec<(String, String)>,
+}
+
+impl ToolCommand {
+    pub fn new(program: &str) -> Self {
+        ToolCommand {
+            program: program.to_string(),
+            args: Vec::new(),
+            stdout_to: None,
+            quiet_stderr: false,
+            env: Vec::new(),
+        }
+    }
+
+    pub fn arg(&mut self, arg: impl AsRef<str>) -> &mut Self {
+        self.args.push(arg.as_ref().to_string());
+        self
+    }
+
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.args.extend(args.into_iter().map(|a| a.as_ref().to_string()));
+        self
+    }
+
+    pub fn stdout_to(&mut self, path: &str) -> &mut Self {
+        self.stdout_to = Some(path.to_string());
+        self
+    }
+
+    pub fn env(&mut self, key: &str, value: impl AsRef<str>) -> &mut Self {
+        self.env.push((key.to_string(), value.as_ref().to_string()));
+        self
+    }
+
+    pub fn quiet_stderr(&mut self, quiet: bool) -> &mut Self {
+        self.quiet_stderr = quiet;
+        self
+    }
+
+    /// File this invocation writes, following each tool's conventions
+    pub fn output_path(&self) -> Option<&str> {
+        if let Some(ref p) = self.stdout_to {
+            return Some(p);
+        }
+        let after = |flag: &str| {
+            self.args.iter().position(|a| a == flag)
+                .and_then(|i| self.args.get(i + 1))
+                .map(|s| s.as_str())
+        };
+        match self.program.as_str() {
+            "gs" => self.args.iter().find_map(|a| a.strip_prefix("-sOutputFile=")),
+            "pngquant" => after("--output"),
+            "oxipng" => after("--out").or_else(|| self.args.last().map(|s| s.as_str())),
+            "darktable-cli" => self.args.get(1).map(|s| s.as_str()),
+            _ => self.args.last().map(|s| s.as_str()),
+        }
+    }
+
+    /// The invocation as one line of POSIX shell. Passwords are never
+    /// written out: they read `$CRNCH_PDF_PASSWORD` instead.
+    pub fn shell_line(&self) -> String {
+        let mut words: Vec<String> = self.env.iter().map(|(k, v)| format!("{}={}", k, shell_quote(v))).collect();
+        words.push(shell_quote(&self.program));
+        let mut secret_next = false;
+        for arg in &self.args {
+            let word = if secret_next {
+                format!("\"${}\"", PASSWORD_VAR)
+            } else if let Some(flag) = ["-sPDFPassword=", "--password="].into_iter().find(|f| arg.starts_with(f)) {
+                format!("\"{}${}\"", flag, PASSWORD_VAR)
+            } else {
+                shell_quote(arg)
+            };
+            secret_next = matches!(arg.as_str(), "-authenticate" | "--password");
+            words.push(word);
+        }
+        if let Some(ref path) = self.stdout_to {
+            words.push(format!("> {}", shell_quote(path)));
+        }
+        if self.quiet_stderr {
+            words.push("2>/dev/null".to_string());
+        }
+        words.join(" ")
+    }
+}
+
+/// Where a replayed script takes the PDF password from
+pub const PASSWORD_VAR: &str = "CRNCH_PDF_PASSWORD";
+
+fn shell_quote(word: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "_-./%=:,+@".contains(c);
+    if !word.is_empty() && word.chars().all(safe) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+/// Outcome of a finished tool run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToolStatus {
+    pub code: Option<i32>,
+}
+
+impl ToolStatus {
+    pub fn ok() -> Self {
+        ToolStatus { code: Some(0) }
+    }
+
+    pub fn failed(code: i32) -> Self {
+        ToolStatus { code: Some(code) }
+    }
+
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+/// Runs tool invocations for the engines.
+///
+/// The default [`SystemExecutor`] spawns real processes; tests swap in a
+/// fake that writes files of scripted sizes so the waterfall logic can be
+/// exercised without gs/magick/pngquant installed.
+pub trait Executor: Send + Sync {
+    fn run(&self, cmd: &ToolCommand, cancel: &CancellationToken) -> Result<ToolStatus>;
+}
+
+/// Caps handed to the child tools (`--mem-limit`, `--threads`).
+///
+/// Only flags each tool understands are added: memory applies to ImageMagick
+/// and Ghostscript, threads to ImageMagick, Ghostscript, oxipng and pngquant.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct ResourceLimits {
+    pub memory_bytes: Option<u64>,
+    pub threads: Option<u32>,
+}
+
+impl ResourceLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.memory_bytes.is_none() && self.threads.is_none()
+    }
+
+    /// Add this envelope's flags to `cmd`
+    pub fn apply(&self, cmd: &mut ToolCommand) {
+        let mut front: Vec<String> = Vec::new();
+        match cmd.program.as_str() {
+            "magick" => {
+                if let Some(bytes) = self.memory_bytes {
+                    front.extend(["-limit".into(), "memory".into(), bytes.to_string()]);
+                    front.extend(["-limit".into(), "map".into(), bytes.to_string()]);
+                }
+                if let Some(n) = self.threads {
+                    front.extend(["-limit".into(), "thread".into(), n.to_string()]);
+                }
+            },
+            "gs" => {
+                if let Some(bytes) = self.memory_bytes {
+                    front.push(format!("-dMaxBitmap={}", bytes));
+                }
+                if let Some(n) = self.threads {
+                    front.push(format!("-dNumRenderingThreads={}", n));
+                }
+            },
+            "oxipng" => {
+                if let Some(n) = self.threads {
+                    front.extend(["--threads".into(), n.to_string()]);
+                }
+            },
+            // pngquant parallelizes through OpenMP
+            "pngquant" => {
+                if let Some(n) = self.threads {
+                    cmd.env("OMP_NUM_THREADS", n.to_string());
+                }
+            },
+            _ => {},
+        }
+        // identify and other magick subcommands must stay first
+        let at = usize::from(cmd.program == "magick" && cmd.args.first().is_some_and(|a| a == "identify"));
+        cmd.args.splice(at..at, front);
+    }
+}
+
+/// Spawns the real tools found on PATH
+pub struct SystemExecutor;
+
+impl Executor for SystemExecutor {
+    fn run(&self, cmd: &ToolCommand, cancel: &CancellationToken) -> Result<ToolStatus> {
+        let mut command = Command::new(&cmd.program);
+        command.args(&cmd.args);
+        command.envs(cmd.env.iter().map(|(k, v)| (k, v)));
+        if let Some(ref path) = cmd.stdout_to {
+            command.stdout(fs::File::create(path)?);
+        }
+        if cmd.quiet_stderr {
+            command.stderr(Stdio::null());
+        }
+        let status = cancel.run(&mut command)?;
+        Ok(ToolStatus { code: status.code() })
+    }
+}
+
+/// One logged invocation; `status` is `None` when it couldn't run to the end
+struct RecordedRun {
+    cmd: ToolCommand,
+    status: Option<ToolStatus>,
+    output_bytes: Option<u64>,
+}
+
+/// Passes every invocation on to `inner` and keeps a log of it (`--emit-script`)
+pub struct RecordingExecutor {
+    inner: Arc<dyn Executor>,
+    log: Mutex<Vec<RecordedRun>>,
+}
+
+impl RecordingExecutor {
+    pub fn new(inner: Arc<dyn Executor>) -> Self {
+        RecordingExecutor { inner, log: Mutex::new(Vec::new()) }
+    }
+
+    /// Shell script replaying the recorded commands in order, each followed
+    /// by how it went; `header` lines become comments at the top
+    pub fn script(&self, header: &[String]) -> String {
+        let mut out = String::from("#!/bin/sh\n");
+        for line in header {
+            out.push_str(&format!("# {}\n", line));
+        }
+        out.push_str("# Scratch files (*.tmp*) were renamed or removed by crnch between steps.\n");
+        let log = self.log.lock().unwrap();
+        if log.iter().any(|run| run.cmd.shell_line().contains(PASSWORD_VAR)) {
+            out.push_str(&format!("# Set {} to the PDF's password before running it.\n", PASSWORD_VAR));
+        }
+        for run in log.iter() {
+            out.push('\n');
+            out.push_str(&run.cmd.shell_line());
+            out.push('\n');
+            let outcome = match (run.status, run.output_bytes) {
+                (None, _) => "# -> interrupted".to_string(),
+                (Some(s), _) if !s.success() => format!("# -> failed (exit {})", s.code.map_or("signal".to_string(), |c| c.to_string())),
+                (Some(_), Some(bytes)) => format!("# -> {} KB", bytes / 1024),
+                (Some(_), None) => "# -> ok".to_string(),
+            };
+            out.push_str(&outcome);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl Executor for RecordingExecutor {
+    fn run(&self, cmd: &ToolCommand, cancel: &CancellationToken) -> Result<ToolStatus> {
+        let status = self.inner.run(cmd, cancel);
+        self.log.lock().unwrap().push(RecordedRun {
+            cmd: cmd.clone(),
+            status: status.as_ref().ok().copied(),
+            output_bytes: cmd.output_path().and_then(|p| fs::metadata(p).ok()).map(|m| m.len()),
+        });
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_map_to_tool_flags() {
+        let limits = ResourceLimits { memory_bytes: Some(1024), threads: Some(2) };
+        let mut magick = ToolCommand::new("magick");
+        magick.args(["in.png", "out.png"]);
+        limits.apply(&mut magick);
+        assert_eq!(magick.args, ["-limit", "memory", "1024", "-limit", "map", "1024", "-limit", "thread", "2", "in.png", "out.png"]);
+        assert_eq!(magick.output_path(), Some("out.png"));
+
+        let mut gs = ToolCommand::new("gs");
+        gs.args(["-sOutputFile=o.pdf", "in.pdf"]);
+        limits.apply(&mut gs);
+        assert_eq!(gs.args, ["-dMaxBitmap=1024", "-dNumRenderingThreads=2", "-sOutputFile=o.pdf", "in.pdf"]);
+
+        let mut pq = ToolCommand::new("pngquant");
+        limits.apply(&mut pq);
+        assert_eq!(pq.env, [("OMP_NUM_THREADS".to_string(), "2".to_string())]);
+
+        let mut jo = ToolCommand::new("jpegoptim");
+        jo.arg("in.jpg");
+        limits.apply(&mut jo);
+        assert_eq!(jo.args, ["in.jpg"]);
+    }
+
+    #[test]
+    fn test_output_path_per_tool() {
+        let mut gs = ToolCommand::new("gs");
+        gs.arg("-dBATCH").arg("-sOutputFile=out.pdf").arg("in.pdf");
+        assert_eq!(gs.output_path(), Some("out.pdf"));
+
+        let mut pq = ToolCommand::new("pngquant");
+        pq.args(["--force", "--output", "q.png", "in.png"]);
+        assert_eq!(pq.output_path(), Some("q.png"));
+
+        let mut oxi = ToolCommand::new("oxipng");
+        oxi.args(["-o", "2", "--out", "o.png", "in.png"]);
+        assert_eq!(oxi.output_path(), Some("o.png"));
+        let mut oxi_in_place = ToolCommand::new("oxipng");
+        oxi_in_place.args(["-o", "2", "final.png"]);
+        assert_eq!(oxi_in_place.output_path(), Some("final.png"));
+
+        let mut jo = ToolCommand::new("jpegoptim");
+        jo.args(["--strip-all", "--stdout", "in.jpg"]).stdout_to("t.jpg");
+        assert_eq!(jo.output_path(), Some("t.jpg"));
+
+        let mut magick = ToolCommand::new("magick");
+        magick.args(["in.png", "-resize", "50%", "r.png"]);
+        assert_eq!(magick.output_path(), Some("r.png"));
+    }
+
+    #[test]
+    fn test_recorded_commands_become_a_script() {
+        struct GsFails;
+        impl Executor for GsFails {
+            fn run(&self, cmd: &ToolCommand, _: &CancellationToken) -> Result<ToolStatus> {
+                Ok(if cmd.program == "gs" { ToolStatus::failed(1) } else { ToolStatus::ok() })
+            }
+        }
+        let recorder = RecordingExecutor::new(Arc::new(GsFails));
+        let mut jo = ToolCommand::new("jpegoptim");
+        jo.args(["--max=80", "--stdout", "my photo's.jpg"]).stdout_to("t.jpg").quiet_stderr(true);
+        let mut gs = ToolCommand::new("gs");
+        gs.arg("-sOutputFile=o.pdf").arg("in.pdf");
+        for cmd in [&jo, &gs] {
+            recorder.run(cmd, &CancellationToken::new()).unwrap();
+        }
+
+        let script = recorder.script(&["crnch photo.jpg --size 200k".to_string()]);
+        assert!(script.starts_with("#!/bin/sh\n# crnch photo.jpg --size 200k\n"));
+        assert!(script.contains("\njpegoptim --max=80 --stdout 'my photo'\\''s.jpg' > t.jpg 2>/dev/null\n# -> ok\n"));
+        assert!(script.contains("\ngs -sOutputFile=o.pdf in.pdf\n# -> failed (exit 1)\n"));
+    }
+
+    #[test]
+    fn test_scripts_never_hold_passwords() {
+        let recorder = RecordingExecutor::new(Arc::new(SystemExecutor));
+        let mut gs = ToolCommand::new("gs");
+        gs.args(["-sPDFPassword=hunter2", "-sOutputFile=o.pdf", "in.pdf"]);
+        let mut magick = ToolCommand::new("magick");
+        magick.args(["-authenticate", "hunter2", "in.pdf", "p.png"]);
+        recorder.log.lock().unwrap().extend([&gs, &magick].map(|cmd| RecordedRun { cmd: cmd.clone(), status: Some(ToolStatus::ok()), output_bytes: None }));
+        let mut crnch = ToolCommand::new("crnch");
+        crnch.args(["in.pdf", "--password", "hunter2", "--emit-script", "run.sh"]);
+        let mut crnch_eq = ToolCommand::new("crnch");
+        crnch_eq.args(["in.pdf", "--password=hunter2"]);
+
+        let script = recorder.script(&[crnch.shell_line(), crnch_eq.shell_line()]);
+        assert!(!script.contains("hunter2"), "{}", script);
+        assert!(script.contains("# crnch in.pdf --password \"$CRNCH_PDF_PASSWORD\" --emit-script run.sh\n"));
+        assert!(script.contains("# crnch in.pdf \"--password=$CRNCH_PDF_PASSWORD\"\n"));
+        assert!(script.contains("# Set CRNCH_PDF_PASSWORD to the PDF's password before running it.\n"));
+        assert!(script.contains("\ngs \"-sPDFPassword=$CRNCH_PDF_PASSWORD\" -sOutputFile=o.pdf in.pdf\n"));
+        assert!(script.contains("\nmagick -authenticate \"$CRNCH_PDF_PASSWORD\" in.pdf p.png\n"));
+    }
+}
@@ -0,0 +1,114 @@
+//! Pull pages out of PDFs and frames out of multi-page TIFFs as individually
+//! compressed images (`crnch extract`).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Result};
+use crate::compression::{compress_file, gs_password_arg, CompResult};
+use crate::exec::ToolCommand;
+use crate::format::Format;
+use crate::options::CompressionOptions;
+
+/// One extracted page or frame and how its compression went
+#[derive(Debug)]
+pub struct Extracted {
+    pub path: PathBuf,
+    pub result: CompResult,
+}
+
+/// Render every page (PDF, at `dpi`) or frame (TIFF) of `input` into
+/// `out_dir` as `format` images, each compressed with `opts`
+pub fn extract(input: &str, out_dir: &Path, format: Format, dpi: u32, opts: &CompressionOptions) -> Result<Vec<Extracted>> {
+    if format == Format::Pdf {
+        return Err(anyhow!("Pages can be extracted as jpg or png, not pdf."));
+    }
+    let source = Path::new(input);
+    let ext = source.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    let stem = source.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+
+    let staging = tempfile::Builder::new().prefix("crnch-extract-").tempdir()?;
+    let pattern = staging.path().join(format!("%03d.{}", format.extension())).to_string_lossy().to_string();
+    let (mut cmd, kind) = match ext.as_str() {
+        "pdf" => {
+            let device = if format == Format::Jpg { "jpeg" } else { "png16m" };
+            let mut cmd = ToolCommand::new("gs");
+            cmd.arg(format!("-sDEVICE={}", device))
+                .arg(format!("-r{}", dpi))
+                .arg("-dJPEGQ=95")
+                .arg("-dNOPAUSE").arg("-dQUIET").arg("-dBATCH").args(gs_password_arg(opts))
+                .arg(format!("-sOutputFile={}", pattern)).arg(input);
+            (cmd, "page")
+        },
+        "tif" | "tiff" => {
+            let mut cmd = ToolCommand::new("magick");
+            cmd.arg(input).arg("+adjoin").arg("-quality").arg("95").arg(&pattern);
+            (cmd, "frame")
+        },
+        _ => return Err(anyhow!("Can only extract from PDF and TIFF files, not .{}", ext)),
+    };
+    opts.cancel.check()?;
+    opts.limits.apply(&mut cmd);
+    let status = opts.executor.run(&cmd, &opts.cancel)?;
+    if !status.success() {
+        return Err(anyhow!("{} could not split {}.", cmd.program, input));
+    }
+
+    let mut pages: Vec<PathBuf> = fs::read_dir(staging.path())?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| Format::from_path(&path.to_string_lossy()) == Some(format))
+        .collect();
+    pages.sort();
+    if pages.is_empty() {
+        return Err(anyhow!("No {}s found in {}.", kind, input));
+    }
+
+    fs::create_dir_all(out_dir)?;
+    let mut extracted = Vec::with_capacity(pages.len());
+    for (i, page) in pages.iter().enumerate() {
+        opts.cancel.check()?;
+        let dest = out_dir.join(format!("{}-{}{:03}.{}", stem, kind, i + 1, format.extension()));
+        let result = compress_file(&page.to_string_lossy(), &dest.to_string_lossy(), opts)?;
+        extracted.push(Extracted { path: dest, result });
+    }
+    Ok(extracted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::tests::{FakeRun, FakeTools};
+
+    #[test]
+    fn test_pdf_pages_become_numbered_images() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("doc.pdf");
+        fs::write(&input, b"%PDF").unwrap();
+        // gs renders three pages; every compression tool then writes 40 KB
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "gs" => {
+                let pattern = cmd.output_path().unwrap();
+                for page in 1..=3 {
+                    fs::write(pattern.replace("%03d", &format!("{:03}", page)), vec![0u8; 200 * 1024]).unwrap();
+                }
+                FakeRun::Keep
+            },
+            _ => FakeRun::Write(40),
+        });
+        let opts = CompressionOptions::builder().target_kb(Some(50)).executor(tools.clone()).build();
+
+        let out_dir = dir.path().join("pages");
+        let pages = extract(&input.to_string_lossy(), &out_dir, Format::Jpg, 150, &opts).unwrap();
+        let names: Vec<_> = pages.iter().map(|p| p.path.file_name().unwrap().to_string_lossy().to_string()).collect();
+        assert_eq!(names, ["doc-page001.jpg", "doc-page002.jpg", "doc-page003.jpg"]);
+        assert!(pages.iter().all(|p| p.result.target_met == Some(true)));
+        assert_eq!(tools.calls_to("gs"), 1);
+    }
+
+    #[test]
+    fn test_unsupported_sources_are_rejected() {
+        let opts = CompressionOptions::default();
+        assert!(extract("photo.png", Path::new("out"), Format::Jpg, 150, &opts).is_err());
+        assert!(extract("doc.pdf", Path::new("out"), Format::Pdf, 150, &opts).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! URL inputs (`crnch https://example.com/big.pdf`): downloaded to a scratch
+//! folder with a progress bar, then checked and compressed like a local file.
+
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use crnch::report::Reporter;
+use crnch::t;
+
+pub fn is_url(arg: &str) -> bool {
+    let lower = arg.to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// Download `url` into `dir`, named after the last segment of the URL it
+/// ends up at (after redirects)
+#[cfg(feature = "url-input")]
+pub fn download(url: &str, dir: &Path, reporter: &dyn Reporter) -> Result<PathBuf> {
+    use std::io::{Read, Write};
+    use anyhow::Context;
+
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(concat!("crnch/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let mut response = client.get(url).send()
+        .and_then(|r| r.error_for_status())
+        .with_context(|| format!("Cannot download {}", url))?;
+    let content_type = response.headers().get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let name = file_name(response.url().as_str(), content_type.as_deref());
+    // Two URLs can end in the same name
+    let path = crnch::utils::numbered_path(&dir.join(&name));
+
+    let mut bar = reporter.progress(response.content_length().map_or(0, |b| b / 1024), &t!("progress-downloading", file = name));
+    let mut file = std::fs::File::create(&path)?;
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut done = 0u64;
+    loop {
+        let n = response.read(&mut buf).with_context(|| format!("Download of {} was cut off", url))?;
+        if n == 0 {
+            break;
+        }
+        file.write_all(&buf[..n])?;
+        done += n as u64;
+        bar.set(done / 1024);
+    }
+    bar.finish_with_message(&t!("progress-downloaded", file = name, size = crate::logger::format_size(done / 1024)));
+    Ok(path)
+}
+
+#[cfg(not(feature = "url-input"))]
+pub fn download(url: &str, _dir: &Path, _reporter: &dyn Reporter) -> Result<PathBuf> {
+    Err(anyhow::anyhow!("Can't download {}: this crnch was built without the url-input feature.", url))
+}
+
+/// Local name for `url`: its last path segment (`download` if it has none)
+/// with anything unsafe in a file name replaced, plus the extension
+/// `content_type` implies when the name has no supported one
+#[cfg(feature = "url-input")]
+fn file_name(url: &str, content_type: Option<&str>) -> String {
+    use crnch::format::Format;
+
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let path = path.split_once("://").map_or(path, |(_, rest)| rest);
+    let segment = path.split_once('/').map_or("", |(_, p)| p).rsplit('/').next().unwrap_or("");
+    let name: String = match segment {
+        "" => "download".to_string(),
+        s => s.chars().map(|c| if c.is_ascii_alphanumeric() || "._-".contains(c) { c } else { '_' }).collect(),
+    };
+    if Format::from_path(&name).is_some() {
+        return name;
+    }
+    let ext = match content_type.and_then(|t| t.split(';').next()).map(str::trim) {
+        Some("image/jpeg") => "jpg",
+        Some("image/png") => "png",
+        Some("application/pdf") => "pdf",
+        Some("image/webp") => "webp",
+        Some("image/gif") => "gif",
+        Some("image/svg+xml") => "svg",
+        Some("image/heic") => "heic",
+        Some("image/avif") => "avif",
+        Some("image/bmp") => "bmp",
+        Some("image/tiff") => "tiff",
+        Some("video/mp4") => "mp4",
+        Some("application/zip") => "zip",
+        _ => return name,
+    };
+    format!("{}.{}", name, ext)
+}
+
+#[cfg(all(test, feature = "url-input"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name_from_url_and_type() {
+        assert_eq!(file_name("https://example.com/files/big.pdf?dl=1#p2", None), "big.pdf");
+        assert_eq!(file_name("https://cdn.example.com/img/abc123", Some("image/jpeg; charset=binary")), "abc123.jpg");
+        assert_eq!(file_name("https://example.com/", Some("image/png")), "download.png");
+        assert_eq!(file_name("http://example.com/my%20photo.jpg", None), "my_20photo.jpg");
+        assert_eq!(file_name("https://example.com/page", Some("text/html")), "page");
+    }
+
+    #[test]
+    fn test_is_url() {
+        assert!(is_url("https://example.com/a.png") && is_url("HTTP://example.com/a.png"));
+        assert!(!is_url("photos/a.png") && !is_url("ftp://example.com/a.png"));
+    }
+}
@@ -0,0 +1,170 @@
+use std::path::Path;
+use crate::{gif, webp};
+
+/// File formats the engines understand
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Format {
+    Jpg,
+    Png,
+    Pdf,
+    Webp,
+    Gif,
+    /// SVG, or gzipped `.svgz`; minified in-process
+    Svg,
+    /// HEIC/HEIF; decoded and written as JPEG unless kept
+    Heic,
+    /// AVIF; encoded with libavif's avifenc
+    Avif,
+    /// BMP; input only, written as PNG or JPEG depending on content
+    Bmp,
+    /// Camera RAW (CR2, NEF, ARW, DNG, ...); input only, developed and written as JPEG
+    Raw,
+    /// TIFF, typically a multi-page scan; input only, bundled into a PDF
+    Tiff,
+    /// Photoshop document; input only, flattened and written as PNG or JPEG
+    Psd,
+    /// Windows icon; each PNG or BMP frame optimized losslessly
+    Ico,
+    /// Word or PowerPoint package; the engines run over its embedded images
+    Office,
+    /// Zip archive; the engines run over the members they support
+    Zip,
+    /// Comic book archive: `.cbz`, or `.cbr` (RAR, written back as `.cbz`)
+    Comic,
+    /// MP4, MOV or MKV video; re-encoded with ffmpeg (x264 + AAC)
+    Video,
+}
+
+impl Format {
+    /// Match a file extension (without the dot, any case)
+    pub fn from_extension(ext: &str) -> Option<Format> {
+        match ext.to_lowercase().as_str() {
+            "jpg" | "jpeg" => Some(Format::Jpg),
+            "png" => Some(Format::Png),
+            "pdf" => Some(Format::Pdf),
+            "webp" => Some(Format::Webp),
+            "gif" => Some(Format::Gif),
+            "svg" | "svgz" => Some(Format::Svg),
+            "heic" | "heif" => Some(Format::Heic),
+            "avif" => Some(Format::Avif),
+            "bmp" => Some(Format::Bmp),
+            "tif" | "tiff" => Some(Format::Tiff),
+            "psd" => Some(Format::Psd),
+            "ico" => Some(Format::Ico),
+            "docx" | "pptx" => Some(Format::Office),
+            "zip" => Some(Format::Zip),
+            "cbz" | "cbr" => Some(Format::Comic),
+            "mp4" | "mov" | "mkv" => Some(Format::Video),
+            "cr2" | "cr3" | "nef" | "arw" | "dng" | "raf" | "orf" | "rw2" => Some(Format::Raw),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &str) -> Option<Format> {
+        Path::new(path).extension()
+            .and_then(|e| e.to_str())
+            .and_then(Format::from_extension)
+    }
+
+    /// Whether the file at `path` has more than one frame: an APNG (`acTL`
+    /// before the image data), an animated WebP or a multi-frame GIF
+    pub fn is_animated(self, path: &Path) -> bool {
+        let Ok(data) = std::fs::read(path) else { return false };
+        match self {
+            Format::Png => png_has_actl(&data),
+            Format::Webp => webp::chunks(&data).first()
+                .is_some_and(|(kind, body)| *kind == b"VP8X" && body.first().is_some_and(|f| f & webp::FLAG_ANIMATION != 0)),
+            Format::Gif => gif::frame_delays(&data).is_some_and(|d| d.len() > 1),
+            _ => false,
+        }
+    }
+
+    /// Still or animated pictures, which convert to any image format or PDF
+    pub fn is_image(self) -> bool {
+        matches!(self, Format::Jpg | Format::Png | Format::Webp | Format::Gif | Format::Heic | Format::Avif
+            | Format::Bmp | Format::Psd | Format::Raw | Format::Tiff)
+    }
+
+    /// Canonical extension used for files we create
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Jpg => "jpg",
+            Format::Png => "png",
+            Format::Pdf => "pdf",
+            Format::Webp => "webp",
+            Format::Gif => "gif",
+            Format::Svg => "svg",
+            Format::Heic => "heic",
+            Format::Avif => "avif",
+            Format::Bmp => "bmp",
+            Format::Raw => "dng",
+            Format::Tiff => "tiff",
+            Format::Psd => "psd",
+            Format::Ico => "ico",
+            Format::Office => "docx",
+            Format::Zip => "zip",
+            Format::Comic => "cbz",
+            Format::Video => "mp4",
+        }
+    }
+}
+
+fn png_has_actl(data: &[u8]) -> bool {
+    if !data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return false;
+    }
+    let mut pos = 8;
+    while pos + 8 <= data.len() {
+        let len = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        match &data[pos + 4..pos + 8] {
+            b"acTL" => return true,
+            b"IDAT" | b"IEND" => return false,
+            _ => {},
+        }
+        pos += 12 + len;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(Format::from_path("a/photo.JPEG"), Some(Format::Jpg));
+        assert_eq!(Format::from_path("scan.pdf"), Some(Format::Pdf));
+        assert_eq!(Format::from_path("photo.WebP"), Some(Format::Webp));
+        assert_eq!(Format::from_path("IMG_0001.HEIC"), Some(Format::Heic));
+        assert_eq!(Format::from_path("logo.svgz"), Some(Format::Svg));
+        assert_eq!(Format::from_path("Capture.BMP"), Some(Format::Bmp));
+        assert_eq!(Format::from_path("DSC_0042.NEF"), Some(Format::Raw));
+        assert_eq!(Format::from_path("deck.pptx"), Some(Format::Office));
+        assert_eq!(Format::from_path("attachments.ZIP"), Some(Format::Zip));
+        assert_eq!(Format::from_path("issue_01.cbr"), Some(Format::Comic));
+        assert_eq!(Format::from_path("clip.MOV"), Some(Format::Video));
+        assert_eq!(Format::from_path("scan.tif"), Some(Format::Tiff));
+        assert_eq!(Format::from_path("mockup.PSD"), Some(Format::Psd));
+        assert_eq!(Format::from_path("favicon.ico"), Some(Format::Ico));
+        assert_eq!(Format::from_path("hero.avif"), Some(Format::Avif));
+        assert!(Format::Tiff.is_image() && !Format::Pdf.is_image());
+        assert_eq!(Format::from_path("notes.txt"), None);
+        assert_eq!(Format::from_path("README"), None);
+    }
+
+    #[test]
+    fn test_is_animated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a");
+        let check = |format: Format, data: &[u8]| {
+            std::fs::write(&path, data).unwrap();
+            format.is_animated(&path)
+        };
+        assert!(check(Format::Png, b"\x89PNG\r\n\x1a\n\0\0\0\x08acTL\0\0\0\x02\0\0\0\0\0\0\0\0"));
+        assert!(!check(Format::Png, b"\x89PNG\r\n\x1a\n\0\0\0\0IDAT\0\0\0\0\0\0\0\x08acTL"));
+        assert!(check(Format::Webp, &webp::tests::webp(webp::FLAG_ANIMATION, &[])));
+        assert!(!check(Format::Webp, &webp::tests::webp(0, &[])));
+        assert!(check(Format::Gif, &gif::tests::gif(&[5, 5], &[])));
+        assert!(!check(Format::Gif, &gif::tests::gif(&[5], &[])));
+    }
+}
@@ -0,0 +1,119 @@
+//! GIF block structure, for frame timing and the checks that look inside outputs.
+
+/// A top-level block after the header and global color table
+#[derive(Debug, PartialEq)]
+pub enum Block {
+    /// Extension label (`0xF9` graphic control, `0xFE` comment, `0xFF`
+    /// application) and its sub-blocks joined
+    Extension { label: u8, data: Vec<u8> },
+    Image,
+}
+
+/// Blocks in file order; `None` for anything not GIF or cut short
+pub fn blocks(data: &[u8]) -> Option<Vec<Block>> {
+    if data.len() < 13 || !data.starts_with(b"GIF8") {
+        return None;
+    }
+    let table_len = |packed: u8| if packed & 0x80 != 0 { 3 << ((packed & 7) + 1) } else { 0 };
+    let mut pos = 13 + table_len(data[10]);
+    let mut found = Vec::new();
+    loop {
+        match *data.get(pos)? {
+            0x21 => {
+                let label = *data.get(pos + 1)?;
+                let (body, next) = sub_blocks(data, pos + 2)?;
+                found.push(Block::Extension { label, data: body });
+                pos = next;
+            },
+            0x2C => {
+                let packed = *data.get(pos + 9)?;
+                // Descriptor, local color table, LZW minimum code size
+                let (_, next) = sub_blocks(data, pos + 10 + table_len(packed) + 1)?;
+                found.push(Block::Image);
+                pos = next;
+            },
+            0x3B => return Some(found),
+            _ => return None,
+        }
+    }
+}
+
+/// Sub-blocks from `pos` joined, and the position after their terminator
+fn sub_blocks(data: &[u8], mut pos: usize) -> Option<(Vec<u8>, usize)> {
+    let mut body = Vec::new();
+    loop {
+        let len = *data.get(pos)? as usize;
+        pos += 1;
+        if len == 0 {
+            return Some((body, pos));
+        }
+        body.extend(data.get(pos..pos + len)?);
+        pos += len;
+    }
+}
+
+/// Logical screen width and height
+pub fn dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    (data.len() >= 10 && data.starts_with(b"GIF8")).then(|| (
+        u16::from_le_bytes([data[6], data[7]]) as u32,
+        u16::from_le_bytes([data[8], data[9]]) as u32,
+    ))
+}
+
+/// Each frame's delay in hundredths of a second (0 when none is set)
+pub fn frame_delays(data: &[u8]) -> Option<Vec<u16>> {
+    let mut delays = Vec::new();
+    let mut pending = 0;
+    for block in blocks(data)? {
+        match block {
+            Block::Extension { label: 0xF9, data } if data.len() >= 3 => pending = u16::from_le_bytes([data[1], data[2]]),
+            Block::Image => delays.push(std::mem::take(&mut pending)),
+            Block::Extension { .. } => {},
+        }
+    }
+    Some(delays)
+}
+
+/// Whether any frame has a transparent color index
+pub fn has_transparency(data: &[u8]) -> bool {
+    blocks(data).unwrap_or_default().iter()
+        .any(|b| matches!(b, Block::Extension { label: 0xF9, data } if data.first().is_some_and(|f| f & 1 != 0)))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// 4x3 GIF with a 2-color global table and one frame per delay; `extra`
+    /// extension blocks go before the frames
+    pub(crate) fn gif(delays: &[u16], extra: &[(u8, &[u8])]) -> Vec<u8> {
+        let mut data = b"GIF89a\x04\x00\x03\x00\x80\x00\x00".to_vec();
+        data.extend([0, 0, 0, 255, 255, 255]);
+        let extension = |data: &mut Vec<u8>, label: u8, body: &[u8]| {
+            data.extend([0x21, label, body.len() as u8]);
+            data.extend(body);
+            data.push(0);
+        };
+        for (label, body) in extra {
+            extension(&mut data, *label, body);
+        }
+        for delay in delays {
+            let [lo, hi] = delay.to_le_bytes();
+            extension(&mut data, 0xF9, &[1, lo, hi, 0]);
+            data.extend([0x2C, 0, 0, 0, 0, 4, 0, 3, 0, 0, 2, 2, 0x4C, 0x01, 0]);
+        }
+        data.push(0x3B);
+        data
+    }
+
+    #[test]
+    fn test_frames_and_delays() {
+        let data = gif(&[10, 20, 30], &[(0xFE, b"hello")]);
+        assert_eq!(dimensions(&data), Some((4, 3)));
+        assert_eq!(frame_delays(&data), Some(vec![10, 20, 30]));
+        assert!(has_transparency(&data));
+        assert_eq!(blocks(&data).unwrap()[0], Block::Extension { label: 0xFE, data: b"hello".to_vec() });
+        assert_eq!(frame_delays(&data[..data.len() - 1]), None);
+        assert_eq!(frame_delays(b"\x89PNG"), None);
+    }
+}
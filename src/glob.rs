@@ -0,0 +1,167 @@
+//! Input patterns expanded by crnch itself, so `"photos/**/*.jpg"` means the
+//! same in every shell, the matcher behind `crnch plan` rules, and the
+//! `--exclude` / `.crnchignore` patterns batch walks leave out.
+//!
+//! Matching is the `glob` crate's: `*` and `?` stay within a path segment,
+//! `**` spans segments, `[...]` is a class (`[*]` for a literal `*`), and
+//! names starting with `.` only match a pattern that spells out the dot, as
+//! in a shell. Ignore patterns are the exception: `*` there covers dotfiles
+//! too, as in `.gitignore`.
+
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Result};
+use glob::{MatchOptions, Pattern};
+
+/// Input patterns and plan rules
+const SHELL: MatchOptions = MatchOptions { case_sensitive: true, require_literal_separator: true, require_literal_leading_dot: true };
+/// `--exclude` and `.crnchignore`
+const IGNORE: MatchOptions = MatchOptions { require_literal_leading_dot: false, ..SHELL };
+
+/// Whether `text`, a `/`-separated path, matches `pattern`; a malformed
+/// pattern matches nothing
+pub fn matches(pattern: &str, text: &str) -> bool {
+    matches_with(pattern, text, SHELL)
+}
+
+fn matches_with(pattern: &str, text: &str, options: MatchOptions) -> bool {
+    Pattern::new(pattern).is_ok_and(|p| p.matches_with(text, options))
+}
+
+/// Per-directory ignore file, one pattern per line
+pub const IGNORE_FILE: &str = ".crnchignore";
+
+/// Files and folders batch walks skip. A pattern without a `/` matches any
+/// single name (`node_modules`, `*.thumb.jpg`), one with a `/` the path from
+/// the walk's root (`/build` too), and a trailing `/` only matches folders.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ignore {
+    patterns: Vec<String>,
+}
+
+impl Ignore {
+    pub fn new<S: Into<String>>(patterns: impl IntoIterator<Item = S>) -> Self {
+        Ignore { patterns: patterns.into_iter().map(Into::into).collect() }
+    }
+
+    /// These patterns plus those in `dir`'s `.crnchignore`, if it has one;
+    /// blank lines and `#` comments are skipped
+    pub fn with_file_in(&self, dir: &Path) -> Result<Ignore> {
+        let mut ignore = self.clone();
+        match std::fs::read_to_string(dir.join(IGNORE_FILE)) {
+            Ok(text) => ignore.patterns.extend(text.lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(String::from)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {},
+            Err(e) => return Err(anyhow!("Cannot read {}: {}", dir.join(IGNORE_FILE).display(), e)),
+        }
+        Ok(ignore)
+    }
+
+    /// `relative` is the path from the walk's root
+    pub fn matches(&self, relative: &Path, is_dir: bool) -> bool {
+        let text = relative.to_string_lossy().replace('\\', "/");
+        let name = text.rsplit('/').next().unwrap_or(&text);
+        self.patterns.iter().any(|pattern| {
+            let (pattern, dir_only) = match pattern.strip_suffix('/') {
+                Some(p) => (p, true),
+                None => (pattern.as_str(), false),
+            };
+            if dir_only && !is_dir {
+                return false;
+            }
+            match pattern.strip_prefix('/') {
+                Some(anchored) => matches_with(anchored, &text, IGNORE),
+                None if pattern.contains('/') => matches_with(pattern, &text, IGNORE),
+                None => matches_with(pattern, name, IGNORE),
+            }
+        })
+    }
+}
+
+/// Files `pattern` matches, sorted, less what `ignore` (with the walked
+/// folder's `.crnchignore`) leaves out; `None` when it has no wildcards or names
+/// an existing file as written. No match at all is an error, like a missing file.
+pub fn expand(pattern: &str, ignore: &Ignore) -> Result<Option<Vec<PathBuf>>> {
+    if !pattern.contains(['*', '?', '[']) || Path::new(pattern).exists() {
+        return Ok(None);
+    }
+    let pattern = pattern.replace('\\', "/");
+    // Ignore patterns are relative to the last directory before the first wildcard
+    let literal = pattern[..pattern.find(['*', '?', '[']).unwrap_or(0)].rfind('/').map_or(0, |i| i + 1);
+    let base = &pattern[..literal];
+    let root = if base.is_empty() { Path::new(".") } else { Path::new(base) };
+    let ignore = ignore.with_file_in(root)?;
+
+    // Only `**` descends, so "*.jpg" reads one folder
+    let paths = glob::glob_with(&pattern, SHELL).map_err(|e| anyhow!("Invalid pattern '{}': {}", pattern, e))?;
+    let mut found: Vec<PathBuf> = paths.flatten()
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            // The file, and every folder on the way to it
+            !relative.ancestors()
+                .filter(|a| !a.as_os_str().is_empty())
+                .any(|a| ignore.matches(a, a != relative))
+        })
+        .collect();
+    if found.is_empty() {
+        return Err(anyhow!("No files match '{}'.", pattern));
+    }
+    found.sort();
+    Ok(Some(found))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_segments() {
+        assert!(matches("a/**/b.pdf", "a/b.pdf") && matches("a/**/b.pdf", "a/x/y/b.pdf"));
+        assert!(!matches("a/**/b.pdf", "a/xb.pdf"));
+        assert!(matches("*.jpg", "cat.jpg") && !matches("*.jpg", "dir/cat.jpg"));
+        assert!(matches("img?.png", "img1.png") && !matches("img?.png", "img/.png"));
+        assert!(matches("scan[0-9].pdf", "scan7.pdf") && !matches("scan[0-9].pdf", "scanX.pdf"));
+        assert!(matches("[*].txt", "*.txt") && !matches("[*].txt", "a.txt"));
+        assert!(!matches("*.jpg", ".hidden.jpg") && matches(".*.jpg", ".hidden.jpg"));
+        assert!(Ignore::new(["*.jpg"]).matches(Path::new(".hidden.jpg"), false));
+    }
+
+    fn expand_all(pattern: &str) -> Result<Option<Vec<PathBuf>>> {
+        expand(pattern, &Ignore::default())
+    }
+
+    #[test]
+    fn test_ignore_patterns() {
+        let ignore = Ignore::new(["node_modules", "*.thumb.jpg", "cache/", "/build", "assets/raw/**"]);
+        assert!(ignore.matches(Path::new("web/node_modules"), true));
+        assert!(ignore.matches(Path::new("a/b/cat.thumb.jpg"), false));
+        assert!(ignore.matches(Path::new("x/cache"), true) && !ignore.matches(Path::new("x/cache"), false));
+        assert!(ignore.matches(Path::new("build"), true) && !ignore.matches(Path::new("src/build"), true));
+        assert!(ignore.matches(Path::new("assets/raw/a/b.png"), false) && !ignore.matches(Path::new("assets/b.png"), false));
+        assert!(!ignore.matches(Path::new("cat.jpg"), false));
+    }
+
+    #[test]
+    fn test_expand_walks_from_the_literal_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("photos");
+        fs::create_dir_all(root.join("2024/trip")).unwrap();
+        for name in ["a.jpg", "b.png", "2024/c.jpg", "2024/trip/d.jpg"] {
+            fs::write(root.join(name), b"x").unwrap();
+        }
+        let base = root.to_string_lossy().replace('\\', "/");
+        let found = expand_all(&format!("{}/**/*.jpg", base)).unwrap().unwrap();
+        assert_eq!(found, [root.join("2024/c.jpg"), root.join("2024/trip/d.jpg"), root.join("a.jpg")]);
+        assert_eq!(expand_all(&format!("{}/*.jpg", base)).unwrap().unwrap(), [root.join("a.jpg")]);
+        assert!(expand_all(&format!("{}/*.gif", base)).is_err());
+        assert!(expand_all(&format!("{}/a.jpg", base)).unwrap().is_none());
+        assert_eq!(expand_all(&format!("{}/[ab].*", base)).unwrap().unwrap(), [root.join("a.jpg"), root.join("b.png")]);
+
+        fs::write(root.join(IGNORE_FILE), "# trips are done\ntrip/\n").unwrap();
+        let found = expand(&format!("{}/**/*.jpg", base), &Ignore::new(["a.*"])).unwrap().unwrap();
+        assert_eq!(found, [root.join("2024/c.jpg")]);
+    }
+}
@@ -0,0 +1,174 @@
+//! Per-file result store: one JSON line per finished run.
+//!
+//! Shell loops are crnch's batch mode, so the history is what ties their runs
+//! together afterwards, e.g. for `crnch review`.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use crate::compression::CompResult;
+use crate::retarget::Setting;
+
+/// Runs kept before the oldest are dropped
+const MAX_RECORDS: usize = 1000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RunRecord {
+    pub finished_at: u64,
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub algorithm: String,
+    pub original_kb: u64,
+    pub final_kb: u64,
+    pub target_kb: Option<u64>,
+    /// The exact limit when the target was given in bytes
+    #[serde(default)]
+    pub target_bytes: Option<u64>,
+    pub target_met: Option<bool>,
+    /// SHA-256 of the output as written, for `crnch verify`
+    #[serde(default)]
+    pub output_sha256: Option<String>,
+    /// Winning search setting, the starting point when the output is re-targeted
+    #[serde(default)]
+    pub setting: Option<Setting>,
+    /// Quality (1-100) the output was saved at: estimated from a JPEG's
+    /// tables, else the searched quality
+    #[serde(default)]
+    pub quality: Option<u32>,
+    /// Resolution a PDF's images were downsampled to
+    #[serde(default)]
+    pub dpi: Option<u32>,
+}
+
+impl RunRecord {
+    pub fn new(input: &str, output: &str, result: &CompResult) -> Self {
+        RunRecord {
+            finished_at: now(),
+            // Absolute, so `crnch review` works from any directory
+            input: std::path::absolute(input).unwrap_or_else(|_| PathBuf::from(input)),
+            output: std::path::absolute(output).unwrap_or_else(|_| PathBuf::from(output)),
+            algorithm: result.algorithm.clone(),
+            original_kb: result.original_kb,
+            final_kb: result.final_kb,
+            target_kb: result.target_kb,
+            target_bytes: result.target_bytes,
+            target_met: result.target_met,
+            output_sha256: crate::verify::sha256_file(Path::new(output)).ok(),
+            quality: crate::jpeg::estimate_quality(Path::new(output)).or_else(|| searched(result, "quality")),
+            dpi: searched(result, "dpi"),
+            setting: Setting::from_result(result),
+        }
+    }
+
+    /// Whether this run asked for a size and didn't get it
+    pub fn missed_target(&self) -> bool {
+        self.target_met == Some(false)
+    }
+
+    /// Whether the output's quality or resolution ended under these floors
+    pub fn below_floor(&self, min_quality: Option<u32>, min_dpi: Option<u32>) -> bool {
+        let under = |value: Option<u32>, min: Option<u32>| value.zip(min).is_some_and(|(value, min)| value < min);
+        under(self.quality, min_quality) || under(self.dpi, min_dpi)
+    }
+}
+
+/// The `parameter` setting the run settled on
+fn searched(result: &CompResult, parameter: &str) -> Option<u32> {
+    Setting::from_result(result).filter(|s| s.parameter == parameter).and_then(|s| u32::try_from(s.value).ok())
+}
+
+pub struct History {
+    file: PathBuf,
+}
+
+impl History {
+    pub fn new(file: impl Into<PathBuf>) -> Self {
+        History { file: file.into() }
+    }
+
+    /// Per-user history under the crnch cache directory
+    pub fn default_location() -> Option<Self> {
+        crate::utils::cache_dir().map(|d| History::new(d.join("history.jsonl")))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn append(&self, record: &RunRecord) -> Result<()> {
+        if let Some(dir) = self.file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.file)?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        drop(file);
+
+        let records = self.load();
+        if records.len() > MAX_RECORDS {
+            let kept: Vec<String> = records[records.len() - MAX_RECORDS..].iter()
+                .filter_map(|r| serde_json::to_string(r).ok())
+                .collect();
+            fs::write(&self.file, kept.join("\n") + "\n")?;
+        }
+        Ok(())
+    }
+
+    /// Every readable record, oldest first; unreadable lines are skipped
+    pub fn load(&self) -> Vec<RunRecord> {
+        let Ok(text) = fs::read_to_string(&self.file) else { return Vec::new() };
+        text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect()
+    }
+
+    /// Records finished within the last `secs` seconds
+    pub fn since(&self, secs: u64) -> Vec<RunRecord> {
+        let cutoff = now().saturating_sub(secs);
+        self.load().into_iter().filter(|r| r.finished_at >= cutoff).collect()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(input: &str, target_met: Option<bool>) -> RunRecord {
+        let result = CompResult { target_kb: Some(100), target_met, ..CompResult::default() };
+        RunRecord::new(input, &format!("crnched_{}", input), &result)
+    }
+
+    #[test]
+    fn test_append_and_filter_recent() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = History::new(dir.path().join("sub").join("history.jsonl"));
+        let mut old = record("old.png", Some(false));
+        old.finished_at = 0;
+        history.append(&old).unwrap();
+        history.append(&record("a.png", Some(true))).unwrap();
+        history.append(&record("b.png", Some(false))).unwrap();
+
+        assert_eq!(history.load().len(), 3);
+        let missed: Vec<_> = history.since(3600).into_iter().filter(|r| r.missed_target()).collect();
+        assert_eq!(missed.len(), 1);
+        assert!(missed[0].input.is_absolute() && missed[0].input.ends_with("b.png"));
+    }
+
+    #[test]
+    fn test_quality_and_dpi_floors() {
+        let hit = |parameter, value| crate::progress::Attempt { stage: 1, index: 1, max: 8, parameter, value, size_kb: 90, target_kb: 100, time_ms: 0 };
+        let photo = CompResult { attempts: vec![hit("quality", 35)], ..CompResult::default() };
+        let record = RunRecord::new("photo.webp", "crnched_photo.webp", &photo);
+        assert_eq!((record.quality, record.dpi), (Some(35), None));
+        assert!(record.below_floor(Some(50), Some(100)) && !record.below_floor(Some(30), None));
+
+        let scan = CompResult { attempts: vec![hit("dpi", 150)], ..CompResult::default() };
+        let record = RunRecord::new("scan.pdf", "crnched_scan.pdf", &scan);
+        assert_eq!((record.quality, record.dpi), (None, Some(150)));
+        assert!(record.below_floor(None, Some(200)) && !record.below_floor(Some(100), Some(100)));
+    }
+}
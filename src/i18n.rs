@@ -0,0 +1,145 @@
+//! Message catalog for user-facing text.
+//!
+//! Messages live in `locales/<lang>.ftl`, compiled in, in the plain
+//! `key = text` subset of Fluent with `{ $name }` placeables. A key missing
+//! from the active language falls back to English, and a key missing from
+//! English prints as itself, so a partial translation is always usable.
+//! Adding a language is one `.ftl` file and one line in [`LOCALES`].
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Compiled-in catalogs; English first, it's the fallback
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+];
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+#[derive(Debug, Default)]
+pub struct Catalog {
+    lang: String,
+    messages: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Catalog {
+    /// Catalog for `lang` (`de`, `de_DE.UTF-8`, ...); unknown languages get English
+    pub fn new(lang: &str) -> Catalog {
+        let lang = normalize(lang);
+        let source = LOCALES.iter().find(|(code, _)| *code == lang);
+        Catalog {
+            lang: source.map_or("en", |(code, _)| code).to_string(),
+            messages: source.map(|(_, text)| parse(text)).unwrap_or_default(),
+            fallback: parse(LOCALES[0].1),
+        }
+    }
+
+    pub fn lang(&self) -> &str {
+        &self.lang
+    }
+
+    /// `key` with its `{ $name }` placeables filled from `args`
+    pub fn format(&self, key: &str, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+        let Some(template) = self.messages.get(key).or_else(|| self.fallback.get(key)) else {
+            return key.to_string();
+        };
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find("{ $") {
+            out.push_str(&rest[..start]);
+            let Some(end) = rest[start..].find('}') else { break };
+            let name = rest[start + 3..start + end].trim();
+            match args.iter().find(|(n, _)| *n == name) {
+                Some((_, value)) => out.push_str(&value.to_string()),
+                None => out.push_str(&rest[start..=start + end]),
+            }
+            rest = &rest[start + end + 1..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+/// Select the process-wide language; first call wins. `None` reads the
+/// environment (`LC_ALL`, `LC_MESSAGES`, `LANG`).
+pub fn set_language(lang: Option<&str>) {
+    let lang = lang.map(str::to_string).unwrap_or_else(|| {
+        ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|v| !v.is_empty())
+            .unwrap_or_default()
+    });
+    let _ = CATALOG.set(Catalog::new(&lang));
+}
+
+/// The active catalog, English unless [`set_language`] chose otherwise
+pub fn catalog() -> &'static Catalog {
+    CATALOG.get_or_init(|| Catalog::new("en"))
+}
+
+/// Languages with a compiled-in catalog
+pub fn available() -> impl Iterator<Item = &'static str> {
+    LOCALES.iter().map(|(code, _)| *code)
+}
+
+/// Look up a message: `t!("error-not-found", file = path)`
+#[macro_export]
+macro_rules! t {
+    ($key:expr) => {
+        $crate::i18n::catalog().format($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::catalog().format($key, &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+])
+    };
+}
+
+/// `de_DE.UTF-8` -> `de`; `C` and `POSIX` are English
+fn normalize(lang: &str) -> String {
+    let code: String = lang.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+    match code.to_ascii_lowercase().as_str() {
+        "c" | "posix" | "" => "en".to_string(),
+        code => code.to_string(),
+    }
+}
+
+/// `key = text` lines; `#` comments and blank lines skipped. Leading spaces
+/// after the `=` are kept past the first, for indented follow-up lines.
+fn parse(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.strip_prefix(' ').unwrap_or(value).to_string()))
+        .filter(|(key, _)| !key.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeables_and_fallback() {
+        let en = Catalog::new("C");
+        assert_eq!(en.lang(), "en");
+        assert_eq!(en.format("error-not-found", &[("file", &"a.png")]), "File 'a.png' not found.");
+        assert_eq!(en.format("tip-not-found-path", &[]), "     Use absolute path or relative path from current directory.");
+        assert_eq!(en.format("no-such-key", &[]), "no-such-key");
+
+        let de = Catalog::new("de_DE.UTF-8");
+        assert_eq!(de.lang(), "de");
+        assert_eq!(de.format("prompt-overwrite", &[("file", &"a.png")]), "a.png überschreiben?");
+        assert_eq!(Catalog::new("xx").lang(), "en");
+    }
+
+    #[test]
+    fn test_every_translation_key_exists_in_english() {
+        let en = parse(LOCALES[0].1);
+        for (code, text) in &LOCALES[1..] {
+            for key in parse(text).keys() {
+                assert!(en.contains_key(key), "{}: {} has no English message", code, key);
+            }
+        }
+    }
+}
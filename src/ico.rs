@@ -0,0 +1,93 @@
+//! Windows icons (`.ico`): the directory of frames, each a PNG or a headerless
+//! BMP (DIB), taken apart for the PNG engine and put back together.
+
+/// One image in the icon, with its 16-byte directory entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    entry: [u8; 16],
+    pub data: Vec<u8>,
+}
+
+impl Frame {
+    pub fn is_png(&self) -> bool {
+        self.data.starts_with(b"\x89PNG\r\n\x1a\n")
+    }
+
+    /// Width and height; 0 in the directory means 256
+    pub fn size(&self) -> (u32, u32) {
+        let side = |b: u8| if b == 0 { 256 } else { b as u32 };
+        (side(self.entry[0]), side(self.entry[1]))
+    }
+}
+
+/// Every frame in directory order; `None` if the directory or a frame is cut off
+pub fn frames(data: &[u8]) -> Option<Vec<Frame>> {
+    let u16_at = |p: usize| data.get(p..p + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize);
+    let u32_at = |p: usize| data.get(p..p + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+    // Reserved 0, type 1 (icon; 2 is a cursor)
+    if u16_at(0)? != 0 || u16_at(2)? != 1 {
+        return None;
+    }
+    let count = u16_at(4)?;
+    if count == 0 {
+        return None;
+    }
+    (0..count)
+        .map(|i| {
+            let at = 6 + i * 16;
+            let entry: [u8; 16] = data.get(at..at + 16)?.try_into().ok()?;
+            let (size, offset) = (u32_at(at + 8)?, u32_at(at + 12)?);
+            Some(Frame { entry, data: data.get(offset..offset + size)?.to_vec() })
+        })
+        .collect()
+}
+
+pub fn is_complete(data: &[u8]) -> bool {
+    frames(data).is_some()
+}
+
+/// The icon with `frames` packed right after the directory
+pub fn to_bytes(frames: &[Frame]) -> Vec<u8> {
+    let mut out = vec![0, 0, 1, 0];
+    out.extend((frames.len() as u16).to_le_bytes());
+    let mut offset = 6 + 16 * frames.len();
+    for frame in frames {
+        out.extend(&frame.entry[..8]);
+        out.extend((frame.data.len() as u32).to_le_bytes());
+        out.extend((offset as u32).to_le_bytes());
+        offset += frame.data.len();
+    }
+    for frame in frames {
+        out.extend(&frame.data);
+    }
+    out
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// An icon of `frames` given as (side, data); sides of 256 are written as 0
+    pub(crate) fn ico(frames: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let frames: Vec<Frame> = frames.iter().map(|(side, data)| {
+            let mut entry = [0u8; 16];
+            entry[0] = (*side % 256) as u8;
+            entry[1] = (*side % 256) as u8;
+            entry[4] = 1;
+            entry[6] = 32;
+            Frame { entry, data: data.clone() }
+        }).collect();
+        to_bytes(&frames)
+    }
+
+    #[test]
+    fn test_frames_round_trip() {
+        let png = [&b"\x89PNG\r\n\x1a\n"[..], &[7; 40]].concat();
+        let data = ico(&[(16, vec![40, 0, 0, 0, 1, 2, 3]), (256, png)]);
+        let parsed = frames(&data).unwrap();
+        assert_eq!((parsed[0].size(), parsed[1].size()), ((16, 16), (256, 256)));
+        assert!(!parsed[0].is_png() && parsed[1].is_png());
+        assert_eq!(to_bytes(&parsed), data);
+        assert!(!is_complete(&data[..data.len() - 1]) && !is_complete(b"\0\0\x02\0\x01\0"));
+    }
+}
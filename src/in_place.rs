@@ -0,0 +1,156 @@
+//! `--in-place`: replace the input with its compressed version.
+//!
+//! The output is written to a scratch folder next to the input, decoded by
+//! [`verify::verify_file`], and only then renamed over the original, so a run
+//! that fails or is cancelled at any point leaves the source as it was.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Context, Result};
+use crate::compression::{compress_file, CompResult};
+use crate::options::CompressionOptions;
+use crate::{utils, verify};
+
+#[derive(Debug, Clone)]
+pub struct InPlace {
+    pub result: CompResult,
+    /// Where the original was kept, if it was replaced and backed up
+    pub backup: Option<PathBuf>,
+}
+
+/// Compress `input` over itself, keeping the original as `<input>.bak`
+/// (numbered if one exists) when `backup` is set
+pub fn compress_in_place(input: &Path, backup: bool, opts: &CompressionOptions) -> Result<InPlace> {
+    // Through a symlink, the file it points to is the one to replace; the
+    // rename would otherwise swap the link itself for a regular file
+    let resolved;
+    let input = if fs::symlink_metadata(input).is_ok_and(|m| m.file_type().is_symlink()) {
+        resolved = fs::canonicalize(input).with_context(|| format!("Cannot resolve the link {}", input.display()))?;
+        &resolved
+    } else {
+        input
+    };
+    let name = input.file_name().ok_or_else(|| anyhow!("'{}' is not a file.", input.display()))?;
+    let folder = input.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    // Same filesystem as the input, so the final rename is atomic
+    let scratch = tempfile::Builder::new().prefix(".crnch-").tempdir_in(folder)
+        .with_context(|| format!("Cannot create a scratch folder in {}", folder.display()))?;
+    let output = scratch.path().join(name);
+
+    let result = compress_file(&input.to_string_lossy(), &output.to_string_lossy(), opts)?;
+    // A link to the original, or the original copied back, has nothing to
+    // swap in; renaming a link over its own target would lose the file
+    let linked = fs::symlink_metadata(&output).is_ok_and(|m| m.file_type().is_symlink());
+    if result.skipped || result.not_worth || linked || !output.exists() {
+        return Ok(InPlace { result, backup: None });
+    }
+    let verdict = verify::verify_file(&output, None, opts)?;
+    if !verdict.ok() {
+        let problems: Vec<String> = verdict.problems.iter().map(|p| p.to_string()).collect();
+        return Err(anyhow!("The compressed file {}; {} was left untouched.", problems.join("; "), input.display()));
+    }
+    fs::set_permissions(&output, fs::metadata(input)?.permissions())?;
+
+    let backup = if backup {
+        let path = utils::numbered_path(&PathBuf::from(format!("{}.bak", input.display())));
+        fs::hard_link(input, &path).or_else(|_| fs::copy(input, &path).map(|_| ()))
+            .with_context(|| format!("Cannot back up {} to {}", input.display(), path.display()))?;
+        Some(path)
+    } else {
+        None
+    };
+    fs::rename(&output, input).with_context(|| format!("Cannot replace {}", input.display()))?;
+    Ok(InPlace { result, backup })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::tests::{FakeRun, FakeTools};
+
+    fn leftovers(dir: &Path) -> Vec<String> {
+        fs::read_dir(dir).unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|n| n.starts_with(".crnch-"))
+            .collect()
+    }
+
+    #[test]
+    fn test_replaces_the_input_and_keeps_a_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("logo.svg");
+        let original = format!("<svg xmlns=\"http://www.w3.org/2000/svg\">{}<rect width=\"10\" height=\"10\"/></svg>\n", "<!-- note -->\n".repeat(200));
+        fs::write(&input, &original).unwrap();
+        let opts = CompressionOptions::builder().executor(FakeTools::new(|_| FakeRun::Keep)).build();
+
+        let done = compress_in_place(&input, true, &opts).unwrap();
+        assert_eq!(done.backup.as_deref(), Some(dir.path().join("logo.svg.bak").as_path()));
+        assert_eq!(fs::read_to_string(dir.path().join("logo.svg.bak")).unwrap(), original);
+        assert!(fs::metadata(&input).unwrap().len() < original.len() as u64);
+        assert!(leftovers(dir.path()).is_empty());
+
+        // A second run doesn't overwrite the first backup
+        fs::write(&input, &original).unwrap();
+        let done = compress_in_place(&input, true, &opts).unwrap();
+        assert_eq!(done.backup.as_deref(), Some(dir.path().join("logo.svg(2).bak").as_path()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_under_target_keeps_the_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("image.png");
+        fs::write(&input, vec![3u8; 4 * 1024]).unwrap();
+        let opts = CompressionOptions::builder()
+            .target_kb(Some(8))
+            .under_target(crate::options::UnderTarget::Symlink)
+            .executor(FakeTools::new(|_| FakeRun::Fail))
+            .build();
+
+        for backup in [true, false] {
+            let done = compress_in_place(&input, backup, &opts).unwrap();
+            assert!(done.backup.is_none());
+            assert!(!fs::symlink_metadata(&input).unwrap().file_type().is_symlink());
+            assert_eq!(fs::read(&input).unwrap(), vec![3u8; 4 * 1024]);
+            assert!(leftovers(dir.path()).is_empty());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_input_replaces_its_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("logo.svg");
+        let link = dir.path().join("link.svg");
+        let original = format!("<svg xmlns=\"http://www.w3.org/2000/svg\">{}<rect width=\"10\" height=\"10\"/></svg>\n", "<!-- note -->\n".repeat(200));
+        fs::write(&target, &original).unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        let opts = CompressionOptions::builder().executor(FakeTools::new(|_| FakeRun::Keep)).build();
+
+        let done = compress_in_place(&link, true, &opts).unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert!(fs::metadata(&target).unwrap().len() < original.len() as u64);
+        let backup = done.backup.unwrap();
+        assert_eq!(backup.file_name().unwrap(), "logo.svg.bak");
+        assert!(!fs::symlink_metadata(&backup).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&backup).unwrap(), original);
+        assert!(leftovers(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_failed_verification_leaves_the_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("photo.jpg");
+        fs::write(&input, vec![7u8; 16 * 1024]).unwrap();
+        // Zero-filled output has no JPEG end marker; the decode check writes nothing
+        let opts = CompressionOptions::builder().executor(FakeTools::new(|cmd| match cmd.args.last().map(String::as_str) {
+            Some("null:") => FakeRun::Keep,
+            _ => FakeRun::Write(4),
+        })).build();
+
+        assert!(compress_in_place(&input, true, &opts).is_err());
+        assert_eq!(fs::read(&input).unwrap(), vec![7u8; 16 * 1024]);
+        assert!(!dir.path().join("photo.jpg.bak").exists());
+        assert!(leftovers(dir.path()).is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+//! Crash journal: one small file per running compression, removed when the run ends.
+//!
+//! An entry that outlives its process means that run died (crash, `kill -9`,
+//! power loss) and may have left scratch files or a half-written output
+//! behind. The journal finds those, and refuses to start a second run on an
+//! output another live process is still writing.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use crate::options::{find_scratch_files, CompressionOptions};
+
+/// What a run records before it touches the filesystem
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub pid: u32,
+    pub started_at: u64,
+    pub input: PathBuf,
+    pub output: PathBuf,
+    /// Where this run's scratch files go (see `CompressionOptions::scratch_dir`)
+    pub scratch_dir: PathBuf,
+    /// Whether `output` existed before the run; if not, a leftover one is partial
+    pub output_existed: bool,
+}
+
+impl JournalEntry {
+    pub fn new(input: &str, output: &str, opts: &CompressionOptions) -> Self {
+        JournalEntry {
+            pid: std::process::id(),
+            started_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            input: PathBuf::from(input),
+            output: PathBuf::from(output),
+            scratch_dir: opts.scratch_dir(output),
+            output_existed: Path::new(output).exists(),
+        }
+    }
+
+    /// Scratch files currently on disk for this entry's output
+    pub fn scratch_files(&self) -> Vec<PathBuf> {
+        let Some(name) = self.output.file_name() else { return Vec::new() };
+        find_scratch_files(&self.scratch_dir, &name.to_string_lossy())
+    }
+
+    fn same_output(&self, other: &JournalEntry) -> bool {
+        let canonical = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+        canonical(&self.output) == canonical(&other.output)
+    }
+}
+
+/// A run interrupted before it could clean up after itself
+#[derive(Debug, Clone)]
+pub struct Leftover {
+    pub entry: JournalEntry,
+    pub scratch_files: Vec<PathBuf>,
+    /// Output the run created but may not have finished writing
+    pub partial_output: Option<PathBuf>,
+    file: PathBuf,
+}
+
+/// Directory of journal entries
+pub struct Journal {
+    dir: PathBuf,
+}
+
+impl Journal {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Journal { dir: dir.into() }
+    }
+
+    /// Per-user journal under the crnch cache directory
+    pub fn default_location() -> Option<Self> {
+        crate::utils::cache_dir().map(|d| Journal::new(d.join("journal")))
+    }
+
+    /// Record a run. Fails if a live process is already writing the same output.
+    pub fn begin(&self, entry: JournalEntry) -> Result<JournalGuard> {
+        if let Some(other) = self.entries().into_iter()
+            .map(|(_, e)| e)
+            .find(|e| e.pid != entry.pid && process_alive(e.pid) && e.same_output(&entry))
+        {
+            return Err(anyhow!(
+                "Another crnch (pid {}) is already writing {}.",
+                other.pid, other.output.display()
+            ));
+        }
+        fs::create_dir_all(&self.dir)?;
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+        let file = self.dir.join(format!("{}-{}.json", entry.pid, nanos));
+        fs::write(&file, serde_json::to_vec_pretty(&entry)?)?;
+        Ok(JournalGuard { file })
+    }
+
+    /// Entries whose process is gone, with whatever they left behind
+    pub fn leftovers(&self) -> Vec<Leftover> {
+        self.entries().into_iter()
+            .filter(|(_, e)| !process_alive(e.pid))
+            .map(|(file, entry)| Leftover {
+                scratch_files: entry.scratch_files(),
+                partial_output: (!entry.output_existed && entry.output.exists()).then(|| entry.output.clone()),
+                entry,
+                file,
+            })
+            .collect()
+    }
+
+    /// Delete a leftover's scratch files and partial output, then forget it.
+    /// Returns how many files were removed.
+    pub fn reclaim(&self, leftover: &Leftover) -> Result<usize> {
+        let mut removed = 0;
+        for path in leftover.scratch_files.iter().chain(leftover.partial_output.iter()) {
+            if fs::remove_file(path).is_ok() {
+                removed += 1;
+            }
+        }
+        fs::remove_file(&leftover.file)?;
+        Ok(removed)
+    }
+
+    /// Forget a leftover without touching its files
+    pub fn dismiss(&self, leftover: &Leftover) -> Result<()> {
+        fs::remove_file(&leftover.file)?;
+        Ok(())
+    }
+
+    fn entries(&self) -> Vec<(PathBuf, JournalEntry)> {
+        let Ok(dir) = fs::read_dir(&self.dir) else { return Vec::new() };
+        dir.flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|p| {
+                let entry = serde_json::from_slice(&fs::read(&p).ok()?).ok()?;
+                Some((p, entry))
+            })
+            .collect()
+    }
+}
+
+/// Removes the journal entry when the run finishes, successfully or not
+#[must_use]
+pub struct JournalGuard {
+    file: PathBuf,
+}
+
+impl Drop for JournalGuard {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.file);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn process_alive(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn process_alive(pid: u32) -> bool {
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+#[cfg(not(unix))]
+fn process_alive(pid: u32) -> bool {
+    pid == std::process::id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Far above any real pid_max
+    const DEAD_PID: u32 = u32::MAX - 7;
+
+    fn entry(dir: &Path, pid: u32) -> JournalEntry {
+        let opts = CompressionOptions::default();
+        let output = dir.join("crnched_a.png");
+        JournalEntry { pid, ..JournalEntry::new("a.png", output.to_str().unwrap(), &opts) }
+    }
+
+    #[test]
+    fn test_finished_run_leaves_no_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("journal"));
+        let guard = journal.begin(entry(dir.path(), std::process::id())).unwrap();
+        assert_eq!(journal.entries().len(), 1);
+        drop(guard);
+        assert!(journal.entries().is_empty());
+        assert!(journal.leftovers().is_empty());
+    }
+
+    #[test]
+    fn test_crashed_run_is_reclaimed() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("journal"));
+        let crashed = entry(dir.path(), DEAD_PID);
+        std::mem::forget(journal.begin(crashed.clone()).unwrap());
+        fs::write(dir.path().join("crnched_a.png.oxipng.tmp.png"), b"x").unwrap();
+        fs::write(dir.path().join("crnched_a.png"), b"half").unwrap();
+        fs::write(dir.path().join("crnched_b.png.oxipng.tmp.png"), b"other run").unwrap();
+
+        let leftovers = journal.leftovers();
+        assert_eq!(leftovers.len(), 1);
+        assert_eq!(leftovers[0].entry, crashed);
+        assert_eq!(leftovers[0].scratch_files.len(), 1);
+        assert_eq!(journal.reclaim(&leftovers[0]).unwrap(), 2);
+        assert!(!dir.path().join("crnched_a.png").exists());
+        assert!(dir.path().join("crnched_b.png.oxipng.tmp.png").exists());
+        assert!(journal.leftovers().is_empty());
+    }
+
+    #[test]
+    fn test_refuses_output_owned_by_live_process() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("journal"));
+        // Our own test process plays the other live run; a different pid starts the second
+        let _guard = journal.begin(entry(dir.path(), std::process::id())).unwrap();
+        assert!(journal.begin(entry(dir.path(), DEAD_PID)).is_err());
+    }
+}
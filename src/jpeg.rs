@@ -0,0 +1,121 @@
+//! JPEG quality estimation from quantization tables.
+//!
+//! Encoders derived from libjpeg scale the standard tables from Annex K of
+//! the JPEG spec by the quality setting, so the luminance table tells us
+//! roughly which quality the file was saved at. Re-encoding above that only
+//! adds bytes.
+
+use std::path::Path;
+
+/// Annex K luminance table, natural (row-major) order
+const STD_LUMINANCE: [u16; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61,
+    12, 12, 14, 19, 26, 58, 60, 55,
+    14, 13, 16, 24, 40, 57, 69, 56,
+    14, 17, 22, 29, 51, 87, 80, 62,
+    18, 22, 37, 56, 68, 109, 103, 77,
+    24, 35, 55, 64, 81, 104, 113, 92,
+    49, 64, 78, 87, 103, 121, 120, 101,
+    72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+/// Natural-order index of the k-th coefficient as stored in a DQT segment
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10,
+    17, 24, 32, 25, 18, 11, 4, 5,
+    12, 19, 26, 33, 40, 48, 41, 34,
+    27, 20, 13, 6, 7, 14, 21, 28,
+    35, 42, 49, 56, 57, 50, 43, 36,
+    29, 22, 15, 23, 30, 37, 44, 51,
+    58, 59, 52, 45, 38, 31, 39, 46,
+    53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+/// The luminance table libjpeg writes for `quality` (1-100), natural order
+pub fn scaled_luminance(quality: u32) -> [u16; 64] {
+    let quality = quality.clamp(1, 100);
+    let scale = if quality < 50 { 5000 / quality } else { 200 - quality * 2 };
+    STD_LUMINANCE.map(|base| ((base as u32 * scale + 50) / 100).clamp(1, 255) as u16)
+}
+
+/// Estimated quality (1-100) the JPEG at `path` was saved with, or `None`
+/// when it isn't a JPEG or has no luminance table
+pub fn estimate_quality(path: &Path) -> Option<u32> {
+    let data = std::fs::read(path).ok()?;
+    let table = luminance_table(&data)?;
+    (1..=100).min_by_key(|&q| {
+        scaled_luminance(q).iter().zip(table.iter())
+            .map(|(a, b)| (*a as i64 - *b as i64).abs())
+            .sum::<i64>()
+    })
+}
+
+/// Table 0 from the first DQT segment that defines it, in natural order
+fn luminance_table(data: &[u8]) -> Option<[u16; 64]> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut pos = 2;
+    while pos + 4 <= data.len() && data[pos] == 0xFF {
+        let marker = data[pos + 1];
+        if marker == 0xDA || marker == 0xD9 {
+            break;
+        }
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let segment = data.get(pos + 4..pos + 2 + len)?;
+        if marker == 0xDB {
+            let mut at = 0;
+            while at < segment.len() {
+                let (precision, id) = (segment[at] >> 4, segment[at] & 0x0F);
+                let width = if precision == 0 { 1 } else { 2 };
+                let values = segment.get(at + 1..at + 1 + 64 * width)?;
+                if id == 0 {
+                    let mut table = [0u16; 64];
+                    for (k, &natural) in ZIGZAG.iter().enumerate() {
+                        table[natural] = if width == 1 {
+                            values[k] as u16
+                        } else {
+                            u16::from_be_bytes([values[2 * k], values[2 * k + 1]])
+                        };
+                    }
+                    return Some(table);
+                }
+                at += 1 + 64 * width;
+            }
+        }
+        pos += 2 + len;
+    }
+    None
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Minimal JPEG header carrying the libjpeg luminance table for `quality`
+    pub(crate) fn jpeg_with_quality(quality: u32) -> Vec<u8> {
+        let table = scaled_luminance(quality);
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xDB, 0, 67, 0x00];
+        jpeg.extend(ZIGZAG.iter().map(|&natural| table[natural] as u8));
+        jpeg.extend([0xFF, 0xDA]);
+        jpeg
+    }
+
+    #[test]
+    fn test_estimate_recovers_libjpeg_quality() {
+        let dir = tempfile::tempdir().unwrap();
+        for quality in [30, 60, 75, 92] {
+            let path = dir.path().join(format!("q{}.jpg", quality));
+            std::fs::write(&path, jpeg_with_quality(quality)).unwrap();
+            assert_eq!(estimate_quality(&path), Some(quality));
+        }
+    }
+
+    #[test]
+    fn test_non_jpeg_has_no_estimate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("zeros.jpg");
+        std::fs::write(&path, [0u8; 128]).unwrap();
+        assert_eq!(estimate_quality(&path), None);
+    }
+}
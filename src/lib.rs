@@ -0,0 +1,60 @@
+//! crnch - intelligent file compression for images, PDFs, documents, archives, and video.
+//!
+//! The `crnch` binary is a thin CLI over these modules. Embedders drive the
+//! engines through [`compression::compress_file`] with a
+//! [`options::CompressionOptions`] and observe them through a
+//! [`progress::ProgressSink`]; [`batch::compress_all`] runs many files on
+//! per-format worker lanes. Nothing is printed by the library itself; all
+//! human-readable output goes through an injected [`report::Reporter`].
+
+pub mod animate;
+pub mod artifacts;
+pub mod batch;
+pub mod bmp;
+pub mod butteraugli;
+pub mod cancel;
+pub mod changes;
+pub mod compression;
+pub mod config;
+pub mod decision;
+pub mod exec;
+pub mod extract;
+pub mod format;
+pub mod gif;
+pub mod glob;
+pub mod history;
+pub mod ico;
+pub mod i18n;
+pub mod in_place;
+pub mod jpeg;
+pub mod journal;
+pub mod manifest;
+pub mod merge;
+pub mod naming;
+pub mod office;
+pub mod optimized;
+pub mod options;
+pub mod organize;
+pub mod pdf_interactive;
+pub mod pdf_pages;
+pub mod pdf_quick;
+pub mod perceptual;
+pub mod plan;
+pub mod platform;
+pub mod privacy;
+pub mod probe;
+pub mod progress;
+pub mod psd;
+pub mod report;
+pub mod retarget;
+pub mod scan;
+pub mod screenshot;
+pub mod search;
+pub mod split;
+pub mod ssim;
+pub mod svg;
+pub mod throttle;
+pub mod utils;
+pub mod verify;
+pub mod webp;
+pub mod zip;
@@ -1,29 +1,84 @@
 use colored::*;
-use std::io::{self, Write};
+use crate::theme::{Paint, Role};
+use std::io::{self, IsTerminal, Write};
+use std::sync::{Mutex, OnceLock};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Instant;
 use std::process::Command;
 use std::path::Path;
-use std::sync::atomic::{AtomicU8, Ordering};
+use crnch::compression::CompResult;
+use crnch::pdf_pages::PageSizes;
+use crnch::t;
+use crnch::report::{ProgressBar, Reporter};
+use crnch::utils::SizeUnits;
 
-// Verbosity levels: 0=quiet, 1=normal, 2=verbose, 3=nerd
-static VERBOSITY: AtomicU8 = AtomicU8::new(1);
+/// Pages the nerd page report lists before it only counts the rest
+const PAGE_ROWS: usize = 20;
 
-pub fn set_verbosity(level: u8) {
-    VERBOSITY.store(level, Ordering::Relaxed);
+/// Human-readable output moves to stderr when stdout carries data (--stdout)
+static TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+pub fn route_to_stderr() {
+    TO_STDERR.store(true, Ordering::Relaxed);
 }
 
-pub fn get_verbosity() -> u8 {
-    VERBOSITY.load(Ordering::Relaxed)
+/// Convention sizes are shown in (`--si`)
+static UNITS: OnceLock<SizeUnits> = OnceLock::new();
+
+pub fn set_units(units: SizeUnits) {
+    let _ = UNITS.set(units);
 }
 
-// Legacy compatibility
-#[allow(dead_code)]
-pub fn set_nerd_mode(enabled: bool) {
-    set_verbosity(if enabled { 3 } else { 1 });
+pub fn units() -> SizeUnits {
+    UNITS.get().copied().unwrap_or_default()
+}
+
+/// Stream for human-readable output
+pub fn out() -> Box<dyn Write> {
+    if TO_STDERR.load(Ordering::Relaxed) {
+        Box::new(io::stderr())
+    } else {
+        Box::new(io::stdout())
+    }
 }
 
-pub fn is_nerd_mode() -> bool {
-    get_verbosity() >= 3
+/// `print!` to the human-readable stream, flushed immediately
+macro_rules! out {
+    ($($arg:tt)*) => {{
+        use std::io::Write as _;
+        let mut w = $crate::logger::out();
+        let _ = write!(w, $($arg)*);
+        let _ = w.flush();
+    }};
+}
+
+/// `println!` to the human-readable stream
+macro_rules! outln {
+    ($($arg:tt)*) => {{
+        use std::io::Write as _;
+        let _ = writeln!($crate::logger::out(), $($arg)*);
+    }};
+}
+
+/// Terminal output for the CLI; handed to the engines as their Reporter.
+///
+/// Verbosity levels: 0=quiet, 1=normal, 2=verbose, 3=nerd
+pub struct TerminalReporter {
+    verbosity: u8,
+}
+
+impl TerminalReporter {
+    pub fn new(verbosity: u8) -> Self {
+        TerminalReporter { verbosity }
+    }
+
+    pub fn is_nerd_mode(&self) -> bool {
+        self.verbosity >= 3
+    }
+
+    pub fn is_quiet(&self) -> bool {
+        self.verbosity == 0
+    }
 }
 
 // ==================== PACMAN PROGRESS BAR ====================
@@ -34,28 +89,26 @@ pub struct PacmanProgress {
     width: usize,
     start_time: Instant,
     message: String,
+    hidden: bool,
 }
 
 impl PacmanProgress {
-    pub fn new(total: u64, message: &str) -> Self {
+    /// A `hidden` bar never draws (nerd and quiet mode)
+    pub fn new(total: u64, message: &str, hidden: bool) -> Self {
         let bar = Self {
             total,
             current: 0,
             width: 30,
             start_time: Instant::now(),
             message: message.to_string(),
+            hidden,
         };
         bar.render();
         bar
     }
 
-    pub fn set(&mut self, current: u64) {
-        self.current = current.min(self.total);
-        self.render();
-    }
-
     fn render(&self) {
-        if is_nerd_mode() { return; } // No progress bar in nerd mode
+        if self.hidden { return; }
 
         let progress = if self.total > 0 {
             self.current as f64 / self.total as f64
@@ -74,172 +127,176 @@ impl PacmanProgress {
         let percent = (progress * 100.0) as u64;
 
         // Use ANSI escape codes to clear the line properly
-        print!("\r\x1B[2K");  // Clear entire line
-        print!("\r   [{}{}{}] {}% {}   ", 
-            behind, 
-            pacman.yellow(), 
-            ahead.dimmed(),
+        out!("\r\x1B[2K");  // Clear entire line
+        out!("\r   [{}{}{}] {}% {}   ",
+            behind,
+            pacman.paint(Role::Highlight),
+            ahead.paint(Role::Muted),
             percent,
             self.message
         );
-        io::stdout().flush().unwrap();
     }
+}
+
+impl ProgressBar for PacmanProgress {
+    fn set(&mut self, current: u64) {
+        self.current = current.min(self.total);
+        self.render();
+    }
+
+    fn finish(&self) {
+        if self.hidden { return; }
 
-    pub fn finish(&self) {
-        if is_nerd_mode() { return; }
-        
         let elapsed = self.start_time.elapsed();
         // Clear the entire line with ANSI escape code
-        print!("\r\x1B[2K");
+        out!("\r\x1B[2K");
         // Final state: pacman at the end, all dots eaten
         let behind = " ".repeat(self.width);
-        println!("\r   [{}{}] 100% Done! ({:.1}s)", 
-            behind, 
-            "C".green(),
-            elapsed.as_secs_f64()
+        outln!("\r   [{}{}] 100% {}",
+            behind,
+            "C".paint(Role::Success),
+            t!("progress-done", seconds = format!("{:.1}", elapsed.as_secs_f64()))
         );
     }
 
-    pub fn finish_with_message(&self, msg: &str) {
-        if is_nerd_mode() { return; }
-        
+    fn finish_with_message(&self, msg: &str) {
+        if self.hidden { return; }
+
         // Clear the entire line with ANSI escape code
-        print!("\r\x1B[2K");
+        out!("\r\x1B[2K");
         let behind = " ".repeat(self.width);
-        println!("\r   [{}{}] {}", behind, "C".green(), msg);
+        outln!("\r   [{}{}] {}", behind, "C".paint(Role::Success), msg);
     }
 }
 
 // ==================== DEFAULT MODE LOGGING ====================
 
-pub fn log_start(filename: &str) {
-    if is_nerd_mode() { return; }
-    println!("\n{} Crnching '{}'...", ">>".cyan(), filename);
-}
+impl TerminalReporter {
+    pub fn log_start(&self, filename: &str) {
+        if self.is_nerd_mode() || self.is_quiet() { return; }
+        outln!("\n{} {}", ">>".paint(Role::Accent), t!("log-start", file = filename));
+    }
 
-pub fn log_target(target: &str) {
-    if is_nerd_mode() { return; }
-    println!("   Target: {}", target.cyan());
-}
+    pub fn log_target(&self, target: &str) {
+        if self.is_nerd_mode() || self.is_quiet() { return; }
+        outln!("   {} {}", t!("log-target"), target.paint(Role::Accent));
+    }
 
-pub fn log_done() {
-    if is_nerd_mode() { return; }
-    println!("{}", ">> Done!".green());
-}
+    pub fn log_done(&self) {
+        if self.is_nerd_mode() || self.is_quiet() { return; }
+        outln!("{}", format!(">> {}", t!("log-done")).paint(Role::Success));
+    }
 
-pub fn log_result(input_path: &str, output_path: &str, old_kb: u64, new_kb: u64) {
-    if is_nerd_mode() { return; }
-    
-    log_summary(input_path, output_path, old_kb, new_kb, None, None);
-}
-
-/// Enhanced summary output with detailed compression statistics
-pub fn log_summary(
-    input_path: &str, 
-    output_path: &str, 
-    old_kb: u64, 
-    new_kb: u64, 
-    method: Option<&str>,
-    time_ms: Option<u128>
-) {
-    if is_nerd_mode() { return; }
-    
-    let reduction_pct = if old_kb > 0 && new_kb <= old_kb {
-        (old_kb - new_kb) as f64 / old_kb as f64 * 100.0
-    } else { 0.0 };
-    
-    let saved_kb = old_kb.saturating_sub(new_kb);
-    let ratio = if new_kb > 0 { old_kb as f64 / new_kb as f64 } else { 1.0 };
-    
-    // Format file sizes nicely
-    let old_size_str = format_size(old_kb);
-    let new_size_str = format_size(new_kb);
-    
-    println!();
-    println!("{}", "┌─────────────────────────────────────────────────────────┐".dimmed());
-    println!("{}", "│                    COMPRESSION SUMMARY                  │".cyan().bold());
-    println!("{}", "├─────────────────────────────────────────────────────────┤".dimmed());
-    
-    // Input/Output files
-    let in_name = Path::new(input_path).file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| input_path.to_string());
-    let out_name = Path::new(output_path).file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| output_path.to_string());
-    
-    println!("  {} {}", "Input: ".dimmed(), in_name);
-    println!("  {} {}", "Output:".dimmed(), out_name.green());
-    
-    println!("{}", "├─────────────────────────────────────────────────────────┤".dimmed());
-    
-    // Size info with visual bar
-    let bar_width = 30;
-    let (filled, bar_color) = if new_kb > old_kb {
-        // File grew - show empty bar in red
-        (0, "red")
-    } else if old_kb > 0 {
-        // Normal compression - green bar based on compression ratio
-        let ratio = (new_kb as f64 / old_kb as f64 * bar_width as f64).round() as usize;
-        (ratio.min(bar_width), "green")
-    } else {
-        (bar_width, "green")
-    };
-    let empty = bar_width - filled;
-    
-    let bar = if bar_color == "red" {
-        format!("{}{}",
-            "░".repeat(empty).red(),
-            "█".repeat(filled).red()
-        )
-    } else {
-        format!("{}{}",
-            "█".repeat(filled).green(),
-            "░".repeat(empty).dimmed()
-        )
-    };
-    
-    println!("  {} {} → {}", "Size:  ".dimmed(), old_size_str, new_size_str.green());
-    println!("         [{}]", bar);
-    
-    // Statistics
-    if new_kb > old_kb {
-        let increase_msg = if old_kb == 0 {
-            "file grew from < 1 KB".to_string()
+    pub fn log_result(&self, input_path: &str, output_path: &str, old_kb: u64, new_kb: u64) {
+        if self.is_nerd_mode() { return; }
+
+        self.log_summary(input_path, output_path, old_kb, new_kb, None, None);
+    }
+
+    /// Enhanced summary output with detailed compression statistics
+    pub fn log_summary(
+        &self,
+        input_path: &str,
+        output_path: &str,
+        old_kb: u64,
+        new_kb: u64,
+        method: Option<&str>,
+        time_ms: Option<u128>
+    ) {
+        if self.is_nerd_mode() || self.is_quiet() { return; }
+
+        let reduction_pct = if old_kb > 0 && new_kb <= old_kb {
+            (old_kb - new_kb) as f64 / old_kb as f64 * 100.0
+        } else { 0.0 };
+
+        let saved_kb = old_kb.saturating_sub(new_kb);
+        let ratio = if new_kb > 0 { old_kb as f64 / new_kb as f64 } else { 1.0 };
+
+        // Format file sizes nicely
+        let old_size_str = format_size(old_kb);
+        let new_size_str = format_size(new_kb);
+
+        outln!();
+        outln!("{}", "┌─────────────────────────────────────────────────────────┐".paint(Role::Border));
+        outln!("{}", box_title(&t!("summary-title"), 57, '│').paint(Role::Title));
+        outln!("{}", "├─────────────────────────────────────────────────────────┤".paint(Role::Border));
+
+        // Input/Output files
+        let in_name = Path::new(input_path).file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| input_path.to_string());
+        let out_name = Path::new(output_path).file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| output_path.to_string());
+
+        let [input, output, size, saved, method_label, time] = labels(["label-input", "label-output", "label-size", "label-saved", "label-method", "label-time"]);
+        outln!("  {} {}", input.paint(Role::Label), in_name);
+        outln!("  {} {}", output.paint(Role::Label), out_name.paint(Role::Success));
+
+        outln!("{}", "├─────────────────────────────────────────────────────────┤".paint(Role::Border));
+
+        // Size info with visual bar
+        let bar_width = 30;
+        let (filled, bar_color) = if new_kb > old_kb {
+            // File grew - show empty bar in red
+            (0, "red")
+        } else if old_kb > 0 {
+            // Normal compression - green bar based on compression ratio
+            let ratio = (new_kb as f64 / old_kb as f64 * bar_width as f64).round() as usize;
+            (ratio.min(bar_width), "green")
         } else {
-            let increase_pct = (new_kb - old_kb) as f64 / old_kb as f64 * 100.0;
-            format!("file grew by {:.1}%", increase_pct)
+            (bar_width, "green")
         };
-        println!("  {} {} ({})", 
-            "Saved: ".dimmed(), 
-            "0%".yellow(),
-            increase_msg.yellow()
-        );
-    } else {
-        println!("  {} {} ({} saved, {:.2}:1 ratio)", 
-            "Saved: ".dimmed(),
-            format!("{:.1}%", reduction_pct).green().bold(),
-            format_size(saved_kb).green(),
-            ratio
-        );
-    }
-    
-    // Optional method info (verbose mode)
-    if let Some(m) = method {
-        println!("  {} {}", "Method:".dimmed(), m.cyan());
-    }
-    
-    // Optional timing info
-    if let Some(ms) = time_ms {
-        let time_str = if ms >= 1000 {
-            format!("{:.2}s", ms as f64 / 1000.0)
+        let empty = bar_width - filled;
+
+        let bar = if bar_color == "red" {
+            format!("{}{}",
+                "░".repeat(empty).paint(Role::Error),
+                "█".repeat(filled).paint(Role::Error)
+            )
         } else {
-            format!("{}ms", ms)
+            format!("{}{}",
+                "█".repeat(filled).paint(Role::Success),
+                "░".repeat(empty).paint(Role::Muted)
+            )
         };
-        println!("  {} {}", "Time:  ".dimmed(), time_str);
+
+        outln!("  {} {} → {}", size.paint(Role::Label), old_size_str, new_size_str.paint(Role::Success));
+        outln!("  {} [{}]", " ".repeat(size.chars().count()), bar);
+
+        // Statistics
+        if new_kb > old_kb {
+            let increase_msg = if old_kb == 0 {
+                t!("summary-grew-from-nothing")
+            } else {
+                let increase_pct = (new_kb - old_kb) as f64 / old_kb as f64 * 100.0;
+                t!("summary-grew", percent = format!("{:.1}", increase_pct))
+            };
+            outln!("  {} {} ({})",
+                saved.paint(Role::Label),
+                "0%".paint(Role::Warning),
+                increase_msg.paint(Role::Warning)
+            );
+        } else {
+            outln!("  {} {} ({})",
+                saved.paint(Role::Label),
+                format!("{:.1}%", reduction_pct).paint(Role::Success).bold(),
+                t!("summary-saved", size = format_size(saved_kb).paint(Role::Success), ratio = format!("{:.2}", ratio))
+            );
+        }
+
+        // Optional method info (verbose mode)
+        if let Some(m) = method {
+            outln!("  {} {}", method_label.paint(Role::Label), m.paint(Role::Accent));
+        }
+
+        // Optional timing info
+        if let Some(ms) = time_ms {
+            outln!("  {} {}", time.paint(Role::Label), format_time(ms));
+        }
+
+        outln!("{}", "└─────────────────────────────────────────────────────────┘".paint(Role::Border));
     }
-    
-    println!("{}", "└─────────────────────────────────────────────────────────┘".dimmed());
 }
 
 #[allow(dead_code)]
@@ -247,247 +304,451 @@ pub fn nerd_final_result(_dpi: u64, _old_kb: u64, _new_kb: u64, _iterations: usi
     // Placeholder for potential future use
 }
 
-/// Format size in human-readable form
-fn format_size(kb: u64) -> String {
-    if kb >= 1024 {
-        format!("{:.1} MB", kb as f64 / 1024.0)
-    } else if kb == 0 {
-        // File is less than 1KB, show as bytes
-        "< 1 KB".to_string()
-    } else {
-        format!("{} KB", kb)
-    }
+/// `title` centered in a box row `width` wide, between two `side` characters
+fn box_title(title: &str, width: usize, side: char) -> String {
+    let room = width.saturating_sub(title.chars().count());
+    format!("{side}{}{title}{}{side}", " ".repeat(room / 2), " ".repeat(room - room / 2))
 }
 
-pub fn log_warning(msg: &str) {
-    println!("\n{} {}", "WARNING:".yellow().bold(), msg);
+/// Catalog labels with their colon, padded to the longest so the values line up
+fn labels<const N: usize>(keys: [&str; N]) -> [String; N] {
+    let labels = keys.map(|key| format!("{}:", t!(key)));
+    let width = labels.iter().map(|label| label.chars().count()).max().unwrap_or(0);
+    labels.map(|label| format!("{:<width$}", label))
 }
 
-pub fn log_error(msg: &str) {
-    println!("{} {}", "ERROR:".red().bold(), msg);
+fn format_time(ms: u128) -> String {
+    if ms >= 1000 {
+        format!("{:.2}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}ms", ms)
+    }
 }
 
-// ==================== NERD MODE LOGGING ====================
-
-pub fn nerd_header() {
-    if !is_nerd_mode() { return; }
-    
-    // Get system info
-    let os_info = get_os_info();
-    let arch = get_arch();
-    let gs_version = get_tool_version("gs", &["--version"]);
-    let magick_version = get_tool_version("magick", &["--version"]);
-    let pngquant_version = get_tool_version("pngquant", &["--version"]);
-    let cpu_info = get_cpu_info();
-    let mem_info = get_mem_info();
-    
-    println!("\n{}", "╔═══════════════════════════════════════════════════════════════════════╗".cyan());
-    println!("{}", "║                          SYSTEM INFORMATION                           ║".cyan().bold());
-    println!("{}", "╠═══════════════════════════════════════════════════════════════════════╣".cyan());
-    println!("  {} {:<25} {} {}", "OS:".dimmed(), os_info, "Arch:".dimmed(), arch);
-    println!("  {} {}", "CPU:".dimmed(), cpu_info);
-    println!("  {} {}", "RAM:".dimmed(), mem_info);
-    println!("{}", "╠═══════════════════════════════════════════════════════════════════════╣".cyan());
-    println!("  {} {:<40}", "Ghostscript:".green(), gs_version);
-    println!("  {} {:<40}", "ImageMagick:".green(), magick_version);
-    println!("  {} {:<40}", "pngquant:   ".green(), pngquant_version);
-    println!("{}", "╚═══════════════════════════════════════════════════════════════════════╝".cyan());
-}
-
-pub fn nerd_file_info(input: &str, size_kb: u64, target_kb: Option<u64>) {
-    if !is_nerd_mode() { return; }
-    
-    let path = Path::new(input);
-    let filename = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
-    let ext = path.extension().map(|e| e.to_string_lossy().to_uppercase()).unwrap_or_default();
-    let abs_path = std::fs::canonicalize(input).map(|p| p.display().to_string()).unwrap_or(input.to_string());
-    
-    println!("\n{}", "╔═══════════════════════════════════════════════════════════════════════╗".cyan());
-    println!("{}", "║                            INPUT FILE                                 ║".cyan().bold());
-    println!("{}", "╠═══════════════════════════════════════════════════════════════════════╣".cyan());
-    println!("  {} {}", "Filename:".dimmed(), filename.green());
-    println!("  {} {}", "Type:    ".dimmed(), ext.yellow());
-    println!("  {} {}", "Path:    ".dimmed(), abs_path.dimmed());
-    
-    // Show actual file size in bytes if we have it
-    if let Ok(metadata) = std::fs::metadata(input) {
-        let bytes = metadata.len();
-        if bytes < 1024 {
-            println!("  {} {} bytes", "Size:    ".dimmed(), bytes);
-        } else if bytes < 1024 * 1024 {
-            println!("  {} {:.2} KB ({} bytes)", "Size:    ".dimmed(), bytes as f64 / 1024.0, bytes);
+/// Sizes, savings, method and time for every file a batch finished, plus totals
+pub fn log_batch_table(rows: &[(&str, &CompResult)]) {
+    let lines = batch_table(rows);
+    outln!();
+    for (i, line) in lines.iter().enumerate() {
+        if i == 0 {
+            outln!("   {}", line.paint(Role::Label));
+        } else if i == lines.len() - 1 {
+            outln!("   {}", line.bold());
         } else {
-            println!("  {} {:.2} MB ({} bytes)", "Size:    ".dimmed(), bytes as f64 / (1024.0 * 1024.0), bytes);
+            outln!("   {}", line);
         }
-    } else {
-        println!("  {} {} KB (approx)", "Size:    ".dimmed(), size_kb);
     }
-    
-    // Try to get image dimensions for JPG/PNG
-    if ext == "JPG" || ext == "JPEG" || ext == "PNG" {
-        if let Some((width, height)) = get_image_dimensions(input) {
-            println!("  {} {}x{} pixels", "Dimensions:".dimmed(), width, height);
-            let megapixels = (width * height) as f64 / 1_000_000.0;
-            println!("  {} {:.2} MP", "Resolution:".dimmed(), megapixels);
+}
+
+/// The table's lines, uncolored: header, a rule, one row per file, a rule and totals
+fn batch_table(rows: &[(&str, &CompResult)]) -> Vec<String> {
+    let saved = |original: u64, final_kb: u64| match original {
+        0 => "-".to_string(),
+        original => format!("{:.1}%", (original as f64 - final_kb as f64) / original as f64 * 100.0),
+    };
+    let mut cells: Vec<[String; 6]> = vec![
+        ["table-file", "table-before", "table-after", "table-saved", "table-method", "table-time"].map(|key| t!(key)),
+    ];
+    for (name, result) in rows {
+        let (after, saved, method) = if result.skipped {
+            let method = if result.already_optimized { t!("table-skipped-optimized") } else { t!("table-skipped") };
+            (format_size(result.original_kb), "-".to_string(), method)
+        } else {
+            (format_size(result.final_kb), saved(result.original_kb, result.final_kb), result.algorithm.clone())
+        };
+        cells.push([name.to_string(), format_size(result.original_kb), after, saved, method, format_time(result.time_ms)]);
+    }
+    let original: u64 = rows.iter().map(|(_, r)| r.original_kb).sum();
+    let final_kb: u64 = rows.iter().map(|(_, r)| if r.skipped { r.original_kb } else { r.final_kb }).sum();
+    let time_ms: u128 = rows.iter().map(|(_, r)| r.time_ms).sum();
+    cells.push([t!("table-files", count = rows.len()), format_size(original), format_size(final_kb), saved(original, final_kb), String::new(), format_time(time_ms)]);
+
+    let mut widths = [0; 6];
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
         }
     }
-    
-    println!("{}", "╠═══════════════════════════════════════════════════════════════════════╣".cyan());
-    
-    if let Some(target) = target_kb {
-        let reduction = if size_kb > 0 && size_kb > target {
-            ((size_kb - target) as f64 / size_kb as f64 * 100.0) as u64
-        } else { 0 };
-        let ratio_needed = if target > 0 { size_kb as f64 / target as f64 } else { 0.0 };
-        println!("  {} {} KB", "Target:  ".dimmed(), target.to_string().cyan());
-        println!("  {} {}%", "Reduction:".dimmed(), reduction.to_string().yellow());
-        println!("  {} {:.2}:1", "Ratio:   ".dimmed(), ratio_needed.to_string().green());
-    } else {
-        println!("  {} Auto (preset-based)", "Target:  ".dimmed());
+    let line = |row: &[String; 6]| {
+        let mut line = String::new();
+        for (i, (cell, width)) in row.iter().zip(widths).enumerate() {
+            let pad = " ".repeat(width - cell.chars().count());
+            match i {
+                // Names and methods read left to right, numbers line up on the right
+                0 | 4 => line.push_str(&format!("{}{}  ", cell, pad)),
+                _ => line.push_str(&format!("{}{}  ", pad, cell)),
+            }
+        }
+        line.trim_end().to_string()
+    };
+    let rule = "─".repeat(widths.iter().sum::<usize>() + 2 * (widths.len() - 1));
+    let totals = cells.pop().unwrap();
+    let mut lines: Vec<String> = cells.iter().map(line).collect();
+    lines.insert(1, rule.clone());
+    lines.push(rule);
+    lines.push(line(&totals));
+    lines
+}
+
+/// Format size in human-readable form
+pub fn format_size(kb: u64) -> String {
+    format_size_with(kb, 1)
+}
+
+fn format_size_with(kb: u64, decimals: usize) -> String {
+    match units() {
+        SizeUnits::Binary if kb >= 1024 => format!("{:.*} MB", decimals, kb as f64 / 1024.0),
+        // File is less than 1KB, show as bytes
+        SizeUnits::Binary if kb == 0 => "< 1 KB".to_string(),
+        SizeUnits::Binary => format!("{} KB", kb),
+        SizeUnits::Decimal if kb == 0 => "< 1 kB".to_string(),
+        SizeUnits::Decimal => {
+            let bytes = kb * 1024;
+            if bytes >= 1_000_000 {
+                format!("{:.*} MB", decimals, bytes as f64 / 1_000_000.0)
+            } else {
+                format!("{} kB", bytes / 1000)
+            }
+        },
     }
-    println!("{}", "╚═══════════════════════════════════════════════════════════════════════╝".cyan());
 }
 
-pub fn nerd_stage(stage_num: u32, name: &str) {
-    if !is_nerd_mode() { return; }
-    println!("\n{}", "─".repeat(75).dimmed());
-    println!("{} {}", format!("[STAGE {}]", stage_num).yellow().bold(), name.bold());
-    println!("{}", "─".repeat(75).dimmed());
+/// "479,812 of 480,000 bytes" when whole KB would hide how the output and
+/// the target compare: a target given to the byte, or one missed by less than a KB
+pub fn exact_size(result: &CompResult) -> Option<String> {
+    let limit = result.target_bytes?;
+    let hidden_miss = result.target_met == Some(false) && result.target_kb.is_some_and(|kb| result.final_kb <= kb);
+    (!limit.is_multiple_of(1024) || hidden_miss)
+        .then(|| format!("{} of {} bytes", group_digits(result.final_bytes), group_digits(limit)))
 }
 
-pub fn nerd_cmd(cmd_str: &str) {
-    if !is_nerd_mode() { return; }
-    println!("  ├─ Cmd: {}", cmd_str.dimmed());
+fn group_digits(n: u64) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
 }
 
-pub fn nerd_attempt(attempt: u32, max: u32, dpi: u64, size_kb: u64, target_kb: u64, time_ms: u128, action: &str) {
-    if !is_nerd_mode() { return; }
-    
-    let delta = if size_kb > target_kb {
-        format!("+{} KB", size_kb - target_kb).red()
-    } else {
-        format!("-{} KB", target_kb - size_kb).green()
-    };
-    
-    let status_icon = if size_kb <= target_kb { "OK".green() } else { "XX".red() };
-    
-    let prefix = if attempt == max { "  └─" } else { "  ├─" };
-    println!("{} [{:>2}/{}] {:>4} DPI -> {:>4} KB [{}] ({}) | {}ms | next: {}", 
-        prefix, attempt, max, dpi, size_kb, status_icon, delta, time_ms, action.dimmed());
+pub fn log_warning(msg: &str) {
+    outln!("\n{} {}", format!("{}:", t!("warning-label").to_uppercase()).paint(Role::Warning).bold(), msg);
 }
 
-pub fn nerd_quality_attempt(attempt: u32, max: u32, quality: u8, size_kb: u64, target_kb: u64, time_ms: u128, action: &str) {
-    if !is_nerd_mode() { return; }
-    
-    let delta = if size_kb > target_kb {
-        format!("+{} KB", size_kb - target_kb).red()
-    } else {
-        format!("-{} KB", target_kb - size_kb).green()
-    };
-    
-    let status_icon = if size_kb <= target_kb { "OK".green() } else { "XX".red() };
-    
-    let prefix = if attempt == max { "  └─" } else { "  ├─" };
-    println!("{} [{:>2}] Quality {:>3}% -> {:>4} KB [{}] ({}) | {}ms | next: {}", 
-        prefix, attempt, quality, size_kb, status_icon, delta, time_ms, action.dimmed());
+pub fn log_error(msg: &str) {
+    outln!("{} {}", format!("{}:", t!("error-label").to_uppercase()).paint(Role::Error).bold(), msg);
 }
 
-pub fn nerd_scale_attempt(attempt: u32, max: u32, scale: u8, size_kb: u64, target_kb: u64, time_ms: u128, action: &str) {
-    if !is_nerd_mode() { return; }
-    
-    let delta = if size_kb > target_kb {
-        format!("+{} KB", size_kb - target_kb).red()
-    } else {
-        format!("-{} KB", target_kb - size_kb).green()
-    };
-    
-    let status_icon = if size_kb <= target_kb { "OK".green() } else { "XX".red() };
-    
-    let prefix = if attempt == max { "  └─" } else { "  ├─" };
-    println!("{} [{:>2}] Scale {:>3}% -> {:>4} KB [{}] ({}) | {}ms | next: {}", 
-        prefix, attempt, scale, size_kb, status_icon, delta, time_ms, action.dimmed());
+/// A line per file in flight on the batch workers, kept under the result
+/// lines printed so far; plain result lines only when not on a terminal
+pub struct BatchProgress {
+    active: Mutex<Vec<String>>,
+    live: bool,
 }
 
-pub fn nerd_result(label: &str, value: &str, is_last: bool) {
-    if !is_nerd_mode() { return; }
-    let prefix = if is_last { "  └─" } else { "  ├─" };
-    if value.is_empty() {
-        println!("{} {}", prefix.dimmed(), label.yellow());
-    } else {
-        println!("{} {} {}", prefix.dimmed(), format!("{}:", label).dimmed(), value);
+impl BatchProgress {
+    pub fn new(enabled: bool) -> Self {
+        let terminal = if TO_STDERR.load(Ordering::Relaxed) { io::stderr().is_terminal() } else { io::stdout().is_terminal() };
+        BatchProgress { active: Mutex::new(Vec::new()), live: enabled && terminal }
+    }
+
+    pub fn start(&self, name: &str) {
+        let mut active = self.active.lock().unwrap();
+        self.clear(&active);
+        active.push(name.to_string());
+        self.draw(&active);
+    }
+
+    /// Drop `name`'s live line and print its result in its place
+    pub fn finish(&self, name: &str, report: impl FnOnce()) {
+        let mut active = self.active.lock().unwrap();
+        self.clear(&active);
+        if let Some(i) = active.iter().position(|a| a == name) {
+            active.remove(i);
+        }
+        report();
+        self.draw(&active);
+    }
+
+    fn clear(&self, active: &[String]) {
+        if self.live && !active.is_empty() {
+            out!("\x1B[{}A\x1B[J", active.len());
+        }
+    }
+
+    fn draw(&self, active: &[String]) {
+        if self.live {
+            for name in active {
+                outln!("   {} {}", "…".paint(Role::Muted), name);
+            }
+        }
     }
 }
 
-pub fn nerd_output_summary(_input: &str, output: &str, old_kb: u64, new_kb: u64, method: &str, time_s: f64) {
-    if !is_nerd_mode() { return; }
-    
-    let reduction_pct = if old_kb > 0 && new_kb <= old_kb {
-        (old_kb - new_kb) as f64 / old_kb as f64 * 100.0
-    } else { 0.0 };
-    
-    let ratio = if new_kb > 0 { old_kb as f64 / new_kb as f64 } else { 1.0 };
-    let saved_kb = old_kb.saturating_sub(new_kb);
-    
-    println!("\n{}", "╔═══════════════════════════════════════════════════════════════════════╗".green());
-    println!("{}", "║                         COMPRESSION RESULT                            ║".green().bold());
-    println!("{}", "╠═══════════════════════════════════════════════════════════════════════╣".green());
-    
-    let out_name = Path::new(output).file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_else(|| output.to_string());
-    println!("  {} {}", "Output File:".dimmed(), out_name.green());
-    println!("  {} {}", "Method:     ".dimmed(), method.cyan());
-    println!("{}", "╠═══════════════════════════════════════════════════════════════════════╣".green());
-    
-    let old_size_str = if old_kb >= 1024 {
-        format!("{:.2} MB", old_kb as f64 / 1024.0)
-    } else if old_kb == 0 {
-        "< 1 KB".to_string()
-    } else {
-        format!("{} KB", old_kb)
-    };
-    
-    let new_size_str = if new_kb >= 1024 {
-        format!("{:.2} MB", new_kb as f64 / 1024.0)
-    } else if new_kb == 0 {
-        "< 1 KB".to_string()
-    } else {
-        format!("{} KB", new_kb)
-    };
-    
-    println!("  {} {} → {}", "Size:       ".dimmed(), old_size_str, new_size_str.green());
-    println!("  {} {:.1}% ({} KB saved)", "Reduction:  ".dimmed(), reduction_pct, saved_kb);
-    println!("  {} {:.2}:1", "Ratio:      ".dimmed(), ratio);
-    println!("  {} {:.2}s", "Time:       ".dimmed(), time_s);
-    
-    println!("{}", "╚═══════════════════════════════════════════════════════════════════════╝".green());
+// ==================== NERD MODE LOGGING ====================
+
+impl TerminalReporter {
+    pub fn nerd_header(&self) {
+        if !self.is_nerd_mode() { return; }
+
+        // Get system info
+        let os_info = get_os_info();
+        let arch = get_arch();
+        let gs_version = get_tool_version("gs", &["--version"]);
+        let magick_version = get_tool_version("magick", &["--version"]);
+        let pngquant_version = get_tool_version("pngquant", &["--version"]);
+        let cpu_info = get_cpu_info();
+        let mem_info = get_mem_info();
+
+        outln!("\n{}", "╔═══════════════════════════════════════════════════════════════════════╗".paint(Role::Accent));
+        outln!("{}", box_title(&t!("system-title"), 71, '║').paint(Role::Title));
+        outln!("{}", "╠═══════════════════════════════════════════════════════════════════════╣".paint(Role::Accent));
+        let [os, cpu, ram] = labels(["label-os", "label-cpu", "label-ram"]);
+        outln!("  {} {:<25} {} {}", os.paint(Role::Label), os_info, format!("{}:", t!("label-arch")).paint(Role::Label), arch);
+        outln!("  {} {}", cpu.paint(Role::Label), cpu_info);
+        outln!("  {} {}", ram.paint(Role::Label), mem_info);
+        outln!("{}", "╠═══════════════════════════════════════════════════════════════════════╣".paint(Role::Accent));
+        outln!("  {} {:<40}", "Ghostscript:".paint(Role::Success), gs_version);
+        outln!("  {} {:<40}", "ImageMagick:".paint(Role::Success), magick_version);
+        outln!("  {} {:<40}", "pngquant:   ".paint(Role::Success), pngquant_version);
+        outln!("{}", "╚═══════════════════════════════════════════════════════════════════════╝".paint(Role::Accent));
+    }
+
+    pub fn nerd_file_info(&self, input: &str, size_kb: u64, target_kb: Option<u64>) {
+        if !self.is_nerd_mode() { return; }
+
+        let path = Path::new(input);
+        let filename = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+        let ext = path.extension().map(|e| e.to_string_lossy().to_uppercase()).unwrap_or_default();
+        let abs_path = std::fs::canonicalize(input).map(|p| p.display().to_string()).unwrap_or(input.to_string());
+
+        outln!("\n{}", "╔═══════════════════════════════════════════════════════════════════════╗".paint(Role::Accent));
+        outln!("{}", box_title(&t!("input-title"), 71, '║').paint(Role::Title));
+        outln!("{}", "╠═══════════════════════════════════════════════════════════════════════╣".paint(Role::Accent));
+        let [name, kind, location, size, dimensions, resolution] = labels(["label-filename", "label-type", "label-path", "label-size", "label-dimensions", "label-resolution"]);
+        outln!("  {} {}", name.paint(Role::Label), filename.paint(Role::Success));
+        outln!("  {} {}", kind.paint(Role::Label), ext.paint(Role::Highlight));
+        outln!("  {} {}", location.paint(Role::Label), abs_path.paint(Role::Muted));
+
+        // Show actual file size in bytes if we have it
+        if let Ok(metadata) = std::fs::metadata(input) {
+            let bytes = metadata.len();
+            if bytes < units().base() {
+                outln!("  {} {}", size.paint(Role::Label), t!("value-bytes", count = bytes));
+            } else {
+                outln!("  {} {} ({})", size.paint(Role::Label), units().format(bytes, 2), t!("value-bytes", count = bytes));
+            }
+        } else {
+            outln!("  {} {}", size.paint(Role::Label), t!("value-approx", size = format_size(size_kb)));
+        }
+
+        // Try to get image dimensions for JPG/PNG
+        if ext == "JPG" || ext == "JPEG" || ext == "PNG" {
+            if let Some((width, height)) = get_image_dimensions(input) {
+                outln!("  {} {}", dimensions.paint(Role::Label), t!("value-pixels", width = width, height = height));
+                let megapixels = (width * height) as f64 / 1_000_000.0;
+                outln!("  {} {:.2} MP", resolution.paint(Role::Label), megapixels);
+            }
+        }
+
+        outln!("{}", "╠═══════════════════════════════════════════════════════════════════════╣".paint(Role::Accent));
+
+        let [target_label, reduction_label, ratio_label] = labels(["label-target", "label-reduction", "label-ratio"]);
+        if let Some(target) = target_kb {
+            let reduction = if size_kb > 0 && size_kb > target {
+                ((size_kb - target) as f64 / size_kb as f64 * 100.0) as u64
+            } else { 0 };
+            let ratio_needed = if target > 0 { size_kb as f64 / target as f64 } else { 0.0 };
+            outln!("  {} {}", target_label.paint(Role::Label), format_size(target).paint(Role::Accent));
+            outln!("  {} {}%", reduction_label.paint(Role::Label), reduction.to_string().paint(Role::Highlight));
+            outln!("  {} {:.2}:1", ratio_label.paint(Role::Label), ratio_needed.to_string().paint(Role::Success));
+        } else {
+            outln!("  {} {}", target_label.paint(Role::Label), t!("value-target-auto"));
+        }
+        outln!("{}", "╚═══════════════════════════════════════════════════════════════════════╝".paint(Role::Accent));
+    }
 }
 
-// Binary search visualization helper
-pub fn nerd_search_range(min: u64, max: u64, mid: u64) {
-    if !is_nerd_mode() { return; }
-    
-    // Visual representation of search range
-    let total_range = 2400u64;
-    let width = 50usize;
-    
-    let min_pos = (min as f64 / total_range as f64 * width as f64) as usize;
-    let max_pos = (max as f64 / total_range as f64 * width as f64) as usize;
-    let mid_pos = (mid as f64 / total_range as f64 * width as f64) as usize;
-    
-    let mut bar = vec!['.'; width];
-    for item in bar.iter_mut().take(max_pos.min(width - 1) + 1).skip(min_pos) {
-        *item = '=';
+impl Reporter for TerminalReporter {
+    fn message(&self, msg: &str) {
+        if self.is_quiet() { return; }
+        outln!("{}", msg);
     }
-    if mid_pos < width {
-        bar[mid_pos] = '^';
+
+    fn warning(&self, msg: &str) {
+        if self.is_quiet() { return; }
+        log_warning(msg);
+    }
+
+    fn progress(&self, total: u64, message: &str) -> Box<dyn ProgressBar> {
+        Box::new(PacmanProgress::new(total, message, self.is_nerd_mode() || self.is_quiet()))
+    }
+
+    fn nerd_stage(&self, stage_num: u32, name: &str) {
+        if !self.is_nerd_mode() { return; }
+        outln!("\n{}", "─".repeat(75).paint(Role::Border));
+        outln!("{} {}", format!("[{}]", t!("nerd-stage", number = stage_num)).paint(Role::Highlight).bold(), name.bold());
+        outln!("{}", "─".repeat(75).paint(Role::Border));
+    }
+
+    fn nerd_cmd(&self, cmd_str: &str) {
+        if !self.is_nerd_mode() { return; }
+        outln!("  ├─ {}: {}", t!("nerd-cmd"), cmd_str.paint(Role::Muted));
+    }
+
+    fn nerd_attempt(&self, attempt: u32, max: u32, dpi: u64, size_kb: u64, target_kb: u64, time_ms: u128, action: &str) {
+        if !self.is_nerd_mode() { return; }
+
+        let delta = if size_kb > target_kb {
+            format!("+{} KB", size_kb - target_kb).paint(Role::Error)
+        } else {
+            format!("-{} KB", target_kb - size_kb).paint(Role::Success)
+        };
+
+        let status_icon = if size_kb <= target_kb { "OK".paint(Role::Success) } else { "XX".paint(Role::Error) };
+
+        let prefix = if attempt == max { "  └─" } else { "  ├─" };
+        outln!("{} [{:>2}/{}] {:>4} DPI -> {:>4} KB [{}] ({}) | {}ms | {}: {}",
+            prefix, attempt, max, dpi, size_kb, status_icon, delta, time_ms, t!("nerd-next"), action.paint(Role::Muted));
+    }
+
+    fn nerd_quality_attempt(&self, attempt: u32, max: u32, quality: u8, size_kb: u64, target_kb: u64, time_ms: u128, action: &str) {
+        if !self.is_nerd_mode() { return; }
+
+        let delta = if size_kb > target_kb {
+            format!("+{} KB", size_kb - target_kb).paint(Role::Error)
+        } else {
+            format!("-{} KB", target_kb - size_kb).paint(Role::Success)
+        };
+
+        let status_icon = if size_kb <= target_kb { "OK".paint(Role::Success) } else { "XX".paint(Role::Error) };
+
+        let prefix = if attempt == max { "  └─" } else { "  ├─" };
+        outln!("{} [{:>2}] {} {:>3}% -> {:>4} KB [{}] ({}) | {}ms | {}: {}",
+            prefix, attempt, t!("nerd-quality"), quality, size_kb, status_icon, delta, time_ms, t!("nerd-next"), action.paint(Role::Muted));
+    }
+
+    fn nerd_scale_attempt(&self, attempt: u32, max: u32, scale: u8, size_kb: u64, target_kb: u64, time_ms: u128, action: &str) {
+        if !self.is_nerd_mode() { return; }
+
+        let delta = if size_kb > target_kb {
+            format!("+{} KB", size_kb - target_kb).paint(Role::Error)
+        } else {
+            format!("-{} KB", target_kb - size_kb).paint(Role::Success)
+        };
+
+        let status_icon = if size_kb <= target_kb { "OK".paint(Role::Success) } else { "XX".paint(Role::Error) };
+
+        let prefix = if attempt == max { "  └─" } else { "  ├─" };
+        outln!("{} [{:>2}] {} {:>3}% -> {:>4} KB [{}] ({}) | {}ms | {}: {}",
+            prefix, attempt, t!("nerd-scale"), scale, size_kb, status_icon, delta, time_ms, t!("nerd-next"), action.paint(Role::Muted));
+    }
+
+    fn nerd_result(&self, label: &str, value: &str, is_last: bool) {
+        if !self.is_nerd_mode() { return; }
+        let prefix = if is_last { "  └─" } else { "  ├─" };
+        if value.is_empty() {
+            outln!("{} {}", prefix.paint(Role::Muted), label.paint(Role::Highlight));
+        } else {
+            outln!("{} {} {}", prefix.paint(Role::Muted), format!("{}:", label).paint(Role::Label), value);
+        }
+    }
+
+    fn nerd_output_summary(&self, _input: &str, output: &str, old_kb: u64, new_kb: u64, method: &str, time_s: f64) {
+        if !self.is_nerd_mode() { return; }
+
+        let reduction_pct = if old_kb > 0 && new_kb <= old_kb {
+            (old_kb - new_kb) as f64 / old_kb as f64 * 100.0
+        } else { 0.0 };
+
+        let ratio = if new_kb > 0 { old_kb as f64 / new_kb as f64 } else { 1.0 };
+        let saved_kb = old_kb.saturating_sub(new_kb);
+
+        outln!("\n{}", "╔═══════════════════════════════════════════════════════════════════════╗".paint(Role::Success));
+        outln!("{}", box_title(&t!("result-title"), 71, '║').paint(Role::Success).bold());
+        outln!("{}", "╠═══════════════════════════════════════════════════════════════════════╣".paint(Role::Success));
+
+        let out_name = Path::new(output).file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_else(|| output.to_string());
+        let [output, method_label, size, reduction, ratio_label, time] = labels(["label-output-file", "label-method", "label-size", "label-reduction", "label-ratio", "label-time"]);
+        outln!("  {} {}", output.paint(Role::Label), out_name.paint(Role::Success));
+        outln!("  {} {}", method_label.paint(Role::Label), method.paint(Role::Accent));
+        outln!("{}", "╠═══════════════════════════════════════════════════════════════════════╣".paint(Role::Success));
+
+        let old_size_str = format_size_with(old_kb, 2);
+        let new_size_str = format_size_with(new_kb, 2);
+
+        outln!("  {} {} → {}", size.paint(Role::Label), old_size_str, new_size_str.paint(Role::Success));
+        outln!("  {} {:.1}% ({})", reduction.paint(Role::Label), reduction_pct, t!("result-saved", size = format_size(saved_kb)));
+        outln!("  {} {:.2}:1", ratio_label.paint(Role::Label), ratio);
+        outln!("  {} {:.2}s", time.paint(Role::Label), time_s);
+
+        outln!("{}", "╚═══════════════════════════════════════════════════════════════════════╝".paint(Role::Success));
+    }
+
+    fn nerd_pages(&self, before: &PageSizes, after: Option<&PageSizes>) {
+        if !self.is_nerd_mode() { return; }
+        outln!("\n{}", "─".repeat(75).paint(Role::Border));
+        outln!("{} {}", format!("[{}]", t!("pages-tag")).paint(Role::Highlight).bold(), t!("pages-title").bold());
+        outln!("{}", "─".repeat(75).paint(Role::Border));
+
+        // The same pages after, or no after column
+        let after = after.filter(|a| a.pages.len() == before.pages.len());
+        let total = before.pages.iter().sum::<u64>() + before.shared;
+        let row = |label: String, old: u64, new: Option<u64>, prefix: &str| {
+            let share = if total > 0 { old as f64 / total as f64 } else { 0.0 };
+            let bar = "█".repeat((share * 20.0).round() as usize);
+            let new = new.map_or_else(|| "-".to_string(), |b| format_size(b.div_ceil(1024)));
+            outln!("{} {:<8} {:>10} → {:>10}  {:>5.1}% {}", prefix.paint(Role::Muted), label,
+                format_size(old.div_ceil(1024)), new, share * 100.0, bar.paint(Role::Accent));
+        };
+
+        // Long documents list their biggest pages only
+        let mut order: Vec<usize> = (0..before.pages.len()).collect();
+        if order.len() > PAGE_ROWS {
+            order.sort_by_key(|&i| std::cmp::Reverse(before.pages[i]));
+            order.truncate(PAGE_ROWS);
+            order.sort_unstable();
+        }
+        for &i in &order {
+            row(t!("pages-page", number = i + 1), before.pages[i], after.map(|a| a.pages[i]), "  ├─");
+        }
+        if order.len() < before.pages.len() {
+            outln!("{} {}", "  ├─".paint(Role::Muted), format!("... {}", t!("pages-smaller", count = before.pages.len() - order.len())).paint(Role::Muted));
+        }
+        row(t!("pages-shared"), before.shared, after.map(|a| a.shared), "  └─");
+    }
+
+    // Binary search visualization helper
+    fn nerd_search_range(&self, min: u64, max: u64, mid: u64) {
+        if !self.is_nerd_mode() { return; }
+
+        // Visual representation of search range
+        let total_range = 2400u64;
+        let width = 50usize;
+
+        let min_pos = (min as f64 / total_range as f64 * width as f64) as usize;
+        let max_pos = (max as f64 / total_range as f64 * width as f64) as usize;
+        let mid_pos = (mid as f64 / total_range as f64 * width as f64) as usize;
+
+        let mut bar = vec!['.'; width];
+        for item in bar.iter_mut().take(max_pos.min(width - 1) + 1).skip(min_pos) {
+            *item = '=';
+        }
+        if mid_pos < width {
+            bar[mid_pos] = '^';
+        }
+
+        let bar_str: String = bar.iter().collect();
+        outln!("  ├─ {}: [{}]", t!("nerd-range"), bar_str.paint(Role::Muted));
+        outln!("  ├─         {} DPI{}{} DPI",
+            min,
+            " ".repeat(mid_pos.saturating_sub(min_pos.to_string().len())),
+            max
+        );
     }
-    
-    let bar_str: String = bar.iter().collect();
-    println!("  ├─ Range: [{}]", bar_str.dimmed());
-    println!("  ├─         {} DPI{}{} DPI", 
-        min, 
-        " ".repeat(mid_pos.saturating_sub(min_pos.to_string().len())),
-        max
-    );
 }
 
 // ==================== HELPERS ====================
@@ -542,7 +803,7 @@ fn get_os_info() -> String {
 fn get_arch() -> String {
     #[cfg(target_os = "windows")]
     {
-        std::env::var("PROCESSOR_ARCHITECTURE").unwrap_or_else(|_| "Unknown".to_string())
+        std::env::var("PROCESSOR_ARCHITECTURE").unwrap_or_else(|_| t!("value-unknown"))
     }
     
     #[cfg(not(target_os = "windows"))]
@@ -551,7 +812,7 @@ fn get_arch() -> String {
             .arg("-m")
             .output()
             .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
-            .unwrap_or_else(|_| "Unknown".to_string())
+            .unwrap_or_else(|_| t!("value-unknown"))
     }
 }
 
@@ -566,7 +827,7 @@ fn get_cpu_info() -> String {
                     .and_then(|line| line.split(':').nth(1))
                     .map(|s| s.trim().to_string())
             })
-            .unwrap_or_else(|| "Unknown".to_string())
+            .unwrap_or_else(|| t!("value-unknown"))
     }
     
     #[cfg(target_os = "macos")]
@@ -576,17 +837,17 @@ fn get_cpu_info() -> String {
             .arg("machdep.cpu.brand_string")
             .output()
             .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
-            .unwrap_or_else(|_| "Unknown".to_string())
+            .unwrap_or_else(|_| t!("value-unknown"))
     }
     
     #[cfg(target_os = "windows")]
     {
-        std::env::var("PROCESSOR_IDENTIFIER").unwrap_or_else(|_| "Unknown".to_string())
+        std::env::var("PROCESSOR_IDENTIFIER").unwrap_or_else(|_| t!("value-unknown"))
     }
     
     #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
     {
-        "Unknown".to_string()
+        t!("value-unknown")
     }
 }
 
@@ -602,7 +863,7 @@ fn get_mem_info() -> String {
                     .and_then(|kb| kb.parse::<u64>().ok())
                     .map(|kb| format!("{:.1} GB", kb as f64 / 1024.0 / 1024.0))
             })
-            .unwrap_or_else(|| "Unknown".to_string())
+            .unwrap_or_else(|| t!("value-unknown"))
     }
     
     #[cfg(target_os = "macos")]
@@ -619,7 +880,7 @@ fn get_mem_info() -> String {
                     .ok()
                     .map(|bytes| format!("{:.1} GB", bytes as f64 / 1024.0 / 1024.0 / 1024.0))
             })
-            .unwrap_or_else(|| "Unknown".to_string())
+            .unwrap_or_else(|| t!("value-unknown"))
     }
     
     #[cfg(target_os = "windows")]
@@ -635,12 +896,12 @@ fn get_mem_info() -> String {
                     .and_then(|line| line.trim().parse::<u64>().ok())
                     .map(|bytes| format!("{:.1} GB", bytes as f64 / 1024.0 / 1024.0 / 1024.0))
             })
-            .unwrap_or_else(|| "Unknown".to_string())
+            .unwrap_or_else(|| t!("value-unknown"))
     }
     
     #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
     {
-        "Unknown".to_string()
+        t!("value-unknown")
     }
 }
 
@@ -650,9 +911,9 @@ fn get_tool_version(tool: &str, args: &[&str]) -> String {
         .output()
         .map(|o| {
             let out = String::from_utf8_lossy(&o.stdout);
-            out.lines().next().unwrap_or("Unknown").trim().to_string()
+            out.lines().next().map_or_else(|| t!("value-unknown"), |line| line.trim().to_string())
         })
-        .unwrap_or_else(|_| "Not found".red().to_string())
+        .unwrap_or_else(|_| t!("value-not-found").paint(Role::Error).to_string())
 }
 
 fn get_image_dimensions(path: &str) -> Option<(u32, u32)> {
@@ -673,3 +934,35 @@ fn get_image_dimensions(path: &str) -> Option<(u32, u32)> {
             }
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_size_for_byte_targets() {
+        let upload = CompResult { final_kb: 469, final_bytes: 479_812, target_kb: Some(468), target_bytes: Some(480_000), target_met: Some(true), ..CompResult::default() };
+        assert_eq!(exact_size(&upload).as_deref(), Some("479,812 of 480,000 bytes"));
+        let whole = CompResult { final_kb: 300, final_bytes: 307_200, target_kb: Some(300), target_bytes: Some(307_200), target_met: Some(true), ..CompResult::default() };
+        assert_eq!(exact_size(&whole), None);
+        assert_eq!(exact_size(&CompResult::default()), None);
+    }
+
+    #[test]
+    fn test_batch_table_aligns_columns_and_totals() {
+        let photo = CompResult { algorithm: "jpegoptim".into(), time_ms: 1500, original_kb: 2048, final_kb: 512, ..CompResult::default() };
+        let icon = CompResult { algorithm: "oxipng".into(), time_ms: 40, original_kb: 4, final_kb: 3, ..CompResult::default() };
+        let tiny = CompResult { time_ms: 2, original_kb: 10, skipped: true, ..CompResult::default() };
+        let lines = batch_table(&[("holiday/photo.jpg", &photo), ("icon.png", &icon), ("a.pdf", &tiny)]);
+
+        assert_eq!(lines.len(), 7);
+        assert!(lines[0].starts_with("File") && lines[0].ends_with("Time"));
+        assert!(lines[2].contains("2.0 MB") && lines[2].contains("512 KB") && lines[2].contains("75.0%") && lines[2].ends_with("1.50s"));
+        assert!(lines[4].contains("skipped"));
+        assert_eq!(lines[6], "3 file(s)          2.0 MB  525 KB  74.5%             1.54s");
+        // Every row lines up with the rule
+        let width = lines[1].chars().count();
+        assert!(lines.iter().all(|l| l.chars().count() <= width));
+        assert_eq!(lines[2].chars().count(), width);
+    }
+}
@@ -1,35 +1,173 @@
-mod checks;
-mod compression;
+#[macro_use]
 mod logger;
-mod utils;
+mod checks;
+mod fetch;
+mod theme;
+#[cfg(feature = "self-update")]
+mod update;
 
-use clap::Parser;
-use std::path::Path;
-use compression::CompressionLevel;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crnch::{animate, artifacts, batch, bmp, compression, extract, glob, i18n, in_place, manifest, merge, organize, plan, platform, probe, psd, retarget, split, t, utils, verify};
+use crnch::cancel::{CancellationToken, Cancelled};
+use crnch::compression::CompressionLevel;
+use crnch::config::Config;
+use crnch::decision::DecisionPolicy;
+use crnch::exec::{RecordingExecutor, ResourceLimits, SystemExecutor, ToolCommand};
+use crnch::format::Format;
+use crnch::glob::Ignore;
+use crnch::history::{History, RunRecord};
+use crnch::journal::{Journal, JournalEntry};
+use crnch::naming::{self, NameFields, NameTemplate};
+use crnch::options::{BwMode, ComicPages, CompressionOptions, CompressionOptionsBuilder, ConvertTo, Effort, PdfFonts, PdfImages, PdfMode, Stage, UnderTarget};
+use crnch::throttle::{self, Throttle};
+use crnch::utils::SizeUnits;
+use theme::{Paint, Role};
 
 #[derive(Parser)]
 #[command(name = "crnch")]
-#[command(about = "Intelligent file compression for PNG, JPG, and PDF", long_about = None)]
+#[command(about = "Intelligent file compression for images, PDFs, documents, archives, and video", long_about = None)]
 #[command(version)]
 #[command(author = "Kartik <kartikhalkunde26@gmail.com>")]
-#[command(override_usage = "crnch <FILE> [OPTIONS]")]
-#[command(after_help = "EXAMPLES:\n  crnch image.png                      Auto-compress PNG (lossless optimization)\n  crnch document.pdf                   Auto-compress PDF (standard compression)\n  crnch photo.jpg --size 200k          Compress JPG to exactly 200KB\n  crnch file.png --size 1.5m --nerd    Compress to 1.5MB with detailed output\n  crnch file.png --output result.png   Compress with custom output path\n  crnch image.png -y                   Auto-compress without prompts\n\nNOTE:\n  All options are optional! Just 'crnch file.png' works perfectly.\n  --size is only needed if you want a specific target file size.\n\nSUPPORTED FORMATS:\n  .jpg, .jpeg    JPEG images\n  .png           PNG images\n  .pdf           PDF documents\n\nSIZE FORMAT (optional):\n  Examples: 200k, 1.5m, 500kb, 2mb, 1g, 1.5gb\n  Units: k/kb (kilobytes), m/mb (megabytes), g/gb (gigabytes)\n\nFor more information, visit: https://github.com/KartikHalkunde/crnch")]
+#[command(override_usage = "crnch <FILE>... [OPTIONS]\n       crnch <COMMAND>")]
+#[command(args_conflicts_with_subcommands = true)]
+#[command(after_help = "EXAMPLES:\n  crnch image.png                      Auto-compress PNG (lossless optimization)\n  crnch document.pdf                   Auto-compress PDF (standard compression)\n  crnch photo.jpg --size 200k          Compress JPG to exactly 200KB\n  crnch file.png --size 1.5m --nerd    Compress to 1.5MB with detailed output\n  crnch file.png --output result.png   Compress with custom output path\n  crnch image.png -y                   Auto-compress without prompts\n  crnch a.png b.jpg c.pdf --size 500k  Compress several files, one result line each\n  crnch \"photos/**/*.jpg\" --size 300k  Expand the pattern in crnch itself, in any shell\n\nNOTE:\n  All options are optional! Just 'crnch file.png' works perfectly.\n  --size is only needed if you want a specific target file size.\n\nSUPPORTED FORMATS:\n  .jpg, .jpeg        JPEG images\n  .png               PNG images\n  .pdf               PDF documents\n  .webp              WebP images\n  .avif              AVIF images\n  .heic, .heif       HEIC photos (written as .jpg)\n  .gif               GIF animations\n  .svg, .svgz        SVG drawings\n  .bmp               BMP images\n  .psd               Photoshop documents (flattened)\n  .ico               Icons\n  .tif, .tiff        Scans (written as .pdf)\n  camera RAW         .cr2 .cr3 .nef .arw .dng .raf .orf .rw2 (written as .jpg)\n  .docx, .pptx       Office documents\n  .zip               ZIP archives\n  .cbz, .cbr         Comic archives (written as .cbz)\n  .mp4, .mov, .mkv   Videos\n\nSIZE FORMAT (optional):\n  Examples: 200k, 1.5m, 500kB, 2MB, 1g, 1.5GiB, 50000b\n  Units: b (bytes), k (kilobytes), m (megabytes), g (gigabytes)\n  k/m/g are 1024-based; with --si they're 1000-based\n  kB/MB/GB are always 1000-based (as upload limits are), KiB/MiB/GiB always 1024-based\n  A decimal comma works too: 1,5m\n  A unit is required: 200 on its own is rejected\n\nFor more information, visit: https://github.com/KartikHalkunde/crnch")]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// The file to compress
-    file: String,
+    #[arg(required = true)]
+    file: Option<String>,
+
+    /// More files to compress (more frames, in order, with --animate)
+    #[arg(value_name = "FILES", conflicts_with_all = ["output", "stdout", "emit_script"])]
+    frames: Vec<String>,
 
-    /// Target size (e.g., '200k', '1.5m') - Optional, auto-compress if not specified
+    /// Target size (e.g., '200k', '1.5m'), or a range ('180k-220k') to stop at the first fit inside it - Optional, auto-compress if not specified
     #[arg(short, long)]
     size: Option<String>,
 
+    /// Target the attachment limit of email, gmail, whatsapp, discord or slack, less a safety margin
+    #[arg(long, value_enum, value_name = "PLATFORM", conflicts_with_all = ["size", "total_size", "sizes"])]
+    fit: Option<platform::Platform>,
+
+    /// One budget for all the files together (e.g. '25m'), shared out in proportion to their sizes
+    #[arg(long = "total-size", value_name = "SIZE", conflicts_with_all = ["size", "stdout", "animate"])]
+    total_size: Option<String>,
+
+    /// Several outputs per file, one per size, named with it (e.g. '50k,200k,1m' writes photo_50k.jpg, ...)
+    #[arg(long, value_name = "LIST", conflicts_with_all = ["size", "total_size", "in_place", "stdout", "animate"])]
+    sizes: Option<String>,
+
+    /// Write every file's input, output, sizes, method, time or error to FILE as JSON
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["stdout", "animate"])]
+    manifest: Option<PathBuf>,
+
+    /// With several files or --recursive, when one fails: continue with the rest, or abort [default: continue]
+    #[arg(long = "on-error", value_enum, value_name = "POLICY")]
+    on_error: Option<batch::OnError>,
+
     /// Compression level (overrides size)
     #[arg(short, long, value_enum)]
     level: Option<CompressionLevel>,
 
+    /// Encode at this quality, 1-100, instead of searching for a size: JPEG/WebP/AVIF quality, pngquant's ceiling, a PDF image DPI
+    #[arg(short, long, value_name = "1-100", value_parser = clap::value_parser!(u64).range(1..=100),
+        conflicts_with_all = ["size", "level", "fit", "total_size", "sizes", "probe"])]
+    quality: Option<u64>,
+
+    /// Password of an encrypted PDF input, handed to Ghostscript
+    #[arg(long, value_name = "PW")]
+    password: Option<String>,
+
+    /// PDFs: keep only these pages, e.g. 1-10,15
+    #[arg(long, value_name = "LIST", value_parser = parse_pages)]
+    pages: Option<String>,
+
+    /// PDFs: drop every image, keeping text and vector art
+    #[arg(long = "remove-images", conflicts_with = "images_grayscale")]
+    remove_images: bool,
+
+    /// PDFs: convert images (and the rest of each page) to grayscale
+    #[arg(long = "images-grayscale")]
+    images_grayscale: bool,
+
+    /// PDFs: downsample images to exactly this DPI in one Ghostscript pass, no size search
+    #[arg(long, value_name = "DPI", value_parser = clap::value_parser!(u64).range(1..=2400),
+        conflicts_with_all = ["size", "quality", "level", "total_size", "sizes", "probe", "min_ssim", "max_distance"])]
+    dpi: Option<u64>,
+
+    /// PDFs: embed whole fonts instead of just the glyphs used
+    #[arg(long = "no-subset-fonts")]
+    no_subset_fonts: bool,
+
+    /// PDFs: don't embed fonts the input leaves out
+    #[arg(long = "no-embed-fonts")]
+    no_embed_fonts: bool,
+
+    /// PDFs: drop embedded copies of the 14 standard fonts (Times, Helvetica, Courier, ...); viewers supply their own
+    #[arg(long = "unembed-standard-fonts")]
+    unembed_standard_fonts: bool,
+
+    /// PDFs: only downsample images above this DPI; the rest are left as they are
+    #[arg(long = "downsample-above", value_name = "DPI", value_parser = clap::value_parser!(u64).range(1..=2400))]
+    downsample_above: Option<u64>,
+
     /// Custom output path
     #[arg(short, long)]
     output: Option<String>,
 
+    /// Leave matching files and folders out of --recursive walks and patterns (repeatable); a .crnchignore in the folder adds more
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// With several files or --recursive, skip files smaller than this, e.g. 500k
+    #[arg(long = "min-size", value_name = "SIZE", value_parser = parse_bytes)]
+    min_size: Option<u64>,
+
+    /// With several files or --recursive, skip files larger than this, e.g. 50m
+    #[arg(long = "max-size", value_name = "SIZE", value_parser = parse_bytes)]
+    max_size: Option<u64>,
+
+    /// With several files or --recursive, only take these formats, e.g. jpg,png
+    #[arg(long, value_name = "EXTS", value_delimiter = ',', value_parser = parse_format)]
+    only: Vec<Format>,
+
+    /// Replace FILE with the compressed version once it decodes cleanly, keeping the original as FILE.bak
+    #[arg(long = "in-place", conflicts_with_all = ["output", "output_dir", "name_template", "stdout", "organize_by_date", "convert_to", "webp", "svgz", "animate", "recursive"])]
+    in_place: bool,
+
+    /// With --in-place, don't keep FILE.bak
+    #[arg(long = "no-backup", requires = "in_place")]
+    no_backup: bool,
+
+    /// Folder for the outputs, under their original names (created if missing)
+    #[arg(long = "output-dir", value_name = "DIR", conflicts_with_all = ["output", "stdout"])]
+    output_dir: Option<PathBuf>,
+
+    /// Output name from a template: {stem}, {ext}, {date}, {target}, {size} (KB), {saved} (percent), e.g. "{stem}_{target}k.{ext}"
+    #[arg(long = "name-template", value_name = "TEMPLATE", conflicts_with_all = ["output", "stdout", "prefix", "suffix"])]
+    name_template: Option<NameTemplate>,
+
+    /// Prefix for the default output name [default: crnched_]
+    #[arg(long, value_name = "TEXT", conflicts_with = "output")]
+    prefix: Option<String>,
+
+    /// Suffix before the extension for the default output name, e.g. .min -> photo.min.jpg
+    #[arg(long, value_name = "TEXT", conflicts_with = "output")]
+    suffix: Option<String>,
+
+    /// Put the output in YYYY/MM/ folders by EXIF capture date (file date when there is none)
+    #[arg(long = "organize-by-date", conflicts_with_all = ["output", "stdout"])]
+    organize_by_date: bool,
+
+    /// FILE is a directory: compress every supported file under it into --output or --output-dir [default: crnched_<dir>], keeping the folder layout
+    #[arg(short, long, conflicts_with_all = ["stdout", "animate", "emit_script", "organize_by_date"])]
+    recursive: bool,
+
     /// Verbosity level (-v=verbose, -vv=nerd mode)
     #[arg(short = 'v', long = "verbose", action = clap::ArgAction::Count)]
     verbose: u8,
@@ -41,61 +179,1369 @@ struct Cli {
     /// Assume yes to all prompts (non-interactive mode)
     #[arg(short = 'y', long)]
     yes: bool,
+
+    /// Never accept grayscale or resize fallbacks; don't prompt for them either
+    #[arg(long)]
+    non_destructive: bool,
+
+    /// Remove GPS, serials, owner names and XMP history, then verify none remain in the output
+    #[arg(long)]
+    privacy: bool,
+
+    /// PDFs: re-encode black-and-white scanned pages as 1-bit CCITT Group 4 (--bw picks threshold or dither)
+    #[arg(long = "scan-mode")]
+    scan_mode: bool,
+
+    /// PDFs: add a searchable text layer to scanned pages (ocrmypdf + tesseract) before compressing
+    #[arg(long, conflicts_with = "split_max")]
+    ocr: bool,
+
+    /// PDFs: drop XMP, DocInfo (author, producer, creation tool, dates) and thumbnails while compressing
+    #[arg(long = "scrub-metadata")]
+    scrub_metadata: bool,
+
+    /// PDF pipeline: full (Ghostscript) or quick (lossless, in-process, no Ghostscript)
+    #[arg(long = "pdf-mode", value_enum, value_name = "MODE", default_value_t = PdfMode::Full)]
+    pdf_mode: PdfMode,
+
+    /// Write the result as WebP via cwebp; same as -o NAME.webp
+    #[arg(long, conflicts_with = "output")]
+    webp: bool,
+
+    /// Transcode an image and hit --size in the new format; same as -o NAME.<FORMAT>
+    #[arg(long = "convert-to", value_enum, value_name = "FORMAT", conflicts_with_all = ["output", "webp", "svgz", "keep_heic"])]
+    convert_to: Option<ConvertTo>,
+
+    /// Keep HEIC input as HEIC (needs heif-enc with an HEVC encoder) instead of writing JPEG
+    #[arg(long = "keep-heic", conflicts_with = "webp")]
+    keep_heic: bool,
+
+    /// Write SVG input gzipped as .svgz; same as -o NAME.svgz
+    #[arg(long, conflicts_with_all = ["output", "webp"])]
+    svgz: bool,
+
+    /// Repack .zip archives with zstd instead of deflate (smaller; Windows Explorer can't open them)
+    #[arg(long)]
+    zstd: bool,
+
+    /// What comic (.cbz/.cbr) pages are written as: png keeps each page's format, jpg turns PNG pages into JPEGs
+    #[arg(long = "comic-pages", value_enum, value_name = "FORMAT", default_value_t = ComicPages::Png)]
+    comic_pages: ComicPages,
+
+    /// Fax mode: fall back to 1-bit black and white instead of grayscale (dither or threshold)
+    #[arg(long, value_enum, value_name = "MODE", num_args = 0..=1, default_missing_value = "dither")]
+    bw: Option<BwMode>,
+
+    /// How hard to try, 1-10: search probes, oxipng level, Zopfli (9+), Guetzli (10), parallel PDF probes (7+) [default: 5]
+    #[arg(long, value_name = "1-10", value_parser = clap::value_parser!(u8).range(1..=10))]
+    effort: Option<u8>,
+
+    /// Leave a stage out of this run (repeatable): quantize, grayscale, resize, lossy
+    #[arg(long = "skip-stage", value_enum, value_name = "STAGE", value_delimiter = ',')]
+    skip_stage: Vec<Stage>,
+
+    /// What to do when the file is already under --size: skip, copy or symlink [default: ask]
+    #[arg(long = "if-under-target", value_enum, value_name = "POLICY", conflicts_with = "stdout")]
+    if_under_target: Option<UnderTarget>,
+
+    /// With no --size, skip files whose headers say they'd shrink less than PERCENT more [default: 10]
+    #[arg(long = "skip-optimized", value_name = "PERCENT", num_args = 0..=1, require_equals = true, default_missing_value = "10",
+        value_parser = clap::value_parser!(u32).range(1..=100))]
+    skip_optimized: Option<u32>,
+
+    /// With no --size, keep the original when the output saves less than PERCENT, e.g. 5%
+    #[arg(long = "min-savings", value_name = "PERCENT", value_parser = parse_savings)]
+    min_savings: Option<f64>,
+
+    /// Fail and remove the output when it ends up over --size or its --total-size share, instead of warning
+    #[arg(long)]
+    strict: bool,
+
+    /// Lowest quality the JPEG, PNG, WebP and AVIF searches may use; below it the target is missed instead
+    #[arg(long = "min-quality", value_name = "Q", value_parser = clap::value_parser!(u64).range(1..=100))]
+    min_quality: Option<u64>,
+
+    /// Smallest file whose SSIM against the input stays at least this (e.g. 0.95); with --size, a floor under the search
+    #[arg(long = "min-ssim", value_name = "SSIM", value_parser = parse_ssim, conflicts_with = "quality")]
+    min_ssim: Option<f64>,
+
+    /// Smallest JPEG or WebP whose butteraugli distance from the input stays at most this (about 1 is just visible); with --size, a floor
+    #[arg(long = "max-distance", value_name = "DISTANCE", value_parser = parse_distance, conflicts_with = "quality")]
+    max_distance: Option<f64>,
+
+    /// Smallest resize in percent the fallbacks may use
+    #[arg(long = "min-scale", value_name = "PERCENT", value_parser = clap::value_parser!(u64).range(1..=100))]
+    min_scale: Option<u64>,
+
+    /// Downscale JPGs and PNGs to fit this many pixels on each side before the size search
+    #[arg(long = "max-dimension", value_name = "PX", value_parser = clap::value_parser!(u32).range(1..))]
+    max_dimension: Option<u32>,
+
+    /// Like --max-dimension for the width only
+    #[arg(long = "max-width", value_name = "PX", value_parser = clap::value_parser!(u32).range(1..))]
+    max_width: Option<u32>,
+
+    /// Like --max-dimension for the height only
+    #[arg(long = "max-height", value_name = "PX", value_parser = clap::value_parser!(u32).range(1..))]
+    max_height: Option<u32>,
+
+    /// Cap reads and writes of the input/output at this rate, e.g. 50MB/s (tools work on local copies)
+    #[arg(long = "io-limit", value_name = "RATE", value_parser = throttle::parse_rate)]
+    io_limit: Option<u64>,
+
+    /// Copy the input and output one file at a time through local scratch space, without a rate cap
+    #[arg(long = "sequential-io")]
+    sequential_io: bool,
+
+    /// Memory cap for ImageMagick and Ghostscript, e.g. 2g
+    #[arg(long = "mem-limit", value_name = "SIZE", value_parser = parse_bytes)]
+    mem_limit: Option<u64>,
+
+    /// Thread cap for every tool that parallelizes
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    threads: Option<u32>,
+
+    /// Files compressed at once with several files or --recursive [default: CPU count]
+    #[arg(short = 'j', long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    jobs: Option<u32>,
+
+    /// Write every tool command crnch ran, in order, to this shell script
+    #[arg(long = "emit-script", value_name = "FILE")]
+    emit_script: Option<PathBuf>,
+
+    /// Combine FILE and FRAMES into an animated .webp or .gif at this path
+    #[arg(long, value_name = "OUT", conflicts_with_all = ["output", "stdout", "privacy"])]
+    animate: Option<String>,
+
+    /// Frame rate for --animate
+    #[arg(long, default_value_t = 12, requires = "animate", value_parser = clap::value_parser!(u32).range(1..=100))]
+    fps: u32,
+
+    /// Show sizes in powers of 1000 (upload portals' MB) instead of 1024, and read bare k/m/g that way
+    #[arg(long, global = true)]
+    si: bool,
+
+    /// Language for messages (en, de); defaults to LC_ALL, LC_MESSAGES or LANG
+    #[arg(long, global = true, value_name = "LANG")]
+    lang: Option<String>,
+
+    /// PDFs: join all the inputs, in order, into one and compress that (e.g. with --size 5m)
+    #[arg(long, conflicts_with_all = ["total_size", "sizes", "in_place", "stdout", "animate", "recursive", "probe", "split_max"])]
+    merge: bool,
+
+    /// PDFs: split into numbered parts (_part1, _part2, ...) each compressed under this size, e.g. 10m
+    #[arg(long = "split-max", value_name = "SIZE", conflicts_with_all = ["size", "fit", "total_size", "sizes", "pages", "in_place", "stdout", "animate", "recursive", "probe"])]
+    split_max: Option<String>,
+
+    /// Only report the size range a target can be set in, from one lossless and one aggressive encode; writes no output
+    #[arg(long, conflicts_with_all = ["size", "fit", "total_size", "sizes", "in_place", "stdout", "animate", "recursive", "emit_script", "manifest"])]
+    probe: bool,
+
+    /// Print the compression result as JSON instead of the summary
+    #[arg(long, conflicts_with = "nerd")]
+    json: bool,
+
+    /// Write the compressed file to stdout; logs go to stderr and nothing prompts
+    #[arg(long, conflicts_with_all = ["output", "json"])]
+    stdout: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Update crnch to the latest GitHub release
+    SelfUpdate {
+        /// Only report whether a newer release exists
+        #[arg(long)]
+        check: bool,
+
+        /// Install without asking for confirmation
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+    /// Save each page of a PDF (or frame of a TIFF) as a compressed image
+    Extract {
+        /// PDF or multi-page TIFF to split
+        file: String,
+
+        /// Image format for the extracted pages
+        #[arg(long, default_value = "jpg", value_parser = ["jpg", "png"])]
+        format: String,
+
+        /// Target size per page (e.g., '300k')
+        #[arg(short, long)]
+        size: Option<String>,
+
+        /// Directory for the pages [default: <name>_pages]
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Resolution PDF pages are rendered at
+        #[arg(long, default_value_t = 150)]
+        dpi: u32,
+
+        /// Accept fallbacks without prompting
+        #[arg(short = 'y', long)]
+        yes: bool,
+
+        /// Password of an encrypted PDF
+        #[arg(long, value_name = "PW")]
+        password: Option<String>,
+    },
+    /// Write a reviewable JSON plan of what compressing DIR with a rules file would do
+    Plan {
+        /// Directory to plan for
+        dir: PathBuf,
+
+        /// TOML rules: [[rule]] with match, size/level and destructive
+        #[arg(long = "size-rules", value_name = "FILE")]
+        size_rules: PathBuf,
+    },
+    /// Carry out a plan written by crnch plan
+    Apply {
+        /// The plan JSON
+        plan: PathBuf,
+    },
+    /// Check that outputs decode cleanly and still match what crnch recorded
+    Verify {
+        /// Directory of outputs, a --manifest file, or records in the history's JSON-lines format [default: every output in the run history]
+        path: Option<PathBuf>,
+    },
+    /// List recent runs that missed their target or quality floor and re-run the ones you pick
+    Review {
+        /// How far back to look, in minutes
+        #[arg(long, default_value_t = 60)]
+        minutes: u64,
+        /// Also list outputs saved under this quality (1-100)
+        #[arg(long = "min-quality", value_name = "1-100", default_value_t = 50, value_parser = clap::value_parser!(u32).range(1..=100))]
+        min_quality: u32,
+        /// Also list PDFs whose images went under this DPI
+        #[arg(long = "min-dpi", value_name = "DPI", default_value_t = 100)]
+        min_dpi: u32,
+    },
+}
+
+fn write_script(path: &Path, script: &str) -> std::io::Result<()> {
+    std::fs::write(path, script)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
+
+fn parse_bytes(s: &str) -> Result<u64, String> {
+    s.parse::<utils::TargetSize>()
+        .map(|size| size.bytes())
+        .map_err(|e| e.to_string())
+}
+
+fn parse_ssim(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(ssim) if ssim > 0.0 && ssim <= 1.0 => Ok(ssim),
+        _ => Err(format!("'{}' is not an SSIM between 0 and 1, e.g. 0.95", s)),
+    }
+}
+
+fn parse_distance(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(distance) if distance > 0.0 && distance.is_finite() => Ok(distance),
+        _ => Err(format!("'{}' is not a butteraugli distance above 0, e.g. 1.0", s)),
+    }
+}
+
+fn parse_savings(s: &str) -> Result<f64, String> {
+    match s.trim_end_matches('%').parse::<f64>() {
+        Ok(percent) if percent > 0.0 && percent < 100.0 => Ok(percent),
+        _ => Err(format!("'{}' is not a percentage between 0 and 100, e.g. 5%", s)),
+    }
+}
+
+/// Page numbers and ranges from 1, e.g. "1-10,15", in Ghostscript's PageList form
+fn parse_pages(s: &str) -> Result<String, String> {
+    let invalid = || format!("'{}' is not a page list like 1-10,15 (pages count from 1)", s);
+    let parts: Vec<String> = s.split(',').map(|part| {
+        let page = |n: &str| n.trim().parse::<u32>().ok().filter(|&n| n > 0);
+        match part.split_once('-') {
+            Some((first, last)) => match (page(first), page(last)) {
+                (Some(first), Some(last)) if first <= last => Some(format!("{}-{}", first, last)),
+                _ => None,
+            },
+            None => page(part).map(|n| n.to_string()),
+        }
+    }).collect::<Option<_>>().ok_or_else(invalid)?;
+    Ok(parts.join(","))
+}
+
+fn parse_format(s: &str) -> Result<Format, String> {
+    Format::from_extension(s.trim_start_matches('.')).ok_or_else(|| format!("'{}' is not a format crnch compresses", s))
+}
+
+fn selection(cli: &Cli) -> batch::Selection {
+    batch::Selection { min_bytes: cli.min_size, max_bytes: cli.max_size, only: cli.only.clone() }
+}
+
+/// Drops what --min-size, --max-size and --only rule out, saying how many
+fn select<T>(items: &mut Vec<T>, cli: &Cli, path: impl Fn(&T) -> &str) {
+    let selection = selection(cli);
+    let before = items.len();
+    items.retain(|item| selection.admits(Path::new(path(item))));
+    if items.len() < before && !cli.json {
+        outln!("   {}", t!("selection-left-out", count = before - items.len()));
+    }
+}
+
+fn run_command(command: Command) -> anyhow::Result<()> {
+    match command {
+        #[cfg(feature = "self-update")]
+        Command::SelfUpdate { check, yes } => update::self_update(check, yes),
+        #[cfg(not(feature = "self-update"))]
+        Command::SelfUpdate { .. } => Err(anyhow::anyhow!(t!("error-no-self-update"))),
+        Command::Extract { file, format, size, output, dpi, yes, password } => run_extract(&file, &format, size.as_deref(), output, dpi, yes, password),
+        Command::Review { minutes, min_quality, min_dpi } => run_review(minutes, min_quality, min_dpi),
+        Command::Verify { path } => run_verify(path),
+        Command::Plan { dir, size_rules } => run_plan(&dir, &size_rules),
+        Command::Apply { plan } => run_apply(&plan),
+    }
+}
+
+fn run_review(minutes: u64, min_quality: u32, min_dpi: u32) -> anyhow::Result<()> {
+    let history = History::default_location()
+        .ok_or_else(|| anyhow::anyhow!(t!("error-no-history")))?;
+    let missed: Vec<RunRecord> = history.since(minutes * 60).into_iter()
+        .filter(|r| (r.missed_target() || r.below_floor(Some(min_quality), Some(min_dpi))) && r.input.exists())
+        .collect();
+    if missed.is_empty() {
+        outln!("{} {}", "✓".paint(Role::Success), t!("review-none-missed", minutes = minutes));
+        return Ok(());
+    }
+
+    let items: Vec<String> = missed.iter()
+        .map(|r| {
+            let why = match (r.missed_target(), r.quality, r.dpi) {
+                (true, _, _) => t!("review-missed-target", size = logger::format_size(r.target_kb.unwrap_or(0))),
+                (false, Some(quality), _) if quality < min_quality => t!("review-low-quality", quality = quality),
+                (false, _, dpi) => t!("review-low-dpi", dpi = dpi.unwrap_or(0)),
+            };
+            format!("{}  {} ({})", r.input.display(), logger::format_size(r.final_kb), why)
+        })
+        .collect();
+    if !std::io::stdin().is_terminal() {
+        for item in &items {
+            outln!("   {}", item);
+        }
+        return Ok(());
+    }
+    let picked = dialoguer::MultiSelect::new()
+        .with_prompt(t!("prompt-review-pick", count = missed.len()))
+        .items(&items)
+        .interact()?;
+    if picked.is_empty() {
+        return Ok(());
+    }
+    let size: String = dialoguer::Input::new()
+        .with_prompt(t!("prompt-review-size"))
+        .interact_text()?;
+    let target = utils::validate_size(&size, logger::units())?;
+    let levels = ["low", "medium", "high"];
+    let level = match dialoguer::Select::new().with_prompt(t!("prompt-review-level")).items(&levels).default(1).interact()? {
+        0 => CompressionLevel::Low,
+        1 => CompressionLevel::Medium,
+        _ => CompressionLevel::High,
+    };
+    checks::check_dependencies(&[])?;
+
+    for record in picked.iter().map(|&i| &missed[i]) {
+        let (input, output) = (record.input.to_string_lossy(), record.output.to_string_lossy());
+        let options = CompressionOptions::builder()
+            .target(Some(target))
+            .level(Some(level))
+            .reporter(Arc::new(logger::TerminalReporter::new(1)))
+            .build();
+        match compression::compress_file(&input, &output, &options) {
+            Ok(result) => {
+                let _ = history.append(&RunRecord::new(&input, &output, &result));
+                let verdict = if result.target_met == Some(true) { "✓".paint(Role::Success) } else { "✗".paint(Role::Warning) };
+                outln!("   {} {}  {} → {}", verdict, output, logger::format_size(result.original_kb), logger::format_size(result.final_kb));
+            },
+            Err(e) => logger::log_error(&format!("{}: {:#}", input, e)),
+        }
+    }
+    Ok(())
+}
+
+fn run_plan(dir: &Path, rules_path: &Path) -> anyhow::Result<()> {
+    let text = std::fs::read_to_string(rules_path)
+        .map_err(|e| anyhow::anyhow!(t!("error-read-rules", file = rules_path.display(), error = e)))?;
+    let rules = plan::parse_rules(&text, logger::units())
+        .map_err(|e| anyhow::anyhow!(t!("error-invalid-rules", file = rules_path.display(), error = format!("{:#}", e))))?;
+    let plan = plan::plan(dir, &rules)?;
+    println!("{}", serde_json::to_string_pretty(&plan)?);
+    let compress = plan.entries.iter().filter(|e| e.action == plan::Action::Compress).count();
+    eprintln!("{}", t!("plan-counts", compress = compress, under = plan.entries.len() - compress));
+    Ok(())
+}
+
+fn run_apply(plan_path: &Path) -> anyhow::Result<()> {
+    let plan = plan::Plan::load(plan_path)?;
+    checks::check_dependencies(&[])?;
+    let options = CompressionOptions::builder()
+        .reporter(Arc::new(logger::TerminalReporter::new(1)))
+        .build();
+
+    let mut failed = 0;
+    for entry in plan.entries.iter().filter(|e| e.action == plan::Action::Compress) {
+        match plan::apply_entry(entry, &options) {
+            Ok(result) => {
+                let verdict = if result.target_met == Some(false) { "✗".paint(Role::Warning) } else { "✓".paint(Role::Success) };
+                outln!("   {} {}  {} → {}", verdict, entry.output.display(),
+                    logger::format_size(result.original_kb), logger::format_size(result.final_kb));
+            },
+            Err(e) => {
+                failed += 1;
+                logger::log_error(&format!("{}: {:#}", entry.input.display(), e));
+            },
+        }
+    }
+    if failed > 0 {
+        return Err(anyhow::anyhow!(t!("error-plan-failed", count = failed)));
+    }
+    Ok(())
+}
+
+fn run_verify(path: Option<PathBuf>) -> anyhow::Result<()> {
+    checks::check_dependencies(&[])?;
+    let history = History::default_location().map(|h| h.load()).unwrap_or_default();
+    let (mut outputs, records) = match path {
+        Some(ref dir) if dir.is_dir() => (verify::outputs_in(dir)?, history),
+        Some(ref manifest) => {
+            let records = match manifest::Manifest::load(manifest) {
+                Ok(written) => written.records(),
+                Err(_) => History::new(manifest).load(),
+            };
+            if records.is_empty() {
+                return Err(anyhow::anyhow!(t!("error-no-records", file = manifest.display())));
+            }
+            (records.iter().map(|r| r.output.clone()).collect(), records)
+        },
+        None => (history.iter().map(|r| r.output.clone()).filter(|p| p.exists()).collect(), history),
+    };
+    outputs.sort();
+    outputs.dedup();
+    let options = CompressionOptions::default();
+
+    let mut failed = 0;
+    for output in &outputs {
+        let verdict = verify::verify_file(output, verify::record_for(output, &records), &options)?;
+        if verdict.ok() {
+            let note = if verdict.recorded { String::new() } else { format!(" ({})", t!("verify-decoded-only")) };
+            outln!("   {} {}{}", "✓".paint(Role::Success), output.display(), note);
+        } else {
+            failed += 1;
+            let problems: Vec<String> = verdict.problems.iter().map(|p| p.to_string()).collect();
+            outln!("   {} {}: {}", "✗".paint(Role::Error), output.display(), problems.join("; "));
+        }
+    }
+    if failed > 0 {
+        return Err(anyhow::anyhow!(t!("error-verify-failed", failed = failed, count = outputs.len())));
+    }
+    outln!("{}", format!(">> {}", t!("verify-done", count = outputs.len())).paint(Role::Success));
+    Ok(())
+}
+
+fn run_extract(file: &str, format: &str, size: Option<&str>, output: Option<String>, dpi: u32, yes: bool, password: Option<String>) -> anyhow::Result<()> {
+    checks::check_dependencies(&[])?;
+    let format = Format::from_extension(format).expect("clap restricts --format");
+    let target = size.map(|s| utils::validate_size(s, logger::units())).transpose()?;
+    let out_dir = output.map(PathBuf::from).unwrap_or_else(|| {
+        let stem = Path::new(file).file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+        PathBuf::from(format!("{}_pages", stem))
+    });
+    let options = CompressionOptions::builder()
+        .target(target)
+        .auto_yes(yes)
+        .pdf_password(password)
+        .build();
+
+    outln!("\n{} {}", ">>".paint(Role::Accent), t!("extract-start", file = file));
+    let pages = extract::extract(file, &out_dir, format, dpi, &options)?;
+    for page in &pages {
+        outln!("   {}  {} → {}", page.path.display(),
+            logger::format_size(page.result.original_kb), logger::format_size(page.result.final_kb).paint(Role::Success));
+    }
+    outln!("{}", format!(">> {}", t!("extract-done", count = pages.len(), dir = out_dir.display())).paint(Role::Success));
+    Ok(())
+}
+
+fn run_animate(frames: &[String], output: &str, cli: &Cli) -> anyhow::Result<()> {
+    checks::check_dependencies(&[])?;
+    let target = target_size(cli)?;
+    let options = CompressionOptions::builder()
+        .target(target)
+        .level(cli.level)
+        .effort(cli.effort.map_or_else(Effort::default, Effort::new))
+        .reporter(Arc::new(logger::TerminalReporter::new(if cli.json { 0 } else { 1 })))
+        .build();
+
+    if !cli.json {
+        outln!("\n{} {}", ">>".paint(Role::Accent), t!("animate-start", count = frames.len(), fps = cli.fps));
+    }
+    let result = animate::animate(frames, output, cli.fps, &options)?;
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+    outln!("   {}  {} → {} ({})", output, t!("animate-frames", size = logger::format_size(result.original_kb)),
+        logger::format_size(result.final_kb).paint(Role::Success), result.algorithm);
+    Ok(())
+}
+
+/// --probe: "achievable: 180 KB – 2.1 MB" per file, nothing written
+fn run_probe(files: &[String], cli: &Cli, config: &Config) -> anyhow::Result<()> {
+    let formats: Vec<Format> = files.iter().filter_map(|f| Format::from_path(f)).collect();
+    checks::check_dependencies(&formats)?;
+    let options = options_builder(cli, config, None, false, DecisionPolicy::NeverDestructive, Arc::new(logger::TerminalReporter::new(0)))
+        .cancel_token(cancel_on_ctrl_c())
+        .build();
+    let mut probes = Vec::new();
+    for file in files {
+        let found = probe::probe(file, &options)?;
+        if !cli.json {
+            let ceiling = found.lossless_bytes.unwrap_or(found.original_bytes);
+            outln!("   {}  {}", file, t!("probe-range",
+                floor = logger::format_size(found.floor_bytes.div_ceil(1024)).paint(Role::Success),
+                ceiling = logger::format_size(ceiling.div_ceil(1024)), method = found.floor_method,
+                original = logger::format_size(found.original_bytes.div_ceil(1024))));
+        }
+        probes.push(found);
+    }
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(&probes)?);
+    }
+    Ok(())
+}
+
+/// `--merge`: the PDFs as one compressed document, named after the first
+/// with `_merged` unless -o names it
+fn run_merge(files: &[String], cli: &Cli, config: &Config) -> anyhow::Result<()> {
+    if let Some(file) = files.iter().find(|f| Format::from_path(f) != Some(Format::Pdf)) {
+        anyhow::bail!(t!("error-merge-not-pdf", file = file));
+    }
+    checks::check_dependencies(&[Format::Pdf])?;
+    if cli.ocr {
+        checks::check_ocr()?;
+    }
+    let output = cli.output.clone().unwrap_or_else(|| {
+        utils::labeled_path(&default_output(cli, config, Path::new(&files[0])), "merged").to_string_lossy().to_string()
+    });
+    let decisions = if cli.non_destructive { DecisionPolicy::NeverDestructive } else if cli.yes { DecisionPolicy::AlwaysAccept } else { DecisionPolicy::Ask };
+    let verbosity = if cli.nerd { 3 } else { cli.verbose.saturating_add(1).min(3) };
+    let options = options_builder(cli, config, target_size(cli)?, verbosity >= 3, decisions, Arc::new(logger::TerminalReporter::new(verbosity)))
+        .cancel_token(cancel_on_ctrl_c())
+        .build();
+    let inputs_kb: u64 = files.iter().map(|f| std::fs::metadata(f).map_or(0, |m| m.len())).sum::<u64>().div_ceil(1024);
+    let result = merge::merge(files, &output, &options)?;
+    let verdict = if result.target_met == Some(false) { "✗".paint(Role::Warning) } else { "✓".paint(Role::Success) };
+    outln!("   {} {} → {}  {} → {}", verdict, t!("merge-count", count = files.len()), output,
+        logger::format_size(inputs_kb), logger::format_size(result.final_kb).paint(Role::Success));
+    Ok(())
+}
+
+/// `--split-max`: the PDF as parts under the limit, a line per part
+fn run_split(file: &str, limit: &str, cli: &Cli, config: &Config) -> anyhow::Result<()> {
+    if Format::from_path(file) != Some(Format::Pdf) {
+        anyhow::bail!(t!("error-split-not-pdf", file = file));
+    }
+    let limit = utils::validate_size(limit, logger::units())?;
+    checks::check_dependencies(&[Format::Pdf])?;
+    let output = cli.output.clone().unwrap_or_else(|| default_output(cli, config, Path::new(file)).to_string_lossy().to_string());
+    let decisions = if cli.non_destructive { DecisionPolicy::NeverDestructive } else if cli.yes { DecisionPolicy::AlwaysAccept } else { DecisionPolicy::Ask };
+    let verbosity = if cli.nerd { 3 } else { cli.verbose.saturating_add(1).min(3) };
+    let options = options_builder(cli, config, None, verbosity >= 3, decisions, Arc::new(logger::TerminalReporter::new(verbosity)))
+        .cancel_token(cancel_on_ctrl_c())
+        .build();
+    let parts = split::split(file, &output, limit, &options)?;
+    for part in &parts {
+        let (first, last) = part.pages;
+        let pages = if first == last { t!("split-page", page = first) } else { t!("split-pages", first = first, last = last) };
+        let verdict = if part.result.target_met == Some(false) { "✗".paint(Role::Warning) } else { "✓".paint(Role::Success) };
+        outln!("   {} {}  {}  {}", verdict, part.path.display(), pages, logger::format_size(part.result.final_kb));
+    }
+    outln!("{}", format!(">> {}", t!("split-done", file = file, count = parts.len(), size = logger::format_size(limit.kb()))).paint(Role::Success));
+    Ok(())
+}
+
+/// Default output name for `input_path`: prefix/suffix from the flags or config
+/// (`default_prefix` when neither sets one), with the extension of the format it's written as
+fn output_name(cli: &Cli, config: &Config, input_path: &Path, default_prefix: &str) -> String {
+    // A suffix alone means suffix style (photo.min.jpg), so drop the default prefix
+    let suffix = cli.suffix.clone().or_else(|| config.output.suffix.clone()).unwrap_or_default();
+    let prefix = cli.prefix.clone()
+        .or_else(|| config.output.prefix.clone())
+        .unwrap_or_else(|| if suffix.is_empty() { default_prefix.to_string() } else { String::new() });
+    let name = utils::default_output_name(input_path, &prefix, &suffix);
+    let name = match output_extension(cli, input_path) {
+        Some(ext) => Path::new(&name).with_extension(ext).to_string_lossy().to_string(),
+        None => name,
+    };
+    match name_template(cli, config) {
+        Some(template) => template.render(&name_fields(cli, input_path, &name)),
+        None => name,
+    }
+}
+
+/// The extension `input_path` is written with when that isn't its own: HEIC
+/// is written as JPEG unless kept, RAW always, BMP as whichever of PNG and JPEG suits it,
+/// PSD as PNG with transparency and JPEG without, TIFF scans as PDF, CBR comics as CBZ
+fn output_extension(cli: &Cli, input_path: &Path) -> Option<&'static str> {
+    match Format::from_path(&input_path.to_string_lossy()) {
+        _ if cli.convert_to.is_some() => cli.convert_to.map(|c| c.format().extension()),
+        _ if cli.webp => Some("webp"),
+        _ if cli.svgz => Some("svgz"),
+        Some(Format::Heic) if !cli.keep_heic => Some("jpg"),
+        Some(Format::Raw) => Some("jpg"),
+        Some(Format::Tiff) => Some("pdf"),
+        Some(Format::Psd) => Some(psd::output_format(input_path).extension()),
+        Some(Format::Bmp) => Some(bmp::output_format(input_path).extension()),
+        Some(Format::Comic) if !input_path.extension().is_some_and(|e| e.eq_ignore_ascii_case("cbz")) => Some("cbz"),
+        _ => None,
+    }
+}
+
+/// --name-template, else the config's template unless --prefix or --suffix overrides it
+fn name_template<'a>(cli: &'a Cli, config: &'a Config) -> Option<&'a NameTemplate> {
+    cli.name_template.as_ref()
+        .or_else(|| config.output.template.as_ref().filter(|_| cli.prefix.is_none() && cli.suffix.is_none()))
+}
+
+/// Template fields for `input_path` written as `name`, before its size is known
+fn name_fields(cli: &Cli, input_path: &Path, name: &str) -> NameFields {
+    NameFields {
+        stem: input_path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| "output".to_string()),
+        ext: Path::new(name).extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default(),
+        target_kb: target_size(cli).ok().flatten().map(|t| t.kb()),
+        ..Default::default()
+    }
+}
+
+/// A template with {size} or {saved} can only be filled in once the output is
+/// written, so it's renamed then; returns where the output ended up
+fn rename_by_result(cli: &Cli, config: &Config, input: &str, output: &str, result: &compression::CompResult) -> anyhow::Result<String> {
+    let Some(template) = name_template(cli, config).filter(|t| t.needs_result() && !result.skipped) else {
+        return Ok(output.to_string());
+    };
+    let fields = NameFields {
+        size_kb: Some(result.final_kb),
+        saved_percent: Some(naming::saved_percent(result.original_kb, result.final_kb)),
+        ..name_fields(cli, Path::new(input), output)
+    };
+    let renamed = Path::new(output).with_file_name(template.render(&fields));
+    if renamed == Path::new(output) {
+        return Ok(output.to_string());
+    }
+    let renamed = utils::numbered_path(&renamed);
+    std::fs::rename(output, &renamed)
+        .map_err(|e| anyhow::anyhow!(t!("error-rename", file = output, renamed = renamed.display(), error = e)))?;
+    Ok(renamed.to_string_lossy().to_string())
+}
+
+/// Where `input_path` goes without --output: its output name in --output-dir
+/// (the original name there, unless --prefix or --suffix is given) or the
+/// current directory, in YYYY/MM/ folders with --organize-by-date
+fn default_output(cli: &Cli, config: &Config, input_path: &Path) -> PathBuf {
+    let (dir, default_prefix) = match cli.output_dir {
+        Some(ref dir) => (dir.as_path(), ""),
+        None => (Path::new(""), utils::DEFAULT_OUTPUT_PREFIX),
+    };
+    let name = output_name(cli, config, input_path, default_prefix);
+    if cli.organize_by_date {
+        organize::dated_path(input_path, dir, &name)
+    } else {
+        dir.join(name)
+    }
+}
+
+/// Everything the flags (and the config's per-format defaults under them) set on a run,
+/// short of the cancel token, executor and retarget ceiling
+fn options_builder(cli: &Cli, config: &Config, target: Option<utils::TargetSize>, is_nerd: bool, decisions: DecisionPolicy, reporter: Arc<logger::TerminalReporter>) -> CompressionOptionsBuilder {
+    let builder = CompressionOptions::builder()
+        .target(target)
+        .target_min(target_floor(cli))
+        .level(cli.level)
+        .quality(cli.quality)
+        .nerd(is_nerd)
+        .decisions(decisions)
+        .skip_stages(cli.skip_stage.iter().copied())
+        .privacy(cli.privacy)
+        .pdf_scrub_metadata(cli.scrub_metadata)
+        .pdf_ocr(cli.ocr)
+        .pdf_scan_mode(cli.scan_mode)
+        .limits(ResourceLimits { memory_bytes: cli.mem_limit, threads: cli.threads })
+        .under_target(cli.if_under_target.unwrap_or_default())
+        .skip_optimized(cli.skip_optimized)
+        .strict(cli.strict)
+        .min_savings(cli.min_savings)
+        .min_quality(cli.min_quality)
+        .min_scale(cli.min_scale)
+        .min_ssim(cli.min_ssim)
+        .pdf_dpi(cli.dpi)
+        .pdf_downsample_above(cli.downsample_above)
+        .pdf_fonts(PdfFonts { subset: !cli.no_subset_fonts, embed: !cli.no_embed_fonts, unembed_standard: cli.unembed_standard_fonts })
+        .pdf_password(cli.password.clone())
+        .pdf_pages(cli.pages.clone())
+        .pdf_images(if cli.remove_images { PdfImages::Remove } else if cli.images_grayscale { PdfImages::Grayscale } else { PdfImages::Keep })
+        .max_distance(cli.max_distance)
+        .max_dimensions(cli.max_width.or(cli.max_dimension), cli.max_height.or(cli.max_dimension))
+        .pdf_mode(cli.pdf_mode)
+        .zip_zstd(cli.zstd)
+        .comic_pages(cli.comic_pages)
+        .bw(cli.bw)
+        .effort(cli.effort.map_or_else(Effort::default, Effort::new))
+        .jpeg_quality(config.formats.jpeg_quality)
+        .png_effort(config.formats.png_effort.filter(|_| cli.effort.is_none()).map(Effort::new))
+        .pdf_preset(config.formats.pdf_preset)
+        .reporter(reporter);
+    if cli.io_limit.is_some() || cli.sequential_io {
+        builder.io_throttle(Throttle::new(cli.io_limit))
+    } else {
+        builder
+    }
+}
+
+/// Ctrl+C stops the search and kills the running tool; a second press exits immediately
+fn cancel_on_ctrl_c() -> CancellationToken {
+    let cancel = CancellationToken::new();
+    let handler_token = cancel.clone();
+    let _ = ctrlc::set_handler(move || {
+        if handler_token.is_cancelled() {
+            std::process::exit(130);
+        }
+        handler_token.cancel();
+    });
+    cancel
+}
+
+/// One line per file for runs over several of them
+fn log_file_result(input: &str, output: &str, result: &compression::CompResult) {
+    if result.already_optimized {
+        outln!("   {} {}", "-".paint(Role::Muted), t!("already-optimized", file = input));
+        return;
+    }
+    if result.skipped {
+        outln!("   {} {}", "-".paint(Role::Muted), t!("already-under-target", file = input));
+        return;
+    }
+    if result.not_worth {
+        outln!("   {} {}", "-".paint(Role::Muted), t!("not-worth-recompressing", file = input));
+        return;
+    }
+    let verdict = if result.target_met == Some(false) { "✗".paint(Role::Warning) } else { "✓".paint(Role::Success) };
+    let exact = logger::exact_size(result).map(|e| format!("  ({})", e)).unwrap_or_default();
+    outln!("   {} {} → {}  {} → {}{}", verdict, input, output,
+        logger::format_size(result.original_kb), logger::format_size(result.final_kb), exact);
+}
+
+/// Workers for a run over several files: --jobs, else one per CPU
+fn workers(cli: &Cli) -> usize {
+    cli.jobs.map_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()), |n| n as usize)
+}
+
+/// Workers can't prompt, so fallbacks are declined unless -y accepts them;
+/// a run on one worker asks as a single file would
+fn batch_decisions(cli: &Cli, workers: usize) -> DecisionPolicy {
+    if cli.non_destructive {
+        DecisionPolicy::NeverDestructive
+    } else if cli.yes {
+        DecisionPolicy::AlwaysAccept
+    } else if workers == 1 {
+        DecisionPolicy::Ask
+    } else {
+        DecisionPolicy::NeverDestructive
+    }
+}
+
+/// With --sizes, each job becomes one per size, its output labeled with it
+fn with_sizes(jobs: Vec<batch::Job>, cli: &Cli) -> anyhow::Result<Vec<batch::Job>> {
+    let Some(ref list) = cli.sizes else { return Ok(jobs) };
+    let sizes = utils::parse_sizes(list, logger::units())?;
+    Ok(jobs.iter().flat_map(|job| sizes.iter().map(move |(label, target)| batch::Job {
+        output: utils::numbered_path(&utils::labeled_path(Path::new(&job.output), label)).to_string_lossy().to_string(),
+        target: Some(*target),
+        ..job.clone()
+    })).collect())
+}
+
+/// --size (the upper end of a range), or the limit --fit names
+fn target_size(cli: &Cli) -> anyhow::Result<Option<utils::TargetSize>> {
+    match cli.fit {
+        Some(platform) => Ok(Some(platform.target())),
+        None => Ok(cli.size.as_deref().map(|s| utils::validate_size_range(s, logger::units())).transpose()?.map(|(_, max)| max)),
+    }
+}
+
+/// Lower end of a --size range; [`target_size`] reports a malformed one
+fn target_floor(cli: &Cli) -> Option<utils::TargetSize> {
+    let size = cli.size.as_deref().filter(|_| cli.fit.is_none())?;
+    utils::validate_size_range(size, logger::units()).ok()?.0
+}
+
+/// --total-size in KB
+fn total_size(cli: &Cli) -> anyhow::Result<Option<u64>> {
+    Ok(cli.total_size.as_deref().map(|s| utils::validate_size(s, logger::units())).transpose()?.map(|t| t.kb()))
+}
+
+/// `work` over every job: on the batch lanes with a live line per file in
+/// flight, or in order on one worker. Result lines as files finish, then
+/// history and --json; returns every file's manifest entry.
+fn run_jobs(
+    jobs: &[batch::Job],
+    workers: usize,
+    cli: &Cli,
+    config: &Config,
+    work: impl Fn(&batch::Job) -> anyhow::Result<(String, compression::CompResult)> + Sync,
+) -> anyhow::Result<Vec<manifest::Entry>> {
+    let report = |job: &batch::Job, result: &anyhow::Result<(String, compression::CompResult)>| match result {
+        Ok((output, result)) if !cli.json => log_file_result(&job.input, output, result),
+        Err(e) if !cli.json && !e.is::<Cancelled>() && !e.is::<batch::NotStarted>() => logger::log_error(&format!("{}: {:#}", job.input, e)),
+        _ => {},
+    };
+    let on_error = cli.on_error.unwrap_or_default();
+    // Byte-identical inputs are compressed once; every file is its own output in place
+    let twins = if cli.in_place { vec![None; jobs.len()] } else { batch::duplicates(jobs) };
+    let unique: Vec<batch::Job> = jobs.iter().zip(&twins).filter(|(_, twin)| twin.is_none()).map(|(job, _)| job.clone()).collect();
+    if unique.len() < jobs.len() && !cli.json {
+        outln!("   {}", t!("duplicates-found", count = jobs.len() - unique.len()));
+    }
+    let done = if workers > 1 {
+        let progress = logger::BatchProgress::new(!cli.json);
+        batch::run_all(&unique, workers, on_error, |job| {
+            progress.start(&job.input);
+            work(job)
+        }, |job, result| progress.finish(&job.input, || report(job, result)))
+    } else {
+        let mut done = Vec::new();
+        let mut stopped = false;
+        for job in &unique {
+            let result = if stopped { Err(batch::NotStarted.into()) } else { work(job) };
+            report(job, &result);
+            let cancelled = result.as_ref().is_err_and(|e| e.is::<Cancelled>());
+            stopped |= result.is_err() && on_error == batch::OnError::Abort;
+            done.push(result);
+            if cancelled {
+                break;
+            }
+        }
+        done
+    };
+    if done.iter().any(|r| r.as_ref().is_err_and(|e| e.is::<Cancelled>())) {
+        outln!();
+        logger::log_warning(&t!("warning-cancelled"));
+        std::process::exit(130);
+    }
+
+    // Back to one result per job, duplicates taking their first copy's output
+    let mut done = done.into_iter();
+    let mut results: Vec<anyhow::Result<(String, compression::CompResult)>> = Vec::with_capacity(jobs.len());
+    for (job, twin) in jobs.iter().zip(&twins) {
+        let Some(first) = *twin else {
+            results.push(done.next().expect("every unique job ran"));
+            continue;
+        };
+        let result = match &results[first] {
+            Ok((_, result)) if result.skipped => Ok((job.output.clone(), result.clone())),
+            Ok((output, result)) => batch::link_duplicate(Path::new(output), Path::new(&job.output)).and_then(|_| {
+                let result = compression::CompResult { algorithm: format!("Duplicate of {}", jobs[first].input), time_ms: 0, ..result.clone() };
+                Ok((rename_by_result(cli, config, &job.input, &job.output, &result)?, result))
+            }),
+            Err(e) if e.is::<batch::NotStarted>() => Err(batch::NotStarted.into()),
+            Err(_) => Err(anyhow::anyhow!(t!("error-duplicate-failed", file = jobs[first].input))),
+        };
+        report(job, &result);
+        results.push(result);
+    }
+    let unstarted = results.iter().filter(|r| r.as_ref().is_err_and(|e| e.is::<batch::NotStarted>())).count();
+    if unstarted > 0 && !cli.json {
+        logger::log_warning(&t!("warning-aborted", count = unstarted));
+    }
+
+    let history = History::default_location();
+    let mut entries = Vec::new();
+    let mut manifest = Vec::new();
+    for (job, result) in jobs.iter().zip(&results) {
+        match result {
+            Ok((output, result)) => {
+                if let (Some(history), false) = (&history, result.skipped) {
+                    let _ = history.append(&RunRecord::new(&job.input, output, result));
+                }
+                entries.push(serde_json::json!({ "input": job.input, "output": output, "result": result }));
+                manifest.push(manifest::Entry::done(&job.input, output, result));
+            },
+            Err(e) => manifest.push(manifest::Entry::failed(&job.input, e)),
+        }
+    }
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else {
+        // Result lines scroll away on big runs; the table keeps them together with totals
+        let finished: Vec<(&str, &compression::CompResult)> = jobs.iter().zip(&results)
+            .filter_map(|(job, result)| result.as_ref().ok().map(|(_, r)| (job.input.as_str(), r)))
+            .collect();
+        if finished.len() > 1 {
+            logger::log_batch_table(&finished);
+        }
+    }
+    Ok(manifest)
+}
+
+/// --manifest, then the --total-size budget; fails if any file did
+fn finish_batch(cli: &Cli, entries: Vec<manifest::Entry>) -> anyhow::Result<()> {
+    let manifest = manifest::Manifest::new(entries);
+    if let Some(ref path) = cli.manifest {
+        manifest.write(path).map_err(|e| anyhow::anyhow!(t!("error-manifest-write", file = path.display(), error = format!("{:#}", e))))?;
+    }
+    if let Some(budget_kb) = total_size(cli)? {
+        // Skipped files weren't written; the original is what gets sent
+        let total_kb = manifest.totals.final_kb;
+        if total_kb > budget_kb {
+            return Err(anyhow::anyhow!(t!("error-over-budget",
+                total = logger::format_size(total_kb), budget = logger::format_size(budget_kb))));
+        }
+        if !cli.json {
+            outln!("\n   {} {}", "✓".paint(Role::Success),
+                t!("within-budget", total = logger::format_size(total_kb), budget = logger::format_size(budget_kb)));
+        }
+    }
+    if manifest.totals.failed > 0 {
+        return Err(anyhow::anyhow!(t!("error-batch-failed", failed = manifest.totals.failed, count = manifest.totals.files)));
+    }
+    Ok(())
+}
+
+/// Everything but the reporter for a run over several files; stage-by-stage
+/// logs from several workers would interleave, so those get a line per file
+fn batch_options(cli: &Cli, config: &Config, workers: usize) -> anyhow::Result<CompressionOptions> {
+    let target = target_size(cli)?;
+    let verbosity = if cli.json || workers > 1 { 0 } else if cli.nerd { 3 } else { cli.verbose.saturating_add(1).min(3) };
+    let mut builder = options_builder(cli, config, target, verbosity >= 3, batch_decisions(cli, workers), Arc::new(logger::TerminalReporter::new(verbosity)))
+        .cancel_token(cancel_on_ctrl_c());
+    // Every size starts from the same lossless pass or decode
+    if cli.sizes.is_some() {
+        builder = builder.shared(Arc::new(artifacts::Shared::new(None)?));
+    }
+    Ok(builder.build())
+}
+
+/// Directories mirrored into `crnched_<dir>` (or --output) on the batch worker lanes
+fn run_tree(roots: &[String], cli: &Cli, config: &Config) -> anyhow::Result<()> {
+    let mut jobs = Vec::new();
+    for root in roots {
+        let root = Path::new(root);
+        if !root.is_dir() {
+            return Err(anyhow::anyhow!(t!("error-not-directory", dir = root.display())));
+        }
+        let absolute = std::path::absolute(root)?;
+        let name = absolute.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| "root".to_string());
+        // Several trees under one --output-dir each keep their own folder
+        let out_dir = match (&cli.output, &cli.output_dir) {
+            (Some(dir), _) => PathBuf::from(dir),
+            (_, Some(dir)) if roots.len() == 1 => dir.clone(),
+            (_, Some(dir)) => dir.join(name),
+            _ => absolute.with_file_name(format!("{}{}", utils::DEFAULT_OUTPUT_PREFIX, name)),
+        };
+        jobs.extend(batch::mirror_tree(root, &out_dir, &Ignore::new(cli.exclude.iter().cloned()), |input| output_name(cli, config, input, ""))?);
+    }
+    if jobs.is_empty() {
+        return Err(anyhow::anyhow!(t!("error-no-supported-files", dirs = roots.join(", "))));
+    }
+    select(&mut jobs, cli, |j| j.input.as_str());
+    if jobs.is_empty() {
+        return Ok(());
+    }
+    let mut jobs = with_sizes(jobs, cli)?;
+    let formats: Vec<Format> = jobs.iter().filter_map(|j| Format::from_path(&j.input)).collect();
+    checks::check_dependencies(&formats)?;
+    if let Some(convert_to) = cli.convert_to {
+        checks::check_format_tool(convert_to.format())?;
+    }
+    if cli.max_distance.is_some() {
+        checks::check_butteraugli()?;
+    }
+    if cli.ocr && formats.contains(&Format::Pdf) {
+        checks::check_ocr()?;
+    }
+    if let Some(budget_kb) = total_size(cli)? {
+        batch::share_budget(&mut jobs, budget_kb);
+    }
+    let workers = workers(cli).min(jobs.len());
+    let options = batch_options(cli, config, workers)?;
+    let journal = Journal::default_location();
+    if let Some(ref journal) = journal {
+        recover_leftovers(journal, cli.yes, cli.json);
+    }
+
+    if !cli.json {
+        outln!("\n{} {}", ">>".paint(Role::Accent), t!("batch-start", count = jobs.len()));
+    }
+    let entries = run_jobs(&jobs, workers, cli, config, |job| {
+        let _guard = journal.as_ref().map(|j| j.begin(JournalEntry::new(&job.input, &job.output, &options))).transpose()?;
+        let result = batch::compress_job(job, &options)?;
+        Ok((rename_by_result(cli, config, &job.input, &job.output, &result)?, result))
+    })?;
+    finish_batch(cli, entries)
+}
+
+/// Several FILEs (or one --in-place), with a result line each; fails if any of them did
+fn run_many(mut files: Vec<String>, cli: &Cli, config: &Config) -> anyhow::Result<()> {
+    select(&mut files, cli, |f| f.as_str());
+    if files.is_empty() {
+        return Ok(());
+    }
+    let formats: Vec<Format> = files.iter().filter_map(|f| Format::from_path(f)).collect();
+    checks::check_dependencies(&formats)?;
+    if let Some(convert_to) = cli.convert_to {
+        checks::check_format_tool(convert_to.format())?;
+    }
+    if cli.max_distance.is_some() {
+        checks::check_butteraugli()?;
+    }
+    if cli.ocr && formats.contains(&Format::Pdf) {
+        checks::check_ocr()?;
+    }
+    let workers = workers(cli).min(files.len());
+    let options = batch_options(cli, config, workers)?;
+    let journal = Journal::default_location();
+    if let Some(ref journal) = journal {
+        recover_leftovers(journal, cli.yes, cli.json);
+    }
+
+    // Outputs are planned up front, so two photo.jpg from different folders don't share one
+    let mut taken: Vec<PathBuf> = Vec::new();
+    let mut jobs = Vec::new();
+    let mut rejected = Vec::new();
+    for file in &files {
+        let input_path = Path::new(file);
+        let checked = if !input_path.exists() {
+            Err(anyhow::anyhow!(t!("error-not-found", file = file)))
+        } else if input_path.is_dir() {
+            Err(anyhow::anyhow!(t!("error-is-directory", file = file)))
+        } else if let (true, Some(ext)) = (cli.in_place, output_extension(cli, input_path)) {
+            Err(anyhow::anyhow!(t!("error-in-place-convert", file = file, ext = ext)))
+        } else {
+            utils::validate_file_extension(file).map(|_| ())
+        };
+        if let Err(e) = checked {
+            if !cli.json {
+                logger::log_error(&format!("{}: {:#}", file, e));
+            }
+            rejected.push(manifest::Entry::failed(file, &e));
+            continue;
+        }
+        if cli.in_place {
+            jobs.push(batch::Job::new(file.as_str(), file.as_str()));
+            continue;
+        }
+        // With --sizes, -o names the one file's outputs before their labels
+        let planned = match cli.output {
+            Some(ref output) if cli.sizes.is_some() && files.len() == 1 => PathBuf::from(output),
+            _ => default_output(cli, config, input_path),
+        };
+        let output = utils::numbered_path_avoiding(&planned, &taken);
+        taken.push(output.clone());
+        jobs.push(batch::Job::new(file.as_str(), output.to_string_lossy()));
+    }
+    let mut jobs = with_sizes(jobs, cli)?;
+
+    if let Some(budget_kb) = total_size(cli)? {
+        batch::share_budget(&mut jobs, budget_kb);
+    }
+    if !rejected.is_empty() && cli.on_error == Some(batch::OnError::Abort) {
+        if !cli.json && !jobs.is_empty() {
+            logger::log_warning(&t!("warning-aborted", count = jobs.len()));
+        }
+        rejected.extend(jobs.iter().map(|job| manifest::Entry::failed(&job.input, &batch::NotStarted.into())));
+        return finish_batch(cli, rejected);
+    }
+    let entries = run_jobs(&jobs, workers, cli, config, |job| {
+        if cli.in_place {
+            let mut options = options.clone();
+            if let Some(target) = job.target {
+                options.set_target(target);
+            }
+            let done = in_place::compress_in_place(Path::new(&job.input), !cli.no_backup, &options)?;
+            return Ok((job.input.clone(), done.result));
+        }
+        let _guard = journal.as_ref().map(|j| j.begin(JournalEntry::new(&job.input, &job.output, &options))).transpose()?;
+        let result = batch::compress_job(job, &options)?;
+        Ok((rename_by_result(cli, config, &job.input, &job.output, &result)?, result))
+    })?;
+    rejected.extend(entries);
+    finish_batch(cli, rejected)
+}
+
+/// Offer to clean up after earlier runs that died before they could
+fn recover_leftovers(journal: &Journal, auto_yes: bool, quiet: bool) {
+    for leftover in journal.leftovers() {
+        let count = leftover.scratch_files.len() + usize::from(leftover.partial_output.is_some());
+        if count == 0 {
+            let _ = journal.dismiss(&leftover);
+            continue;
+        }
+        let clean = if quiet || auto_yes {
+            auto_yes
+        } else {
+            logger::log_warning(&t!("warning-leftovers", file = leftover.entry.input.display(), count = count));
+            dialoguer::Confirm::new()
+                .with_prompt(t!("prompt-clean-up"))
+                .default(true)
+                .interact()
+                .unwrap_or(false)
+        };
+        if !clean {
+            continue;
+        }
+        match journal.reclaim(&leftover) {
+            Ok(removed) if !quiet => {
+                outln!("   {}", t!("leftovers-removed", count = removed));
+                if leftover.partial_output.is_some() {
+                    outln!("   {}", t!("leftovers-resume", file = leftover.entry.input.display(), output = leftover.entry.output.display()));
+                }
+            },
+            Ok(_) => {},
+            Err(e) => logger::log_warning(&t!("warning-leftovers-failed", error = e)),
+        }
+    }
+}
+
+/// User config; a broken file is reported but never blocks a run
+fn load_config() -> Config {
+    let config = match Config::default_location().map(|path| Config::load(&path)) {
+        Some(Ok(config)) => config,
+        Some(Err(e)) => {
+            logger::log_warning(&format!("{:#}", e));
+            Config::default()
+        },
+        None => Config::default(),
+    };
+    match theme::Theme::from_config(&config.theme) {
+        Ok(theme) => theme::init(theme),
+        Err(e) => logger::log_warning(&t!("warning-theme", error = e)),
+    }
+    config
 }
 
 fn main() {
-    // 1. Check Dependencies (Cross-Distro)
-    if let Err(e) = checks::check_dependencies() {
-        eprintln!("{}", e);
+    let mut cli = Cli::parse();
+    i18n::set_language(cli.lang.as_deref());
+
+    // stdout carries the compressed bytes, so everything human-readable moves to stderr
+    if cli.stdout {
+        logger::route_to_stderr();
+    }
+    let config = load_config();
+    if let Some(ref lang) = cli.lang {
+        if i18n::catalog().lang() == "en" && !lang.to_ascii_lowercase().starts_with("en") {
+            logger::log_warning(&t!("warning-no-language", lang = lang, available = i18n::available().collect::<Vec<_>>().join(", ")));
+        }
+    }
+    logger::set_units(if cli.si { SizeUnits::Decimal } else { SizeUnits::Binary });
+
+    if let Some(command) = cli.command.take() {
+        if let Err(e) = run_command(command) {
+            logger::log_error(&format!("{:#}", e));
+            std::process::exit(1);
+        }
+        return;
+    }
+    let file = cli.file.take().expect("clap requires FILE without a subcommand");
+
+    // The config's defaults, where no flag says otherwise
+    cli.yes |= config.auto_yes;
+    if cli.output.is_none() && cli.output_dir.is_none() && !cli.stdout && !cli.in_place {
+        cli.output_dir = config.output.dir.clone();
+    }
+
+    // Quoted patterns ("photos/**/*.jpg") expand here, so every shell gets the same files
+    let mut files = Vec::new();
+    for arg in std::iter::once(file).chain(cli.frames.drain(..)) {
+        match glob::expand(&arg, &Ignore::new(cli.exclude.iter().cloned())) {
+            _ if fetch::is_url(&arg) => files.push(arg),
+            Ok(Some(found)) => files.extend(found.into_iter().map(|p| p.to_string_lossy().to_string())),
+            Ok(None) => files.push(arg),
+            Err(e) => {
+                logger::log_error(&e.to_string());
+                std::process::exit(1);
+            },
+        }
+    }
+
+    // URLs are downloaded first, then checked like any local file; outputs land in the current directory
+    let downloads = match files.iter().any(|f| fetch::is_url(f)).then(tempfile::tempdir).transpose() {
+        Ok(dir) => dir,
+        Err(e) => {
+            logger::log_error(&t!("error-temp-dir", error = e));
+            std::process::exit(1);
+        },
+    };
+    if cli.in_place && cli.if_under_target == Some(UnderTarget::Symlink) {
+        logger::log_error(&t!("error-in-place-symlink"));
+        std::process::exit(1);
+    }
+    if let (Some(_), true) = (&downloads, cli.in_place) {
+        logger::log_error(&t!("error-in-place-url"));
+        std::process::exit(1);
+    }
+    if let Some(ref dir) = downloads {
+        let reporter = logger::TerminalReporter::new(if cli.json { 0 } else { 1 });
+        for arg in files.iter_mut().filter(|f| fetch::is_url(f)) {
+            match fetch::download(arg, dir.path(), &reporter) {
+                Ok(path) => *arg = path.to_string_lossy().to_string(),
+                Err(e) => {
+                    logger::log_error(&format!("{:#}", e));
+                    std::process::exit(1);
+                },
+            }
+        }
+    }
+    let file = files.remove(0);
+    cli.frames = files;
+
+    if cli.stdout && std::io::stdout().is_terminal() {
+        logger::log_error(&t!("error-stdout-terminal"));
+        eprintln!("\n{}", t!("tip-stdout-terminal", file = file, ext = Path::new(&file).extension().and_then(|e| e.to_str()).unwrap_or("bin")));
         std::process::exit(1);
     }
 
-    let cli = Cli::parse();
+    if cli.probe {
+        let files: Vec<String> = std::iter::once(file).chain(cli.frames.drain(..)).collect();
+        if let Err(e) = run_probe(&files, &cli, &config) {
+            logger::log_error(&format!("{:#}", e));
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    // Set verbosity level: --nerd = 3, -vv = 3, -v = 2, default = 1
-    let verbosity = if cli.nerd { 3 } else { cli.verbose.saturating_add(1).min(3) };
-    logger::set_verbosity(verbosity);
+    if cli.merge {
+        let files: Vec<String> = std::iter::once(file).chain(cli.frames.drain(..)).collect();
+        if let Err(e) = run_merge(&files, &cli, &config) {
+            logger::log_error(&format!("{:#}", e));
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(ref limit) = cli.split_max {
+        if let Err(e) = run_split(&file, limit, &cli, &config) {
+            logger::log_error(&format!("{:#}", e));
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(out) = cli.animate.take() {
+        let frames: Vec<String> = std::iter::once(file).chain(cli.frames.drain(..)).collect();
+        if let Err(e) = run_animate(&frames, &out, &cli) {
+            logger::log_error(&format!("{:#}", e));
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if cli.recursive {
+        let roots: Vec<String> = std::iter::once(file).chain(cli.frames.drain(..)).collect();
+        if let Err(e) = run_tree(&roots, &cli, &config) {
+            logger::log_error(&format!("{:#}", e));
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if !cli.frames.is_empty() || cli.in_place || cli.total_size.is_some() || cli.sizes.is_some() || cli.skip_optimized.is_some() || cli.manifest.is_some() || !selection(&cli).is_unrestricted() {
+        let files: Vec<String> = std::iter::once(file).chain(cli.frames.drain(..)).collect();
+        if let Err(e) = run_many(files, &cli, &config) {
+            logger::log_error(&format!("{:#}", e));
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // 1. Check Dependencies (Cross-Distro); quick PDF mode and SVG need none, video only ffmpeg
+    let input_format = Format::from_path(&file);
+    let needs_tools = !(cli.pdf_mode == PdfMode::Quick && input_format == Some(Format::Pdf) && !cli.privacy) && input_format != Some(Format::Svg);
+    let checked = match input_format {
+        Some(Format::Video) => checks::check_format_tool(Format::Video),
+        _ if needs_tools => checks::check_dependencies(input_format.as_slice()),
+        _ => Ok(()),
+    };
+    if let Err(e) = checked {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+
+    // Set verbosity level: --json = 0, --nerd = 3, -vv = 3, -v = 2, default = 1
+    let verbosity = if cli.json { 0 } else if cli.nerd { 3 } else { cli.verbose.saturating_add(1).min(3) };
+    let reporter = Arc::new(logger::TerminalReporter::new(verbosity));
     let is_nerd = verbosity >= 3;
 
     // 2. Validate input file exists
-    let input_path = Path::new(&cli.file);
+    let input_path = Path::new(&file);
     
     if !input_path.exists() {
-        logger::log_error(&format!("File '{}' not found.", cli.file));
-        eprintln!("\nTip: Check the file path and try again.");
-        eprintln!("     Use absolute path or relative path from current directory.");
+        logger::log_error(&t!("error-not-found", file = file));
+        eprintln!("\n{}", t!("tip-not-found"));
+        eprintln!("{}", t!("tip-not-found-path"));
         std::process::exit(1);
     }
     
     // 3. Validate file is not a directory
     if input_path.is_dir() {
-        logger::log_error(&format!("'{}' is a directory, not a file.", cli.file));
-        eprintln!("\nTip: Compress individual files, not directories.");
+        logger::log_error(&t!("error-is-directory", file = file));
+        eprintln!("\n{}", t!("tip-is-directory"));
         std::process::exit(1);
     }
     
     // 4. Validate file extension
-    if let Err(e) = utils::validate_file_extension(&cli.file) {
+    if let Err(e) = utils::validate_file_extension(&file) {
         logger::log_error(&e.to_string());
         std::process::exit(1);
     }
     
     // 5. Validate file is readable
-    if let Err(e) = std::fs::File::open(&cli.file) {
-        logger::log_error(&format!("Cannot read file '{}': {}", cli.file, e));
-        eprintln!("\nTip: Check file permissions with: ls -l {}", cli.file);
+    if let Err(e) = std::fs::File::open(&file) {
+        logger::log_error(&t!("error-unreadable", file = file, error = e));
+        eprintln!("\n{}", t!("tip-unreadable", file = file));
         std::process::exit(1);
     }
     
     // 6. Validate size parameter if provided
-    if let Some(ref size_str) = cli.size {
-        if let Err(e) = utils::validate_size(size_str) {
+    let target = match target_size(&cli) {
+        Ok(t) => t,
+        Err(e) => {
             logger::log_error(&e.to_string());
             std::process::exit(1);
         }
-    }
+    };
+
+    // 6b. One of crnch's own outputs asked to get smaller: redo it from its original,
+    // searching below the setting that came out too big
+    let retarget = target.and_then(|t| retarget::find(input_path, t.kb(), &History::default_location()?.load()));
+    let file = match retarget {
+        Some(ref r) => r.source.to_string_lossy().to_string(),
+        None => file,
+    };
+    let input_path = Path::new(&file);
 
-    // 7. Determine and validate output path
+    // 7. Determine and validate output path (--stdout compresses into a scratch dir first)
+    let stdout_dir = if cli.stdout {
+        match tempfile::tempdir() {
+            Ok(dir) => Some(dir),
+            Err(e) => {
+                logger::log_error(&t!("error-temp-dir", error = e));
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
     let output_path = match cli.output {
         Some(ref p) => {
             // Validate output path
@@ -108,156 +1554,313 @@ fn main() {
             if Path::new(p).exists() {
                 if cli.yes {
                     // Auto-yes mode: skip overwrite
-                    logger::log_warning(&format!("File '{}' already exists. Skipping (auto-yes mode).", p));
+                    logger::log_warning(&t!("warning-exists-skipping", file = p));
                     std::process::exit(0);
                 }
                 
                 match dialoguer::Confirm::new()
-                    .with_prompt(format!("Overwrite {}?", p))
+                    .with_prompt(t!("prompt-overwrite", file = p))
                     .default(false)
                     .interact() {
                     Ok(true) => {},
                     Ok(false) => {
-                        println!("Operation cancelled.");
+                        outln!("{}", t!("cancelled"));
                         std::process::exit(0);
                     },
                     Err(e) => {
-                        logger::log_error(&format!("Input error: {}", e));
+                        logger::log_error(&t!("error-input", error = e));
                         std::process::exit(1);
                     }
                 }
             }
             p.clone()
         },
-        None => {
-            let stem = input_path.file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("output");
-            let ext = input_path.extension()
-                .and_then(|e| e.to_str())
-                .unwrap_or("bin")
-                .to_lowercase();
-            format!("crnched_{}.{}", stem, ext)
+        None => match stdout_dir {
+            Some(ref dir) => dir.path().join(output_name(&cli, &config, input_path, utils::DEFAULT_OUTPUT_PREFIX)).to_string_lossy().to_string(),
+            None => {
+                let planned = default_output(&cli, &config, input_path);
+                if let Some(parent) = planned.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    if let Err(e) = std::fs::create_dir_all(parent) {
+                        logger::log_error(&t!("error-create-dir", dir = parent.display(), error = e));
+                        std::process::exit(1);
+                    }
+                }
+                // Repeated runs in one folder get crnched_file(2).png instead of clobbering
+                utils::numbered_path(&planned).to_string_lossy().to_string()
+            },
         }
     };
     
     // 8. Check if input and output are the same file
     if input_path.canonicalize().ok() == Path::new(&output_path).canonicalize().ok() {
-        logger::log_error("Input and output files cannot be the same.");
-        eprintln!("\nTip: Use --output to specify a different output file.");
+        logger::log_error(&t!("error-same-file"));
+        eprintln!("\n{}", t!("tip-same-file"));
         std::process::exit(1);
     }
 
+    // A different output format may need its own tool (cwebp for --webp)
+    if let Some(format) = Format::from_path(&output_path) {
+        if let Err(e) = checks::check_format_tool(format) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+    if Format::from_path(&output_path) == Some(Format::Heic) {
+        if let Err(e) = checks::check_heif_enc() {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+    if cli.max_distance.is_some() && matches!(Format::from_path(&output_path), Some(Format::Jpg | Format::Webp)) {
+        if let Err(e) = checks::check_butteraugli() {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+    if cli.ocr && input_format == Some(Format::Pdf) {
+        if let Err(e) = checks::check_ocr() {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+    if cli.zstd && Format::from_path(&output_path) == Some(Format::Zip) {
+        if let Err(e) = checks::check_zstd() {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+
     // Get input size for logging
-    let input_size_kb = std::fs::metadata(&cli.file)
-        .map(|m| m.len() / 1024)
+    let input_size_kb = std::fs::metadata(&file)
+        .map(|m| m.len().div_ceil(1024))
         .unwrap_or(0);
 
-    // Parse target for nerd mode header
-    let target_kb: Option<u64> = cli.size.as_ref().and_then(|s| utils::parse_size(s));
+    let target_kb: Option<u64> = target.map(|t| t.kb());
 
     // Start logging
     if is_nerd {
-        logger::nerd_header();
-        logger::nerd_file_info(&cli.file, input_size_kb, target_kb);
-    } else {
-        logger::log_start(&cli.file);
-        if let Some(target) = &cli.size {
-            logger::log_target(target);
+        reporter.nerd_header();
+        reporter.nerd_file_info(&file, input_size_kb, target_kb);
+    } else if !cli.json {
+        reporter.log_start(&file);
+        if let Some(target) = &target {
+            let shown = target.display_in(logger::units());
+            match cli.fit.and_then(|p| p.to_possible_value()) {
+                Some(platform) => reporter.log_target(&t!("target-platform", size = shown, platform = platform.get_name())),
+                None => reporter.log_target(&shown),
+            }
         } else if let Some(lvl) = &cli.level {
-            println!("   Level: {:?}", lvl);
+            outln!("   {}", t!("log-level", level = format!("{:?}", lvl)));
+        } else if let Some(quality) = cli.quality {
+            outln!("   {}", t!("log-quality", quality = quality));
+        }
+    }
+    if let (Some(r), false) = (&retarget, cli.json) {
+        outln!("   {}", t!("retarget-found", size = logger::format_size(r.record.final_kb), source = r.source.display()));
+        if let Some(ref prior) = r.prior {
+            outln!("   {}", t!("retarget-ceiling", parameter = prior.parameter, value = prior.value));
         }
     }
 
-    let size_option = cli.size.clone();
-    let level_option = cli.level;
+    // Once-a-day release check runs alongside the compression
+    #[cfg(feature = "self-update")]
+    let update_check = if cli.json || !config.update_check { None } else { update::spawn_check() };
+
+    let decisions = if cli.non_destructive {
+        DecisionPolicy::NeverDestructive
+    } else if cli.yes || cli.stdout {
+        DecisionPolicy::AlwaysAccept
+    } else {
+        DecisionPolicy::Ask
+    };
+    let mut builder = options_builder(&cli, &config, target, is_nerd, decisions, reporter.clone())
+        .prior(retarget.as_ref().and_then(|r| r.prior.clone()))
+        .cancel_token(cancel_on_ctrl_c());
+    let recorder = cli.emit_script.as_ref().map(|_| Arc::new(RecordingExecutor::new(Arc::new(SystemExecutor))));
+    if let Some(ref recorder) = recorder {
+        builder = builder.executor(recorder.clone());
+    }
+    let options = builder.build();
+
+    // A journal entry marks the run as in flight until it finishes
+    let journal = Journal::default_location();
+    if let Some(ref journal) = journal {
+        recover_leftovers(journal, cli.yes, cli.json || cli.stdout);
+    }
+    let journal_guard = match journal.as_ref().map(|j| j.begin(JournalEntry::new(&file, &output_path, &options))) {
+        Some(Err(e)) => {
+            logger::log_error(&e.to_string());
+            std::process::exit(1);
+        },
+        Some(Ok(guard)) => Some(guard),
+        None => None,
+    };
 
     // 9. Run Compression
-    match compression::compress_file(&cli.file, &output_path, size_option.clone(), level_option, is_nerd, cli.yes) {
+    let outcome = compression::compress_file(&file, &output_path, &options);
+    drop(journal_guard);
+    if let (Some(path), Some(recorder)) = (&cli.emit_script, &recorder) {
+        let header = [
+            ToolCommand::new("crnch").args(std::env::args().skip(1)).shell_line(),
+            format!("Tool commands run by crnch {} for {}, in order.", env!("CARGO_PKG_VERSION"), file),
+        ];
+        if let Err(e) = write_script(path, &recorder.script(&header)) {
+            logger::log_warning(&t!("warning-script-write", file = path.display(), error = e));
+        }
+    }
+    match outcome {
+        Ok(result) if result.skipped => {
+            if cli.json {
+                if let Ok(json) = serde_json::to_string_pretty(&result) {
+                    println!("{}", json);
+                }
+            } else if !reporter.is_quiet() {
+                outln!("   {}", t!("already-under-target", file = file));
+            }
+        },
         Ok(result) => {
+            let output_path = match rename_by_result(&cli, &config, &file, &output_path, &result) {
+                Ok(path) => path,
+                Err(e) => {
+                    logger::log_error(&format!("{:#}", e));
+                    std::process::exit(1);
+                },
+            };
+            // Where the summary says the result went
+            let shown_output = if cli.stdout { "<stdout>".to_string() } else { output_path.clone() };
+
             // Verify output file was created
             if !Path::new(&output_path).exists() {
-                logger::log_error("Compression completed but output file not found.");
-                eprintln!("\nThis may indicate a system error. Check disk space and permissions.");
+                logger::log_error(&t!("error-output-missing"));
+                eprintln!("\n{}", t!("hint-output-missing"));
                 std::process::exit(1);
             }
             
             match std::fs::metadata(&output_path) {
                 Ok(meta_new) => {
-                    let new_kb = meta_new.len() / 1024;
+                    let new_kb = meta_new.len().div_ceil(1024);
                     
                     // Sanity check: output file should not be empty
-                    if new_kb == 0 {
-                        logger::log_error("Output file is empty (0 bytes).");
-                        eprintln!("\nThis indicates a compression failure. The original file is intact.");
+                    if meta_new.len() == 0 {
+                        logger::log_error(&t!("error-output-empty"));
+                        eprintln!("\n{}", t!("hint-output-empty"));
                         let _ = std::fs::remove_file(&output_path);
                         std::process::exit(1);
                     }
                     
-                    if !is_nerd {
-                        logger::log_done();
+                    // Remembered for `crnch review`; a history that can't be written isn't worth failing over
+                    if !cli.stdout {
+                        if let Some(history) = History::default_location() {
+                            let _ = history.append(&RunRecord::new(&file, &output_path, &result));
+                        }
+                    }
+
+                    if cli.stdout {
+                        let copied = std::fs::File::open(&output_path)
+                            .and_then(|mut f| std::io::copy(&mut f, &mut std::io::stdout().lock()));
+                        if let Err(e) = copied {
+                            logger::log_error(&t!("error-stdout-write", error = e));
+                            std::process::exit(1);
+                        }
+                    }
+
+                    if cli.json {
+                        match serde_json::to_string_pretty(&result) {
+                            Ok(json) => println!("{}", json),
+                            Err(e) => {
+                                logger::log_error(&t!("error-serialize", error = e));
+                                std::process::exit(1);
+                            }
+                        }
+                    } else if !is_nerd {
+                        reporter.log_done();
                         
                         // Use enhanced summary with timing in verbose mode
                         if verbosity >= 2 {
-                            logger::log_summary(
-                                &cli.file, 
-                                &output_path, 
+                            reporter.log_summary(
+                                &file, 
+                                &shown_output, 
                                 input_size_kb, 
                                 new_kb, 
                                 Some(&result.algorithm),
                                 Some(result.time_ms)
                             );
                         } else {
-                            logger::log_result(&cli.file, &output_path, input_size_kb, new_kb);
+                            reporter.log_result(&file, &shown_output, input_size_kb, new_kb);
+                        }
+                        if let Some(exact) = logger::exact_size(&result) {
+                            let verdict = if result.target_met == Some(true) { "✓".paint(Role::Success) } else { "✗".paint(Role::Warning) };
+                            outln!("  {} {}", verdict, exact);
                         }
                         
                         // Validation check - only show warning if target was significantly missed
-                        if let Some(target_str) = size_option.as_ref() {
-                            if let Some(target_val) = utils::parse_size(target_str) {
-                                // Only warn if we're more than 20% over target (not just 10%)
-                                if new_kb > target_val + (target_val / 5) {
-                                    // Get file extension to provide relevant suggestions
-                                    let ext = input_path.extension()
-                                        .and_then(|e| e.to_str())
-                                        .unwrap_or("")
-                                        .to_lowercase();
-                                    
-                                    logger::log_warning("Could not reach target size.");
-                                    match ext.as_str() {
-                                        "pdf" => {
-                                            println!("   Tip: Try a larger target size, or use lower quality settings.");
-                                        },
-                                        "jpg" | "jpeg" => {
-                                            println!("   Tip: Try resizing the image dimensions for better compression.");
-                                        },
-                                        "png" => {
-                                            println!("   Tip: Try resizing the image or converting to JPEG format.");
-                                        },
-                                        _ => {}
-                                    }
+                        if let Some(target_val) = target_kb {
+                            // Only warn if we're more than 20% over target (not just 10%)
+                            if new_kb > target_val + (target_val / 5) {
+                                // Get file extension to provide relevant suggestions
+                                let ext = input_path.extension()
+                                    .and_then(|e| e.to_str())
+                                    .unwrap_or("")
+                                    .to_lowercase();
+                                
+                                logger::log_warning(&t!("warning-target-missed"));
+                                match ext.as_str() {
+                                    "pdf" => {
+                                        outln!("   {}", t!("tip-target-pdf"));
+                                    },
+                                    "jpg" | "jpeg" => {
+                                        outln!("   {}", t!("tip-target-jpg"));
+                                    },
+                                    "png" => {
+                                        outln!("   {}", t!("tip-target-png"));
+                                    },
+                                    _ => {}
                                 }
                             }
                         }
                     }
+
+                    if let Some(ref report) = result.privacy {
+                        if !cli.json {
+                            let removed = if report.found.is_empty() { t!("privacy-nothing-found") } else { t!("privacy-removed", fields = report.found.join(", ")) };
+                            outln!("   {}", t!("privacy-summary", removed = removed,
+                                remaining = t!("privacy-remaining", count = report.remaining.len()).paint(Role::Success)));
+                        }
+                    }
+
+                    if !result.changes.is_empty() && !cli.json {
+                        logger::log_warning(&t!("warning-changes"));
+                        for change in &result.changes {
+                            outln!("   - {}", change.message);
+                        }
+                    }
+
+                    #[cfg(feature = "self-update")]
+                    update::print_notice(update_check);
                 },
                 Err(e) => {
-                    logger::log_error(&format!("Cannot read output file: {}", e));
+                    logger::log_error(&t!("error-read-output", error = e));
                     std::process::exit(1);
                 }
             }
         },
         Err(e) => {
+            if e.downcast_ref::<Cancelled>().is_some() {
+                outln!();
+                logger::log_warning(&t!("warning-cancelled"));
+                std::process::exit(130);
+            }
             let error_msg = e.to_string();
-            logger::log_error(&format!("Compression failed: {}", error_msg));
+            logger::log_error(&t!("error-failed", error = error_msg));
             
             // Provide helpful tips based on error type
             if error_msg.contains("No such file") || error_msg.contains("not found") {
-                eprintln!("\nTip: Check that all required tools are installed.");
-                eprintln!("     Run: crnch --help for installation instructions.");
+                eprintln!("\n{}", t!("tip-failed-tools"));
+                eprintln!("{}", t!("tip-failed-tools-help"));
             } else if error_msg.contains("Permission denied") {
-                eprintln!("\nTip: Check file and directory permissions.");
+                eprintln!("\n{}", t!("tip-failed-permissions"));
             } else if error_msg.contains("Disk quota") || error_msg.contains("No space") {
-                eprintln!("\nTip: Free up disk space and try again.");
+                eprintln!("\n{}", t!("tip-failed-space"));
             }
             
             std::process::exit(1);
@@ -0,0 +1,159 @@
+//! `--manifest`: one JSON document per batch run for scripts and asset
+//! pipelines, every input with its output, sizes, method, time or error.
+//!
+//! `crnch verify` reads it back like a history file.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use crate::compression::CompResult;
+use crate::history::RunRecord;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub input: PathBuf,
+    /// Where the output went; none when the file failed
+    pub output: Option<PathBuf>,
+    pub original_kb: Option<u64>,
+    pub final_kb: Option<u64>,
+    pub method: Option<String>,
+    pub time_ms: Option<u64>,
+    pub target_kb: Option<u64>,
+    #[serde(default)]
+    pub target_bytes: Option<u64>,
+    pub target_met: Option<bool>,
+    /// Nothing was written (under target or already optimized)
+    pub skipped: bool,
+    pub output_sha256: Option<String>,
+    pub error: Option<String>,
+}
+
+impl Entry {
+    pub fn done(input: &str, output: &str, result: &CompResult) -> Self {
+        Entry {
+            input: std::path::absolute(input).unwrap_or_else(|_| PathBuf::from(input)),
+            output: Some(std::path::absolute(output).unwrap_or_else(|_| PathBuf::from(output))),
+            original_kb: Some(result.original_kb),
+            final_kb: Some(result.final_kb),
+            method: Some(result.algorithm.clone()),
+            time_ms: Some(result.time_ms as u64),
+            target_kb: result.target_kb,
+            target_bytes: result.target_bytes,
+            target_met: result.target_met,
+            skipped: result.skipped,
+            output_sha256: (!result.skipped).then(|| crate::verify::sha256_file(Path::new(output)).ok()).flatten(),
+            error: None,
+        }
+    }
+
+    pub fn failed(input: &str, error: &anyhow::Error) -> Self {
+        Entry {
+            input: std::path::absolute(input).unwrap_or_else(|_| PathBuf::from(input)),
+            output: None,
+            original_kb: fs::metadata(input).ok().map(|m| m.len().div_ceil(1024)),
+            final_kb: None,
+            method: None,
+            time_ms: None,
+            target_kb: None,
+            target_bytes: None,
+            target_met: None,
+            skipped: false,
+            output_sha256: None,
+            error: Some(format!("{:#}", error)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Totals {
+    pub files: usize,
+    pub failed: usize,
+    /// Over the files that were compressed or skipped
+    pub original_kb: u64,
+    pub final_kb: u64,
+    pub time_ms: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    pub files: Vec<Entry>,
+    pub totals: Totals,
+}
+
+impl Manifest {
+    pub fn new(files: Vec<Entry>) -> Self {
+        let done = files.iter().filter(|e| e.error.is_none());
+        let totals = Totals {
+            files: files.len(),
+            failed: files.iter().filter(|e| e.error.is_some()).count(),
+            original_kb: done.clone().filter_map(|e| e.original_kb).sum(),
+            final_kb: done.clone().filter_map(|e| e.final_kb).sum(),
+            time_ms: done.filter_map(|e| e.time_ms).sum(),
+        };
+        Manifest { files, totals }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    /// The written outputs as history records, for `crnch verify`
+    pub fn records(&self) -> Vec<RunRecord> {
+        self.files.iter().filter(|e| !e.skipped).filter_map(|e| Some(RunRecord {
+            finished_at: 0,
+            input: e.input.clone(),
+            output: e.output.clone()?,
+            algorithm: e.method.clone()?,
+            original_kb: e.original_kb?,
+            final_kb: e.final_kb?,
+            target_kb: e.target_kb,
+            target_bytes: e.target_bytes,
+            target_met: e.target_met,
+            output_sha256: e.output_sha256.clone(),
+            setting: None,
+            quality: None,
+            dpi: None,
+        })).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_with_totals_and_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("crnched_a.png");
+        fs::write(&output, b"png").unwrap();
+        let result = CompResult { algorithm: "pngquant".into(), time_ms: 120, original_kb: 400, final_kb: 90, ..CompResult::default() };
+        let skipped = CompResult { original_kb: 10, final_kb: 10, skipped: true, ..CompResult::default() };
+        let manifest = Manifest::new(vec![
+            Entry::done("a.png", &output.to_string_lossy(), &result),
+            Entry::done("b.jpg", "crnched_b.jpg", &skipped),
+            Entry::failed("missing.pdf", &anyhow::anyhow!("File not found")),
+        ]);
+        assert_eq!(manifest.totals, Totals { files: 3, failed: 1, original_kb: 410, final_kb: 100, time_ms: 120 });
+
+        let path = dir.path().join("results.json");
+        manifest.write(&path).unwrap();
+        let loaded = Manifest::load(&path).unwrap();
+        assert_eq!(loaded, manifest);
+        assert_eq!(loaded.files[2].error.as_deref(), Some("File not found"));
+
+        // Only the written output is there to verify
+        let records = loaded.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].output, output);
+        assert_eq!(records[0].output_sha256, crate::verify::sha256_file(&output).ok());
+    }
+}
@@ -0,0 +1,70 @@
+//! `--merge`: several PDFs joined into one, then compressed as one.
+//!
+//! Ghostscript concatenates the inputs at their own resolution; the single
+//! compression pass after it is what brings the document down to the target.
+
+use std::fs;
+use anyhow::{anyhow, Result};
+use crate::compression::{compress_file, gs_password_arg, run_tool, CompResult};
+use crate::exec::ToolCommand;
+use crate::options::CompressionOptions;
+
+/// Join `inputs`, in order, and compress the result into `output`
+pub fn merge(inputs: &[String], output: &str, opts: &CompressionOptions) -> Result<CompResult> {
+    if inputs.len() < 2 {
+        return Err(anyhow!("--merge needs at least two PDFs."));
+    }
+    let joined = opts.scratch_path(output, "merged.tmp.pdf");
+    let result = join(inputs, &joined, opts).and_then(|_| compress_file(&joined, output, opts));
+    let _ = fs::remove_file(&joined);
+    result
+}
+
+/// `inputs` as one PDF at `dest`, pages in order, nothing recompressed
+pub(crate) fn join(inputs: &[String], dest: &str, opts: &CompressionOptions) -> Result<()> {
+    opts.cancel.check()?;
+    let mut cmd = ToolCommand::new("gs");
+    cmd.arg("-sDEVICE=pdfwrite").arg("-dNOPAUSE").arg("-dQUIET").arg("-dBATCH")
+        .args(gs_password_arg(opts))
+        .arg(format!("-sOutputFile={}", dest))
+        .args(inputs)
+        .quiet_stderr(!opts.nerd);
+    if opts.nerd { opts.reporter.nerd_cmd(&cmd.shell_line()); }
+    if !run_tool(opts, &cmd)?.success() {
+        let _ = fs::remove_file(dest);
+        return Err(anyhow!("Ghostscript could not merge {}.", inputs.join(", ")));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::tests::{FakeRun, FakeTools};
+
+    #[test]
+    fn test_inputs_joined_in_order_then_compressed() {
+        let dir = tempfile::tempdir().unwrap();
+        let inputs: Vec<String> = ["scan1.pdf", "scan2.pdf", "scan3.pdf"].iter().map(|name| {
+            let path = dir.path().join(name);
+            fs::write(&path, vec![0u8; 300 * 1024]).unwrap();
+            path.to_string_lossy().to_string()
+        }).collect();
+        let output = dir.path().join("scans.pdf").to_string_lossy().to_string();
+        // The join only succeeds with the inputs last on the line, in order
+        let expected = inputs.clone();
+        let tools = FakeTools::new(move |cmd| match cmd.program.as_str() {
+            "gs" if cmd.args.iter().any(|a| a.ends_with("scan3.pdf")) => {
+                if cmd.args.ends_with(&expected) { FakeRun::Write(900) } else { FakeRun::Fail }
+            },
+            "gs" => FakeRun::Write(400),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder().target_kb(Some(500)).executor(tools.clone()).build();
+
+        let result = merge(&inputs, &output, &opts).unwrap();
+        assert_eq!((result.original_kb, result.final_kb), (900, 400));
+        assert!(fs::read_dir(dir.path()).unwrap().all(|e| !e.unwrap().file_name().to_string_lossy().contains(".tmp")));
+        assert!(merge(&inputs[..1], &output, &opts).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+//! `--name-template`: output names such as `{stem}_{target}k.{ext}`, for
+//! teams whose naming conventions `crnched_` prefixes don't fit.
+
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::{anyhow, Result};
+use crate::organize;
+
+const FIELDS: [&str; 6] = ["stem", "ext", "date", "target", "size", "saved"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    Field(&'static str),
+}
+
+/// A checked output-name template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameTemplate(Vec<Piece>);
+
+/// What a template's fields are filled with; `size_kb` and `saved_percent`
+/// are only known once the output is written
+#[derive(Debug, Clone, Default)]
+pub struct NameFields {
+    pub stem: String,
+    /// Extension of the output, after any format change
+    pub ext: String,
+    pub target_kb: Option<u64>,
+    pub size_kb: Option<u64>,
+    pub saved_percent: Option<u64>,
+}
+
+impl FromStr for NameTemplate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.contains(['/', '\\']) {
+            return Err(anyhow!("A name template names a file; use --output-dir for the folder."));
+        }
+        let mut pieces = Vec::new();
+        let mut rest = s;
+        while let Some(open) = rest.find(['{', '}']) {
+            if rest[open..].starts_with('}') {
+                return Err(anyhow!("Unmatched '}}' in name template '{}'.", s));
+            }
+            let close = rest[open..].find('}').ok_or_else(|| anyhow!("Unclosed '{{' in name template '{}'.", s))? + open;
+            let name = &rest[open + 1..close];
+            let field = FIELDS.iter().find(|&&f| f == name)
+                .ok_or_else(|| anyhow!("Unknown field {{{}}} in name template; use {}.", name, FIELDS.map(|f| format!("{{{}}}", f)).join(", ")))?;
+            if open > 0 {
+                pieces.push(Piece::Text(rest[..open].to_string()));
+            }
+            pieces.push(Piece::Field(field));
+            rest = &rest[close + 1..];
+        }
+        if !rest.is_empty() {
+            pieces.push(Piece::Text(rest.to_string()));
+        }
+        if !pieces.iter().any(|p| matches!(p, Piece::Field(_))) {
+            return Err(anyhow!("Name template '{}' has no fields, so every file would get the same name.", s));
+        }
+        Ok(NameTemplate(pieces))
+    }
+}
+
+impl NameTemplate {
+    /// Uses `{size}` or `{saved}`, so the output is renamed after it's written
+    pub fn needs_result(&self) -> bool {
+        self.0.iter().any(|p| matches!(p, Piece::Field("size" | "saved")))
+    }
+
+    /// The name for `fields`; `{target}` without a target is `auto`, and the
+    /// result fields are `0` until known
+    pub fn render(&self, fields: &NameFields) -> String {
+        self.0.iter().map(|piece| match *piece {
+            Piece::Text(ref text) => text.clone(),
+            Piece::Field("stem") => fields.stem.clone(),
+            Piece::Field("ext") => fields.ext.clone(),
+            Piece::Field("date") => today(),
+            Piece::Field("target") => fields.target_kb.map_or("auto".to_string(), |kb| kb.to_string()),
+            Piece::Field("size") => fields.size_kb.unwrap_or(0).to_string(),
+            Piece::Field(_) => fields.saved_percent.unwrap_or(0).to_string(),
+        }).collect()
+    }
+}
+
+/// Percent of `original_kb` that compressing to `final_kb` saved
+pub fn saved_percent(original_kb: u64, final_kb: u64) -> u64 {
+    match original_kb {
+        0 => 0,
+        original => original.saturating_sub(final_kb) * 100 / original,
+    }
+}
+
+/// Today as `YYYY-MM-DD` (UTC)
+fn today() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (year, month, day) = organize::civil_date((secs / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> NameFields {
+        NameFields { stem: "photo".into(), ext: "jpg".into(), target_kb: Some(300), ..Default::default() }
+    }
+
+    #[test]
+    fn test_render_fields() {
+        let template: NameTemplate = "{stem}_{target}k.{ext}".parse().unwrap();
+        assert!(!template.needs_result());
+        assert_eq!(template.render(&fields()), "photo_300k.jpg");
+        assert_eq!(template.render(&NameFields { target_kb: None, ..fields() }), "photo_autok.jpg");
+
+        let template: NameTemplate = "{stem}-{size}kb-{saved}pct.{ext}".parse().unwrap();
+        assert!(template.needs_result());
+        let done = NameFields { size_kb: Some(280), saved_percent: Some(saved_percent(1000, 280)), ..fields() };
+        assert_eq!(template.render(&done), "photo-280kb-72pct.jpg");
+
+        let dated: NameTemplate = "{date}_{stem}.{ext}".parse().unwrap();
+        let name = dated.render(&fields());
+        assert_eq!((name.len(), &name[4..5], &name[10..]), (20, "-", "_photo.jpg"));
+    }
+
+    #[test]
+    fn test_bad_templates() {
+        for bad in ["{stem.{ext}", "{name}.{ext}", "out}.{ext}", "out/{stem}.{ext}", "out.jpg"] {
+            assert!(bad.parse::<NameTemplate>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_civil_date() {
+        assert_eq!(organize::civil_date(0), (1970, 1, 1));
+        assert_eq!(organize::civil_date(19_782), (2024, 2, 29));
+    }
+}
@@ -0,0 +1,98 @@
+//! Office Open XML packages (`.docx`, `.pptx`): the embedded media the
+//! image engines work on, and the document properties that name people.
+//! The zip container itself is [`crate::zip`].
+
+use crate::format::Format;
+use crate::zip::Entry;
+
+/// Folders whose images the engines recompress
+const MEDIA_DIRS: &[&str] = &["word/media/", "ppt/media/"];
+/// Property parts, and the elements in them that name a person or organization
+const PEOPLE: &[(&str, &str, &str)] = &[
+    ("docProps/core.xml", "dc:creator", "document author"),
+    ("docProps/core.xml", "cp:lastModifiedBy", "last modified by"),
+    ("docProps/app.xml", "Manager", "manager"),
+    ("docProps/app.xml", "Company", "company"),
+];
+
+/// A JPEG or PNG in one of the media folders
+pub fn is_media(name: &str) -> bool {
+    MEDIA_DIRS.iter().any(|d| name.starts_with(d)) && matches!(Format::from_path(name), Some(Format::Jpg | Format::Png))
+}
+
+/// Labels for the property elements that name someone
+pub fn people(entries: &[Entry]) -> Vec<String> {
+    let mut found = Vec::new();
+    for (part, tag, label) in PEOPLE {
+        let Some(entry) = entries.iter().find(|e| e.name == *part) else { continue };
+        let xml = String::from_utf8_lossy(&entry.data);
+        if element_text(&xml, tag).is_some_and(|r| !xml[r].trim().is_empty()) {
+            found.push(label.to_string());
+        }
+    }
+    found
+}
+
+/// Empty every element [`people`] reports
+pub fn clear_people(entries: &mut [Entry]) {
+    for (part, tag, _) in PEOPLE {
+        let Some(entry) = entries.iter_mut().find(|e| e.name == *part) else { continue };
+        let mut xml = String::from_utf8_lossy(&entry.data).into_owned();
+        if let Some(range) = element_text(&xml, tag) {
+            xml.replace_range(range, "");
+            entry.data = xml.into_bytes();
+        }
+    }
+}
+
+/// Byte range of the text inside the first `<tag>...</tag>`
+fn element_text(xml: &str, tag: &str) -> Option<std::ops::Range<usize>> {
+    let open = format!("<{}", tag);
+    let mut from = 0;
+    loop {
+        let at = from + xml[from..].find(&open)?;
+        let after = at + open.len();
+        from = after;
+        // `<dc:creator>` or `<dc:creator attr="...">`, not `<dc:creatorX>` or `<dc:creator/>`
+        if !xml[after..].starts_with(['>', ' ']) {
+            continue;
+        }
+        let start = after + xml[after..].find('>')? + 1;
+        if xml[..start].ends_with("/>") {
+            return None;
+        }
+        let end = start + xml[start..].find(&format!("</{}>", tag))?;
+        return Some(start..end);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::zip;
+
+    /// Minimal .docx parts plus `media` as `word/media/` entries
+    pub(crate) fn docx(media: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut entries = vec![
+            Entry::new("[Content_Types].xml", b"<Types/>".to_vec()),
+            Entry::new("word/document.xml", "<w:document>".repeat(200).into_bytes()),
+            Entry::new(
+                "docProps/core.xml",
+                b"<cp:coreProperties><dc:creator>Jane Doe</dc:creator><cp:lastModifiedBy></cp:lastModifiedBy></cp:coreProperties>".to_vec(),
+            ),
+        ];
+        entries.extend(media.iter().map(|(name, data)| Entry::new(format!("word/media/{}", name), data.clone())));
+        zip::to_bytes(&entries).unwrap()
+    }
+
+    #[test]
+    fn test_media_and_people() {
+        let mut parsed = zip::entries(&docx(&[("image1.png", vec![7; 100])])).unwrap();
+        assert!(is_media(&parsed[3].name) && !is_media("word/document.xml"));
+        assert_eq!(people(&parsed), ["document author"]);
+
+        clear_people(&mut parsed);
+        assert!(people(&parsed).is_empty());
+        assert!(String::from_utf8_lossy(&parsed[2].data).contains("<dc:creator></dc:creator>"));
+    }
+}
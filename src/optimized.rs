@@ -0,0 +1,119 @@
+//! Cheap guesses at whether a file has been through an optimizer already.
+//!
+//! Re-runs over a folder spend most of their time on files an earlier run
+//! (or another tool) already squeezed, for a few percent at best. The
+//! headers tell most of them apart without running anything: a JPEG's
+//! quantization tables give its quality, a PNG's header and size its
+//! palette and bits per pixel, a PDF's Producer the tool that wrote it.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use crate::format::Format;
+use crate::jpeg;
+
+/// PDF producers that already downsample images and recompress streams
+const OPTIMIZING_PRODUCERS: &[&str] = &["ghostscript", "pdfsizeopt", "ilovepdf", "smallpdf", "pdf compressor", "crnch"];
+/// Bytes read from each end of a PDF looking for its Producer
+const PDF_WINDOW: u64 = 256 * 1024;
+
+/// Rough percent a run without a target would still save on `path`, or
+/// `None` when the headers don't tell
+pub fn expected_savings(path: &Path, format: Format) -> Option<u32> {
+    match format {
+        Format::Jpg => jpeg::estimate_quality(path).map(|quality| match quality {
+            // The lossy search re-encodes to 60-95% of the size; at low
+            // qualities little of that is left
+            0..=75 => 3,
+            76..=85 => 10,
+            86..=92 => 25,
+            _ => 40,
+        }),
+        Format::Png => png_savings(path),
+        Format::Pdf => pdf_producer(path).and_then(|producer| {
+            let producer = producer.to_lowercase();
+            OPTIMIZING_PRODUCERS.iter().any(|p| producer.contains(p)).then_some(3)
+        }),
+        _ => None,
+    }
+}
+
+/// Palette PNGs have been quantized; truecolor ones under a bit per pixel
+/// are flat enough that quantizing gains little
+fn png_savings(path: &Path) -> Option<u32> {
+    let mut header = [0u8; 29];
+    let mut file = File::open(path).ok()?;
+    file.read_exact(&mut header).ok()?;
+    if !header.starts_with(b"\x89PNG\r\n\x1a\n") || &header[12..16] != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes([header[16], header[17], header[18], header[19]]) as u64;
+    let height = u32::from_be_bytes([header[20], header[21], header[22], header[23]]) as u64;
+    let color_type = header[25];
+    let bits = file.metadata().ok()?.len() * 8;
+    match color_type {
+        3 => Some(5),
+        _ if width * height > 0 && bits < width * height => Some(5),
+        _ => None,
+    }
+}
+
+/// The Producer from the Info dictionary or XMP, near either end of the file
+fn pdf_producer(path: &Path) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    let mut data = Vec::new();
+    (&mut file).take(PDF_WINDOW).read_to_end(&mut data).ok()?;
+    if len > PDF_WINDOW {
+        file.seek(SeekFrom::Start(len.saturating_sub(PDF_WINDOW).max(PDF_WINDOW))).ok()?;
+        file.read_to_end(&mut data).ok()?;
+    }
+    let at = data.windows(9).position(|w| w == b"/Producer" || w == b":Producer")?;
+    // `/Producer (GPL Ghostscript 10.02)` or `<pdf:Producer>GPL Ghostscript</pdf:Producer>`
+    let rest = &data[at + 9..data.len().min(at + 9 + 128)];
+    let text: String = rest.iter()
+        .skip_while(|&&b| matches!(b, b' ' | b'(' | b'>'))
+        .take_while(|&&b| !matches!(b, b')' | b'<' | b'\n' | b'\r'))
+        .map(|&b| b as char)
+        .collect();
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_guesses_from_headers() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut palette = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\x01\0\0\0\x01\0\x08\x03\0\0\0".to_vec();
+        palette.extend(vec![0u8; 4096]);
+        fs::write(dir.path().join("icon.png"), &palette).unwrap();
+        assert_eq!(expected_savings(&dir.path().join("icon.png"), Format::Png), Some(5));
+        // Truecolor at 8 bits per pixel: no telling
+        palette[25] = 6;
+        palette.extend(vec![0u8; 60 * 1024]);
+        fs::write(dir.path().join("photo.png"), &palette).unwrap();
+        assert_eq!(expected_savings(&dir.path().join("photo.png"), Format::Png), None);
+
+        let mut pdf = b"%PDF-1.7\n".to_vec();
+        pdf.extend(vec![b' '; 300 * 1024]);
+        pdf.extend(b"<< /Producer (GPL Ghostscript 10.02.1) >>\n%%EOF\n");
+        fs::write(dir.path().join("scan.pdf"), &pdf).unwrap();
+        assert_eq!(expected_savings(&dir.path().join("scan.pdf"), Format::Pdf), Some(3));
+        fs::write(dir.path().join("word.pdf"), b"%PDF-1.7\n<pdf:Producer>Microsoft Word</pdf:Producer>\n%%EOF").unwrap();
+        assert_eq!(expected_savings(&dir.path().join("word.pdf"), Format::Pdf), None);
+    }
+
+    #[test]
+    fn test_jpeg_quality_sets_the_guess() {
+        let dir = tempfile::tempdir().unwrap();
+        for (quality, savings) in [(70, 3), (95, 40)] {
+            let path = dir.path().join(format!("q{}.jpg", quality));
+            fs::write(&path, jpeg::tests::jpeg_with_quality(quality)).unwrap();
+            assert_eq!(expected_savings(&path, Format::Jpg), Some(savings));
+        }
+    }
+}
@@ -0,0 +1,810 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use clap::ValueEnum;
+use crate::artifacts::Shared;
+use crate::cancel::CancellationToken;
+use crate::compression::CompressionLevel;
+use crate::decision::DecisionPolicy;
+use crate::exec::{Executor, ResourceLimits, SystemExecutor};
+use crate::format::Format;
+use crate::progress::{NoProgress, ProgressSink};
+use crate::report::{Reporter, SilentReporter};
+use crate::retarget::Setting;
+use crate::search::{Strategy, Window};
+use crate::throttle::Throttle;
+use crate::utils::TargetSize;
+
+/// What to do with EXIF/XMP/text metadata in the input
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum MetadataMode {
+    /// Remove everything the tools can safely strip (default)
+    #[default]
+    Strip,
+    /// Leave metadata in place
+    Keep,
+}
+
+/// What to do when the target is at or above the original size
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, ValueEnum)]
+pub enum UnderTarget {
+    /// Ask whether to keep the original, through the decision policy
+    #[default]
+    Ask,
+    /// Write no output at all
+    Skip,
+    /// Copy the original to the output
+    Copy,
+    /// Point the output at the original with a symlink
+    Symlink,
+}
+
+/// How PDFs are compressed
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, ValueEnum)]
+pub enum PdfMode {
+    /// Ghostscript pipeline: presets, DPI search, fallbacks
+    #[default]
+    Full,
+    /// Lossless restructuring in-process, no Ghostscript
+    Quick,
+}
+
+/// What becomes of the images in a PDF
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum PdfImages {
+    /// Downsampled like the rest of the document (default)
+    #[default]
+    Keep,
+    /// Dropped, leaving text and vector art (Ghostscript's `-dFILTERIMAGE`)
+    Remove,
+    /// Converted to grayscale, along with the rest of the page
+    Grayscale,
+}
+
+/// Fonts in Ghostscript's PDF passes
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PdfFonts {
+    /// Embed only the glyphs the document uses (`-dSubsetFonts`)
+    pub subset: bool,
+    /// Embed fonts the input doesn't (`-dEmbedAllFonts`)
+    pub embed: bool,
+    /// Leave the 14 standard fonts (Times, Helvetica, Courier, ...) to the viewer
+    pub unembed_standard: bool,
+}
+
+impl Default for PdfFonts {
+    fn default() -> Self {
+        PdfFonts { subset: true, embed: true, unembed_standard: false }
+    }
+}
+
+/// Ghostscript's `-dPDFSETTINGS` presets, for PDFs compressed without a target
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+pub enum PdfPreset {
+    /// 72 DPI images, smallest
+    Screen,
+    /// 150 DPI images
+    Ebook,
+    /// 300 DPI images
+    Printer,
+    /// 300 DPI, color preserving
+    Prepress,
+}
+
+impl PdfPreset {
+    pub fn gs_setting(self) -> &'static str {
+        match self {
+            PdfPreset::Screen => "/screen",
+            PdfPreset::Ebook => "/ebook",
+            PdfPreset::Printer => "/printer",
+            PdfPreset::Prepress => "/prepress",
+        }
+    }
+}
+
+/// Output format for `--convert-to`
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+pub enum ConvertTo {
+    Png,
+    Jpg,
+    Webp,
+    Avif,
+    Pdf,
+}
+
+impl ConvertTo {
+    pub fn format(self) -> Format {
+        match self {
+            ConvertTo::Png => Format::Png,
+            ConvertTo::Jpg => Format::Jpg,
+            ConvertTo::Webp => Format::Webp,
+            ConvertTo::Avif => Format::Avif,
+            ConvertTo::Pdf => Format::Pdf,
+        }
+    }
+}
+
+/// What comic archive pages are written as
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, ValueEnum)]
+pub enum ComicPages {
+    /// Pages keep their format; PNGs are quantized
+    #[default]
+    Png,
+    /// PNG pages become JPEGs, far smaller for scanned or painted art
+    Jpg,
+}
+
+/// 1-bit black and white ("fax mode", `--bw`) in place of grayscale
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+pub enum BwMode {
+    /// Error-diffusion dithering; keeps photos and shading recognizable
+    Dither,
+    /// Hard cut at 50% gray; crispest text on forms and receipts
+    Threshold,
+}
+
+/// Optional pipeline stages that can be switched off per run (`--skip-stage`)
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+pub enum Stage {
+    /// PNG palette reduction (pngquant)
+    Quantize,
+    /// Grayscale conversion, in the PNG chain and as a fallback
+    Grayscale,
+    /// Shrinking image dimensions
+    Resize,
+    /// JPEG re-encoding, PDF image downsampling and GIF lossy/frame dropping
+    Lossy,
+}
+
+impl Stage {
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Quantize => "quantize",
+            Stage::Grayscale => "grayscale",
+            Stage::Resize => "resize",
+            Stage::Lossy => "lossy",
+        }
+    }
+}
+
+/// How hard the engines try, 1-10 (`--effort`). Every internal budget
+/// scales from it; 5 is the behavior from before the knob existed.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Effort(u8);
+
+impl Effort {
+    pub const MIN: u8 = 1;
+    pub const MAX: u8 = 10;
+
+    /// Clamped into 1-10
+    pub fn new(level: u8) -> Effort {
+        Effort(level.clamp(Self::MIN, Self::MAX))
+    }
+
+    pub fn get(self) -> u8 {
+        self.0
+    }
+
+    /// Probe cap for quality and scale searches on images (8 at 5)
+    pub fn image_probes(self) -> u32 {
+        3 + self.0 as u32
+    }
+
+    /// Probe cap for the PDF DPI search (14 at 5); each probe is a full Ghostscript pass
+    pub fn pdf_probes(self) -> u32 {
+        9 + self.0 as u32
+    }
+
+    /// oxipng `-o` for the lossless PNG passes (2 at 5)
+    pub fn oxipng_level(self) -> u8 {
+        match self.0 {
+            1 => 0,
+            2 | 3 => 1,
+            4 | 5 => 2,
+            6 => 3,
+            7 => 4,
+            8 => 5,
+            _ => 6,
+        }
+    }
+
+    /// Zopfli deflate in oxipng: a few percent smaller, many times slower
+    pub fn zopfli(self) -> bool {
+        self.0 >= 9
+    }
+
+    /// Try a Guetzli re-encode of JPEGs, when it's installed
+    pub fn guetzli(self) -> bool {
+        self.0 >= Self::MAX
+    }
+
+    /// Probes run concurrently per round of the PDF DPI search
+    pub fn parallel_probes(self) -> usize {
+        match self.0 {
+            0..=6 => 1,
+            7 | 8 => 2,
+            _ => 3,
+        }
+    }
+
+    /// x264 `-preset` for video ("medium" at 5)
+    pub fn x264_preset(self) -> &'static str {
+        match self.0 {
+            1 | 2 => "veryfast",
+            3 | 4 => "fast",
+            5 | 6 => "medium",
+            7 | 8 => "slow",
+            _ => "veryslow",
+        }
+    }
+
+    /// avifenc `-s`, 10 the fastest and 0 the slowest (6 at 5)
+    pub fn avif_speed(self) -> u8 {
+        11 - self.0
+    }
+
+    pub fn strategy(self) -> Strategy {
+        match self.parallel_probes() {
+            1 => Strategy::Binary,
+            width => Strategy::Parallel(width),
+        }
+    }
+}
+
+impl Default for Effort {
+    fn default() -> Self {
+        Effort(5)
+    }
+}
+
+/// Which implementation performs the actual encoding
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum Backend {
+    /// Shell out to gs/magick/pngquant/jpegoptim/oxipng
+    #[default]
+    External,
+}
+
+/// Everything an engine needs to know about a single compression run.
+///
+/// Build one with [`CompressionOptions::builder`]; unset fields fall back to
+/// the same defaults the CLI uses.
+#[derive(Clone)]
+pub struct CompressionOptions {
+    /// What the engines search against, in KB; see [`TargetSize::kb`]
+    pub target_kb: Option<u64>,
+    /// The exact target, e.g. `480000b`; see [`CompressionOptions::target_limit`]
+    pub target_bytes: Option<u64>,
+    /// Lower end of a range target (`--size 180k-220k`), in KB: the searches
+    /// stop at the first output between it and `target_kb`
+    pub target_min_kb: Option<u64>,
+    pub level: Option<CompressionLevel>,
+    pub nerd: bool,
+    pub decisions: DecisionPolicy,
+    pub metadata: MetadataMode,
+    pub privacy: bool,
+    pub backend: Backend,
+    pub temp_dir: Option<PathBuf>,
+    pub skip_stages: Vec<Stage>,
+    pub under_target: UnderTarget,
+    /// With no target, skip files [`optimized::expected_savings`](crate::optimized::expected_savings)
+    /// puts under this percent
+    pub skip_optimized: Option<u32>,
+    /// A missed target is an error and the output is removed
+    pub strict: bool,
+    /// With no target, keep the original when the output saves less than this percent
+    pub min_savings: Option<f64>,
+    /// Quality and scale searches stop here and miss the target instead
+    pub min_quality: Option<u64>,
+    pub min_scale: Option<u64>,
+    /// Lowest SSIM against the input the output may have; see [`perceptual`](crate::perceptual)
+    pub min_ssim: Option<f64>,
+    /// Highest butteraugli distance from the input the output may have (JPEG and WebP)
+    pub max_distance: Option<f64>,
+    /// PDF images are downsampled to exactly this DPI in one pass, with no search
+    pub pdf_dpi: Option<u64>,
+    /// PDF images at or below this DPI are left as they are (Ghostscript's downsample threshold)
+    pub pdf_downsample_above: Option<u64>,
+    /// Opens an encrypted PDF input (Ghostscript's `-sPDFPassword`)
+    pub pdf_password: Option<String>,
+    /// Only these PDF pages go into the output, e.g. "1-10,15" (Ghostscript's `-sPageList`)
+    pub pdf_pages: Option<String>,
+    pub pdf_images: PdfImages,
+    pub pdf_fonts: PdfFonts,
+    /// Bilevel scanned PDF pages are re-encoded as CCITT Group 4 first; see [`scan`](crate::scan)
+    pub pdf_scan_mode: bool,
+    /// Scanned PDF pages get a searchable text layer from ocrmypdf first
+    pub pdf_ocr: bool,
+    /// Ghostscript keeps every annotation, printable or not; set for the retry
+    /// after a pass drops bookmarks, links or form fields
+    pub pdf_keep_interactive: bool,
+    /// Drop the PDF's XMP packet and blank its DocInfo (author, title, producer, dates)
+    pub pdf_scrub_metadata: bool,
+    /// Lowest image DPI the PDF search may use
+    pub min_dpi: Option<u64>,
+    /// JPGs and PNGs larger than this are downscaled before the size search
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+    pub pdf_mode: PdfMode,
+    /// Repack `.zip` archives with zstd instead of deflate
+    pub zip_zstd: bool,
+    pub comic_pages: ComicPages,
+    pub bw: Option<BwMode>,
+    pub effort: Effort,
+    /// Encode at this quality (1-100) when there's no target, instead of the level's:
+    /// JPEG, WebP, AVIF and HEIC quality, GIF lossiness, pngquant's ceiling, a Ghostscript DPI
+    pub quality: Option<u64>,
+    /// JPEG quality with neither a target nor a level
+    pub jpeg_quality: Option<u64>,
+    /// Effort for PNG outputs, in place of `effort`
+    pub png_effort: Option<Effort>,
+    /// Ghostscript preset for PDFs without a target, instead of one picked by size
+    pub pdf_preset: Option<PdfPreset>,
+    /// Setting that overshot the target in an earlier run; searches stay at or below it
+    pub prior: Option<Setting>,
+    pub io_throttle: Option<Arc<Throttle>>,
+    /// Lossless passes and decodes kept for other runs over the same input
+    pub shared: Option<Arc<Shared>>,
+    pub limits: ResourceLimits,
+    pub progress: Arc<dyn ProgressSink>,
+    pub reporter: Arc<dyn Reporter>,
+    pub executor: Arc<dyn Executor>,
+    pub cancel: CancellationToken,
+}
+
+impl Default for CompressionOptions {
+    fn default() -> Self {
+        CompressionOptions {
+            target_kb: None,
+            target_bytes: None,
+            target_min_kb: None,
+            level: None,
+            nerd: false,
+            decisions: DecisionPolicy::default(),
+            metadata: MetadataMode::default(),
+            privacy: false,
+            backend: Backend::default(),
+            temp_dir: None,
+            skip_stages: Vec::new(),
+            under_target: UnderTarget::default(),
+            skip_optimized: None,
+            strict: false,
+            min_savings: None,
+            pdf_dpi: None,
+            pdf_downsample_above: None,
+            pdf_password: None,
+            pdf_pages: None,
+            pdf_images: PdfImages::Keep,
+            pdf_fonts: PdfFonts::default(),
+            pdf_scan_mode: false,
+            pdf_ocr: false,
+            pdf_keep_interactive: false,
+            pdf_scrub_metadata: false,
+            min_quality: None,
+            min_scale: None,
+            min_ssim: None,
+            max_distance: None,
+            min_dpi: None,
+            max_width: None,
+            max_height: None,
+            pdf_mode: PdfMode::default(),
+            zip_zstd: false,
+            comic_pages: ComicPages::default(),
+            bw: None,
+            effort: Effort::default(),
+            quality: None,
+            jpeg_quality: None,
+            png_effort: None,
+            pdf_preset: None,
+            prior: None,
+            io_throttle: None,
+            shared: None,
+            limits: ResourceLimits::default(),
+            progress: Arc::new(NoProgress),
+            reporter: Arc::new(SilentReporter),
+            executor: Arc::new(SystemExecutor),
+            cancel: CancellationToken::new(),
+        }
+    }
+}
+
+impl CompressionOptions {
+    pub fn builder() -> CompressionOptionsBuilder {
+        CompressionOptionsBuilder::default()
+    }
+
+    /// Bytes the output may take: the exact target while it still belongs to
+    /// `target_kb` (a caller that sets its own `target_kb`, like a share of a
+    /// budget, gets whole KB), else `target_kb` whole
+    pub fn target_limit(&self) -> Option<u64> {
+        self.target_kb.map(|kb| {
+            self.target_bytes.filter(|&bytes| TargetSize::from_bytes(bytes).kb() == kb).unwrap_or(kb.saturating_mul(1024))
+        })
+    }
+
+    /// What a search against `target_kb` is after, the range's lower end included
+    pub fn search_window(&self, target_kb: u64) -> Window {
+        Window { min_kb: self.target_min_kb, max_kb: target_kb }
+    }
+
+    /// Aim at `target` instead of the run's own
+    pub fn set_target(&mut self, target: TargetSize) {
+        self.target_kb = Some(target.kb());
+        self.target_bytes = Some(target.bytes());
+    }
+
+    /// Lowest quality a search may try: the strategy's own `floor`, or
+    /// `min_quality` when that is higher
+    pub fn quality_floor(&self, floor: u64) -> u64 {
+        self.min_quality.map_or(floor, |min| min.max(floor))
+    }
+
+    /// Smallest resize in percent
+    pub fn scale_floor(&self) -> u64 {
+        self.min_scale.unwrap_or(1)
+    }
+
+    /// Whether `stage` may run (it wasn't skipped)
+    pub fn runs(&self, stage: Stage) -> bool {
+        !self.skip_stages.contains(&stage)
+    }
+
+    /// Path for an intermediate file belonging to `output`.
+    ///
+    /// Lives next to the output unless a temp dir was configured.
+    pub fn scratch_path(&self, output: &str, suffix: &str) -> String {
+        match self.temp_dir {
+            Some(ref dir) => {
+                let name = std::path::Path::new(output)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "crnch".to_string());
+                dir.join(format!("{}.{}", name, suffix)).to_string_lossy().to_string()
+            },
+            None => format!("{}.{}", output, suffix),
+        }
+    }
+
+    /// Directory the scratch files for `output` are written to
+    pub fn scratch_dir(&self, output: &str) -> PathBuf {
+        match self.temp_dir {
+            Some(ref d) => d.clone(),
+            None => match Path::new(output).parent() {
+                Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+                _ => PathBuf::from("."),
+            },
+        }
+    }
+}
+
+/// Scratch files in `dir` belonging to an output named `output_name`.
+///
+/// Matches the [`CompressionOptions::scratch_path`] naming: `<output>.<...>.tmp[.<ext>]`.
+pub fn find_scratch_files(dir: &Path, output_name: &str) -> Vec<PathBuf> {
+    let prefix = format!("{}.", output_name);
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    entries.flatten()
+        .filter(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            file_name.strip_prefix(&prefix)
+                .is_some_and(|rest| rest.split('.').any(|part| part == "tmp"))
+        })
+        .map(|entry| entry.path())
+        .collect()
+}
+
+#[derive(Default)]
+pub struct CompressionOptionsBuilder {
+    opts: CompressionOptions,
+}
+
+impl CompressionOptionsBuilder {
+    /// Target size in KB; `None` runs the preset path
+    pub fn target_kb(mut self, kb: Option<u64>) -> Self {
+        self.opts.target_kb = kb;
+        self.opts.target_bytes = None;
+        self
+    }
+
+    /// Target size to the byte, e.g. from `--size 480000b`
+    pub fn target(mut self, target: Option<TargetSize>) -> Self {
+        self.opts.target_kb = target.map(|t| t.kb());
+        self.opts.target_bytes = target.map(|t| t.bytes());
+        self
+    }
+
+    /// Lower end of a range target, e.g. the 180k of `--size 180k-220k`
+    pub fn target_min(mut self, min: Option<TargetSize>) -> Self {
+        self.opts.target_min_kb = min.map(|t| t.kb());
+        self
+    }
+
+    pub fn level(mut self, level: Option<CompressionLevel>) -> Self {
+        self.opts.level = level;
+        self
+    }
+
+    /// Print stage-by-stage technical detail
+    pub fn nerd(mut self, enabled: bool) -> Self {
+        self.opts.nerd = enabled;
+        self
+    }
+
+    /// How grayscale/resize/keep-original questions get answered
+    pub fn decisions(mut self, policy: DecisionPolicy) -> Self {
+        self.opts.decisions = policy;
+        self
+    }
+
+    /// Shorthand for `AlwaysAccept` (true) or `Ask` (false)
+    pub fn auto_yes(mut self, enabled: bool) -> Self {
+        self.opts.decisions = if enabled { DecisionPolicy::AlwaysAccept } else { DecisionPolicy::Ask };
+        self
+    }
+
+    pub fn metadata(mut self, mode: MetadataMode) -> Self {
+        self.opts.metadata = mode;
+        self
+    }
+
+    /// Scrub identifying metadata from the output and verify none remains;
+    /// implies `MetadataMode::Strip`
+    pub fn privacy(mut self, enabled: bool) -> Self {
+        self.opts.privacy = enabled;
+        if enabled {
+            self.opts.metadata = MetadataMode::Strip;
+        }
+        self
+    }
+
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.opts.backend = backend;
+        self
+    }
+
+    /// Directory for intermediate files instead of next to the output
+    pub fn temp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.opts.temp_dir = Some(dir.into());
+        self
+    }
+
+    pub fn under_target(mut self, mode: UnderTarget) -> Self {
+        self.opts.under_target = mode;
+        self
+    }
+
+    pub fn skip_optimized(mut self, min_percent: Option<u32>) -> Self {
+        self.opts.skip_optimized = min_percent;
+        self
+    }
+
+    pub fn strict(mut self, enabled: bool) -> Self {
+        self.opts.strict = enabled;
+        self
+    }
+
+    pub fn pdf_dpi(mut self, dpi: Option<u64>) -> Self {
+        self.opts.pdf_dpi = dpi;
+        self
+    }
+
+    pub fn pdf_downsample_above(mut self, dpi: Option<u64>) -> Self {
+        self.opts.pdf_downsample_above = dpi;
+        self
+    }
+
+    pub fn pdf_password(mut self, password: Option<String>) -> Self {
+        self.opts.pdf_password = password;
+        self
+    }
+
+    pub fn pdf_pages(mut self, pages: Option<String>) -> Self {
+        self.opts.pdf_pages = pages;
+        self
+    }
+
+    pub fn pdf_images(mut self, images: PdfImages) -> Self {
+        self.opts.pdf_images = images;
+        self
+    }
+
+    pub fn pdf_fonts(mut self, fonts: PdfFonts) -> Self {
+        self.opts.pdf_fonts = fonts;
+        self
+    }
+
+    pub fn pdf_scan_mode(mut self, enabled: bool) -> Self {
+        self.opts.pdf_scan_mode = enabled;
+        self
+    }
+
+    pub fn pdf_ocr(mut self, enabled: bool) -> Self {
+        self.opts.pdf_ocr = enabled;
+        self
+    }
+
+    pub fn pdf_keep_interactive(mut self, enabled: bool) -> Self {
+        self.opts.pdf_keep_interactive = enabled;
+        self
+    }
+
+    pub fn pdf_scrub_metadata(mut self, enabled: bool) -> Self {
+        self.opts.pdf_scrub_metadata = enabled;
+        self
+    }
+
+    pub fn min_savings(mut self, percent: Option<f64>) -> Self {
+        self.opts.min_savings = percent;
+        self
+    }
+
+    pub fn min_quality(mut self, quality: Option<u64>) -> Self {
+        self.opts.min_quality = quality;
+        self
+    }
+
+    pub fn min_scale(mut self, percent: Option<u64>) -> Self {
+        self.opts.min_scale = percent;
+        self
+    }
+
+    pub fn min_ssim(mut self, ssim: Option<f64>) -> Self {
+        self.opts.min_ssim = ssim;
+        self
+    }
+
+    pub fn max_distance(mut self, distance: Option<f64>) -> Self {
+        self.opts.max_distance = distance;
+        self
+    }
+
+    /// Box (either side optional) that JPGs and PNGs are shrunk to fit
+    pub fn max_dimensions(mut self, width: Option<u32>, height: Option<u32>) -> Self {
+        self.opts.max_width = width;
+        self.opts.max_height = height;
+        self
+    }
+
+    pub fn pdf_mode(mut self, mode: PdfMode) -> Self {
+        self.opts.pdf_mode = mode;
+        self
+    }
+
+    /// Smaller archives, but Windows Explorer and older unzips can't open them
+    pub fn zip_zstd(mut self, enabled: bool) -> Self {
+        self.opts.zip_zstd = enabled;
+        self
+    }
+
+    pub fn comic_pages(mut self, pages: ComicPages) -> Self {
+        self.opts.comic_pages = pages;
+        self
+    }
+
+    /// Reduce to black and white instead of grayscale where the chain drops color
+    pub fn bw(mut self, mode: Option<BwMode>) -> Self {
+        self.opts.bw = mode;
+        self
+    }
+
+    pub fn effort(mut self, effort: Effort) -> Self {
+        self.opts.effort = effort;
+        self
+    }
+
+    pub fn quality(mut self, quality: Option<u64>) -> Self {
+        self.opts.quality = quality;
+        self
+    }
+
+    pub fn jpeg_quality(mut self, quality: Option<u64>) -> Self {
+        self.opts.jpeg_quality = quality;
+        self
+    }
+
+    pub fn png_effort(mut self, effort: Option<Effort>) -> Self {
+        self.opts.png_effort = effort;
+        self
+    }
+
+    pub fn pdf_preset(mut self, preset: Option<PdfPreset>) -> Self {
+        self.opts.pdf_preset = preset;
+        self
+    }
+
+    /// Re-targeting: cap the matching search at what an earlier, too-large run used
+    pub fn prior(mut self, setting: Option<Setting>) -> Self {
+        self.opts.prior = setting;
+        self
+    }
+
+    /// Stage input and output through `throttle`; the tools then only touch local scratch files
+    pub fn io_throttle(mut self, throttle: Throttle) -> Self {
+        self.opts.io_throttle = Some(Arc::new(throttle));
+        self
+    }
+
+    pub fn shared(mut self, shared: Arc<Shared>) -> Self {
+        self.opts.shared = Some(shared);
+        self
+    }
+
+    /// Memory and thread caps passed to every tool invocation
+    pub fn limits(mut self, limits: ResourceLimits) -> Self {
+        self.opts.limits = limits;
+        self
+    }
+
+    /// Stages to leave out of this run
+    pub fn skip_stages(mut self, stages: impl IntoIterator<Item = Stage>) -> Self {
+        self.opts.skip_stages = stages.into_iter().collect();
+        self
+    }
+
+    pub fn progress(mut self, sink: Arc<dyn ProgressSink>) -> Self {
+        self.opts.progress = sink;
+        self
+    }
+
+    /// Where human-readable output goes; silent by default
+    pub fn reporter(mut self, reporter: Arc<dyn Reporter>) -> Self {
+        self.opts.reporter = reporter;
+        self
+    }
+
+    /// What actually runs gs/magick/pngquant; replaceable for tests
+    pub fn executor(mut self, executor: Arc<dyn Executor>) -> Self {
+        self.opts.executor = executor;
+        self
+    }
+
+    /// Token checked between stages and attempts; cancelling it also
+    /// kills the tool currently running
+    pub fn cancel_token(mut self, token: CancellationToken) -> Self {
+        self.opts.cancel = token;
+        self
+    }
+
+    pub fn build(self) -> CompressionOptions {
+        self.opts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scratch_path_next_to_output() {
+        let opts = CompressionOptions::default();
+        assert_eq!(opts.scratch_path("out/a.png", "gray.tmp.png"), "out/a.png.gray.tmp.png");
+    }
+
+    #[test]
+    fn test_exact_target_only_while_it_matches_target_kb() {
+        let mut opts = CompressionOptions::builder().target(Some(TargetSize::from_bytes(480_000))).build();
+        assert_eq!((opts.target_kb, opts.target_limit()), (Some(468), Some(480_000)));
+        // A share set on top falls back to whole KB
+        opts.target_kb = Some(200);
+        assert_eq!(opts.target_limit(), Some(200 * 1024));
+        assert_eq!(CompressionOptions::builder().target_kb(Some(300)).build().target_limit(), Some(307_200));
+    }
+
+    #[test]
+    fn test_scratch_path_in_temp_dir() {
+        let opts = CompressionOptions::builder().temp_dir("/tmp/work").build();
+        assert_eq!(opts.scratch_path("out/a.png", "gray.tmp.png"), "/tmp/work/a.png.gray.tmp.png");
+    }
+
+    #[test]
+    fn test_default_effort_keeps_historical_budgets() {
+        let effort = Effort::default();
+        assert_eq!((effort.image_probes(), effort.pdf_probes(), effort.oxipng_level()), (8, 14, 2));
+        assert!(!effort.zopfli() && !effort.guetzli());
+        assert_eq!(effort.strategy(), Strategy::Binary);
+        assert_eq!(Effort::new(0).get(), 1);
+        assert_eq!(Effort::new(42).strategy(), Strategy::Parallel(3));
+        assert!(Effort::new(1).image_probes() < Effort::new(10).image_probes());
+        assert_eq!((effort.x264_preset(), Effort::new(10).x264_preset()), ("medium", "veryslow"));
+        assert_eq!((effort.avif_speed(), Effort::new(10).avif_speed()), (6, 1));
+    }
+}
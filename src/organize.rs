@@ -0,0 +1,166 @@
+//! `--organize-by-date`: outputs filed into `YYYY/MM/` folders by when the
+//! photo was taken (EXIF `DateTimeOriginal`), or by modification time for
+//! files that don't say.
+
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Year and month a file's folder is named after
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct YearMonth {
+    pub year: i32,
+    pub month: u32,
+}
+
+impl YearMonth {
+    /// `YYYY/MM` under `root`
+    pub fn dir_in(&self, root: &Path) -> PathBuf {
+        root.join(format!("{:04}", self.year)).join(format!("{:02}", self.month))
+    }
+}
+
+/// Capture date of `path`, else its modification date (UTC)
+pub fn capture_month(path: &Path) -> Option<YearMonth> {
+    let exif = std::fs::read(path).ok().and_then(|data| exif_block(&data).and_then(exif_date));
+    exif.or_else(|| {
+        let secs = std::fs::metadata(path).ok()?.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        Some(from_unix_days((secs / 86_400) as i64))
+    })
+}
+
+/// Where the output for `input` goes: `root/YYYY/MM/name`
+pub fn dated_path(input: &Path, root: &Path, name: &str) -> PathBuf {
+    match capture_month(input) {
+        Some(month) => month.dir_in(root).join(name),
+        None => root.join(name),
+    }
+}
+
+/// The TIFF-structured EXIF payload of a JPEG (APP1) or PNG (eXIf)
+fn exif_block(data: &[u8]) -> Option<&[u8]> {
+    if data.starts_with(&[0xFF, 0xD8]) {
+        let mut pos = 2;
+        while pos + 4 <= data.len() && data[pos] == 0xFF {
+            let marker = data[pos + 1];
+            if marker == 0xDA || marker == 0xD9 {
+                break;
+            }
+            let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+            let payload = data.get(pos + 4..pos + 2 + len)?;
+            if marker == 0xE1 && payload.starts_with(b"Exif\0\0") {
+                return Some(&payload[6..]);
+            }
+            pos += 2 + len;
+        }
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        let mut pos = 8;
+        while pos + 8 <= data.len() {
+            let len = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+            let body = data.get(pos + 8..pos + 8 + len)?;
+            match &data[pos + 4..pos + 8] {
+                b"eXIf" => return Some(body),
+                b"IEND" => break,
+                _ => {},
+            }
+            pos += 12 + len;
+        }
+    }
+    None
+}
+
+/// `DateTimeOriginal` from the EXIF sub-IFD, else `DateTime` from IFD0
+fn exif_date(tiff: &[u8]) -> Option<YearMonth> {
+    let big_endian = match tiff.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |at: usize| tiff.get(at..at + 2).map(|b| if big_endian { u16::from_be_bytes([b[0], b[1]]) } else { u16::from_le_bytes([b[0], b[1]]) });
+    let u32_at = |at: usize| tiff.get(at..at + 4).map(|b| {
+        let b = [b[0], b[1], b[2], b[3]];
+        if big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) }
+    });
+    // Value field of `tag` in the IFD at `ifd`
+    let entry = |ifd: usize, tag: u16| (0..u16_at(ifd)? as usize)
+        .map(|i| ifd + 2 + i * 12)
+        .find(|&e| u16_at(e) == Some(tag))
+        .map(|e| e + 8);
+    let date_at = |value: usize| u32_at(value).and_then(|offset| parse_date(tiff.get(offset as usize..offset as usize + 19)?));
+
+    let ifd0 = u32_at(4)? as usize;
+    let original = entry(ifd0, 0x8769)
+        .and_then(u32_at)
+        .and_then(|sub| entry(sub as usize, 0x9003))
+        .and_then(date_at);
+    original.or_else(|| entry(ifd0, 0x0132).and_then(date_at))
+}
+
+/// `YYYY:MM:DD HH:MM:SS`
+fn parse_date(text: &[u8]) -> Option<YearMonth> {
+    let text = std::str::from_utf8(text).ok()?;
+    let year = text.get(0..4)?.parse().ok()?;
+    let month = text.get(5..7)?.parse().ok()?;
+    // Cameras without a set clock write zeros
+    (year > 0 && (1..=12).contains(&month)).then_some(YearMonth { year, month })
+}
+
+/// Civil (year, month, day) of a day count since 1970-01-01
+pub fn civil_date(days: i64) -> (i32, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+    (year, month, day)
+}
+
+fn from_unix_days(days: i64) -> YearMonth {
+    let (year, month, _) = civil_date(days);
+    YearMonth { year, month }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Little-endian EXIF with IFD0 -> Exif sub-IFD -> DateTimeOriginal
+    fn exif_with_date(date: &str) -> Vec<u8> {
+        let mut tiff = b"II\x2a\x00\x08\x00\x00\x00".to_vec();
+        // IFD0 at 8: one entry pointing at the sub-IFD at 26
+        tiff.extend([1, 0, 0x69, 0x87, 4, 0, 1, 0, 0, 0, 26, 0, 0, 0, 0, 0, 0, 0]);
+        // Sub-IFD at 26: DateTimeOriginal, 20 ASCII bytes at 44
+        tiff.extend([1, 0, 0x03, 0x90, 2, 0, 20, 0, 0, 0, 44, 0, 0, 0, 0, 0, 0, 0]);
+        tiff.extend(date.as_bytes());
+        tiff.push(0);
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        jpeg.extend(((tiff.len() + 8) as u16).to_be_bytes());
+        jpeg.extend(b"Exif\0\0");
+        jpeg.extend(tiff);
+        jpeg.extend([0xFF, 0xDA]);
+        jpeg
+    }
+
+    #[test]
+    fn test_exif_date_picks_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let photo = dir.path().join("IMG_0001.jpg");
+        std::fs::write(&photo, exif_with_date("2019:07:14 10:22:05")).unwrap();
+        let path = dated_path(&photo, Path::new("out"), "crnched_IMG_0001.jpg");
+        assert_eq!(path, Path::new("out/2019/07/crnched_IMG_0001.jpg"));
+    }
+
+    #[test]
+    fn test_unset_clock_falls_back_to_mtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let photo = dir.path().join("scan.jpg");
+        std::fs::write(&photo, exif_with_date("0000:00:00 00:00:00")).unwrap();
+        let secs = std::fs::metadata(&photo).unwrap().modified().unwrap().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        assert_eq!(capture_month(&photo), Some(from_unix_days((secs / 86_400) as i64)));
+        assert_eq!(from_unix_days(0), YearMonth { year: 1970, month: 1 });
+        assert_eq!(from_unix_days(19_782), YearMonth { year: 2024, month: 2 });
+    }
+}
@@ -0,0 +1,146 @@
+//! Bookmarks, links and form fields in a PDF, counted before and after
+//! compression so a Ghostscript pass that drops them doesn't go unnoticed.
+//!
+//! Only objects reachable from the catalog count, so unused leftovers in the
+//! input don't read as losses. Objects packed in object streams are read
+//! too, through [`pdf_quick::read_objects`](crate::pdf_quick::read_objects).
+
+use std::collections::{BTreeMap, HashSet};
+use crate::pdf_quick::{dict_value, find_ref, read_objects, refs, trim, Id, Object};
+
+/// How many of each interactive element a PDF has
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Interactive {
+    pub bookmarks: usize,
+    pub links: usize,
+    pub fields: usize,
+}
+
+impl Interactive {
+    /// What `after` has fewer of, e.g. "3 of 12 bookmarks"
+    pub fn lost(&self, after: &Interactive) -> Vec<String> {
+        [(self.bookmarks, after.bookmarks, "bookmarks"), (self.links, after.links, "links"), (self.fields, after.fields, "form fields")]
+            .into_iter()
+            .filter(|(before, after, _)| after < before)
+            .map(|(before, after, what)| format!("{} of {} {}", before - after, before, what))
+            .collect()
+    }
+}
+
+/// Counts for the PDF in `data`; `None` when it's encrypted or its catalog
+/// can't be found
+pub fn count(data: &[u8]) -> Option<Interactive> {
+    let (objects, trailer) = read_objects(data)?;
+    let root = find_ref(&trailer, b"/Root")?;
+    let catalog = objects.get(&root)?;
+
+    let mut counts = Interactive::default();
+    if let Some(first) = find_ref(&catalog.body, b"/Outlines").and_then(|id| find_ref(&objects.get(&id)?.body, b"/First")) {
+        counts.bookmarks = count_outline(&objects, first);
+    }
+    let mut seen = HashSet::new();
+    let mut stack = vec![root];
+    while let Some(id) = stack.pop() {
+        let Some(object) = objects.get(&id).filter(|_| seen.insert(id)) else { continue };
+        if dict_value(&object.body, b"/Subtype").is_some_and(|v| trim(v) == b"/Link") {
+            counts.links += 1;
+        }
+        if dict_value(&object.body, b"/FT").is_some() {
+            counts.fields += 1;
+        }
+        stack.extend(refs(&object.body));
+    }
+    Some(counts)
+}
+
+/// Outline items from `first` on, through `/Next` siblings and `/First` children
+fn count_outline(objects: &BTreeMap<Id, Object>, first: Id) -> usize {
+    let mut seen = HashSet::new();
+    let mut stack = vec![first];
+    while let Some(id) = stack.pop() {
+        let Some(item) = objects.get(&id).filter(|_| seen.insert(id)) else { continue };
+        stack.extend(find_ref(&item.body, b"/Next"));
+        stack.extend(find_ref(&item.body, b"/First"));
+    }
+    seen.len()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A PDF of `objects`, numbered from 1, the first being the catalog
+    pub(crate) fn pdf(objects: &[&str]) -> Vec<u8> {
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        for (i, object) in objects.iter().enumerate() {
+            pdf.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+        }
+        pdf.extend(format!("xref\n0 {}\ntrailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n0\n%%EOF\n", objects.len() + 1, objects.len() + 1).as_bytes());
+        pdf
+    }
+
+    #[test]
+    fn test_counts_reachable_bookmarks_links_and_fields() {
+        let full = pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R /Outlines 4 0 R /AcroForm << /Fields [8 0 R] >> >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /Annots [7 0 R 8 0 R] >>",
+            "<< /Type /Outlines /First 5 0 R /Last 6 0 R >>",
+            "<< /Title (Intro) /Parent 4 0 R /Next 6 0 R /First 9 0 R >>",
+            "<< /Title (Method) /Parent 4 0 R >>",
+            "<< /Type /Annot /Subtype /Link /A << /URI (https://example.com) >> >>",
+            "<< /Type /Annot /Subtype /Widget /FT /Tx /T (name) >>",
+            "<< /Title (Background) /Parent 5 0 R >>",
+            // Unreferenced: not counted
+            "<< /Type /Annot /Subtype /Link >>",
+        ]);
+        let before = count(&full).unwrap();
+        assert_eq!(before, Interactive { bookmarks: 3, links: 1, fields: 1 });
+
+        let flattened = pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /Annots [4 0 R] >>",
+            "<< /Type /Annot /Subtype /Link >>",
+        ]);
+        let after = count(&flattened).unwrap();
+        assert_eq!(before.lost(&after), vec!["3 of 3 bookmarks", "1 of 1 form fields"]);
+        assert!(after.lost(&before).is_empty());
+        assert!(count(b"%PDF-1.5\n1 0 obj\n<< /Type /XRef >>\nendobj\n").is_none());
+        assert!(count(b"%PDF-1.4\ntrailer\n<< /Root 1 0 R /Encrypt 2 0 R >>\n").is_none());
+    }
+
+    #[test]
+    fn test_counts_through_object_and_xref_streams() {
+        use flate2::{write::ZlibEncoder, Compression};
+        use std::io::Write;
+
+        let packed = [
+            "<< /Type /Catalog /Pages 2 0 R /Outlines 4 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /Annots [6 0 R] >>",
+            "<< /Type /Outlines /First 5 0 R >>",
+            "<< /Title (Intro) /Parent 4 0 R >>",
+            "<< /Type /Annot /Subtype /Link >>",
+        ];
+        let mut header = String::new();
+        let mut bodies = String::new();
+        for (i, object) in packed.iter().enumerate() {
+            header.push_str(&format!("{} {} ", i + 1, bodies.len()));
+            bodies.push_str(object);
+            bodies.push('\n');
+        }
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(format!("{}{}", header, bodies).as_bytes()).unwrap();
+        let stream = encoder.finish().unwrap();
+
+        let mut data = b"%PDF-1.5\n".to_vec();
+        data.extend(format!("7 0 obj\n<< /Type /ObjStm /N 6 /First {} /Filter /FlateDecode /Length {} >>\nstream\n", header.len(), stream.len()).as_bytes());
+        data.extend(&stream);
+        data.extend(b"\nendstream\nendobj\n");
+        let xref = data.len();
+        data.extend(format!("8 0 obj\n<< /Type /XRef /Size 9 /Root 1 0 R /Length 0 >>\nstream\n\nendstream\nendobj\nstartxref\n{}\n%%EOF\n", xref).as_bytes());
+
+        assert_eq!(count(&data), Some(Interactive { bookmarks: 1, links: 1, fields: 0 }));
+    }
+}
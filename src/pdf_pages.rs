@@ -0,0 +1,109 @@
+//! Where a PDF's bytes are, page by page, for nerd mode's page report.
+//!
+//! Each object is charged to the one page that reaches it (contents, and
+//! images and fonts through `/Resources`); objects several pages reach count
+//! as shared, and the rest (catalog, outlines, ...) is left out. Reads the
+//! same classic-xref files as [`pdf_quick`](crate::pdf_quick).
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::pdf_quick::{contains, dict_value, find_ref, is_page, last_trailer, parse_objects, refs, trim, Id, Object};
+
+/// Bytes per page, in page order, and in objects the pages share
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PageSizes {
+    pub pages: Vec<u64>,
+    pub shared: u64,
+}
+
+/// Page sizes of the PDF in `data`; `None` for object streams,
+/// cross-reference streams and encryption
+pub fn page_sizes(data: &[u8]) -> Option<PageSizes> {
+    if !data.starts_with(b"%PDF-") || contains(data, b"/ObjStm") || contains(data, b"/XRef") || contains(data, b"/Encrypt") {
+        return None;
+    }
+    let objects = parse_objects(data);
+    let root = find_ref(&last_trailer(data)?, b"/Root")?;
+    let mut pages = Vec::new();
+    collect_pages(&objects, find_ref(&objects.get(&root)?.body, b"/Pages")?, &mut HashSet::new(), &mut pages);
+
+    // Owning page of every object reached, None once a second page reaches it
+    let mut owner: HashMap<Id, Option<usize>> = HashMap::new();
+    for (index, &page) in pages.iter().enumerate() {
+        let mut seen = HashSet::new();
+        let mut stack = vec![page];
+        while let Some(id) = stack.pop() {
+            let Some(object) = objects.get(&id) else { continue };
+            // Not through /Parent into the tree, nor into other pages
+            if id != page && (is_page(&object.body) || is_page_tree(&object.body)) {
+                continue;
+            }
+            if seen.insert(id) {
+                stack.extend(refs(&object.body));
+            }
+        }
+        for id in seen {
+            owner.entry(id).and_modify(|o| if *o != Some(index) { *o = None }).or_insert(Some(index));
+        }
+    }
+
+    let mut sizes = PageSizes { pages: vec![0; pages.len()], shared: 0 };
+    for (id, page) in owner {
+        let bytes = object_bytes(&objects[&id]);
+        match page {
+            Some(index) => sizes.pages[index] += bytes,
+            None => sizes.shared += bytes,
+        }
+    }
+    Some(sizes)
+}
+
+/// Pages under the tree node `node`, in order
+pub(crate) fn collect_pages(objects: &BTreeMap<Id, Object>, node: Id, visited: &mut HashSet<Id>, pages: &mut Vec<Id>) {
+    let Some(object) = objects.get(&node).filter(|_| visited.insert(node)) else { return };
+    if is_page(&object.body) {
+        pages.push(node);
+    } else if let Some(kids) = dict_value(&object.body, b"/Kids") {
+        for kid in refs(kids) {
+            collect_pages(objects, kid, visited, pages);
+        }
+    }
+}
+
+pub(crate) fn is_page_tree(body: &[u8]) -> bool {
+    dict_value(body, b"/Type").is_some_and(|v| trim(v) == b"/Pages")
+}
+
+fn object_bytes(object: &Object) -> u64 {
+    (object.body.len() + object.stream.as_ref().map_or(0, Vec::len)) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sizes_per_page_and_shared() {
+        let scan = "x".repeat(5000);
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /Contents 5 0 R /Resources << /Font << /F1 7 0 R >> >> >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /Contents 6 0 R /Resources << /Font << /F1 7 0 R >> /XObject << /Im1 8 0 R >> >> >>".to_string(),
+            "<< /Length 2 >>\nstream\nET\nendstream".to_string(),
+            "<< /Length 2 >>\nstream\nET\nendstream".to_string(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+            format!("<< /Type /XObject /Subtype /Image /Length {} >>\nstream\n{}\nendstream", scan.len(), scan),
+        ];
+        for (i, object) in objects.iter().enumerate() {
+            pdf.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+        }
+        pdf.extend(b"xref\n0 9\ntrailer\n<< /Size 9 /Root 1 0 R >>\nstartxref\n0\n%%EOF\n");
+
+        let sizes = page_sizes(&pdf).unwrap();
+        assert_eq!(sizes.pages.len(), 2);
+        assert!(sizes.pages[1] > 5000 && sizes.pages[0] < 200, "{:?}", sizes);
+        assert_eq!(sizes.shared, objects[6].len() as u64);
+        assert!(page_sizes(b"%PDF-1.5\n1 0 obj\n<< /Type /XRef >>\nendobj\n").is_none());
+    }
+}
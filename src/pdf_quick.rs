@@ -0,0 +1,616 @@
+//! `--pdf-mode quick`: lossless PDF size reduction without Ghostscript.
+//!
+//! The file is rewritten from its objects: unreachable ones are dropped,
+//! byte-identical ones merged, and Flate streams re-deflated at the highest
+//! level (unfiltered ones deflated) whenever that comes out smaller. Images
+//! and fonts are never decoded, so the output renders exactly like the input.
+//! Files with object streams, cross-reference streams or encryption are left
+//! alone.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io::{Read, Write};
+use std::path::Path;
+use anyhow::Result;
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+
+/// What a quick pass did
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct QuickStats {
+    pub objects_before: usize,
+    pub objects_after: usize,
+    pub streams_recompressed: usize,
+}
+
+/// Object id: (number, generation)
+pub(crate) type Id = (u32, u16);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Object {
+    /// Everything between `obj` and `stream`/`endobj`
+    pub(crate) body: Vec<u8>,
+    pub(crate) stream: Option<Vec<u8>>,
+}
+
+/// Optimize `input` into `output`. `None` (and nothing written) when the
+/// file's structure is one this pass doesn't rewrite.
+pub fn optimize(input: &Path, output: &Path) -> Result<Option<QuickStats>> {
+    let data = std::fs::read(input)?;
+    let Some((optimized, stats)) = rewrite(&data) else { return Ok(None) };
+    // Never hand back something bigger than what came in
+    if optimized.len() < data.len() {
+        std::fs::write(output, optimized)?;
+    } else {
+        std::fs::write(output, &data)?;
+    }
+    Ok(Some(stats))
+}
+
+fn rewrite(data: &[u8]) -> Option<(Vec<u8>, QuickStats)> {
+    if !data.starts_with(b"%PDF-") || contains(data, b"/ObjStm") || contains(data, b"/XRef") || contains(data, b"/Encrypt") {
+        return None;
+    }
+    let header = &data[..data.iter().position(|&b| b == b'\r' || b == b'\n')?];
+    let mut objects = parse_objects(data);
+    let trailer = last_trailer(data)?;
+    let root = find_ref(&trailer, b"/Root")?;
+    let info = find_ref(&trailer, b"/Info");
+    let id = dict_value(&trailer, b"/ID").map(|v| v.to_vec());
+    let mut stats = QuickStats { objects_before: objects.len(), ..QuickStats::default() };
+
+    // Reachable from the trailer, in original number order
+    let mut reachable = HashSet::new();
+    let mut queue: VecDeque<Id> = [root].into_iter().chain(info).collect();
+    while let Some(id) = queue.pop_front() {
+        if !objects.contains_key(&id) || !reachable.insert(id) {
+            continue;
+        }
+        queue.extend(refs(&objects[&id].body));
+    }
+    objects.retain(|id, _| reachable.contains(id));
+
+    for object in objects.values_mut() {
+        if recompress(object) {
+            stats.streams_recompressed += 1;
+        }
+    }
+
+    // Identical objects collapse onto the first one; pages stay distinct
+    let mut canonical: HashMap<Id, Id> = HashMap::new();
+    let mut seen: HashMap<&Object, Id> = HashMap::new();
+    for (id, object) in &objects {
+        if is_page(&object.body) {
+            continue;
+        }
+        match seen.get(object) {
+            Some(&first) => { canonical.insert(*id, first); },
+            None => { seen.insert(object, *id); },
+        }
+    }
+    let kept: Vec<Id> = objects.keys().filter(|id| !canonical.contains_key(id)).copied().collect();
+    let numbers: HashMap<Id, u32> = kept.iter().enumerate().map(|(i, id)| (*id, i as u32 + 1)).collect();
+    let renumber = |id: Id| numbers.get(canonical.get(&id).unwrap_or(&id)).copied();
+    stats.objects_after = kept.len();
+
+    let mut out = Vec::with_capacity(data.len());
+    out.extend(header);
+    out.extend(b"\n%\xE2\xE3\xCF\xD3\n");
+    let mut offsets = Vec::with_capacity(kept.len());
+    for (i, id) in kept.iter().enumerate() {
+        let object = &objects[id];
+        offsets.push(out.len());
+        writeln!(out, "{} 0 obj", i + 1).ok()?;
+        out.extend(rewrite_refs(&object.body, renumber));
+        if let Some(ref stream) = object.stream {
+            out.extend(b"\nstream\n");
+            out.extend(stream);
+            out.extend(b"\nendstream");
+        }
+        out.extend(b"\nendobj\n");
+    }
+    let xref = out.len();
+    write!(out, "xref\n0 {}\n0000000000 65535 f \n", kept.len() + 1).ok()?;
+    for offset in offsets {
+        writeln!(out, "{:010} 00000 n ", offset).ok()?;
+    }
+    write!(out, "trailer\n<< /Size {} /Root {} 0 R", kept.len() + 1, renumber(root)?).ok()?;
+    if let Some(info) = info.and_then(renumber) {
+        write!(out, " /Info {} 0 R", info).ok()?;
+    }
+    if let Some(id) = id {
+        out.extend(b" /ID ");
+        out.extend(id);
+    }
+    write!(out, " >>\nstartxref\n{}\n%%EOF\n", xref).ok()?;
+    Some((out, stats))
+}
+
+/// Every `N G obj ... endobj` in file order; later definitions (incremental
+/// updates) replace earlier ones
+pub(crate) fn parse_objects(data: &[u8]) -> BTreeMap<Id, Object> {
+    let mut objects = BTreeMap::new();
+    let mut pos = 0;
+    while let Some(at) = find(data, b"obj", pos) {
+        pos = at + 3;
+        let Some(id) = object_header(data, at) else { continue };
+        if data.get(pos).is_some_and(|b| !is_delimiter(*b)) {
+            continue;
+        }
+        let Some(end) = find(data, b"endobj", pos) else { break };
+        let value_end = value_end(data, pos);
+        let body_end = value_end.filter(|&e| e <= end).unwrap_or(end);
+        let body = trim(&data[pos..body_end]).to_vec();
+        let after = skip_whitespace(data, body_end);
+        let mut stream = None;
+        if data[after..].starts_with(b"stream") {
+            let mut begin = after + 6;
+            if data.get(begin) == Some(&b'\r') { begin += 1; }
+            if data.get(begin) == Some(&b'\n') { begin += 1; }
+            let length = direct_or_indirect_length(data, &body)
+                .filter(|&len| data.get(begin + len..).is_some_and(|rest| trim_start(rest).starts_with(b"endstream")));
+            let stream_end = match length {
+                Some(len) => begin + len,
+                None => match find(data, b"endstream", begin) {
+                    Some(e) => trim_eol(data, begin, e),
+                    None => break,
+                },
+            };
+            stream = Some(data[begin..stream_end].to_vec());
+            pos = find(data, b"endobj", stream_end).map_or(data.len(), |e| e + 6);
+        } else {
+            pos = end + 6;
+        }
+        objects.insert(id, Object { body, stream });
+    }
+    objects
+}
+
+/// Every object, including those packed in object streams, and the trailer
+/// dictionary (a cross-reference stream's, when there's no `trailer`);
+/// `None` for anything that isn't an unencrypted PDF
+pub(crate) fn read_objects(data: &[u8]) -> Option<(BTreeMap<Id, Object>, Vec<u8>)> {
+    if !data.starts_with(b"%PDF-") || contains(data, b"/Encrypt") {
+        return None;
+    }
+    let mut objects = parse_objects(data);
+    let packed: Vec<(Id, Vec<u8>)> = objects.values()
+        .filter(|o| dict_value(&o.body, b"/Type").is_some_and(|v| trim(v) == b"/ObjStm"))
+        .map(unpack)
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+    // Objects written out directly belong to a later update
+    for (id, body) in packed {
+        objects.entry(id).or_insert(Object { body, stream: None });
+    }
+    let trailer = last_trailer(data).or_else(|| xref_stream_dict(data, &objects))?;
+    Some((objects, trailer))
+}
+
+/// The objects packed in an object stream, by number (generation 0)
+fn unpack(stream: &Object) -> Option<Vec<(Id, Vec<u8>)>> {
+    let plain = inflate(stream)?;
+    let number = |key: &[u8]| -> Option<usize> { std::str::from_utf8(trim(dict_value(&stream.body, key)?)).ok()?.parse().ok() };
+    let (count, first) = (number(b"/N")?, number(b"/First")?);
+    let header = std::str::from_utf8(plain.get(..first)?).ok()?;
+    let pairs: Vec<usize> = header.split_ascii_whitespace().map(|n| n.parse().ok()).collect::<Option<_>>()?;
+    let entries: Vec<(u32, usize)> = pairs.chunks_exact(2).take(count).map(|p| (p[0] as u32, first + p[1])).collect();
+    let mut unpacked = Vec::with_capacity(entries.len());
+    for (index, &(num, start)) in entries.iter().enumerate() {
+        let end = entries.get(index + 1).map_or(plain.len(), |next| next.1);
+        unpacked.push(((num, 0), trim(plain.get(start..end)?).to_vec()));
+    }
+    Some(unpacked)
+}
+
+/// Dictionary of the cross-reference stream the last `startxref` points at
+fn xref_stream_dict(data: &[u8], objects: &BTreeMap<Id, Object>) -> Option<Vec<u8>> {
+    let at = data.windows(9).rposition(|w| w == b"startxref")? + 9;
+    let offset: usize = std::str::from_utf8(trim(&data[at..]).split(|b| b.is_ascii_whitespace()).next()?).ok()?.parse().ok()?;
+    let head = String::from_utf8_lossy(&data.get(offset..)?[..32.min(data.len() - offset)]).to_string();
+    let mut words = head.split_ascii_whitespace();
+    let id = (words.next()?.parse().ok()?, words.next()?.parse().ok()?);
+    let body = &objects.get(&id)?.body;
+    dict_value(body, b"/Type").is_some_and(|v| trim(v) == b"/XRef").then(|| body.clone())
+}
+
+/// The stream of a `/FlateDecode` object, inflated; predictors, if any, are
+/// left applied
+pub(crate) fn inflate(object: &Object) -> Option<Vec<u8>> {
+    let filter = trim(dict_value(&object.body, b"/Filter")?);
+    if !matches!(filter, b"/FlateDecode" | b"[/FlateDecode]" | b"[ /FlateDecode ]") {
+        return None;
+    }
+    let mut plain = Vec::new();
+    ZlibDecoder::new(&object.stream.as_ref()?[..]).read_to_end(&mut plain).ok()?;
+    Some(plain)
+}
+
+/// The `N G` before the `obj` at `at`
+fn object_header(data: &[u8], at: usize) -> Option<Id> {
+    let mut i = at;
+    let mut numbers = [0u64; 2];
+    for slot in (0..2).rev() {
+        let ws_end = i;
+        while i > 0 && data[i - 1].is_ascii_whitespace() { i -= 1; }
+        if i == ws_end { return None; }
+        let digits_end = i;
+        while i > 0 && data[i - 1].is_ascii_digit() { i -= 1; }
+        if i == digits_end || digits_end - i > 10 { return None; }
+        numbers[slot] = std::str::from_utf8(&data[i..digits_end]).ok()?.parse().ok()?;
+    }
+    if i > 0 && !is_delimiter(data[i - 1]) {
+        return None;
+    }
+    Some((u32::try_from(numbers[0]).ok()?, u16::try_from(numbers[1]).ok()?))
+}
+
+/// End of the dictionary starting at `pos` (after whitespace), if it is one
+fn value_end(data: &[u8], pos: usize) -> Option<usize> {
+    let start = skip_whitespace(data, pos);
+    if !data[start..].starts_with(b"<<") {
+        return None;
+    }
+    let mut depth = 0;
+    let mut i = start;
+    while i < data.len() {
+        match data[i] {
+            b'(' => i = skip_string(data, i),
+            b'%' => while i < data.len() && data[i] != b'\n' && data[i] != b'\r' { i += 1; },
+            b'<' if data.get(i + 1) == Some(&b'<') => { depth += 1; i += 2; continue; },
+            b'>' if data.get(i + 1) == Some(&b'>') => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 { return Some(i); }
+                continue;
+            },
+            _ => {},
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Index of the `)` closing the literal string opened at `open`
+fn skip_string(data: &[u8], open: usize) -> usize {
+    let mut depth = 0;
+    let mut i = open;
+    while i < data.len() {
+        match data[i] {
+            b'\\' => i += 1,
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 { return i; }
+            },
+            _ => {},
+        }
+        i += 1;
+    }
+    data.len()
+}
+
+fn direct_or_indirect_length(data: &[u8], dict: &[u8]) -> Option<usize> {
+    let value = dict_value(dict, b"/Length")?;
+    if let Some(&(num, gen)) = refs(value).first() {
+        let header = format!("{} {} obj", num, gen);
+        let at = find(data, header.as_bytes(), 0)?;
+        let rest = trim_start(&data[at + header.len()..]);
+        let digits = rest.iter().take_while(|b| b.is_ascii_digit()).count();
+        return std::str::from_utf8(&rest[..digits]).ok()?.parse().ok();
+    }
+    std::str::from_utf8(trim(value)).ok()?.parse().ok()
+}
+
+/// Re-deflate a Flate stream (or deflate an unfiltered one) when smaller
+fn recompress(object: &mut Object) -> bool {
+    let Some(ref stream) = object.stream else { return false };
+    let filter = dict_value(&object.body, b"/Filter").map(|v| trim(v).to_vec());
+    let plain = match filter.as_deref() {
+        Some(b"/FlateDecode") | Some(b"[/FlateDecode]") | Some(b"[ /FlateDecode ]") => {
+            let Some(plain) = inflate(object) else { return false };
+            plain
+        },
+        // XMP is meant to stay readable without decoding
+        None if !contains(&object.body, b"/Metadata") && dict_value(&object.body, b"/DecodeParms").is_none() => stream.clone(),
+        _ => return false,
+    };
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    if encoder.write_all(&plain).is_err() {
+        return false;
+    }
+    let Ok(deflated) = encoder.finish() else { return false };
+    // The dictionary grows by a few bytes when a filter is added
+    let overhead = if filter.is_none() { 20 } else { 0 };
+    if deflated.len() + overhead >= stream.len() {
+        return false;
+    }
+    let mut body = remove_entry(&object.body, b"/Length");
+    if filter.is_none() {
+        body = insert_entry(&body, b"/Filter /FlateDecode");
+    }
+    object.body = insert_entry(&body, format!("/Length {}", deflated.len()).as_bytes());
+    object.stream = Some(deflated);
+    true
+}
+
+pub(crate) fn is_page(body: &[u8]) -> bool {
+    dict_value(body, b"/Type").is_some_and(|v| trim(v) == b"/Page")
+}
+
+/// Raw value following `key` at the top level of a dictionary
+pub(crate) fn dict_value<'a>(dict: &'a [u8], key: &[u8]) -> Option<&'a [u8]> {
+    let (start, end) = entry_span(dict, key)?;
+    Some(&dict[start + key.len()..end])
+}
+
+/// Span of `key` and its value among the top-level entries of `dict`
+fn entry_span(dict: &[u8], key: &[u8]) -> Option<(usize, usize)> {
+    let mut i = skip_whitespace(dict, 0);
+    if !dict[i..].starts_with(b"<<") {
+        return None;
+    }
+    i += 2;
+    let mut key_start = None;
+    let mut expecting_key = true;
+    while i < dict.len() {
+        i = skip_whitespace(dict, i);
+        if i >= dict.len() || dict[i..].starts_with(b">>") {
+            return key_start.map(|s| (s, i));
+        }
+        let end_of_token = token_end(dict, i);
+        if expecting_key {
+            if let Some(s) = key_start {
+                return Some((s, i));
+            }
+            if &dict[i..end_of_token] == key {
+                key_start = Some(i);
+            }
+            expecting_key = false;
+            i = end_of_token;
+        } else {
+            // A value: a reference, or one object
+            let mut end = end_of_token;
+            if dict[i].is_ascii_digit() {
+                let second = skip_whitespace(dict, end);
+                if dict.get(second).is_some_and(|b| b.is_ascii_digit()) {
+                    let r = skip_whitespace(dict, token_end(dict, second));
+                    if dict.get(r) == Some(&b'R') && dict.get(r + 1).is_none_or(|b| is_delimiter(*b)) {
+                        end = r + 1;
+                    }
+                }
+            }
+            if let Some(s) = key_start {
+                return Some((s, end));
+            }
+            expecting_key = true;
+            i = end;
+        }
+    }
+    None
+}
+
+/// End of the token starting at `i`, with nested dicts, arrays and strings as one token
+fn token_end(data: &[u8], i: usize) -> usize {
+    match data[i] {
+        b'<' if data.get(i + 1) == Some(&b'<') => value_end(data, i).unwrap_or(data.len()),
+        b'<' => data[i..].iter().position(|&b| b == b'>').map_or(data.len(), |p| i + p + 1),
+        b'(' => skip_string(data, i) + 1,
+        b'[' => {
+            let mut depth = 0;
+            let mut j = i;
+            while j < data.len() {
+                match data[j] {
+                    b'[' => depth += 1,
+                    b']' => {
+                        depth -= 1;
+                        if depth == 0 { return j + 1; }
+                    },
+                    b'(' => j = skip_string(data, j),
+                    _ => {},
+                }
+                j += 1;
+            }
+            data.len()
+        },
+        b'/' => i + 1 + data[i + 1..].iter().position(|&b| is_delimiter(b)).unwrap_or(data.len() - i - 1),
+        _ => i + data[i..].iter().position(|&b| is_delimiter(b)).unwrap_or(data.len() - i).max(1),
+    }
+}
+
+fn remove_entry(dict: &[u8], key: &[u8]) -> Vec<u8> {
+    match entry_span(dict, key) {
+        Some((start, end)) => [&dict[..start], &dict[end..]].concat(),
+        None => dict.to_vec(),
+    }
+}
+
+fn insert_entry(dict: &[u8], entry: &[u8]) -> Vec<u8> {
+    let at = skip_whitespace(dict, 0) + 2;
+    [&dict[..at], b" ", entry, &dict[at..]].concat()
+}
+
+/// Every `N G R` reference outside strings and comments
+pub(crate) fn refs(body: &[u8]) -> Vec<Id> {
+    let mut found = Vec::new();
+    rewrite_refs(body, |id| {
+        found.push(id);
+        Some(0)
+    });
+    found
+}
+
+/// `body` with every reference renumbered; unknown targets become `null`
+fn rewrite_refs(body: &[u8], mut map: impl FnMut(Id) -> Option<u32>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(body.len());
+    let mut i = 0;
+    while i < body.len() {
+        match body[i] {
+            b'(' => {
+                let end = (skip_string(body, i) + 1).min(body.len());
+                out.extend(&body[i..end]);
+                i = end;
+                continue;
+            },
+            b'%' => {
+                let end = body[i..].iter().position(|&b| b == b'\n' || b == b'\r').map_or(body.len(), |p| i + p);
+                out.extend(&body[i..end]);
+                i = end;
+                continue;
+            },
+            b'<' if body.get(i + 1) == Some(&b'<') => {
+                out.extend(b"<<");
+                i += 2;
+                continue;
+            },
+            b'<' => {
+                let end = token_end(body, i);
+                out.extend(&body[i..end]);
+                i = end;
+                continue;
+            },
+            b if b.is_ascii_digit() && (i == 0 || is_delimiter(body[i - 1])) => {
+                if let Some((id, end)) = reference_at(body, i) {
+                    match map(id) {
+                        Some(n) => write!(out, "{} 0 R", n).unwrap_or(()),
+                        None => out.extend(b"null"),
+                    }
+                    i = end;
+                    continue;
+                }
+                let end = i + body[i..].iter().take_while(|b| b.is_ascii_digit() || **b == b'.').count();
+                out.extend(&body[i..end]);
+                i = end;
+                continue;
+            },
+            _ => {},
+        }
+        out.push(body[i]);
+        i += 1;
+    }
+    out
+}
+
+/// A `N G R` starting at `i`, and the index just past the `R`
+fn reference_at(body: &[u8], i: usize) -> Option<(Id, usize)> {
+    let number = |from: usize| -> Option<(u64, usize)> {
+        let len = body[from..].iter().take_while(|b| b.is_ascii_digit()).count();
+        if len == 0 || len > 10 { return None; }
+        Some((std::str::from_utf8(&body[from..from + len]).ok()?.parse().ok()?, from + len))
+    };
+    let (num, end) = number(i)?;
+    let gen_start = skip_whitespace(body, end);
+    if gen_start == end { return None; }
+    let (gen, end) = number(gen_start)?;
+    let r = skip_whitespace(body, end);
+    if r == end || body.get(r) != Some(&b'R') || body.get(r + 1).is_some_and(|b| !is_delimiter(*b)) {
+        return None;
+    }
+    Some(((u32::try_from(num).ok()?, u16::try_from(gen).ok()?), r + 1))
+}
+
+pub(crate) fn find_ref(dict: &[u8], key: &[u8]) -> Option<Id> {
+    refs(dict_value(dict, key)?).first().copied()
+}
+
+/// The dictionary after the last `trailer` keyword
+pub(crate) fn last_trailer(data: &[u8]) -> Option<Vec<u8>> {
+    let at = data.windows(7).rposition(|w| w == b"trailer")? + 7;
+    let end = value_end(data, at)?;
+    Some(trim(&data[at..end]).to_vec())
+}
+
+fn is_delimiter(b: u8) -> bool {
+    b.is_ascii_whitespace() || b"()<>[]{}/%".contains(&b)
+}
+
+pub(crate) fn contains(data: &[u8], needle: &[u8]) -> bool {
+    find(data, needle, 0).is_some()
+}
+
+fn find(data: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    data.get(from..)?.windows(needle.len()).position(|w| w == needle).map(|p| from + p)
+}
+
+fn skip_whitespace(data: &[u8], mut i: usize) -> usize {
+    while i < data.len() && data[i].is_ascii_whitespace() { i += 1; }
+    i
+}
+
+fn trim_start(data: &[u8]) -> &[u8] {
+    &data[skip_whitespace(data, 0)..]
+}
+
+pub(crate) fn trim(data: &[u8]) -> &[u8] {
+    let data = trim_start(data);
+    let end = data.iter().rposition(|b| !b.is_ascii_whitespace()).map_or(0, |p| p + 1);
+    &data[..end]
+}
+
+/// `end` moved back over the EOL that precedes `endstream`
+fn trim_eol(data: &[u8], begin: usize, mut end: usize) -> usize {
+    if end > begin && data[end - 1] == b'\n' { end -= 1; }
+    if end > begin && data[end - 1] == b'\r' { end -= 1; }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A classic-xref PDF with an orphan, a duplicate and an unfiltered stream
+    fn sample() -> Vec<u8> {
+        let content = "BT /F1 12 Tf 72 712 Td (Hello) Tj ET\n".repeat(40);
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R /Resources << /Font << /F1 5 0 R /F2 6 0 R >> >> >>".to_string(),
+            format!("<< /Length {} >>\nstream\n{}\nendstream", content.len(), content),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+            "<< /Orphan (not referenced 9 0 R) >>".to_string(),
+        ];
+        for (i, object) in objects.iter().enumerate() {
+            pdf.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+        }
+        pdf.extend(b"xref\n0 8\ntrailer\n<< /Size 8 /Root 1 0 R >>\nstartxref\n0\n%%EOF\n");
+        pdf
+    }
+
+    #[test]
+    fn test_rewrite_drops_orphans_merges_duplicates_and_deflates() {
+        let input = sample();
+        let (out, stats) = rewrite(&input).unwrap();
+        assert_eq!(stats, QuickStats { objects_before: 7, objects_after: 5, streams_recompressed: 1 });
+        assert!(out.len() < input.len());
+        assert!(!contains(&out, b"/Orphan"));
+        assert!(contains(&out, b"/F1 5 0 R /F2 5 0 R"));
+        assert!(contains(&out, b"/Filter /FlateDecode"));
+
+        // The result parses back to the same content
+        let objects = parse_objects(&out);
+        let stream = objects[&(4, 0)].stream.as_ref().unwrap();
+        let mut plain = String::new();
+        ZlibDecoder::new(&stream[..]).read_to_string(&mut plain).unwrap();
+        assert!(plain.starts_with("BT /F1 12 Tf"));
+        assert_eq!(find_ref(&last_trailer(&out).unwrap(), b"/Root"), Some((1, 0)));
+        let (again, _) = rewrite(&out).unwrap();
+        assert_eq!(parse_objects(&again), objects);
+    }
+
+    #[test]
+    fn test_unsupported_structures_are_left_alone() {
+        assert!(rewrite(b"%PDF-1.5\n1 0 obj\n<< /Type /XRef >>\nendobj\n").is_none());
+        assert!(rewrite(b"not a pdf").is_none());
+    }
+
+    #[test]
+    fn test_refs_skip_strings() {
+        assert_eq!(refs(b"<< /A 1 0 R /B (2 0 R) /C [3 0 R 4 1 R] /D 5 0 >>"), [(1, 0), (3, 0), (4, 1)]);
+        assert_eq!(dict_value(b"<< /Length 10 0 R /Filter /FlateDecode >>", b"/Length"), Some(&b" 10 0 R"[..]));
+        assert_eq!(remove_entry(b"<< /Length 5 /Type /X >>", b"/Length"), b"<<  /Type /X >>");
+    }
+}
@@ -0,0 +1,161 @@
+//! Quality floors from how the output looks: `--min-ssim` and
+//! `--max-distance`.
+//!
+//! Probes are encoded with one tool per output format (ImageMagick for the
+//! quality-scaled formats, pngquant for PNG, Ghostscript for PDF) and each is
+//! scored against the input; the score is taken to improve with quality, so
+//! the lowest passing quality is found by bisection. Without a target that
+//! quality is the encode; with one it's a floor under the size search.
+
+use std::fs;
+use anyhow::Result;
+use crate::butteraugli;
+use crate::compression::{quality_dpi, run_gs, run_tool};
+use crate::exec::ToolCommand;
+use crate::format::Format;
+use crate::options::{CompressionOptions, MetadataMode};
+use crate::ssim;
+use crate::t;
+
+/// What a probe has to score to pass
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Criterion {
+    /// SSIM at least this
+    MinSsim(f64),
+    /// Butteraugli distance at most this
+    MaxDistance(f64),
+}
+
+impl Criterion {
+    /// Output formats the criterion can search a quality for
+    pub fn covers(self, format: Format) -> bool {
+        match self {
+            Criterion::MinSsim(_) => matches!(format, Format::Jpg | Format::Png | Format::Webp | Format::Avif | Format::Heic | Format::Pdf),
+            Criterion::MaxDistance(_) => matches!(format, Format::Jpg | Format::Webp),
+        }
+    }
+
+    fn score(self, input: &str, probe: &str, scratch: &str, opts: &CompressionOptions) -> Result<f64> {
+        match self {
+            Criterion::MinSsim(_) => ssim::measure(input, probe, scratch, opts),
+            Criterion::MaxDistance(_) => butteraugli::distance(input, probe, scratch, opts),
+        }
+    }
+
+    fn passes(self, score: f64) -> bool {
+        match self {
+            Criterion::MinSsim(min) => score >= min,
+            Criterion::MaxDistance(max) => score <= max,
+        }
+    }
+}
+
+impl std::fmt::Display for Criterion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Criterion::MinSsim(min) => write!(f, "SSIM >= {}", min),
+            Criterion::MaxDistance(max) => write!(f, "butteraugli <= {}", max),
+        }
+    }
+}
+
+/// Lowest quality (from `min_quality` up to 100) whose `format` output of
+/// `input` passes `criterion`; 100 when none does. `None` for formats the
+/// criterion doesn't cover.
+pub fn quality_floor(input: &str, output: &str, format: Format, criterion: Criterion, opts: &CompressionOptions) -> Result<Option<u64>> {
+    if !criterion.covers(format) {
+        return Ok(None);
+    }
+    let rep = opts.reporter.as_ref();
+    let score_path = opts.scratch_path(output, "score.tmp");
+    let score = |quality: u64| -> Result<Option<f64>> {
+        opts.cancel.check()?;
+        let probe = opts.scratch_path(output, &format!("look-q{}.tmp.{}", quality, format.extension()));
+        let score = match encode(input, &probe, format, quality, opts) {
+            Ok(true) => criterion.score(input, &probe, &score_path, opts).map(Some),
+            Ok(false) => Ok(None),
+            Err(e) => Err(e),
+        };
+        let _ = fs::remove_file(&probe);
+        let score = score?;
+        if opts.nerd {
+            let shown = score.map_or_else(|| "encode failed".to_string(), |s| format!("{:.4}", s));
+            rep.nerd_result(&format!("Quality {}", quality), &shown, false);
+        }
+        Ok(score)
+    };
+
+    let (mut lo, mut hi) = (opts.quality_floor(1), 100);
+    let mut best = None;
+    while lo <= hi {
+        let mid = lo + (hi - lo) / 2;
+        if score(mid)?.is_some_and(|s| criterion.passes(s)) {
+            best = Some(mid);
+            hi = mid - 1;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    if best.is_none() {
+        rep.warning(&t!("warning-no-quality-keeps", criterion = criterion));
+    }
+    Ok(Some(best.unwrap_or(100)))
+}
+
+/// One probe at `quality`; false when the tool failed
+fn encode(input: &str, dest: &str, format: Format, quality: u64, opts: &CompressionOptions) -> Result<bool> {
+    let mut cmd = match format {
+        Format::Pdf => return Ok(run_gs(input, dest, "/printer", Some(quality_dpi(quality)), opts).is_ok()),
+        Format::Png => {
+            let mut cmd = ToolCommand::new("pngquant");
+            cmd.arg("--quality").arg(format!("0-{}", quality)).arg("--force").arg("--output").arg(dest).arg(input);
+            cmd
+        },
+        _ => {
+            let mut cmd = ToolCommand::new("magick");
+            cmd.arg(input);
+            if format == Format::Jpg { cmd.arg("-sampling-factor").arg("4:4:4"); }
+            if opts.metadata == MetadataMode::Strip { cmd.arg("-strip"); }
+            cmd.arg("-quality").arg(quality.to_string()).arg(dest);
+            cmd
+        },
+    };
+    Ok(run_tool(opts, cmd.quiet_stderr(!opts.nerd))?.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::tests::{FakeRun, FakeTools};
+
+    /// Quality from a probe's name
+    fn probe_quality(path: &str) -> f64 {
+        path.rsplit("look-q").next().unwrap().split('.').next().unwrap().parse().unwrap()
+    }
+
+    #[test]
+    fn test_floor_is_lowest_quality_that_passes() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("photo.jpg");
+        fs::write(&input, vec![0u8; 100 * 1024]).unwrap();
+        let output = dir.path().join("crnched_photo.jpg").to_string_lossy().to_string();
+        // SSIM rises with quality, 0.9 at 0 to 1.0 at 100; butteraugli falls, 5.0 to 0.0
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "magick" if cmd.args.contains(&"-compare".to_string()) => FakeRun::Print(format!("{}", 0.9 + probe_quality(&cmd.args[1]) / 1000.0)),
+            "magick" => FakeRun::Write(20),
+            "butteraugli_main" => FakeRun::Print(format!("{}\n3-norm: 0.5\n", 5.0 - probe_quality(&cmd.args[1]) / 20.0)),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder().executor(tools.clone()).build();
+        let input = input.to_string_lossy().to_string();
+
+        assert_eq!(quality_floor(&input, &output, Format::Jpg, Criterion::MinSsim(0.975), &opts).unwrap(), Some(75));
+        assert_eq!(quality_floor(&input, &output, Format::Jpg, Criterion::MinSsim(0.9999), &opts).unwrap(), Some(100));
+        assert_eq!(quality_floor(&input, &output, Format::Jpg, Criterion::MaxDistance(1.0), &opts).unwrap(), Some(80));
+        assert_eq!(quality_floor(&input, &output, Format::Gif, Criterion::MinSsim(0.95), &opts).unwrap(), None);
+        assert_eq!(quality_floor(&input, &output, Format::Png, Criterion::MaxDistance(1.0), &opts).unwrap(), None);
+        let floored = CompressionOptions::builder().min_quality(Some(90)).executor(tools.clone()).build();
+        assert_eq!(quality_floor(&input, &output, Format::Jpg, Criterion::MinSsim(0.975), &floored).unwrap(), Some(90));
+        assert!(fs::read_dir(dir.path()).unwrap().all(|e| !e.unwrap().file_name().to_string_lossy().contains(".tmp")));
+    }
+}
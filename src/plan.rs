@@ -0,0 +1,226 @@
+//! Two-phase runs: `crnch plan` decides, `crnch apply` executes.
+//!
+//! A plan lists every file a rules file selects, with its target, output
+//! path and the SHA-256 it had when planned, as reviewable JSON. Applying
+//! refuses entries whose input changed since, so what was approved is what
+//! runs. Rules are TOML, first match wins, and unmatched files are left out:
+//!
+//! ```toml
+//! [[rule]]
+//! match = "scans/**/*.pdf"   # patterns without a '/' match the file name
+//! size = "2m"
+//! destructive = true         # accept grayscale/resize fallbacks
+//!
+//! [[rule]]
+//! match = "*.png"
+//! level = "high"
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use toml_edit::DocumentMut;
+use crate::{bmp, glob};
+use crate::compression::{compress_file, CompResult, CompressionLevel};
+use crate::decision::DecisionPolicy;
+use crate::format::Format;
+use crate::options::CompressionOptions;
+use crate::psd;
+use crate::utils::{self, SizeUnits, TargetSize};
+use crate::verify;
+
+const PLAN_VERSION: u32 = 1;
+
+/// One `[[rule]]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub pattern: String,
+    pub target: Option<TargetSize>,
+    pub level: Option<CompressionLevel>,
+    pub destructive: bool,
+}
+
+impl Rule {
+    pub fn matches(&self, relative: &Path) -> bool {
+        let path = relative.to_string_lossy().replace('\\', "/");
+        if self.pattern.contains('/') {
+            glob::matches(&self.pattern, &path)
+        } else {
+            let name = path.rsplit('/').next().unwrap_or(&path);
+            glob::matches(&self.pattern, name)
+        }
+    }
+}
+
+pub fn parse_rules(text: &str, units: SizeUnits) -> Result<Vec<Rule>> {
+    let doc: DocumentMut = text.parse()?;
+    let Some(rules) = doc.get("rule") else { return Err(anyhow!("No [[rule]] entries")) };
+    let rules = rules.as_array_of_tables().ok_or_else(|| anyhow!("rule must be an array of tables ([[rule]])"))?;
+    rules.iter().enumerate().map(|(i, table)| {
+        let string = |key: &str| -> Result<Option<&str>> {
+            match table.get(key) {
+                Some(value) => value.as_str().map(Some).ok_or_else(|| anyhow!("rule {}: {} must be a string", i + 1, key)),
+                None => Ok(None),
+            }
+        };
+        let pattern = string("match")?.ok_or_else(|| anyhow!("rule {}: match is required", i + 1))?.to_string();
+        let target = string("size")?.map(|s| TargetSize::parse_in(s, units)).transpose()
+            .with_context(|| format!("rule {}", i + 1))?;
+        let level = string("level")?.map(|s| {
+            <CompressionLevel as clap::ValueEnum>::from_str(s, true).map_err(|_| anyhow!("rule {}: level must be low, medium or high", i + 1))
+        }).transpose()?;
+        let destructive = match table.get("destructive") {
+            Some(value) => value.as_bool().ok_or_else(|| anyhow!("rule {}: destructive must be true or false", i + 1))?,
+            None => false,
+        };
+        if target.is_none() && level.is_none() {
+            return Err(anyhow!("rule {}: needs a size or a level", i + 1));
+        }
+        Ok(Rule { pattern, target, level, destructive })
+    }).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Compress,
+    /// Already under its target; listed so the reviewer sees it was considered
+    Skip,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanEntry {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub action: Action,
+    /// The `match` of the rule that selected the file
+    pub rule: String,
+    pub input_bytes: u64,
+    pub input_sha256: String,
+    pub target_kb: Option<u64>,
+    pub level: Option<CompressionLevel>,
+    /// Grayscale, resize and similar fallbacks are accepted
+    pub destructive: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plan {
+    pub version: u32,
+    pub created_at: u64,
+    pub root: PathBuf,
+    pub entries: Vec<PlanEntry>,
+}
+
+/// Plan every supported file under `root` that a rule selects. Outputs sit
+/// next to their inputs under the default name; earlier outputs are skipped.
+pub fn plan(root: &Path, rules: &[Rule]) -> Result<Plan> {
+    // Absolute, so the plan can be applied from any directory
+    let root = &std::path::absolute(root)?;
+    let mut entries = Vec::new();
+    for input in verify::outputs_in(root)? {
+        let name = input.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        if name.starts_with(utils::DEFAULT_OUTPUT_PREFIX) {
+            continue;
+        }
+        let relative = input.strip_prefix(root).unwrap_or(&input);
+        let Some(rule) = rules.iter().find(|r| r.matches(relative)) else { continue };
+        let input_bytes = std::fs::metadata(&input)?.len();
+        let target_kb = rule.target.map(|t| t.kb());
+        let mut output = input.with_file_name(utils::default_output_name(&input, utils::DEFAULT_OUTPUT_PREFIX, ""));
+        match Format::from_path(&name) {
+            Some(Format::Bmp) => { output.set_extension(bmp::output_format(&input).extension()); },
+            Some(Format::Raw) => { output.set_extension("jpg"); },
+            Some(Format::Tiff) => { output.set_extension("pdf"); },
+            Some(Format::Psd) => { output.set_extension(psd::output_format(&input).extension()); },
+            Some(Format::Comic) => { output.set_extension("cbz"); },
+            _ => {},
+        }
+        entries.push(PlanEntry {
+            action: if target_kb.is_some_and(|t| t >= input_bytes / 1024) { Action::Skip } else { Action::Compress },
+            input_sha256: verify::sha256_file(&input)?,
+            input,
+            output,
+            rule: rule.pattern.clone(),
+            input_bytes,
+            target_kb,
+            level: rule.level,
+            destructive: rule.destructive,
+        });
+    }
+    let created_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    Ok(Plan { version: PLAN_VERSION, created_at, root: root.to_path_buf(), entries })
+}
+
+impl Plan {
+    pub fn load(path: &Path) -> Result<Plan> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))?;
+        let plan: Plan = serde_json::from_str(&text).with_context(|| format!("{} is not a crnch plan", path.display()))?;
+        if plan.version != PLAN_VERSION {
+            return Err(anyhow!("Plan version {} is not supported (expected {}).", plan.version, PLAN_VERSION));
+        }
+        Ok(plan)
+    }
+}
+
+/// Run one entry with `base` plus the entry's target, level and decisions.
+/// An input that no longer matches its planned hash is refused.
+pub fn apply_entry(entry: &PlanEntry, base: &CompressionOptions) -> Result<CompResult> {
+    if verify::sha256_file(&entry.input).ok().as_ref() != Some(&entry.input_sha256) {
+        return Err(anyhow!("{} changed since it was planned; plan again.", entry.input.display()));
+    }
+    if Format::from_path(&entry.input.to_string_lossy()).is_none() {
+        return Err(anyhow!("{} is not a supported file.", entry.input.display()));
+    }
+    let mut opts = base.clone();
+    opts.target_kb = entry.target_kb;
+    opts.level = entry.level;
+    opts.decisions = if entry.destructive { DecisionPolicy::AlwaysAccept } else { DecisionPolicy::NeverDestructive };
+    compress_file(&entry.input.to_string_lossy(), &entry.output.to_string_lossy(), &opts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::compression::tests::{FakeRun, FakeTools};
+
+    const RULES: &str = "[[rule]]\nmatch = \"scans/**/*.pdf\"\nsize = \"100k\"\ndestructive = true\n\n[[rule]]\nmatch = \"*.png\"\nlevel = \"high\"\n";
+
+    #[test]
+    fn test_rules_and_globs() {
+        let rules = parse_rules(RULES, SizeUnits::Binary).unwrap();
+        assert_eq!(rules[0].target.map(|t| t.kb()), Some(100));
+        assert_eq!(rules[1].level, Some(CompressionLevel::High));
+        assert!(rules[0].matches(Path::new("scans/2024/a.pdf")));
+        assert!(rules[0].matches(Path::new("scans/a.pdf")));
+        assert!(!rules[0].matches(Path::new("other/a.pdf")));
+        assert!(rules[1].matches(Path::new("deep/dir/logo.png")));
+        assert!(!rules[1].matches(Path::new("logo.png.bak")));
+        assert!(parse_rules("[[rule]]\nmatch = \"*\"\n", SizeUnits::Binary).is_err());
+    }
+
+    #[test]
+    fn test_plan_then_apply_refuses_changed_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("scans")).unwrap();
+        fs::write(dir.path().join("scans/big.pdf"), vec![0u8; 300 * 1024]).unwrap();
+        fs::write(dir.path().join("scans/small.pdf"), vec![0u8; 50 * 1024]).unwrap();
+        fs::write(dir.path().join("photo.jpg"), vec![0u8; 10 * 1024]).unwrap();
+        let rules = parse_rules(RULES, SizeUnits::Binary).unwrap();
+
+        let plan = plan(dir.path(), &rules).unwrap();
+        let actions: Vec<_> = plan.entries.iter().map(|e| (e.input.file_name().unwrap().to_str().unwrap(), e.action)).collect();
+        assert_eq!(actions, [("big.pdf", Action::Compress), ("small.pdf", Action::Skip)]);
+        let json = serde_json::to_string(&plan).unwrap();
+        assert_eq!(serde_json::from_str::<Plan>(&json).unwrap(), plan);
+
+        let opts = CompressionOptions::builder().executor(FakeTools::new(|_| FakeRun::Write(80))).build();
+        let result = apply_entry(&plan.entries[0], &opts).unwrap();
+        assert_eq!(result.target_met, Some(true));
+        assert!(plan.entries[0].output.ends_with("scans/crnched_big.pdf"));
+
+        fs::write(dir.path().join("scans/big.pdf"), vec![1u8; 300 * 1024]).unwrap();
+        assert!(apply_entry(&plan.entries[0], &opts).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+//! `--fit`: where a file is going instead of how big it may be.
+//!
+//! Limits are the published per-file ones in decimal megabytes. Email
+//! attachments travel base64-encoded, a third bigger than the file, and every
+//! target keeps a margin below the limit for the platform's own rounding.
+
+use clap::ValueEnum;
+use crate::utils::TargetSize;
+
+/// Share of the limit a target leaves unused
+const MARGIN_PERCENT: u64 = 5;
+const MB: u64 = 1_000_000;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+pub enum Platform {
+    /// 25 MB per message, base64-encoded (Outlook, most providers)
+    Email,
+    /// 25 MB per message, base64-encoded
+    Gmail,
+    /// 16 MB for photos and videos
+    Whatsapp,
+    /// 10 MB without Nitro
+    Discord,
+    /// 1 GB per file
+    Slack,
+}
+
+impl Platform {
+    /// Published limit in bytes
+    pub fn limit_bytes(self) -> u64 {
+        match self {
+            Platform::Email | Platform::Gmail => 25 * MB,
+            Platform::Whatsapp => 16 * MB,
+            Platform::Discord => 10 * MB,
+            Platform::Slack => 1000 * MB,
+        }
+    }
+
+    /// The largest file that clears the limit with the margin to spare
+    pub fn target(self) -> TargetSize {
+        let limit = match self {
+            // Every 3 bytes go out as 4
+            Platform::Email | Platform::Gmail => self.limit_bytes() / 4 * 3,
+            _ => self.limit_bytes(),
+        };
+        TargetSize::from_bytes(limit - limit * MARGIN_PERCENT / 100)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_targets_clear_the_limits() {
+        assert_eq!(Platform::Whatsapp.target().bytes(), 15_200_000);
+        // 18.75 MB of file become 25 MB of message
+        assert_eq!(Platform::Gmail.target().bytes(), 17_812_500);
+        for platform in Platform::value_variants() {
+            assert!(platform.target().bytes() < platform.limit_bytes());
+        }
+    }
+}
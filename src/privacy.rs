@@ -0,0 +1,438 @@
+//! Finds identifying metadata in JPEG, PNG, PDF, WebP, GIF, HEIC and SVG files (`--privacy`).
+//!
+//! The scan reads the container structure itself rather than trusting the
+//! tools that stripped it, so the "0 identifying fields remain" report can be
+//! checked independently of them.
+
+use std::path::Path;
+use anyhow::Result;
+use serde::Serialize;
+use crate::format::Format;
+use crate::gif;
+use crate::ico;
+use crate::office;
+use crate::svg;
+use crate::webp;
+use crate::zip;
+
+/// Identifying fields found before the scrub and left after it
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PrivacyReport {
+    pub found: Vec<String>,
+    pub remaining: Vec<String>,
+}
+
+/// Identifying fields in the file at `path`, one human-readable label each
+pub fn scan(path: &Path, format: Format) -> Result<Vec<String>> {
+    scan_data(&std::fs::read(path)?, format)
+}
+
+fn scan_data(data: &[u8], format: Format) -> Result<Vec<String>> {
+    Ok(match format {
+        Format::Jpg => scan_jpeg(data),
+        Format::Png => scan_png(data),
+        Format::Pdf => scan_pdf(data),
+        Format::Webp => scan_webp(data),
+        Format::Heic | Format::Avif => scan_heic(data),
+        // Photoshop's XMP resource is a plain packet
+        Format::Psd => xmp_packet(data),
+        // Only PNG frames carry text chunks
+        Format::Ico => ico::frames(data).unwrap_or_default().iter().flat_map(|f| scan_png(&f.data)).collect(),
+        Format::Gif => scan_gif(data),
+        // No metadata fields in the format; converted outputs are scanned as what they became
+        Format::Bmp => Vec::new(),
+        // TIFF, and TIFF-based RAWs (CR2, NEF, ARW, DNG), are one big EXIF structure
+        Format::Raw | Format::Tiff => exif_fields(data),
+        Format::Office => scan_office(data)?,
+        Format::Zip | Format::Comic => scan_zip(data)?,
+        Format::Svg => scan_svg(&svg::decode(data)?),
+        Format::Video => scan_video(data),
+    })
+}
+
+fn scan_jpeg(data: &[u8]) -> Vec<String> {
+    let mut found = Vec::new();
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return found;
+    }
+    let mut pos = 2;
+    while pos + 4 <= data.len() && data[pos] == 0xFF {
+        let marker = data[pos + 1];
+        match marker {
+            0xFF => { pos += 1; continue; },
+            0x01 | 0xD0..=0xD7 => { pos += 2; continue; },
+            0xD9 | 0xDA => break,
+            _ => {},
+        }
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let Some(payload) = data.get(pos + 4..pos + 2 + len) else { break };
+        match marker {
+            0xE1 if payload.starts_with(b"Exif\0\0") => found.extend(exif_fields(&payload[6..])),
+            0xE1 if payload.starts_with(b"http://ns.adobe.com/xap/1.0/") => found.extend(xmp_fields(payload)),
+            0xED => found.push("IPTC/Photoshop metadata".to_string()),
+            0xFE => found.push("JPEG comment".to_string()),
+            _ => {},
+        }
+        pos += 2 + len;
+    }
+    found
+}
+
+fn scan_png(data: &[u8]) -> Vec<String> {
+    let mut found = Vec::new();
+    if !data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return found;
+    }
+    let mut pos = 8;
+    while pos + 8 <= data.len() {
+        let len = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        let Some(body) = data.get(pos + 8..pos + 8 + len) else { break };
+        match kind {
+            b"eXIf" => found.extend(exif_fields(body)),
+            b"tEXt" | b"zTXt" | b"iTXt" => {
+                let keyword = String::from_utf8_lossy(body.split(|&b| b == 0).next().unwrap_or_default()).to_string();
+                if keyword == "XML:com.adobe.xmp" {
+                    found.extend(xmp_fields(body));
+                } else {
+                    found.push(format!("PNG text '{}'", keyword));
+                }
+            },
+            b"IEND" => break,
+            _ => {},
+        }
+        pos += 12 + len;
+    }
+    found
+}
+
+fn scan_webp(data: &[u8]) -> Vec<String> {
+    let mut found = Vec::new();
+    for (kind, body) in webp::chunks(data) {
+        match kind {
+            b"EXIF" => found.extend(exif_fields(body.strip_prefix(b"Exif\0\0").unwrap_or(body))),
+            b"XMP " => found.extend(xmp_fields(body)),
+            _ => {},
+        }
+    }
+    found
+}
+
+fn scan_svg(text: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    if let Some(start) = text.find("<metadata") {
+        let block = &text[start..];
+        let block = &block[..block.find("</metadata>").unwrap_or(block.len())];
+        found.extend(xmp_fields(block.as_bytes()));
+    }
+    // Inkscape remembers where the file was exported to, home directory included
+    if text.contains("inkscape:export-filename") {
+        found.push("Inkscape export path".to_string());
+    }
+    found
+}
+
+/// Document properties naming people, and each embedded image's own fields
+fn scan_office(data: &[u8]) -> Result<Vec<String>> {
+    let Some(entries) = zip::entries(data) else { return Ok(Vec::new()) };
+    let mut found = office::people(&entries);
+    found.extend(scan_members(&entries, office::is_media)?);
+    Ok(found)
+}
+
+/// Each member's own fields, recursively for archives inside the archive
+fn scan_zip(data: &[u8]) -> Result<Vec<String>> {
+    match zip::entries(data) {
+        Some(entries) => scan_members(&entries, |name| Format::from_path(name).is_some()),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn scan_members(entries: &[zip::Entry], include: impl Fn(&str) -> bool) -> Result<Vec<String>> {
+    let mut found = Vec::new();
+    for entry in entries.iter().filter(|e| !e.is_dir() && include(&e.name)) {
+        let Some(format) = Format::from_path(&entry.name) else { continue };
+        found.extend(scan_data(&entry.data, format)?.into_iter().map(|f| format!("{}: {}", entry.name, f)));
+    }
+    Ok(found)
+}
+
+fn scan_gif(data: &[u8]) -> Vec<String> {
+    let mut found = Vec::new();
+    for block in gif::blocks(data).unwrap_or_default() {
+        match block {
+            gif::Block::Extension { label: 0xFE, .. } => found.push("GIF comment".to_string()),
+            gif::Block::Extension { label: 0xFF, data } if data.starts_with(b"XMP DataXMP") => found.extend(xmp_fields(&data)),
+            _ => {},
+        }
+    }
+    found
+}
+
+/// HEIF keeps EXIF and XMP as items whose payloads sit in `mdat`; Apple's
+/// EXIF items start with the JPEG-style `Exif\0\0` header
+fn scan_heic(data: &[u8]) -> Vec<String> {
+    let mut found = Vec::new();
+    if let Some(start) = data.windows(6).position(|w| w == b"Exif\0\0") {
+        found.extend(exif_fields(&data[start + 6..]));
+    }
+    found.extend(xmp_packet(data));
+    found
+}
+
+/// Fields of the first `<x:xmpmeta>` packet anywhere in the file
+fn xmp_packet(data: &[u8]) -> Vec<String> {
+    let text = String::from_utf8_lossy(data);
+    let Some(start) = text.find("<x:xmpmeta") else { return Vec::new() };
+    let packet = &text[start..];
+    xmp_fields(&packet.as_bytes()[..packet.find("</x:xmpmeta>").unwrap_or(packet.len())])
+}
+
+/// QuickTime user data and Apple metadata keys (MP4/MOV) and Matroska tags
+/// a phone or camera writes
+fn scan_video(data: &[u8]) -> Vec<String> {
+    const FIELDS: &[(&[&[u8]], &str)] = &[
+        (&[b"\xA9xyz", b"com.apple.quicktime.location.ISO6709", b"LOCATION"], "GPS location"),
+        (&[b"\xA9mak", b"com.apple.quicktime.make"], "camera make"),
+        (&[b"\xA9mod", b"com.apple.quicktime.model"], "camera model"),
+        (&[b"\xA9ART", b"\xA9aut", b"com.apple.quicktime.author", b"ARTIST"], "author"),
+    ];
+    FIELDS.iter()
+        .filter(|(keys, _)| keys.iter().any(|k| data.windows(k.len()).any(|w| w == *k)))
+        .map(|(_, label)| label.to_string())
+        .collect()
+}
+
+fn scan_pdf(data: &[u8]) -> Vec<String> {
+    let text = String::from_utf8_lossy(data);
+    let mut found = Vec::new();
+
+    // Document Info dictionaries, reached from the trailer's /Info reference
+    for reference in text.match_indices("/Info").map(|(i, _)| &text[i + 5..]) {
+        let Some(num) = reference.split_whitespace().next().and_then(|n| n.parse::<u32>().ok()) else { continue };
+        let header = format!("{} 0 obj", num);
+        let Some(dict) = text.match_indices(&header)
+            .find(|(i, _)| *i == 0 || !text.as_bytes()[i - 1].is_ascii_digit())
+            .map(|(i, _)| &text[i..])
+            .map(|obj| &obj[..obj.find("endobj").unwrap_or(obj.len())])
+        else { continue };
+        for key in ["Author", "Title", "Subject", "Keywords", "Creator"] {
+            if pdf_string_set(dict, key) {
+                found.push(format!("PDF /{}", key));
+            }
+        }
+    }
+
+    let mut rest = &text[..];
+    while let Some(start) = rest.find("<x:xmpmeta") {
+        let packet = &rest[start..];
+        let end = packet.find("</x:xmpmeta>").unwrap_or(packet.len());
+        found.extend(xmp_fields(&packet.as_bytes()[..end]));
+        rest = &packet[end..];
+    }
+
+    let embedded = data.windows(6).filter(|w| w == b"Exif\0\0").count();
+    if embedded > 0 {
+        found.push(format!("EXIF in {} embedded image(s)", embedded));
+    }
+    found
+}
+
+/// Whether `/key` in a PDF dictionary holds a non-empty string
+fn pdf_string_set(dict: &str, key: &str) -> bool {
+    let name = format!("/{}", key);
+    dict.match_indices(&name).any(|(i, _)| {
+        let value = dict[i + name.len()..].trim_start();
+        match value.chars().next() {
+            Some('(') => !value[1..].starts_with(')'),
+            Some('<') => !value[1..].trim_start().starts_with('>') && !value.starts_with("<<"),
+            _ => false,
+        }
+    })
+}
+
+fn xmp_fields(packet: &[u8]) -> Vec<String> {
+    let xml = String::from_utf8_lossy(packet);
+    let mut found = Vec::new();
+    if xml.contains("xmpMM:History") {
+        found.push("XMP edit history".to_string());
+    }
+    for (tag, label) in [("dc:creator", "XMP author"), ("pdf:Author", "XMP author"), ("xmp:CreatorTool", "XMP creator tool"), ("dc:rights", "XMP rights owner")] {
+        let open = format!("<{}>", tag);
+        let close = format!("</{}>", tag);
+        let Some(start) = xml.find(&open) else { continue };
+        let inner = &xml[start + open.len()..];
+        let inner = &inner[..inner.find(&close).unwrap_or(inner.len())];
+        let value = strip_tags(inner);
+        if !value.is_empty() && value != "UnknownApplication" && !found.iter().any(|f| f == label) {
+            found.push(label.to_string());
+        }
+    }
+    if found.is_empty() && xml.contains("xmpmeta") {
+        found.push("XMP metadata".to_string());
+    }
+    found
+}
+
+fn strip_tags(xml: &str) -> String {
+    let mut out = String::new();
+    let mut in_tag = false;
+    for c in xml.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => out.push(c),
+            _ => {},
+        }
+    }
+    out.trim().to_string()
+}
+
+/// Labels for the identifying tags in a TIFF-structured EXIF block
+fn exif_fields(tiff: &[u8]) -> Vec<String> {
+    const LABELS: [(u16, &str); 10] = [
+        (0x8825, "GPS location"),
+        (0xA431, "camera serial number"),
+        (0xA435, "lens serial number"),
+        (0xA430, "camera owner name"),
+        (0x013B, "artist"),
+        (0x8298, "copyright holder"),
+        (0xA420, "image unique ID"),
+        (0x927C, "maker notes"),
+        (0x9286, "user comment"),
+        (0x9003, "capture time"),
+    ];
+    let mut found = Vec::new();
+    let big_endian = match tiff.get(..2) {
+        Some(b"MM") => true,
+        Some(b"II") => false,
+        _ => return vec!["EXIF metadata".to_string()],
+    };
+    let u16_at = |at: usize| tiff.get(at..at + 2).map(|b| if big_endian { u16::from_be_bytes([b[0], b[1]]) } else { u16::from_le_bytes([b[0], b[1]]) });
+    let u32_at = |at: usize| tiff.get(at..at + 4).map(|b| {
+        let b = [b[0], b[1], b[2], b[3]];
+        if big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) }
+    });
+
+    let mut pending = vec![u32_at(4).unwrap_or(0) as usize];
+    let mut visited = Vec::new();
+    while let Some(ifd) = pending.pop() {
+        if ifd == 0 || visited.contains(&ifd) {
+            continue;
+        }
+        visited.push(ifd);
+        let Some(count) = u16_at(ifd) else { continue };
+        for i in 0..count as usize {
+            let entry = ifd + 2 + i * 12;
+            let Some(tag) = u16_at(entry) else { break };
+            if let Some((_, label)) = LABELS.iter().find(|(t, _)| *t == tag) {
+                if !found.iter().any(|f| f == label) {
+                    found.push(label.to_string());
+                }
+            }
+            // Exif sub-IFD holds the serials and owner name
+            if tag == 0x8769 {
+                pending.extend(u32_at(entry + 8).map(|o| o as usize));
+            }
+        }
+    }
+    if found.is_empty() {
+        found.push("EXIF metadata".to_string());
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Little-endian TIFF: IFD0 {Artist, ExifIFD -> {BodySerialNumber}, GPSInfo}
+    fn exif_block() -> Vec<u8> {
+        let mut t = b"II\x2a\x00\x08\x00\x00\x00".to_vec();
+        t.extend([3, 0]);
+        t.extend([0x3B, 0x01, 2, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        t.extend([0x69, 0x87, 4, 0, 1, 0, 0, 0, 50, 0, 0, 0]);
+        t.extend([0x25, 0x88, 4, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        t.extend([0, 0, 0, 0]);
+        t.extend([1, 0]);
+        t.extend([0x31, 0xA4, 2, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        t
+    }
+
+    #[test]
+    fn test_jpeg_exif_fields() {
+        let mut app1 = b"Exif\0\0".to_vec();
+        app1.extend(exif_block());
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        jpeg.extend(((app1.len() + 2) as u16).to_be_bytes());
+        jpeg.extend(app1);
+        jpeg.extend([0xFF, 0xFE, 0, 4, b'h', b'i', 0xFF, 0xDA]);
+
+        let found = scan_jpeg(&jpeg);
+        for field in ["artist", "GPS location", "camera serial number", "JPEG comment"] {
+            assert!(found.iter().any(|f| f == field), "{} missing from {:?}", field, found);
+        }
+        assert!(scan_jpeg(&[0xFF, 0xD8, 0xFF, 0xDA]).is_empty());
+    }
+
+    #[test]
+    fn test_png_text_chunks() {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        let body = b"Author\0Jane Doe";
+        png.extend((body.len() as u32).to_be_bytes());
+        png.extend(b"tEXt");
+        png.extend(body);
+        png.extend([0, 0, 0, 0]);
+        png.extend([0, 0, 0, 0]);
+        png.extend(b"IEND");
+        assert_eq!(scan_png(&png), vec!["PNG text 'Author'"]);
+    }
+
+    #[test]
+    fn test_pdf_info_and_xmp() {
+        let pdf = b"%PDF-1.4\n3 0 obj\n<< /Author (Jane Doe) /Title () /Producer (GPL Ghostscript) >>\nendobj\n\
+            4 0 obj\n<< /Title (Chapter 1) >>\nendobj\n\
+            <x:xmpmeta><dc:creator><rdf:Seq><rdf:li>Jane</rdf:li></rdf:Seq></dc:creator><xmp:CreatorTool>UnknownApplication</xmp:CreatorTool></x:xmpmeta>\n\
+            trailer << /Info 3 0 R >>";
+        assert_eq!(scan_pdf(pdf), vec!["PDF /Author", "XMP author"]);
+
+        let clean = b"%PDF-1.4\n3 0 obj\n<< /Author () /Producer (GPL Ghostscript) >>\nendobj\ntrailer << /Info 3 0 R >>";
+        assert!(scan_pdf(clean).is_empty());
+    }
+
+    #[test]
+    fn test_webp_chunks_and_strip() {
+        use crate::webp::{self, tests::webp, FLAG_EXIF, FLAG_XMP};
+        let xmp = b"<x:xmpmeta><dc:creator>Jane</dc:creator></x:xmpmeta>";
+        let data = webp(FLAG_EXIF | FLAG_XMP, &[(b"EXIF", &exif_block()), (b"XMP ", xmp)]);
+        let found = scan_webp(&data);
+        assert!(found.iter().any(|f| f == "GPS location"), "{:?}", found);
+        assert!(found.iter().any(|f| f == "XMP author"), "{:?}", found);
+        assert!(scan_webp(&webp::strip_metadata(&data).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_raw_is_scanned_as_tiff() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("DSC_0042.nef");
+        std::fs::write(&path, exif_block()).unwrap();
+        let found = scan(&path, Format::Raw).unwrap();
+        assert!(found.iter().any(|f| f == "camera serial number"), "{:?}", found);
+    }
+
+    #[test]
+    fn test_video_location_and_camera() {
+        let mut mov = b"\0\0\0\x14ftypqt  \0\0\0\0qt  ".to_vec();
+        mov.extend(b"\0\0\0\x1c\xA9xyz\0\x0c\x15\xc7+37.3349-122.0090/");
+        mov.extend(b"com.apple.quicktime.model\0iPhone 15");
+        assert_eq!(scan_data(&mov, Format::Video).unwrap(), vec!["GPS location", "camera model"]);
+        assert!(scan_video(b"\0\0\0\x08free").is_empty());
+    }
+
+    #[test]
+    fn test_svg_metadata_and_export_path() {
+        let svg = r#"<svg inkscape:export-filename="/home/jane/logo.png"><metadata><rdf:RDF><dc:creator>Jane</dc:creator></rdf:RDF></metadata></svg>"#;
+        assert_eq!(scan_svg(svg), vec!["XMP author", "Inkscape export path"]);
+        assert!(scan_svg(&crate::svg::minify(svg, None, false)).is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+//! `--probe`: the sizes a target can be set between, from one cheap encode
+//! at each end instead of a full search.
+//!
+//! The lossless pass says how far the file goes without losing anything, the
+//! most aggressive single setting (Ghostscript's `/screen`, pngquant at
+//! quality 30, JPEG quality 50) roughly where the searches bottom out before
+//! grayscale or resizing. Both are written to a private scratch folder.
+
+use std::fs;
+use std::path::Path;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use crate::compression::{run_gs, run_tool};
+use crate::exec::ToolCommand;
+use crate::format::Format;
+use crate::options::{CompressionOptions, MetadataMode};
+use crate::pdf_quick;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Probe {
+    pub input: String,
+    pub original_bytes: u64,
+    /// Lossless optimization, if it managed anything
+    pub lossless_bytes: Option<u64>,
+    /// The cheapest aggressive setting's output, or the lossless one when smaller
+    pub floor_bytes: u64,
+    /// What the floor was measured with
+    pub floor_method: String,
+}
+
+/// Probe `input` (PDF, PNG or JPG) at both ends; writes nothing next to it
+pub fn probe(input: &str, opts: &CompressionOptions) -> Result<Probe> {
+    let format = Format::from_path(input).filter(|f| matches!(f, Format::Pdf | Format::Png | Format::Jpg))
+        .ok_or_else(|| anyhow!("--probe covers PDF, PNG and JPG files, not {}.", input))?;
+    let original_bytes = fs::metadata(input)?.len();
+    let mut builder = tempfile::Builder::new();
+    builder.prefix("crnch-probe-");
+    let scratch = match opts.temp_dir {
+        Some(ref dir) => builder.tempdir_in(dir)?,
+        None => builder.tempdir()?,
+    };
+    let lossless = scratch.path().join(format!("lossless.{}", format.extension())).to_string_lossy().to_string();
+    let floor = scratch.path().join(format!("floor.{}", format.extension())).to_string_lossy().to_string();
+    let size = |path: &str| fs::metadata(path).map(|m| m.len()).ok().filter(|&len| len > 0);
+
+    opts.cancel.check()?;
+    let (lossless_bytes, floor_method) = match format {
+        Format::Pdf => {
+            let quick = pdf_quick::optimize(Path::new(input), Path::new(&lossless))?;
+            run_gs(input, &floor, "/screen", None, opts)?;
+            (quick.and_then(|_| size(&lossless)), "gs /screen".to_string())
+        },
+        Format::Png => {
+            let quality = opts.quality_floor(30);
+            run_tool(opts, ToolCommand::new("oxipng").arg("-o").arg("2").arg("--strip").arg("safe").arg("--quiet")
+                .arg("--out").arg(&lossless).arg(input))?;
+            opts.cancel.check()?;
+            run_tool(opts, ToolCommand::new("pngquant").arg("--quality").arg(format!("0-{}", quality))
+                .arg("--force").arg("--output").arg(&floor).arg(input))?;
+            (size(&lossless), format!("pngquant quality {}", quality))
+        },
+        _ => {
+            let quality = opts.quality_floor(50);
+            run_tool(opts, ToolCommand::new("jpegoptim").arg("--strip-all").arg("--stdout").arg(input)
+                .stdout_to(&lossless).quiet_stderr(true))?;
+            opts.cancel.check()?;
+            let mut cmd = ToolCommand::new("magick");
+            cmd.arg(input).arg("-sampling-factor").arg("4:4:4").arg("-quality").arg(quality.to_string());
+            if opts.metadata == MetadataMode::Strip { cmd.arg("-strip"); }
+            run_tool(opts, cmd.arg(&floor))?;
+            (size(&lossless), format!("JPEG quality {}", quality))
+        },
+    };
+    let floor_bytes = size(&floor).ok_or_else(|| anyhow!("The {} probe of {} failed.", floor_method, input))?;
+    Ok(Probe {
+        input: input.to_string(),
+        original_bytes,
+        lossless_bytes: lossless_bytes.filter(|&b| b < original_bytes),
+        floor_bytes: floor_bytes.min(lossless_bytes.unwrap_or(u64::MAX)).min(original_bytes),
+        floor_method,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::tests::{FakeRun, FakeTools};
+
+    #[test]
+    fn test_probes_both_ends_and_writes_nothing_beside_the_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("photo.jpg");
+        fs::write(&input, vec![0u8; 2000 * 1024]).unwrap();
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "jpegoptim" => FakeRun::Write(1900),
+            "magick" => FakeRun::Write(180),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder().min_quality(Some(60)).executor(tools.clone()).build();
+
+        let probe = probe(&input.to_string_lossy(), &opts).unwrap();
+        assert_eq!((probe.lossless_bytes, probe.floor_bytes), (Some(1900 * 1024), 180 * 1024));
+        assert_eq!(probe.floor_method, "JPEG quality 60");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        // A floor that fails is an error, not a 0 KB floor
+        let failing = CompressionOptions::builder().executor(FakeTools::new(|_| FakeRun::Fail)).build();
+        assert!(super::probe(&input.to_string_lossy(), &failing).is_err());
+        assert!(super::probe("notes.txt", &opts).is_err());
+    }
+}
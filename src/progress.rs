@@ -0,0 +1,119 @@
+use std::sync::{Arc, Mutex};
+use serde::Serialize;
+use crate::compression::{CompResult, StageRecord};
+
+/// One probe of a search loop (quality, scale or DPI) and what it produced
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Attempt {
+    pub stage: u32,
+    pub index: u32,
+    pub max: u32,
+    pub parameter: &'static str,
+    pub value: u64,
+    pub size_kb: u64,
+    pub target_kb: u64,
+    pub time_ms: u128,
+}
+
+impl Attempt {
+    pub fn hit(&self) -> bool {
+        self.size_kb <= self.target_kb
+    }
+}
+
+/// Receives progress events from the compression engines.
+///
+/// Every method has an empty default so embedders only implement what their
+/// UI needs. Events are delivered synchronously on the compressing thread,
+/// or on its probe threads when a high `--effort` searches PDFs in parallel.
+pub trait ProgressSink: Send + Sync {
+    /// A new stage of the waterfall has started
+    fn on_stage(&self, _number: u32, _name: &str) {}
+
+    /// A search iteration finished
+    fn on_attempt(&self, _attempt: &Attempt) {}
+
+    /// The engine gave up on the normal path and took a fallback
+    fn on_fallback(&self, _description: &str) {}
+
+    /// The engine finished and produced `result`
+    fn on_done(&self, _result: &CompResult) {}
+}
+
+/// Sink that ignores every event
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {}
+
+#[derive(Default)]
+pub(crate) struct RunLog {
+    pub stages: Vec<StageRecord>,
+    pub attempts: Vec<Attempt>,
+    pub fallbacks: Vec<String>,
+}
+
+/// Forwards events to an inner sink while keeping a copy for the CompResult
+pub(crate) struct Recorder {
+    inner: Arc<dyn ProgressSink>,
+    log: Mutex<RunLog>,
+}
+
+impl Recorder {
+    pub fn new(inner: Arc<dyn ProgressSink>) -> Self {
+        Recorder { inner, log: Mutex::new(RunLog::default()) }
+    }
+
+    pub fn take(&self) -> RunLog {
+        std::mem::take(&mut *self.log.lock().unwrap())
+    }
+}
+
+impl ProgressSink for Recorder {
+    fn on_stage(&self, number: u32, name: &str) {
+        self.log.lock().unwrap().stages.push(StageRecord { number, name: name.to_string() });
+        self.inner.on_stage(number, name);
+    }
+
+    fn on_attempt(&self, attempt: &Attempt) {
+        self.log.lock().unwrap().attempts.push(attempt.clone());
+        self.inner.on_attempt(attempt);
+    }
+
+    fn on_fallback(&self, description: &str) {
+        self.log.lock().unwrap().fallbacks.push(description.to_string());
+        self.inner.on_fallback(description);
+    }
+
+    fn on_done(&self, result: &CompResult) {
+        self.inner.on_done(result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recorder_collects_events() {
+        let recorder = Recorder::new(Arc::new(NoProgress));
+        recorder.on_stage(2, "Color Quantization");
+        recorder.on_attempt(&Attempt {
+            stage: 2,
+            index: 1,
+            max: 8,
+            parameter: "quality",
+            value: 65,
+            size_kb: 120,
+            target_kb: 100,
+            time_ms: 3,
+        });
+        recorder.on_fallback("grayscale");
+
+        let log = recorder.take();
+        assert_eq!(log.stages, vec![StageRecord { number: 2, name: "Color Quantization".to_string() }]);
+        assert_eq!(log.attempts.len(), 1);
+        assert!(!log.attempts[0].hit());
+        assert_eq!(log.fallbacks, vec!["grayscale".to_string()]);
+        assert!(recorder.take().attempts.is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+//! Photoshop header reading, to pick PNG or JPEG output for the flattened image.
+
+use std::path::Path;
+use crate::format::Format;
+
+/// The fixed 26-byte file header
+struct Header {
+    channels: u16,
+    width: u32,
+    height: u32,
+    /// Bitmap (0), grayscale (1), indexed (2), RGB (3), CMYK (4), ...
+    mode: u16,
+}
+
+fn header(data: &[u8]) -> Option<Header> {
+    // Version 1 is PSD, 2 the large-document PSB
+    if data.len() < 26 || !data.starts_with(b"8BPS") || !matches!(data[4..6], [0, 1] | [0, 2]) {
+        return None;
+    }
+    let u16_at = |p: usize| u16::from_be_bytes([data[p], data[p + 1]]);
+    let u32_at = |p: usize| u32::from_be_bytes([data[p], data[p + 1], data[p + 2], data[p + 3]]);
+    Some(Header { channels: u16_at(12), height: u32_at(14), width: u32_at(18), mode: u16_at(24) })
+}
+
+pub fn dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    header(data).map(|h| (h.width, h.height))
+}
+
+/// A channel beyond the color mode's own, which Photoshop stores as the
+/// merged image's transparency
+pub fn has_alpha(data: &[u8]) -> bool {
+    header(data).is_some_and(|h| {
+        let color = match h.mode {
+            3 | 9 => 3,
+            4 => 4,
+            _ => 1,
+        };
+        h.channels > color
+    })
+}
+
+/// What the PSD at `path` is written as: PNG when it has transparency, JPEG otherwise
+pub fn output_format(path: &Path) -> Format {
+    match std::fs::read(path) {
+        Ok(data) if !has_alpha(&data) && header(&data).is_some() => Format::Jpg,
+        _ => Format::Png,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A PSD header (version 1, 8-bit) with empty sections after it
+    pub(crate) fn psd(width: u32, height: u32, channels: u16, mode: u16) -> Vec<u8> {
+        let mut data = b"8BPS\0\x01".to_vec();
+        data.extend([0; 6]);
+        data.extend(channels.to_be_bytes());
+        data.extend(height.to_be_bytes());
+        data.extend(width.to_be_bytes());
+        data.extend(8u16.to_be_bytes());
+        data.extend(mode.to_be_bytes());
+        data.extend([0; 12]);
+        data
+    }
+
+    #[test]
+    fn test_header_and_alpha() {
+        let rgba = psd(1920, 1080, 4, 3);
+        assert_eq!(dimensions(&rgba), Some((1920, 1080)));
+        assert!(has_alpha(&rgba));
+        assert!(!has_alpha(&psd(800, 600, 3, 3)) && !has_alpha(&psd(800, 600, 4, 4)));
+        assert!(has_alpha(&psd(800, 600, 2, 1)));
+        assert!(dimensions(b"8BPS").is_none());
+    }
+}
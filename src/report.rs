@@ -0,0 +1,65 @@
+//! Output hooks for the engines.
+//!
+//! The library never prints. Human-facing text goes to a [`Reporter`] that the
+//! embedder injects through the options; the CLI's terminal implementation
+//! lives in its `logger` module.
+
+use crate::pdf_pages::PageSizes;
+
+/// A progress bar handed out by [`Reporter::progress`]
+pub trait ProgressBar: Send {
+    fn set(&mut self, current: u64);
+    fn finish(&self);
+    fn finish_with_message(&self, msg: &str);
+}
+
+/// Bar that draws nothing
+pub struct NoBar;
+
+impl ProgressBar for NoBar {
+    fn set(&mut self, _current: u64) {}
+    fn finish(&self) {}
+    fn finish_with_message(&self, _msg: &str) {}
+}
+
+/// Receives all human-readable output from the engines.
+///
+/// `nerd_*` methods carry the stage-by-stage technical detail and are only
+/// called when the options ask for nerd output. Every method defaults to
+/// doing nothing.
+#[allow(clippy::too_many_arguments)]
+pub trait Reporter: Send + Sync {
+    /// Plain informational line
+    fn message(&self, _msg: &str) {}
+
+    /// Something the user should notice (target missed, limits reached)
+    fn warning(&self, _msg: &str) {}
+
+    fn progress(&self, _total: u64, _message: &str) -> Box<dyn ProgressBar> {
+        Box::new(NoBar)
+    }
+
+    fn nerd_stage(&self, _stage_num: u32, _name: &str) {}
+
+    fn nerd_cmd(&self, _cmd_str: &str) {}
+
+    fn nerd_result(&self, _label: &str, _value: &str, _is_last: bool) {}
+
+    fn nerd_attempt(&self, _attempt: u32, _max: u32, _dpi: u64, _size_kb: u64, _target_kb: u64, _time_ms: u128, _action: &str) {}
+
+    fn nerd_quality_attempt(&self, _attempt: u32, _max: u32, _quality: u8, _size_kb: u64, _target_kb: u64, _time_ms: u128, _action: &str) {}
+
+    fn nerd_scale_attempt(&self, _attempt: u32, _max: u32, _scale: u8, _size_kb: u64, _target_kb: u64, _time_ms: u128, _action: &str) {}
+
+    fn nerd_search_range(&self, _min: u64, _max: u64, _mid: u64) {}
+
+    fn nerd_output_summary(&self, _input: &str, _output: &str, _old_kb: u64, _new_kb: u64, _method: &str, _time_s: f64) {}
+
+    /// Per-page sizes of a PDF before and, when the output can be read, after
+    fn nerd_pages(&self, _before: &PageSizes, _after: Option<&PageSizes>) {}
+}
+
+/// Reporter that discards everything; the library default
+pub struct SilentReporter;
+
+impl Reporter for SilentReporter {}
@@ -0,0 +1,86 @@
+//! Re-targeting crnch's own outputs.
+//!
+//! A portal rejects a file and it needs to get "just a bit smaller". When the
+//! file handed to crnch is an output the history knows, byte for byte, the
+//! run starts from that output's original (no second generation of loss)
+//! and searches below the setting that produced the rejected size instead
+//! of over the whole range again.
+
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::compression::CompResult;
+use crate::history::RunRecord;
+use crate::verify;
+
+/// The searched setting a run settled on, e.g. stage 2 `dpi` = 180
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Setting {
+    pub stage: u32,
+    pub parameter: String,
+    pub value: u64,
+}
+
+impl Setting {
+    /// Largest fitting probe of the last stage that had one
+    pub fn from_result(result: &CompResult) -> Option<Setting> {
+        result.attempts.iter()
+            .filter(|a| a.hit())
+            .max_by_key(|a| (a.stage, a.value))
+            .map(|a| Setting { stage: a.stage, parameter: a.parameter.to_string(), value: a.value })
+    }
+}
+
+/// How to redo an earlier output at a smaller target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Retarget {
+    /// File to compress: the earlier run's input if it's still there, else the output itself
+    pub source: PathBuf,
+    /// Upper bound for the search that produced the earlier output
+    pub prior: Option<Setting>,
+    pub record: RunRecord,
+}
+
+/// The earlier run that wrote `path`, if `path` is unchanged since and
+/// `target_kb` is below what that run produced
+pub fn find(path: &Path, target_kb: u64, records: &[RunRecord]) -> Option<Retarget> {
+    let record = verify::record_for(path, records)?;
+    let hash = verify::sha256_file(path).ok()?;
+    if record.output_sha256.as_ref() != Some(&hash) || target_kb >= record.final_kb {
+        return None;
+    }
+    let same_format = |p: &Path| p.extension().map(|e| e.to_ascii_lowercase()) == path.extension().map(|e| e.to_ascii_lowercase());
+    let source = if record.input.is_file() && same_format(&record.input) { record.input.clone() } else { path.to_path_buf() };
+    Some(Retarget { source, prior: record.setting.clone(), record: record.clone() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::Attempt;
+
+    #[test]
+    fn test_recognizes_unchanged_output_and_its_setting() {
+        let dir = tempfile::tempdir().unwrap();
+        let (input, output) = (dir.path().join("scan.pdf"), dir.path().join("crnched_scan.pdf"));
+        std::fs::write(&input, vec![1u8; 4096]).unwrap();
+        std::fs::write(&output, vec![2u8; 2048]).unwrap();
+        let attempt = |stage, value, size_kb| Attempt { stage, index: 1, max: 14, parameter: "dpi", value, size_kb, target_kb: 500, time_ms: 0 };
+        let result = CompResult {
+            final_kb: 480,
+            attempts: vec![attempt(2, 150, 520), attempt(2, 110, 470), attempt(2, 130, 480), attempt(1, 300, 100)],
+            ..CompResult::default()
+        };
+        let records = [RunRecord::new(&input.to_string_lossy(), &output.to_string_lossy(), &result)];
+
+        let retarget = find(&output, 400, &records).unwrap();
+        assert_eq!(retarget.source, input);
+        assert_eq!(retarget.prior, Some(Setting { stage: 2, parameter: "dpi".into(), value: 130 }));
+        assert!(find(&output, 500, &records).is_none());
+
+        // Edited since, or its original gone
+        std::fs::remove_file(&input).unwrap();
+        assert_eq!(find(&output, 400, &records).unwrap().source, output);
+        std::fs::write(&output, vec![3u8; 2048]).unwrap();
+        assert!(find(&output, 400, &records).is_none());
+    }
+}
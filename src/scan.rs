@@ -0,0 +1,279 @@
+//! `--scan-mode`: scanned pages that are really black and white become 1-bit
+//! CCITT Group 4 images before the PDF pipeline runs.
+//!
+//! Pages are classified from a low-resolution render: a page counts as bilevel
+//! when nearly all of it is close to black or white, and only if it is
+//! image-only. Typed pages pass the pixel test too, but have fonts and text
+//! operators, and keep their text as it is. Runs of bilevel pages are
+//! rendered at scan resolution and the other pages copied as they are, then
+//! joined in order. JBIG2 would be smaller still, but Ghostscript only decodes
+//! it and crnch doesn't drive jbig2enc.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use anyhow::{anyhow, Result};
+use crate::compression::{gs_password_arg, run_bilevel_pages, run_tool};
+use crate::exec::ToolCommand;
+use crate::merge::join;
+use crate::options::{BwMode, CompressionOptions};
+use crate::pdf_pages::{collect_pages, is_page_tree};
+use crate::pdf_quick::{dict_value, find_ref, inflate, is_page, read_objects, refs, trim, Id, Object};
+use crate::t;
+
+/// Resolution pages are classified at
+const DETECT_DPI: u64 = 36;
+/// Resolution bilevel pages are rendered at
+const SCAN_DPI: u64 = 300;
+/// Share of a page that has to be near black or white for it to count as bilevel
+const BILEVEL_SHARE: f64 = 0.97;
+
+/// `input` at `dest` with its bilevel pages in Group 4; false, and nothing
+/// written, when no page is bilevel
+pub(crate) fn encode_bilevel_pages(input: &str, dest: &str, opts: &CompressionOptions) -> Result<bool> {
+    let bilevel = classify(input, dest, opts)?;
+    let converted = bilevel.iter().filter(|&&b| b).count();
+    if opts.nerd {
+        opts.reporter.nerd_result("Scan mode", &format!("{} of {} pages bilevel", converted, bilevel.len()), converted == 0);
+    }
+    if converted == 0 {
+        return Ok(false);
+    }
+
+    let runs = runs(&bilevel);
+    let mut parts = Vec::new();
+    let mut written = || -> Result<()> {
+        for (index, &(bw, first, last)) in runs.iter().enumerate() {
+            opts.cancel.check()?;
+            let part = if runs.len() == 1 { dest.to_string() } else { opts.scratch_path(dest, &format!("scan-{}.tmp.pdf", index)) };
+            parts.push(part.clone());
+            let pages = if first == last { first.to_string() } else { format!("{}-{}", first, last) };
+            if bw {
+                let mut page_opts = opts.clone();
+                page_opts.pdf_pages = Some(pages);
+                page_opts.bw = Some(opts.bw.unwrap_or(BwMode::Threshold));
+                run_bilevel_pages(input, &part, SCAN_DPI, &page_opts)?;
+            } else {
+                copy_pages(input, &part, &pages, opts)?;
+            }
+        }
+        if runs.len() > 1 {
+            join(&parts, dest, opts)?;
+        }
+        Ok(())
+    };
+    let result = written();
+    for part in parts.iter().filter(|p| p.as_str() != dest) {
+        let _ = fs::remove_file(part);
+    }
+    if result.is_err() {
+        let _ = fs::remove_file(dest);
+    }
+    result.map(|_| true)
+}
+
+/// Whether each page, in order, is bilevel: image-only, and near black or white
+fn classify(input: &str, dest: &str, opts: &CompressionOptions) -> Result<Vec<bool>> {
+    let shares = bilevel_shares(input, dest, opts)?;
+    let image_only = fs::read(input).ok().and_then(|data| image_only_pages(&data)).filter(|pages| pages.len() == shares.len());
+    let Some(image_only) = image_only else {
+        opts.reporter.warning(&t!("warning-scan-unreadable"));
+        return Ok(vec![false; shares.len()]);
+    };
+    Ok(shares.into_iter().zip(image_only).map(|(share, image)| image && share >= BILEVEL_SHARE).collect())
+}
+
+/// Per page, the share of pixels near black or white
+fn bilevel_shares(input: &str, dest: &str, opts: &CompressionOptions) -> Result<Vec<f64>> {
+    let scratch = opts.scratch_path(dest, "scan-shares.tmp");
+    let mut cmd = ToolCommand::new("magick");
+    if let Some(password) = &opts.pdf_password {
+        cmd.arg("-authenticate").arg(password);
+    }
+    // Per page, the share of pixels within a quarter of black or white
+    cmd.arg("-density").arg(DETECT_DPI.to_string()).arg(input)
+        .arg("-colorspace").arg("Gray").arg("-fx").arg("u<0.25||u>0.75")
+        .arg("-format").arg("%[fx:mean]\n").arg("info:")
+        .stdout_to(&scratch).quiet_stderr(!opts.nerd);
+    let ran = run_tool(opts, &cmd)?.success();
+    let text = fs::read_to_string(&scratch).unwrap_or_default();
+    let _ = fs::remove_file(&scratch);
+    let shares: Option<Vec<f64>> = text.lines().map(|line| line.trim().parse().ok()).collect();
+    match shares {
+        Some(shares) if ran && !shares.is_empty() => Ok(shares),
+        _ => Err(anyhow!("ImageMagick could not read the pages of {}.", input)),
+    }
+}
+
+/// Whether each page, in order, draws no text: no fonts in its resources and
+/// no `BT` in its content or forms. `None` when the PDF can't be read.
+fn image_only_pages(data: &[u8]) -> Option<Vec<bool>> {
+    let (objects, trailer) = read_objects(data)?;
+    let catalog = objects.get(&find_ref(&trailer, b"/Root")?)?;
+    let mut pages = Vec::new();
+    collect_pages(&objects, find_ref(&catalog.body, b"/Pages")?, &mut HashSet::new(), &mut pages);
+    Some(pages.into_iter().map(|page| !has_text(&objects, page)).collect())
+}
+
+fn has_text(objects: &BTreeMap<Id, Object>, page: Id) -> bool {
+    let mut stack = vec![page];
+    // Resources inherited from the page tree
+    let mut node = objects.get(&page);
+    while let Some(object) = node.filter(|o| dict_value(&o.body, b"/Resources").is_none()) {
+        node = find_ref(&object.body, b"/Parent").and_then(|id| objects.get(&id));
+        if let Some(resources) = node.and_then(|o| dict_value(&o.body, b"/Resources")) {
+            stack.extend(refs(resources));
+        }
+    }
+    let contents: HashSet<Id> = objects.get(&page)
+        .and_then(|o| dict_value(&o.body, b"/Contents"))
+        .map(|v| refs(v).into_iter().collect())
+        .unwrap_or_default();
+    let mut seen = HashSet::new();
+    while let Some(id) = stack.pop() {
+        let Some(object) = objects.get(&id) else { continue };
+        if (id != page && (is_page(&object.body) || is_page_tree(&object.body))) || !seen.insert(id) {
+            continue;
+        }
+        let kind = |key: &[u8]| dict_value(&object.body, key).map(trim);
+        if kind(b"/Type") == Some(b"/Font") {
+            return true;
+        }
+        if object.stream.is_some() && (contents.contains(&id) || kind(b"/Subtype") == Some(b"/Form")) {
+            // Content we can't decode might hold text; keep the page as it is
+            let plain = if kind(b"/Filter").is_none() { object.stream.clone() } else { inflate(object) };
+            if plain.is_none_or(|plain| has_operator(&plain, b"BT")) {
+                return true;
+            }
+        }
+        stack.extend(refs(&object.body));
+    }
+    false
+}
+
+/// `op` as a whole token in a content stream
+fn has_operator(content: &[u8], op: &[u8]) -> bool {
+    content.windows(op.len()).enumerate().any(|(at, w)| {
+        w == op
+            && (at == 0 || content[at - 1].is_ascii_whitespace())
+            && content.get(at + op.len()).is_none_or(|b| b.is_ascii_whitespace())
+    })
+}
+
+/// Consecutive pages of one kind: (bilevel, first, last), from 1
+fn runs(bilevel: &[bool]) -> Vec<(bool, u32, u32)> {
+    let mut runs: Vec<(bool, u32, u32)> = Vec::new();
+    for (index, &bw) in bilevel.iter().enumerate() {
+        let page = index as u32 + 1;
+        match runs.last_mut() {
+            Some(run) if run.0 == bw => run.2 = page,
+            _ => runs.push((bw, page, page)),
+        }
+    }
+    runs
+}
+
+/// `pages` of `input` as they are, for the pages that stay in color or gray
+fn copy_pages(input: &str, dest: &str, pages: &str, opts: &CompressionOptions) -> Result<()> {
+    let mut cmd = ToolCommand::new("gs");
+    cmd.arg("-sDEVICE=pdfwrite").arg("-dNOPAUSE").arg("-dQUIET").arg("-dBATCH")
+        .args(gs_password_arg(opts))
+        .arg(format!("-sPageList={}", pages))
+        .arg(format!("-sOutputFile={}", dest))
+        .arg(input)
+        .quiet_stderr(!opts.nerd);
+    if !run_tool(opts, &cmd)?.success() {
+        return Err(anyhow!("Ghostscript could not copy pages {} of {}.", pages, input));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::tests::{FakeRun, FakeTools};
+
+    const SCAN_PAGE: &str = "<< /Type /Page /Parent 2 0 R /Resources << /XObject << /Im0 9 0 R >> >> /Contents 10 0 R >>";
+    const TEXT_PAGE: &str = "<< /Type /Page /Parent 2 0 R /Resources << /Font << /F1 11 0 R >> >> /Contents 12 0 R >>";
+
+    /// A three-page PDF, written to `dir`, with `pages` as objects 3 to 5
+    fn paperwork(dir: &std::path::Path, pages: [&str; 3]) -> String {
+        let image = "<< /Type /XObject /Subtype /Image /Width 1 /Height 1 /BitsPerComponent 8 /ColorSpace /DeviceGray /Length 1 >>\nstream\n\0\nendstream";
+        let draw = "<< /Length 31 >>\nstream\nq 612 0 0 792 0 0 cm /Im0 Do Q\nendstream";
+        let typed = "<< /Length 37 >>\nstream\nBT /F1 12 Tf 72 712 Td (Dear) Tj ET\nendstream";
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R 5 0 R] /Count 3 >>",
+            pages[0], pages[1], pages[2],
+            "", "", "",
+            image, draw,
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>", typed,
+        ];
+        let path = dir.join("paperwork.pdf");
+        fs::write(&path, crate::pdf_interactive::tests::pdf(&objects)).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_bilevel_runs_to_group4_rest_copied() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = paperwork(dir.path(), [SCAN_PAGE, SCAN_PAGE, SCAN_PAGE]);
+        let dest = dir.path().join("scan.tmp.pdf").to_string_lossy().to_string();
+        // Two scanned forms, then a photo
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "magick" if cmd.args.contains(&"-fx".to_string()) => FakeRun::Print("0.99\n0.985\n0.41\n".into()),
+            "magick" => FakeRun::Write(60),
+            "gs" => FakeRun::Write(200),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder().executor(tools.clone()).build();
+        assert!(encode_bilevel_pages(&input, &dest, &opts).unwrap());
+        assert_eq!(runs(&[true, true, false, true]), vec![(true, 1, 2), (false, 3, 3), (true, 4, 4)]);
+
+        let calls = tools.calls.lock().unwrap();
+        let group4 = calls.iter().find(|c| c.args.contains(&"Group4".to_string())).unwrap();
+        assert!(group4.args.contains(&format!("{}[0-1]", input)) && group4.args.contains(&"Bilevel".to_string()));
+        assert!(calls.iter().any(|c| c.args.contains(&"-sPageList=3".to_string())));
+        let joined = calls.last().unwrap();
+        assert!(joined.args.iter().filter(|a| a.contains("scan-")).count() == 2);
+        drop(calls);
+        assert!(fs::read_dir(dir.path()).unwrap().all(|e| !e.unwrap().file_name().to_string_lossy().contains("scan-")));
+
+        let photos = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "magick" => FakeRun::Print("0.3\n0.5\n".into()),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder().executor(photos.clone()).build();
+        assert!(!encode_bilevel_pages(&input, &dest, &opts).unwrap());
+        assert_eq!(photos.calls.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_typed_page_is_not_a_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        // A typed cover letter, a scanned form, then the letter's second page
+        let input = paperwork(dir.path(), [TEXT_PAGE, SCAN_PAGE, TEXT_PAGE]);
+        let dest = dir.path().join("scan.tmp.pdf").to_string_lossy().to_string();
+        let data = fs::read(&input).unwrap();
+        assert_eq!(image_only_pages(&data), Some(vec![false, true, false]));
+
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "magick" if cmd.args.contains(&"-fx".to_string()) => FakeRun::Print("0.99\n0.98\n0.99\n".into()),
+            "magick" => FakeRun::Write(60),
+            "gs" => FakeRun::Write(200),
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder().executor(tools.clone()).build();
+        assert!(encode_bilevel_pages(&input, &dest, &opts).unwrap());
+        let calls = tools.calls.lock().unwrap();
+        let group4: Vec<_> = calls.iter().filter(|c| c.args.contains(&"Group4".to_string())).collect();
+        assert_eq!(group4.len(), 1);
+        assert!(group4[0].args.contains(&format!("{}[1]", input)));
+        assert!(calls.iter().any(|c| c.args.contains(&"-sPageList=1".to_string())));
+        assert!(calls.iter().any(|c| c.args.contains(&"-sPageList=3".to_string())));
+        drop(calls);
+
+        // Text drawn without a font object still counts
+        assert!(has_operator(b"q BT /F1 9 Tf ET Q", b"BT"));
+        assert!(!has_operator(b"/BTX Do", b"BT"));
+    }
+}
@@ -0,0 +1,197 @@
+//! Screenshot detection for PNGs.
+//!
+//! Screenshots are flat color areas with sharp text edges. Dithered
+//! quantization smears those edges and costs bytes on the flat areas, so
+//! they get their own tuning in the PNG engine. A PNG counts as one when its
+//! tools say so in a text chunk, or when most pixels repeat their left
+//! neighbor exactly and there are few distinct colors, which photos never do.
+
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::Path;
+use flate2::read::ZlibDecoder;
+
+/// Pixels that must equal their left neighbor
+const MIN_FLAT_RATIO: f64 = 0.5;
+/// Distinct colors allowed
+const MAX_COLORS: usize = 16384;
+/// Rows decoded; plenty to tell, and bounds the work on huge images
+pub(crate) const MAX_ROWS: usize = 2048;
+
+pub fn looks_like_screenshot(path: &Path) -> bool {
+    std::fs::read(path).is_ok_and(|data| detect(&data))
+}
+
+fn detect(data: &[u8]) -> bool {
+    if !data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return false;
+    }
+    let mut header = None;
+    let mut idat = Vec::new();
+    let mut pos = 8;
+    while pos + 8 <= data.len() {
+        let len = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        let Some(body) = data.get(pos + 8..pos + 8 + len) else { break };
+        match &data[pos + 4..pos + 8] {
+            b"IHDR" if body.len() >= 13 => header = Some(body.to_vec()),
+            b"IDAT" => idat.extend_from_slice(body),
+            // gnome-screenshot, macOS ("Screenshot" in XMP), Windows Snipping Tool
+            b"tEXt" | b"iTXt" if contains_ignore_case(body, b"screenshot") => return true,
+            b"IEND" => break,
+            _ => {},
+        }
+        pos += 12 + len;
+    }
+    let Some(header) = header else { return false };
+    let width = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let (depth, color_type, interlace) = (header[8], header[9], header[12]);
+    let channels = match color_type {
+        0 | 3 => 1,
+        4 => 2,
+        2 => 3,
+        6 => 4,
+        _ => return false,
+    };
+    // Low bit depths are line art or palettes already
+    if depth < 8 {
+        return true;
+    }
+    if depth != 8 || interlace != 0 || width == 0 {
+        return false;
+    }
+    let stride = width * channels;
+    let mut raw = Vec::new();
+    let limit = (MAX_ROWS * (stride + 1)) as u64;
+    if ZlibDecoder::new(&idat[..]).take(limit).read_to_end(&mut raw).is_err() && raw.len() < stride + 1 {
+        return false;
+    }
+
+    let mut previous = vec![0u8; stride];
+    let mut row = vec![0u8; stride];
+    let mut counter = FlatCounter::default();
+    for line in raw.chunks_exact(stride + 1) {
+        if !unfilter(line[0], &line[1..], &previous, channels, &mut row) {
+            return false;
+        }
+        counter.add_row(&row, channels);
+        std::mem::swap(&mut previous, &mut row);
+    }
+    counter.is_flat()
+}
+
+/// Tallies decoded rows for the flat-content test; also used for BMP input
+#[derive(Default)]
+pub(crate) struct FlatCounter {
+    pixels: usize,
+    flat: usize,
+    colors: HashSet<Vec<u8>>,
+}
+
+impl FlatCounter {
+    pub(crate) fn add_row(&mut self, row: &[u8], channels: usize) {
+        for (x, pixel) in row.chunks_exact(channels).enumerate() {
+            self.pixels += 1;
+            if x > 0 && pixel == &row[(x - 1) * channels..x * channels] {
+                self.flat += 1;
+            } else if self.colors.len() <= MAX_COLORS {
+                self.colors.insert(pixel.to_vec());
+            }
+        }
+    }
+
+    pub(crate) fn is_flat(&self) -> bool {
+        self.pixels > 0 && self.flat as f64 / self.pixels as f64 >= MIN_FLAT_RATIO && self.colors.len() <= MAX_COLORS
+    }
+}
+
+/// Undo one row's PNG filter into `out`; false for an unknown filter
+fn unfilter(filter: u8, line: &[u8], previous: &[u8], bpp: usize, out: &mut [u8]) -> bool {
+    for i in 0..line.len() {
+        let left = if i >= bpp { out[i - bpp] } else { 0 };
+        let up = previous[i];
+        let up_left = if i >= bpp { previous[i - bpp] } else { 0 };
+        out[i] = line[i].wrapping_add(match filter {
+            0 => 0,
+            1 => left,
+            2 => up,
+            3 => ((left as u16 + up as u16) / 2) as u8,
+            4 => paeth(left, up, up_left),
+            _ => return false,
+        });
+    }
+    true
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
+}
+
+fn contains_ignore_case(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w.eq_ignore_ascii_case(needle))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Write;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+
+    fn chunk(kind: &[u8], body: &[u8]) -> Vec<u8> {
+        let mut out = (body.len() as u32).to_be_bytes().to_vec();
+        out.extend(kind);
+        out.extend(body);
+        // Nothing here checks CRCs
+        out.extend([0, 0, 0, 0]);
+        out
+    }
+
+    /// 8-bit RGB PNG from `pixel(x, y)`, every row unfiltered
+    pub(crate) fn rgb_png(width: u32, height: u32, pixel: impl Fn(u32, u32) -> [u8; 3]) -> Vec<u8> {
+        let mut raw = Vec::new();
+        for y in 0..height {
+            raw.push(0);
+            for x in 0..width {
+                raw.extend(pixel(x, y));
+            }
+        }
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(&raw).unwrap();
+        let mut ihdr = width.to_be_bytes().to_vec();
+        ihdr.extend(height.to_be_bytes());
+        ihdr.extend([8, 2, 0, 0, 0]);
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend(chunk(b"IHDR", &ihdr));
+        png.extend(chunk(b"IDAT", &encoder.finish().unwrap()));
+        png.extend(chunk(b"IEND", b""));
+        png
+    }
+
+    #[test]
+    fn test_flat_ui_is_a_screenshot_and_noise_is_not() {
+        // Title bar, white page, a few dark "text" runs
+        let ui = rgb_png(64, 48, |x, y| match (x, y) {
+            (_, 0..=7) => [40, 44, 52],
+            (8..=40, 20 | 21 | 30) if x % 3 != 0 => [20, 20, 20],
+            _ => [255, 255, 255],
+        });
+        assert!(detect(&ui));
+
+        let photo = rgb_png(64, 48, |x, y| {
+            let n = x.wrapping_mul(2_654_435_761).wrapping_add(y.wrapping_mul(40_503));
+            [(n >> 3) as u8, (n >> 11) as u8, (n >> 19) as u8]
+        });
+        assert!(!detect(&photo));
+    }
+
+    #[test]
+    fn test_software_tag_is_enough() {
+        let mut png = rgb_png(4, 4, |x, y| [(x * 60) as u8, (y * 60) as u8, 7]);
+        assert!(!detect(&png));
+        let end = png.len() - 12;
+        png.splice(end..end, chunk(b"tEXt", b"Software\0gnome-screenshot"));
+        assert!(detect(&png));
+    }
+}
@@ -0,0 +1,245 @@
+//! Strategies for finding the largest parameter (quality, DPI, ...) whose
+//! output still fits the target. Every strategy assumes output size grows
+//! with the parameter; a probe that fails counts as "too big". With a range
+//! target the first fit inside the range ends the search.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use anyhow::Result;
+
+/// Best fitting `(parameter, size_kb)` found and how many probes it took
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SearchResult {
+    pub best: Option<(u64, u64)>,
+    pub probes: u32,
+}
+
+/// Sizes a search is after: anything up to `max_kb` fits, and a fit of at
+/// least `min_kb` (a range target like 180k-220k) is good enough to stop at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window {
+    pub min_kb: Option<u64>,
+    pub max_kb: u64,
+}
+
+impl Window {
+    pub fn fits(self, size_kb: u64) -> bool {
+        size_kb <= self.max_kb
+    }
+
+    /// Inside the range, so no further probe is needed
+    pub fn settles(self, size_kb: u64) -> bool {
+        self.fits(size_kb) && self.min_kb.is_some_and(|min| size_kb >= min)
+    }
+
+    /// Size interpolation aims for: the middle of a range, else the limit
+    fn aim(self) -> u64 {
+        self.min_kb.map_or(self.max_kb, |min| min + self.max_kb.saturating_sub(min) / 2)
+    }
+}
+
+impl From<u64> for Window {
+    fn from(max_kb: u64) -> Self {
+        Window { min_kb: None, max_kb }
+    }
+}
+
+/// Search strategy, selectable so the alternatives can be benchmarked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    Binary,
+    Interpolation,
+    /// K-ary search probing this many points per round on separate threads
+    Parallel(usize),
+}
+
+impl Strategy {
+    pub fn run<F>(self, lo: u64, hi: u64, target: impl Into<Window>, max_probes: u32, probe: F) -> Result<SearchResult>
+    where
+        F: Fn(u32, u64) -> Result<Option<u64>> + Sync,
+    {
+        match self {
+            Strategy::Binary => binary(lo, hi, target, max_probes, probe),
+            Strategy::Interpolation => interpolation(lo, hi, target, max_probes, probe),
+            Strategy::Parallel(width) => parallel(lo, hi, target, max_probes, width, probe),
+        }
+    }
+}
+
+/// Classic bisection over `[lo, hi]`.
+///
+/// `probe(attempt, value)` returns the output size in KB, or `None` when the tool failed.
+pub fn binary<F>(mut lo: u64, mut hi: u64, target: impl Into<Window>, max_probes: u32, mut probe: F) -> Result<SearchResult>
+where
+    F: FnMut(u32, u64) -> Result<Option<u64>>,
+{
+    let window = target.into();
+    let mut result = SearchResult::default();
+    while lo <= hi && result.probes < max_probes {
+        result.probes += 1;
+        let mid = lo + (hi - lo) / 2;
+        match probe(result.probes, mid)? {
+            Some(size) if window.fits(size) => {
+                result.best = Some((mid, size));
+                if window.settles(size) {
+                    break;
+                }
+                lo = mid + 1;
+            },
+            _ => match mid.checked_sub(1) {
+                Some(h) => hi = h,
+                None => break,
+            },
+        }
+    }
+    Ok(result)
+}
+
+/// Guesses where the size curve crosses the target from the two bracketing
+/// probes, falling back to bisection whenever a guess doesn't halve the range.
+pub fn interpolation<F>(mut lo: u64, mut hi: u64, target: impl Into<Window>, max_probes: u32, mut probe: F) -> Result<SearchResult>
+where
+    F: FnMut(u32, u64) -> Result<Option<u64>>,
+{
+    let window = target.into();
+    let mut result = SearchResult::default();
+    let mut below: Option<(u64, u64)> = None;
+    let mut above: Option<(u64, u64)> = None;
+    let mut bisect = true;
+    while lo <= hi && result.probes < max_probes {
+        let width = hi - lo;
+        let guess = match (below, above) {
+            (Some((p0, s0)), Some((p1, s1))) if !bisect && s1 > s0 => {
+                p0 + (window.aim().saturating_sub(s0)) * (p1 - p0) / (s1 - s0)
+            },
+            _ => lo + width / 2,
+        };
+        let guess = guess.clamp(lo, hi);
+        result.probes += 1;
+        match probe(result.probes, guess)? {
+            Some(size) if window.fits(size) => {
+                result.best = Some((guess, size));
+                if window.settles(size) {
+                    break;
+                }
+                below = Some((guess, size));
+                lo = guess + 1;
+            },
+            outcome => {
+                if let Some(size) = outcome {
+                    above = Some((guess, size));
+                }
+                match guess.checked_sub(1) {
+                    Some(h) => hi = h,
+                    None => break,
+                }
+            },
+        }
+        bisect = hi.saturating_sub(lo) > width / 2;
+    }
+    Ok(result)
+}
+
+/// Probes `width` evenly spaced points per round concurrently, then narrows
+/// to the gap between the largest fit and the next miss.
+pub fn parallel<F>(mut lo: u64, mut hi: u64, target: impl Into<Window>, max_probes: u32, width: usize, probe: F) -> Result<SearchResult>
+where
+    F: Fn(u32, u64) -> Result<Option<u64>> + Sync,
+{
+    let window = target.into();
+    let mut result = SearchResult::default();
+    let counter = AtomicU32::new(0);
+    while lo <= hi && result.probes < max_probes {
+        let n = (width.max(1) as u64)
+            .min((max_probes - result.probes) as u64)
+            .min(hi - lo + 1);
+        let mut points: Vec<u64> = (0..n).map(|i| lo + (hi - lo) * (i + 1) / (n + 1)).collect();
+        points.dedup();
+
+        let outcomes: Vec<(u64, Result<Option<u64>>)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = points.iter()
+                .map(|&p| {
+                    let (probe, counter) = (&probe, &counter);
+                    scope.spawn(move || (p, probe(counter.fetch_add(1, Ordering::SeqCst) + 1, p)))
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().expect("probe thread panicked")).collect()
+        });
+        result.probes += outcomes.len() as u32;
+
+        let mut fit: Option<(u64, u64)> = None;
+        let mut sizes = Vec::with_capacity(outcomes.len());
+        for (p, outcome) in outcomes {
+            let size = outcome?;
+            if let Some(s) = size.filter(|&s| window.fits(s)) {
+                fit = Some((p, s));
+            }
+            sizes.push((p, size));
+        }
+        let miss = sizes.iter()
+            .find(|(p, size)| fit.is_none_or(|(fp, _)| *p > fp) && size.is_none_or(|s| !window.fits(s)))
+            .map(|(p, _)| *p);
+
+        if let Some((p, s)) = fit {
+            if result.best.is_none_or(|(bp, _)| p > bp) {
+                result.best = Some((p, s));
+            }
+            if window.settles(s) {
+                break;
+            }
+            lo = p + 1;
+        }
+        match miss.map(|p| p.checked_sub(1)) {
+            Some(Some(h)) => hi = h,
+            Some(None) => break,
+            None => {},
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Size grows 3 KB per step; the largest value fitting 600 KB is 200
+    fn linear(_: u32, value: u64) -> Result<Option<u64>> {
+        Ok(Some(value * 3))
+    }
+
+    #[test]
+    fn test_strategies_agree_on_largest_fit() {
+        for strategy in [Strategy::Binary, Strategy::Interpolation, Strategy::Parallel(4)] {
+            let result = strategy.run(72, 250, 600, 14, linear).unwrap();
+            assert_eq!(result.best, Some((200, 600)), "{:?}", strategy);
+            assert!(result.probes <= 14, "{:?} took {} probes", strategy, result.probes);
+        }
+    }
+
+    #[test]
+    fn test_failed_probes_count_as_misses() {
+        let flaky = |_: u32, value: u64| Ok(if value > 50 { None } else { Some(value) });
+        for strategy in [Strategy::Binary, Strategy::Interpolation, Strategy::Parallel(3)] {
+            let result = strategy.run(30, 100, 1000, 14, flaky).unwrap();
+            assert_eq!(result.best, Some((50, 50)), "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn test_range_stops_at_first_fit_inside() {
+        let window = Window { min_kb: Some(300), max_kb: 600 };
+        for strategy in [Strategy::Binary, Strategy::Interpolation, Strategy::Parallel(4)] {
+            let full = strategy.run(72, 250, 600, 14, linear).unwrap();
+            let ranged = strategy.run(72, 250, window, 14, linear).unwrap();
+            let (_, size) = ranged.best.unwrap();
+            assert!((300..=600).contains(&size), "{:?} settled on {} KB", strategy, size);
+            assert!(ranged.probes < full.probes, "{:?} took {} probes", strategy, ranged.probes);
+        }
+    }
+
+    #[test]
+    fn test_probe_errors_propagate() {
+        let failing = |_: u32, _: u64| -> Result<Option<u64>> { Err(anyhow::anyhow!("boom")) };
+        assert!(binary(1, 10, 5, 8, failing).is_err());
+        assert!(parallel(1, 10, 5, 8, 2, failing).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+//! `--split-max`: a PDF too big for one upload becomes numbered parts, each
+//! compressed under the limit.
+//!
+//! Split points come from cumulative page sizes (see
+//! [`pdf_pages`](crate::pdf_pages)), scaled by how far Ghostscript's `/screen`
+//! preset shrinks the whole file. A part of several pages that still misses
+//! the limit is halved and both halves redone; a single page that misses is
+//! kept as it came out.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use crate::compression::{compress_file, gs_password_arg, run_gs, run_tool, CompResult};
+use crate::decision::{Decision, DecisionPolicy};
+use crate::exec::ToolCommand;
+use crate::options::CompressionOptions;
+use crate::pdf_pages::{self, PageSizes};
+use crate::utils::{labeled_path, TargetSize};
+
+/// Share of the limit the plan fills, leaving room for estimates that run low
+const PLAN_HEADROOM: f64 = 0.9;
+
+/// One written part: its pages (from 1, inclusive) and how its compression went
+#[derive(Debug)]
+pub struct Part {
+    pub path: PathBuf,
+    pub pages: (u32, u32),
+    pub result: CompResult,
+}
+
+/// Compress `input` into parts of at most `limit` each, named after `output`
+/// with `_part1`, `_part2`, ...; one part keeps `output` as it is
+pub fn split(input: &str, output: &str, limit: TargetSize, opts: &CompressionOptions) -> Result<Vec<Part>> {
+    let sizes = match fs::read(input).ok().and_then(|data| pdf_pages::page_sizes(&data)) {
+        Some(sizes) => sizes,
+        // Unreadable structure: count the pages and spread the bytes evenly
+        None => {
+            let count = page_count(input, output, opts)?;
+            let each = fs::metadata(input)?.len() / u64::from(count.max(1));
+            PageSizes { pages: vec![each; count as usize], shared: 0 }
+        },
+    };
+    if sizes.pages.is_empty() {
+        return Err(anyhow!("{} has no pages to split.", input));
+    }
+    let ratio = floor_ratio(input, output, opts);
+    let mut pending: Vec<(u32, u32)> = plan(&sizes, ratio, limit.bytes()).into_iter().rev().collect();
+
+    let mut done = Vec::new();
+    while let Some((first, last)) = pending.pop() {
+        opts.cancel.check()?;
+        let dest = opts.scratch_path(output, &format!("pages{}-{}.tmp.pdf", first, last));
+        let result = compress_part(input, &dest, (first, last), limit, opts);
+        match result {
+            Ok(result) if result.target_met == Some(false) && first < last => {
+                let _ = fs::remove_file(&dest);
+                let middle = first + (last - first) / 2;
+                pending.push((middle + 1, last));
+                pending.push((first, middle));
+            },
+            Ok(result) => done.push(Part { path: PathBuf::from(dest), pages: (first, last), result }),
+            Err(e) => {
+                let _ = fs::remove_file(&dest);
+                for part in &done {
+                    let _ = fs::remove_file(&part.path);
+                }
+                return Err(e);
+            },
+        }
+    }
+
+    let count = done.len();
+    for (index, part) in done.iter_mut().enumerate() {
+        let path = if count == 1 { PathBuf::from(output) } else { labeled_path(Path::new(output), &format!("part{}", index + 1)) };
+        fs::rename(&part.path, &path)?;
+        part.path = path;
+    }
+    Ok(done)
+}
+
+/// One part at the limit. Parts of several pages decline every fallback but
+/// saving the `/screen` floor, so a miss shows up as a miss and gets halved.
+fn compress_part(input: &str, dest: &str, (first, last): (u32, u32), limit: TargetSize, opts: &CompressionOptions) -> Result<CompResult> {
+    let mut part_opts = opts.clone();
+    part_opts.set_target(limit);
+    part_opts.target_min_kb = None;
+    part_opts.pdf_pages = Some(if first == last { first.to_string() } else { format!("{}-{}", first, last) });
+    if first < last {
+        part_opts.decisions = DecisionPolicy::Custom(Arc::new(|decision| *decision == Decision::SaveFloor));
+    }
+    compress_file(input, dest, &part_opts)
+}
+
+/// Page ranges whose estimated output, pages times `ratio` plus the shared
+/// objects every part carries, stays inside the headroom of `limit_bytes`
+fn plan(sizes: &PageSizes, ratio: f64, limit_bytes: u64) -> Vec<(u32, u32)> {
+    let budget = limit_bytes as f64 * PLAN_HEADROOM;
+    let shared = sizes.shared as f64 * ratio;
+    let mut parts = Vec::new();
+    let (mut first, mut estimate) = (1, shared);
+    for (index, &bytes) in sizes.pages.iter().enumerate() {
+        let page = index as u32 + 1;
+        let cost = bytes as f64 * ratio;
+        if page > first && estimate + cost > budget {
+            parts.push((first, page - 1));
+            first = page;
+            estimate = shared;
+        }
+        estimate += cost;
+    }
+    parts.push((first, sizes.pages.len() as u32));
+    parts
+}
+
+/// How small the `/screen` preset gets the whole file, as a fraction of it;
+/// 1.0 when Ghostscript can't say
+fn floor_ratio(input: &str, output: &str, opts: &CompressionOptions) -> f64 {
+    let probe = opts.scratch_path(output, "split-floor.tmp.pdf");
+    let original = fs::metadata(input).map_or(0, |m| m.len());
+    let floor = run_gs(input, &probe, "/screen", None, opts).ok().and_then(|_| fs::metadata(&probe).ok()).map(|m| m.len());
+    let _ = fs::remove_file(&probe);
+    match floor {
+        Some(floor) if original > 0 => (floor as f64 / original as f64).min(1.0),
+        _ => 1.0,
+    }
+}
+
+/// Page count from Ghostscript, for files [`pdf_pages`] can't read
+fn page_count(input: &str, output: &str, opts: &CompressionOptions) -> Result<u32> {
+    let scratch = opts.scratch_path(output, "pagecount.tmp");
+    let mut cmd = ToolCommand::new("gs");
+    // SAFER keeps gs's file sandbox on; runpdfbegin only needs to read the input
+    cmd.arg("-q").arg("-dNODISPLAY").arg("-dSAFER").arg(format!("--permit-file-read={}", input)).arg("-dBATCH").args(gs_password_arg(opts))
+        .arg("-c").arg(format!("({}) (r) file runpdfbegin pdfpagecount = quit", input.replace('\\', "\\\\").replace('(', "\\(").replace(')', "\\)")))
+        .stdout_to(&scratch).quiet_stderr(!opts.nerd);
+    let ran = run_tool(opts, &cmd)?.success();
+    let count = fs::read_to_string(&scratch).ok().and_then(|text| text.trim().parse::<u32>().ok());
+    let _ = fs::remove_file(&scratch);
+    count.filter(|_| ran).ok_or_else(|| anyhow!("Ghostscript could not count the pages of {}.", input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::tests::{FakeRun, FakeTools};
+
+    #[test]
+    fn test_plan_by_cumulative_page_size() {
+        let sizes = PageSizes { pages: vec![100, 100, 400, 400, 100], shared: 50 };
+        // Half size after compression, 500 byte parts: 450 of room each
+        assert_eq!(plan(&sizes, 0.5, 500), vec![(1, 3), (4, 5)]);
+        assert_eq!(plan(&sizes, 1.0, 10_000), vec![(1, 5)]);
+        // A page over the budget on its own still gets a part
+        assert_eq!(plan(&PageSizes { pages: vec![900, 10], shared: 0 }, 1.0, 500), vec![(1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn test_parts_that_miss_are_halved() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("scans.pdf");
+        fs::write(&input, vec![0u8; 40 * 1024]).unwrap();
+        let output = dir.path().join("crnched_scans.pdf").to_string_lossy().to_string();
+        // 100 KB a page, whatever the DPI; no /screen estimate
+        let tools = FakeTools::new(|cmd| match cmd.program.as_str() {
+            "gs" if cmd.args.iter().any(|a| a.contains("pdfpagecount")) => FakeRun::Print("4\n".into()),
+            "gs" if cmd.args.iter().any(|a| a.starts_with("-sPageList=")) => {
+                let list = cmd.args.iter().find_map(|a| a.strip_prefix("-sPageList=")).unwrap();
+                let (first, last) = list.split_once('-').unwrap_or((list, list));
+                FakeRun::Write((last.parse::<u64>().unwrap() - first.parse::<u64>().unwrap() + 1) * 100)
+            },
+            _ => FakeRun::Fail,
+        });
+        let opts = CompressionOptions::builder().executor(tools.clone()).build();
+        let parts = split(&input.to_string_lossy(), &output, TargetSize::from_kb(250), &opts).unwrap();
+        assert_eq!(parts.iter().map(|p| p.pages).collect::<Vec<_>>(), vec![(1, 2), (3, 4)]);
+        assert!(parts.iter().all(|p| p.result.target_met == Some(true) && p.path.exists()));
+        assert!(parts[1].path.ends_with("crnched_scans_part2.pdf"));
+        assert!(fs::read_dir(dir.path()).unwrap().all(|e| !e.unwrap().file_name().to_string_lossy().contains(".tmp")));
+        // The count runs sandboxed, with only the input readable
+        let calls = tools.calls.lock().unwrap();
+        let count = calls.iter().find(|c| c.args.iter().any(|a| a.contains("pdfpagecount"))).unwrap();
+        assert!(count.args.contains(&"-dSAFER".to_string()) && !count.args.contains(&"-dNOSAFER".to_string()));
+        assert!(count.args.contains(&format!("--permit-file-read={}", input.display())));
+    }
+}
@@ -0,0 +1,30 @@
+//! SSIM of an output against its input, by ImageMagick (1.0 is identical);
+//! the `--min-ssim` criterion of [`perceptual`](crate::perceptual).
+
+use std::fs;
+use anyhow::{anyhow, Result};
+use crate::compression::run_tool;
+use crate::exec::ToolCommand;
+use crate::format::Format;
+use crate::options::CompressionOptions;
+
+/// Resolution PDF pages are rendered at for the comparison
+const PDF_COMPARE_DPI: u64 = 150;
+
+/// SSIM of `candidate` against `original`; PDFs compare their first page
+pub fn measure(original: &str, candidate: &str, scratch: &str, opts: &CompressionOptions) -> Result<f64> {
+    let mut cmd = ToolCommand::new("magick");
+    let page = |path: &str| match Format::from_path(path) {
+        Some(Format::Pdf) => format!("{}[0]", path),
+        _ => path.to_string(),
+    };
+    if Format::from_path(original) == Some(Format::Pdf) {
+        cmd.arg("-density").arg(PDF_COMPARE_DPI.to_string());
+    }
+    cmd.arg(page(original)).arg(page(candidate)).arg("-metric").arg("SSIM").arg("-compare")
+        .arg("-format").arg("%[distortion]").arg("info:").stdout_to(scratch).quiet_stderr(!opts.nerd);
+    let ran = run_tool(opts, &cmd)?.success();
+    let score = fs::read_to_string(scratch).ok().and_then(|text| text.trim().parse::<f64>().ok());
+    let _ = fs::remove_file(scratch);
+    score.filter(|_| ran).ok_or_else(|| anyhow!("ImageMagick could not compare {} with {}.", candidate, original))
+}
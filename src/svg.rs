@@ -0,0 +1,247 @@
+//! In-process SVG minification.
+//!
+//! One pass over the markup: comments, the XML declaration, a DOCTYPE without
+//! an internal subset, `<metadata>` and Inkscape/Sodipodi editor state are
+//! dropped, indentation between tags goes, and numbers in attribute values
+//! are rounded to a fixed number of decimals. Text content and CDATA are
+//! copied untouched. `.svgz` is the same document gzipped.
+
+use std::io::{Read, Write};
+use std::path::Path;
+use anyhow::Result;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+
+/// Editor namespaces whose elements and attributes are dropped
+const EDITOR_PREFIXES: &[&str] = &["inkscape:", "sodipodi:"];
+/// Namespaces only `<metadata>` uses
+const METADATA_NAMESPACES: &[&str] = &["xmlns:rdf", "xmlns:dc", "xmlns:cc"];
+/// Attribute values that hold names, not numbers
+const NAME_ATTRIBUTES: &[&str] = &["id", "class", "href", "xlink:href", "font-family"];
+
+/// The document at `path`, gunzipped when it's `.svgz`
+pub fn read(path: &Path) -> Result<String> {
+    decode(&std::fs::read(path)?)
+}
+
+/// `data` as text, gunzipped when it's `.svgz`
+pub fn decode(data: &[u8]) -> Result<String> {
+    Ok(String::from_utf8_lossy(&gunzip(data)?).into_owned())
+}
+
+/// Write `svg` to `path`, gzipped when the name ends in `.svgz`
+pub fn write(path: &Path, svg: &str) -> Result<()> {
+    let gzip = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("svgz"));
+    if !gzip {
+        return Ok(std::fs::write(path, svg)?);
+    }
+    let mut encoder = GzEncoder::new(std::fs::File::create(path)?, Compression::best());
+    encoder.write_all(svg.as_bytes())?;
+    encoder.finish()?;
+    Ok(())
+}
+
+fn gunzip(data: &[u8]) -> Result<Vec<u8>> {
+    if !data.starts_with(&[0x1F, 0x8B]) {
+        return Ok(data.to_vec());
+    }
+    let mut out = Vec::new();
+    GzDecoder::new(data).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+/// Whether the document (gzipped or not) ends with its closing `</svg>`
+pub fn is_complete(data: &[u8]) -> bool {
+    gunzip(data).is_ok_and(|text| String::from_utf8_lossy(&text).trim_end().ends_with("</svg>"))
+}
+
+/// `svg` minified, numbers rounded to `precision` decimals when given;
+/// `<metadata>` survives with `keep_metadata`
+pub fn minify(svg: &str, precision: Option<usize>, keep_metadata: bool) -> String {
+    let mut out = String::with_capacity(svg.len());
+    let mut rest = svg;
+    // Open elements being dropped, and open <text> elements (whitespace matters there)
+    let (mut skipping, mut in_text) = (0usize, 0usize);
+    while !rest.is_empty() {
+        let Some(lt) = rest.find('<') else {
+            if skipping == 0 && (in_text > 0 || !rest.trim().is_empty()) { out.push_str(rest); }
+            break;
+        };
+        let text = &rest[..lt];
+        if skipping == 0 && (in_text > 0 || !text.trim().is_empty()) {
+            out.push_str(text);
+        }
+        rest = &rest[lt..];
+
+        let (end, copy) = if rest.starts_with("<!--") {
+            (rest.find("-->").map_or(rest.len(), |i| i + 3), false)
+        } else if rest.starts_with("<![CDATA[") {
+            (rest.find("]]>").map_or(rest.len(), |i| i + 3), true)
+        } else if rest.starts_with("<?") {
+            (rest.find("?>").map_or(rest.len(), |i| i + 2), false)
+        } else if rest.starts_with("<!") {
+            // A DOCTYPE with an internal subset may declare entities the document uses
+            let end = rest.find('>').map_or(rest.len(), |i| i + 1);
+            match rest[..end].contains('[') {
+                true => (rest.find("]>").map_or(rest.len(), |i| i + 2), true),
+                false => (end, false),
+            }
+        } else {
+            let end = tag_end(rest);
+            let tag = &rest[..end];
+            let name = tag.trim_start_matches(['<', '/']).split(|c: char| c.is_whitespace() || c == '>' || c == '/').next().unwrap_or("");
+            let closing = tag.starts_with("</");
+            let self_closing = tag.ends_with("/>");
+            let dropped = (name == "metadata" && !keep_metadata) || EDITOR_PREFIXES.iter().any(|p| name.starts_with(p));
+            if skipping > 0 || dropped {
+                if closing { skipping = skipping.saturating_sub(1); } else if !self_closing { skipping += 1; }
+            } else if closing {
+                if name == "text" { in_text = in_text.saturating_sub(1); }
+                out.push_str("</");
+                out.push_str(name);
+                out.push('>');
+            } else {
+                if name == "text" && !self_closing { in_text += 1; }
+                write_tag(&mut out, name, &tag[1 + name.len()..], self_closing, precision, keep_metadata);
+            }
+            (end, false)
+        };
+        if copy && skipping == 0 {
+            out.push_str(&rest[..end]);
+        }
+        rest = &rest[end..];
+    }
+    out
+}
+
+/// End of the tag starting `s`, past its `>`; quoted `>` don't count
+fn tag_end(s: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '>') => return i + 1,
+            _ => {},
+        }
+    }
+    s.len()
+}
+
+/// `<name attrs>` with editor attributes dropped and numbers rounded
+fn write_tag(out: &mut String, name: &str, mut attrs: &str, self_closing: bool, precision: Option<usize>, keep_metadata: bool) {
+    out.push('<');
+    out.push_str(name);
+    loop {
+        attrs = attrs.trim_start();
+        let Some(eq) = attrs.find('=') else { break };
+        let key = attrs[..eq].trim();
+        let value_start = attrs[eq + 1..].trim_start();
+        let Some(quote) = value_start.chars().next().filter(|c| *c == '"' || *c == '\'') else { break };
+        let Some(len) = value_start[1..].find(quote) else { break };
+        let value = &value_start[1..1 + len];
+        attrs = &value_start[len + 2..];
+
+        let editor = EDITOR_PREFIXES.iter().any(|p| key.starts_with(p) || key == format!("xmlns:{}", p.trim_end_matches(':')));
+        if editor || (!keep_metadata && METADATA_NAMESPACES.contains(&key)) {
+            continue;
+        }
+        let value = match precision {
+            Some(p) if !NAME_ATTRIBUTES.contains(&key) && !key.starts_with("data-") && !key.starts_with("xmlns") => round_numbers(value, p),
+            _ => value.to_string(),
+        };
+        out.push(' ');
+        out.push_str(key);
+        out.push('=');
+        out.push(quote);
+        out.push_str(&value);
+        out.push(quote);
+    }
+    out.push_str(if self_closing { "/>" } else { ">" });
+}
+
+/// Every decimal number in `value` rounded to `precision` places, written
+/// short (`0.50` -> `.5`); integers, exponents and names are left alone
+fn round_numbers(value: &str, precision: usize) -> String {
+    let bytes = value.as_bytes();
+    let mut out = String::with_capacity(value.len());
+    let mut i = 0;
+    // Inside `#fff` or `url(#id)`, where digits aren't numbers. Path
+    // commands are letters too, so a letter alone doesn't start a name.
+    let mut in_name = false;
+    while i < bytes.len() {
+        let starts_number = bytes[i].is_ascii_digit() || (bytes[i] == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit));
+        match bytes[i] {
+            b'#' | b'_' => in_name = true,
+            b' ' | b',' | b';' | b':' | b'(' | b')' => in_name = false,
+            _ => {},
+        }
+        if !starts_number || in_name {
+            let len = value[i..].chars().next().map_or(1, char::len_utf8);
+            out.push_str(&value[i..i + len]);
+            i += len;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && bytes[i].is_ascii_digit() { i += 1; }
+        if i + 1 < bytes.len() && bytes[i] == b'.' && bytes[i + 1].is_ascii_digit() {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_digit() { i += 1; }
+        }
+        let number = &value[start..i];
+        let has_exponent = i < bytes.len() && matches!(bytes[i], b'e' | b'E');
+        match number.parse::<f64>() {
+            Ok(n) if number.contains('.') && !has_exponent => out.push_str(&short(n, precision)),
+            _ => out.push_str(number),
+        }
+    }
+    out
+}
+
+fn short(n: f64, precision: usize) -> String {
+    let mut s = format!("{:.*}", precision, n);
+    if s.contains('.') {
+        s = s.trim_end_matches('0').trim_end_matches('.').to_string();
+    }
+    match s.strip_prefix("0.") {
+        Some(frac) => format!(".{}", frac),
+        None if s.is_empty() => "0".to_string(),
+        None => s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minify_drops_editor_state_and_rounds() {
+        let svg = r##"<?xml version="1.0"?>
+<!-- Created with Inkscape -->
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:inkscape="http://www.inkscape.org" inkscape:version="1.2" viewBox="0 0 24.000 24">
+  <metadata><rdf:RDF><dc:creator>Jane</dc:creator></rdf:RDF></metadata>
+  <sodipodi:namedview id="nv" pagecolor="#ffffff"/>
+  <path id="p1.5" d="M0.12345 10.5L-3.14159 2e-3" fill="#1a2b3c"/>
+  <text x="1.25"> Hello <tspan>world</tspan></text>
+</svg>
+"##;
+        assert_eq!(
+            minify(svg, Some(2), false),
+            r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><path id="p1.5" d="M.12 10.5L-3.14 2e-3" fill="#1a2b3c"/><text x="1.25"> Hello <tspan>world</tspan></text></svg>"##
+        );
+        assert!(minify(svg, None, true).contains("<dc:creator>Jane</dc:creator>"));
+        assert!(minify(svg, None, true).contains("d=\"M0.12345 10.5L-3.14159 2e-3\""));
+    }
+
+    #[test]
+    fn test_svgz_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("icon.svgz");
+        write(&path, "<svg></svg>").unwrap();
+        let data = std::fs::read(&path).unwrap();
+        assert!(data.starts_with(&[0x1F, 0x8B]) && is_complete(&data));
+        assert_eq!(read(&path).unwrap(), "<svg></svg>");
+        assert!(!is_complete(b"<svg><path/>"));
+    }
+}
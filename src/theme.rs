@@ -0,0 +1,180 @@
+//! Colors and styles for terminal output, set by the `[theme]` config section.
+//!
+//! Output code names the *role* of a piece of text and the active theme
+//! decides how it looks, so light-background users can swap out the dim greys.
+
+use std::sync::OnceLock;
+use anyhow::{anyhow, Result};
+use colored::{Color, ColoredString, Colorize};
+use crnch::config::ThemeConfig;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Role {
+    /// Summary box and separator lines
+    Border,
+    /// Box titles
+    Title,
+    /// Field names such as "Input:" or "Size:"
+    Label,
+    /// Secondary text: paths, commands, hints
+    Muted,
+    /// Highlighted values: targets, methods, nerd box frames
+    Accent,
+    /// Emphasis that isn't good or bad news: stage numbers, file types
+    Highlight,
+    Success,
+    Warning,
+    Error,
+}
+
+const ROLES: [(&str, Role); 9] = [
+    ("border", Role::Border),
+    ("title", Role::Title),
+    ("label", Role::Label),
+    ("muted", Role::Muted),
+    ("accent", Role::Accent),
+    ("highlight", Role::Highlight),
+    ("success", Role::Success),
+    ("warning", Role::Warning),
+    ("error", Role::Error),
+];
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Style {
+    color: Option<Color>,
+    bold: bool,
+    dimmed: bool,
+    underline: bool,
+}
+
+impl Style {
+    /// Parse a spec like `"bold bright-blue"`; `"none"` means unstyled
+    pub fn parse(spec: &str) -> Result<Style> {
+        let mut style = Style::default();
+        for token in spec.split_whitespace() {
+            match token {
+                "none" | "plain" => {},
+                "bold" => style.bold = true,
+                "dim" | "dimmed" => style.dimmed = true,
+                "underline" => style.underline = true,
+                color => {
+                    let parsed = color.replace(['-', '_'], " ").parse()
+                        .map_err(|_| anyhow!("Unknown color or style '{}'", color))?;
+                    style.color = Some(parsed);
+                },
+            }
+        }
+        Ok(style)
+    }
+
+    fn apply(self, text: &str) -> ColoredString {
+        let mut out = text.normal();
+        if let Some(color) = self.color {
+            out = out.color(color);
+        }
+        if self.bold {
+            out = out.bold();
+        }
+        if self.dimmed {
+            out = out.dimmed();
+        }
+        if self.underline {
+            out = out.underline();
+        }
+        out
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    styles: [Style; ROLES.len()],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::from_specs(["dimmed", "cyan bold", "dimmed", "dimmed", "cyan", "yellow", "green", "yellow", "red"])
+    }
+}
+
+impl Theme {
+    /// No dimming, bold text and colors that read on light and dark backgrounds
+    pub fn high_contrast() -> Self {
+        Theme::from_specs(["bold", "bold", "bold", "none", "bold blue", "bold", "bold green", "bold magenta", "bold red"])
+    }
+
+    /// No colors at all
+    pub fn plain() -> Self {
+        Theme { styles: [Style::default(); ROLES.len()] }
+    }
+
+    pub fn preset(name: &str) -> Result<Self> {
+        match name {
+            "default" => Ok(Theme::default()),
+            "high-contrast" => Ok(Theme::high_contrast()),
+            "plain" | "none" => Ok(Theme::plain()),
+            _ => Err(anyhow!("Unknown theme preset '{}' (expected default, high-contrast or plain)", name)),
+        }
+    }
+
+    /// Start from the configured preset, then apply per-role overrides
+    pub fn from_config(config: &ThemeConfig) -> Result<Self> {
+        let mut theme = Theme::preset(config.preset.as_deref().unwrap_or("default"))?;
+        for (name, spec) in &config.styles {
+            let role = ROLES.iter().find(|(n, _)| n == name).map(|(_, r)| *r)
+                .ok_or_else(|| anyhow!("Unknown theme role '{}'", name))?;
+            theme.styles[role as usize] = Style::parse(spec)?;
+        }
+        Ok(theme)
+    }
+
+    fn from_specs(specs: [&str; ROLES.len()]) -> Self {
+        Theme { styles: specs.map(|spec| Style::parse(spec).expect("built-in theme")) }
+    }
+
+    pub fn style(&self, role: Role) -> Style {
+        self.styles[role as usize]
+    }
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Install the theme for the rest of the run; only the first call counts
+pub fn init(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+/// Style text by role with the active theme
+pub trait Paint {
+    fn paint(&self, role: Role) -> ColoredString;
+}
+
+impl<T: AsRef<str> + ?Sized> Paint for T {
+    fn paint(&self, role: Role) -> ColoredString {
+        THEME.get_or_init(Theme::default).style(role).apply(self.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_style_parse() {
+        let style = Style::parse("bold bright-blue").unwrap();
+        assert_eq!(style, Style { color: Some(Color::BrightBlue), bold: true, ..Style::default() });
+        assert_eq!(Style::parse("none").unwrap(), Style::default());
+        assert!(Style::parse("sparkly").is_err());
+    }
+
+    #[test]
+    fn test_overrides_apply_on_top_of_preset() {
+        let mut config = ThemeConfig { preset: Some("high-contrast".to_string()), ..ThemeConfig::default() };
+        config.styles.insert("border".to_string(), "none".to_string());
+        let theme = Theme::from_config(&config).unwrap();
+        assert_eq!(theme.style(Role::Border), Style::default());
+        assert_eq!(theme.style(Role::Success), Theme::high_contrast().style(Role::Success));
+
+        config.styles.insert("sparkles".to_string(), "red".to_string());
+        assert!(Theme::from_config(&config).is_err());
+    }
+}
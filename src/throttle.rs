@@ -0,0 +1,104 @@
+//! Rate-limited file copies for runs against network shares (`--io-limit`).
+//!
+//! With a [`Throttle`] set, the engines work on a local copy of the input and
+//! the only traffic to the share is one throttled read and one throttled write.
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use anyhow::{anyhow, Result};
+use crate::cancel::CancellationToken;
+use crate::utils::TargetSize;
+
+const CHUNK: usize = 64 * 1024;
+
+/// Copies one file at a time, optionally capped at a byte rate
+#[derive(Debug, Default)]
+pub struct Throttle {
+    bytes_per_sec: Option<u64>,
+    busy: Mutex<()>,
+}
+
+impl Throttle {
+    /// `None` keeps copies sequential without limiting their speed
+    pub fn new(bytes_per_sec: Option<u64>) -> Self {
+        Throttle { bytes_per_sec, busy: Mutex::new(()) }
+    }
+
+    pub fn bytes_per_sec(&self) -> Option<u64> {
+        self.bytes_per_sec
+    }
+
+    /// Copy `from` to `to`, sleeping as needed to stay under the rate
+    pub fn copy(&self, from: &Path, to: &Path, cancel: &CancellationToken) -> Result<u64> {
+        let _turn = self.busy.lock().unwrap_or_else(|e| e.into_inner());
+        let mut reader = File::open(from)?;
+        let mut writer = File::create(to)?;
+        let chunk = match self.bytes_per_sec {
+            Some(rate) => (rate as usize / 10).clamp(1, CHUNK),
+            None => CHUNK,
+        };
+        let mut buf = vec![0u8; chunk];
+        let start = Instant::now();
+        let mut copied = 0u64;
+        loop {
+            cancel.check()?;
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            writer.write_all(&buf[..n])?;
+            copied += n as u64;
+            if let Some(rate) = self.bytes_per_sec {
+                let due = Duration::from_secs_f64(copied as f64 / rate as f64);
+                if let Some(wait) = due.checked_sub(start.elapsed()) {
+                    std::thread::sleep(wait);
+                }
+            }
+        }
+        writer.flush()?;
+        Ok(copied)
+    }
+}
+
+/// Parse a rate such as `50MB/s`, `512k` or `1.5m/s`
+pub fn parse_rate(input: &str) -> Result<u64> {
+    let s = input.trim();
+    let size = s.strip_suffix("/s").unwrap_or(s);
+    let bytes = size.parse::<TargetSize>()
+        .map_err(|e| anyhow!("Invalid I/O rate '{}': {}. Example: 50MB/s", input, e))?
+        .bytes();
+    if bytes == 0 {
+        return Err(anyhow!("I/O rate must be greater than 0. Example: 50MB/s"));
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("50MB/s").unwrap(), 50_000_000);
+        assert_eq!(parse_rate("50MiB/s").unwrap(), 50 * 1024 * 1024);
+        assert_eq!(parse_rate("512k").unwrap(), 512 * 1024);
+        assert!(parse_rate("0k/s").is_err());
+        assert!(parse_rate("fast").is_err());
+    }
+
+    #[test]
+    fn test_copy_respects_rate() {
+        let dir = tempfile::tempdir().unwrap();
+        let (from, to) = (dir.path().join("a"), dir.path().join("b"));
+        std::fs::write(&from, vec![7u8; 100 * 1024]).unwrap();
+
+        let start = Instant::now();
+        let copied = Throttle::new(Some(500 * 1024)).copy(&from, &to, &CancellationToken::new()).unwrap();
+        assert_eq!(copied, 100 * 1024);
+        assert!(start.elapsed() >= Duration::from_millis(180), "{:?}", start.elapsed());
+        assert_eq!(std::fs::read(&to).unwrap(), std::fs::read(&from).unwrap());
+    }
+}
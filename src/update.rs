@@ -0,0 +1,188 @@
+//! `crnch self-update`: replace the running binary with the latest GitHub release.
+//!
+//! Every release archive ships with a `<archive>.sha256` next to it; the
+//! download is refused if that file is missing or doesn't match.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use anyhow::{Context, Result, anyhow};
+use colored::*;
+use crate::theme::{Paint, Role};
+use crnch::t;
+use self_update::backends::github::ReleaseList;
+use self_update::update::{Release, ReleaseAsset};
+use sha2::{Digest, Sha256};
+
+const REPO_OWNER: &str = "KartikHalkunde";
+const REPO_NAME: &str = "crnch";
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Set to any value to disable the background release check
+pub const NO_CHECK_ENV: &str = "CRNCH_NO_UPDATE_CHECK";
+const CHECK_INTERVAL_SECS: u64 = 24 * 60 * 60;
+/// How long the notice may hold up exit once the summary is printed
+const NOTICE_WAIT: Duration = Duration::from_millis(300);
+
+/// Newest published release
+pub fn latest_release() -> Result<Release> {
+    let releases = ReleaseList::configure()
+        .repo_owner(REPO_OWNER)
+        .repo_name(REPO_NAME)
+        .build()?
+        .fetch()?;
+    releases.into_iter().next().ok_or_else(|| anyhow!("No releases published yet."))
+}
+
+/// True when `latest` is newer than the running binary
+pub fn is_newer(latest: &str) -> bool {
+    self_update::version::bump_is_greater(CURRENT_VERSION, latest).unwrap_or(false)
+}
+
+pub fn self_update(check_only: bool, yes: bool) -> Result<()> {
+    let release = latest_release().context("Cannot reach GitHub releases")?;
+    if !is_newer(&release.version) {
+        outln!("{} {}", "✓".paint(Role::Success), t!("update-current", version = CURRENT_VERSION));
+        return Ok(());
+    }
+    outln!("{}", t!("update-available", version = release.version.paint(Role::Success).bold(), installed = CURRENT_VERSION));
+    if check_only {
+        return Ok(());
+    }
+
+    let target = self_update::get_target();
+    let asset = release.asset_for(target, None)
+        .ok_or_else(|| anyhow!("Release {} has no build for {}.", release.version, target))?;
+    let checksum = release.assets.iter()
+        .find(|a| a.name == format!("{}.sha256", asset.name))
+        .ok_or_else(|| anyhow!(
+            "Release {} has no checksum for {}; refusing to install an unverified binary.",
+            release.version, asset.name
+        ))?;
+
+    let exe = std::env::current_exe()?;
+    if !yes {
+        let confirmed = dialoguer::Confirm::new()
+            .with_prompt(t!("prompt-update", file = exe.display(), version = release.version))
+            .default(true)
+            .interact()?;
+        if !confirmed {
+            outln!("{}", t!("update-cancelled"));
+            return Ok(());
+        }
+    }
+
+    let tmp = tempfile::tempdir()?;
+    let archive = tmp.path().join(&asset.name);
+    download(&asset, fs::File::create(&archive)?)?;
+    let mut expected = Vec::new();
+    download(checksum, &mut expected)?;
+    verify_checksum(&archive, &String::from_utf8_lossy(&expected))?;
+
+    let bin_name = format!("crnch{}", std::env::consts::EXE_SUFFIX);
+    self_update::Extract::from_source(&archive).extract_file(tmp.path(), &bin_name)?;
+    self_update::self_replace::self_replace(tmp.path().join(&bin_name))
+        .with_context(|| format!("Cannot replace {}", exe.display()))?;
+
+    outln!("{} {}", "✓".paint(Role::Success), t!("update-done", version = release.version));
+    Ok(())
+}
+
+// ==================== UPDATE NOTICE ====================
+
+/// Start the once-a-day release check in the background.
+///
+/// Returns `None` when checks are disabled. The receiver yields the latest
+/// version (from the cache or GitHub) when there's one newer than this binary.
+pub fn spawn_check() -> Option<Receiver<String>> {
+    if std::env::var_os(NO_CHECK_ENV).is_some() {
+        return None;
+    }
+    let cache = cache_file()?;
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let cached = fs::read_to_string(&cache).ok().and_then(|c| parse_cache(&c));
+        let latest = match cached {
+            Some((checked_at, version)) if now.saturating_sub(checked_at) < CHECK_INTERVAL_SECS => version,
+            _ => {
+                let Ok(release) = latest_release() else { return };
+                if let Some(dir) = cache.parent() {
+                    let _ = fs::create_dir_all(dir);
+                }
+                let _ = fs::write(&cache, format!("{} {}\n", now, release.version));
+                release.version
+            },
+        };
+        if is_newer(&latest) {
+            let _ = tx.send(latest);
+        }
+    });
+    Some(rx)
+}
+
+/// Print the single dim notice line if the check found a newer release in time
+pub fn print_notice(check: Option<Receiver<String>>) {
+    if let Some(latest) = check.and_then(|rx| rx.recv_timeout(NOTICE_WAIT).ok()) {
+        outln!("{}", format!("   {}", t!("update-notice", version = latest)).paint(Role::Muted));
+    }
+}
+
+fn cache_file() -> Option<PathBuf> {
+    crnch::utils::cache_dir().map(|d| d.join("latest-release"))
+}
+
+/// Cache format: "<unix seconds> <version>"
+fn parse_cache(contents: &str) -> Option<(u64, String)> {
+    let (checked_at, version) = contents.trim().split_once(' ')?;
+    Some((checked_at.parse().ok()?, version.trim().to_string()))
+}
+
+fn download<W: Write>(asset: &ReleaseAsset, dest: W) -> Result<()> {
+    self_update::Download::from_url(&asset.download_url)
+        .set_header(reqwest::header::ACCEPT, "application/octet-stream".parse()?)
+        .download_to(dest)
+        .with_context(|| format!("Download of {} failed", asset.name))
+}
+
+/// Compare a file's SHA-256 with a `sha256sum`-style line ("<hex>  <name>")
+fn verify_checksum(path: &Path, expected: &str) -> Result<()> {
+    let expected = expected.split_whitespace().next().unwrap_or("").to_lowercase();
+    let actual = format!("{:x}", Sha256::digest(fs::read(path)?));
+    if actual != expected {
+        return Err(anyhow!("Checksum mismatch for {}: expected {}, got {}", path.display(), expected, actual));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("crnch.tar.gz");
+        fs::write(&path, b"hello").unwrap();
+        let digest = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert!(verify_checksum(&path, &format!("{}  crnch.tar.gz\n", digest)).is_ok());
+        assert!(verify_checksum(&path, &digest.to_uppercase()).is_ok());
+        assert!(verify_checksum(&path, "deadbeef  crnch.tar.gz").is_err());
+        assert!(verify_checksum(&path, "").is_err());
+    }
+
+    #[test]
+    fn test_parse_cache() {
+        assert_eq!(parse_cache("1700000000 0.4.0\n"), Some((1700000000, "0.4.0".to_string())));
+        assert_eq!(parse_cache("garbage"), None);
+        assert_eq!(parse_cache("soon 0.4.0"), None);
+    }
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("99.0.0"));
+        assert!(!is_newer(CURRENT_VERSION));
+        assert!(!is_newer("0.0.1"));
+    }
+}
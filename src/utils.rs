@@ -1,39 +1,227 @@
-use regex::Regex;
 use anyhow::{Result, anyhow};
+use std::fmt;
+use std::str::FromStr;
 
-/// Parse a size string like "200k", "1.5m", "500kb", "2mb" into KB
-pub fn parse_size(size_str: &str) -> Option<u64> {
-    let re = Regex::new(r"(?i)^(\d+(?:\.\d+)?)(k|m|kb|mb|g|gb)?$").ok()?;
-    let caps = re.captures(size_str)?;
-    let val: f64 = caps[1].parse().ok()?;
-    let unit = caps.get(2).map_or("k", |m| m.as_str()).to_lowercase();
-    match unit.as_str() {
-        "g" | "gb" => Some((val * 1024.0 * 1024.0) as u64),
-        "m" | "mb" => Some((val * 1024.0) as u64),
-        _ => Some(val as u64),
+/// Largest target accepted on the command line (10GB)
+pub const MAX_TARGET_BYTES: u64 = 10 * 1024 * 1024 * 1024;
+
+/// Whether bare k/m/g mean powers of 1024 (the default) or of 1000 (`--si`),
+/// and which sizes are shown in.
+///
+/// Spelled-out units don't depend on it: KiB/MiB/GiB are always 1024-based,
+/// kB/MB/GB always 1000-based, as upload limits are.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum SizeUnits {
+    #[default]
+    Binary,
+    Decimal,
+}
+
+impl SizeUnits {
+    pub fn base(self) -> u64 {
+        match self {
+            SizeUnits::Binary => 1024,
+            SizeUnits::Decimal => 1000,
+        }
+    }
+
+    /// `bytes` in the largest unit it reaches, e.g. "1.50 MiB" or "200 kB";
+    /// binary sizes carry IEC names so they read back as the same bytes
+    pub fn format(self, bytes: u64, decimals: usize) -> String {
+        let base = self.base();
+        let units = match self {
+            SizeUnits::Binary => [(base * base * base, "GiB"), (base * base, "MiB"), (base, "KiB")],
+            SizeUnits::Decimal => [(base * base * base, "GB"), (base * base, "MB"), (base, "kB")],
+        };
+        for (size, unit) in units {
+            if bytes >= size {
+                return if bytes.is_multiple_of(size) {
+                    format!("{} {}", bytes / size, unit)
+                } else {
+                    format!("{:.*} {}", decimals, bytes as f64 / size as f64, unit)
+                };
+            }
+        }
+        format!("{} B", bytes)
+    }
+}
+
+/// A target size like "200k", "1.5m" or "512000b", kept exact to the byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TargetSize {
+    bytes: u64,
+}
+
+impl TargetSize {
+    pub fn from_bytes(bytes: u64) -> Self {
+        TargetSize { bytes }
+    }
+
+    pub fn from_kb(kb: u64) -> Self {
+        TargetSize { bytes: kb.saturating_mul(1024) }
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Whole kilobytes, as the engines search; never rounds a non-zero size to 0.
+    ///
+    /// The engines measure sizes in KB rounded up, so a file they accept at
+    /// this many KB is at most this many times 1024 bytes, and a target that
+    /// isn't a whole number of KB (e.g. 2 MB decimal) rounds down.
+    pub fn kb(&self) -> u64 {
+        match self.bytes {
+            0 => 0,
+            bytes => (bytes / 1024).max(1),
+        }
+    }
+
+    /// Parse with bare k/m/g taken as `units`; a decimal comma ("1,5m") works like a point
+    pub fn parse_in(input: &str, units: SizeUnits) -> Result<Self> {
+        let s = input.trim();
+        let split = s.find(|c: char| !c.is_ascii_digit() && c != '.' && c != ',').unwrap_or(s.len());
+        let (number, unit) = (&s[..split], s[split..].trim_start());
+        if number.contains(',') && number.contains('.') {
+            return Err(anyhow!("Invalid number '{}' in '{}': use either a decimal point or a decimal comma", number, input));
+        }
+        // "1,000k" reads as a thousands separator just as well
+        if number.split_once(',').is_some_and(|(_, frac)| frac.len() == 3) {
+            return Err(anyhow!("Ambiguous number '{}' in '{}': write it without the comma", number, input));
+        }
+        let number = number.replace(',', ".");
+        let number = number.as_str();
+
+        let base = units.base();
+        let multiplier: u64 = match unit.to_ascii_lowercase().as_str() {
+            "b" => 1,
+            "k" => base,
+            "m" => base * base,
+            "g" => base * base * base,
+            "kb" => 1000,
+            "mb" => 1000 * 1000,
+            "gb" => 1000 * 1000 * 1000,
+            "kib" => 1024,
+            "mib" => 1024 * 1024,
+            "gib" => 1024 * 1024 * 1024,
+            "" => return Err(anyhow!("Missing unit in '{}'. Did you mean {}k?", input, number)),
+            other => return Err(anyhow!("Unknown unit '{}' in '{}'. Use b, k, m, g, kB, MB, GB or KiB, MiB, GiB", other, input)),
+        };
+
+        let (whole, frac) = match number.split_once('.') {
+            Some((w, f)) => (w, f),
+            None => (number, ""),
+        };
+        if whole.is_empty() || frac.contains('.') || (number.contains('.') && frac.is_empty()) {
+            return Err(anyhow!("Invalid number '{}' in '{}'", number, input));
+        }
+        if !frac.is_empty() && multiplier == 1 {
+            return Err(anyhow!("Byte sizes must be whole numbers. Got: {}", input));
+        }
+
+        let overflow = || anyhow!("Size too large: {}", input);
+        let whole: u64 = whole.parse().map_err(|_| overflow())?;
+        let mut bytes = whole.checked_mul(multiplier).ok_or_else(overflow)?;
+        // Fractional part, exact to the byte: 1.5k = 1536b
+        if !frac.is_empty() {
+            let digits = &frac[..frac.len().min(9)];
+            let scale = 10u64.pow(digits.len() as u32);
+            let frac_val: u64 = digits.parse().map_err(|_| overflow())?;
+            let extra = (frac_val as u128 * multiplier as u128 / scale as u128) as u64;
+            bytes = bytes.checked_add(extra).ok_or_else(overflow)?;
+        }
+        Ok(TargetSize { bytes })
+    }
+
+    pub fn display_in(&self, units: SizeUnits) -> String {
+        units.format(self.bytes, 2)
+    }
+}
+
+impl FromStr for TargetSize {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        TargetSize::parse_in(input, SizeUnits::Binary)
+    }
+}
+
+impl fmt::Display for TargetSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.display_in(SizeUnits::Binary))
     }
 }
 
 /// Validate size string and provide helpful error message
-pub fn validate_size(size_str: &str) -> Result<u64> {
-    if size_str.is_empty() {
+pub fn validate_size(size_str: &str, units: SizeUnits) -> Result<TargetSize> {
+    if size_str.trim().is_empty() {
         return Err(anyhow!("Size cannot be empty. Examples: 200k, 1.5m, 500kb"));
     }
-    
-    match parse_size(size_str) {
-        Some(0) => {
-            Err(anyhow!("Size must be greater than 0. Examples: 200k, 1.5m, 500kb"))
-        },
-        Some(kb) if kb > 10485760 => { // 10GB limit
-            Err(anyhow!("Size too large (max 10GB). Got: {}", size_str))
+
+    let size = TargetSize::parse_in(size_str, units).map_err(|e| anyhow!(
+        "{}\nExamples:\n   - 200k or 200KiB (200 x 1024 bytes), 200kB (200,000 bytes)\n   - 1.5m, 1,5m or 1.5MiB (1.5 x 1024 x 1024 bytes), 1.5MB (1,500,000 bytes)\n   - 2g or 2GiB, 2GB (2,000,000,000 bytes)\n   - 50000b (bytes)",
+        e
+    ))?;
+    if size.bytes() == 0 {
+        return Err(anyhow!("Size must be greater than 0. Examples: 200k, 1.5m, 500kb"));
+    }
+    if size.bytes() > MAX_TARGET_BYTES {
+        return Err(anyhow!("Size too large (max 10GB). Got: {}", size_str));
+    }
+    Ok(size)
+}
+
+/// `--size`: one size, or a range like "180k-220k" whose lower end comes
+/// back too; "180-220k" takes the unit from the upper end
+pub fn validate_size_range(size_str: &str, units: SizeUnits) -> Result<(Option<TargetSize>, TargetSize)> {
+    let Some((low, high)) = size_str.split_once('-') else {
+        return Ok((None, validate_size(size_str, units)?));
+    };
+    let (low, high) = (low.trim(), high.trim());
+    if low.is_empty() {
+        return Err(anyhow!("Size range '{}' is missing its lower end, e.g. 180k-220k", size_str));
+    }
+    let max = validate_size(high, units)?;
+    let low = if low.chars().all(|c| c.is_ascii_digit() || c == '.' || c == ',') {
+        let unit = high.find(|c: char| !c.is_ascii_digit() && c != '.' && c != ',').map_or("", |i| &high[i..]);
+        format!("{}{}", low, unit)
+    } else {
+        low.to_string()
+    };
+    let min = validate_size(&low, units)?;
+    if min >= max {
+        return Err(anyhow!("Size range '{}' must go from smaller to larger, e.g. 180k-220k", size_str));
+    }
+    Ok((Some(min), max))
+}
+
+/// Per-user cache directory for crnch (`$XDG_CACHE_HOME/crnch`, else `~/.cache/crnch`)
+pub fn cache_dir() -> Option<std::path::PathBuf> {
+    let base = match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) if !dir.is_empty() => std::path::PathBuf::from(dir),
+        _ => std::path::PathBuf::from(std::env::var_os("HOME").or_else(|| std::env::var_os("LOCALAPPDATA"))?).join(".cache"),
+    };
+    Some(base.join("crnch"))
+}
+
+/// Per-user config directory for crnch (`$XDG_CONFIG_HOME/crnch`, else `~/.config/crnch`)
+pub fn config_dir() -> Option<std::path::PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => std::path::PathBuf::from(dir),
+        _ => match std::env::var_os("HOME") {
+            Some(home) => std::path::PathBuf::from(home).join(".config"),
+            None => std::path::PathBuf::from(std::env::var_os("APPDATA")?),
         },
-        Some(kb) => Ok(kb),
-        None => {
-            Err(anyhow!(
-                "Invalid size format: '{}'. Examples:\n   - 200k or 200kb (200 kilobytes)\n   - 1.5m or 1.5mb (1.5 megabytes)\n   - 2g or 2gb (2 gigabytes)",
-                size_str
-            ))
-        }
+    };
+    Some(base.join("crnch"))
+}
+
+/// `path` with a leading `~/` turned into the home directory; config files
+/// aren't run through a shell
+pub fn expand_home(path: &str) -> std::path::PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => std::path::PathBuf::from(home).join(rest),
+        _ => std::path::PathBuf::from(path),
     }
 }
 
@@ -43,12 +231,13 @@ pub fn validate_file_extension(filename: &str) -> Result<String> {
     let ext = path.extension()
         .and_then(|e| e.to_str())
         .map(|e| e.to_lowercase())
-        .ok_or_else(|| anyhow!("File '{}' has no extension.\nSupported formats: .jpg, .jpeg, .png, .pdf", filename))?;
+        .ok_or_else(|| anyhow!("File '{}' has no extension.\nSupported formats: .jpg, .jpeg, .png, .pdf, .webp, .gif, .heic, .avif, .svg, .bmp, .tiff, .psd, .ico, .docx, .pptx, .zip, .cbz, .cbr, .mp4, .mov, .mkv, camera RAW", filename))?;
     
     match ext.as_str() {
-        "jpg" | "jpeg" | "png" | "pdf" => Ok(ext),
+        "jpg" | "jpeg" | "png" | "pdf" | "webp" | "gif" | "heic" | "heif" | "avif" | "svg" | "svgz" | "bmp" | "tif" | "tiff" | "psd" | "ico" | "docx" | "pptx" | "zip" | "cbz" | "cbr" | "mp4" | "mov" | "mkv" => Ok(ext),
+        "cr2" | "cr3" | "nef" | "arw" | "dng" | "raf" | "orf" | "rw2" => Ok(ext),
         _ => Err(anyhow!(
-            "Unsupported file type: .{}\nSupported formats: .jpg, .jpeg, .png, .pdf",
+            "Unsupported file type: .{}\nSupported formats: .jpg, .jpeg, .png, .pdf, .webp, .gif, .heic, .avif, .svg, .bmp, .tiff, .psd, .ico, .docx, .pptx, .zip, .cbz, .cbr, .mp4, .mov, .mkv, camera RAW",
             ext
         ))
     }
@@ -92,71 +281,272 @@ pub fn validate_output_path(output: &str) -> Result<()> {
     Ok(())
 }
 
+/// Prefix used for default output names when none is configured
+pub const DEFAULT_OUTPUT_PREFIX: &str = "crnched_";
+
+/// Default output file name for `input`: `<prefix><stem><suffix>.<ext>`
+pub fn default_output_name(input: &std::path::Path, prefix: &str, suffix: &str) -> String {
+    let stem = input.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("output");
+    let ext = input.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("bin")
+        .to_lowercase();
+    format!("{}{}{}.{}", prefix, stem, suffix, ext)
+}
+
+/// `path` with `_<label>` ahead of its extension: `photo_200k.jpg`
+pub fn labeled_path(path: &std::path::Path, label: &str) -> std::path::PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{}_{}.{}", stem, label, ext.to_string_lossy()),
+        None => format!("{}_{}", stem, label),
+    };
+    path.with_file_name(name)
+}
+
+/// `--sizes 50k,200k,1m`: every size with the label its output is named by,
+/// as written (lowercased). Commas separate sizes here, so decimals take a point.
+pub fn parse_sizes(list: &str, units: SizeUnits) -> Result<Vec<(String, TargetSize)>> {
+    let mut sizes: Vec<(String, TargetSize)> = Vec::new();
+    for item in list.split(',') {
+        let label: String = item.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+        let size = validate_size(&label, units)?;
+        if sizes.iter().any(|(_, s)| *s == size) {
+            return Err(anyhow!("'{}' is in --sizes twice.", item.trim()));
+        }
+        sizes.push((label, size));
+    }
+    Ok(sizes)
+}
+
+/// `path` if it's free, else the first free `name(2).ext`, `name(3).ext`, ...
+pub fn numbered_path(path: &std::path::Path) -> std::path::PathBuf {
+    numbered_path_avoiding(path, &[])
+}
+
+/// [`numbered_path`], also passing over `taken`: outputs claimed by files
+/// earlier in the same run that aren't written yet
+pub fn numbered_path_avoiding(path: &std::path::Path, taken: &[std::path::PathBuf]) -> std::path::PathBuf {
+    let free = |candidate: &std::path::Path| !candidate.exists() && !taken.iter().any(|t| t == candidate);
+    if free(path) {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (2u32..)
+        .map(|n| path.with_file_name(format!("{}({}){}", stem, n, ext)))
+        .find(|candidate| free(candidate))
+        .expect("ran out of numbered names")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_default_output_name() {
+        let input = std::path::Path::new("photos/Beach.JPG");
+        assert_eq!(default_output_name(input, DEFAULT_OUTPUT_PREFIX, ""), "crnched_Beach.jpg");
+        assert_eq!(default_output_name(input, "", ".min"), "Beach.min.jpg");
+        assert_eq!(labeled_path(std::path::Path::new("out/crnched_Beach.jpg"), "1.5m"), std::path::Path::new("out/crnched_Beach_1.5m.jpg"));
+    }
+
+    #[test]
+    fn test_parse_sizes_keeps_labels() {
+        let sizes = parse_sizes("50k, 200KB,480000b", SizeUnits::Binary).unwrap();
+        let labels: Vec<&str> = sizes.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(labels, ["50k", "200kb", "480000b"]);
+        assert_eq!(sizes[2].1.bytes(), 480_000);
+        assert!(parse_sizes("200k,0.2m", SizeUnits::Decimal).is_err());
+        assert!(parse_sizes("50k,", SizeUnits::Binary).is_err());
+    }
+
+    #[test]
+    fn test_numbered_path_skips_taken_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("crnched_a.png");
+        assert_eq!(numbered_path(&path), path);
+        std::fs::write(&path, b"1").unwrap();
+        std::fs::write(dir.path().join("crnched_a(2).png"), b"2").unwrap();
+        assert_eq!(numbered_path(&path), dir.path().join("crnched_a(3).png"));
+    }
+
+    #[test]
+    fn test_numbered_path_avoids_names_claimed_this_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("crnched_a.png");
+        let taken = [path.clone(), dir.path().join("crnched_a(2).png")];
+        assert_eq!(numbered_path_avoiding(&path, &taken), dir.path().join("crnched_a(3).png"));
+    }
+
+    fn parse(s: &str) -> Option<u64> {
+        s.parse::<TargetSize>().ok().map(|t| t.bytes())
+    }
 
     #[test]
     fn test_parse_size_kilobytes() {
-        assert_eq!(parse_size("200k"), Some(200));
-        assert_eq!(parse_size("200kb"), Some(200));
-        assert_eq!(parse_size("200K"), Some(200));
-        assert_eq!(parse_size("200KB"), Some(200));
+        assert_eq!(parse("200k"), Some(200 * 1024));
+        assert_eq!(parse("200K"), Some(200 * 1024));
+        assert_eq!(parse("200KiB"), Some(200 * 1024));
+        assert_eq!(parse("200kB"), Some(200_000));
+        assert_eq!(parse("200 kb"), Some(200_000));
     }
 
     #[test]
     fn test_parse_size_megabytes() {
-        assert_eq!(parse_size("1m"), Some(1024));
-        assert_eq!(parse_size("1mb"), Some(1024));
-        assert_eq!(parse_size("1.5m"), Some(1536));
-        assert_eq!(parse_size("2M"), Some(2048));
+        assert_eq!(parse("1m"), Some(1024 * 1024));
+        assert_eq!(parse("1MiB"), Some(1024 * 1024));
+        assert_eq!(parse("1mb"), Some(1_000_000));
+        assert_eq!(parse("1.5m"), Some(1536 * 1024));
+        assert_eq!(parse("2M"), Some(2048 * 1024));
     }
 
     #[test]
     fn test_parse_size_gigabytes() {
-        assert_eq!(parse_size("1g"), Some(1024 * 1024));
-        assert_eq!(parse_size("1gb"), Some(1024 * 1024));
-        assert_eq!(parse_size("2G"), Some(2 * 1024 * 1024));
+        assert_eq!(parse("1g"), Some(1024 * 1024 * 1024));
+        assert_eq!(parse("1GiB"), Some(1024 * 1024 * 1024));
+        assert_eq!(parse("1gb"), Some(1_000_000_000));
+        assert_eq!(parse("2G"), Some(2 * 1024 * 1024 * 1024));
+    }
+
+    #[test]
+    fn test_parse_size_bytes() {
+        assert_eq!(parse("512b"), Some(512));
+        assert_eq!(parse("512B"), Some(512));
+        assert_eq!(parse("1.5b"), None);
     }
 
     #[test]
     fn test_parse_size_decimals() {
-        assert_eq!(parse_size("0.5m"), Some(512));
-        assert_eq!(parse_size("1.5k"), Some(1));
+        assert_eq!(parse("0.5m"), Some(512 * 1024));
+        assert_eq!(parse("1.5k"), Some(1536));
+        assert_eq!("1.5k".parse::<TargetSize>().unwrap().kb(), 1);
+        assert_eq!("0.5k".parse::<TargetSize>().unwrap().kb(), 1);
     }
 
     #[test]
     fn test_parse_size_invalid() {
-        assert_eq!(parse_size(""), None);
-        assert_eq!(parse_size("invalid"), None);
-        assert_eq!(parse_size("k"), None);
-        assert_eq!(parse_size("-100k"), None);
-        assert_eq!(parse_size("100x"), None);
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("invalid"), None);
+        assert_eq!(parse("k"), None);
+        assert_eq!(parse("-100k"), None);
+        assert_eq!(parse("100x"), None);
+        assert_eq!(parse("1.5.5m"), None);
+        assert_eq!(parse("1.m"), None);
+        assert_eq!(parse(".5m"), None);
+    }
+
+    #[test]
+    fn test_parse_size_requires_unit() {
+        let err = "200".parse::<TargetSize>().unwrap_err();
+        assert!(err.to_string().contains("200k"));
+    }
+
+    #[test]
+    fn test_parse_size_overflow() {
+        assert_eq!(parse("99999999999999999999k"), None);
+        assert_eq!(parse("18446744073709551615g"), None);
     }
 
     #[test]
     fn test_validate_size_success() {
-        assert!(validate_size("200k").is_ok());
-        assert!(validate_size("1.5m").is_ok());
-        assert!(validate_size("1g").is_ok());
+        assert!(validate_size("200k", SizeUnits::Binary).is_ok());
+        assert!(validate_size("1.5m", SizeUnits::Binary).is_ok());
+        assert!(validate_size("1g", SizeUnits::Binary).is_ok());
     }
 
     #[test]
     fn test_validate_size_zero() {
-        assert!(validate_size("0k").is_err());
-        assert!(validate_size("0").is_err());
+        assert!(validate_size("0k", SizeUnits::Binary).is_err());
+        assert!(validate_size("0b", SizeUnits::Binary).is_err());
     }
 
     #[test]
     fn test_validate_size_too_large() {
-        assert!(validate_size("20g").is_err()); // > 10GB
+        assert!(validate_size("20g", SizeUnits::Binary).is_err()); // > 10GB
+    }
+
+    #[test]
+    fn test_validate_size_range() {
+        let kb = |n| TargetSize::from_kb(n);
+        assert_eq!(validate_size_range("200k", SizeUnits::Binary).unwrap(), (None, kb(200)));
+        assert_eq!(validate_size_range("180k-220k", SizeUnits::Binary).unwrap(), (Some(kb(180)), kb(220)));
+        assert_eq!(validate_size_range("180 - 220k", SizeUnits::Binary).unwrap(), (Some(kb(180)), kb(220)));
+        assert_eq!(validate_size_range("900k-1.2m", SizeUnits::Binary).unwrap(), (Some(kb(900)), TargetSize::parse_in("1.2m", SizeUnits::Binary).unwrap()));
+        assert!(validate_size_range("220k-180k", SizeUnits::Binary).is_err());
+        assert!(validate_size_range("-220k", SizeUnits::Binary).is_err());
+        assert!(validate_size_range("180k-", SizeUnits::Binary).is_err());
     }
 
     #[test]
     fn test_validate_size_invalid_format() {
-        assert!(validate_size("invalid").is_err());
-        assert!(validate_size("").is_err());
-        assert!(validate_size("-100k").is_err());
+        assert!(validate_size("invalid", SizeUnits::Binary).is_err());
+        assert!(validate_size("", SizeUnits::Binary).is_err());
+        assert!(validate_size("-100k", SizeUnits::Binary).is_err());
+        assert!(validate_size("200", SizeUnits::Binary).is_err());
+    }
+
+    #[test]
+    fn test_parse_size_si_and_decimal_comma() {
+        assert_eq!(TargetSize::parse_in("2mb", SizeUnits::Decimal).unwrap().bytes(), 2_000_000);
+        assert_eq!(TargetSize::parse_in("1,5m", SizeUnits::Decimal).unwrap().bytes(), 1_500_000);
+        assert_eq!(TargetSize::parse_in("2MiB", SizeUnits::Decimal).unwrap().bytes(), 2 * 1024 * 1024);
+        assert_eq!(TargetSize::parse_in("2MB", SizeUnits::Binary).unwrap().bytes(), 2_000_000);
+        assert_eq!(parse("1,5m"), Some(1536 * 1024));
+        assert_eq!(parse("300 KiB"), Some(300 * 1024));
+        assert_eq!(parse("1,5.5m"), None);
+        assert_eq!(parse("1,m"), None);
+        assert_eq!(parse("1,000k"), None);
+    }
+
+    #[test]
+    fn test_kb_never_exceeds_target_bytes() {
+        let two_mb = TargetSize::parse_in("2mb", SizeUnits::Decimal).unwrap();
+        assert!(two_mb.kb() * 1024 <= 2_000_000);
+        assert_eq!(two_mb.kb(), 1953);
+        assert_eq!(TargetSize::from_kb(200).kb(), 200);
+    }
+
+    #[test]
+    fn test_target_size_display() {
+        assert_eq!(TargetSize::from_kb(200).to_string(), "200 KiB");
+        assert_eq!(TargetSize::from_bytes(1536 * 1024).to_string(), "1.50 MiB");
+        assert_eq!(TargetSize::from_bytes(512).to_string(), "512 B");
+        assert_eq!(TargetSize::from_bytes(1_500_000).display_in(SizeUnits::Decimal), "1.50 MB");
+        assert_eq!(TargetSize::from_bytes(200_000).display_in(SizeUnits::Decimal), "200 kB");
+    }
+
+    proptest! {
+        #[test]
+        fn prop_parse_never_panics(s in "\\PC*") {
+            let _ = s.parse::<TargetSize>();
+        }
+
+        #[test]
+        fn prop_whole_units_roundtrip(n in 0u64..1_000_000, unit in prop::sample::select(vec![("b", 1u64), ("k", 1024), ("kb", 1000), ("kib", 1024), ("m", 1 << 20), ("mb", 1_000_000), ("mib", 1 << 20), ("g", 1 << 30)])) {
+            let parsed: TargetSize = format!("{}{}", n, unit.0).parse().unwrap();
+            prop_assert_eq!(parsed.bytes(), n * unit.1);
+        }
+
+        #[test]
+        fn prop_fraction_is_bounded(whole in 0u64..10_000, frac in 0u32..1000) {
+            let parsed: TargetSize = format!("{}.{:03}k", whole, frac).parse().unwrap();
+            prop_assert!(parsed.bytes() >= whole * 1024);
+            prop_assert!(parsed.bytes() < (whole + 1) * 1024);
+        }
+
+        #[test]
+        fn prop_display_reparses_close(bytes in 1u64..MAX_TARGET_BYTES) {
+            let size = TargetSize::from_bytes(bytes);
+            let shown = size.to_string().replace(' ', "");
+            let reparsed: TargetSize = shown.parse().unwrap();
+            // Display rounds to 2 decimals of the unit
+            let unit = if bytes >= 1 << 30 { 1u64 << 30 } else if bytes >= 1 << 20 { 1 << 20 } else if bytes >= 1024 { 1024 } else { 1 };
+            prop_assert!(reparsed.bytes().abs_diff(bytes) <= unit / 100 + 1);
+        }
     }
 
     #[test]
@@ -165,12 +555,26 @@ mod tests {
         assert!(validate_file_extension("photo.jpg").is_ok());
         assert!(validate_file_extension("photo.JPEG").is_ok());
         assert!(validate_file_extension("document.pdf").is_ok());
+        assert!(validate_file_extension("photo.webp").is_ok());
+        assert!(validate_file_extension("IMG_0001.heic").is_ok());
+        assert!(validate_file_extension("loop.gif").is_ok());
+        assert!(validate_file_extension("logo.svgz").is_ok());
+        assert!(validate_file_extension("capture.bmp").is_ok());
+        assert!(validate_file_extension("IMG_1234.CR2").is_ok());
+        assert!(validate_file_extension("report.docx").is_ok());
+        assert!(validate_file_extension("photos.zip").is_ok());
+        assert!(validate_file_extension("issue_01.cbz").is_ok());
+        assert!(validate_file_extension("clip.mkv").is_ok());
+        assert!(validate_file_extension("scans.TIF").is_ok());
+        assert!(validate_file_extension("mockup.psd").is_ok());
+        assert!(validate_file_extension("favicon.ico").is_ok());
+        assert!(validate_file_extension("hero.avif").is_ok());
     }
 
     #[test]
     fn test_validate_file_extension_unsupported() {
         assert!(validate_file_extension("file.txt").is_err());
-        assert!(validate_file_extension("file.zip").is_err());
+        assert!(validate_file_extension("file.tar").is_err());
         assert!(validate_file_extension("file.md").is_err());
     }
 
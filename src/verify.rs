@@ -0,0 +1,246 @@
+//! Integrity checks over finished outputs (`crnch verify`).
+//!
+//! Every output is decoded by the tool that reads its format, and, when the
+//! run history (or a manifest in the same format) knows the file, compared
+//! with the hash and target recorded when it was written.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use crate::bmp;
+use crate::ico;
+use crate::exec::ToolCommand;
+use crate::format::Format;
+use crate::history::RunRecord;
+use crate::options::CompressionOptions;
+use crate::svg;
+use crate::webp;
+use crate::zip;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    Missing,
+    /// The format's end marker isn't there
+    Truncated,
+    DecodeFailed,
+    HashMismatch,
+    OverTarget { size_kb: u64, target_kb: u64 },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Missing => write!(f, "file is missing"),
+            Problem::Truncated => write!(f, "file is truncated"),
+            Problem::DecodeFailed => write!(f, "does not decode cleanly"),
+            Problem::HashMismatch => write!(f, "contents changed since crnch wrote it"),
+            Problem::OverTarget { size_kb, target_kb } => write!(f, "{} KB, over its {} KB target", size_kb, target_kb),
+        }
+    }
+}
+
+/// Outcome for one file; no problems means it passed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verdict {
+    pub path: PathBuf,
+    /// Whether a history/manifest record was checked against
+    pub recorded: bool,
+    pub problems: Vec<Problem>,
+}
+
+impl Verdict {
+    pub fn ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+pub fn sha256_file(path: &Path) -> Result<String> {
+    Ok(format!("{:x}", Sha256::digest(fs::read(path)?)))
+}
+
+/// Every file in a supported format under `dir`, recursively, sorted
+pub fn outputs_in(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)?.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if Format::from_path(&path.to_string_lossy()).is_some() {
+                found.push(path);
+            }
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+/// The newest record that wrote `path`
+pub fn record_for<'a>(path: &Path, records: &'a [RunRecord]) -> Option<&'a RunRecord> {
+    let absolute = std::path::absolute(path).ok()?;
+    records.iter().rev().find(|r| r.output == absolute)
+}
+
+/// Check `path` on its own and against `record`
+pub fn verify_file(path: &Path, record: Option<&RunRecord>, opts: &CompressionOptions) -> Result<Verdict> {
+    let mut verdict = Verdict { path: path.to_path_buf(), recorded: record.is_some(), problems: Vec::new() };
+    let Ok(data) = fs::read(path) else {
+        verdict.problems.push(Problem::Missing);
+        return Ok(verdict);
+    };
+    if let Some(format) = Format::from_path(&path.to_string_lossy()) {
+        if !has_end_marker(&data, format) {
+            verdict.problems.push(Problem::Truncated);
+        }
+        opts.cancel.check()?;
+        if let Some(mut cmd) = decode_command(path, format) {
+            opts.limits.apply(&mut cmd);
+            if !opts.executor.run(&cmd, &opts.cancel)?.success() {
+                verdict.problems.push(Problem::DecodeFailed);
+            }
+        }
+    }
+    if let Some(record) = record {
+        if record.output_sha256.as_ref().is_some_and(|h| *h != format!("{:x}", Sha256::digest(&data))) {
+            verdict.problems.push(Problem::HashMismatch);
+        }
+        if let (Some(true), Some(target_kb)) = (record.target_met, record.target_kb) {
+            let size = data.len() as u64;
+            if size > record.target_bytes.unwrap_or(target_kb * 1024) {
+                verdict.problems.push(Problem::OverTarget { size_kb: size.div_ceil(1024), target_kb });
+            }
+        }
+    }
+    Ok(verdict)
+}
+
+/// A full decode that writes nothing and fails on any warning; none for
+/// zips and Office packages, whose end check already inflates every entry
+fn decode_command(path: &Path, format: Format) -> Option<ToolCommand> {
+    let path = path.to_string_lossy().to_string();
+    let mut cmd = match format {
+        Format::Pdf => {
+            let mut cmd = ToolCommand::new("gs");
+            cmd.arg("-q").arg("-dSAFER").arg("-dBATCH").arg("-dNOPAUSE").arg("-sDEVICE=nullpage").arg(path);
+            cmd
+        },
+        Format::Webp => {
+            let mut cmd = ToolCommand::new("dwebp");
+            cmd.arg("-quiet").arg(path);
+            cmd
+        },
+        Format::Jpg | Format::Png | Format::Gif | Format::Heic | Format::Avif | Format::Svg | Format::Bmp | Format::Raw | Format::Tiff | Format::Psd | Format::Ico => {
+            let mut cmd = ToolCommand::new("magick");
+            cmd.arg("-regard-warnings").arg(path).arg("null:");
+            cmd
+        },
+        Format::Video => {
+            let mut cmd = ToolCommand::new("ffmpeg");
+            cmd.arg("-v").arg("error").arg("-xerror").arg("-i").arg(path).arg("-f").arg("null").arg("-");
+            cmd
+        },
+        Format::Office | Format::Zip | Format::Comic => return None,
+    };
+    cmd.quiet_stderr(true);
+    Some(cmd)
+}
+
+/// End-of-image marker, IEND chunk, GIF trailer or `%%EOF` near the end of
+/// the file; for WebP, a RIFF length that matches the file's, for HEIC and AVIF,
+/// top-level boxes that end exactly at the end of the file, for SVG, `</svg>`, for BMP, a header
+/// file size that matches, for ICO, every frame the directory lists, for zips and Office packages, every entry inflating to its CRC,
+/// and for MP4/MOV, top-level boxes as for HEIC
+fn has_end_marker(data: &[u8], format: Format) -> bool {
+    let (marker, window): (&[u8], usize) = match format {
+        Format::Jpg => (&[0xFF, 0xD9], 64),
+        Format::Png => (b"IEND", 64),
+        Format::Pdf => (b"%%EOF", 1024),
+        Format::Gif => (b";", 1),
+        Format::Webp => return webp::is_complete(data),
+        Format::Heic | Format::Avif => return boxes_complete(data),
+        Format::Svg => return svg::is_complete(data),
+        Format::Bmp => return bmp::is_complete(data),
+        Format::Ico => return ico::is_complete(data),
+        // Layouts differ per camera; the decode is the whole check
+        Format::Raw | Format::Tiff | Format::Psd => return true,
+        Format::Office | Format::Zip => return zip::is_complete(data),
+        // RAR comics can't be checked without unrar
+        Format::Comic => return data.starts_with(b"Rar!") || zip::is_complete(data),
+        // MKV (EBML) has no fixed end; MP4 and MOV are boxes like HEIC
+        Format::Video => return data.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) || boxes_complete(data),
+    };
+    let tail = &data[data.len().saturating_sub(window)..];
+    tail.windows(marker.len()).any(|w| w == marker)
+}
+
+fn boxes_complete(data: &[u8]) -> bool {
+    let mut pos = 0;
+    while pos + 8 <= data.len() {
+        let size = match u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) {
+            // Last box, runs to the end of the file
+            0 => return true,
+            1 => match data.get(pos + 8..pos + 16) {
+                Some(b) => u64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as usize,
+                None => return false,
+            },
+            size => size as usize,
+        };
+        if size < 8 {
+            return false;
+        }
+        pos += size;
+    }
+    pos == data.len() && pos > 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::tests::{FakeRun, FakeTools};
+    use crate::compression::CompResult;
+
+    #[test]
+    fn test_recorded_output_checks_hash_and_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("crnched_a.png");
+        let mut png = vec![0u8; 60 * 1024];
+        png.extend(b"\0\0\0\0IEND\xAE\x42\x60\x82");
+        fs::write(&output, &png).unwrap();
+        let result = CompResult { target_kb: Some(64), target_met: Some(true), ..CompResult::default() };
+        let record = RunRecord::new("a.png", &output.to_string_lossy(), &result);
+        let opts = CompressionOptions::builder().executor(FakeTools::new(|_| FakeRun::Keep)).build();
+
+        let verdict = verify_file(&output, record_for(&output, std::slice::from_ref(&record)), &opts).unwrap();
+        assert!(verdict.ok() && verdict.recorded);
+
+        // Re-saved bigger and cut short afterwards
+        fs::write(&output, vec![1u8; 70 * 1024]).unwrap();
+        let verdict = verify_file(&output, Some(&record), &opts).unwrap();
+        assert_eq!(verdict.problems, [Problem::Truncated, Problem::HashMismatch, Problem::OverTarget { size_kb: 70, target_kb: 64 }]);
+
+        // A byte target is held to the byte, not to the KB it rounds to
+        let exact = RunRecord { target_kb: Some(70), target_bytes: Some(70 * 1024 - 100), output_sha256: None, ..record };
+        let verdict = verify_file(&output, Some(&exact), &opts).unwrap();
+        assert_eq!(verdict.problems, [Problem::Truncated, Problem::OverTarget { size_kb: 70, target_kb: 70 }]);
+    }
+
+    #[test]
+    fn test_directory_walk_and_decode_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("2024/03")).unwrap();
+        fs::write(dir.path().join("2024/03/doc.pdf"), b"%PDF-1.4\n%%EOF\n").unwrap();
+        fs::write(dir.path().join("notes.txt"), b"skip me").unwrap();
+        let outputs = outputs_in(dir.path()).unwrap();
+        assert_eq!(outputs, [dir.path().join("2024/03/doc.pdf")]);
+
+        let opts = CompressionOptions::builder().executor(FakeTools::new(|_| FakeRun::Fail)).build();
+        let verdict = verify_file(&outputs[0], None, &opts).unwrap();
+        assert_eq!(verdict.problems, [Problem::DecodeFailed]);
+        assert!(!verdict.recorded);
+        let missing = verify_file(&dir.path().join("gone.jpg"), None, &opts).unwrap();
+        assert_eq!(missing.problems, [Problem::Missing]);
+    }
+}
@@ -0,0 +1,128 @@
+//! WebP container (RIFF) reading for the checks that look inside outputs.
+
+/// `VP8X` feature flags
+pub const FLAG_ICC: u8 = 0x20;
+pub const FLAG_ALPHA: u8 = 0x10;
+pub const FLAG_EXIF: u8 = 0x08;
+pub const FLAG_XMP: u8 = 0x04;
+pub const FLAG_ANIMATION: u8 = 0x02;
+
+/// Top-level chunks as `(fourcc, payload)`; empty for anything not WebP
+pub fn chunks(data: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut found = Vec::new();
+    if data.len() < 12 || &data[..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+        return found;
+    }
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let len = u32::from_le_bytes([data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]]) as usize;
+        let Some(payload) = data.get(pos + 8..pos + 8 + len) else { break };
+        found.push((&data[pos..pos + 4], payload));
+        // Odd-sized payloads are padded to even
+        pos += 8 + len + (len & 1);
+    }
+    found
+}
+
+/// Whether the RIFF header's length covers exactly the bytes present
+pub fn is_complete(data: &[u8]) -> bool {
+    data.len() >= 12
+        && &data[..4] == b"RIFF"
+        && u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize + 8 == data.len()
+}
+
+/// Canvas width and height from `VP8X`, `VP8L` or `VP8 `
+pub fn dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let u24 = |b: &[u8]| u32::from_le_bytes([b[0], b[1], b[2], 0]) + 1;
+    chunks(data).into_iter().find_map(|(kind, body)| match kind {
+        b"VP8X" if body.len() >= 10 => Some((u24(&body[4..7]), u24(&body[7..10]))),
+        b"VP8L" if body.len() >= 5 && body[0] == 0x2F => {
+            let bits = u32::from_le_bytes([body[1], body[2], body[3], body[4]]);
+            Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+        },
+        b"VP8 " if body.len() >= 10 && body[3..6] == [0x9D, 0x01, 0x2A] => Some((
+            u16::from_le_bytes([body[6], body[7]]) as u32 & 0x3FFF,
+            u16::from_le_bytes([body[8], body[9]]) as u32 & 0x3FFF,
+        )),
+        _ => None,
+    })
+}
+
+/// The TIFF-structured payload of the `EXIF` chunk; some writers keep JPEG's `Exif\0\0`
+pub fn exif(data: &[u8]) -> Option<&[u8]> {
+    chunks(data).into_iter()
+        .find(|(kind, _)| *kind == b"EXIF")
+        .map(|(_, body)| body.strip_prefix(b"Exif\0\0").unwrap_or(body))
+}
+
+/// `data` without its `EXIF` and `XMP ` chunks, flags and RIFF length fixed up
+pub fn strip_metadata(data: &[u8]) -> Option<Vec<u8>> {
+    let chunks = chunks(data);
+    if chunks.is_empty() {
+        return None;
+    }
+    let mut out = b"RIFF\0\0\0\0WEBP".to_vec();
+    for (kind, body) in chunks {
+        if kind == b"EXIF" || kind == b"XMP " {
+            continue;
+        }
+        let start = out.len();
+        out.extend(kind);
+        out.extend((body.len() as u32).to_le_bytes());
+        out.extend(body);
+        if kind == b"VP8X" {
+            out[start + 8] &= !(FLAG_EXIF | FLAG_XMP);
+        }
+        if body.len() & 1 == 1 {
+            out.push(0);
+        }
+    }
+    let riff_len = (out.len() - 8) as u32;
+    out[4..8].copy_from_slice(&riff_len.to_le_bytes());
+    Some(out)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Extended WebP with the given flags, a lossless 3x2 bitstream and extra chunks
+    pub(crate) fn webp(flags: u8, extra: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut body = b"WEBP".to_vec();
+        let mut chunk = |kind: &[u8], payload: &[u8]| {
+            body.extend(kind);
+            body.extend((payload.len() as u32).to_le_bytes());
+            body.extend(payload);
+            if payload.len() & 1 == 1 {
+                body.push(0);
+            }
+        };
+        chunk(b"VP8X", &[flags, 0, 0, 0, 2, 0, 0, 1, 0, 0]);
+        for (kind, payload) in extra {
+            chunk(*kind, payload);
+        }
+        let bits: u32 = 2 | (1 << 14);
+        let mut vp8l = vec![0x2F];
+        vp8l.extend(bits.to_le_bytes());
+        chunk(b"VP8L", &vp8l);
+        let mut data = b"RIFF".to_vec();
+        data.extend((body.len() as u32).to_le_bytes());
+        data.extend(body);
+        data
+    }
+
+    #[test]
+    fn test_chunks_dimensions_and_strip() {
+        let data = webp(FLAG_EXIF | FLAG_XMP | FLAG_ICC, &[(b"ICCP", b"icc"), (b"EXIF", b"Exif\0\0II*\0"), (b"XMP ", b"<x/>")]);
+        assert!(is_complete(&data));
+        assert_eq!(dimensions(&data), Some((3, 2)));
+        assert_eq!(exif(&data), Some(&b"II*\0"[..]));
+
+        let stripped = strip_metadata(&data).unwrap();
+        assert!(is_complete(&stripped));
+        let kinds: Vec<&[u8]> = chunks(&stripped).into_iter().map(|(k, _)| k).collect();
+        assert_eq!(kinds, [&b"VP8X"[..], b"ICCP", b"VP8L"]);
+        assert_eq!(chunks(&stripped)[0].1[0], FLAG_ICC);
+        assert!(!is_complete(&data[..data.len() - 1]));
+    }
+}
@@ -0,0 +1,245 @@
+//! Zip containers, for `.zip` archives, CBZ comics and Office packages.
+//!
+//! Only the common subset is read: stored, deflated or (our own) zstd
+//! entries, no ZIP64, no encryption. Entries are written back in their
+//! original order, with their timestamps, attributes and "version made by".
+
+use std::io::{Read, Write};
+use std::path::Path;
+use anyhow::{anyhow, Result};
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use crate::format::Format;
+
+pub const STORED: u16 = 0;
+pub const DEFLATED: u16 = 8;
+/// Zstandard, from the APPNOTE; not every unzip reads it
+pub const ZSTD: u16 = 93;
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    pub data: Vec<u8>,
+    pub meta: Meta,
+}
+
+impl Entry {
+    /// An entry dated 1980-01-01 00:00, with no attributes
+    pub fn new(name: impl Into<String>, data: Vec<u8>) -> Self {
+        Entry { name: name.into(), data, meta: Meta::default() }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+}
+
+/// What the central directory says about an entry besides its data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Meta {
+    /// Host system (high byte) and zip version (low byte) that wrote it
+    pub made_by: u16,
+    /// MS-DOS time and date of last modification
+    pub time: u16,
+    pub date: u16,
+    /// Host attributes; Unix mode bits in the high 16 for Unix hosts
+    pub external_attributes: u32,
+}
+
+impl Default for Meta {
+    fn default() -> Self {
+        Meta { made_by: 20, time: 0, date: 0x21, external_attributes: 0 }
+    }
+}
+
+pub fn read(path: &Path) -> Result<Vec<Entry>> {
+    entries(&std::fs::read(path)?)
+        .ok_or_else(|| anyhow!("{} is not a readable zip (encrypted, ZIP64 and zstd members aren't supported).", path.display()))
+}
+
+pub fn write(path: &Path, entries: &[Entry]) -> Result<()> {
+    Ok(std::fs::write(path, to_bytes(entries)?)?)
+}
+
+/// Every entry, decompressed and CRC-checked; `None` for anything unreadable
+pub fn entries(data: &[u8]) -> Option<Vec<Entry>> {
+    parse(data, false)
+}
+
+/// Whether the central directory lists entries that are all present and
+/// intact; zstd members are only checked for their frame header
+pub fn is_complete(data: &[u8]) -> bool {
+    parse(data, true).is_some()
+}
+
+fn parse(data: &[u8], accept_zstd: bool) -> Option<Vec<Entry>> {
+    let u16_at = |p: usize| data.get(p..p + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize);
+    let u32_at = |p: usize| data.get(p..p + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+    // End of central directory, searched back over the longest archive comment
+    let last = data.len().checked_sub(22)?;
+    let eocd = (last.saturating_sub(u16::MAX as usize)..=last).rev().find(|&i| data[i..].starts_with(b"PK\x05\x06"))?;
+    let count = u16_at(eocd + 10)?;
+    let mut pos = u32_at(eocd + 16)? as usize;
+    let mut found = Vec::with_capacity(count);
+    for _ in 0..count {
+        if !data.get(pos..)?.starts_with(b"PK\x01\x02") {
+            return None;
+        }
+        let (flags, method, crc) = (u16_at(pos + 8)?, u16_at(pos + 10)? as u16, u32_at(pos + 16)?);
+        let (compressed, size, local) = (u32_at(pos + 20)?, u32_at(pos + 24)?, u32_at(pos + 42)?);
+        // Encrypted, or sizes moved to a ZIP64 extra field
+        if flags & 1 != 0 || [compressed, size, local].contains(&u32::MAX) {
+            return None;
+        }
+        let name_len = u16_at(pos + 28)?;
+        let name = String::from_utf8_lossy(data.get(pos + 46..pos + 46 + name_len)?).into_owned();
+        let meta = Meta {
+            made_by: u16_at(pos + 4)? as u16,
+            time: u16_at(pos + 12)? as u16,
+            date: u16_at(pos + 14)? as u16,
+            external_attributes: u32_at(pos + 38)?,
+        };
+
+        let local = local as usize;
+        if !data.get(local..)?.starts_with(b"PK\x03\x04") {
+            return None;
+        }
+        let start = local + 30 + u16_at(local + 26)? + u16_at(local + 28)?;
+        let raw = data.get(start..start + compressed as usize)?;
+        let body = match method {
+            STORED => raw.to_vec(),
+            DEFLATED => {
+                let mut body = Vec::with_capacity(size as usize);
+                DeflateDecoder::new(raw).read_to_end(&mut body).ok()?;
+                body
+            },
+            ZSTD if accept_zstd && raw.starts_with(&ZSTD_MAGIC) => {
+                found.push(Entry { name, data: raw.to_vec(), meta });
+                pos += 46 + name_len + u16_at(pos + 30)? + u16_at(pos + 32)?;
+                continue;
+            },
+            _ => return None,
+        };
+        if body.len() != size as usize || crc32(&body) != crc {
+            return None;
+        }
+        found.push(Entry { name, data: body, meta });
+        pos += 46 + name_len + u16_at(pos + 30)? + u16_at(pos + 32)?;
+    }
+    Some(found)
+}
+
+/// The archive as zip bytes: images stored (deflating them again gains
+/// nothing), everything else deflated at the highest level
+pub fn to_bytes(entries: &[Entry]) -> Result<Vec<u8>> {
+    to_bytes_with(entries, |entry| {
+        if is_compressed_image(&entry.name) || entry.data.is_empty() {
+            return Ok((STORED, entry.data.clone()));
+        }
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&entry.data)?;
+        Ok((DEFLATED, encoder.finish()?))
+    })
+}
+
+/// The archive as zip bytes, `encode` giving each entry's method and body
+pub fn to_bytes_with(entries: &[Entry], mut encode: impl FnMut(&Entry) -> Result<(u16, Vec<u8>)>) -> Result<Vec<u8>> {
+    if entries.len() > u16::MAX as usize {
+        return Err(anyhow!("Too many entries for a zip without ZIP64 ({}).", entries.len()));
+    }
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+    for entry in entries {
+        let (method, body) = encode(entry)?;
+        let offset = u32::try_from(out.len())?;
+        let sizes = [crc32(&entry.data), u32::try_from(body.len())?, u32::try_from(entry.data.len())?];
+        // Version 2.0 (6.3 for zstd), UTF-8 names, the entry's own time and date
+        let needed: u16 = if method == ZSTD { 63 } else { 20 };
+        let mut common = Vec::new();
+        common.extend(needed.to_le_bytes());
+        common.extend(0x0800u16.to_le_bytes());
+        common.extend(method.to_le_bytes());
+        common.extend(entry.meta.time.to_le_bytes());
+        common.extend(entry.meta.date.to_le_bytes());
+        sizes.iter().for_each(|v| common.extend(v.to_le_bytes()));
+        common.extend((entry.name.len() as u16).to_le_bytes());
+        common.extend([0, 0]);
+
+        out.extend(b"PK\x03\x04");
+        out.extend(&common);
+        out.extend(entry.name.as_bytes());
+        out.extend(&body);
+
+        central.extend(b"PK\x01\x02");
+        // Same host; the version is at least what reading the entry needs
+        let made_by = (entry.meta.made_by & 0xFF00) | (entry.meta.made_by & 0xFF).max(needed);
+        central.extend(made_by.to_le_bytes());
+        central.extend(&common);
+        // Comment length, disk, internal and external attributes
+        central.extend([0; 6]);
+        central.extend(entry.meta.external_attributes.to_le_bytes());
+        central.extend(offset.to_le_bytes());
+        central.extend(entry.name.as_bytes());
+    }
+    let directory_offset = u32::try_from(out.len())?;
+    out.extend(&central);
+    out.extend(b"PK\x05\x06\0\0\0\0");
+    out.extend((entries.len() as u16).to_le_bytes());
+    out.extend((entries.len() as u16).to_le_bytes());
+    out.extend(u32::try_from(central.len())?.to_le_bytes());
+    out.extend(directory_offset.to_le_bytes());
+    out.extend([0, 0]);
+    Ok(out)
+}
+
+/// JPEG, PNG and GIF data, which deflate can't shrink
+pub fn is_compressed_image(name: &str) -> bool {
+    matches!(Format::from_path(name), Some(Format::Jpg | Format::Png | Format::Gif))
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = flate2::Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_damage() {
+        let entries = vec![
+            Entry::new("docs/", Vec::new()),
+            Entry::new("docs/notes.txt", "notes ".repeat(100).into_bytes()),
+            Entry::new("photo.jpg", vec![7; 100]),
+        ];
+        let data = to_bytes(&entries).unwrap();
+        assert_eq!(super::entries(&data).unwrap(), entries);
+        assert!(entries[0].is_dir() && !entries[1].is_dir());
+
+        // A flipped byte in the stored image fails its CRC
+        let mut broken = data.clone();
+        let at = data.windows(100).position(|w| w == [7; 100]).unwrap();
+        broken[at + 50] ^= 0xFF;
+        assert!(!is_complete(&broken) && !is_complete(&data[..data.len() - 1]));
+
+        let zstd = to_bytes_with(&entries, |e| Ok((ZSTD, [&ZSTD_MAGIC[..], &e.data].concat()))).unwrap();
+        assert!(is_complete(&zstd) && super::entries(&zstd).is_none());
+    }
+
+    #[test]
+    fn test_times_and_permissions_survive() {
+        // Written on Unix by Info-ZIP 3.0: an executable, 2024-05-17 13:45:30
+        let meta = Meta { made_by: 0x031E, time: (13 << 11) | (45 << 5) | 15, date: (44 << 9) | (5 << 5) | 17, external_attributes: 0o100755 << 16 };
+        let entries = vec![Entry { meta, ..Entry::new("bin/run.sh", b"#!/bin/sh\necho hi\n".to_vec()) }];
+        assert_eq!(super::entries(&to_bytes(&entries).unwrap()).unwrap(), entries);
+
+        // zstd raises the version, never the host
+        let zstd = to_bytes_with(&entries, |e| Ok((ZSTD, [&ZSTD_MAGIC[..], &e.data].concat()))).unwrap();
+        let at = zstd.windows(4).position(|w| w == b"PK\x01\x02").unwrap();
+        assert_eq!(u16::from_le_bytes([zstd[at + 4], zstd[at + 5]]), 0x033F);
+    }
+}
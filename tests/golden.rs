@@ -0,0 +1,122 @@
+//! Golden-file tests: run the real pipelines over tests/fixtures.
+//! Each test is skipped (with a note on stderr) when the tools it needs aren't installed.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use crnch::compression::{compress_file, CompResult};
+use crnch::decision::DecisionPolicy;
+use crnch::options::CompressionOptions;
+
+/// Same slack the CLI allows before warning that a target was missed
+const TARGET_TOLERANCE: f64 = 1.2;
+
+fn have_tools(tools: &[&str]) -> bool {
+    let missing: Vec<_> = tools.iter().filter(|t| which::which(t).is_err()).collect();
+    if !missing.is_empty() {
+        eprintln!("skipping: missing {:?}", missing);
+    }
+    missing.is_empty()
+}
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+fn run(name: &str, target_kb: Option<u64>) -> (tempfile::TempDir, PathBuf, CompResult) {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join(format!("crnched_{}", name));
+    let opts = CompressionOptions::builder()
+        .target_kb(target_kb)
+        .decisions(DecisionPolicy::AlwaysAccept)
+        .build();
+    let input = fixture(name);
+    let result = compress_file(input.to_str().unwrap(), output.to_str().unwrap(), &opts)
+        .unwrap_or_else(|e| panic!("{}: {}", name, e));
+    (dir, output, result)
+}
+
+fn assert_valid(path: &Path) {
+    let data = fs::read(path).unwrap();
+    let magic: &[u8] = match path.extension().and_then(|e| e.to_str()) {
+        Some("png") => b"\x89PNG",
+        Some("jpg") => b"\xff\xd8\xff",
+        Some("pdf") => b"%PDF",
+        _ => unreachable!(),
+    };
+    assert!(data.starts_with(magic), "{} has a bad header", path.display());
+    let status = std::process::Command::new("magick")
+        .arg("identify")
+        .arg(path)
+        .stdout(std::process::Stdio::null())
+        .status()
+        .unwrap();
+    assert!(status.success(), "{} can't be decoded", path.display());
+}
+
+fn assert_within_target(name: &str, result: &CompResult, target_kb: u64) {
+    let limit = (target_kb as f64 * TARGET_TOLERANCE) as u64;
+    assert!(
+        result.final_kb <= limit,
+        "{}: {} KB is over the {} KB target (limit {} KB, {})",
+        name, result.final_kb, target_kb, limit, result.algorithm
+    );
+}
+
+#[test]
+fn golden_auto_mode_never_grows() {
+    if !have_tools(&["gs", "magick", "pngquant", "jpegoptim", "oxipng"]) {
+        return;
+    }
+    for name in ["photo.jpg", "photo.png", "screenshot.png", "scan.pdf", "optimized.jpg", "optimized.png"] {
+        let (_dir, output, result) = run(name, None);
+        assert_valid(&output);
+        assert!(
+            result.final_kb <= result.original_kb,
+            "{}: grew from {} KB to {} KB ({})",
+            name, result.original_kb, result.final_kb, result.algorithm
+        );
+    }
+}
+
+#[test]
+fn golden_jpeg_targets() {
+    if !have_tools(&["magick", "jpegoptim"]) {
+        return;
+    }
+    let (_dir, output, result) = run("photo.jpg", Some(60));
+    assert_valid(&output);
+    assert_within_target("photo.jpg", &result, 60);
+    assert_eq!(result.target_met, Some(result.final_kb <= 60));
+}
+
+#[test]
+fn golden_png_targets() {
+    if !have_tools(&["magick", "pngquant", "oxipng"]) {
+        return;
+    }
+    for (name, target) in [("photo.png", 150), ("screenshot.png", 40)] {
+        let (_dir, output, result) = run(name, Some(target));
+        assert_valid(&output);
+        assert_within_target(name, &result, target);
+    }
+}
+
+#[test]
+fn golden_pdf_targets() {
+    if !have_tools(&["gs", "magick"]) {
+        return;
+    }
+    let (_dir, output, result) = run("scan.pdf", Some(120));
+    assert_valid(&output);
+    assert_within_target("scan.pdf", &result, 120);
+}
+
+#[test]
+fn golden_target_above_input_keeps_file_small() {
+    if !have_tools(&["magick", "jpegoptim"]) {
+        return;
+    }
+    let (_dir, output, result) = run("optimized.jpg", Some(500));
+    assert_valid(&output);
+    assert!(result.final_kb <= result.original_kb);
+}
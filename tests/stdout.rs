@@ -0,0 +1,55 @@
+//! `--stdout` end to end: the real binary, with stub tools on PATH, piped
+//! like `crnch logo.svg --stdout > out.svg`. Everything human-readable has to
+//! go to stderr, or it ends up inside the compressed file.
+
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use crnch::compression::compress_file;
+use crnch::options::CompressionOptions;
+
+/// The tools crnch checks for, as scripts that succeed and do nothing; SVG
+/// is compressed in-process, so none of them is really run for it
+fn stub_tools(dir: &Path) {
+    for tool in ["gs", "magick", "pngquant", "jpegoptim", "oxipng"] {
+        let path = dir.join(tool);
+        fs::write(&path, "#!/bin/sh\nexit 0\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+}
+
+#[test]
+fn stdout_holds_only_the_compressed_bytes() {
+    let dir = tempfile::tempdir().unwrap();
+    let bin = dir.path().join("bin");
+    fs::create_dir(&bin).unwrap();
+    stub_tools(&bin);
+    let input = dir.path().join("logo.svg");
+    let svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\">{}<rect width=\"10.123456\" height=\"10\"/></svg>\n", "<!-- note -->\n".repeat(200));
+    fs::write(&input, &svg).unwrap();
+
+    let run = Command::new(env!("CARGO_BIN_EXE_crnch"))
+        .arg(&input)
+        .arg("--stdout")
+        .current_dir(dir.path())
+        .env("PATH", format!("{}:/bin:/usr/bin", bin.display()))
+        .env("HOME", dir.path())
+        .env("XDG_CACHE_HOME", dir.path().join("cache"))
+        .env("XDG_CONFIG_HOME", dir.path().join("config"))
+        .env("CRNCH_NO_UPDATE_CHECK", "1")
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(run.status.success(), "{}", String::from_utf8_lossy(&run.stderr));
+
+    // Byte for byte what the library writes to a file
+    let expected = dir.path().join("expected.svg");
+    compress_file(&input.to_string_lossy(), &expected.to_string_lossy(), &CompressionOptions::builder().build()).unwrap();
+    assert_eq!(String::from_utf8_lossy(&run.stdout), fs::read_to_string(&expected).unwrap());
+    assert!(run.stdout.len() < svg.len());
+    assert!(!run.stderr.is_empty(), "the summary should have gone to stderr");
+    assert_eq!(fs::read_dir(dir.path()).unwrap().filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().starts_with("crnched_")).count(), 0);
+}